@@ -0,0 +1,80 @@
+#!/usr/bin/env python3
+"""Generate src/markdown/unicode/tables.rs from Python's Unicode database.
+
+    python3 scripts/unicode_tables.py > src/markdown/unicode/tables.rs
+    rustfmt --edition 2021 src/markdown/unicode/tables.rs
+
+(or `just unicode-tables`). Only non-ASCII characters are listed; the
+classifiers answer ASCII without a lookup. The case folding table lists
+only the characters whose full case folding differs from their lowercase.
+"""
+
+import unicodedata
+
+
+def ranges(pred):
+    """Inclusive (first, last) code point ranges above ASCII matching pred."""
+    out = []
+    start = None
+    for cp in range(0x80, 0x110000):
+        if pred(chr(cp)):
+            if start is None:
+                start = cp
+        elif start is not None:
+            out.append((start, cp - 1))
+            start = None
+    if start is not None:
+        out.append((start, 0x10FFFF))
+    return out
+
+
+def table(name, doc, rows):
+    values = [f"0x{v:x}" for pair in rows for v in pair]
+    lines = [f"/// {doc}", f"pub const {name}: &[u32] = &["]
+    line = " "
+    for value in values:
+        if len(line) + len(value) + 2 > 100:
+            lines.append(line.rstrip())
+            line = " "
+        line += f" {value},"
+    lines.append(line.rstrip())
+    lines.append("];")
+    return "\n".join(lines)
+
+
+def folds():
+    """Characters whose full case folding isn't their lowercase."""
+    rows = []
+    for cp in range(0x80, 0x110000):
+        c = chr(cp)
+        if unicodedata.category(c) != "Cs" and c.casefold() != c.lower():
+            rows.append((cp, c.casefold()))
+    lines = [
+        "/// Full case folding where it differs from `char::to_lowercase`",
+        "pub const CASE_FOLD: &[(char, &str)] = &[",
+    ]
+    for cp, folded in rows:
+        escaped = "".join(f if f.isascii() else f"\\u{{{ord(f):x}}}" for f in folded)
+        lines.append(f"  ('\\u{{{cp:x}}}', \"{escaped}\"),")
+    lines.append("];")
+    return "\n".join(lines)
+
+
+def main():
+    punctuation = ranges(lambda c: unicodedata.category(c)[0] in "PS")
+    whitespace = ranges(lambda c: unicodedata.category(c) == "Zs")
+    print(f"//! Generated by `scripts/unicode_tables.py` from Unicode {unicodedata.unidata_version}. Do not edit.")
+    print("//!")
+    print("//! The class tables list inclusive ranges of code points as first, last")
+    print("//! pairs, sorted, for binary search; the folding table is sorted by")
+    print("//! character.")
+    print()
+    print(table("PUNCTUATION", "General categories P and S", punctuation))
+    print()
+    print(table("WHITESPACE", "General category Zs", whitespace))
+    print()
+    print(folds())
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,172 @@
+//! Text statistics (`--analyze`): how many nodes of each kind a document
+//! has and how many of its bytes they cover, per file and for the whole
+//! run, collected into `analysis.json`.
+
+use crate::ast::{Document, Node};
+use crate::formats::esc;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Count and byte total of one node kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KindStats {
+  pub count: usize,
+  /// Source bytes covered by nodes of this kind. A node inside another of
+  /// the same kind adds nothing, so nested lists or quotes count once.
+  pub bytes: usize,
+}
+
+/// Statistics of one document, or of several merged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+  /// Source size in bytes
+  pub bytes: usize,
+  /// By `NodeKind::name`
+  pub kinds: BTreeMap<&'static str, KindStats>,
+}
+
+impl Analysis {
+  /// Statistics of a parsed document whose source is `bytes` long.
+  pub fn of(doc: &Document, bytes: usize) -> Self {
+    let mut analysis = Self {
+      bytes,
+      kinds: BTreeMap::new(),
+    };
+    let mut open = Vec::new();
+    for node in &doc.nodes {
+      analysis.add(node, &mut open);
+    }
+    analysis
+  }
+
+  /// Add `other`'s sizes and counts to these.
+  pub fn merge(&mut self, other: &Analysis) {
+    self.bytes += other.bytes;
+    for (name, stats) in &other.kinds {
+      let entry = self.kinds.entry(name).or_default();
+      entry.count += stats.count;
+      entry.bytes += stats.bytes;
+    }
+  }
+
+  /// Share of the source covered by `kind`, in percent.
+  #[allow(dead_code)] // Part of public API
+  pub fn percent(&self, kind: &str) -> f64 {
+    match (self.kinds.get(kind), self.bytes) {
+      (Some(stats), bytes) if bytes > 0 => stats.bytes as f64 * 100.0 / bytes as f64,
+      _ => 0.0,
+    }
+  }
+
+  /// `open` holds the kinds of the node's ancestors.
+  fn add(&mut self, node: &Node, open: &mut Vec<&'static str>) {
+    let name = node.kind.name();
+    let stats = self.kinds.entry(name).or_default();
+    stats.count += 1;
+    if !open.contains(&name) {
+      stats.bytes += node.span.end.saturating_sub(node.span.start);
+    }
+    open.push(name);
+    for child in &node.children {
+      self.add(child, open);
+    }
+    open.pop();
+  }
+
+  fn write_json(&self, s: &mut String) {
+    s.push_str(&format!("\"bytes\":{},\"kinds\":{{", self.bytes));
+    for (i, (name, stats)) in self.kinds.iter().enumerate() {
+      if i > 0 {
+        s.push(',');
+      }
+      s.push_str(&format!(
+        "\"{}\":{{\"count\":{},\"bytes\":{}}}",
+        name, stats.count, stats.bytes
+      ));
+    }
+    s.push('}');
+  }
+}
+
+/// The report as JSON: the run's totals, then each file's statistics.
+/// `{"total":{"files":N,"bytes":B,"kinds":{...}},"documents":[{"file",...}]}`
+pub fn to_json(files: &[(PathBuf, Analysis)]) -> String {
+  let mut total = Analysis::default();
+  files.iter().for_each(|(_, a)| total.merge(a));
+  let mut s = String::with_capacity(256 + files.len() * 512);
+  s.push_str(&format!("{{\"total\":{{\"files\":{},", files.len()));
+  total.write_json(&mut s);
+  s.push_str("},\"documents\":[");
+  for (i, (path, analysis)) in files.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    s.push_str(&format!("{{\"file\":\"{}\",", esc(&display(path))));
+    analysis.write_json(&mut s);
+    s.push('}');
+  }
+  s.push_str("]}");
+  s
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn analyze(source: &str) -> Analysis {
+    Analysis::of(&MarkdownParser::new(source).parse(), source.len())
+  }
+
+  #[test]
+  fn test_kind_stats() {
+    let source = "# Title\n\nSome text.\n\n```rust\nfn main() {}\n```\n";
+    let analysis = analyze(source);
+    assert_eq!(analysis.bytes, source.len());
+    assert_eq!(analysis.kinds["Heading"], KindStats { count: 1, bytes: 8 });
+    assert_eq!(
+      analysis.kinds["Paragraph"],
+      KindStats {
+        count: 1,
+        bytes: 11
+      }
+    );
+    assert_eq!(analysis.kinds["Text"].count, 3);
+    assert_eq!(analysis.kinds["FencedCodeBlock"].count, 1);
+    assert!(analysis.percent("FencedCodeBlock") > 50.0);
+    assert_eq!(analysis.percent("Table"), 0.0);
+  }
+
+  #[test]
+  fn test_nested_kinds_count_once() {
+    let source = "> outer\n> > inner\n";
+    let doc = MarkdownParser::new(source).parse();
+    let outer = doc.nodes[0].span;
+    let quotes = Analysis::of(&doc, source.len()).kinds["BlockQuote"];
+    assert_eq!(quotes.count, 2);
+    assert_eq!(quotes.bytes, outer.end - outer.start);
+  }
+
+  #[test]
+  fn test_report() {
+    let files = vec![
+      (PathBuf::from("a.md"), analyze("Hi\n")),
+      (PathBuf::from("docs/b.md"), analyze("Yo\n\nThere\n")),
+    ];
+    assert_eq!(
+      to_json(&files),
+      "{\"total\":{\"files\":2,\"bytes\":13,\"kinds\":{\
+       \"Paragraph\":{\"count\":3,\"bytes\":12},\"Text\":{\"count\":3,\"bytes\":9}}},\
+       \"documents\":[\
+       {\"file\":\"a.md\",\"bytes\":3,\"kinds\":{\
+       \"Paragraph\":{\"count\":1,\"bytes\":3},\"Text\":{\"count\":1,\"bytes\":2}}},\
+       {\"file\":\"docs/b.md\",\"bytes\":10,\"kinds\":{\
+       \"Paragraph\":{\"count\":2,\"bytes\":9},\"Text\":{\"count\":2,\"bytes\":7}}}]}"
+    );
+  }
+}
@@ -0,0 +1,220 @@
+//! Anchor maps: every fragment target a document defines, with its
+//! position, so links like `page.md#setup` can be resolved without loading
+//! the whole AST.
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::esc;
+use crate::markdown::html::scan_tag;
+
+/// What defines an anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorKind {
+  /// Heading id, explicit `{#id}` or generated by the slugs pass
+  Heading,
+  /// Footnote definition label
+  Footnote,
+  /// `id` attribute of an HTML tag, custom element or image
+  Html,
+}
+
+impl AnchorKind {
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Heading => "heading",
+      Self::Footnote => "footnote",
+      Self::Html => "html",
+    }
+  }
+}
+
+/// One fragment target.
+#[derive(Debug, Clone)]
+pub struct Anchor {
+  pub id: String,
+  pub kind: AnchorKind,
+  /// Byte offset of the defining node
+  pub offset: usize,
+  /// Line number (1-based). Inline nodes carry no line, so anchors from
+  /// inline HTML and images get the line of the block holding them.
+  pub line: usize,
+  /// Column number (1-based), of the holding block for inline anchors
+  pub column: usize,
+  /// Included file the anchor comes from; `None` for the document itself
+  pub source: Option<String>,
+}
+
+/// Anchors of a document, in document order. Duplicates are kept; the
+/// first one is the one browsers jump to.
+#[derive(Debug, Default)]
+pub struct AnchorMap {
+  pub source_path: String,
+  pub anchors: Vec<Anchor>,
+}
+
+impl AnchorMap {
+  pub fn from_document(doc: &Document) -> Self {
+    let mut map = Self {
+      source_path: doc.source_path.clone(),
+      anchors: Vec::new(),
+    };
+    map.collect(&doc.nodes, None, (0, 0));
+    map
+  }
+
+  /// `block` is the line and column of the nearest node that has them.
+  fn collect(&mut self, nodes: &[Node], source: Option<&str>, block: (usize, usize)) {
+    for node in nodes {
+      let at = if node.span.line > 0 {
+        (node.span.line, node.span.column)
+      } else {
+        block
+      };
+      let mut push = |id: &str, kind| {
+        self.anchors.push(Anchor {
+          id: id.to_string(),
+          kind,
+          offset: node.span.start,
+          line: at.0,
+          column: at.1,
+          source: source.map(str::to_string),
+        })
+      };
+      match &node.kind {
+        NodeKind::Heading { id: Some(id), .. } => push(id, AnchorKind::Heading),
+        NodeKind::FootnoteDefinition { label, .. } | NodeKind::Footnote { label } => {
+          push(label, AnchorKind::Footnote)
+        }
+        NodeKind::HtmlTag { attributes, .. }
+        | NodeKind::CustomElement { attributes, .. }
+        | NodeKind::Image { attributes, .. } => {
+          if let Some(id) = id_attribute(attributes) {
+            push(&id, AnchorKind::Html);
+          }
+        }
+        NodeKind::HtmlInline { content } => {
+          for id in html_ids(content) {
+            push(&id, AnchorKind::Html);
+          }
+        }
+        _ => {}
+      }
+      let child_source = match &node.kind {
+        NodeKind::Include { src } => Some(src.as_str()),
+        _ => source,
+      };
+      self.collect(&node.children, child_source, at);
+    }
+  }
+
+  /// First anchor with this id.
+  #[allow(dead_code)]
+  pub fn get(&self, id: &str) -> Option<&Anchor> {
+    self.anchors.iter().find(|a| a.id == id)
+  }
+
+  /// Convert to JSON format.
+  pub fn to_json(&self) -> String {
+    let mut s = String::with_capacity(64 + self.anchors.len() * 64);
+    s.push_str("{\"source\":\"");
+    s.push_str(&esc(&self.source_path));
+    s.push_str("\",\"anchors\":[");
+    for (i, anchor) in self.anchors.iter().enumerate() {
+      if i > 0 {
+        s.push(',');
+      }
+      s.push_str(&format!(
+        "{{\"id\":\"{}\",\"kind\":\"{}\",\"offset\":{},\"line\":{},\"col\":{}",
+        esc(&anchor.id),
+        anchor.kind.name(),
+        anchor.offset,
+        anchor.line,
+        anchor.column
+      ));
+      if let Some(source) = &anchor.source {
+        s.push_str(&format!(",\"source\":\"{}\"", esc(source)));
+      }
+      s.push('}');
+    }
+    s.push_str("]}");
+    s
+  }
+}
+
+fn id_attribute(attributes: &[(String, String)]) -> Option<String> {
+  attributes
+    .iter()
+    .find(|(name, value)| name.eq_ignore_ascii_case("id") && !value.is_empty())
+    .map(|(_, value)| value.clone())
+}
+
+/// `id`s of the opening tags in raw inline HTML.
+fn html_ids(content: &str) -> Vec<String> {
+  let mut ids = Vec::new();
+  let mut rest = content;
+  while let Some(at) = rest.find('<') {
+    rest = &rest[at..];
+    match scan_tag(rest) {
+      Some(tag) => {
+        ids.extend(id_attribute(&tag.attributes));
+        rest = &rest[tag.len..];
+      }
+      None => rest = &rest[1..],
+    }
+  }
+  ids
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+  use crate::processor::transform::Pipeline;
+
+  fn anchors(input: &str) -> Vec<(String, &'static str, usize)> {
+    let mut doc = MarkdownParser::new(input).parse();
+    Pipeline::sandboxed().run(&mut doc).unwrap();
+    AnchorMap::from_document(&doc)
+      .anchors
+      .into_iter()
+      .map(|a| (a.id, a.kind.name(), a.line))
+      .collect()
+  }
+
+  #[test]
+  fn test_collects_every_kind() {
+    let input = "# Intro\n\nSee[^note] <a id=\"here\"></a>\n\n## Setup {#install}\n\n\
+                 ![logo](logo.png){id=logo}\n\n[^note]: Text\n";
+    assert_eq!(
+      anchors(input),
+      vec![
+        ("intro".to_string(), "heading", 1),
+        ("here".to_string(), "html", 3),
+        ("install".to_string(), "heading", 5),
+        ("logo".to_string(), "html", 7),
+        ("note".to_string(), "footnote", 9),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_html_ids() {
+    assert_eq!(
+      html_ids("<span ID='a'>x</span><br/> < <b id=\"\"><i id=b>"),
+      vec!["a", "b"]
+    );
+  }
+
+  #[test]
+  fn test_to_json() {
+    let mut doc = MarkdownParser::new("# A \"q\"").parse();
+    doc.source_path = "docs/a.md".to_string();
+    Pipeline::sandboxed().run(&mut doc).unwrap();
+    let map = AnchorMap::from_document(&doc);
+    assert_eq!(map.get("a-q").map(|a| a.offset), Some(0));
+    assert_eq!(
+      map.to_json(),
+      "{\"source\":\"docs/a.md\",\"anchors\":[{\"id\":\"a-q\",\"kind\":\"heading\",\
+       \"offset\":0,\"line\":1,\"col\":1}]}"
+    );
+  }
+}
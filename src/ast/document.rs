@@ -40,7 +40,7 @@ impl Document {
 /// Type of document being parsed.
 ///
 /// Determines which parser is used and affects output format.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DocumentType {
   Markdown,
   JavaScript,
@@ -50,6 +50,15 @@ pub enum DocumentType {
 }
 
 impl DocumentType {
+  /// Every document type, in declaration order.
+  pub const ALL: [Self; 5] = [
+    Self::Markdown,
+    Self::JavaScript,
+    Self::TypeScript,
+    Self::Java,
+    Self::Python,
+  ];
+
   /// Determine document type from file extension.
   ///
   /// # Examples
@@ -58,13 +67,31 @@ impl DocumentType {
   /// assert_eq!(DocumentType::from_extension("py"), Some(DocumentType::Python));
   /// ```
   pub fn from_extension(ext: &str) -> Option<Self> {
-    match ext.to_lowercase().as_str() {
-      "md" | "markdown" | "mdown" | "mkd" => Some(Self::Markdown),
-      "js" | "mjs" | "cjs" => Some(Self::JavaScript),
-      "ts" | "tsx" | "mts" | "cts" => Some(Self::TypeScript),
-      "java" => Some(Self::Java),
-      "py" | "pyi" | "pyw" => Some(Self::Python),
-      _ => None,
+    let ext = ext.to_lowercase();
+    Self::ALL
+      .into_iter()
+      .find(|doc_type| doc_type.extensions().contains(&ext.as_str()))
+  }
+
+  /// File extensions the built-in parser for this type reads.
+  pub fn extensions(&self) -> &'static [&'static str] {
+    match self {
+      Self::Markdown => &["md", "markdown", "mdown", "mkd"],
+      Self::JavaScript => &["js", "mjs", "cjs"],
+      Self::TypeScript => &["ts", "tsx", "mts", "cts"],
+      Self::Java => &["java"],
+      Self::Python => &["py", "pyi", "pyw"],
+    }
+  }
+
+  /// Lowercase name used in reports.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Markdown => "markdown",
+      Self::JavaScript => "javascript",
+      Self::TypeScript => "typescript",
+      Self::Java => "java",
+      Self::Python => "python",
     }
   }
 
@@ -88,12 +115,30 @@ pub struct DocumentMetadata {
   pub title: Option<String>,
   /// Document description
   pub description: Option<String>,
+  /// Abbreviations defined in the document (`*[HTML]: ...`), as (term,
+  /// title) in definition order
+  pub glossary: Vec<(String, String)>,
+  /// Frontmatter `date`, as an ISO 8601 date or date and time
+  pub date: Option<String>,
+  /// Frontmatter `updated`, normalized like `date`
+  pub updated: Option<String>,
+  /// Frontmatter `authors` (or `author`), in order
+  pub authors: Vec<Author>,
   /// Total lines in source
   pub total_lines: usize,
   /// Total AST nodes generated
   pub total_nodes: usize,
 }
 
+/// A document author, from `Name <email> (url)` or a `name`/`email`/`url`
+/// mapping.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Author {
+  pub name: String,
+  pub email: Option<String>,
+  pub url: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
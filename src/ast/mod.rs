@@ -5,7 +5,9 @@ mod nodes;
 mod span;
 mod types;
 
-pub use document::{Document, DocumentMetadata, DocumentType};
+pub use document::{Author, Document, DocumentMetadata, DocumentType};
 pub use nodes::{FrontmatterFormat, Node, NodeKind};
 pub use span::Span;
-pub use types::{AlertType, Alignment, DocStyle, ListMarker, ReferenceType};
+pub use types::{
+  AlertType, Alignment, DocStyle, HtmlTagKind, ListMarker, ReferenceType, ReleaseStage, TypeSource,
+};
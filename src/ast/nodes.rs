@@ -1,6 +1,6 @@
 //! AST nodes.
 
-use super::types::AlertType;
+use super::types::{AlertType, HtmlTagKind, ReleaseStage, TypeSource};
 use super::{Alignment, DocStyle, ListMarker, ReferenceType, Span};
 
 /// AST node: kind + span + children.
@@ -9,6 +9,9 @@ pub struct Node {
   pub kind: NodeKind,
   pub span: Span,
   pub children: Vec<Node>,
+  /// Hash of the block's content, set on block-level nodes by the
+  /// `content-hashes` transform
+  pub hash: Option<u64>,
 }
 
 impl Node {
@@ -18,6 +21,7 @@ impl Node {
       kind,
       span,
       children: Vec::new(),
+      hash: None,
     }
   }
 
@@ -27,6 +31,7 @@ impl Node {
       kind,
       span,
       children,
+      hash: None,
     }
   }
 
@@ -60,6 +65,12 @@ pub enum NodeKind {
   Heading {
     level: u8,
     id: Option<String>,
+    /// Classes and `key=value` pairs from a trailing `{#id .class key=value}`
+    /// list; its `#id` goes to `id`
+    attributes: Vec<(String, String)>,
+    /// Span of the whole section the heading opens, set by the `sections`
+    /// transform
+    section: Option<Span>,
   },
   /// Paragraph of text
   Paragraph,
@@ -70,10 +81,12 @@ pub enum NodeKind {
     language: Option<String>,
     info: Option<String>,
   },
-  /// Fenced code block (``` or ~~~)
+  /// Fenced code block (``` or ~~~); `attributes` come from a
+  /// `{#id .class key=value}` list in the info string
   FencedCodeBlock {
     language: Option<String>,
     info: Option<String>,
+    attributes: Vec<(String, String)>,
   },
   /// Indented code block (4+ spaces)
   IndentedCodeBlock,
@@ -105,6 +118,9 @@ pub enum NodeKind {
   TableCell {
     alignment: Alignment,
     is_header: bool,
+    /// Columns the cell covers; more than one only for cells joined with
+    /// `||` (`ParseOptions::table_colspan`)
+    colspan: u32,
   },
 
   // === Inline Elements ===
@@ -126,17 +142,20 @@ pub enum NodeKind {
   CodeSpan {
     content: String,
   },
-  /// Inline link or image reference
+  /// Inline link or image reference; `attributes` come from a trailing
+  /// `{#id .class key=value}`
   Link {
     url: String,
     title: Option<String>,
     ref_type: ReferenceType,
+    attributes: Vec<(String, String)>,
   },
-  /// Image
+  /// Image; `attributes` come from a trailing `{#id .class width=400}`
   Image {
     url: String,
     alt: String,
     title: Option<String>,
+    attributes: Vec<(String, String)>,
   },
   /// Autolink (`<url>`)
   AutoLink {
@@ -150,6 +169,18 @@ pub enum NodeKind {
   HtmlInline {
     content: String,
   },
+  /// Tokenized inline HTML tag (from the `html-tags` pass)
+  HtmlTag {
+    name: String,
+    tag: HtmlTagKind,
+    attributes: Vec<(String, String)>,
+  },
+  /// HTML comment (`<!-- ... -->`), without its delimiters; `block` is true
+  /// when it stands on its own lines rather than inside a line of text
+  Comment {
+    content: String,
+    block: bool,
+  },
 
   // === References ===
   LinkReference {
@@ -161,11 +192,23 @@ pub enum NodeKind {
     url: String,
     title: Option<String>,
   },
+  /// `[^label]`; `index` is the 1-based footnote number, 0 until the
+  /// footnotes pass resolves it
   FootnoteReference {
     label: String,
+    index: u32,
   },
+  /// `[^label]: text`; children are markdown blocks
   FootnoteDefinition {
     label: String,
+    index: u32,
+  },
+  /// Document-level section collecting every `FootnoteDefinition`
+  Footnotes,
+  /// Occurrence of a term defined by `*[HTML]: Hyper Text Markup Language`;
+  /// the child is the term as written and `title` its expansion
+  Abbreviation {
+    title: String,
   },
 
   // === GFM Extensions ===
@@ -194,10 +237,14 @@ pub enum NodeKind {
     name: String,
     param_type: Option<String>,
     description: Option<String>,
+    /// Where `param_type` came from; `None` without a type
+    type_source: Option<TypeSource>,
   },
   DocReturn {
     return_type: Option<String>,
     description: Option<String>,
+    /// Where `return_type` came from; `None` without a type
+    type_source: Option<TypeSource>,
   },
   DocThrows {
     exception_type: String,
@@ -211,6 +258,8 @@ pub enum NodeKind {
   },
   DocDeprecated {
     message: Option<String>,
+    /// The version that deprecated it (Sphinx's `.. deprecated:: 1.2`)
+    version: Option<String>,
   },
   DocSince {
     version: String,
@@ -239,6 +288,37 @@ pub enum NodeKind {
     name: String,
     type_expr: Option<String>,
   },
+  /// TSDoc `@typeParam T - description`
+  DocTypeParam {
+    name: String,
+    description: Option<String>,
+  },
+  /// TSDoc `@remarks` block (children hold parsed markdown)
+  DocRemarks {
+    content: String,
+  },
+  /// TSDoc `@defaultValue`
+  DocDefaultValue {
+    value: String,
+  },
+  /// TSDoc release tag (`@alpha`, `@beta`, `@public`, ...)
+  DocReleaseTag {
+    stage: ReleaseStage,
+  },
+  /// TSDoc modifier tag without content (`@packageDocumentation`, `@readonly`, ...)
+  DocModifier {
+    name: String,
+  },
+  /// Inline tag such as `{@inheritDoc Base.method}` or `{@label NAME}`
+  DocInlineTag {
+    name: String,
+    content: Option<String>,
+  },
+  /// Single doctest: `>>>`/`...` source lines and the expected output
+  DocDoctest {
+    source: String,
+    expected: Option<String>,
+  },
 
   // === Extended Markdown ===
   /// YAML/TOML frontmatter block
@@ -246,13 +326,23 @@ pub enum NodeKind {
     format: FrontmatterFormat,
     content: String,
   },
-  /// Inline math ($...$)
+  /// Inline math (`$...$` or `\(...\)`)
   MathInline {
     content: String,
   },
-  /// Block math ($$...$$)
+  /// Display math (`$$...$$`, `\[...\]` or a LaTeX environment such as
+  /// `align`); `display` is true when it stands on its own lines rather
+  /// than inside a line of text
   MathBlock {
     content: String,
+    display: bool,
+  },
+  /// Fenced diagram source for a diagram renderer: ```` ```mermaid ````,
+  /// ```` ```plantuml ```` or ```` ```graphviz ```` (`dot`, `puml` too);
+  /// `engine` is `mermaid`, `plantuml` or `graphviz`
+  DiagramBlock {
+    engine: String,
+    content: String,
   },
   /// Footnote definition [^label]: content
   Footnote {
@@ -276,22 +366,174 @@ pub enum NodeKind {
   },
   /// Steps container (`<steps>`)
   Steps,
-  /// Individual step (`<step>`)
-  Step,
+  /// Individual step (`<step title="...">`), numbered from 1 within its
+  /// `Steps`; children are markdown blocks
+  Step {
+    number: u32,
+    title: Option<String>,
+  },
   /// Table of contents placeholder (`<toc>` or `<toc />`)
   Toc,
   /// Tabbed code blocks container (`<tabs names="...">`)
   Tabs {
     names: Vec<String>,
   },
-  /// Code block with extended attributes
+  /// One tab of a `<tabs>` container, grouping that tab's content
+  Tab {
+    name: String,
+  },
+  /// Code block with extended attributes; line ranges are inclusive and
+  /// 1-based (`highlight="3, 5-7"` is `[(3, 3), (5, 7)]`)
   CodeBlockExt {
     language: Option<String>,
-    highlight: Option<String>,
-    plusdiff: Option<String>,
-    minusdiff: Option<String>,
+    highlight: Vec<(u32, u32)>,
+    plusdiff: Vec<(u32, u32)>,
+    minusdiff: Vec<(u32, u32)>,
     linenumbers: bool,
+    /// Range attributes that failed to parse, as (name, raw value)
+    invalid_ranges: Vec<(String, String)>,
+    /// Pairs from a `{#id .class key=value}` list in the info string
+    attributes: Vec<(String, String)>,
+  },
+  /// Registered custom element (`<callout type="...">`); children are markdown
+  CustomElement {
+    name: String,
+    attributes: Vec<(String, String)>,
   },
+  /// Transclusion (`<include src="..." />`). Once resolved, children are the
+  /// included file's nodes and their spans are offsets into `src`.
+  Include {
+    src: String,
+  },
+  /// Node of a kind this build does not know, read from a newer DAST file
+  /// in forward-compatible mode; only its tag is kept, its payload skipped
+  Unknown {
+    tag: u8,
+  },
+}
+
+impl NodeKind {
+  /// Variant name, as used for `type` in JSON output.
+  pub fn name(&self) -> &'static str {
+    use NodeKind::*;
+    match self {
+      Document => "Document",
+      Heading { .. } => "Heading",
+      Paragraph => "Paragraph",
+      BlockQuote => "BlockQuote",
+      CodeBlock { .. } => "CodeBlock",
+      FencedCodeBlock { .. } => "FencedCodeBlock",
+      IndentedCodeBlock => "IndentedCodeBlock",
+      HtmlBlock { .. } => "HtmlBlock",
+      ThematicBreak => "ThematicBreak",
+      List { .. } => "List",
+      ListItem { .. } => "ListItem",
+      Table => "Table",
+      TableHead => "TableHead",
+      TableBody => "TableBody",
+      TableRow => "TableRow",
+      TableCell { .. } => "TableCell",
+      Text { .. } => "Text",
+      Emphasis => "Emphasis",
+      Strong => "Strong",
+      Strikethrough => "Strikethrough",
+      Code { .. } => "Code",
+      CodeSpan { .. } => "CodeSpan",
+      Link { .. } => "Link",
+      Image { .. } => "Image",
+      AutoLink { .. } => "AutoLink",
+      HardBreak => "HardBreak",
+      SoftBreak => "SoftBreak",
+      HtmlInline { .. } => "HtmlInline",
+      HtmlTag { .. } => "HtmlTag",
+      Comment { .. } => "Comment",
+      LinkReference { .. } => "LinkReference",
+      LinkDefinition { .. } => "LinkDefinition",
+      FootnoteReference { .. } => "FootnoteReference",
+      FootnoteDefinition { .. } => "FootnoteDefinition",
+      Footnotes => "Footnotes",
+      Abbreviation { .. } => "Abbreviation",
+      TaskListMarker { .. } => "TaskListMarker",
+      Emoji { .. } => "Emoji",
+      Mention { .. } => "Mention",
+      IssueReference { .. } => "IssueReference",
+      DocComment { .. } => "DocComment",
+      DocTag { .. } => "DocTag",
+      DocParam { .. } => "DocParam",
+      DocReturn { .. } => "DocReturn",
+      DocThrows { .. } => "DocThrows",
+      DocExample { .. } => "DocExample",
+      DocSee { .. } => "DocSee",
+      DocDeprecated { .. } => "DocDeprecated",
+      DocSince { .. } => "DocSince",
+      DocAuthor { .. } => "DocAuthor",
+      DocVersion { .. } => "DocVersion",
+      DocDescription { .. } => "DocDescription",
+      DocType { .. } => "DocType",
+      DocProperty { .. } => "DocProperty",
+      DocCallback { .. } => "DocCallback",
+      DocTypedef { .. } => "DocTypedef",
+      DocTypeParam { .. } => "DocTypeParam",
+      DocRemarks { .. } => "DocRemarks",
+      DocDefaultValue { .. } => "DocDefaultValue",
+      DocReleaseTag { .. } => "DocReleaseTag",
+      DocModifier { .. } => "DocModifier",
+      DocInlineTag { .. } => "DocInlineTag",
+      DocDoctest { .. } => "DocDoctest",
+      Frontmatter { .. } => "Frontmatter",
+      MathInline { .. } => "MathInline",
+      MathBlock { .. } => "MathBlock",
+      DiagramBlock { .. } => "DiagramBlock",
+      Footnote { .. } => "Footnote",
+      DefinitionList => "DefinitionList",
+      DefinitionTerm => "DefinitionTerm",
+      DefinitionDescription => "DefinitionDescription",
+      AutoUrl { .. } => "AutoUrl",
+      Alert { .. } => "Alert",
+      Steps => "Steps",
+      Step { .. } => "Step",
+      Toc => "Toc",
+      Tabs { .. } => "Tabs",
+      Tab { .. } => "Tab",
+      CodeBlockExt { .. } => "CodeBlockExt",
+      CustomElement { .. } => "CustomElement",
+      Include { .. } => "Include",
+      Unknown { .. } => "Unknown",
+    }
+  }
+
+  /// Whether this kind appears inside a block's text rather than as a
+  /// block of its own.
+  pub fn is_inline(&self) -> bool {
+    use NodeKind::*;
+    match self {
+      Comment { block, .. } => !block,
+      Text { .. }
+      | Emphasis
+      | Strong
+      | Strikethrough
+      | Code { .. }
+      | CodeSpan { .. }
+      | Link { .. }
+      | Image { .. }
+      | AutoLink { .. }
+      | HardBreak
+      | SoftBreak
+      | HtmlInline { .. }
+      | HtmlTag { .. }
+      | LinkReference { .. }
+      | FootnoteReference { .. }
+      | Abbreviation { .. }
+      | TaskListMarker { .. }
+      | Emoji { .. }
+      | Mention { .. }
+      | IssueReference { .. }
+      | DocInlineTag { .. }
+      | MathInline { .. }
+      | AutoUrl { .. } => true,
+      _ => false,
+    }
+  }
 }
 
 /// Frontmatter format type
@@ -61,6 +61,8 @@ pub enum DocStyle {
   PyDoc,
   PyDocGoogle,
   PyDocNumpy,
+  /// JavaDoc written in Markdown on `///` lines (JDK 23)
+  JavaDocMarkdown,
 }
 
 impl fmt::Display for DocStyle {
@@ -71,6 +73,92 @@ impl fmt::Display for DocStyle {
       Self::PyDoc => write!(f, "PyDoc"),
       Self::PyDocGoogle => write!(f, "PyDoc (Google)"),
       Self::PyDocNumpy => write!(f, "PyDoc (NumPy)"),
+      Self::JavaDocMarkdown => write!(f, "JavaDoc (Markdown)"),
+    }
+  }
+}
+
+/// TSDoc release stage for API items.
+///
+/// Set by the `@alpha`, `@beta`, `@public`, `@internal` and
+/// `@experimental` modifier tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseStage {
+  Alpha,
+  Beta,
+  Public,
+  Internal,
+  Experimental,
+}
+
+impl ReleaseStage {
+  /// Map a lowercased tag name to its release stage.
+  pub fn from_tag(name: &str) -> Option<Self> {
+    match name {
+      "alpha" => Some(Self::Alpha),
+      "beta" => Some(Self::Beta),
+      "public" => Some(Self::Public),
+      "internal" => Some(Self::Internal),
+      "experimental" => Some(Self::Experimental),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for ReleaseStage {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Alpha => write!(f, "alpha"),
+      Self::Beta => write!(f, "beta"),
+      Self::Public => write!(f, "public"),
+      Self::Internal => write!(f, "internal"),
+      Self::Experimental => write!(f, "experimental"),
+    }
+  }
+}
+
+/// Where the type of a `DocParam` or `DocReturn` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeSource {
+  /// Written in the doc comment (`{string}`, `name (int):`, `:type:`)
+  Doc,
+  /// Taken from the signature's annotation (`name: int`, `-> str`)
+  Annotation,
+}
+
+impl TypeSource {
+  /// `Doc` when the comment wrote a type.
+  pub fn of_doc(type_expr: &Option<String>) -> Option<Self> {
+    type_expr.as_ref().map(|_| Self::Doc)
+  }
+}
+
+impl fmt::Display for TypeSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Doc => write!(f, "doc"),
+      Self::Annotation => write!(f, "annotation"),
+    }
+  }
+}
+
+/// Which side of an element an inline HTML tag is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlTagKind {
+  /// `<span>`
+  Open,
+  /// `</span>`
+  Close,
+  /// `<br />`
+  SelfClosing,
+}
+
+impl fmt::Display for HtmlTagKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Open => write!(f, "open"),
+      Self::Close => write!(f, "close"),
+      Self::SelfClosing => write!(f, "self-closing"),
     }
   }
 }
@@ -78,7 +166,7 @@ impl fmt::Display for DocStyle {
 /// Alert type for GitHub-style blockquote callouts
 ///
 /// Used with `> [!TYPE]` syntax in blockquotes.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AlertType {
   /// Highlights information users should take into account
   Note,
@@ -90,6 +178,23 @@ pub enum AlertType {
   Warning,
   /// Negative potential consequences of an action
   Caution,
+  /// A class GitHub doesn't define, such as `SUCCESS`, in uppercase
+  Other(String),
+}
+
+impl AlertType {
+  /// The type named `name` (any case); names other than GitHub's five are
+  /// `Other`.
+  pub fn from_name(name: &str) -> Self {
+    match name.to_ascii_uppercase().as_str() {
+      "NOTE" => Self::Note,
+      "TIP" => Self::Tip,
+      "IMPORTANT" => Self::Important,
+      "WARNING" => Self::Warning,
+      "CAUTION" => Self::Caution,
+      other => Self::Other(other.to_string()),
+    }
+  }
 }
 
 impl fmt::Display for AlertType {
@@ -100,6 +205,7 @@ impl fmt::Display for AlertType {
       Self::Important => write!(f, "IMPORTANT"),
       Self::Warning => write!(f, "WARNING"),
       Self::Caution => write!(f, "CAUTION"),
+      Self::Other(name) => write!(f, "{}", name),
     }
   }
 }
@@ -128,6 +234,17 @@ mod tests {
     assert_eq!(format!("{}", DocStyle::PyDocGoogle), "PyDoc (Google)");
   }
 
+  #[test]
+  fn test_release_stage_from_tag() {
+    assert_eq!(ReleaseStage::from_tag("beta"), Some(ReleaseStage::Beta));
+    assert_eq!(
+      ReleaseStage::from_tag("internal"),
+      Some(ReleaseStage::Internal)
+    );
+    assert_eq!(ReleaseStage::from_tag("remarks"), None);
+    assert_eq!(format!("{}", ReleaseStage::Alpha), "alpha");
+  }
+
   #[test]
   fn test_alert_type_display() {
     assert_eq!(format!("{}", AlertType::Note), "NOTE");
@@ -135,5 +252,7 @@ mod tests {
     assert_eq!(format!("{}", AlertType::Important), "IMPORTANT");
     assert_eq!(format!("{}", AlertType::Warning), "WARNING");
     assert_eq!(format!("{}", AlertType::Caution), "CAUTION");
+    assert_eq!(AlertType::from_name("success").to_string(), "SUCCESS");
+    assert_eq!(AlertType::from_name("Tip"), AlertType::Tip);
   }
 }
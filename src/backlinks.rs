@@ -0,0 +1,272 @@
+//! Backlinks index (`--backlinks`): for every processed document, the
+//! links in other processed documents that point to it, collected into
+//! `backlinks.json`.
+//!
+//! Relative link targets resolve against the linking file's directory:
+//! `../guide/setup.md#install` from `docs/faq/index.md` names
+//! `docs/guide/setup.md`. A target without an extension also matches the
+//! same path with `.md`, and one ending in `/` its `index.md` or
+//! `README.md`, as static site URLs are often written. URLs with a scheme,
+//! absolute paths and links within the same page are not backlinks.
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::esc;
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// A link from a document to a possibly local file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingLink {
+  /// Link target without its fragment
+  pub target: String,
+  /// Fragment after `#`, if any
+  pub anchor: Option<String>,
+  /// Line of the block holding the link
+  pub line: usize,
+}
+
+/// Relative links of a parsed document, in document order. Reference links
+/// are resolved through the document's link definitions.
+pub fn collect(doc: &Document) -> Vec<OutgoingLink> {
+  let mut definitions = HashMap::new();
+  collect_definitions(&doc.nodes, &mut definitions);
+  let mut out = Vec::new();
+  collect_links(&doc.nodes, &definitions, 0, &mut out);
+  out
+}
+
+fn collect_definitions<'a>(nodes: &'a [Node], out: &mut HashMap<String, &'a str>) {
+  for node in nodes {
+    if let NodeKind::LinkDefinition { label, url, .. } = &node.kind {
+      out.entry(label.to_lowercase()).or_insert(url);
+    }
+    collect_definitions(&node.children, out);
+  }
+}
+
+/// `line` is the line of the nearest node that has one.
+fn collect_links(
+  nodes: &[Node],
+  definitions: &HashMap<String, &str>,
+  line: usize,
+  out: &mut Vec<OutgoingLink>,
+) {
+  for node in nodes {
+    let line = if node.span.line > 0 {
+      node.span.line
+    } else {
+      line
+    };
+    let url = match &node.kind {
+      NodeKind::Link { url, .. } => Some(url.as_str()),
+      NodeKind::LinkReference { label, .. } => definitions.get(&label.to_lowercase()).copied(),
+      _ => None,
+    };
+    if let Some(link) = url.and_then(|url| local_link(url, line)) {
+      out.push(link);
+    }
+    collect_links(&node.children, definitions, line, out);
+  }
+}
+
+/// The link if `url` points to a file next to the document.
+fn local_link(url: &str, line: usize) -> Option<OutgoingLink> {
+  let url = url.trim();
+  let (target, anchor) = match url.split_once('#') {
+    Some((target, anchor)) => (target, Some(anchor.to_string())),
+    None => (url, None),
+  };
+  let target = target.split('?').next().unwrap_or(target);
+  let scheme = target
+    .split_once(':')
+    .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/'));
+  if target.is_empty() || scheme || target.starts_with('/') {
+    return None;
+  }
+  Some(OutgoingLink {
+    target: target.to_string(),
+    anchor: anchor.filter(|a| !a.is_empty()),
+    line,
+  })
+}
+
+/// One link into a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backlink {
+  pub source: PathBuf,
+  pub line: usize,
+  pub anchor: Option<String>,
+}
+
+/// Backlinks of every processed document, in input order.
+#[derive(Debug, Default)]
+pub struct BacklinkIndex {
+  pub documents: Vec<(PathBuf, Vec<Backlink>)>,
+}
+
+impl BacklinkIndex {
+  /// Resolve each file's links against the set of processed files. Links
+  /// to files outside the run and links from a file to itself are dropped.
+  pub fn build(files: &[(PathBuf, Vec<OutgoingLink>)]) -> Self {
+    let mut documents: Vec<(PathBuf, Vec<Backlink>)> = files
+      .iter()
+      .map(|(path, _)| (path.clone(), Vec::new()))
+      .collect();
+    let by_path: HashMap<PathBuf, usize> = files
+      .iter()
+      .enumerate()
+      .map(|(i, (path, _))| (lexical(path), i))
+      .collect();
+    for (source, links) in files {
+      let base = source.parent().unwrap_or(Path::new(""));
+      for link in links {
+        let Some(&i) = candidates(&base.join(&link.target))
+          .iter()
+          .find_map(|path| by_path.get(path))
+        else {
+          continue;
+        };
+        if documents[i].0 == *source {
+          continue;
+        }
+        documents[i].1.push(Backlink {
+          source: source.clone(),
+          line: link.line,
+          anchor: link.anchor.clone(),
+        });
+      }
+    }
+    Self { documents }
+  }
+
+  /// `{"documents":[{"file":...,"backlinks":[{"file","line","anchor"}]}]}`
+  pub fn to_json(&self) -> String {
+    let mut s = String::with_capacity(64 + self.documents.len() * 96);
+    s.push_str("{\"documents\":[");
+    for (i, (path, backlinks)) in self.documents.iter().enumerate() {
+      if i > 0 {
+        s.push(',');
+      }
+      s.push_str(&format!(
+        "{{\"file\":\"{}\",\"backlinks\":[",
+        esc(&display(path))
+      ));
+      for (j, link) in backlinks.iter().enumerate() {
+        if j > 0 {
+          s.push(',');
+        }
+        s.push_str(&format!(
+          "{{\"file\":\"{}\",\"line\":{},\"anchor\":{}}}",
+          esc(&display(&link.source)),
+          link.line,
+          link
+            .anchor
+            .as_deref()
+            .map_or_else(|| "null".to_string(), |a| format!("\"{}\"", esc(a)))
+        ));
+      }
+      s.push_str("]}");
+    }
+    s.push_str("]}");
+    s
+  }
+}
+
+/// Files a resolved link target may name.
+fn candidates(target: &Path) -> Vec<PathBuf> {
+  let path = lexical(target);
+  let text = target.to_string_lossy();
+  if text.ends_with('/') || text.is_empty() {
+    return vec![path.join("index.md"), path.join("README.md")];
+  }
+  if path.extension().is_none() {
+    return vec![path.with_extension("md"), path];
+  }
+  vec![path]
+}
+
+/// `path` with `.` and `..` components resolved without touching the disk.
+fn lexical(path: &Path) -> PathBuf {
+  let mut out = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir
+        if matches!(out.components().next_back(), Some(Component::Normal(_))) =>
+      {
+        out.pop();
+      }
+      other => out.push(other),
+    }
+  }
+  out
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn links(source: &str) -> Vec<OutgoingLink> {
+    collect(&MarkdownParser::new(source).parse())
+  }
+
+  #[test]
+  fn test_collect() {
+    let found = links(
+      "See [setup](../guide/setup.md#install) and [site](https://x.io/a.md).\n\n\
+       [Ref][faq], [top](#top), [abs](/a.md), <mailto:a@b.c>\n\n[faq]: faq.md\n",
+    );
+    assert_eq!(
+      found,
+      vec![
+        OutgoingLink {
+          target: "../guide/setup.md".to_string(),
+          anchor: Some("install".to_string()),
+          line: 1,
+        },
+        OutgoingLink {
+          target: "faq.md".to_string(),
+          anchor: None,
+          line: 3,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_build() {
+    let files = vec![
+      (
+        PathBuf::from("docs/faq/index.md"),
+        links("[a](../guide/setup.md#install) [b](../guide/) [c](./index.md)\n"),
+      ),
+      (PathBuf::from("docs/guide/index.md"), links("[x](setup)\n")),
+      (
+        PathBuf::from("docs/guide/setup.md"),
+        links("[y](nowhere.md)\n"),
+      ),
+    ];
+    let index = BacklinkIndex::build(&files);
+    assert_eq!(
+      index.to_json(),
+      "{\"documents\":[{\"file\":\"docs/faq/index.md\",\"backlinks\":[]},\
+       {\"file\":\"docs/guide/index.md\",\"backlinks\":[\
+       {\"file\":\"docs/faq/index.md\",\"line\":1,\"anchor\":null}]},\
+       {\"file\":\"docs/guide/setup.md\",\"backlinks\":[\
+       {\"file\":\"docs/faq/index.md\",\"line\":1,\"anchor\":\"install\"},\
+       {\"file\":\"docs/guide/index.md\",\"line\":1,\"anchor\":null}]}]}"
+    );
+  }
+
+  #[test]
+  fn test_lexical() {
+    assert_eq!(lexical(Path::new("a/./b/../c.md")), PathBuf::from("a/c.md"));
+    assert_eq!(lexical(Path::new("../x.md")), PathBuf::from("../x.md"));
+  }
+}
@@ -2,8 +2,68 @@
 //!
 //! Provides lightweight timing measurements without external dependencies.
 
+#[cfg(any(test, feature = "bench"))]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
 use std::time::{Duration, Instant};
 
+/// Whether allocations are counted: the counting allocator is installed
+/// only in tests and with the `bench` feature.
+pub const COUNTS_ALLOCATIONS: bool = cfg!(any(test, feature = "bench"));
+
+/// The system allocator, counting allocations per thread so benchmarks
+/// can report them.
+#[cfg(any(test, feature = "bench"))]
+struct CountingAlloc;
+
+thread_local! {
+  static ALLOCATIONS: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+#[cfg(any(test, feature = "bench"))]
+fn record(size: usize) {
+  let _ = ALLOCATIONS.try_with(|counts| {
+    let (count, bytes) = counts.get();
+    counts.set((count + 1, bytes + size));
+  });
+}
+
+// SAFETY: every call is forwarded to `System` unchanged.
+#[cfg(any(test, feature = "bench"))]
+unsafe impl GlobalAlloc for CountingAlloc {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    record(layout.size());
+    System.alloc(layout)
+  }
+
+  unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+    record(layout.size());
+    System.alloc_zeroed(layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    record(new_size);
+    System.realloc(ptr, layout, new_size)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+}
+
+#[cfg(any(test, feature = "bench"))]
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Heap allocations (and reallocations) made by `f` on this thread, with
+/// the bytes requested; always zero without [`COUNTS_ALLOCATIONS`].
+pub fn count_allocations<F: FnOnce()>(f: F) -> (usize, usize) {
+  let before = ALLOCATIONS.with(Cell::get);
+  f();
+  let after = ALLOCATIONS.with(Cell::get);
+  (after.0 - before.0, after.1 - before.1)
+}
+
 /// Result of a benchmark run.
 #[derive(Debug, Clone)]
 pub struct BenchResult {
@@ -130,6 +190,16 @@ mod tests {
     assert!(count >= 100); // includes warmup
   }
 
+  #[test]
+  fn test_count_allocations() {
+    let (count, bytes) = count_allocations(|| {
+      let v: Vec<u64> = Vec::with_capacity(8);
+      std::hint::black_box(v);
+    });
+    assert_eq!((count, bytes), (1, 64));
+    assert_eq!(count_allocations(|| {}), (0, 0));
+  }
+
   #[test]
   fn test_bench_suite() {
     let mut suite = BenchSuite::new();
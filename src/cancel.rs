@@ -0,0 +1,51 @@
+//! Ctrl-C handling. The first SIGINT asks the run to stop: no new files are
+//! started, files already being processed finish, and the summary covers
+//! what was done. A second SIGINT ends the process at once.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Route SIGINT to `cancel`. Only on Unix; elsewhere Ctrl-C still ends the
+/// process immediately.
+pub fn install() {
+  #[cfg(unix)]
+  unix::install();
+}
+
+/// Ask the run to stop after the files in progress.
+pub fn cancel() {
+  CANCELLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_cancelled() -> bool {
+  CANCELLED.load(Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+mod unix {
+  use std::os::raw::c_int;
+
+  const SIGINT: c_int = 2;
+  const SIG_DFL: usize = 0;
+
+  extern "C" {
+    fn signal(signum: c_int, handler: usize) -> usize;
+  }
+
+  extern "C" fn on_sigint(_: c_int) {
+    super::cancel();
+    // SAFETY: `signal` is async-signal-safe. Restoring the default action
+    // lets a second Ctrl-C terminate the process.
+    unsafe {
+      signal(SIGINT, SIG_DFL);
+    }
+  }
+
+  pub fn install() {
+    // SAFETY: `on_sigint` only stores to an atomic and calls `signal`.
+    unsafe {
+      signal(SIGINT, on_sigint as extern "C" fn(c_int) as usize);
+    }
+  }
+}
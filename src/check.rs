@@ -0,0 +1,353 @@
+//! Code block checks (`--check-code`): a command run on every block written
+//! by `--extract-code`, such as
+//! `rustc --edition 2021 --crate-type lib {file}`, with the outcome of each
+//! run collected into `code/check.json` and failures reported as
+//! validation errors.
+//!
+//! Commands run through the shell (`sh -c`, `cmd /C` on Windows) on up to
+//! `--threads` blocks at a time. `{file}` in the command is replaced by the
+//! block's file, quoted for the shell, and `{lang}` by its language. A run
+//! that outlives `--check-timeout` is killed. Each run's output goes to a
+//! `.log` file next to the block, kept only when the check does not pass.
+
+use crate::cancel;
+use crate::extract::ExtractedCode;
+use crate::formats::esc;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Seconds a check may run by default.
+pub const DEFAULT_TIMEOUT: u64 = 30;
+
+/// How often a running check is polled for exit.
+const POLL: Duration = Duration::from_millis(10);
+
+/// What to run and how.
+#[derive(Debug, Clone)]
+pub struct CheckConfig {
+  /// Command template with `{file}` and `{lang}` placeholders
+  pub command: String,
+  pub timeout: Duration,
+  /// Checks run at the same time
+  pub jobs: usize,
+}
+
+/// How a check ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+  /// Exit code 0
+  Pass,
+  /// Non-zero exit code, `None` when ended by a signal
+  Fail(Option<i32>),
+  /// Killed after the timeout
+  Timeout,
+  /// The command could not be started
+  Error(String),
+  /// Not run because the run was cancelled
+  Skipped,
+}
+
+impl Status {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Status::Pass => "pass",
+      Status::Fail(_) => "fail",
+      Status::Timeout => "timeout",
+      Status::Error(_) => "error",
+      Status::Skipped => "skipped",
+    }
+  }
+
+  /// Whether the check ran and did not pass.
+  pub fn failed(&self) -> bool {
+    !matches!(self, Status::Pass | Status::Skipped)
+  }
+}
+
+/// Outcome of one block's check.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+  pub block: ExtractedCode,
+  pub status: Status,
+  pub duration: Duration,
+  /// Output of a failed check, relative to the output directory
+  pub log: Option<PathBuf>,
+}
+
+impl CheckResult {
+  /// One line for the validation report.
+  pub fn message(&self) -> String {
+    let file = display(&self.block.path);
+    match &self.status {
+      Status::Fail(Some(code)) => format!("code block {} failed (exit code {})", file, code),
+      Status::Fail(None) => format!("code block {} failed (killed by a signal)", file),
+      Status::Timeout => format!(
+        "code block {} timed out after {:.1}s",
+        file,
+        self.duration.as_secs_f64()
+      ),
+      Status::Error(e) => format!("code block {}: cannot run check: {}", file, e),
+      Status::Pass | Status::Skipped => format!("code block {} {}", file, self.status.name()),
+    }
+  }
+}
+
+/// Check every block, `config.jobs` at a time; `output` is the directory
+/// the block paths are relative to. Results are in the order of `blocks`.
+pub fn run_checks(
+  blocks: &[ExtractedCode],
+  output: &Path,
+  config: &CheckConfig,
+) -> Vec<CheckResult> {
+  let next = AtomicUsize::new(0);
+  let results: Mutex<Vec<Option<CheckResult>>> = Mutex::new(vec![None; blocks.len()]);
+  thread::scope(|scope| {
+    for _ in 0..config.jobs.clamp(1, blocks.len().max(1)) {
+      scope.spawn(|| loop {
+        let i = next.fetch_add(1, Ordering::SeqCst);
+        let Some(block) = blocks.get(i) else {
+          break;
+        };
+        let result = if cancel::is_cancelled() {
+          CheckResult {
+            block: block.clone(),
+            status: Status::Skipped,
+            duration: Duration::ZERO,
+            log: None,
+          }
+        } else {
+          check(block, output, config)
+        };
+        results.lock().unwrap()[i] = Some(result);
+      });
+    }
+  });
+  results
+    .into_inner()
+    .unwrap()
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn check(block: &ExtractedCode, output: &Path, config: &CheckConfig) -> CheckResult {
+  let file = output.join(&block.path);
+  let log_rel = log_path(&block.path);
+  let log = output.join(&log_rel);
+  let command = expand(&config.command, &file, &block.language);
+  let start = Instant::now();
+  let status = match spawn(&command, &log) {
+    Ok(child) => wait(child, config.timeout),
+    Err(e) => Status::Error(e),
+  };
+  let log = if status.failed() && log.exists() {
+    Some(log_rel)
+  } else {
+    let _ = fs::remove_file(&log);
+    None
+  };
+  CheckResult {
+    block: block.clone(),
+    status,
+    duration: start.elapsed(),
+    log,
+  }
+}
+
+/// `block.rs` -> `block.rs.log`
+fn log_path(path: &Path) -> PathBuf {
+  let mut log = path.as_os_str().to_owned();
+  log.push(".log");
+  PathBuf::from(log)
+}
+
+/// The command with its placeholders filled in, both quoted: the language
+/// comes from the document's fence info string.
+fn expand(template: &str, file: &Path, language: &str) -> String {
+  template
+    .replace("{file}", &shell_quote(&file.to_string_lossy()))
+    .replace("{lang}", &shell_quote(language))
+}
+
+#[cfg(not(windows))]
+fn shell_quote(text: &str) -> String {
+  format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(text: &str) -> String {
+  format!("\"{}\"", text)
+}
+
+/// Start `command` in the shell with stdout and stderr going to `log`.
+fn spawn(command: &str, log: &Path) -> Result<Child, String> {
+  let out = File::create(log).map_err(|e| format!("cannot create {}: {}", log.display(), e))?;
+  let err = out.try_clone().map_err(|e| e.to_string())?;
+  #[cfg(not(windows))]
+  let mut shell = {
+    let mut shell = Command::new("sh");
+    shell.arg("-c");
+    shell
+  };
+  #[cfg(windows)]
+  let mut shell = {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C");
+    shell
+  };
+  shell
+    .arg(command)
+    .stdin(Stdio::null())
+    .stdout(out)
+    .stderr(err)
+    .spawn()
+    .map_err(|e| e.to_string())
+}
+
+/// Wait for `child` to exit, killing it once `timeout` has passed.
+fn wait(mut child: Child, timeout: Duration) -> Status {
+  let deadline = Instant::now() + timeout;
+  loop {
+    match child.try_wait() {
+      Ok(Some(status)) => return exit_status(status),
+      Ok(None) if Instant::now() >= deadline => {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Status::Timeout;
+      }
+      Ok(None) => thread::sleep(POLL),
+      Err(e) => return Status::Error(e.to_string()),
+    }
+  }
+}
+
+fn exit_status(status: ExitStatus) -> Status {
+  if status.success() {
+    Status::Pass
+  } else {
+    Status::Fail(status.code())
+  }
+}
+
+/// `{"command":...,"passed":N,"failed":N,"blocks":[{"file","source","line",
+/// "language","status","exit_code","ms","log"}]}`
+pub fn to_json(command: &str, results: &[CheckResult]) -> String {
+  let passed = results.iter().filter(|r| r.status == Status::Pass).count();
+  let failed = results.iter().filter(|r| r.status.failed()).count();
+  let mut s = String::with_capacity(64 + results.len() * 192);
+  s.push_str(&format!(
+    "{{\"command\":\"{}\",\"passed\":{},\"failed\":{},\"blocks\":[",
+    esc(command),
+    passed,
+    failed
+  ));
+  for (i, result) in results.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    let exit_code = match result.status {
+      Status::Pass => "0".to_string(),
+      Status::Fail(Some(code)) => code.to_string(),
+      _ => "null".to_string(),
+    };
+    s.push_str(&format!(
+      "{{\"file\":\"{}\",\"source\":\"{}\",\"line\":{},\"language\":\"{}\",\
+       \"status\":\"{}\",\"exit_code\":{},\"ms\":{},\"log\":{}}}",
+      esc(&display(&result.block.path)),
+      esc(&display(&result.block.source)),
+      result.block.line,
+      esc(&result.block.language),
+      result.status.name(),
+      exit_code,
+      result.duration.as_millis(),
+      result.log.as_deref().map_or_else(
+        || "null".to_string(),
+        |log| format!("\"{}\"", esc(&display(log)))
+      )
+    ));
+  }
+  s.push_str("]}");
+  s
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  fn block(name: &str, language: &str) -> ExtractedCode {
+    ExtractedCode {
+      path: PathBuf::from(format!("code/{}/{}", language, name)),
+      language: language.to_string(),
+      source: PathBuf::from("guide.md"),
+      line: 3,
+    }
+  }
+
+  #[test]
+  fn test_run_checks() {
+    let dir = std::env::temp_dir().join(format!("bukvar-check-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let blocks = vec![
+      block("ok.sh", "sh"),
+      block("bad.sh", "sh"),
+      block("slow.sh", "sh"),
+    ];
+    for (b, body) in blocks
+      .iter()
+      .zip(["exit 0", "echo broken; exit 3", "sleep 5"])
+    {
+      fs::create_dir_all(dir.join(&b.path).parent().unwrap()).unwrap();
+      fs::write(dir.join(&b.path), body).unwrap();
+    }
+    let config = CheckConfig {
+      command: "test {lang} = sh && sh {file}".to_string(),
+      timeout: Duration::from_millis(300),
+      jobs: 2,
+    };
+    let results = run_checks(&blocks, &dir, &config);
+    let statuses: Vec<&Status> = results.iter().map(|r| &r.status).collect();
+    assert_eq!(
+      statuses,
+      [&Status::Pass, &Status::Fail(Some(3)), &Status::Timeout]
+    );
+    assert_eq!(results[0].log, None);
+    assert!(!dir.join("code/sh/ok.sh.log").exists());
+    let log = results[1].log.as_ref().unwrap();
+    assert_eq!(fs::read_to_string(dir.join(log)).unwrap(), "broken\n");
+    assert_eq!(
+      results[1].message(),
+      "code block code/sh/bad.sh failed (exit code 3)"
+    );
+
+    let json = to_json(&config.command, &results);
+    assert!(json.contains("\"passed\":1,\"failed\":2,"));
+    assert!(json.contains(
+      "\"file\":\"code/sh/bad.sh\",\"source\":\"guide.md\",\"line\":3,\"language\":\"sh\",\
+       \"status\":\"fail\",\"exit_code\":3,"
+    ));
+    assert!(json.contains("\"log\":\"code/sh/bad.sh.log\"}"));
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_expand() {
+    assert_eq!(
+      expand("rustc {file} # {lang}", Path::new("out/it's.rs"), "rust"),
+      "rustc 'out/it'\\''s.rs' # 'rust'"
+    );
+    assert_eq!(
+      expand("true {lang}", Path::new("a.sh"), "sh;>pwned"),
+      "true 'sh;>pwned'"
+    );
+  }
+}
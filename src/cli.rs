@@ -1,5 +1,12 @@
 //! CLI argument parsing
 
+use crate::check;
+use crate::formats::{is_kind_name, JsonOptions};
+use crate::locale;
+use crate::markdown::{trace, KindFilter, ParseOptions};
+use crate::parsers::version::Version;
+use crate::processor::transform::SanitizeMode;
+
 use std::env;
 use std::path::PathBuf;
 
@@ -8,21 +15,231 @@ pub struct Args {
   pub input: PathBuf,
   pub output: PathBuf,
   pub format: OutputFormat,
+  /// Deepest AST level drawn by `-f dot`
+  pub dot_depth: Option<usize>,
   pub recursive: bool,
+  /// Follow symlinks while collecting files (off with `--no-follow-symlinks`)
+  pub follow_symlinks: bool,
+  /// Collect hidden files and directories (off with `--no-hidden`)
+  pub hidden: bool,
+  /// Directory levels to read (`--max-depth`), 1 being the input directory
+  pub max_depth: Option<usize>,
+  pub flat: bool,
   pub verbose: bool,
+  pub quiet: bool,
+  pub json_output: bool,
   pub parallel: bool,
+  /// Parse the blocks of one Markdown file on several threads
+  pub parallel_blocks: bool,
+  /// Worker threads (`--threads`); the available parallelism when unset
+  pub threads: Option<usize>,
+  /// Run at a lower scheduling priority (`--nice`)
+  pub nice: bool,
   pub pretty: bool,
+  /// Write node spans in JSON output (off with `--no-spans`)
+  pub spans: bool,
+  /// Node kinds kept in JSON output (`--json-kinds`)
+  pub json_kinds: Option<Vec<String>>,
+  /// Sort the DAST string table for reproducible output (`--sort-strings`)
+  pub sort_strings: bool,
+  /// End DAST files with an index of top-level nodes and headings (`--dast-index`)
+  pub dast_index: bool,
+  pub html_tags: bool,
+  pub extended_autolinks: bool,
+  /// Fill in the document title, description, dates and authors (`--extract-metadata`)
+  pub extract_metadata: bool,
+  /// Record the span of each heading's section (`--section-spans`)
+  pub section_spans: bool,
+  /// Drop HTML comments from the output (`--strip-comments`)
+  pub strip_comments: bool,
+  /// Replace `{{name}}` with frontmatter values (`--vars`)
+  pub vars: bool,
+  /// Hash each block's content for incremental rendering (`--content-hashes`)
+  pub content_hashes: bool,
+  pub dollar_math: bool,
+  /// Parse `\(...\)` and `\[...\]` as math (`--latex-math`)
+  pub latex_math: bool,
+  pub strict_math: bool,
+  /// Let `||` in a table row join a cell with the one before (`--table-colspan`)
+  pub table_colspan: bool,
+  /// Collect every triple-quoted Python string (`--all-strings`)
+  pub all_strings: bool,
+  /// Node kinds the Markdown parser builds (`--only-kinds`)
+  pub only_kinds: Option<Vec<String>>,
+  /// Node kinds the Markdown parser leaves out (`--skip-kinds`)
+  pub skip_kinds: Option<Vec<String>>,
+  pub sanitize: Option<SanitizeMode>,
+  pub rewrite_config: Option<PathBuf>,
   pub validate: bool,
+  pub fail_on_error: bool,
+  pub fail_on_warning: bool,
+  pub min_doc_coverage: Option<f64>,
+  /// Release that `@since` tags may not exceed (`--current-version`)
+  pub current_version: Option<Version>,
+  pub lint: bool,
+  pub lint_config: Option<PathBuf>,
   pub sourcemap: bool,
+  /// Add UTF-16 line/character ranges to source maps (`--sourcemap-utf16`)
+  pub sourcemap_utf16: bool,
+  /// Write each document's anchor map (`--anchors`)
+  pub anchors: bool,
+  pub extract_doctests: bool,
+  /// Write fenced code blocks under `code/` (`--extract-code[=LANGS]`);
+  /// the languages to extract, or every language when empty
+  pub extract_code: Option<Vec<String>>,
+  /// Command run on each extracted code block (`--check-code`)
+  pub check_code: Option<String>,
+  /// Seconds before a code block check is killed (`--check-timeout`)
+  pub check_timeout: u64,
+  /// Write the rules the Markdown parser tried (`--trace-parse`)
+  pub trace_parse: bool,
+  /// Turn source files into API reference Markdown (`--api-reference`)
+  pub api_reference: bool,
+  /// Write the cross-reference table of `{@link}` and `@see` targets (`--xref`)
+  pub xref: bool,
+  /// Write the deprecation report (`--deprecations`)
+  pub deprecations: bool,
+  /// Write node counts and byte totals per kind (`--analyze`)
+  pub analyze: bool,
+  /// Write the navigation tree of the processed documents (`--nav`)
+  pub nav: bool,
+  /// Locales every page should be translated into, the default first
+  /// (`--locales`)
+  pub locales: Vec<String>,
+  /// Write which documents link to each document (`--backlinks`)
+  pub backlinks: bool,
+  /// Write the releases of Keep a Changelog files (`--releases`)
+  pub releases: bool,
   pub bench: bool,
+  /// Print the parser for each file extension (`--list-parsers`)
+  pub list_parsers: bool,
   pub streaming: bool,
+  /// Stream Markdown files larger than this many bytes (`--streaming-threshold`)
+  pub streaming_threshold: Option<u64>,
+  /// Report peak memory and estimated AST sizes (`--mem-stats`)
+  pub mem_stats: bool,
+  /// Files to sample for a pre-flight estimate instead of a run (`--estimate`)
+  pub estimate: Option<usize>,
   pub extensions: Vec<String>,
+  pub unknown: UnknownPolicy,
+  /// How each file's parser is chosen (`--detect`)
+  pub detect: DetectMode,
+  /// Which Markdown files marked as drafts are processed (`--drafts`)
+  pub drafts: DraftPolicy,
+  pub elements: Option<PathBuf>,
+  pub serve: Option<ServeMode>,
+  /// Write the source-and-AST debug page for this file
+  pub explain: Option<PathBuf>,
+  /// `bukvar inspect <FILE>`: decode a DAST file to JSON
+  pub inspect: bool,
+  /// Check the DAST checksum when reading (off with `--no-verify`)
+  pub verify: bool,
+  /// `inspect --strings`: report string storage instead of the AST
+  pub string_stats: bool,
+  /// `inspect --index`: print the DAST index instead of the AST
+  pub index_entries: bool,
+  /// `bukvar schema`: print the JSON Schema of the JSON output
+  pub schema: bool,
+  /// `bukvar merge <FILE>... | <DIR>`: the chapters to combine
+  pub merge: Option<Vec<PathBuf>>,
+}
+
+/// Transport for `bukvar serve`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServeMode {
+  /// Content-Length framed JSON requests on stdin, responses on stdout
+  Stdio,
+  /// HTTP/1.1 server bound to the given address
+  Http(String),
+}
+
+/// What to do with selected files no parser understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownPolicy {
+  /// Leave them out (logged with `--verbose`)
+  Skip,
+  /// Parse them as plain Markdown
+  Text,
+  /// Count each as a failed file
+  Error,
+}
+
+/// What to do with Markdown files whose frontmatter has `draft: true` or
+/// `publish: false`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DraftPolicy {
+  /// Leave them out (logged with `--verbose`)
+  Skip,
+  /// Process them, marked as drafts in `nav.json`
+  Include,
+  /// Process only drafts
+  Only,
+}
+
+/// How a file's parser is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectMode {
+  /// By file extension only
+  Extension,
+  /// By content only: shebang, leading `/**`, frontmatter
+  Content,
+  /// By extension, then by content when the extension is missing or unknown
+  Both,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
   Dast,
   Json,
+  /// Graphviz graph of the AST, for debugging
+  Dot,
+  /// S-expression dump, for diffs and golden tests
+  Sexp,
+  /// Reading-order segments, for text-to-speech and accessibility tools
+  Segments,
+}
+
+impl Args {
+  /// Markdown parser settings selected on the command line.
+  pub fn parse_options(&self) -> ParseOptions {
+    ParseOptions {
+      dollar_math: self.dollar_math,
+      latex_math: self.latex_math,
+      strict_math: self.strict_math,
+      table_colspan: self.table_colspan,
+      all_strings: self.all_strings,
+      kinds: KindFilter::ALL
+        .with_only(self.only_kinds.as_deref().unwrap_or_default())
+        .with_skip(self.skip_kinds.as_deref().unwrap_or_default()),
+    }
+  }
+
+  /// Worker threads: `--threads`, or the available parallelism.
+  pub fn thread_count(&self) -> usize {
+    self.threads.unwrap_or_else(|| {
+      std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+    })
+  }
+
+  /// Threads for parsing the blocks of one Markdown file; one when
+  /// tracing, which only sees the parsing thread.
+  pub fn block_threads(&self) -> usize {
+    if self.parallel_blocks && !self.trace_parse {
+      self.thread_count()
+    } else {
+      1
+    }
+  }
+
+  pub fn json_options(&self) -> JsonOptions {
+    JsonOptions {
+      pretty: self.pretty,
+      spans: self.spans,
+      kinds: self.json_kinds.clone(),
+    }
+  }
 }
 
 impl Default for Args {
@@ -31,14 +248,69 @@ impl Default for Args {
       input: PathBuf::from("."),
       output: PathBuf::from("./ast_output"),
       format: OutputFormat::Dast,
+      dot_depth: None,
       recursive: true,
+      follow_symlinks: true,
+      hidden: true,
+      max_depth: None,
+      flat: false,
       verbose: false,
+      quiet: false,
+      json_output: false,
       parallel: true,
+      parallel_blocks: false,
+      threads: None,
+      nice: false,
       pretty: false,
+      spans: true,
+      json_kinds: None,
+      sort_strings: false,
+      dast_index: false,
+      html_tags: false,
+      extended_autolinks: false,
+      extract_metadata: false,
+      section_spans: false,
+      strip_comments: false,
+      vars: false,
+      content_hashes: false,
+      dollar_math: true,
+      latex_math: false,
+      strict_math: false,
+      table_colspan: false,
+      all_strings: false,
+      only_kinds: None,
+      skip_kinds: None,
+      sanitize: None,
+      rewrite_config: None,
       validate: false,
+      fail_on_error: false,
+      fail_on_warning: false,
+      min_doc_coverage: None,
+      current_version: None,
+      lint: false,
+      lint_config: None,
       sourcemap: false,
+      sourcemap_utf16: false,
+      anchors: false,
+      extract_doctests: false,
+      extract_code: None,
+      check_code: None,
+      check_timeout: check::DEFAULT_TIMEOUT,
+      trace_parse: false,
+      api_reference: false,
+      xref: false,
+      deprecations: false,
+      analyze: false,
+      nav: false,
+      locales: Vec::new(),
+      backlinks: false,
+      releases: false,
       bench: false,
+      list_parsers: false,
       streaming: false,
+      streaming_threshold: None,
+      mem_stats: false,
+      estimate: None,
       extensions: vec![
         "md".to_string(),
         "markdown".to_string(),
@@ -52,19 +324,45 @@ impl Default for Args {
         "py".to_string(),
         "pyi".to_string(),
       ],
+      unknown: UnknownPolicy::Skip,
+      detect: DetectMode::Extension,
+      drafts: DraftPolicy::Include,
+      elements: None,
+      serve: None,
+      explain: None,
+      inspect: false,
+      verify: true,
+      string_stats: false,
+      index_entries: false,
+      schema: false,
+      merge: None,
     }
   }
 }
 
 pub fn parse_args() -> Result<Args, String> {
-  let args: Vec<String> = env::args().collect();
+  parse_args_from(env::args().collect())
+}
 
+/// Parse a command line whose first item is the program name.
+fn parse_args_from(args: Vec<String>) -> Result<Args, String> {
   if args.len() < 2 {
     return Err(get_help());
   }
 
   let mut result = Args::default();
-  let mut i = 1;
+  let serve = args[1] == "serve";
+  let inspect = args[1] == "inspect";
+  result.inspect = inspect;
+  result.schema = args[1] == "schema";
+  if args[1] == "merge" {
+    result.merge = Some(Vec::new());
+  }
+  let mut i = if serve || inspect || result.schema || result.merge.is_some() {
+    2
+  } else {
+    1
+  };
 
   while i < args.len() {
     match args[i].as_str() {
@@ -96,9 +394,30 @@ pub fn parse_args() -> Result<Args, String> {
         result.format = match args[i].to_lowercase().as_str() {
           "dast" | "binary" => OutputFormat::Dast,
           "json" => OutputFormat::Json,
-          _ => return Err(format!("Unknown format: {}. Use 'dast' or 'json'", args[i])),
+          "dot" | "graphviz" => OutputFormat::Dot,
+          "sexp" => OutputFormat::Sexp,
+          "segments" => OutputFormat::Segments,
+          _ => {
+            return Err(format!(
+              "Unknown format: {}. Use 'dast', 'json', 'dot', 'sexp' or 'segments'",
+              args[i]
+            ))
+          }
         };
       }
+      "--dot-depth" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --dot-depth".to_string());
+        }
+        let depth = args[i].parse().ok().filter(|d| *d > 0).ok_or_else(|| {
+          format!(
+            "Invalid --dot-depth: {} (expected a positive number)",
+            args[i]
+          )
+        })?;
+        result.dot_depth = Some(depth);
+      }
       "-e" | "--ext" | "--extensions" => {
         i += 1;
         if i >= args.len() {
@@ -106,36 +425,384 @@ pub fn parse_args() -> Result<Args, String> {
         }
         result.extensions = args[i].split(',').map(|s| s.trim().to_string()).collect();
       }
+      "--unknown" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --unknown".to_string());
+        }
+        result.unknown = parse_unknown_policy(&args[i])?;
+      }
+      arg if arg.starts_with("--unknown=") => {
+        result.unknown = parse_unknown_policy(&arg["--unknown=".len()..])?;
+      }
+      "--detect" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --detect".to_string());
+        }
+        result.detect = parse_detect_mode(&args[i])?;
+      }
+      arg if arg.starts_with("--detect=") => {
+        result.detect = parse_detect_mode(&arg["--detect=".len()..])?;
+      }
+      "--drafts" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --drafts".to_string());
+        }
+        result.drafts = parse_draft_policy(&args[i])?;
+      }
+      arg if arg.starts_with("--drafts=") => {
+        result.drafts = parse_draft_policy(&arg["--drafts=".len()..])?;
+      }
+      "--elements" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --elements".to_string());
+        }
+        result.elements = Some(PathBuf::from(&args[i]));
+      }
       "--no-recursive" => {
         result.recursive = false;
       }
       "-r" | "--recursive" => {
         result.recursive = true;
       }
+      "--no-follow-symlinks" => {
+        result.follow_symlinks = false;
+      }
+      "--no-hidden" => {
+        result.hidden = false;
+      }
+      "--max-depth" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --max-depth".to_string());
+        }
+        let depth = args[i].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+          format!(
+            "Invalid --max-depth: {} (expected a positive number)",
+            args[i]
+          )
+        })?;
+        result.max_depth = Some(depth);
+      }
+      "--flat" => {
+        result.flat = true;
+      }
       "--verbose" => {
         result.verbose = true;
       }
+      "-q" | "--quiet" => {
+        result.quiet = true;
+      }
+      "--json-output" => {
+        result.json_output = true;
+      }
       "--no-parallel" => {
         result.parallel = false;
       }
+      "--parallel-blocks" => {
+        result.parallel_blocks = true;
+      }
+      "--threads" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --threads".to_string());
+        }
+        let threads = args[i].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+          format!(
+            "Invalid --threads: {} (expected a positive number)",
+            args[i]
+          )
+        })?;
+        result.threads = Some(threads);
+      }
+      "--nice" => {
+        result.nice = true;
+      }
       "--pretty" => {
         result.pretty = true;
       }
+      "--no-spans" => {
+        result.spans = false;
+      }
+      "--json-kinds" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --json-kinds".to_string());
+        }
+        result.json_kinds = Some(parse_kinds("--json-kinds", &args[i])?);
+      }
+      "--sort-strings" => {
+        result.sort_strings = true;
+      }
+      "--dast-index" => {
+        result.dast_index = true;
+      }
+      "--html-tags" => {
+        result.html_tags = true;
+      }
+      "--extended-autolinks" => {
+        result.extended_autolinks = true;
+      }
+      "--extract-metadata" => {
+        result.extract_metadata = true;
+      }
+      "--section-spans" => {
+        result.section_spans = true;
+      }
+      "--strip-comments" => {
+        result.strip_comments = true;
+      }
+      "--vars" => {
+        result.vars = true;
+      }
+      "--content-hashes" => {
+        result.content_hashes = true;
+      }
+      "--no-dollar-math" => {
+        result.dollar_math = false;
+      }
+      "--latex-math" => {
+        result.latex_math = true;
+      }
+      "--strict-math" => {
+        result.strict_math = true;
+      }
+      "--table-colspan" => {
+        result.table_colspan = true;
+      }
+      "--all-strings" => {
+        result.all_strings = true;
+      }
+      "--only-kinds" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --only-kinds".to_string());
+        }
+        result.only_kinds = Some(parse_kinds("--only-kinds", &args[i])?);
+      }
+      "--skip-kinds" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --skip-kinds".to_string());
+        }
+        result.skip_kinds = Some(parse_kinds("--skip-kinds", &args[i])?);
+      }
+      "--rewrite-config" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --rewrite-config".to_string());
+        }
+        result.rewrite_config = Some(PathBuf::from(&args[i]));
+      }
+      "--sanitize" => {
+        result.sanitize = Some(SanitizeMode::Strip);
+      }
+      arg if arg.starts_with("--sanitize=") => {
+        result.sanitize = Some(parse_sanitize_mode(&arg["--sanitize=".len()..])?);
+      }
       "--validate" => {
         result.validate = true;
       }
+      "--fail-on-error" => {
+        result.validate = true;
+        result.fail_on_error = true;
+      }
+      "--lint" => {
+        result.lint = true;
+      }
+      "--lint-config" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --lint-config".to_string());
+        }
+        result.lint = true;
+        result.lint_config = Some(PathBuf::from(&args[i]));
+      }
+      "--min-doc-coverage" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --min-doc-coverage".to_string());
+        }
+        let percent: f64 = args[i]
+          .trim_end_matches('%')
+          .parse()
+          .ok()
+          .filter(|p| (0.0..=100.0).contains(p))
+          .ok_or_else(|| format!("Invalid --min-doc-coverage: {} (expected 0-100)", args[i]))?;
+        result.min_doc_coverage = Some(percent);
+      }
+      "--current-version" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --current-version".to_string());
+        }
+        let version = Version::parse(&args[i]).ok_or_else(|| {
+          format!(
+            "Invalid --current-version: {} (expected e.g. 1.4.0)",
+            args[i]
+          )
+        })?;
+        result.validate = true;
+        result.current_version = Some(version);
+      }
+      "--fail-on-warning" => {
+        result.validate = true;
+        result.fail_on_warning = true;
+      }
       "--sourcemap" => {
         result.sourcemap = true;
       }
+      "--sourcemap-utf16" => {
+        result.sourcemap = true;
+        result.sourcemap_utf16 = true;
+      }
+      "--anchors" => {
+        result.anchors = true;
+      }
+      "--extract-doctests" => {
+        result.extract_doctests = true;
+      }
+      "--extract-code" => {
+        result.extract_code = Some(Vec::new());
+      }
+      arg if arg.starts_with("--extract-code=") => {
+        result.extract_code = Some(parse_languages(&arg["--extract-code=".len()..])?);
+      }
+      "--check-code" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --check-code".to_string());
+        }
+        result.check_code = Some(args[i].clone());
+        result.extract_code.get_or_insert_with(Vec::new);
+      }
+      "--check-timeout" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --check-timeout".to_string());
+        }
+        result.check_timeout = args[i].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+          format!(
+            "Invalid --check-timeout: {} (expected a positive number of seconds)",
+            args[i]
+          )
+        })?;
+      }
+      "--trace-parse" => {
+        if !trace::ENABLED {
+          return Err("--trace-parse needs a debug build or --features trace".to_string());
+        }
+        result.trace_parse = true;
+      }
+      "--api-reference" => {
+        result.api_reference = true;
+      }
+      "--xref" => {
+        result.xref = true;
+      }
+      "--deprecations" => {
+        result.deprecations = true;
+      }
+      "--analyze" => {
+        result.analyze = true;
+      }
+      "--nav" => {
+        result.nav = true;
+      }
+      "--locales" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --locales".to_string());
+        }
+        result.locales = args[i]
+          .split(',')
+          .map(|s| s.trim())
+          .filter(|s| !s.is_empty())
+          .map(locale::normalize)
+          .collect();
+      }
+      "--backlinks" => {
+        result.backlinks = true;
+      }
+      "--releases" => {
+        result.releases = true;
+      }
+      "--explain" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --explain".to_string());
+        }
+        result.explain = Some(PathBuf::from(&args[i]));
+      }
       "--bench" => {
         result.bench = true;
       }
+      "--list-parsers" => {
+        result.list_parsers = true;
+      }
       "--streaming" => {
         result.streaming = true;
       }
+      "--streaming-threshold" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --streaming-threshold".to_string());
+        }
+        let bytes = args[i].parse().map_err(|_| {
+          format!(
+            "Invalid --streaming-threshold: {} (expected a number of bytes)",
+            args[i]
+          )
+        })?;
+        result.streaming_threshold = Some(bytes);
+      }
+      "--mem-stats" => {
+        result.mem_stats = true;
+      }
+      "--estimate" => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --estimate".to_string());
+        }
+        let samples = args[i]
+          .parse()
+          .ok()
+          .filter(|&n: &usize| n > 0)
+          .ok_or_else(|| {
+            format!(
+              "Invalid --estimate: {} (expected a positive number)",
+              args[i]
+            )
+          })?;
+        result.estimate = Some(samples);
+      }
+      "--stdio" if serve => {
+        result.serve = Some(ServeMode::Stdio);
+      }
+      "--http" if serve => {
+        i += 1;
+        if i >= args.len() {
+          return Err("Missing argument for --http".to_string());
+        }
+        result.serve = Some(ServeMode::Http(args[i].clone()));
+      }
+      "--no-verify" if inspect => {
+        result.verify = false;
+      }
+      "--strings" if inspect => {
+        result.string_stats = true;
+      }
+      "--index" if inspect => {
+        result.index_entries = true;
+      }
       arg if !arg.starts_with('-') => {
         // Positional argument: treat first as input, second as output
-        if result.input.as_os_str() == "." {
+        if let Some(sources) = &mut result.merge {
+          sources.push(PathBuf::from(arg));
+        } else if result.input.as_os_str() == "." {
           result.input = PathBuf::from(arg);
         } else {
           result.output = PathBuf::from(arg);
@@ -148,29 +815,200 @@ pub fn parse_args() -> Result<Args, String> {
     i += 1;
   }
 
+  if result.only_kinds.is_some() && result.skip_kinds.is_some() {
+    return Err("--only-kinds and --skip-kinds can't be used together".to_string());
+  }
+  if serve && result.serve.is_none() {
+    return Err("serve needs a transport: --stdio or --http <ADDR>".to_string());
+  }
+  if inspect && result.input.as_os_str() == "." {
+    return Err("inspect needs a DAST file".to_string());
+  }
+  if result
+    .merge
+    .as_ref()
+    .is_some_and(|sources| sources.is_empty())
+  {
+    return Err("merge needs files or a directory with a toc.txt".to_string());
+  }
+
+  // Progress lines would break quiet output and the JSON summary
+  if result.quiet || result.json_output {
+    result.verbose = false;
+  }
+
   Ok(result)
 }
 
+fn parse_kinds(flag: &str, value: &str) -> Result<Vec<String>, String> {
+  let kinds: Vec<String> = value
+    .split(',')
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+    .collect();
+  match kinds.iter().find(|k| !is_kind_name(k)) {
+    Some(k) => Err(format!(
+      "Unknown node kind in {}: {} (names as in JSON \"type\", e.g. Heading)",
+      flag, k
+    )),
+    None if kinds.is_empty() => Err(format!("{} needs at least one node kind", flag)),
+    None => Ok(kinds),
+  }
+}
+
+fn parse_languages(value: &str) -> Result<Vec<String>, String> {
+  let languages: Vec<String> = value
+    .split(',')
+    .map(|s| s.trim().to_lowercase())
+    .filter(|s| !s.is_empty())
+    .collect();
+  if languages.is_empty() {
+    return Err("--extract-code= needs at least one language".to_string());
+  }
+  Ok(languages)
+}
+
+fn parse_sanitize_mode(value: &str) -> Result<SanitizeMode, String> {
+  match value {
+    "strip" => Ok(SanitizeMode::Strip),
+    "escape" => Ok(SanitizeMode::Escape),
+    _ => Err(format!(
+      "Invalid --sanitize mode: {} (expected strip or escape)",
+      value
+    )),
+  }
+}
+
+fn parse_unknown_policy(value: &str) -> Result<UnknownPolicy, String> {
+  match value {
+    "skip" => Ok(UnknownPolicy::Skip),
+    "text" => Ok(UnknownPolicy::Text),
+    "error" => Ok(UnknownPolicy::Error),
+    _ => Err(format!(
+      "Invalid --unknown policy: {} (expected skip, text or error)",
+      value
+    )),
+  }
+}
+
+fn parse_detect_mode(value: &str) -> Result<DetectMode, String> {
+  match value {
+    "extension" => Ok(DetectMode::Extension),
+    "content" => Ok(DetectMode::Content),
+    "both" => Ok(DetectMode::Both),
+    _ => Err(format!(
+      "Invalid --detect mode: {} (expected extension, content or both)",
+      value
+    )),
+  }
+}
+
+fn parse_draft_policy(value: &str) -> Result<DraftPolicy, String> {
+  match value {
+    "skip" => Ok(DraftPolicy::Skip),
+    "include" => Ok(DraftPolicy::Include),
+    "only" => Ok(DraftPolicy::Only),
+    _ => Err(format!(
+      "Invalid --drafts policy: {} (expected skip, include or only)",
+      value
+    )),
+  }
+}
+
 fn get_help() -> String {
   r#"bukvar - Ultra-fast zero-dependency markdown parser (Glagolica Project)
 
 USAGE:
     bukvar [OPTIONS] <INPUT> [OUTPUT]
+    bukvar serve --stdio [--elements <PATH>]
+    bukvar serve --http <ADDR> [--elements <PATH>]
+    bukvar inspect <FILE.dast> [--pretty] [--no-verify] [--strings] [--index]
+    bukvar merge <FILE>... | <DIR> [-o <PATH>] [-f <FMT>]
+    bukvar schema
 
 OPTIONS:
     -i, --input <PATH>      Input directory
     -o, --output <PATH>     Output directory (default: ./ast_output)
-    -f, --format <FMT>      dast (binary) or json (default: dast)
+    -f, --format <FMT>      dast (binary), json, dot (Graphviz), sexp or segments
+                            (default: dast)
+    --dot-depth <N>         Collapse AST levels below N in dot output
     -e, --extensions <EXT>  Comma-separated extensions
+    --elements <PATH>       Custom elements and alert keywords (one per line)
+    --unknown <POLICY>      Unparseable extensions: skip, text or error (default: skip)
+    --detect <MODE>         Choose parsers by extension, content or both (default: extension)
+    --drafts <POLICY>       Frontmatter drafts: skip, include or only (default: include)
     -r, --recursive         Recurse into subdirs (default: on)
     --no-recursive          Don't recurse
+    --max-depth <N>         Read at most N directory levels (1: input directory only)
+    --no-follow-symlinks    Skip symlinked files and directories
+    --no-hidden             Skip files and directories starting with '.'
+    --flat                  Write all outputs into one directory
     --no-parallel           Single-threaded
+    --parallel-blocks       Split large Markdown files and parse the parts in parallel
+    --threads <N>           Worker threads (default: available CPUs)
+    --nice                  Run at a lower scheduling priority
     --pretty                Pretty-print JSON output
+    --no-spans              Leave node spans out of JSON output
+    --json-kinds <K,...>    Only write these node kinds to JSON (e.g. Heading,Text)
+    --sort-strings          Sort the DAST string table (reproducible output)
+    --dast-index            End DAST files with offsets of top-level nodes and headings
+    --html-tags             Split inline HTML into HtmlTag nodes
+    --extended-autolinks    Link www. and bare domains (GFM extended autolinks)
+    --extract-metadata      Set title, description, dates and authors
+    --section-spans         Give each heading the span of the section it opens
+    --strip-comments        Drop <!-- --> comments from the output
+    --vars                  Replace {{name}} with the frontmatter's value
+    --content-hashes        Give each block a hash of its content
+    --no-dollar-math        Leave $ as text (for currency)
+    --latex-math            Parse \(...\) and \[...\] as math
+    --strict-math           Parse every $...$ pair as math, even $5 and $10
+    --table-colspan         In tables, || joins a cell with the one before it
+    --all-strings           Python: treat every triple-quoted string as a docstring
+    --only-kinds <K,...>    Only build these Markdown node kinds (e.g. Heading,Link)
+    --skip-kinds <K,...>    Don't build these Markdown node kinds (e.g. FencedCodeBlock)
+    --rewrite-config <PATH> Rewrite link and image URLs (prefix, extension, base)
+    --sanitize[=MODE]       Remove dangerous HTML and URLs; MODE is strip
+                            (default) or escape (keep blocked tags as text)
     --validate              Check for broken links/refs
+    --fail-on-error         Exit 3 on validation errors (implies --validate)
+    --fail-on-warning       Exit 3 on validation warnings too
+    --lint                  Run Markdown style lint rules
+    --lint-config <PATH>    Lint rule settings (implies --lint)
+    --min-doc-coverage <N>  Exit 3 if doc coverage of sources is below N%
+    --current-version <V>   Warn about @since newer than V and unparseable versions
     --sourcemap             Generate source maps (.map.json)
+    --sourcemap-utf16       Also give each mapping its UTF-16 range, as LSP counts
+    --anchors               Write heading, footnote and HTML ids (.anchors.json)
+    --extract-doctests      Write Python doctests (.doctest.txt)
+    --extract-code[=LANGS]  Write fenced code blocks, or those in LANGS (comma-separated),
+                            to code/<lang>/ with code/manifest.json
+    --check-code <CMD>      Run CMD on each extracted block ({file}, {lang}); failures
+                            are validation errors (implies --extract-code)
+    --check-timeout <SECS>  Kill a --check-code run after SECS seconds (default: 30)
+    --trace-parse           Write each Markdown rule tried and its outcome
+                            (.trace.txt; debug builds or --features trace)
+    --api-reference         Output source files as API reference Markdown documents
+    --xref                  Resolve {@link} and @see targets across files (xref.json)
+    --deprecations          Report deprecated symbols (deprecations.json and .md)
+    --analyze               Count nodes and bytes per node kind (analysis.json)
+    --nav                   Write a sidebar tree of titles and headings (nav.json)
+    --locales <LIST>        Locales each page needs a translation into, default first
+    --backlinks             Index the documents linking to each one (backlinks.json)
+    --releases              Read changelogs into versions, dates and changes (releases.json)
     --streaming             Use streaming parser for large files
+    --streaming-threshold <BYTES>
+                            Stream only Markdown files larger than BYTES
+    --mem-stats             Report peak memory and the largest ASTs in the summary
+    --estimate <N>          Process N sample files and estimate the full run; writes nothing
+    --explain <FILE>        Write an HTML page showing FILE next to its AST
     --bench                 Run internal benchmarks
+    --list-parsers          List the parser used for each file extension
+    --no-verify             inspect: skip the DAST checksum check
+    --strings               inspect: report string table size and savings, not the AST
+    --index                 inspect: print the DAST index, not the AST
     --verbose               Show progress
+    -q, --quiet             Print nothing but errors
+    --json-output           Print a JSON summary to stdout
     -h, --help
     -v, --version
 
@@ -178,6 +1016,19 @@ EXAMPLES:
     bukvar ./src ./output -f json --pretty
     bukvar -i ./docs -o ./ast --validate --sourcemap
     bukvar -i ./large-docs --streaming
+    bukvar serve --stdio
+    bukvar serve --http 127.0.0.1:7777
+    bukvar inspect ./ast/README.md.dast --pretty
+    bukvar merge ./book -o ./dist -f json
+    bukvar schema > ast.schema.json
+    bukvar --explain README.md -o /tmp
+
+EXIT CODES:
+    0  success
+    2  one or more files failed to parse
+    3  validation or --min-doc-coverage failed
+    4  I/O or configuration error
+    130  interrupted with Ctrl-C
 "#
   .to_string()
 }
@@ -193,13 +1044,79 @@ mod tests {
     assert_eq!(args.output, PathBuf::from("./ast_output"));
     assert_eq!(args.format, OutputFormat::Dast);
     assert!(args.recursive);
+    assert!(args.follow_symlinks);
+    assert!(args.hidden);
+    assert!(args.max_depth.is_none());
+    assert!(!args.flat);
     assert!(!args.verbose);
+    assert!(!args.quiet);
+    assert!(!args.json_output);
     assert!(args.parallel);
+    assert!(!args.parallel_blocks);
+    assert!(args.threads.is_none());
+    assert!(!args.nice);
     assert!(!args.pretty);
+    assert!(!args.html_tags);
+    assert!(!args.extended_autolinks);
+    assert!(!args.extract_metadata);
+    assert!(!args.section_spans);
+    assert!(!args.strip_comments);
+    assert!(!args.vars);
+    assert!(!args.content_hashes);
+    assert!(args.dollar_math);
+    assert!(!args.latex_math);
+    assert!(!args.strict_math);
+    assert!(!args.table_colspan);
+    assert!(args.only_kinds.is_none() && args.skip_kinds.is_none());
+    assert!(!args.all_strings);
+    assert!(args.sanitize.is_none());
+    assert!(args.rewrite_config.is_none());
     assert!(!args.validate);
+    assert!(!args.fail_on_error);
+    assert!(!args.fail_on_warning);
+    assert!(args.min_doc_coverage.is_none());
+    assert!(args.current_version.is_none());
+    assert!(!args.lint);
+    assert!(args.lint_config.is_none());
     assert!(!args.sourcemap);
+    assert!(!args.sourcemap_utf16);
+    assert!(!args.anchors);
+    assert!(!args.extract_doctests);
+    assert!(args.extract_code.is_none());
+    assert!(args.check_code.is_none());
+    assert_eq!(args.check_timeout, 30);
+    assert!(!args.trace_parse);
+    assert!(!args.api_reference);
+    assert!(!args.xref);
+    assert!(!args.deprecations);
+    assert!(!args.analyze);
+    assert!(!args.nav);
+    assert!(!args.backlinks);
+    assert!(!args.releases);
+    assert!(args.locales.is_empty());
     assert!(!args.bench);
+    assert!(!args.list_parsers);
     assert!(!args.streaming);
+    assert!(args.streaming_threshold.is_none());
+    assert!(!args.mem_stats);
+    assert!(args.estimate.is_none());
+    assert_eq!(args.unknown, UnknownPolicy::Skip);
+    assert_eq!(args.detect, DetectMode::Extension);
+    assert_eq!(args.drafts, DraftPolicy::Include);
+    assert!(args.elements.is_none());
+    assert!(args.serve.is_none());
+    assert!(args.dot_depth.is_none());
+    assert!(args.explain.is_none());
+    assert!(!args.inspect);
+    assert!(args.merge.is_none());
+    assert!(args.verify);
+    assert!(!args.string_stats);
+    assert!(!args.index_entries);
+    assert!(!args.schema);
+    assert!(args.spans);
+    assert!(args.json_kinds.is_none());
+    assert!(!args.sort_strings);
+    assert!(!args.dast_index);
   }
 
   #[test]
@@ -241,4 +1158,59 @@ mod tests {
     assert!(help.contains("EXAMPLES:"));
     assert!(help.contains("bukvar"));
   }
+
+  #[test]
+  fn test_parse_unknown_policy() {
+    assert_eq!(parse_unknown_policy("text"), Ok(UnknownPolicy::Text));
+    assert_eq!(parse_unknown_policy("error"), Ok(UnknownPolicy::Error));
+    assert!(parse_unknown_policy("ignore").is_err());
+    assert_eq!(parse_detect_mode("both"), Ok(DetectMode::Both));
+    assert!(parse_detect_mode("sniff").is_err());
+    assert_eq!(parse_draft_policy("only"), Ok(DraftPolicy::Only));
+    assert!(parse_draft_policy("hide").is_err());
+    assert_eq!(parse_sanitize_mode("escape"), Ok(SanitizeMode::Escape));
+    assert!(parse_sanitize_mode("remove").is_err());
+  }
+
+  #[test]
+  fn test_only_and_skip_kinds_conflict() {
+    let parse = |line: &str| parse_args_from(line.split(' ').map(String::from).collect());
+    let args = parse("bukvar --only-kinds Heading,Link").unwrap();
+    assert_eq!(args.only_kinds.unwrap(), ["Heading", "Link"]);
+    assert!(parse("bukvar --skip-kinds Table").is_ok());
+    assert_eq!(
+      parse("bukvar --only-kinds Heading --skip-kinds Table").unwrap_err(),
+      "--only-kinds and --skip-kinds can't be used together"
+    );
+  }
+
+  #[test]
+  fn test_thread_count() {
+    let mut args = Args {
+      threads: Some(3),
+      ..Args::default()
+    };
+    assert_eq!(args.thread_count(), 3);
+    assert_eq!(args.block_threads(), 1);
+    args.parallel_blocks = true;
+    assert_eq!(args.block_threads(), 3);
+    args.threads = None;
+    assert!(args.thread_count() >= 1);
+  }
+
+  #[test]
+  fn test_parse_kinds() {
+    assert_eq!(
+      parse_kinds("--json-kinds", "Heading, Text"),
+      Ok(vec!["Heading".to_string(), "Text".to_string()])
+    );
+    assert_eq!(
+      parse_kinds("--skip-kinds", "Heading,heading"),
+      Err(
+        "Unknown node kind in --skip-kinds: heading (names as in JSON \"type\", e.g. Heading)"
+          .to_string()
+      )
+    );
+    assert!(parse_kinds("--only-kinds", ",").is_err());
+  }
 }
@@ -0,0 +1,211 @@
+//! Deprecation report (`--deprecations`): every `@deprecated` in the
+//! processed source files, with the symbol it deprecates, collected into
+//! `deprecations.json` and a `deprecations.md` table.
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::esc;
+use crate::parsers::coverage::Coverage;
+use crate::xref::comment_symbols;
+
+use std::path::{Path, PathBuf};
+
+/// One deprecated symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deprecation {
+  /// `Class#method` or bare name; `None` when the comment documents no
+  /// declaration the scan recognized
+  pub symbol: Option<String>,
+  pub message: Option<String>,
+  /// The version that deprecated it (Sphinx's `.. deprecated:: 2.0`)
+  pub deprecated_in: Option<String>,
+  /// The version that added it: `@since` of the same doc comment
+  pub added: Option<String>,
+  /// Line of the doc comment
+  pub line: usize,
+}
+
+/// Deprecations of a parsed source file, in source order.
+pub fn collect(doc: &Document, coverage: Option<&Coverage>) -> Vec<Deprecation> {
+  let comments: Vec<&Node> = doc
+    .nodes
+    .iter()
+    .filter(|n| matches!(n.kind, NodeKind::DocComment { .. }))
+    .collect();
+  let symbols = comment_symbols(&comments, coverage, doc.doc_type);
+  let mut out = Vec::new();
+  for (comment, symbol) in comments.iter().zip(symbols) {
+    let added = comment.children.iter().find_map(|tag| match &tag.kind {
+      NodeKind::DocSince { version } => Some(version.clone()),
+      _ => None,
+    });
+    for tag in &comment.children {
+      if let NodeKind::DocDeprecated { message, version } = &tag.kind {
+        out.push(Deprecation {
+          symbol: symbol.as_ref().map(|s| s.qualified()),
+          message: message.clone(),
+          deprecated_in: version.clone(),
+          added: added.clone(),
+          line: comment.span.line,
+        });
+      }
+    }
+  }
+  out
+}
+
+/// The report as JSON: `{"total":N,"deprecations":[{"file",...}]}`.
+pub fn to_json(files: &[(PathBuf, Vec<Deprecation>)]) -> String {
+  let total: usize = files.iter().map(|(_, d)| d.len()).sum();
+  let mut s = String::with_capacity(64 + total * 96);
+  s.push_str(&format!("{{\"total\":{},\"deprecations\":[", total));
+  let entries = files
+    .iter()
+    .flat_map(|(path, deprecations)| deprecations.iter().map(move |d| (path, d)));
+  for (i, (path, d)) in entries.enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    s.push_str(&format!(
+      "{{\"file\":\"{}\",\"line\":{},\"symbol\":{},\"message\":{},\"deprecated_in\":{},\"added\":{}}}",
+      esc(&display(path)),
+      d.line,
+      json_opt(&d.symbol),
+      json_opt(&d.message),
+      json_opt(&d.deprecated_in),
+      json_opt(&d.added)
+    ));
+  }
+  s.push_str("]}");
+  s
+}
+
+/// The report as a Markdown table, one row per deprecation.
+pub fn to_markdown(files: &[(PathBuf, Vec<Deprecation>)]) -> String {
+  let mut s = String::from("# Deprecations\n\n");
+  if files.iter().all(|(_, d)| d.is_empty()) {
+    s.push_str("No deprecated symbols.\n");
+    return s;
+  }
+  s.push_str("| Symbol | Message | Deprecated in | Added | Location |\n|---|---|---|---|---|\n");
+  for (path, deprecations) in files {
+    for d in deprecations {
+      s.push_str(&format!(
+        "| {} | {} | {} | {} | {}:{} |\n",
+        d.symbol
+          .as_ref()
+          .map_or_else(String::new, |s| format!("`{}`", s)),
+        d.message.as_deref().map_or_else(String::new, cell),
+        d.deprecated_in.as_deref().map_or_else(String::new, cell),
+        d.added.as_deref().map_or_else(String::new, cell),
+        cell(&display(path)),
+        d.line
+      ));
+    }
+  }
+  s
+}
+
+/// Text safe inside a table cell: one line, pipes escaped.
+fn cell(text: &str) -> String {
+  text
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .replace('|', "\\|")
+}
+
+fn json_opt(value: &Option<String>) -> String {
+  match value {
+    Some(v) => format!("\"{}\"", esc(v)),
+    None => "null".to_string(),
+  }
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ast::DocumentType;
+  use crate::parsers::{coverage, JavaDocParser, JsDocParser, PyDocParser};
+
+  fn java(source: &str) -> Vec<Deprecation> {
+    let doc = JavaDocParser::new(source).parse();
+    collect(&doc, coverage::measure(source, DocumentType::Java).as_ref())
+  }
+
+  #[test]
+  fn test_collect() {
+    let source = "public class Old {\n  /**\n   * Greets.\n   * @deprecated use {@link #hello} | hi\n   \
+                  * @since 1.2\n   */\n  public void greet() {\n  }\n}\n\n/** @deprecated */\nint x;\n";
+    assert_eq!(
+      java(source),
+      vec![
+        Deprecation {
+          symbol: Some("Old#greet".to_string()),
+          message: Some("use {@link #hello} | hi".to_string()),
+          deprecated_in: None,
+          added: Some("1.2".to_string()),
+          line: 2,
+        },
+        Deprecation {
+          symbol: None,
+          message: None,
+          deprecated_in: None,
+          added: None,
+          line: 11,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_sphinx_versions() {
+    let source = "def f():\n    \"\"\"Do f.\n\n    .. versionadded:: 1.0\n\n    \
+                  .. deprecated:: 2.0\n       Use g instead.\n    \"\"\"\n";
+    let doc = PyDocParser::new(source).parse();
+    let found = collect(
+      &doc,
+      coverage::measure(source, DocumentType::Python).as_ref(),
+    );
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].message.as_deref(), Some("Use g instead."));
+    assert_eq!(found[0].deprecated_in.as_deref(), Some("2.0"));
+    assert_eq!(found[0].added.as_deref(), Some("1.0"));
+  }
+
+  #[test]
+  fn test_jsdoc_typedef() {
+    let source = "/**\n * @typedef {Object} Point\n * @deprecated\n */\n";
+    let doc = JsDocParser::new(source).parse();
+    let found = collect(
+      &doc,
+      coverage::measure(source, DocumentType::JavaScript).as_ref(),
+    );
+    assert_eq!(found[0].symbol.as_deref(), Some("Point"));
+  }
+
+  #[test]
+  fn test_reports() {
+    let files = vec![(
+      PathBuf::from("src/Old.java"),
+      java("/**\n * @deprecated gone | soon\n */\npublic class Old {\n}\n"),
+    )];
+    assert_eq!(
+      to_json(&files),
+      "{\"total\":1,\"deprecations\":[{\"file\":\"src/Old.java\",\"line\":1,\
+       \"symbol\":\"Old\",\"message\":\"gone | soon\",\"deprecated_in\":null,\"added\":null}]}"
+    );
+    assert_eq!(
+      to_markdown(&files),
+      "# Deprecations\n\n| Symbol | Message | Deprecated in | Added | Location |\n\
+       |---|---|---|---|---|\n| `Old` | gone \\| soon |  |  | src/Old.java:1 |\n"
+    );
+    assert_eq!(
+      to_markdown(&[]),
+      "# Deprecations\n\nNo deprecated symbols.\n"
+    );
+  }
+}
@@ -0,0 +1,206 @@
+//! Pre-flight estimate of a run (`--estimate <N>`): N files spread over the
+//! input are processed with every output counted instead of written, and
+//! their node counts, output sizes and times are scaled up to the whole
+//! input by document type and size.
+
+use crate::ast::DocumentType;
+use crate::memory::human;
+
+use std::time::Duration;
+
+/// Results of one sampled file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+  pub doc_type: DocumentType,
+  /// Size of the input file
+  pub bytes: u64,
+  pub nodes: usize,
+  /// Bytes of every output the file produced
+  pub output_bytes: usize,
+  pub elapsed: Duration,
+}
+
+/// The whole run, extrapolated from samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+  pub sampled_files: usize,
+  /// Samples that failed to process, left out of the rates
+  pub failed: usize,
+  pub total_files: usize,
+  pub sampled_bytes: u64,
+  pub total_bytes: u64,
+  pub nodes: u64,
+  pub output_bytes: u64,
+  /// Wall time on `threads` threads, assuming they scale linearly
+  pub elapsed: Duration,
+  pub threads: usize,
+}
+
+/// Indices of `n` files spread evenly over `total`, so a sample covers
+/// every part of a sorted tree.
+pub fn sample_indices(total: usize, n: usize) -> Vec<usize> {
+  let n = n.min(total);
+  (0..n).map(|i| i * total / n).collect()
+}
+
+impl Estimate {
+  /// Scale `samples` up to `corpus`, the type and size of every input
+  /// file. Each type is scaled by its own samples per input byte, or by
+  /// all samples when none of its files were sampled.
+  pub fn extrapolate(
+    samples: &[Sample],
+    failed: usize,
+    corpus: &[(DocumentType, u64)],
+    threads: usize,
+  ) -> Self {
+    let (mut nodes, mut output_bytes, mut seconds) = (0.0, 0.0, 0.0);
+    for doc_type in DocumentType::ALL {
+      let files: Vec<u64> = corpus
+        .iter()
+        .filter(|(t, _)| *t == doc_type)
+        .map(|(_, bytes)| *bytes)
+        .collect();
+      if files.is_empty() {
+        continue;
+      }
+      let of_type: Vec<&Sample> = samples.iter().filter(|s| s.doc_type == doc_type).collect();
+      let basis = if of_type.is_empty() {
+        samples.iter().collect()
+      } else {
+        of_type
+      };
+      let bytes: u64 = files.iter().sum();
+      nodes += scale(&basis, files.len(), bytes, |s| s.nodes as f64);
+      output_bytes += scale(&basis, files.len(), bytes, |s| s.output_bytes as f64);
+      seconds += scale(&basis, files.len(), bytes, |s| s.elapsed.as_secs_f64());
+    }
+    let threads = threads.clamp(1, corpus.len().max(1));
+    Self {
+      sampled_files: samples.len() + failed,
+      failed,
+      total_files: corpus.len(),
+      sampled_bytes: samples.iter().map(|s| s.bytes).sum(),
+      total_bytes: corpus.iter().map(|(_, bytes)| bytes).sum(),
+      nodes: nodes.round() as u64,
+      output_bytes: output_bytes.round() as u64,
+      elapsed: Duration::from_secs_f64(seconds / threads as f64),
+      threads,
+    }
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut out = format!(
+      "Estimate from {} of {} files ({} of {})\n",
+      self.sampled_files,
+      self.total_files,
+      human(self.sampled_bytes),
+      human(self.total_bytes)
+    );
+    if self.failed > 0 {
+      out.push_str(&format!("  {} sampled file(s) failed\n", self.failed));
+    }
+    out.push_str(&format!("  Nodes        ~{}\n", self.nodes));
+    out.push_str(&format!("  Output       ~{}\n", human(self.output_bytes)));
+    out.push_str(&format!(
+      "  Time         ~{} on {} thread(s)\n",
+      duration(self.elapsed),
+      self.threads
+    ));
+    out
+  }
+
+  pub fn to_json(&self) -> String {
+    format!(
+      "{{\"sampled_files\":{},\"failed\":{},\"total_files\":{},\"sampled_bytes\":{},\"total_bytes\":{},\"nodes\":{},\"output_bytes\":{},\"elapsed_ms\":{:.0},\"threads\":{}}}",
+      self.sampled_files,
+      self.failed,
+      self.total_files,
+      self.sampled_bytes,
+      self.total_bytes,
+      self.nodes,
+      self.output_bytes,
+      self.elapsed.as_secs_f64() * 1000.0,
+      self.threads
+    )
+  }
+}
+
+/// `value` summed over `samples`, scaled to `bytes` of input, or to
+/// `files` files when the samples were empty.
+fn scale(samples: &[&Sample], files: usize, bytes: u64, value: impl Fn(&Sample) -> f64) -> f64 {
+  if samples.is_empty() {
+    return 0.0;
+  }
+  let total: f64 = samples.iter().map(|s| value(s)).sum();
+  let sampled: u64 = samples.iter().map(|s| s.bytes).sum();
+  if sampled > 0 {
+    total * bytes as f64 / sampled as f64
+  } else {
+    total * files as f64 / samples.len() as f64
+  }
+}
+
+/// `2h 14m`, `3m 20s` or `45s`.
+fn duration(d: Duration) -> String {
+  let secs = d.as_secs();
+  match secs {
+    0 => format!("{:.1}s", d.as_secs_f64()),
+    1..=59 => format!("{}s", secs),
+    60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+    _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sample(doc_type: DocumentType, bytes: u64, nodes: usize, millis: u64) -> Sample {
+    Sample {
+      doc_type,
+      bytes,
+      nodes,
+      output_bytes: nodes * 10,
+      elapsed: Duration::from_millis(millis),
+    }
+  }
+
+  #[test]
+  fn test_sample_indices() {
+    assert_eq!(sample_indices(10, 3), vec![0, 3, 6]);
+    assert_eq!(sample_indices(2, 5), vec![0, 1]);
+    assert!(sample_indices(0, 5).is_empty());
+  }
+
+  #[test]
+  fn test_extrapolate() {
+    use DocumentType::{Markdown, Python};
+    let samples = [sample(Markdown, 100, 50, 2), sample(Markdown, 300, 150, 6)];
+    // Python has no samples and is scaled by the Markdown rate
+    let corpus = [
+      (Markdown, 100),
+      (Markdown, 300),
+      (Markdown, 1600),
+      (Python, 2000),
+    ];
+    let estimate = Estimate::extrapolate(&samples, 1, &corpus, 2);
+    assert_eq!(estimate.sampled_files, 3);
+    assert_eq!(estimate.total_files, 4);
+    assert_eq!((estimate.sampled_bytes, estimate.total_bytes), (400, 4000));
+    assert_eq!(estimate.nodes, 2000);
+    assert_eq!(estimate.output_bytes, 20000);
+    assert!((estimate.elapsed.as_secs_f64() - 0.04).abs() < 1e-9);
+    assert_eq!(
+      estimate.to_json(),
+      "{\"sampled_files\":3,\"failed\":1,\"total_files\":4,\"sampled_bytes\":400,\"total_bytes\":4000,\"nodes\":2000,\"output_bytes\":20000,\"elapsed_ms\":40,\"threads\":2}"
+    );
+  }
+
+  #[test]
+  fn test_duration() {
+    assert_eq!(duration(Duration::from_millis(300)), "0.3s");
+    assert_eq!(duration(Duration::from_secs(45)), "45s");
+    assert_eq!(duration(Duration::from_secs(200)), "3m 20s");
+    assert_eq!(duration(Duration::from_secs(8040)), "2h 14m");
+  }
+}
@@ -0,0 +1,35 @@
+//! Write the source-and-AST debug page for one file (`--explain`).
+
+use crate::cli::Args;
+use crate::formats::to_explain_html;
+use crate::markdown::ElementRegistry;
+use crate::parsers::{ParseContext, ParserRegistry};
+use crate::processor::detect_parser;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parse `file` and write `<output>/<file name>.explain.html`, returning
+/// the page's path. No transforms run, so the tree is the parser's own.
+pub fn run(file: &Path, args: &Args) -> Result<PathBuf, String> {
+  let source =
+    fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+  let parser = detect_parser(&ParserRegistry::new(), file, args.unknown, args.detect)?;
+  let elements = match &args.elements {
+    Some(path) => ElementRegistry::load(path)?,
+    None => ElementRegistry::new(),
+  };
+  let mut doc = parser.parse(&source, &ParseContext::new(&elements, args.parse_options()));
+  doc.source_path = file.to_string_lossy().replace('\\', "/");
+
+  let file_name = file
+    .file_name()
+    .and_then(|s| s.to_str())
+    .unwrap_or("output");
+  let path = args.output.join(format!("{}.explain.html", file_name));
+  fs::create_dir_all(&args.output)
+    .map_err(|e| format!("Failed to create output directory: {}", e))?;
+  fs::write(&path, to_explain_html(&doc, &source))
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+  Ok(path)
+}
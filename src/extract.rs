@@ -0,0 +1,239 @@
+//! Code block extraction (`--extract-code`): each fenced code block written
+//! to a file of its own under `code/<language>/`, and `code/manifest.json`
+//! mapping every file back to the document and line it came from, so CI
+//! jobs can compile or run all the examples in the docs.
+//!
+//! A block from line 12 of `guide/setup.md`, the first Rust block in it,
+//! becomes `code/rust/guide/setup.md-1.rs`. Blocks without a language go to
+//! `code/text/`. Diagram blocks are extracted too, under their engine's
+//! name.
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::esc;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directory under the output directory that extracted code goes to.
+pub const CODE_DIR: &str = "code";
+
+/// One extracted block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedCode {
+  /// Where the block is written, relative to the output directory
+  pub path: PathBuf,
+  /// Lowercased language, `text` for blocks without one
+  pub language: String,
+  /// Document it came from, relative to the input directory
+  pub source: PathBuf,
+  /// Line of the opening fence
+  pub line: usize,
+}
+
+/// The fenced code blocks of `doc`, found at `source`, with their contents.
+/// With a non-empty `languages`, only blocks in those languages are kept.
+pub fn extract(
+  doc: &Document,
+  source: &Path,
+  languages: &[String],
+) -> Vec<(ExtractedCode, String)> {
+  let mut blocks = Vec::new();
+  collect(&doc.nodes, &mut blocks);
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  blocks
+    .into_iter()
+    .filter(|(language, ..)| languages.is_empty() || languages.contains(language))
+    .map(|(language, line, content)| {
+      let n = counts.entry(language.clone()).or_insert(0);
+      *n += 1;
+      let file = format!(
+        "{}-{}.{}",
+        source.file_name().unwrap_or_default().to_string_lossy(),
+        n,
+        extension(&language)
+      );
+      let path = Path::new(CODE_DIR)
+        .join(dir_name(&language))
+        .join(source.parent().unwrap_or(Path::new("")))
+        .join(file);
+      let code = ExtractedCode {
+        path,
+        language,
+        source: source.to_path_buf(),
+        line,
+      };
+      (code, content)
+    })
+    .collect()
+}
+
+/// (language, line, content) of each fenced block, in document order.
+fn collect(nodes: &[Node], out: &mut Vec<(String, usize, String)>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::FencedCodeBlock { language, .. }
+      | NodeKind::CodeBlock { language, .. }
+      | NodeKind::CodeBlockExt { language, .. } => {
+        let language = language
+          .as_deref()
+          .filter(|lang| !lang.is_empty())
+          .unwrap_or("text")
+          .to_lowercase();
+        let content = node
+          .children
+          .iter()
+          .filter_map(|child| match &child.kind {
+            NodeKind::Text { content } => Some(content.as_str()),
+            _ => None,
+          })
+          .collect();
+        out.push((language, node.span.line, content));
+      }
+      NodeKind::DiagramBlock { engine, content } => {
+        out.push((engine.clone(), node.span.line, content.clone()))
+      }
+      _ => collect(&node.children, out),
+    }
+  }
+}
+
+/// File extension for a language's code.
+fn extension(language: &str) -> String {
+  let known = match language {
+    "rust" => "rs",
+    "python" | "py" | "python3" => "py",
+    "javascript" | "js" | "node" => "js",
+    "typescript" | "ts" => "ts",
+    "jsx" => "jsx",
+    "tsx" => "tsx",
+    "java" => "java",
+    "kotlin" => "kt",
+    "go" | "golang" => "go",
+    "c" => "c",
+    "cpp" | "c++" | "cxx" => "cpp",
+    "csharp" | "c#" | "cs" => "cs",
+    "ruby" | "rb" => "rb",
+    "sh" | "bash" | "shell" | "zsh" | "console" => "sh",
+    "powershell" | "ps1" => "ps1",
+    "json" | "jsonc" => "json",
+    "yaml" | "yml" => "yaml",
+    "toml" => "toml",
+    "html" => "html",
+    "css" => "css",
+    "sql" => "sql",
+    "markdown" | "md" => "md",
+    "mermaid" => "mmd",
+    "plantuml" => "puml",
+    "graphviz" => "dot",
+    "text" | "txt" | "plain" | "plaintext" => "txt",
+    _ => "",
+  };
+  match known {
+    "" => dir_name(language),
+    ext => ext.to_string(),
+  }
+}
+
+/// `language` with anything unsafe in a file name replaced by `_`.
+fn dir_name(language: &str) -> String {
+  language
+    .chars()
+    .map(|c| match c {
+      'a'..='z' | '0'..='9' | '-' | '_' | '+' => c,
+      _ => '_',
+    })
+    .collect()
+}
+
+/// `{"blocks":[{"file","language","source","line"}]}`, paths with `/`.
+pub fn manifest_json(blocks: &[ExtractedCode]) -> String {
+  let mut s = String::with_capacity(16 + blocks.len() * 128);
+  s.push_str("{\"blocks\":[");
+  for (i, block) in blocks.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    s.push_str(&format!(
+      "{{\"file\":\"{}\",\"language\":\"{}\",\"source\":\"{}\",\"line\":{}}}",
+      esc(&display(&block.path)),
+      esc(&block.language),
+      esc(&display(&block.source)),
+      block.line
+    ));
+  }
+  s.push_str("]}");
+  s
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  const SOURCE: &str = "# Guide\n\n```rust\nfn main() {}\n```\n\n```\nplain\n```\n\n\
+                        > ```Rust\n> let x = 1;\n> ```\n\n```c++\nint x;\n```\n\n\
+                        ```dot\ndigraph {}\n```\n";
+
+  fn extracted(languages: &[&str]) -> Vec<(ExtractedCode, String)> {
+    let doc = MarkdownParser::new(SOURCE).parse();
+    let languages: Vec<String> = languages.iter().map(|l| l.to_string()).collect();
+    extract(&doc, Path::new("guide/setup.md"), &languages)
+  }
+
+  #[test]
+  fn test_extract_all() {
+    let blocks = extracted(&[]);
+    let files: Vec<(String, usize, &str)> = blocks
+      .iter()
+      .map(|(code, content)| (display(&code.path), code.line, content.as_str()))
+      .collect();
+    assert_eq!(
+      files,
+      [
+        (
+          "code/rust/guide/setup.md-1.rs".to_string(),
+          3,
+          "fn main() {}\n"
+        ),
+        ("code/text/guide/setup.md-1.txt".to_string(), 7, "plain\n"),
+        (
+          "code/rust/guide/setup.md-2.rs".to_string(),
+          11,
+          "let x = 1;\n"
+        ),
+        ("code/c++/guide/setup.md-1.cpp".to_string(), 15, "int x;\n"),
+        (
+          "code/graphviz/guide/setup.md-1.dot".to_string(),
+          19,
+          "digraph {}\n"
+        ),
+      ]
+    );
+    assert!(blocks
+      .iter()
+      .all(|(code, _)| code.source == Path::new("guide/setup.md")));
+  }
+
+  #[test]
+  fn test_extract_languages() {
+    let blocks = extracted(&["rust"]);
+    assert_eq!(blocks.len(), 2);
+    assert!(blocks.iter().all(|(code, _)| code.language == "rust"));
+  }
+
+  #[test]
+  fn test_manifest() {
+    let blocks: Vec<ExtractedCode> = extracted(&["c++"]).into_iter().map(|(c, _)| c).collect();
+    assert_eq!(
+      manifest_json(&blocks),
+      "{\"blocks\":[{\"file\":\"code/c++/guide/setup.md-1.cpp\",\"language\":\"c++\",\
+       \"source\":\"guide/setup.md\",\"line\":15}]}"
+    );
+    assert_eq!(extension("haskell"), "haskell");
+    assert_eq!(dir_name("f#"), "f_");
+  }
+}
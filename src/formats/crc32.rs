@@ -0,0 +1,70 @@
+//! CRC-32 (IEEE 802.3, reflected polynomial `0xEDB88320`) for the DAST
+//! trailer.
+
+const POLY: u32 = 0xEDB8_8320;
+
+const TABLE: [u32; 256] = {
+  let mut table = [0u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 == 1 {
+        (crc >> 1) ^ POLY
+      } else {
+        crc >> 1
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
+/// Running checksum; feed it with `update`, read it with `finish`.
+#[derive(Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+  pub const fn new() -> Self {
+    Self(!0)
+  }
+
+  pub fn update(&mut self, data: &[u8]) {
+    for &byte in data {
+      self.0 = TABLE[((self.0 ^ u32::from(byte)) & 0xFF) as usize] ^ (self.0 >> 8);
+    }
+  }
+
+  pub const fn finish(self) -> u32 {
+    !self.0
+  }
+}
+
+/// Checksum of one buffer.
+pub fn checksum(data: &[u8]) -> u32 {
+  let mut crc = Crc32::new();
+  crc.update(data);
+  crc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_check_value() {
+    assert_eq!(checksum(b"123456789"), 0xCBF4_3926);
+    assert_eq!(checksum(b""), 0);
+  }
+
+  #[test]
+  fn test_incremental() {
+    let mut crc = Crc32::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finish(), checksum(b"123456789"));
+  }
+}
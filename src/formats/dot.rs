@@ -0,0 +1,152 @@
+//! Graphviz DOT output, for looking at parse trees.
+
+use super::label::{snippet, truncate};
+use crate::ast::{Document, Node};
+
+/// Most AST nodes drawn; the rest are summarized by one placeholder.
+pub const MAX_DOT_NODES: usize = 2000;
+
+/// Render the document as a `digraph`: one box per node, labeled with its
+/// kind and a snippet of its content, and an edge to each child. Nodes
+/// deeper than `max_depth` (top-level nodes are depth 1) are collapsed
+/// into a count.
+pub fn to_dot(doc: &Document, max_depth: Option<usize>) -> String {
+  let mut dot = DotWriter {
+    out: String::with_capacity(4096),
+    next_id: 1,
+    drawn: 0,
+    max_depth: max_depth.unwrap_or(usize::MAX),
+  };
+  dot
+    .out
+    .push_str("digraph ast {\n  node [shape=box, fontname=\"monospace\"];\n");
+  dot.out.push_str(&format!(
+    "  n0 [label=\"Document\\n{}\"];\n",
+    escape(&truncate(&doc.source_path))
+  ));
+  dot.write_children(0, &doc.nodes, 1);
+
+  let total: usize = doc.nodes.iter().map(Node::count_nodes).sum();
+  if dot.drawn < total {
+    dot.out.push_str(&format!(
+      "  more [label=\"+{} more nodes\", shape=plaintext];\n",
+      total - dot.drawn
+    ));
+  }
+  dot.out.push_str("}\n");
+  dot.out
+}
+
+struct DotWriter {
+  out: String,
+  next_id: usize,
+  /// AST nodes drawn so far, collapsed ones included
+  drawn: usize,
+  max_depth: usize,
+}
+
+impl DotWriter {
+  fn write_children(&mut self, parent: usize, children: &[Node], depth: usize) {
+    if depth > self.max_depth {
+      let hidden: usize = children.iter().map(Node::count_nodes).sum();
+      let id = self.alloc();
+      self.out.push_str(&format!(
+        "  n{} [label=\"+{} nodes\", style=dashed];\n  n{} -> n{};\n",
+        id, hidden, parent, id
+      ));
+      self.drawn += hidden;
+      return;
+    }
+    for child in children {
+      if self.drawn >= MAX_DOT_NODES {
+        return;
+      }
+      let id = self.alloc();
+      self.drawn += 1;
+      self.out.push_str(&format!(
+        "  n{} [label=\"{}\"];\n  n{} -> n{};\n",
+        id,
+        label(child),
+        parent,
+        id
+      ));
+      if !child.children.is_empty() {
+        self.write_children(id, &child.children, depth + 1);
+      }
+    }
+  }
+
+  fn alloc(&mut self) -> usize {
+    self.next_id += 1;
+    self.next_id - 1
+  }
+}
+
+/// Kind name, then a content snippet and the source line when known.
+fn label(node: &Node) -> String {
+  let mut label = node.kind.name().to_string();
+  if let Some(content) = snippet(&node.kind) {
+    label.push_str("\\n");
+    label.push_str(&escape(&truncate(&content)));
+  }
+  if node.span.line > 0 {
+    label.push_str(&format!("\\nline {}", node.span.line));
+  }
+  label
+}
+
+/// Escape for a quoted DOT string; newlines become literal `\n`.
+fn escape(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for ch in s.chars() {
+    match ch {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' | '\t' => out.push(' '),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::formats::label::LABEL_CHARS;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_nodes_and_edges() {
+    let doc = MarkdownParser::new("# Title\n\nSome \"quoted\" text").parse();
+    let dot = to_dot(&doc, None);
+    assert!(dot.starts_with("digraph ast {"));
+    assert!(dot.contains("n1 [label=\"Heading\\nh1\\nline 1\"];\n  n0 -> n1;"));
+    assert!(dot.contains("n2 [label=\"Text\\nTitle\"];\n  n1 -> n2;"));
+    assert!(dot.contains("Some \\\"quoted\\\" text"));
+    assert!(!dot.contains("more nodes"));
+  }
+
+  #[test]
+  fn test_depth_limit() {
+    let doc = MarkdownParser::new("- a *b*\n- c").parse();
+    let dot = to_dot(&doc, Some(1));
+    assert!(dot.contains("label=\"List"));
+    assert!(!dot.contains("label=\"ListItem"));
+    assert!(dot.contains("style=dashed"));
+    assert!(!dot.contains("more nodes"));
+  }
+
+  #[test]
+  fn test_node_cap_and_truncation() {
+    let input = format!(
+      "{}\n\n{}",
+      "x".repeat(100),
+      "para\n\n".repeat(MAX_DOT_NODES)
+    );
+    let doc = MarkdownParser::new(&input).parse();
+    let dot = to_dot(&doc, None);
+    assert!(dot.contains(&format!("{}…", "x".repeat(LABEL_CHARS))));
+    assert!(dot.contains("more nodes\", shape=plaintext"));
+  }
+}
@@ -0,0 +1,145 @@
+//! Self-contained HTML page showing a source file next to its AST
+//! (`--explain`). Hovering a tree node highlights its span in the source.
+
+use super::label::{snippet, truncate};
+use crate::ast::{Document, Node};
+
+const STYLE: &str = "body{margin:0;font:13px/1.4 monospace;color:#222}\
+header{padding:6px 12px;background:#333;color:#eee}\
+main{display:flex;height:calc(100vh - 30px)}\
+pre,.tree{flex:1;margin:0;padding:8px 12px;overflow:auto}\
+pre{border-right:1px solid #ccc;white-space:pre-wrap}\
+mark{background:#ffe066}\
+.tree ul{margin:0;padding-left:16px;list-style:none}\
+.tree span{cursor:default}.tree span:hover{background:#dde8ff}\
+.tree small{color:#666}.tree i{color:#999}";
+
+const SCRIPT: &str = "const src=document.getElementById('src'),text=src.textContent;\
+function show(s,e){src.textContent=text.slice(0,s);const m=document.createElement('mark');\
+m.textContent=text.slice(s,e);src.append(m,text.slice(e));m.scrollIntoView({block:'nearest'});}\
+document.querySelectorAll('[data-s]').forEach(n=>n.addEventListener('mouseover',ev=>{\
+ev.stopPropagation();show(+n.dataset.s,+n.dataset.e);}));\
+document.querySelector('.tree').addEventListener('mouseleave',()=>{src.textContent=text;});";
+
+/// Render `doc`, parsed from `source`, as an HTML page with no external
+/// resources.
+pub fn to_explain_html(doc: &Document, source: &str) -> String {
+  // HTML parsing turns CRLF into LF, so offsets are taken in that text
+  let text = source.replace("\r\n", "\n");
+  let offsets = Utf16Offsets::new(source);
+  let mut out = String::with_capacity(source.len() * 3 + 4096);
+  out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>bukvar explain: ");
+  out.push_str(&escape(&doc.source_path));
+  out.push_str("</title><style>");
+  out.push_str(STYLE);
+  out.push_str("</style></head>\n<body><header>");
+  out.push_str(&escape(&doc.source_path));
+  out.push_str(&format!(
+    " &middot; {} nodes</header>\n<main><pre id=\"src\">",
+    doc.metadata.total_nodes
+  ));
+  out.push_str(&escape(&text));
+  out.push_str("</pre>\n<div class=\"tree\">");
+  write_tree(&mut out, &doc.nodes, &offsets);
+  out.push_str("</div></main>\n<script>");
+  out.push_str(SCRIPT);
+  out.push_str("</script></body></html>\n");
+  out
+}
+
+fn write_tree(out: &mut String, nodes: &[Node], offsets: &Utf16Offsets) {
+  out.push_str("<ul>");
+  for node in nodes {
+    out.push_str("<li><span");
+    if !node.span.is_empty() {
+      out.push_str(&format!(
+        " data-s=\"{}\" data-e=\"{}\"",
+        offsets.at(node.span.start),
+        offsets.at(node.span.end)
+      ));
+    }
+    out.push('>');
+    out.push_str(node.kind.name());
+    if let Some(content) = snippet(&node.kind) {
+      out.push_str(" <small>");
+      out.push_str(&escape(&truncate(&content)));
+      out.push_str("</small>");
+    }
+    if node.span.line > 0 {
+      out.push_str(&format!(" <i>{}:{}</i>", node.span.line, node.span.column));
+    }
+    out.push_str("</span>");
+    if !node.children.is_empty() {
+      write_tree(out, &node.children, offsets);
+    }
+    out.push_str("</li>");
+  }
+  out.push_str("</ul>");
+}
+
+/// Byte offsets of the source mapped to UTF-16 offsets of the text the
+/// browser sees, which is what JavaScript string slicing counts.
+struct Utf16Offsets(Vec<usize>);
+
+impl Utf16Offsets {
+  fn new(source: &str) -> Self {
+    let mut table = vec![0; source.len() + 1];
+    let mut units = 0;
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+      for slot in &mut table[i..i + ch.len_utf8()] {
+        *slot = units;
+      }
+      let crlf = ch == '\r' && matches!(chars.peek(), Some((_, '\n')));
+      if !crlf {
+        units += ch.len_utf16();
+      }
+    }
+    table[source.len()] = units;
+    Self(table)
+  }
+
+  fn at(&self, byte: usize) -> usize {
+    self.0[byte.min(self.0.len() - 1)]
+  }
+}
+
+fn escape(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for ch in s.chars() {
+    match ch {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_page() {
+    let source = "# A <b>\n\ntext";
+    let doc = MarkdownParser::new(source).parse();
+    let html = to_explain_html(&doc, source);
+    assert!(html.contains("<pre id=\"src\"># A &lt;b&gt;\n\ntext</pre>"));
+    assert!(html.contains("<span data-s=\"0\" data-e=\"8\">Heading <small>h1</small> <i>1:1</i>"));
+    assert!(!html.contains("src=\"http"));
+  }
+
+  #[test]
+  fn test_utf16_offsets() {
+    let offsets = Utf16Offsets::new("é\r\n😀x");
+    // é is 2 bytes / 1 unit, CRLF is 1 unit, 😀 is 4 bytes / 2 units
+    assert_eq!(offsets.at(2), 1);
+    assert_eq!(offsets.at(4), 2);
+    assert_eq!(offsets.at(8), 4);
+    assert_eq!(offsets.at(9), 5);
+  }
+}
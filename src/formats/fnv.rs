@@ -0,0 +1,32 @@
+//! 64-bit FNV-1a, for content hashes and ids that must come out the same
+//! on every run and platform.
+
+/// Hash of the empty input; start a running hash from it.
+pub const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+/// Continue the running hash `h` with `bytes`.
+pub fn update(mut h: u64, bytes: &[u8]) -> u64 {
+  for &b in bytes {
+    h ^= b as u64;
+    h = h.wrapping_mul(PRIME);
+  }
+  h
+}
+
+/// Hash of `bytes`.
+pub fn hash(bytes: &[u8]) -> u64 {
+  update(OFFSET, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fnv() {
+    assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(update(hash(b"fo"), b"o"), hash(b"foo"));
+  }
+}
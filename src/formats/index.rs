@@ -0,0 +1,129 @@
+//! Optional DAST index (`--dast-index`): the file offset of every top-level
+//! node and every heading, so a reader can seek to one section and decode
+//! it without the rest of the file.
+//!
+//! The entries follow the nodes, then their count; the CRC-32 still comes
+//! last. [`FLAG_INDEX`] in the header's flags byte marks a file that has
+//! one. Readers that don't look for it stop after the nodes as before.
+
+use std::io;
+
+/// Header flag: the file ends with an index.
+pub const FLAG_INDEX: u8 = 1;
+/// Bytes per entry: offset, node number, level and nested flag.
+pub const ENTRY_LEN: usize = 10;
+
+/// One indexed node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+  /// File offset of the node's tag
+  pub offset: u32,
+  /// Number of the top-level node this is, or is inside
+  pub node: u32,
+  /// Heading level, 0 for other nodes
+  pub level: u8,
+  /// A heading below the top level
+  pub nested: bool,
+}
+
+/// Entries in file order: each top-level node, with the headings inside
+/// it after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DastIndex {
+  pub entries: Vec<IndexEntry>,
+}
+
+impl DastIndex {
+  pub fn write_to(&self, w: &mut Vec<u8>) {
+    for entry in &self.entries {
+      w.extend_from_slice(&entry.offset.to_le_bytes());
+      w.extend_from_slice(&entry.node.to_le_bytes());
+      w.extend_from_slice(&[entry.level, entry.nested as u8]);
+    }
+    w.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+  }
+
+  /// Decode entries written by [`write_to`](Self::write_to), without
+  /// their count.
+  pub fn decode(data: &[u8]) -> io::Result<Self> {
+    if data.len() % ENTRY_LEN != 0 {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Invalid DAST index length",
+      ));
+    }
+    let entries = data
+      .chunks_exact(ENTRY_LEN)
+      .map(|e| IndexEntry {
+        offset: u32::from_le_bytes([e[0], e[1], e[2], e[3]]),
+        node: u32::from_le_bytes([e[4], e[5], e[6], e[7]]),
+        level: e[8],
+        nested: e[9] != 0,
+      })
+      .collect();
+    Ok(Self { entries })
+  }
+
+  /// The section that the top-level heading at entry `i` starts: its
+  /// offset and the number of top-level nodes up to the next top-level
+  /// heading of the same or a higher level, for
+  /// [`DastReader::read_nodes_at`](super::DastReader::read_nodes_at).
+  #[allow(dead_code)]
+  pub fn section(&self, i: usize) -> Option<(u32, usize)> {
+    let start = self.entries.get(i).filter(|e| e.level > 0 && !e.nested)?;
+    let end = self.entries[i + 1..]
+      .iter()
+      .find(|e| !e.nested && e.level > 0 && e.level <= start.level)
+      .map_or_else(|| self.top_level(), |e| e.node as usize);
+    Some((start.offset, end - start.node as usize))
+  }
+
+  /// Number of top-level nodes, each of which has one entry.
+  #[allow(dead_code)]
+  fn top_level(&self) -> usize {
+    self.entries.iter().filter(|e| !e.nested).count()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(offset: u32, node: u32, level: u8, nested: bool) -> IndexEntry {
+    IndexEntry {
+      offset,
+      node,
+      level,
+      nested,
+    }
+  }
+
+  #[test]
+  fn test_roundtrip_and_sections() {
+    // # A, text, a quote holding ## B, ## C, text, # D
+    let index = DastIndex {
+      entries: vec![
+        entry(20, 0, 1, false),
+        entry(40, 1, 0, false),
+        entry(60, 2, 0, false),
+        entry(70, 2, 2, true),
+        entry(90, 3, 2, false),
+        entry(110, 4, 0, false),
+        entry(130, 5, 1, false),
+      ],
+    };
+    let mut data = Vec::new();
+    index.write_to(&mut data);
+    assert_eq!(data.len(), 7 * ENTRY_LEN + 4);
+    assert_eq!(DastIndex::decode(&data[..data.len() - 4]).unwrap(), index);
+    assert!(DastIndex::decode(&data[..5]).is_err());
+
+    assert_eq!(index.section(0), Some((20, 5)));
+    assert_eq!(index.section(4), Some((90, 2)));
+    assert_eq!(index.section(6), Some((130, 1)));
+    // Not a top-level heading
+    assert_eq!(index.section(1), None);
+    assert_eq!(index.section(3), None);
+    assert_eq!(index.section(9), None);
+  }
+}
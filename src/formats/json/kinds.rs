@@ -7,22 +7,37 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
   out.push('{');
   match kind {
     NodeKind::Document => out.push_str("\"type\":\"Document\""),
-    NodeKind::Heading { level, id } => {
+    NodeKind::Heading {
+      level,
+      id,
+      attributes,
+      section,
+    } => {
       out.push_str(&format!("\"type\":\"Heading\",\"level\":{}", level));
       if let Some(id) = id.as_ref() {
         out.push_str(&format!(",\"id\":\"{}\"", esc(id)));
       }
+      write_attributes(out, attributes);
+      if let Some(s) = section {
+        out.push_str(&format!(
+          ",\"section\":{{\"start\":{},\"end\":{},\"line\":{},\"column\":{}}}",
+          s.start, s.end, s.line, s.column
+        ));
+      }
     }
     NodeKind::Paragraph => out.push_str("\"type\":\"Paragraph\""),
     NodeKind::BlockQuote => out.push_str("\"type\":\"BlockQuote\""),
-    NodeKind::CodeBlock { language, info } | NodeKind::FencedCodeBlock { language, info } => {
-      out.push_str("\"type\":\"CodeBlock\"");
+    NodeKind::CodeBlock { language, info } | NodeKind::FencedCodeBlock { language, info, .. } => {
+      out.push_str(&format!("\"type\":\"{}\"", kind.name()));
       if let Some(l) = language.as_ref() {
         out.push_str(&format!(",\"language\":\"{}\"", esc(l)));
       }
       if let Some(i) = info.as_ref() {
         out.push_str(&format!(",\"info\":\"{}\"", esc(i)));
       }
+      if let NodeKind::FencedCodeBlock { attributes, .. } = kind {
+        write_attributes(out, attributes);
+      }
     }
     NodeKind::IndentedCodeBlock => out.push_str("\"type\":\"IndentedCodeBlock\""),
     NodeKind::HtmlBlock { block_type } => {
@@ -61,11 +76,15 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
     NodeKind::TableCell {
       alignment,
       is_header,
+      colspan,
     } => {
       out.push_str(&format!(
         "\"type\":\"TableCell\",\"alignment\":\"{:?}\",\"is_header\":{}",
         alignment, is_header
       ));
+      if *colspan > 1 {
+        out.push_str(&format!(",\"colspan\":{}", colspan));
+      }
     }
     NodeKind::Text { content } => out.push_str(&format!(
       "\"type\":\"Text\",\"content\":\"{}\"",
@@ -76,7 +95,8 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
     NodeKind::Strikethrough => out.push_str("\"type\":\"Strikethrough\""),
     NodeKind::Code { content } | NodeKind::CodeSpan { content } => {
       out.push_str(&format!(
-        "\"type\":\"Code\",\"content\":\"{}\"",
+        "\"type\":\"{}\",\"content\":\"{}\"",
+        kind.name(),
         esc(content)
       ));
     }
@@ -84,14 +104,21 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       url,
       title,
       ref_type,
+      attributes,
     } => {
       out.push_str(&format!("\"type\":\"Link\",\"url\":\"{}\"", esc(url)));
       if let Some(t) = title.as_ref() {
         out.push_str(&format!(",\"title\":\"{}\"", esc(t)));
       }
       out.push_str(&format!(",\"ref_type\":\"{:?}\"", ref_type));
+      write_attributes(out, attributes);
     }
-    NodeKind::Image { url, alt, title } => {
+    NodeKind::Image {
+      url,
+      alt,
+      title,
+      attributes,
+    } => {
       out.push_str(&format!(
         "\"type\":\"Image\",\"url\":\"{}\",\"alt\":\"{}\"",
         esc(url),
@@ -100,6 +127,7 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       if let Some(t) = title.as_ref() {
         out.push_str(&format!(",\"title\":\"{}\"", esc(t)));
       }
+      write_attributes(out, attributes);
     }
     NodeKind::AutoLink { url } => {
       out.push_str(&format!("\"type\":\"AutoLink\",\"url\":\"{}\"", esc(url)))
@@ -126,6 +154,7 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       name,
       param_type,
       description,
+      type_source,
     } => {
       out.push_str(&format!("\"type\":\"DocParam\",\"name\":\"{}\"", esc(name)));
       if let Some(t) = param_type.as_ref() {
@@ -134,6 +163,127 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       if let Some(d) = description.as_ref() {
         out.push_str(&format!(",\"description\":\"{}\"", esc(d)));
       }
+      if let Some(s) = type_source {
+        out.push_str(&format!(",\"type_source\":\"{}\"", s));
+      }
+    }
+    NodeKind::LinkReference { label, ref_type } => out.push_str(&format!(
+      "\"type\":\"LinkReference\",\"label\":\"{}\",\"ref_type\":\"{:?}\"",
+      esc(label),
+      ref_type
+    )),
+    NodeKind::LinkDefinition { label, url, title } => {
+      out.push_str(&format!(
+        "\"type\":\"LinkDefinition\",\"label\":\"{}\",\"url\":\"{}\"",
+        esc(label),
+        esc(url)
+      ));
+      if let Some(t) = title.as_ref() {
+        out.push_str(&format!(",\"title\":\"{}\"", esc(t)));
+      }
+    }
+    NodeKind::TaskListMarker { checked } => out.push_str(&format!(
+      "\"type\":\"TaskListMarker\",\"checked\":{}",
+      checked
+    )),
+    NodeKind::Emoji { shortcode } => out.push_str(&format!(
+      "\"type\":\"Emoji\",\"shortcode\":\"{}\"",
+      esc(shortcode)
+    )),
+    NodeKind::Mention { username } => out.push_str(&format!(
+      "\"type\":\"Mention\",\"username\":\"{}\"",
+      esc(username)
+    )),
+    NodeKind::IssueReference { number } => out.push_str(&format!(
+      "\"type\":\"IssueReference\",\"number\":{}",
+      number
+    )),
+    NodeKind::DocReturn {
+      return_type,
+      description,
+      type_source,
+    } => {
+      out.push_str("\"type\":\"DocReturn\"");
+      if let Some(t) = return_type.as_ref() {
+        out.push_str(&format!(",\"return_type\":\"{}\"", esc(t)));
+      }
+      if let Some(d) = description.as_ref() {
+        out.push_str(&format!(",\"description\":\"{}\"", esc(d)));
+      }
+      if let Some(s) = type_source {
+        out.push_str(&format!(",\"type_source\":\"{}\"", s));
+      }
+    }
+    NodeKind::DocThrows {
+      exception_type,
+      description,
+    } => {
+      out.push_str(&format!(
+        "\"type\":\"DocThrows\",\"exception_type\":\"{}\"",
+        esc(exception_type)
+      ));
+      if let Some(d) = description.as_ref() {
+        out.push_str(&format!(",\"description\":\"{}\"", esc(d)));
+      }
+    }
+    NodeKind::DocExample { content } | NodeKind::DocDescription { content } => {
+      out.push_str(&format!(
+        "\"type\":\"{}\",\"content\":\"{}\"",
+        kind.name(),
+        esc(content)
+      ))
+    }
+    NodeKind::DocSee { reference } => out.push_str(&format!(
+      "\"type\":\"DocSee\",\"reference\":\"{}\"",
+      esc(reference)
+    )),
+    NodeKind::DocDeprecated { message, version } => {
+      out.push_str("\"type\":\"DocDeprecated\"");
+      if let Some(m) = message.as_ref() {
+        out.push_str(&format!(",\"message\":\"{}\"", esc(m)));
+      }
+      if let Some(v) = version.as_ref() {
+        out.push_str(&format!(",\"version\":\"{}\"", esc(v)));
+      }
+    }
+    NodeKind::DocSince { version } | NodeKind::DocVersion { version } => out.push_str(&format!(
+      "\"type\":\"{}\",\"version\":\"{}\"",
+      kind.name(),
+      esc(version)
+    )),
+    NodeKind::DocAuthor { name } | NodeKind::DocCallback { name } => out.push_str(&format!(
+      "\"type\":\"{}\",\"name\":\"{}\"",
+      kind.name(),
+      esc(name)
+    )),
+    NodeKind::DocType { type_expr } => out.push_str(&format!(
+      "\"type\":\"DocType\",\"type_expr\":\"{}\"",
+      esc(type_expr)
+    )),
+    NodeKind::DocProperty {
+      name,
+      prop_type,
+      description,
+    } => {
+      out.push_str(&format!(
+        "\"type\":\"DocProperty\",\"name\":\"{}\"",
+        esc(name)
+      ));
+      if let Some(t) = prop_type.as_ref() {
+        out.push_str(&format!(",\"prop_type\":\"{}\"", esc(t)));
+      }
+      if let Some(d) = description.as_ref() {
+        out.push_str(&format!(",\"description\":\"{}\"", esc(d)));
+      }
+    }
+    NodeKind::DocTypedef { name, type_expr } => {
+      out.push_str(&format!(
+        "\"type\":\"DocTypedef\",\"name\":\"{}\"",
+        esc(name)
+      ));
+      if let Some(t) = type_expr.as_ref() {
+        out.push_str(&format!(",\"type_expr\":\"{}\"", esc(t)));
+      }
     }
     NodeKind::Frontmatter { format, content } => {
       out.push_str(&format!(
@@ -146,10 +296,36 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       "\"type\":\"MathInline\",\"content\":\"{}\"",
       esc(content)
     )),
-    NodeKind::MathBlock { content } => out.push_str(&format!(
-      "\"type\":\"MathBlock\",\"content\":\"{}\"",
+    NodeKind::MathBlock { content, display } => out.push_str(&format!(
+      "\"type\":\"MathBlock\",\"content\":\"{}\",\"display\":{}",
+      esc(content),
+      display
+    )),
+    NodeKind::DiagramBlock { engine, content } => out.push_str(&format!(
+      "\"type\":\"DiagramBlock\",\"engine\":\"{}\",\"content\":\"{}\"",
+      esc(engine),
       esc(content)
     )),
+    NodeKind::Comment { content, block } => out.push_str(&format!(
+      "\"type\":\"Comment\",\"content\":\"{}\",\"block\":{}",
+      esc(content),
+      block
+    )),
+    NodeKind::FootnoteReference { label, index } => out.push_str(&format!(
+      "\"type\":\"FootnoteReference\",\"label\":\"{}\",\"index\":{}",
+      esc(label),
+      index
+    )),
+    NodeKind::FootnoteDefinition { label, index } => out.push_str(&format!(
+      "\"type\":\"FootnoteDefinition\",\"label\":\"{}\",\"index\":{}",
+      esc(label),
+      index
+    )),
+    NodeKind::Footnotes => out.push_str("\"type\":\"Footnotes\""),
+    NodeKind::Abbreviation { title } => out.push_str(&format!(
+      "\"type\":\"Abbreviation\",\"title\":\"{}\"",
+      esc(title)
+    )),
     NodeKind::Footnote { label } => out.push_str(&format!(
       "\"type\":\"Footnote\",\"label\":\"{}\"",
       esc(label)
@@ -165,7 +341,12 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       alert_type
     )),
     NodeKind::Steps => out.push_str("\"type\":\"Steps\""),
-    NodeKind::Step => out.push_str("\"type\":\"Step\""),
+    NodeKind::Step { number, title } => {
+      out.push_str(&format!("\"type\":\"Step\",\"number\":{}", number));
+      if let Some(t) = title.as_ref() {
+        out.push_str(&format!(",\"title\":\"{}\"", esc(t)));
+      }
+    }
     NodeKind::Toc => out.push_str("\"type\":\"Toc\""),
     NodeKind::Tabs { names } => {
       out.push_str("\"type\":\"Tabs\",\"names\":[");
@@ -183,30 +364,144 @@ pub fn write_kind(out: &mut String, kind: &NodeKind) {
       plusdiff,
       minusdiff,
       linenumbers,
+      invalid_ranges,
+      attributes,
     } => {
-      out.push_str("\"type\":\"CodeBlock\"");
+      out.push_str("\"type\":\"CodeBlockExt\"");
       if let Some(l) = language.as_ref() {
         out.push_str(&format!(",\"language\":\"{}\"", esc(l)));
       }
-      if let Some(h) = highlight.as_ref() {
-        out.push_str(&format!(",\"highlight\":\"{}\"", esc(h)));
-      }
-      if let Some(p) = plusdiff.as_ref() {
-        out.push_str(&format!(",\"plusdiff\":\"{}\"", esc(p)));
-      }
-      if let Some(m) = minusdiff.as_ref() {
-        out.push_str(&format!(",\"minusdiff\":\"{}\"", esc(m)));
+      for (name, ranges) in [
+        ("highlight", highlight),
+        ("plusdiff", plusdiff),
+        ("minusdiff", minusdiff),
+      ] {
+        if !ranges.is_empty() {
+          out.push_str(&format!(",\"{}\":{}", name, ranges_json(ranges)));
+        }
       }
       if *linenumbers {
         out.push_str(",\"linenumbers\":true");
       }
+      if !invalid_ranges.is_empty() {
+        out.push_str(",\"invalid_ranges\":{");
+        for (i, (name, value)) in invalid_ranges.iter().enumerate() {
+          if i > 0 {
+            out.push(',');
+          }
+          out.push_str(&format!("\"{}\":\"{}\"", esc(name), esc(value)));
+        }
+        out.push('}');
+      }
+      write_attributes(out, attributes);
+    }
+    NodeKind::DocTypeParam { name, description } => {
+      out.push_str(&format!(
+        "\"type\":\"DocTypeParam\",\"name\":\"{}\"",
+        esc(name)
+      ));
+      if let Some(d) = description.as_ref() {
+        out.push_str(&format!(",\"description\":\"{}\"", esc(d)));
+      }
     }
-    #[allow(unreachable_patterns)]
-    _ => out.push_str(&format!("\"type\":\"{:?}\"", std::mem::discriminant(kind))),
+    NodeKind::DocRemarks { content } => out.push_str(&format!(
+      "\"type\":\"DocRemarks\",\"content\":\"{}\"",
+      esc(content)
+    )),
+    NodeKind::DocDefaultValue { value } => out.push_str(&format!(
+      "\"type\":\"DocDefaultValue\",\"value\":\"{}\"",
+      esc(value)
+    )),
+    NodeKind::DocReleaseTag { stage } => out.push_str(&format!(
+      "\"type\":\"DocReleaseTag\",\"stage\":\"{}\"",
+      stage
+    )),
+    NodeKind::DocModifier { name } => out.push_str(&format!(
+      "\"type\":\"DocModifier\",\"name\":\"{}\"",
+      esc(name)
+    )),
+    NodeKind::DocInlineTag { name, content } => {
+      out.push_str(&format!(
+        "\"type\":\"DocInlineTag\",\"name\":\"{}\"",
+        esc(name)
+      ));
+      if let Some(c) = content.as_ref() {
+        out.push_str(&format!(",\"content\":\"{}\"", esc(c)));
+      }
+    }
+    NodeKind::DocDoctest { source, expected } => {
+      out.push_str(&format!(
+        "\"type\":\"DocDoctest\",\"source\":\"{}\"",
+        esc(source)
+      ));
+      if let Some(e) = expected.as_ref() {
+        out.push_str(&format!(",\"expected\":\"{}\"", esc(e)));
+      }
+    }
+    NodeKind::CustomElement { name, attributes } => {
+      out.push_str(&format!(
+        "\"type\":\"CustomElement\",\"name\":\"{}\",\"attributes\":{{",
+        esc(name)
+      ));
+      for (i, (key, value)) in attributes.iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        out.push_str(&format!("\"{}\":\"{}\"", esc(key), esc(value)));
+      }
+      out.push('}');
+    }
+    NodeKind::HtmlTag {
+      name,
+      tag,
+      attributes,
+    } => {
+      out.push_str(&format!(
+        "\"type\":\"HtmlTag\",\"name\":\"{}\",\"tag\":\"{}\",\"attributes\":{{",
+        esc(name),
+        tag
+      ));
+      for (i, (key, value)) in attributes.iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        out.push_str(&format!("\"{}\":\"{}\"", esc(key), esc(value)));
+      }
+      out.push('}');
+    }
+    NodeKind::Tab { name } => out.push_str(&format!("\"type\":\"Tab\",\"name\":\"{}\"", esc(name))),
+    NodeKind::Include { src } => {
+      out.push_str(&format!("\"type\":\"Include\",\"src\":\"{}\"", esc(src)))
+    }
+    NodeKind::Unknown { tag } => out.push_str(&format!("\"type\":\"Unknown\",\"tag\":{}", tag)),
   }
   out.push('}');
 }
 
+/// `,"attributes":{"key":"value"}`, left out when there are none.
+fn write_attributes(out: &mut String, attributes: &[(String, String)]) {
+  if attributes.is_empty() {
+    return;
+  }
+  out.push_str(",\"attributes\":{");
+  for (i, (key, value)) in attributes.iter().enumerate() {
+    if i > 0 {
+      out.push(',');
+    }
+    out.push_str(&format!("\"{}\":\"{}\"", esc(key), esc(value)));
+  }
+  out.push('}');
+}
+
+/// `[[3,3],[5,7]]`
+fn ranges_json(ranges: &[(u32, u32)]) -> String {
+  let items: Vec<String> = ranges
+    .iter()
+    .map(|(start, end)| format!("[{},{}]", start, end))
+    .collect();
+  format!("[{}]", items.join(","))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -221,7 +516,15 @@ mod tests {
   #[test]
   fn test_write_heading() {
     let mut out = String::new();
-    write_kind(&mut out, &NodeKind::Heading { level: 2, id: None });
+    write_kind(
+      &mut out,
+      &NodeKind::Heading {
+        level: 2,
+        id: None,
+        attributes: vec![],
+        section: None,
+      },
+    );
     assert!(out.contains("\"type\":\"Heading\""));
     assert!(out.contains("\"level\":2"));
   }
@@ -234,9 +537,12 @@ mod tests {
       &NodeKind::Heading {
         level: 1,
         id: Some("intro".to_string()),
+        attributes: vec![("class".to_string(), "unnumbered".to_string())],
+        section: Some(Span::new(0, 40, 1, 1)),
       },
     );
-    assert!(out.contains("\"id\":\"intro\""));
+    assert!(out.contains("\"id\":\"intro\",\"attributes\":{\"class\":\"unnumbered\"}"));
+    assert!(out.contains(",\"section\":{\"start\":0,\"end\":40,\"line\":1,\"column\":1}}"));
   }
 
   #[test]
@@ -261,6 +567,27 @@ mod tests {
     assert!(out.contains("\"info\":\"example\""));
   }
 
+  #[test]
+  fn test_write_code_block_ranges() {
+    let mut out = String::new();
+    write_kind(
+      &mut out,
+      &NodeKind::CodeBlockExt {
+        language: None,
+        highlight: vec![(3, 3), (5, 7)],
+        plusdiff: vec![],
+        minusdiff: vec![],
+        linenumbers: false,
+        invalid_ranges: vec![("minusdiff".to_string(), "4-".to_string())],
+        attributes: vec![],
+      },
+    );
+    assert_eq!(
+      out,
+      "{\"type\":\"CodeBlockExt\",\"highlight\":[[3,3],[5,7]],\"invalid_ranges\":{\"minusdiff\":\"4-\"}}"
+    );
+  }
+
   #[test]
   fn test_write_list() {
     let mut out = String::new();
@@ -299,6 +626,7 @@ mod tests {
         url: "https://example.com".to_string(),
         title: Some("Example".to_string()),
         ref_type: ReferenceType::Full,
+        attributes: vec![],
       },
     );
     assert!(out.contains("\"url\":\"https://example.com\""));
@@ -314,10 +642,12 @@ mod tests {
         url: "img.png".to_string(),
         alt: "Alt text".to_string(),
         title: None,
+        attributes: vec![("width".to_string(), "400".to_string())],
       },
     );
     assert!(out.contains("\"url\":\"img.png\""));
     assert!(out.contains("\"alt\":\"Alt text\""));
+    assert!(out.contains("\"attributes\":{\"width\":\"400\"}"));
   }
 
   #[test]
@@ -349,10 +679,23 @@ mod tests {
       &NodeKind::TableCell {
         alignment: Alignment::Center,
         is_header: true,
+        colspan: 1,
       },
     );
     assert!(out.contains("\"alignment\":\"Center\""));
     assert!(out.contains("\"is_header\":true"));
+    assert!(!out.contains("colspan"));
+
+    out.clear();
+    write_kind(
+      &mut out,
+      &NodeKind::TableCell {
+        alignment: Alignment::None,
+        is_header: false,
+        colspan: 3,
+      },
+    );
+    assert!(out.contains(",\"colspan\":3"));
   }
 
   #[test]
@@ -389,9 +732,11 @@ mod tests {
       &mut out,
       &NodeKind::MathBlock {
         content: "\\sum".to_string(),
+        display: true,
       },
     );
     assert!(out.contains("\"type\":\"MathBlock\""));
+    assert!(out.contains("\"display\":true"));
   }
 
   #[test]
@@ -403,10 +748,12 @@ mod tests {
         name: "x".to_string(),
         param_type: Some("int".to_string()),
         description: Some("The value".to_string()),
+        type_source: Some(TypeSource::Annotation),
       },
     );
     assert!(out.contains("\"name\":\"x\""));
     assert!(out.contains("\"param_type\":\"int\""));
     assert!(out.contains("\"description\":\"The value\""));
+    assert!(out.contains("\"type_source\":\"annotation\""));
   }
 }
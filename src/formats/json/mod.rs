@@ -1,37 +1,104 @@
 //! JSON output format.
 
 mod kinds;
+pub(super) mod schema;
+
+pub use schema::{is_kind_name, json_schema, kind_index};
 
 use crate::ast::*;
 
+/// Version of the JSON layout, written as `schema_version` and in the
+/// schema from `bukvar schema`. Bumped on every change to the shape: a
+/// new, renamed or removed node kind or field, or a changed value type.
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// What each version after the first changed, oldest first; `bukvar
+/// schema` records them under `$comment`.
+pub const SCHEMA_CHANGES: &[&str] = &[
+  "2 adds the Comment kind and the attributes, section, colspan and type_source fields",
+  "3 adds the DiagramBlock kind",
+  "4 adds the version field of DocDeprecated",
+];
+
+/// What the JSON writer includes and how it lays it out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonOptions {
+  /// Newlines and two-space indentation
+  pub pretty: bool,
+  /// Write each node's `span`
+  pub spans: bool,
+  /// Write only nodes of these kinds (`NodeKind::name`); the matching
+  /// descendants of any other node take its place. `None` writes all.
+  pub kinds: Option<Vec<String>>,
+}
+
+impl JsonOptions {
+  pub const fn new() -> Self {
+    Self {
+      pretty: false,
+      spans: true,
+      kinds: None,
+    }
+  }
+}
+
+impl Default for JsonOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 /// Convert document to compact JSON.
 #[inline]
 pub fn to_json(doc: &Document) -> String {
-  JsonWriter::new(false).write_doc(doc)
+  to_json_with(doc, &JsonOptions::new())
 }
 
 /// Convert document to pretty-printed JSON.
 #[inline]
+#[allow(dead_code)]
 pub fn to_json_pretty(doc: &Document) -> String {
-  JsonWriter::new(true).write_doc(doc)
+  to_json_with(
+    doc,
+    &JsonOptions {
+      pretty: true,
+      ..JsonOptions::new()
+    },
+  )
+}
+
+/// Convert document to JSON with the given options.
+pub fn to_json_with(doc: &Document, options: &JsonOptions) -> String {
+  JsonWriter::new(options).write_doc(doc)
+}
+
+/// A node kind's JSON object, as written under a node's `kind`.
+pub fn kind_to_json(kind: &NodeKind) -> String {
+  let mut out = String::new();
+  kinds::write_kind(&mut out, kind);
+  out
 }
 
 /// JSON writer with pre-allocated buffer.
-struct JsonWriter {
+struct JsonWriter<'o> {
   out: String,
   pretty: bool,
+  spans: bool,
+  kinds: Option<&'o [String]>,
   depth: usize,
 }
 
-impl JsonWriter {
+impl<'o> JsonWriter<'o> {
   /// Create a new writer with estimated capacity.
   #[inline]
-  fn new(pretty: bool) -> Self {
+  fn new(options: &'o JsonOptions) -> Self {
     // Estimate ~8KB for typical documents, more for pretty
-    let capacity = if pretty { 16384 } else { 8192 };
+    let capacity = if options.pretty { 16384 } else { 8192 };
     Self {
       out: String::with_capacity(capacity),
-      pretty,
+      pretty: options.pretty,
+      spans: options.spans,
+      kinds: options.kinds.as_deref(),
       depth: 0,
     }
   }
@@ -42,6 +109,9 @@ impl JsonWriter {
     self.out.push('{');
     self.nl();
     self.depth += 1;
+    self.key("schema_version");
+    write_usize(&mut self.out, SCHEMA_VERSION as usize);
+    self.comma();
     self.kv_str("source_path", &doc.source_path);
     self.comma();
     self.kv_raw("doc_type", &format!("{:?}", doc.doc_type));
@@ -49,7 +119,7 @@ impl JsonWriter {
     self.write_metadata(&doc.metadata);
     self.comma();
     self.key("nodes");
-    self.write_array(&doc.nodes, |s, n| s.write_node(n));
+    self.write_nodes(&doc.nodes);
     self.depth -= 1;
     self.nl();
     self.out.push('}');
@@ -64,18 +134,48 @@ impl JsonWriter {
     self.depth += 1;
     self.key("kind");
     kinds::write_kind(&mut self.out, &node.kind);
-    self.comma();
-    self.write_span(&node.span);
-    if !node.children.is_empty() {
+    if self.spans {
+      self.comma();
+      self.write_span(&node.span);
+    }
+    if let Some(hash) = node.hash {
       self.comma();
-      self.key("children");
-      self.write_array(&node.children, |s, n| s.write_node(n));
+      self.kv_str("hash", &format!("{:016x}", hash));
+    }
+    match self.kinds {
+      None if !node.children.is_empty() => {
+        self.comma();
+        self.key("children");
+        self.write_array(&node.children, |s, n| s.write_node(n));
+      }
+      None => {}
+      Some(kinds) => {
+        let mut children = Vec::new();
+        select(&node.children, kinds, &mut children);
+        if !children.is_empty() {
+          self.comma();
+          self.key("children");
+          self.write_array(&children, |s, n| s.write_node(n));
+        }
+      }
     }
     self.depth -= 1;
     self.nl();
     self.out.push('}');
   }
 
+  /// Write a node list, keeping only the selected kinds.
+  fn write_nodes(&mut self, nodes: &[Node]) {
+    match self.kinds {
+      None => self.write_array(nodes, |s, n| s.write_node(n)),
+      Some(kinds) => {
+        let mut selected = Vec::new();
+        select(nodes, kinds, &mut selected);
+        self.write_array(&selected, |s, n| s.write_node(n));
+      }
+    }
+  }
+
   /// Write an array of items using the provided writer function.
   #[inline]
   fn write_array<T, F>(&mut self, items: &[T], mut writer: F)
@@ -126,6 +226,50 @@ impl JsonWriter {
       escape_into(&mut self.out, d);
       self.out.push_str("\",");
     }
+    if !meta.glossary.is_empty() {
+      self.out.push_str("\"glossary\":[");
+      for (i, (term, title)) in meta.glossary.iter().enumerate() {
+        if i > 0 {
+          self.out.push(',');
+        }
+        self.out.push_str("{\"term\":\"");
+        escape_into(&mut self.out, term);
+        self.out.push_str("\",\"title\":\"");
+        escape_into(&mut self.out, title);
+        self.out.push_str("\"}");
+      }
+      self.out.push_str("],");
+    }
+    if let Some(date) = meta.date.as_ref() {
+      self.out.push_str("\"date\":\"");
+      escape_into(&mut self.out, date);
+      self.out.push_str("\",");
+    }
+    if let Some(updated) = meta.updated.as_ref() {
+      self.out.push_str("\"updated\":\"");
+      escape_into(&mut self.out, updated);
+      self.out.push_str("\",");
+    }
+    if !meta.authors.is_empty() {
+      self.out.push_str("\"authors\":[");
+      for (i, author) in meta.authors.iter().enumerate() {
+        if i > 0 {
+          self.out.push(',');
+        }
+        self.out.push_str("{\"name\":\"");
+        escape_into(&mut self.out, &author.name);
+        self.out.push('"');
+        for (key, value) in [("email", &author.email), ("url", &author.url)] {
+          if let Some(value) = value {
+            self.out.push_str(&format!(",\"{}\":\"", key));
+            escape_into(&mut self.out, value);
+            self.out.push('"');
+          }
+        }
+        self.out.push('}');
+      }
+      self.out.push_str("],");
+    }
     self.out.push_str("\"total_lines\":");
     write_usize(&mut self.out, meta.total_lines);
     self.out.push_str(",\"total_nodes\":");
@@ -186,6 +330,18 @@ impl JsonWriter {
   }
 }
 
+/// Nodes of `kinds` among `nodes`, with the nearest matching descendants
+/// standing in for nodes of other kinds.
+fn select<'a>(nodes: &'a [Node], kinds: &[String], out: &mut Vec<&'a Node>) {
+  for node in nodes {
+    if kinds.iter().any(|k| k == node.kind.name()) {
+      out.push(node);
+    } else {
+      select(&node.children, kinds, out);
+    }
+  }
+}
+
 /// Write usize as decimal string directly into buffer.
 /// Avoids format! allocation for numbers.
 #[inline]
@@ -249,7 +405,7 @@ pub fn esc(s: &str) -> String {
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::ast::{Node, Span};
+  use crate::ast::{Author, Node, Span};
 
   fn simple_doc() -> Document {
     Document {
@@ -259,6 +415,10 @@ mod tests {
       metadata: DocumentMetadata {
         title: Some("Test".to_string()),
         description: None,
+        glossary: Vec::new(),
+        date: None,
+        updated: None,
+        authors: Vec::new(),
         total_lines: 1,
         total_nodes: 1,
       },
@@ -334,6 +494,14 @@ mod tests {
       metadata: DocumentMetadata {
         title: Some("My Title".to_string()),
         description: Some("My Description".to_string()),
+        glossary: vec![("HTML".to_string(), "Hyper Text".to_string())],
+        date: Some("2024-03-05".to_string()),
+        updated: None,
+        authors: vec![Author {
+          name: "Ada".to_string(),
+          email: None,
+          url: Some("https://ada.dev".to_string()),
+        }],
         total_lines: 10,
         total_nodes: 5,
       },
@@ -341,6 +509,11 @@ mod tests {
     let json = to_json(&doc);
     assert!(json.contains("\"title\":\"My Title\""));
     assert!(json.contains("\"description\":\"My Description\""));
+    assert!(json.contains("\"glossary\":[{\"term\":\"HTML\",\"title\":\"Hyper Text\"}]"));
+    assert!(json.contains(
+      "\"date\":\"2024-03-05\",\"authors\":[{\"name\":\"Ada\",\"url\":\"https://ada.dev\"}],"
+    ));
+    assert!(!json.contains("\"updated\""));
     assert!(json.contains("\"total_lines\":10"));
     assert!(json.contains("\"total_nodes\":5"));
   }
@@ -379,4 +552,84 @@ mod tests {
     let json = to_json(&doc);
     assert!(json.contains("\"nodes\":[]") || json.contains("\"nodes\":["));
   }
+
+  fn text(content: &str) -> Node {
+    Node::new(
+      NodeKind::Text {
+        content: content.to_string(),
+      },
+      Span::new(0, 1, 1, 1),
+    )
+  }
+
+  /// `# A` followed by a paragraph `b *c*`.
+  fn heading_doc() -> Document {
+    Document {
+      source_path: "a.md".to_string(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![
+        Node::with_children(
+          NodeKind::Heading {
+            level: 1,
+            id: None,
+            attributes: vec![],
+            section: None,
+          },
+          Span::new(0, 3, 1, 1),
+          vec![text("A")],
+        ),
+        Node::with_children(
+          NodeKind::Paragraph,
+          Span::new(5, 11, 3, 1),
+          vec![
+            text("b "),
+            Node::with_children(NodeKind::Emphasis, Span::new(7, 10, 3, 3), vec![text("c")]),
+          ],
+        ),
+      ],
+      metadata: DocumentMetadata::default(),
+    }
+  }
+
+  #[test]
+  fn test_json_without_spans() {
+    let options = JsonOptions {
+      spans: false,
+      ..JsonOptions::new()
+    };
+    let json = to_json_with(&heading_doc(), &options);
+    assert!(!json.contains("\"span\""));
+    assert!(json.contains("{\"kind\":{\"type\":\"Text\",\"content\":\"A\"}}"));
+  }
+
+  #[test]
+  fn test_json_kinds_filter() {
+    let options = JsonOptions {
+      spans: false,
+      kinds: Some(vec!["Heading".to_string(), "Text".to_string()]),
+      ..JsonOptions::new()
+    };
+    let json = to_json_with(&heading_doc(), &options);
+    // The paragraph and emphasis are dropped and their text moves up
+    let nodes = &json[json.find("\"nodes\"").unwrap()..];
+    assert_eq!(
+      nodes,
+      "\"nodes\":[{\"kind\":{\"type\":\"Heading\",\"level\":1},\"children\":[\
+       {\"kind\":{\"type\":\"Text\",\"content\":\"A\"}}]},\
+       {\"kind\":{\"type\":\"Text\",\"content\":\"b \"}},\
+       {\"kind\":{\"type\":\"Text\",\"content\":\"c\"}}]}"
+    );
+  }
+
+  #[test]
+  fn test_json_kinds_filter_drops_children() {
+    let options = JsonOptions {
+      kinds: Some(vec!["Heading".to_string()]),
+      ..JsonOptions::new()
+    };
+    let json = to_json_with(&heading_doc(), &options);
+    assert!(json.contains("\"type\":\"Heading\""));
+    assert!(!json.contains("\"children\""));
+    assert!(!json.contains("Text"));
+  }
 }
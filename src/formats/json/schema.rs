@@ -0,0 +1,421 @@
+//! JSON Schema (draft 2020-12) for the JSON output (`bukvar schema`).
+//!
+//! `KINDS` lists the JSON fields of every `NodeKind`, in the order
+//! `write_kind` writes them. The round-trip tests check generated
+//! documents of every kind against it.
+
+use super::{SCHEMA_CHANGES, SCHEMA_VERSION};
+use crate::ast::DocumentType;
+
+/// JSON type of a field value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+  Str,
+  /// Non-negative integer
+  Int,
+  Bool,
+  /// One of a fixed set of strings
+  Enum(&'static [&'static str]),
+  /// Array of strings
+  Strings,
+  /// Array of `[start, end]` line pairs
+  Ranges,
+  /// Object with string values
+  Map,
+  /// Source span, like a node's `span`
+  Span,
+}
+
+/// One field of a node kind's JSON object, besides `type`.
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+  pub name: &'static str,
+  pub ty: FieldType,
+  pub required: bool,
+}
+
+const fn req(name: &'static str, ty: FieldType) -> Field {
+  Field {
+    name,
+    ty,
+    required: true,
+  }
+}
+
+const fn opt(name: &'static str, ty: FieldType) -> Field {
+  Field {
+    name,
+    ty,
+    required: false,
+  }
+}
+
+use FieldType::*;
+
+const REF_TYPES: FieldType = Enum(&["Full", "Collapsed", "Shortcut"]);
+const ALIGNMENTS: FieldType = Enum(&["None", "Left", "Center", "Right"]);
+const DOC_STYLES: FieldType = Enum(&[
+  "JSDoc",
+  "JavaDoc",
+  "PyDoc",
+  "PyDocGoogle",
+  "PyDocNumpy",
+  "JavaDocMarkdown",
+]);
+const RELEASE_STAGES: FieldType = Enum(&["alpha", "beta", "public", "internal", "experimental"]);
+const TYPE_SOURCES: FieldType = Enum(&["doc", "annotation"]);
+const HTML_TAG_KINDS: FieldType = Enum(&["open", "close", "self-closing"]);
+const FRONTMATTER_FORMATS: FieldType = Enum(&["Yaml", "Toml", "Json"]);
+
+/// Every node kind's `type` name and fields.
+pub const KINDS: &[(&str, &[Field])] = &[
+  ("Document", &[]),
+  (
+    "Heading",
+    &[
+      req("level", Int),
+      opt("id", Str),
+      opt("attributes", Map),
+      opt("section", Span),
+    ],
+  ),
+  ("Paragraph", &[]),
+  ("BlockQuote", &[]),
+  ("CodeBlock", &[opt("language", Str), opt("info", Str)]),
+  (
+    "FencedCodeBlock",
+    &[
+      opt("language", Str),
+      opt("info", Str),
+      opt("attributes", Map),
+    ],
+  ),
+  ("IndentedCodeBlock", &[]),
+  ("HtmlBlock", &[req("block_type", Int)]),
+  ("ThematicBreak", &[]),
+  (
+    "List",
+    &[req("ordered", Bool), req("tight", Bool), opt("start", Int)],
+  ),
+  // `marker` is `Bullet('-')` or `Ordered(46)` (the delimiter's byte)
+  ("ListItem", &[req("marker", Str), opt("checked", Bool)]),
+  ("Table", &[]),
+  ("TableHead", &[]),
+  ("TableBody", &[]),
+  ("TableRow", &[]),
+  (
+    "TableCell",
+    &[
+      req("alignment", ALIGNMENTS),
+      req("is_header", Bool),
+      opt("colspan", Int),
+    ],
+  ),
+  ("Text", &[req("content", Str)]),
+  ("Emphasis", &[]),
+  ("Strong", &[]),
+  ("Strikethrough", &[]),
+  ("Code", &[req("content", Str)]),
+  ("CodeSpan", &[req("content", Str)]),
+  (
+    "Link",
+    &[
+      req("url", Str),
+      opt("title", Str),
+      req("ref_type", REF_TYPES),
+      opt("attributes", Map),
+    ],
+  ),
+  (
+    "Image",
+    &[
+      req("url", Str),
+      req("alt", Str),
+      opt("title", Str),
+      opt("attributes", Map),
+    ],
+  ),
+  ("AutoLink", &[req("url", Str)]),
+  ("HardBreak", &[]),
+  ("SoftBreak", &[]),
+  ("HtmlInline", &[req("content", Str)]),
+  (
+    "HtmlTag",
+    &[
+      req("name", Str),
+      req("tag", HTML_TAG_KINDS),
+      req("attributes", Map),
+    ],
+  ),
+  (
+    "LinkReference",
+    &[req("label", Str), req("ref_type", REF_TYPES)],
+  ),
+  (
+    "LinkDefinition",
+    &[req("label", Str), req("url", Str), opt("title", Str)],
+  ),
+  ("FootnoteReference", &[req("label", Str), req("index", Int)]),
+  (
+    "FootnoteDefinition",
+    &[req("label", Str), req("index", Int)],
+  ),
+  ("Footnotes", &[]),
+  ("Abbreviation", &[req("title", Str)]),
+  ("TaskListMarker", &[req("checked", Bool)]),
+  ("Emoji", &[req("shortcode", Str)]),
+  ("Mention", &[req("username", Str)]),
+  ("IssueReference", &[req("number", Int)]),
+  ("DocComment", &[req("style", DOC_STYLES)]),
+  ("DocTag", &[req("name", Str), opt("content", Str)]),
+  (
+    "DocParam",
+    &[
+      req("name", Str),
+      opt("param_type", Str),
+      opt("description", Str),
+      opt("type_source", TYPE_SOURCES),
+    ],
+  ),
+  (
+    "DocReturn",
+    &[
+      opt("return_type", Str),
+      opt("description", Str),
+      opt("type_source", TYPE_SOURCES),
+    ],
+  ),
+  (
+    "DocThrows",
+    &[req("exception_type", Str), opt("description", Str)],
+  ),
+  ("DocExample", &[req("content", Str)]),
+  ("DocSee", &[req("reference", Str)]),
+  ("DocDeprecated", &[opt("message", Str), opt("version", Str)]),
+  ("DocSince", &[req("version", Str)]),
+  ("DocAuthor", &[req("name", Str)]),
+  ("DocVersion", &[req("version", Str)]),
+  ("DocDescription", &[req("content", Str)]),
+  ("DocType", &[req("type_expr", Str)]),
+  (
+    "DocProperty",
+    &[
+      req("name", Str),
+      opt("prop_type", Str),
+      opt("description", Str),
+    ],
+  ),
+  ("DocCallback", &[req("name", Str)]),
+  ("DocTypedef", &[req("name", Str), opt("type_expr", Str)]),
+  ("DocTypeParam", &[req("name", Str), opt("description", Str)]),
+  ("DocRemarks", &[req("content", Str)]),
+  ("DocDefaultValue", &[req("value", Str)]),
+  ("DocReleaseTag", &[req("stage", RELEASE_STAGES)]),
+  ("DocModifier", &[req("name", Str)]),
+  ("DocInlineTag", &[req("name", Str), opt("content", Str)]),
+  ("DocDoctest", &[req("source", Str), opt("expected", Str)]),
+  (
+    "Frontmatter",
+    &[req("format", FRONTMATTER_FORMATS), req("content", Str)],
+  ),
+  ("MathInline", &[req("content", Str)]),
+  ("MathBlock", &[req("content", Str), req("display", Bool)]),
+  ("Comment", &[req("content", Str), req("block", Bool)]),
+  ("DiagramBlock", &[req("engine", Str), req("content", Str)]),
+  ("Footnote", &[req("label", Str)]),
+  ("DefinitionList", &[]),
+  ("DefinitionTerm", &[]),
+  ("DefinitionDescription", &[]),
+  ("AutoUrl", &[req("url", Str)]),
+  ("Alert", &[req("alert_type", Str)]),
+  ("Steps", &[]),
+  ("Step", &[req("number", Int), opt("title", Str)]),
+  ("Toc", &[]),
+  ("Tabs", &[req("names", Strings)]),
+  ("Tab", &[req("name", Str)]),
+  (
+    "CodeBlockExt",
+    &[
+      opt("language", Str),
+      opt("highlight", Ranges),
+      opt("plusdiff", Ranges),
+      opt("minusdiff", Ranges),
+      opt("linenumbers", Bool),
+      opt("invalid_ranges", Map),
+      opt("attributes", Map),
+    ],
+  ),
+  ("CustomElement", &[req("name", Str), req("attributes", Map)]),
+  ("Include", &[req("src", Str)]),
+  // A DAST node kind this version doesn't know, from `bukvar inspect`
+  ("Unknown", &[req("tag", Int)]),
+];
+
+/// Whether `name` is the `type` of a node kind.
+pub fn is_kind_name(name: &str) -> bool {
+  kind_index(name).is_some()
+}
+
+/// Position of the kind named `name` in `KINDS`.
+pub fn kind_index(name: &str) -> Option<usize> {
+  KINDS.iter().position(|(n, _)| *n == name)
+}
+
+/// The schema as pretty-printed JSON.
+pub fn json_schema() -> String {
+  let doc_types: Vec<String> = DocumentType::ALL
+    .iter()
+    .map(|t| format!("\"{:?}\"", t))
+    .collect();
+  let kind_refs: Vec<String> = KINDS
+    .iter()
+    .map(|(name, _)| format!("{{\"$ref\": \"#/$defs/{}\"}}", name))
+    .collect();
+
+  let mut out = String::with_capacity(16384);
+  out.push_str("{\n");
+  out.push_str("  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n");
+  out.push_str("  \"title\": \"bukvar JSON AST\",\n");
+  out.push_str(&format!(
+    "  \"description\": \"JSON output of bukvar, schema version {}\",\n",
+    SCHEMA_VERSION
+  ));
+  out.push_str(&format!(
+    "  \"$comment\": \"Changes by version: {}\",\n",
+    SCHEMA_CHANGES.join("; ")
+  ));
+  out.push_str("  \"type\": \"object\",\n");
+  out.push_str(
+    "  \"required\": [\"schema_version\", \"source_path\", \"doc_type\", \"metadata\", \"nodes\"],\n",
+  );
+  out.push_str("  \"additionalProperties\": false,\n");
+  out.push_str("  \"properties\": {\n");
+  out.push_str(&format!(
+    "    \"schema_version\": {{\"const\": {}}},\n",
+    SCHEMA_VERSION
+  ));
+  out.push_str("    \"source_path\": {\"type\": \"string\"},\n");
+  out.push_str(&format!(
+    "    \"doc_type\": {{\"enum\": [{}]}},\n",
+    doc_types.join(", ")
+  ));
+  out.push_str("    \"metadata\": {\"$ref\": \"#/$defs/metadata\"},\n");
+  out.push_str("    \"nodes\": {\"type\": \"array\", \"items\": {\"$ref\": \"#/$defs/node\"}}\n");
+  out.push_str("  },\n");
+  out.push_str("  \"$defs\": {\n");
+  out.push_str(
+    "    \"metadata\": {\"type\": \"object\", \"required\": [\"total_lines\", \"total_nodes\"], \
+     \"additionalProperties\": false, \"properties\": {\"title\": {\"type\": \"string\"}, \
+     \"description\": {\"type\": \"string\"}, \"glossary\": {\"type\": \"array\", \"items\":      {\"type\": \"object\", \"required\": [\"term\", \"title\"], \"additionalProperties\": false,      \"properties\": {\"term\": {\"type\": \"string\"}, \"title\": {\"type\": \"string\"}}}}, \
+     \"date\": {\"type\": \"string\"}, \"updated\": {\"type\": \"string\"}, \
+     \"authors\": {\"type\": \"array\", \"items\": {\"type\": \"object\", \"required\": [\"name\"], \
+     \"additionalProperties\": false, \"properties\": {\"name\": {\"type\": \"string\"}, \
+     \"email\": {\"type\": \"string\"}, \"url\": {\"type\": \"string\"}}}}, \"total_lines\": {\"type\": \"integer\", \"minimum\": 0}, \
+     \"total_nodes\": {\"type\": \"integer\", \"minimum\": 0}}},\n",
+  );
+  out.push_str(
+    "    \"node\": {\"type\": \"object\", \"required\": [\"kind\"], \
+     \"additionalProperties\": false, \"properties\": {\"kind\": {\"$ref\": \"#/$defs/kind\"}, \
+     \"span\": {\"$ref\": \"#/$defs/span\"}, \
+     \"hash\": {\"type\": \"string\", \"pattern\": \"^[0-9a-f]{16}$\"}, \
+     \"children\": {\"type\": \"array\", \"items\": {\"$ref\": \"#/$defs/node\"}}}},\n",
+  );
+  out.push_str(
+    "    \"span\": {\"type\": \"object\", \"required\": [\"start\", \"end\", \"line\", \"column\"], \
+     \"additionalProperties\": false, \"properties\": {\"start\": {\"type\": \"integer\", \"minimum\": 0}, \
+     \"end\": {\"type\": \"integer\", \"minimum\": 0}, \"line\": {\"type\": \"integer\", \"minimum\": 0}, \
+     \"column\": {\"type\": \"integer\", \"minimum\": 0}}},\n",
+  );
+  out.push_str(&format!(
+    "    \"kind\": {{\"oneOf\": [{}]}}",
+    kind_refs.join(", ")
+  ));
+  for (name, fields) in KINDS {
+    out.push_str(",\n    ");
+    write_kind_def(&mut out, name, fields);
+  }
+  out.push_str("\n  }\n}\n");
+  out
+}
+
+fn write_kind_def(out: &mut String, name: &str, fields: &[Field]) {
+  let mut required = vec!["\"type\"".to_string()];
+  let mut properties = vec![format!("\"type\": {{\"const\": \"{}\"}}", name)];
+  for field in fields {
+    if field.required {
+      required.push(format!("\"{}\"", field.name));
+    }
+    properties.push(format!("\"{}\": {}", field.name, type_schema(field.ty)));
+  }
+  out.push_str(&format!(
+    "\"{}\": {{\"type\": \"object\", \"required\": [{}], \"additionalProperties\": false, \
+     \"properties\": {{{}}}}}",
+    name,
+    required.join(", "),
+    properties.join(", ")
+  ));
+}
+
+fn type_schema(ty: FieldType) -> String {
+  match ty {
+    Str => "{\"type\": \"string\"}".to_string(),
+    Int => "{\"type\": \"integer\", \"minimum\": 0}".to_string(),
+    Bool => "{\"type\": \"boolean\"}".to_string(),
+    Enum(values) => {
+      let values: Vec<String> = values.iter().map(|v| format!("\"{}\"", v)).collect();
+      format!("{{\"enum\": [{}]}}", values.join(", "))
+    }
+    Strings => "{\"type\": \"array\", \"items\": {\"type\": \"string\"}}".to_string(),
+    Ranges => "{\"type\": \"array\", \"items\": {\"type\": \"array\", \"prefixItems\": \
+               [{\"type\": \"integer\"}, {\"type\": \"integer\"}], \"items\": false}}"
+      .to_string(),
+    Map => "{\"type\": \"object\", \"additionalProperties\": {\"type\": \"string\"}}".to_string(),
+    Span => "{\"$ref\": \"#/$defs/span\"}".to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::BTreeSet;
+
+  #[test]
+  fn test_kind_names_unique() {
+    let names: BTreeSet<&str> = KINDS.iter().map(|(n, _)| *n).collect();
+    assert_eq!(names.len(), KINDS.len());
+    // Parse-time kind filters keep one bit per kind
+    assert!(KINDS.len() <= 128);
+  }
+
+  #[test]
+  fn test_kind_def() {
+    let mut out = String::new();
+    write_kind_def(&mut out, KINDS[1].0, KINDS[1].1);
+    assert_eq!(
+      out,
+      "\"Heading\": {\"type\": \"object\", \"required\": [\"type\", \"level\"], \
+       \"additionalProperties\": false, \"properties\": {\"type\": {\"const\": \"Heading\"}, \
+       \"level\": {\"type\": \"integer\", \"minimum\": 0}, \"id\": {\"type\": \"string\"}, \
+       \"attributes\": {\"type\": \"object\", \"additionalProperties\": {\"type\": \"string\"}}, \
+       \"section\": {\"$ref\": \"#/$defs/span\"}}}"
+    );
+  }
+
+  #[test]
+  fn test_every_version_is_recorded() {
+    assert_eq!(SCHEMA_CHANGES.len() as u32 + 1, SCHEMA_VERSION);
+    for (version, change) in (2..).zip(SCHEMA_CHANGES) {
+      assert!(change.starts_with(&format!("{} ", version)), "{}", change);
+    }
+  }
+
+  #[test]
+  fn test_schema_header() {
+    let schema = json_schema();
+    assert!(schema.contains("\"schema_version\": {\"const\": 4}"));
+    assert!(schema.contains("\"$comment\": \"Changes by version: 2 adds the Comment kind"));
+    assert!(schema.contains("; 3 adds the DiagramBlock kind; 4 adds"));
+    assert!(schema.contains("\"doc_type\": {\"enum\": [\"Markdown\", \"JavaScript\""));
+    assert!(schema.contains("{\"$ref\": \"#/$defs/Unknown\"}"));
+  }
+}
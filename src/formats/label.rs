@@ -0,0 +1,50 @@
+//! Short node descriptions shared by the debugging outputs.
+
+use crate::ast::NodeKind;
+
+/// Characters of content shown in a node label.
+pub const LABEL_CHARS: usize = 32;
+
+/// The most telling field of a node: its text, URL, name or level.
+pub fn snippet(kind: &NodeKind) -> Option<String> {
+  match kind {
+    NodeKind::Heading { level, .. } => Some(format!("h{}", level)),
+    NodeKind::Text { content }
+    | NodeKind::Code { content }
+    | NodeKind::CodeSpan { content }
+    | NodeKind::HtmlInline { content }
+    | NodeKind::MathInline { content }
+    | NodeKind::MathBlock { content, .. }
+    | NodeKind::Comment { content, .. }
+    | NodeKind::DiagramBlock { content, .. }
+    | NodeKind::DocExample { content }
+    | NodeKind::DocDescription { content }
+    | NodeKind::DocRemarks { content } => Some(content.clone()),
+    NodeKind::FencedCodeBlock { language, .. } | NodeKind::CodeBlockExt { language, .. } => {
+      language.clone()
+    }
+    NodeKind::Link { url, .. }
+    | NodeKind::Image { url, .. }
+    | NodeKind::AutoLink { url }
+    | NodeKind::AutoUrl { url } => Some(url.clone()),
+    NodeKind::LinkReference { label, .. }
+    | NodeKind::FootnoteReference { label, .. }
+    | NodeKind::FootnoteDefinition { label, .. } => Some(label.clone()),
+    NodeKind::DocTag { name, .. }
+    | NodeKind::DocParam { name, .. }
+    | NodeKind::CustomElement { name, .. }
+    | NodeKind::HtmlTag { name, .. }
+    | NodeKind::Tab { name } => Some(name.clone()),
+    NodeKind::Abbreviation { title } => Some(title.clone()),
+    NodeKind::Unknown { tag } => Some(format!("tag {}", tag)),
+    _ => None,
+  }
+}
+
+/// First `LABEL_CHARS` characters of `s`, with `…` when cut.
+pub fn truncate(s: &str) -> String {
+  match s.char_indices().nth(LABEL_CHARS) {
+    Some((end, _)) => format!("{}…", &s[..end]),
+    None => s.to_string(),
+  }
+}
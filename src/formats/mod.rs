@@ -1,11 +1,32 @@
 //! Output formats: DAST (binary) and JSON
 
+mod crc32;
+mod dot;
+mod explain;
+pub mod fnv;
+mod index;
 mod json;
+mod label;
 mod reader;
+#[cfg(test)]
+mod roundtrip;
+mod segments;
+mod sexp;
 mod writer;
 
-pub use json::{to_json, to_json_pretty};
-pub use reader::DastReader;
+pub use dot::to_dot;
+pub use explain::to_explain_html;
+pub use index::DastIndex;
+#[allow(unused_imports)]
+pub use index::IndexEntry;
+#[allow(unused_imports)]
+pub use json::to_json_pretty;
+pub use json::{
+  esc, is_kind_name, json_schema, kind_index, kind_to_json, to_json, to_json_with, JsonOptions,
+};
+pub use reader::{DastReader, StringStats};
+pub use segments::to_segments;
+pub use sexp::to_sexp;
 pub use writer::DastWriter;
 
 use crate::ast::Document;
@@ -13,8 +34,38 @@ use std::io;
 
 /// Magic bytes for DAST format identification.
 pub const MAGIC: &[u8; 4] = b"DAST";
-/// Current format version.
-pub const VERSION: u8 = 1;
+/// Current format version. Version 2 stores code block line ranges as
+/// lists instead of strings and prefixes each node's payload with its
+/// length, so readers can skip node kinds they don't know. Files end
+/// with a CRC-32 of everything before it. Version 3 adds the section span
+/// to headings; version 4 records where parameter and return types came
+/// from; version 5 stores the document's abbreviation glossary; version
+/// 6 adds attribute lists to headings, fenced code blocks and links;
+/// version 7 gives each node an optional content hash; version 8 adds
+/// the column span to table cells; version 9 stores the document's dates
+/// and authors; version 10 names alert classes beyond GitHub's five;
+/// version 11 stores strings of up to 3 bytes in place (see
+/// [`INLINE_STR`]); version 12 moves the content hash from before the
+/// payload to its end, and marks files with inline strings by
+/// [`FLAG_INLINE_STRINGS`]; version 13 stores the version that
+/// deprecated a symbol.
+///
+/// Newer versions may add node kinds, and fields at the end of a payload;
+/// readers with forward compatibility skip both. A change that older
+/// readers can't skip sets a header flag instead, and readers refuse
+/// files with flags they don't know.
+pub const VERSION: u8 = 13;
+
+/// Set on a string reference that holds its string instead of a table
+/// index: the length in the rest of the high byte, then up to 3 UTF-8
+/// bytes in the low ones. Keeps strings like `x` or `-` out of the table
+/// without growing the node stream.
+pub const INLINE_STR: u32 = 1 << 31;
+/// Longest string stored in place of a table reference.
+pub const INLINE_MAX: usize = 3;
+/// Header flag of a file with [`INLINE_STR`] references, which readers
+/// that don't know them would take for table indices.
+pub const FLAG_INLINE_STRINGS: u8 = 2;
 
 /// Write document to DAST binary format.
 pub fn write_dast(doc: &Document) -> io::Result<Vec<u8>> {
@@ -47,6 +98,8 @@ mod tests {
           NodeKind::Heading {
             level: 1,
             id: Some("title".to_string()),
+            attributes: vec![("class".to_string(), "lead".to_string())],
+            section: None,
           },
           Span::new(11, 20, 2, 1),
           vec![Node::new(
@@ -60,6 +113,17 @@ mod tests {
       metadata: DocumentMetadata {
         title: Some("Test Doc".to_string()),
         description: Some("A test document".to_string()),
+        glossary: vec![(
+          "API".to_string(),
+          "Application Programming Interface".to_string(),
+        )],
+        date: Some("2024-03-05".to_string()),
+        updated: Some("2024-04-01T09:30:00Z".to_string()),
+        authors: vec![Author {
+          name: "Ada".to_string(),
+          email: Some("ada@example.com".to_string()),
+          url: None,
+        }],
         total_lines: 5,
         total_nodes: 3,
       },
@@ -69,7 +133,7 @@ mod tests {
   #[test]
   fn test_magic_bytes() {
     assert_eq!(MAGIC, b"DAST");
-    assert_eq!(VERSION, 1);
+    assert_eq!(VERSION, 13);
   }
 
   #[test]
@@ -93,6 +157,10 @@ mod tests {
     assert_eq!(restored.doc_type, doc.doc_type);
     assert_eq!(restored.nodes.len(), doc.nodes.len());
     assert_eq!(restored.metadata.title, doc.metadata.title);
+    assert_eq!(restored.metadata.glossary, doc.metadata.glossary);
+    assert_eq!(restored.metadata.date, doc.metadata.date);
+    assert_eq!(restored.metadata.updated, doc.metadata.updated);
+    assert_eq!(restored.metadata.authors, doc.metadata.authors);
     assert_eq!(restored.metadata.total_lines, doc.metadata.total_lines);
   }
 
@@ -139,6 +207,462 @@ mod tests {
     assert_eq!(restored.nodes.len(), 3);
   }
 
+  #[test]
+  fn test_roundtrip_tsdoc_nodes() {
+    let doc = Document {
+      source_path: "api.ts".to_string(),
+      doc_type: DocumentType::TypeScript,
+      nodes: vec![Node::with_children(
+        NodeKind::DocComment {
+          style: DocStyle::JSDoc,
+        },
+        Span::empty(),
+        vec![
+          Node::new(
+            NodeKind::DocTypeParam {
+              name: "T".to_string(),
+              description: Some("Item".to_string()),
+            },
+            Span::empty(),
+          ),
+          Node::new(
+            NodeKind::DocReleaseTag {
+              stage: ReleaseStage::Beta,
+            },
+            Span::empty(),
+          ),
+          Node::new(
+            NodeKind::DocInlineTag {
+              name: "inheritDoc".to_string(),
+              content: None,
+            },
+            Span::empty(),
+          ),
+        ],
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let restored = read_dast(&write_dast(&doc).unwrap()).unwrap();
+    assert_eq!(restored.doc_type, DocumentType::TypeScript);
+    let kinds: Vec<_> = restored.nodes[0].children.iter().map(|n| &n.kind).collect();
+    assert_eq!(kinds[0], &doc.nodes[0].children[0].kind);
+    assert_eq!(kinds[1], &doc.nodes[0].children[1].kind);
+    assert_eq!(kinds[2], &doc.nodes[0].children[2].kind);
+  }
+
+  #[test]
+  fn test_roundtrip_doctest_nodes() {
+    let doc = Document {
+      source_path: "maths.py".to_string(),
+      doc_type: DocumentType::Python,
+      nodes: vec![Node::with_children(
+        NodeKind::DocExample {
+          content: ">>> square(3)\n9".to_string(),
+        },
+        Span::empty(),
+        vec![
+          Node::new(
+            NodeKind::DocDoctest {
+              source: "square(3)".to_string(),
+              expected: Some("9".to_string()),
+            },
+            Span::empty(),
+          ),
+          Node::new(
+            NodeKind::DocDoctest {
+              source: "x = 1".to_string(),
+              expected: None,
+            },
+            Span::empty(),
+          ),
+        ],
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let restored = read_dast(&write_dast(&doc).unwrap()).unwrap();
+    assert_eq!(restored.nodes[0].kind, doc.nodes[0].kind);
+    assert_eq!(
+      restored.nodes[0].children[0].kind,
+      doc.nodes[0].children[0].kind
+    );
+    assert_eq!(
+      restored.nodes[0].children[1].kind,
+      doc.nodes[0].children[1].kind
+    );
+  }
+
+  #[test]
+  fn test_roundtrip_custom_element() {
+    let doc = Document {
+      source_path: "guide.md".to_string(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::with_children(
+        NodeKind::CustomElement {
+          name: "callout".to_string(),
+          attributes: vec![
+            ("type".to_string(), "warning".to_string()),
+            ("title".to_string(), String::new()),
+          ],
+        },
+        Span::empty(),
+        vec![Node::new(NodeKind::Paragraph, Span::empty())],
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let restored = read_dast(&write_dast(&doc).unwrap()).unwrap();
+    assert_eq!(restored.nodes[0].kind, doc.nodes[0].kind);
+    assert_eq!(restored.nodes[0].children.len(), 1);
+  }
+
+  #[test]
+  fn test_roundtrip_code_block_ranges() {
+    let doc = Document {
+      source_path: "guide.md".to_string(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::new(
+        NodeKind::CodeBlockExt {
+          language: Some("go".to_string()),
+          highlight: vec![(3, 3), (5, 7)],
+          plusdiff: vec![],
+          minusdiff: vec![(1, 2)],
+          linenumbers: true,
+          invalid_ranges: vec![("plusdiff".to_string(), "x".to_string())],
+          attributes: vec![],
+        },
+        Span::empty(),
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let restored = read_dast(&write_dast(&doc).unwrap()).unwrap();
+    assert_eq!(restored.nodes[0].kind, doc.nodes[0].kind);
+  }
+
+  #[test]
+  fn test_roundtrip_math() {
+    let doc = Document {
+      source_path: "notes.md".to_string(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![
+        Node::new(
+          NodeKind::MathInline {
+            content: "x^2".to_string(),
+          },
+          Span::empty(),
+        ),
+        Node::new(
+          NodeKind::MathBlock {
+            content: "\\sum_i x_i".to_string(),
+            display: true,
+          },
+          Span::empty(),
+        ),
+      ],
+      metadata: DocumentMetadata::default(),
+    };
+    let restored = read_dast(&write_dast(&doc).unwrap()).unwrap();
+    assert_eq!(restored.nodes[0].kind, doc.nodes[0].kind);
+    assert_eq!(restored.nodes[1].kind, doc.nodes[1].kind);
+  }
+
+  #[test]
+  fn test_writer_reuse() {
+    let mut writer = DastWriter::new();
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    writer.write(&test_doc(), &mut first).unwrap();
+    writer.write(&test_doc(), &mut second).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first, write_dast(&test_doc()).unwrap());
+  }
+
+  #[test]
+  fn test_sorted_strings() {
+    let write = |doc: &Document, sorted: bool| {
+      let mut data = Vec::new();
+      DastWriter::new()
+        .with_sorted_strings(sorted)
+        .write(doc, &mut data)
+        .unwrap();
+      data
+    };
+    // Header, then the table: count and length-prefixed strings
+    let table = |data: &[u8]| {
+      let count = u32::from_le_bytes(data[6..10].try_into().unwrap());
+      let mut strings = Vec::new();
+      let mut at = 10;
+      for _ in 0..count {
+        let len = u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as usize;
+        strings.push(data[at + 4..at + 4 + len].to_vec());
+        at += 4 + len;
+      }
+      strings
+    };
+
+    let doc = test_doc();
+    let sorted = write(&doc, true);
+    assert_eq!(to_json(&read_dast(&sorted).unwrap()), to_json(&doc));
+    let strings = table(&sorted);
+    assert!(strings.windows(2).all(|w| w[0] < w[1]));
+
+    // Meeting the same strings in another order gives the same table
+    let mut swapped = test_doc();
+    std::mem::swap(
+      &mut swapped.metadata.title,
+      &mut swapped.metadata.description,
+    );
+    assert_ne!(table(&write(&swapped, false)), table(&write(&doc, false)));
+    assert_eq!(table(&write(&swapped, true)), strings);
+  }
+
+  #[test]
+  fn test_index() {
+    use crate::markdown::MarkdownParser;
+    use std::io::Cursor;
+
+    let input = "# A\n\none\n\n> ## Quoted\n\n## B\n\ntwo\n\n# C\n";
+    let doc = MarkdownParser::new(input).parse();
+    let mut data = Vec::new();
+    DastWriter::new()
+      .with_index(true)
+      .write(&doc, &mut data)
+      .unwrap();
+    // Readers that don't look for the index still read the file
+    assert_eq!(to_json(&read_dast(&data).unwrap()), to_json(&doc));
+
+    let mut reader = DastReader::new();
+    let index = reader.read_index(&mut Cursor::new(&data)).unwrap().unwrap();
+    let shape: Vec<(u32, u8, bool)> = index
+      .entries
+      .iter()
+      .map(|e| (e.node, e.level, e.nested))
+      .collect();
+    assert_eq!(
+      shape,
+      [
+        (0, 1, false),
+        (1, 0, false),
+        (2, 0, false),
+        (2, 2, true),
+        (3, 2, false),
+        (4, 0, false),
+        (5, 1, false)
+      ]
+    );
+
+    // `## B` and its paragraph, decoded without the rest of the file
+    let (offset, count) = index.section(4).unwrap();
+    let section = reader
+      .read_nodes_at(&mut Cursor::new(&data), offset, count)
+      .unwrap();
+    let json = |nodes: &[Node]| {
+      let nodes: Vec<String> = nodes.iter().map(|n| kind_to_json(&n.kind)).collect();
+      nodes.join(",")
+    };
+    assert_eq!(json(&section), json(&doc.nodes[3..5]));
+    assert_eq!(section[1].children.len(), doc.nodes[4].children.len());
+    let nested = reader
+      .read_nodes_at(&mut Cursor::new(&data), index.entries[3].offset, 1)
+      .unwrap();
+    assert_eq!(json(&nested), json(&doc.nodes[2].children));
+
+    // Without the flag there is no index to read
+    let plain = write_dast(&doc).unwrap();
+    assert_eq!(reader.read_index(&mut Cursor::new(&plain)).unwrap(), None);
+    assert_eq!(plain.len() + index.entries.len() * 10 + 4, data.len());
+  }
+
+  /// A document whose only node is a `Text`, without its checksum, and
+  /// the offset of that node's tag: tag, span, payload length, string
+  /// index, hash flag, child count.
+  fn single_text_dast() -> (Vec<u8>, usize) {
+    let doc = Document {
+      source_path: "a.md".to_string(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::new(
+        NodeKind::Text {
+          content: "hi".to_string(),
+        },
+        Span::empty(),
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let mut data = write_dast(&doc).unwrap();
+    data.truncate(data.len() - 4);
+    let tag_at = data.len() - (1 + 16 + 4 + 4 + 1 + 4);
+    (data, tag_at)
+  }
+
+  fn seal(mut data: Vec<u8>) -> Vec<u8> {
+    let crc = crc32::checksum(&data);
+    data.extend_from_slice(&crc.to_le_bytes());
+    data
+  }
+
+  #[test]
+  fn test_unknown_tag_forward_compat() {
+    let (mut data, tag_at) = single_text_dast();
+    data[tag_at] = 200;
+    let data = seal(data);
+
+    assert!(read_dast(&data).is_err());
+    let doc = DastReader::new()
+      .with_forward_compat(true)
+      .read(&mut std::io::Cursor::new(&data))
+      .unwrap();
+    assert_eq!(doc.nodes[0].kind, NodeKind::Unknown { tag: 200 });
+    assert_eq!(doc.source_path, "a.md");
+  }
+
+  #[test]
+  fn test_newer_version_forward_compat() {
+    let (mut data, _) = single_text_dast();
+    data[4] = VERSION + 1;
+    let data = seal(data);
+
+    assert!(read_dast(&data).is_err());
+    let doc = DastReader::new()
+      .with_forward_compat(true)
+      .read(&mut std::io::Cursor::new(&data))
+      .unwrap();
+    assert!(matches!(doc.nodes[0].kind, NodeKind::Text { .. }));
+  }
+
+  #[test]
+  fn test_newer_payload_fields_skipped() {
+    let (mut data, tag_at) = single_text_dast();
+    // A newer writer appends a field after the hash flag
+    data[4] = VERSION + 1;
+    data[tag_at + 17] = 7;
+    data.splice(tag_at + 26..tag_at + 26, [0xab, 0xcd]);
+    let data = seal(data);
+
+    assert!(read_dast(&data).is_err());
+    let doc = DastReader::new()
+      .with_forward_compat(true)
+      .read(&mut std::io::Cursor::new(&data))
+      .unwrap();
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::Text {
+        content: "hi".to_string()
+      }
+    );
+    assert_eq!(doc.nodes[0].hash, None);
+  }
+
+  #[test]
+  fn test_hash_inside_payload() {
+    let mut doc = test_doc();
+    doc.nodes.truncate(1);
+    doc.nodes[0].hash = Some(0x0102_0304_0506_0708);
+    let data = write_dast(&doc).unwrap();
+
+    // A paragraph has no fields, so its payload is only the hash, which
+    // a reader that stops after the kind's fields skips
+    let hash = 0x0102_0304_0506_0708u64.to_le_bytes();
+    let at = data.windows(8).position(|w| w == hash).unwrap();
+    assert_eq!(data[at - 5..at - 1], 9u32.to_le_bytes());
+    assert_eq!(at + 8 + 4 + 4, data.len());
+    assert_eq!(read_dast(&data).unwrap().nodes[0].hash, doc.nodes[0].hash);
+  }
+
+  #[test]
+  fn test_inline_strings_need_flag() {
+    let (mut data, _) = single_text_dast();
+    assert_eq!(data[5] & FLAG_INLINE_STRINGS, FLAG_INLINE_STRINGS);
+    data[5] &= !FLAG_INLINE_STRINGS;
+    // Without the flag the reference is a table index, out of range here
+    let doc = read_dast(&seal(data)).unwrap();
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::Text {
+        content: String::new()
+      }
+    );
+  }
+
+  #[test]
+  fn test_unknown_flags_rejected() {
+    let (mut data, _) = single_text_dast();
+    data[5] |= 0x80;
+    let data = seal(data);
+
+    let err = DastReader::new()
+      .with_forward_compat(true)
+      .read(&mut std::io::Cursor::new(&data))
+      .unwrap_err();
+    assert!(err.to_string().contains("features"));
+  }
+
+  #[test]
+  fn test_payload_length_mismatch() {
+    let (mut data, tag_at) = single_text_dast();
+    // Claim a longer payload than the string index and hash flag
+    data[tag_at + 17] = 6;
+    data.push(0);
+    assert!(read_dast(&seal(data)).is_err());
+  }
+
+  #[test]
+  fn test_inline_strings() {
+    let text = |content: &str| {
+      Node::new(
+        NodeKind::Text {
+          content: content.to_string(),
+        },
+        Span::empty(),
+      )
+    };
+    let doc = Document {
+      source_path: "a.md".to_string(),
+      doc_type: DocumentType::Markdown,
+      nodes: ["-", "hello", "é", "-", "hello", ""]
+        .into_iter()
+        .map(text)
+        .collect(),
+      metadata: DocumentMetadata::default(),
+    };
+    let data = write_dast(&doc).unwrap();
+    let mut reader = DastReader::new().with_string_stats(true);
+    let restored = reader.read(&mut std::io::Cursor::new(&data)).unwrap();
+    assert_eq!(to_json(&restored), to_json(&doc));
+
+    let stats = reader.string_stats().unwrap();
+    // Only "a.md" and "hello" go to the table
+    assert_eq!((stats.entries, stats.table_bytes), (2, 4 + 8 + 9));
+    assert_eq!((stats.references, stats.referenced_bytes), (3, 14));
+    assert_eq!(stats.dedup_saved_bytes(), -7);
+    assert_eq!((stats.inline, stats.inline_saved_bytes), (4, 5 + 6 + 4));
+    assert!(DastReader::new().string_stats().is_none());
+  }
+
+  #[test]
+  fn test_checksum_mismatch() {
+    let mut data = write_dast(&test_doc()).unwrap();
+    let at = data.windows(5).position(|w| w == b"Hello").unwrap();
+    data[at] = b'J';
+
+    let err = read_dast(&data).unwrap_err();
+    assert!(err.to_string().contains("checksum"));
+    let doc = DastReader::new()
+      .with_verify(false)
+      .read(&mut std::io::Cursor::new(&data))
+      .unwrap();
+    assert_eq!(
+      doc.nodes[1].children[0].kind,
+      NodeKind::Text {
+        content: "Jello".to_string()
+      }
+    );
+  }
+
+  #[test]
+  fn test_truncated_file() {
+    let data = write_dast(&test_doc()).unwrap();
+    assert!(read_dast(&data[..data.len() - 1]).is_err());
+    assert!(read_dast(&data[..7]).is_err());
+  }
+
   #[test]
   fn test_read_invalid_magic() {
     let invalid = b"XXXX\x01\x00";
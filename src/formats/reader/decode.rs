@@ -35,6 +35,33 @@ pub fn u8_to_doc_style(v: u8) -> DocStyle {
     1 => DocStyle::JavaDoc,
     2 => DocStyle::PyDoc,
     3 => DocStyle::PyDocGoogle,
-    _ => DocStyle::PyDocNumpy,
+    4 => DocStyle::PyDocNumpy,
+    _ => DocStyle::JavaDocMarkdown,
+  }
+}
+
+pub fn u8_to_html_tag_kind(v: u8) -> HtmlTagKind {
+  match v {
+    0 => HtmlTagKind::Open,
+    1 => HtmlTagKind::Close,
+    _ => HtmlTagKind::SelfClosing,
+  }
+}
+
+pub fn u8_to_release_stage(v: u8) -> ReleaseStage {
+  match v {
+    0 => ReleaseStage::Alpha,
+    1 => ReleaseStage::Beta,
+    2 => ReleaseStage::Public,
+    3 => ReleaseStage::Internal,
+    _ => ReleaseStage::Experimental,
+  }
+}
+
+pub fn u8_to_type_source(v: u8) -> Option<TypeSource> {
+  match v {
+    0 => None,
+    1 => Some(TypeSource::Doc),
+    _ => Some(TypeSource::Annotation),
   }
 }
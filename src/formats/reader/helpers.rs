@@ -15,6 +15,12 @@ pub fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
   Ok(u32::from_le_bytes(b))
 }
 
+pub fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+  let mut b = [0u8; 8];
+  r.read_exact(&mut b)?;
+  Ok(u64::from_le_bytes(b))
+}
+
 pub fn read_span<R: Read>(r: &mut R) -> io::Result<Span> {
   Ok(Span::new(
     read_u32(r)? as usize,
@@ -24,6 +30,13 @@ pub fn read_span<R: Read>(r: &mut R) -> io::Result<Span> {
   ))
 }
 
+pub fn read_opt_span<R: Read>(r: &mut R) -> io::Result<Option<Span>> {
+  Ok(match read_u8(r)? {
+    0 => None,
+    _ => Some(read_span(r)?),
+  })
+}
+
 pub fn read_opt_u32<R: Read>(r: &mut R) -> io::Result<Option<u32>> {
   Ok(match read_u8(r)? {
     0 => None,
@@ -31,6 +44,22 @@ pub fn read_opt_u32<R: Read>(r: &mut R) -> io::Result<Option<u32>> {
   })
 }
 
+pub fn read_opt_u64<R: Read>(r: &mut R) -> io::Result<Option<u64>> {
+  Ok(match read_u8(r)? {
+    0 => None,
+    _ => Some(read_u64(r)?),
+  })
+}
+
+pub fn read_ranges<R: Read>(r: &mut R) -> io::Result<Vec<(u32, u32)>> {
+  let count = read_u32(r)? as usize;
+  let mut ranges = Vec::with_capacity(count);
+  for _ in 0..count {
+    ranges.push((read_u32(r)?, read_u32(r)?));
+  }
+  Ok(ranges)
+}
+
 pub fn read_opt_bool<R: Read>(r: &mut R) -> io::Result<Option<bool>> {
   Ok(match read_u8(r)? {
     0 => None,
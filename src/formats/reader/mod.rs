@@ -4,31 +4,170 @@ mod decode;
 mod helpers;
 
 use crate::ast::*;
-use std::io::{self, Read};
+use std::collections::HashSet;
+use std::io::{self, Read, Seek, SeekFrom};
 
-use super::{MAGIC, VERSION};
+use super::crc32::checksum;
+use super::index::{DastIndex, ENTRY_LEN, FLAG_INDEX};
+use super::{FLAG_INLINE_STRINGS, INLINE_MAX, INLINE_STR, MAGIC, VERSION};
 use decode::*;
 use helpers::*;
 
+/// How the strings of a DAST file are stored, and what the string table
+/// and inlining save.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringStats {
+  /// Entries in the string table
+  pub entries: usize,
+  /// Size of the table: its count, then each entry's length and bytes
+  pub table_bytes: usize,
+  /// References to table entries from the document
+  pub references: usize,
+  /// Bytes of the referenced strings, once per reference
+  pub referenced_bytes: usize,
+  /// Strings stored in place of a reference
+  pub inline: usize,
+  /// Table bytes the distinct inline strings would have taken
+  pub inline_saved_bytes: usize,
+  inline_seen: HashSet<u32>,
+}
+
+impl StringStats {
+  /// Bytes the table saves over writing every referenced string in place
+  /// after a length; negative when too few strings repeat to pay for it.
+  pub fn dedup_saved_bytes(&self) -> i64 {
+    self.referenced_bytes as i64 - self.table_bytes as i64
+  }
+}
+
 /// Reads a Document from DAST binary format.
 pub struct DastReader {
   strings: Vec<String>,
+  payload: Vec<u8>,
+  forward_compat: bool,
+  verify: bool,
+  stats: Option<StringStats>,
+  /// The file being read has `INLINE_STR` references
+  inline_strings: bool,
 }
 
 impl DastReader {
   pub fn new() -> Self {
     Self {
       strings: Vec::new(),
+      payload: Vec::new(),
+      forward_compat: false,
+      verify: true,
+      stats: None,
+      inline_strings: false,
     }
   }
 
+  /// Accept files from newer format versions: nodes with unknown tags
+  /// become `NodeKind::Unknown` and extra payload bytes are skipped,
+  /// instead of failing the read.
+  pub fn with_forward_compat(mut self, enabled: bool) -> Self {
+    self.forward_compat = enabled;
+    self
+  }
+
+  /// Check the CRC-32 trailer before decoding (on by default), so a
+  /// corrupted file fails up front rather than deep inside the node tree.
+  pub fn with_verify(mut self, enabled: bool) -> Self {
+    self.verify = enabled;
+    self
+  }
+
+  /// Count how the strings of each file read are stored, for
+  /// [`string_stats`](Self::string_stats).
+  pub fn with_string_stats(mut self, enabled: bool) -> Self {
+    self.stats = enabled.then(StringStats::default);
+    self
+  }
+
+  /// String statistics of the last file read, if enabled.
+  pub fn string_stats(&self) -> Option<&StringStats> {
+    self.stats.as_ref()
+  }
+
   pub fn read<R: Read>(&mut self, r: &mut R) -> io::Result<Document> {
+    if let Some(stats) = &mut self.stats {
+      *stats = StringStats::default();
+    }
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    let mut input = data.as_slice();
+    self.read_header(&mut input)?;
+    let mut input = &self.check_trailer(&data)?[MAGIC.len() + 2..];
+    self.read_string_table(&mut input)?;
+    self.read_document(&mut input)
+  }
+
+  /// The index of a file written with [`DastWriter::with_index`], or
+  /// `None` for one written without. Reads only the header and the index.
+  ///
+  /// [`DastWriter::with_index`]: super::DastWriter::with_index
+  pub fn read_index<R: Read + Seek>(&mut self, r: &mut R) -> io::Result<Option<DastIndex>> {
+    r.seek(SeekFrom::Start(0))?;
+    if self.read_header(r)? & FLAG_INDEX == 0 {
+      return Ok(None);
+    }
+    let count_at = r.seek(SeekFrom::End(-8))?;
+    let len = read_u32(r)? as u64 * ENTRY_LEN as u64;
+    let Some(start) = count_at
+      .checked_sub(len)
+      .filter(|&start| start >= (MAGIC.len() + 2) as u64)
+    else {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "DAST index runs past the start of the file",
+      ));
+    };
+    r.seek(SeekFrom::Start(start))?;
+    let mut data = vec![0u8; len as usize];
+    r.read_exact(&mut data)?;
+    DastIndex::decode(&data).map(Some)
+  }
+
+  /// Decode `count` sibling nodes starting at file offset `offset`, such
+  /// as a section from [`DastIndex::section`]. Reads the header and string
+  /// table, then only those nodes; the checksum is not verified.
+  #[allow(dead_code)]
+  pub fn read_nodes_at<R: Read + Seek>(
+    &mut self,
+    r: &mut R,
+    offset: u32,
+    count: usize,
+  ) -> io::Result<Vec<Node>> {
+    r.seek(SeekFrom::Start(0))?;
     self.read_header(r)?;
     self.read_string_table(r)?;
-    self.read_document(r)
+    r.seek(SeekFrom::Start(offset as u64))?;
+    (0..count).map(|_| self.read_node(r)).collect()
+  }
+
+  /// The file without its 4-byte checksum, verified unless disabled.
+  fn check_trailer<'d>(&self, data: &'d [u8]) -> io::Result<&'d [u8]> {
+    let header = MAGIC.len() + 2;
+    let Some(split) = data.len().checked_sub(4).filter(|&n| n >= header) else {
+      return Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "Truncated DAST file: missing checksum",
+      ));
+    };
+    let (content, trailer) = data.split_at(split);
+    let stored = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    if self.verify && checksum(content) != stored {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "DAST checksum mismatch: the file is corrupted or truncated",
+      ));
+    }
+    Ok(content)
   }
 
-  fn read_header<R: Read>(&self, r: &mut R) -> io::Result<()> {
+  /// Check the magic, version and flags, returning the flags.
+  fn read_header<R: Read>(&mut self, r: &mut R) -> io::Result<u8> {
     let mut magic = [0u8; 4];
     r.read_exact(&mut magic)?;
     if &magic != MAGIC {
@@ -39,13 +178,21 @@ impl DastReader {
     }
     let mut ver = [0u8; 2];
     r.read_exact(&mut ver)?;
-    if ver[0] != VERSION {
+    if ver[0] < VERSION || (ver[0] > VERSION && !self.forward_compat) {
       return Err(io::Error::new(
         io::ErrorKind::InvalidData,
         "Unsupported version",
       ));
     }
-    Ok(())
+    // Flags mark changes that can't be skipped, even for newer versions
+    if ver[1] & !(FLAG_INDEX | FLAG_INLINE_STRINGS) != 0 {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "DAST file uses features this reader doesn't support",
+      ));
+    }
+    self.inline_strings = ver[1] & FLAG_INLINE_STRINGS != 0;
+    Ok(ver[1])
   }
 
   fn read_string_table<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
@@ -58,6 +205,10 @@ impl DastReader {
         Ok(String::from_utf8_lossy(&buf).into_owned())
       })
       .collect::<io::Result<Vec<_>>>()?;
+    if let Some(stats) = &mut self.stats {
+      stats.entries = count;
+      stats.table_bytes = 4 + self.strings.iter().map(|s| 4 + s.len()).sum::<usize>();
+    }
     Ok(())
   }
 
@@ -66,6 +217,18 @@ impl DastReader {
     let doc_type = u8_to_doc_type(read_u8(r)?);
     let title = self.read_opt_str(r)?;
     let description = self.read_opt_str(r)?;
+    let glossary = self.read_pairs(r)?;
+    let date = self.read_opt_str(r)?;
+    let updated = self.read_opt_str(r)?;
+    let authors = (0..read_u32(r)?)
+      .map(|_| {
+        Ok(Author {
+          name: self.read_str(r)?,
+          email: self.read_opt_str(r)?,
+          url: self.read_opt_str(r)?,
+        })
+      })
+      .collect::<io::Result<Vec<_>>>()?;
     let total_lines = read_u32(r)? as usize;
     let total_nodes = read_u32(r)? as usize;
     let node_count = read_u32(r)? as usize;
@@ -80,6 +243,10 @@ impl DastReader {
       metadata: DocumentMetadata {
         title,
         description,
+        glossary,
+        date,
+        updated,
+        authors,
         total_lines,
         total_nodes,
       },
@@ -89,7 +256,7 @@ impl DastReader {
   fn read_node<R: Read>(&mut self, r: &mut R) -> io::Result<Node> {
     let tag = read_u8(r)?;
     let span = read_span(r)?;
-    let kind = self.read_kind(tag, r)?;
+    let (kind, hash) = self.read_payload(tag, r)?;
     let child_count = read_u32(r)? as usize;
     let children = (0..child_count)
       .map(|_| self.read_node(r))
@@ -98,15 +265,52 @@ impl DastReader {
       kind,
       span,
       children,
+      hash,
     })
   }
 
+  /// Read the length-prefixed payload whole, then decode it, so unknown
+  /// tags and fields added by newer writers can be skipped. Returns the
+  /// kind and the content hash that follows its fields; no hash for a kind
+  /// this reader doesn't know.
+  fn read_payload<R: Read>(&mut self, tag: u8, r: &mut R) -> io::Result<(NodeKind, Option<u64>)> {
+    let len = read_u32(r)? as usize;
+    let mut payload = std::mem::take(&mut self.payload);
+    payload.clear();
+    r.by_ref().take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() != len {
+      return Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "Truncated node payload",
+      ));
+    }
+
+    let mut data = payload.as_slice();
+    let decoded = self.read_kind(tag, &mut data).and_then(|kind| match kind {
+      NodeKind::Unknown { .. } => Ok((kind, None)),
+      _ => Ok((kind, read_opt_u64(&mut data)?)),
+    });
+    let leftover = !data.is_empty();
+    self.payload = payload;
+
+    let decoded = decoded?;
+    if leftover && !self.forward_compat {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Node payload length mismatch",
+      ));
+    }
+    Ok(decoded)
+  }
+
   fn read_kind<R: Read>(&mut self, tag: u8, r: &mut R) -> io::Result<NodeKind> {
     Ok(match tag {
       0 => NodeKind::Document,
       1 => NodeKind::Heading {
         level: read_u8(r)?,
         id: self.read_opt_str(r)?,
+        section: read_opt_span(r)?,
+        attributes: self.read_pairs(r)?,
       },
       2 => NodeKind::Paragraph,
       3 => NodeKind::BlockQuote,
@@ -117,6 +321,7 @@ impl DastReader {
       5 => NodeKind::FencedCodeBlock {
         language: self.read_opt_str(r)?,
         info: self.read_opt_str(r)?,
+        attributes: self.read_pairs(r)?,
       },
       6 => NodeKind::IndentedCodeBlock,
       7 => NodeKind::HtmlBlock {
@@ -139,6 +344,7 @@ impl DastReader {
       15 => NodeKind::TableCell {
         alignment: u8_to_alignment(read_u8(r)?),
         is_header: read_u8(r)? != 0,
+        colspan: read_u32(r)?,
       },
       16 => NodeKind::Text {
         content: self.read_str(r)?,
@@ -153,11 +359,13 @@ impl DastReader {
         url: self.read_str(r)?,
         title: self.read_opt_str(r)?,
         ref_type: u8_to_ref_type(read_u8(r)?),
+        attributes: self.read_pairs(r)?,
       },
       22 => NodeKind::Image {
         url: self.read_str(r)?,
         alt: self.read_str(r)?,
         title: self.read_opt_str(r)?,
+        attributes: self.read_pairs(r)?,
       },
       23 => NodeKind::AutoLink {
         url: self.read_str(r)?,
@@ -178,9 +386,11 @@ impl DastReader {
       },
       29 => NodeKind::FootnoteReference {
         label: self.read_str(r)?,
+        index: read_u32(r)?,
       },
       30 => NodeKind::FootnoteDefinition {
         label: self.read_str(r)?,
+        index: read_u32(r)?,
       },
       31 => NodeKind::TaskListMarker {
         checked: read_u8(r)? != 0,
@@ -205,10 +415,12 @@ impl DastReader {
         name: self.read_str(r)?,
         param_type: self.read_opt_str(r)?,
         description: self.read_opt_str(r)?,
+        type_source: u8_to_type_source(read_u8(r)?),
       },
       38 => NodeKind::DocReturn {
         return_type: self.read_opt_str(r)?,
         description: self.read_opt_str(r)?,
+        type_source: u8_to_type_source(read_u8(r)?),
       },
       39 => NodeKind::DocThrows {
         exception_type: self.read_str(r)?,
@@ -222,6 +434,7 @@ impl DastReader {
       },
       42 => NodeKind::DocDeprecated {
         message: self.read_opt_str(r)?,
+        version: self.read_opt_str(r)?,
       },
       43 => NodeKind::DocSince {
         version: self.read_str(r)?,
@@ -262,6 +475,7 @@ impl DastReader {
       },
       54 => NodeKind::MathBlock {
         content: self.read_str(r)?,
+        display: read_u8(r)? != 0,
       },
       55 => NodeKind::Footnote {
         label: self.read_str(r)?,
@@ -273,10 +487,16 @@ impl DastReader {
         url: self.read_str(r)?,
       },
       60 => NodeKind::Alert {
-        alert_type: u8_to_alert_type(read_u8(r)?),
+        alert_type: match read_u8(r)? {
+          5 => AlertType::Other(self.read_str(r)?),
+          v => u8_to_alert_type(v),
+        },
       },
       61 => NodeKind::Steps,
-      62 => NodeKind::Step,
+      62 => NodeKind::Step {
+        number: read_u32(r)?,
+        title: self.read_opt_str(r)?,
+      },
       63 => NodeKind::Toc,
       64 => NodeKind::Tabs {
         names: {
@@ -290,11 +510,65 @@ impl DastReader {
       },
       65 => NodeKind::CodeBlockExt {
         language: self.read_opt_str(r)?,
-        highlight: self.read_opt_str(r)?,
-        plusdiff: self.read_opt_str(r)?,
-        minusdiff: self.read_opt_str(r)?,
+        highlight: read_ranges(r)?,
+        plusdiff: read_ranges(r)?,
+        minusdiff: read_ranges(r)?,
         linenumbers: read_u8(r)? != 0,
+        invalid_ranges: self.read_pairs(r)?,
+        attributes: self.read_pairs(r)?,
+      },
+      66 => NodeKind::DocTypeParam {
+        name: self.read_str(r)?,
+        description: self.read_opt_str(r)?,
+      },
+      67 => NodeKind::DocRemarks {
+        content: self.read_str(r)?,
+      },
+      68 => NodeKind::DocDefaultValue {
+        value: self.read_str(r)?,
+      },
+      69 => NodeKind::DocReleaseTag {
+        stage: u8_to_release_stage(read_u8(r)?),
+      },
+      70 => NodeKind::DocModifier {
+        name: self.read_str(r)?,
+      },
+      71 => NodeKind::DocInlineTag {
+        name: self.read_str(r)?,
+        content: self.read_opt_str(r)?,
+      },
+      72 => NodeKind::DocDoctest {
+        source: self.read_str(r)?,
+        expected: self.read_opt_str(r)?,
+      },
+      73 => NodeKind::CustomElement {
+        name: self.read_str(r)?,
+        attributes: self.read_pairs(r)?,
       },
+      74 => NodeKind::Include {
+        src: self.read_str(r)?,
+      },
+      75 => NodeKind::Tab {
+        name: self.read_str(r)?,
+      },
+      76 => NodeKind::HtmlTag {
+        name: self.read_str(r)?,
+        tag: u8_to_html_tag_kind(read_u8(r)?),
+        attributes: self.read_pairs(r)?,
+      },
+      77 => NodeKind::Footnotes,
+      78 => NodeKind::Abbreviation {
+        title: self.read_str(r)?,
+      },
+      79 => NodeKind::Comment {
+        content: self.read_str(r)?,
+        block: read_u8(r)? != 0,
+      },
+      80 => NodeKind::DiagramBlock {
+        engine: self.read_str(r)?,
+        content: self.read_str(r)?,
+      },
+      _ if self.forward_compat => NodeKind::Unknown { tag },
       _ => {
         return Err(io::Error::new(
           io::ErrorKind::InvalidData,
@@ -304,17 +578,52 @@ impl DastReader {
     })
   }
 
-  fn read_str<R: Read>(&self, r: &mut R) -> io::Result<String> {
-    let idx = read_u32(r)? as usize;
-    Ok(self.strings.get(idx).cloned().unwrap_or_default())
+  fn read_str<R: Read>(&mut self, r: &mut R) -> io::Result<String> {
+    let value = read_u32(r)?;
+    if self.inline_strings && value & INLINE_STR != 0 {
+      return self.read_inline(value);
+    }
+    let s = self
+      .strings
+      .get(value as usize)
+      .cloned()
+      .unwrap_or_default();
+    if let Some(stats) = &mut self.stats {
+      stats.references += 1;
+      stats.referenced_bytes += s.len();
+    }
+    Ok(s)
+  }
+
+  fn read_inline(&mut self, value: u32) -> io::Result<String> {
+    let len = (value >> 24 & 0x7f) as usize;
+    if len > INLINE_MAX {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Invalid inline string length",
+      ));
+    }
+    if let Some(stats) = &mut self.stats {
+      stats.inline += 1;
+      if stats.inline_seen.insert(value) {
+        stats.inline_saved_bytes += 4 + len;
+      }
+    }
+    Ok(String::from_utf8_lossy(&value.to_le_bytes()[..len]).into_owned())
   }
 
-  fn read_opt_str<R: Read>(&self, r: &mut R) -> io::Result<Option<String>> {
+  fn read_opt_str<R: Read>(&mut self, r: &mut R) -> io::Result<Option<String>> {
     Ok(match read_u8(r)? {
       0 => None,
       _ => Some(self.read_str(r)?),
     })
   }
+
+  fn read_pairs<R: Read>(&mut self, r: &mut R) -> io::Result<Vec<(String, String)>> {
+    (0..read_u32(r)?)
+      .map(|_| Ok((self.read_str(r)?, self.read_str(r)?)))
+      .collect()
+  }
 }
 
 fn u8_to_alert_type(v: u8) -> AlertType {
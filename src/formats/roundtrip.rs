@@ -0,0 +1,1052 @@
+//! Round-trip tests over generated documents. A seeded generator builds
+//! random trees that use every `NodeKind` variant, with strings full of
+//! quotes, escapes, control characters and non-ASCII text, and checks that:
+//!
+//! - DAST write → read gives back the same document
+//! - the JSON output, read back with a minimal parser, keeps every node's
+//!   type name and field values and matches the published schema
+//!
+//! There is no JSON reader in the crate yet; once there is, the JSON check
+//! should become a write → read comparison like the DAST one.
+//!
+//! Adding a `NodeKind` variant breaks the build here until it is listed in
+//! `fields`; give it a case in `gen_kind` as well, or `test_every_kind_covered`
+//! fails.
+
+use super::json::schema::{FieldType, KINDS};
+use super::json::SCHEMA_VERSION;
+use super::{json_schema, read_dast, to_json, write_dast, DastReader};
+use crate::ast::*;
+
+use std::collections::BTreeSet;
+use std::io::Cursor;
+
+/// Number of cases in `gen_kind`.
+const KIND_COUNT: u32 = 82;
+
+/// xorshift64*: deterministic, so a failing seed can be replayed.
+struct Rng(u64);
+
+impl Rng {
+  fn new(seed: u64) -> Self {
+    Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+  }
+
+  fn next(&mut self) -> u64 {
+    self.0 ^= self.0 >> 12;
+    self.0 ^= self.0 << 25;
+    self.0 ^= self.0 >> 27;
+    self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+  }
+
+  fn below(&mut self, n: u32) -> u32 {
+    (self.next() % n as u64) as u32
+  }
+
+  fn bool(&mut self) -> bool {
+    self.next() & 1 == 1
+  }
+
+  fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+    items[self.below(items.len() as u32) as usize]
+  }
+}
+
+/// Produces strings that are unique within a document, so finding one in
+/// the output means that particular field was written.
+struct Gen {
+  rng: Rng,
+  serial: u32,
+}
+
+const PIECES: &[&str] = &[
+  "a", "word", " ", "\"", "\\", "\n", "\r\n", "\t", "\u{1}", "\u{7f}", "é", "Ж", "漢字", "😀",
+  "<b>", "&amp;", "{}", "[x]", "\u{2028}", "null",
+];
+
+impl Gen {
+  fn string(&mut self) -> String {
+    let mut s = String::new();
+    for _ in 0..self.rng.below(4) {
+      s.push_str(self.rng.pick(PIECES));
+    }
+    self.serial += 1;
+    s.push_str(&format!("~{}", self.serial));
+    s
+  }
+
+  fn opt(&mut self) -> Option<String> {
+    self.rng.bool().then(|| self.string())
+  }
+
+  /// Numbers large enough not to turn up in the output by accident.
+  fn number(&mut self) -> u32 {
+    100_000 + self.rng.below(u32::MAX - 100_000)
+  }
+
+  fn pairs(&mut self) -> Vec<(String, String)> {
+    (0..self.rng.below(3))
+      .map(|_| (self.string(), self.string()))
+      .collect()
+  }
+
+  fn ranges(&mut self) -> Vec<(u32, u32)> {
+    (0..self.rng.below(3))
+      .map(|_| {
+        let start = self.number();
+        (start, start.saturating_add(self.rng.below(50)))
+      })
+      .collect()
+  }
+
+  fn span(&mut self) -> Span {
+    let start = self.rng.below(1 << 24) as usize;
+    let len = self.rng.below(1 << 16) as usize;
+    Span::new(
+      start,
+      start + len,
+      self.rng.below(1 << 20) as usize,
+      self.rng.below(1 << 12) as usize,
+    )
+  }
+
+  fn gen_kind(&mut self, case: u32) -> NodeKind {
+    let rng = &mut self.rng;
+    let ref_type = rng.pick(&[
+      ReferenceType::Full,
+      ReferenceType::Collapsed,
+      ReferenceType::Shortcut,
+    ]);
+    let marker = if rng.bool() {
+      ListMarker::Bullet(rng.pick(&['-', '*', '+']))
+    } else {
+      ListMarker::Ordered(rng.pick(b".)"))
+    };
+    let alignment = rng.pick(&[
+      Alignment::None,
+      Alignment::Left,
+      Alignment::Center,
+      Alignment::Right,
+    ]);
+    let style = rng.pick(&[
+      DocStyle::JSDoc,
+      DocStyle::JavaDoc,
+      DocStyle::PyDoc,
+      DocStyle::PyDocGoogle,
+      DocStyle::PyDocNumpy,
+      DocStyle::JavaDocMarkdown,
+    ]);
+    let stage = rng.pick(&[
+      ReleaseStage::Alpha,
+      ReleaseStage::Beta,
+      ReleaseStage::Public,
+      ReleaseStage::Internal,
+      ReleaseStage::Experimental,
+    ]);
+    let type_source = rng.pick(&[None, Some(TypeSource::Doc), Some(TypeSource::Annotation)]);
+    let tag = rng.pick(&[
+      HtmlTagKind::Open,
+      HtmlTagKind::Close,
+      HtmlTagKind::SelfClosing,
+    ]);
+    let format = rng.pick(&[
+      FrontmatterFormat::Yaml,
+      FrontmatterFormat::Toml,
+      FrontmatterFormat::Json,
+    ]);
+    let alert_type = AlertType::from_name(rng.pick(&[
+      "NOTE",
+      "TIP",
+      "IMPORTANT",
+      "WARNING",
+      "CAUTION",
+      "SUCCESS",
+    ]));
+    let flag = rng.bool();
+    let checked = rng.pick(&[None, Some(false), Some(true)]);
+
+    match case {
+      0 => NodeKind::Document,
+      1 => NodeKind::Heading {
+        level: 1 + self.rng.below(6) as u8,
+        id: self.opt(),
+        attributes: self.pairs(),
+        section: self.rng.bool().then(|| self.span()),
+      },
+      2 => NodeKind::Paragraph,
+      3 => NodeKind::BlockQuote,
+      4 => NodeKind::CodeBlock {
+        language: self.opt(),
+        info: self.opt(),
+      },
+      5 => NodeKind::FencedCodeBlock {
+        language: self.opt(),
+        info: self.opt(),
+        attributes: self.pairs(),
+      },
+      6 => NodeKind::IndentedCodeBlock,
+      7 => NodeKind::HtmlBlock {
+        block_type: 1 + self.rng.below(7) as u8,
+      },
+      8 => NodeKind::ThematicBreak,
+      9 => NodeKind::List {
+        ordered: flag,
+        start: self.rng.bool().then(|| self.number()),
+        tight: self.rng.bool(),
+      },
+      10 => NodeKind::ListItem { marker, checked },
+      11 => NodeKind::Table,
+      12 => NodeKind::TableHead,
+      13 => NodeKind::TableBody,
+      14 => NodeKind::TableRow,
+      15 => NodeKind::TableCell {
+        alignment,
+        is_header: flag,
+        colspan: 1 + self.rng.below(4),
+      },
+      16 => NodeKind::Text {
+        content: self.string(),
+      },
+      17 => NodeKind::Emphasis,
+      18 => NodeKind::Strong,
+      19 => NodeKind::Strikethrough,
+      20 => NodeKind::Code {
+        content: self.string(),
+      },
+      21 => NodeKind::CodeSpan {
+        content: self.string(),
+      },
+      22 => NodeKind::Link {
+        url: self.string(),
+        title: self.opt(),
+        ref_type,
+        attributes: self.pairs(),
+      },
+      23 => NodeKind::Image {
+        url: self.string(),
+        alt: self.string(),
+        title: self.opt(),
+        attributes: self.pairs(),
+      },
+      24 => NodeKind::AutoLink { url: self.string() },
+      25 => NodeKind::HardBreak,
+      26 => NodeKind::SoftBreak,
+      27 => NodeKind::HtmlInline {
+        content: self.string(),
+      },
+      28 => NodeKind::HtmlTag {
+        name: self.string(),
+        tag,
+        attributes: self.pairs(),
+      },
+      29 => NodeKind::LinkReference {
+        label: self.string(),
+        ref_type,
+      },
+      30 => NodeKind::LinkDefinition {
+        label: self.string(),
+        url: self.string(),
+        title: self.opt(),
+      },
+      31 => NodeKind::FootnoteReference {
+        label: self.string(),
+        index: self.number(),
+      },
+      32 => NodeKind::FootnoteDefinition {
+        label: self.string(),
+        index: self.number(),
+      },
+      33 => NodeKind::Footnotes,
+      34 => NodeKind::TaskListMarker { checked: flag },
+      35 => NodeKind::Emoji {
+        shortcode: self.string(),
+      },
+      36 => NodeKind::Mention {
+        username: self.string(),
+      },
+      37 => NodeKind::IssueReference {
+        number: self.number(),
+      },
+      38 => NodeKind::DocComment { style },
+      39 => NodeKind::DocTag {
+        name: self.string(),
+        content: self.opt(),
+      },
+      40 => NodeKind::DocParam {
+        name: self.string(),
+        param_type: self.opt(),
+        description: self.opt(),
+        type_source,
+      },
+      41 => NodeKind::DocReturn {
+        return_type: self.opt(),
+        description: self.opt(),
+        type_source,
+      },
+      42 => NodeKind::DocThrows {
+        exception_type: self.string(),
+        description: self.opt(),
+      },
+      43 => NodeKind::DocExample {
+        content: self.string(),
+      },
+      44 => NodeKind::DocSee {
+        reference: self.string(),
+      },
+      45 => NodeKind::DocDeprecated {
+        message: self.opt(),
+        version: self.opt(),
+      },
+      46 => NodeKind::DocSince {
+        version: self.string(),
+      },
+      47 => NodeKind::DocAuthor {
+        name: self.string(),
+      },
+      48 => NodeKind::DocVersion {
+        version: self.string(),
+      },
+      49 => NodeKind::DocDescription {
+        content: self.string(),
+      },
+      50 => NodeKind::DocType {
+        type_expr: self.string(),
+      },
+      51 => NodeKind::DocProperty {
+        name: self.string(),
+        prop_type: self.opt(),
+        description: self.opt(),
+      },
+      52 => NodeKind::DocCallback {
+        name: self.string(),
+      },
+      53 => NodeKind::DocTypedef {
+        name: self.string(),
+        type_expr: self.opt(),
+      },
+      54 => NodeKind::DocTypeParam {
+        name: self.string(),
+        description: self.opt(),
+      },
+      55 => NodeKind::DocRemarks {
+        content: self.string(),
+      },
+      56 => NodeKind::DocDefaultValue {
+        value: self.string(),
+      },
+      57 => NodeKind::DocReleaseTag { stage },
+      58 => NodeKind::DocModifier {
+        name: self.string(),
+      },
+      59 => NodeKind::DocInlineTag {
+        name: self.string(),
+        content: self.opt(),
+      },
+      60 => NodeKind::DocDoctest {
+        source: self.string(),
+        expected: self.opt(),
+      },
+      61 => NodeKind::Frontmatter {
+        format,
+        content: self.string(),
+      },
+      62 => NodeKind::MathInline {
+        content: self.string(),
+      },
+      63 => NodeKind::MathBlock {
+        content: self.string(),
+        display: flag,
+      },
+      64 => NodeKind::Footnote {
+        label: self.string(),
+      },
+      65 => NodeKind::DefinitionList,
+      66 => NodeKind::DefinitionTerm,
+      67 => NodeKind::DefinitionDescription,
+      68 => NodeKind::AutoUrl { url: self.string() },
+      69 => NodeKind::Alert { alert_type },
+      70 => NodeKind::Steps,
+      71 => NodeKind::Step {
+        number: self.number(),
+        title: self.opt(),
+      },
+      72 => NodeKind::Toc,
+      73 => NodeKind::Tabs {
+        names: (0..self.rng.below(4)).map(|_| self.string()).collect(),
+      },
+      74 => NodeKind::Tab {
+        name: self.string(),
+      },
+      75 => NodeKind::CodeBlockExt {
+        language: self.opt(),
+        highlight: self.ranges(),
+        plusdiff: self.ranges(),
+        minusdiff: self.ranges(),
+        linenumbers: flag,
+        invalid_ranges: self.pairs(),
+        attributes: self.pairs(),
+      },
+      76 => NodeKind::CustomElement {
+        name: self.string(),
+        attributes: self.pairs(),
+      },
+      77 => NodeKind::Include { src: self.string() },
+      78 => NodeKind::Abbreviation {
+        title: self.string(),
+      },
+      79 => NodeKind::Comment {
+        content: self.string(),
+        block: flag,
+      },
+      80 => NodeKind::DiagramBlock {
+        engine: self.string(),
+        content: self.string(),
+      },
+      // Tags no current version uses, as a newer writer would produce
+      81 => NodeKind::Unknown {
+        tag: 200 + self.rng.below(56) as u8,
+      },
+      _ => unreachable!("no generator case {}", case),
+    }
+  }
+
+  fn node(&mut self, depth: u32) -> Node {
+    let case = self.rng.below(KIND_COUNT);
+    let kind = self.gen_kind(case);
+    let span = self.span();
+    let children = if depth < 4 {
+      (0..self.rng.below(4))
+        .map(|_| self.node(depth + 1))
+        .collect()
+    } else {
+      Vec::new()
+    };
+    // A reader can't find the hash behind fields it doesn't know
+    let hashed = self.rng.bool() && !matches!(kind, NodeKind::Unknown { .. });
+    let mut node = Node::with_children(kind, span, children);
+    node.hash = hashed.then(|| self.rng.next());
+    node
+  }
+
+  fn document(&mut self, nodes: Vec<Node>) -> Document {
+    let doc_type = self.rng.pick(&DocumentType::ALL);
+    Document {
+      source_path: self.string(),
+      doc_type,
+      metadata: DocumentMetadata {
+        title: self.opt(),
+        description: self.opt(),
+        glossary: self.pairs(),
+        date: self.opt(),
+        updated: self.opt(),
+        authors: (0..self.rng.below(3))
+          .map(|_| Author {
+            name: self.string(),
+            email: self.opt(),
+            url: self.opt(),
+          })
+          .collect(),
+        total_lines: self.number() as usize,
+        total_nodes: nodes.iter().map(Node::count_nodes).sum(),
+      },
+      nodes,
+    }
+  }
+}
+
+fn random_document(seed: u64) -> Document {
+  let mut g = Gen {
+    rng: Rng::new(seed),
+    serial: 0,
+  };
+  let nodes = (0..1 + g.rng.below(12)).map(|_| g.node(0)).collect();
+  g.document(nodes)
+}
+
+/// One node of every kind, for exhaustive coverage independent of seeds.
+fn every_kind_document(seed: u64) -> Document {
+  let mut g = Gen {
+    rng: Rng::new(seed),
+    serial: 0,
+  };
+  let nodes = (0..KIND_COUNT)
+    .map(|case| {
+      let kind = g.gen_kind(case);
+      Node::new(kind, g.span())
+    })
+    .collect();
+  g.document(nodes)
+}
+
+/// Field values that must come back out of the JSON output, numbers as
+/// text. Booleans and enums are left out; the schema check covers those.
+fn fields(kind: &NodeKind) -> Vec<String> {
+  let s = |s: &String| s.clone();
+  let opt = |o: &Option<String>| o.iter().cloned().collect::<Vec<_>>();
+  let pairs = |p: &[(String, String)]| {
+    p.iter()
+      .flat_map(|(k, v)| [k.clone(), v.clone()])
+      .collect::<Vec<_>>()
+  };
+  let ranges = |r: &[(u32, u32)]| {
+    r.iter()
+      .flat_map(|(a, b)| [a.to_string(), b.to_string()])
+      .collect::<Vec<_>>()
+  };
+  match kind {
+    NodeKind::Document
+    | NodeKind::Paragraph
+    | NodeKind::BlockQuote
+    | NodeKind::IndentedCodeBlock
+    | NodeKind::ThematicBreak
+    | NodeKind::ListItem { .. }
+    | NodeKind::Table
+    | NodeKind::TableHead
+    | NodeKind::TableBody
+    | NodeKind::TableRow
+    | NodeKind::Emphasis
+    | NodeKind::Strong
+    | NodeKind::Strikethrough
+    | NodeKind::HardBreak
+    | NodeKind::SoftBreak
+    | NodeKind::Footnotes
+    | NodeKind::TaskListMarker { .. }
+    | NodeKind::DocComment { .. }
+    | NodeKind::DocReleaseTag { .. }
+    | NodeKind::DefinitionList
+    | NodeKind::DefinitionTerm
+    | NodeKind::DefinitionDescription
+    | NodeKind::Alert { .. }
+    | NodeKind::Steps
+    | NodeKind::Toc => vec![],
+    NodeKind::Heading {
+      level,
+      id,
+      attributes,
+      section,
+    } => {
+      let section = section
+        .iter()
+        .flat_map(|s| [s.start, s.end, s.line, s.column].map(|n| n.to_string()));
+      [
+        vec![level.to_string()],
+        opt(id),
+        pairs(attributes),
+        section.collect(),
+      ]
+      .concat()
+    }
+    NodeKind::CodeBlock { language, info } => [opt(language), opt(info)].concat(),
+    NodeKind::FencedCodeBlock {
+      language,
+      info,
+      attributes,
+    } => [opt(language), opt(info), pairs(attributes)].concat(),
+    NodeKind::HtmlBlock { block_type } => vec![block_type.to_string()],
+    NodeKind::List { start, .. } => start.iter().map(u32::to_string).collect(),
+    NodeKind::TableCell { colspan, .. } if *colspan > 1 => vec![colspan.to_string()],
+    NodeKind::TableCell { .. } => vec![],
+    NodeKind::Text { content }
+    | NodeKind::Code { content }
+    | NodeKind::CodeSpan { content }
+    | NodeKind::HtmlInline { content }
+    | NodeKind::DocExample { content }
+    | NodeKind::DocDescription { content }
+    | NodeKind::DocRemarks { content }
+    | NodeKind::Frontmatter { content, .. }
+    | NodeKind::MathInline { content }
+    | NodeKind::MathBlock { content, .. }
+    | NodeKind::Comment { content, .. } => vec![s(content)],
+    NodeKind::Link {
+      url,
+      title,
+      attributes,
+      ..
+    } => [vec![s(url)], opt(title), pairs(attributes)].concat(),
+    NodeKind::Image {
+      url,
+      alt,
+      title,
+      attributes,
+    } => [vec![s(url), s(alt)], opt(title), pairs(attributes)].concat(),
+    NodeKind::AutoLink { url } | NodeKind::AutoUrl { url } => vec![s(url)],
+    NodeKind::HtmlTag {
+      name, attributes, ..
+    }
+    | NodeKind::CustomElement { name, attributes } => [vec![s(name)], pairs(attributes)].concat(),
+    NodeKind::LinkReference { label, .. } | NodeKind::Footnote { label } => vec![s(label)],
+    NodeKind::LinkDefinition { label, url, title } => [vec![s(label), s(url)], opt(title)].concat(),
+    NodeKind::FootnoteReference { label, index }
+    | NodeKind::FootnoteDefinition { label, index } => vec![s(label), index.to_string()],
+    NodeKind::Emoji { shortcode } => vec![s(shortcode)],
+    NodeKind::Mention { username } => vec![s(username)],
+    NodeKind::IssueReference { number } => vec![number.to_string()],
+    NodeKind::DocTag { name, content } | NodeKind::DocInlineTag { name, content } => {
+      [vec![s(name)], opt(content)].concat()
+    }
+    NodeKind::DocParam {
+      name,
+      param_type: kind,
+      description,
+      ..
+    }
+    | NodeKind::DocProperty {
+      name,
+      prop_type: kind,
+      description,
+    } => [vec![s(name)], opt(kind), opt(description)].concat(),
+    NodeKind::DocReturn {
+      return_type,
+      description,
+      ..
+    } => [opt(return_type), opt(description)].concat(),
+    NodeKind::DocThrows {
+      exception_type,
+      description,
+    } => [vec![s(exception_type)], opt(description)].concat(),
+    NodeKind::DocSee { reference } => vec![s(reference)],
+    NodeKind::DocDeprecated { message, version } => [opt(message), opt(version)].concat(),
+    NodeKind::DocSince { version } | NodeKind::DocVersion { version } => vec![s(version)],
+    NodeKind::DocAuthor { name }
+    | NodeKind::DocCallback { name }
+    | NodeKind::DocModifier { name }
+    | NodeKind::Tab { name } => vec![s(name)],
+    NodeKind::Abbreviation { title } => vec![s(title)],
+    NodeKind::DocType { type_expr } => vec![s(type_expr)],
+    NodeKind::DocTypedef { name, type_expr } => [vec![s(name)], opt(type_expr)].concat(),
+    NodeKind::DocTypeParam { name, description } => [vec![s(name)], opt(description)].concat(),
+    NodeKind::DocDefaultValue { value } => vec![s(value)],
+    NodeKind::DocDoctest { source, expected } => [vec![s(source)], opt(expected)].concat(),
+    NodeKind::Step { number, title } => [vec![number.to_string()], opt(title)].concat(),
+    NodeKind::Tabs { names } => names.iter().map(s).collect(),
+    NodeKind::CodeBlockExt {
+      language,
+      highlight,
+      plusdiff,
+      minusdiff,
+      invalid_ranges,
+      attributes,
+      ..
+    } => [
+      opt(language),
+      ranges(highlight),
+      ranges(plusdiff),
+      ranges(minusdiff),
+      pairs(invalid_ranges),
+      pairs(attributes),
+    ]
+    .concat(),
+    NodeKind::Include { src } => vec![s(src)],
+    NodeKind::DiagramBlock { engine, content } => vec![s(engine), s(content)],
+    NodeKind::Unknown { tag } => vec![tag.to_string()],
+  }
+}
+
+fn assert_same_nodes(expected: &[Node], actual: &[Node], path: &str, seed: u64) {
+  assert_eq!(
+    expected.len(),
+    actual.len(),
+    "seed {}: child count at {}",
+    seed,
+    path
+  );
+  for (i, (e, a)) in expected.iter().zip(actual).enumerate() {
+    let path = format!("{}/{}", path, i);
+    assert_eq!(e.kind, a.kind, "seed {}: kind at {}", seed, path);
+    assert_eq!(e.span, a.span, "seed {}: span at {}", seed, path);
+    assert_eq!(e.hash, a.hash, "seed {}: hash at {}", seed, path);
+    assert_same_nodes(&e.children, &a.children, &path, seed);
+  }
+}
+
+fn check_dast(doc: &Document, seed: u64) {
+  let data = write_dast(doc).unwrap();
+  // Unknown kinds only read back in forward-compatible mode
+  let restored = DastReader::new()
+    .with_forward_compat(true)
+    .read(&mut Cursor::new(&data))
+    .unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+  assert_eq!(restored.source_path, doc.source_path, "seed {}", seed);
+  assert_eq!(restored.doc_type, doc.doc_type, "seed {}", seed);
+  assert_eq!(restored.metadata.title, doc.metadata.title, "seed {}", seed);
+  assert_eq!(
+    restored.metadata.description, doc.metadata.description,
+    "seed {}",
+    seed
+  );
+  assert_eq!(
+    restored.metadata.glossary, doc.metadata.glossary,
+    "seed {}",
+    seed
+  );
+  assert_eq!(restored.metadata.date, doc.metadata.date, "seed {}", seed);
+  assert_eq!(
+    restored.metadata.updated, doc.metadata.updated,
+    "seed {}",
+    seed
+  );
+  assert_eq!(
+    restored.metadata.authors, doc.metadata.authors,
+    "seed {}",
+    seed
+  );
+  assert_eq!(
+    restored.metadata.total_lines, doc.metadata.total_lines,
+    "seed {}",
+    seed
+  );
+  assert_eq!(
+    restored.metadata.total_nodes, doc.metadata.total_nodes,
+    "seed {}",
+    seed
+  );
+  assert_same_nodes(&doc.nodes, &restored.nodes, "", seed);
+}
+
+fn check_json(doc: &Document, seed: u64) {
+  let json = Json::parse(&to_json(doc)).unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+  let keys: Vec<&str> = json.object().iter().map(|(k, _)| k.as_str()).collect();
+  assert_eq!(
+    keys,
+    [
+      "schema_version",
+      "source_path",
+      "doc_type",
+      "metadata",
+      "nodes"
+    ],
+    "seed {}",
+    seed
+  );
+  assert_eq!(
+    json.get("schema_version"),
+    Some(&Json::Num(SCHEMA_VERSION.to_string()))
+  );
+  assert_eq!(
+    json.get("source_path"),
+    Some(&Json::Str(doc.source_path.clone())),
+    "seed {}",
+    seed
+  );
+  check_json_nodes(&doc.nodes, json.get("nodes").unwrap().array(), seed);
+}
+
+fn check_json_nodes(nodes: &[Node], json: &[Json], seed: u64) {
+  assert_eq!(nodes.len(), json.len(), "seed {}", seed);
+  for (node, value) in nodes.iter().zip(json) {
+    let name = node.kind.name();
+    for (key, _) in value.object() {
+      assert!(
+        ["kind", "span", "hash", "children"].contains(&key.as_str()),
+        "seed {}: {} node has a {:?} key",
+        seed,
+        name,
+        key
+      );
+    }
+    assert_eq!(
+      value.get("hash"),
+      node.hash.map(|h| Json::Str(format!("{:016x}", h))).as_ref(),
+      "seed {}",
+      seed
+    );
+    let kind = value.get("kind").unwrap();
+    assert_eq!(
+      kind.get("type"),
+      Some(&Json::Str(name.to_string())),
+      "seed {}",
+      seed
+    );
+    check_kind_schema(name, kind, seed);
+
+    let mut leaves = Vec::new();
+    kind.leaves(&mut leaves);
+    for expected in fields(&node.kind) {
+      assert!(
+        leaves.contains(&expected.as_str()),
+        "seed {}: {} field {:?} missing from JSON",
+        seed,
+        name,
+        expected
+      );
+    }
+    let children = value.get("children").map_or(&[][..], Json::array);
+    check_json_nodes(&node.children, children, seed);
+  }
+}
+
+/// Check a `kind` object against the schema's fields for `name`.
+fn check_kind_schema(name: &str, kind: &Json, seed: u64) {
+  let fields = KINDS
+    .iter()
+    .find(|(n, _)| *n == name)
+    .unwrap_or_else(|| panic!("{} is missing from the schema", name))
+    .1;
+  for field in fields.iter().filter(|f| f.required) {
+    assert!(
+      kind.get(field.name).is_some(),
+      "seed {}: {} without required {}",
+      seed,
+      name,
+      field.name
+    );
+  }
+  for (key, value) in kind.object().iter().skip(1) {
+    let field = fields
+      .iter()
+      .find(|f| f.name == key)
+      .unwrap_or_else(|| panic!("seed {}: {}.{} is not in the schema", seed, name, key));
+    let conforms = match (field.ty, value) {
+      (FieldType::Str, Json::Str(_)) => true,
+      (FieldType::Int, Json::Num(n)) => n.bytes().all(|b| b.is_ascii_digit()),
+      (FieldType::Bool, Json::Bool(_)) => true,
+      (FieldType::Enum(values), Json::Str(s)) => values.contains(&s.as_str()),
+      (FieldType::Strings, Json::Arr(items)) => items.iter().all(|i| matches!(i, Json::Str(_))),
+      (FieldType::Ranges, Json::Arr(items)) => items
+        .iter()
+        .all(|i| matches!(i, Json::Arr(pair) if pair.len() == 2)),
+      (FieldType::Map, Json::Obj(entries)) => {
+        entries.iter().all(|(_, v)| matches!(v, Json::Str(_)))
+      }
+      (FieldType::Span, Json::Obj(entries)) => {
+        entries.len() == 4 && entries.iter().all(|(_, v)| matches!(v, Json::Num(_)))
+      }
+      _ => false,
+    };
+    assert!(
+      conforms,
+      "seed {}: {}.{} = {:?} is not a {:?}",
+      seed, name, key, value, field.ty
+    );
+  }
+}
+
+/// Just enough of a JSON parser to read the output back. Numbers stay as
+/// their text.
+#[derive(Debug, PartialEq)]
+enum Json {
+  Null,
+  Bool(bool),
+  Num(String),
+  Str(String),
+  Arr(Vec<Json>),
+  Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+  fn parse(text: &str) -> Result<Json, String> {
+    let mut chars = text.chars().peekable();
+    let value = parse_value(&mut chars)?;
+    skip_ws(&mut chars);
+    match chars.next() {
+      None => Ok(value),
+      Some(c) => Err(format!("trailing {:?}", c)),
+    }
+  }
+
+  fn object(&self) -> &[(String, Json)] {
+    match self {
+      Json::Obj(entries) => entries,
+      other => panic!("expected an object, got {:?}", other),
+    }
+  }
+
+  fn array(&self) -> &[Json] {
+    match self {
+      Json::Arr(items) => items,
+      other => panic!("expected an array, got {:?}", other),
+    }
+  }
+
+  fn get(&self, key: &str) -> Option<&Json> {
+    self.object().iter().find(|(k, _)| k == key).map(|(_, v)| v)
+  }
+
+  /// Strings, numbers and object keys anywhere below this value.
+  fn leaves<'a>(&'a self, out: &mut Vec<&'a str>) {
+    match self {
+      Json::Num(s) | Json::Str(s) => out.push(s),
+      Json::Arr(items) => items.iter().for_each(|i| i.leaves(out)),
+      Json::Obj(entries) => {
+        for (k, v) in entries {
+          out.push(k);
+          v.leaves(out);
+        }
+      }
+      Json::Null | Json::Bool(_) => {}
+    }
+  }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_ws(chars: &mut Chars) {
+  while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut Chars, word: &str) -> Result<(), String> {
+  for want in word.chars() {
+    if chars.next() != Some(want) {
+      return Err(format!("expected {}", word));
+    }
+  }
+  Ok(())
+}
+
+fn parse_value(chars: &mut Chars) -> Result<Json, String> {
+  skip_ws(chars);
+  match chars.peek().copied() {
+    Some('{') => {
+      chars.next();
+      let mut entries = Vec::new();
+      skip_ws(chars);
+      if chars.next_if_eq(&'}').is_some() {
+        return Ok(Json::Obj(entries));
+      }
+      loop {
+        skip_ws(chars);
+        let key = parse_string(chars)?;
+        skip_ws(chars);
+        expect(chars, ":")?;
+        entries.push((key, parse_value(chars)?));
+        skip_ws(chars);
+        match chars.next() {
+          Some(',') => continue,
+          Some('}') => return Ok(Json::Obj(entries)),
+          c => return Err(format!("expected , or }} in object, got {:?}", c)),
+        }
+      }
+    }
+    Some('[') => {
+      chars.next();
+      let mut items = Vec::new();
+      skip_ws(chars);
+      if chars.next_if_eq(&']').is_some() {
+        return Ok(Json::Arr(items));
+      }
+      loop {
+        items.push(parse_value(chars)?);
+        skip_ws(chars);
+        match chars.next() {
+          Some(',') => continue,
+          Some(']') => return Ok(Json::Arr(items)),
+          c => return Err(format!("expected , or ] in array, got {:?}", c)),
+        }
+      }
+    }
+    Some('"') => parse_string(chars).map(Json::Str),
+    Some('t') => expect(chars, "true").map(|_| Json::Bool(true)),
+    Some('f') => expect(chars, "false").map(|_| Json::Bool(false)),
+    Some('n') => expect(chars, "null").map(|_| Json::Null),
+    Some(c) if c == '-' || c.is_ascii_digit() => {
+      let mut n = String::new();
+      while let Some(c) = chars.next_if(|c| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+        n.push(c);
+      }
+      Ok(Json::Num(n))
+    }
+    c => Err(format!("unexpected {:?}", c)),
+  }
+}
+
+fn parse_string(chars: &mut Chars) -> Result<String, String> {
+  expect(chars, "\"")?;
+  let mut s = String::new();
+  loop {
+    match chars.next() {
+      Some('"') => return Ok(s),
+      Some('\\') => match chars.next() {
+        Some('"') => s.push('"'),
+        Some('\\') => s.push('\\'),
+        Some('/') => s.push('/'),
+        Some('n') => s.push('\n'),
+        Some('r') => s.push('\r'),
+        Some('t') => s.push('\t'),
+        Some('b') => s.push('\u{8}'),
+        Some('f') => s.push('\u{c}'),
+        Some('u') => {
+          let hex: String = chars.by_ref().take(4).collect();
+          let code = u32::from_str_radix(&hex, 16).map_err(|_| format!("bad \\u{}", hex))?;
+          s.push(char::from_u32(code).ok_or_else(|| format!("bad \\u{}", hex))?);
+        }
+        c => return Err(format!("bad escape {:?}", c)),
+      },
+      Some(c) if c.is_control() && c != '\u{7f}' => {
+        return Err(format!("unescaped control character {:?}", c))
+      }
+      Some(c) => s.push(c),
+      None => return Err("unterminated string".to_string()),
+    }
+  }
+}
+
+#[test]
+fn test_every_kind_covered() {
+  let doc = every_kind_document(1);
+  let names: BTreeSet<&str> = doc.nodes.iter().map(|n| n.kind.name()).collect();
+  assert_eq!(names.len(), KIND_COUNT as usize, "duplicate generator case");
+}
+
+#[test]
+fn test_dast_roundtrip_every_kind() {
+  for seed in 1..=32 {
+    check_dast(&every_kind_document(seed), seed);
+  }
+}
+
+#[test]
+fn test_dast_roundtrip_random() {
+  for seed in 1..=200 {
+    check_dast(&random_document(seed), seed);
+  }
+}
+
+#[test]
+fn test_json_every_kind() {
+  for seed in 1..=32 {
+    check_json(&every_kind_document(seed), seed);
+  }
+}
+
+#[test]
+fn test_json_random() {
+  for seed in 1..=200 {
+    check_json(&random_document(seed), seed);
+  }
+}
+
+#[test]
+fn test_dast_roundtrip_reads_strictly_without_unknown() {
+  // The strict reader accepts everything the generator makes except
+  // `Unknown`, so the forward-compatible mode isn't hiding anything
+  let mut doc = every_kind_document(7);
+  doc
+    .nodes
+    .retain(|n| !matches!(n.kind, NodeKind::Unknown { .. }));
+  let restored = read_dast(&write_dast(&doc).unwrap()).unwrap();
+  assert_same_nodes(&doc.nodes, &restored.nodes, "", 7);
+}
+
+#[test]
+fn test_schema_parses() {
+  let schema = Json::parse(&json_schema()).unwrap();
+  let defs = schema.get("$defs").unwrap();
+  for (name, _) in KINDS {
+    assert_eq!(
+      defs
+        .get(name)
+        .and_then(|d| d.get("properties")?.get("type")?.get("const")),
+      Some(&Json::Str(name.to_string()))
+    );
+  }
+  assert_eq!(
+    defs
+      .get("kind")
+      .unwrap()
+      .get("oneOf")
+      .unwrap()
+      .array()
+      .len(),
+    KIND_COUNT as usize
+  );
+}
@@ -0,0 +1,298 @@
+//! Reading-order export (`-f segments`): the document as a flat list of
+//! what a screen reader or text-to-speech pipeline would voice, in order.
+//!
+//! ```json
+//! {"source_path":"guide.md","segments":[
+//!   {"id":"heading-312b17c4","type":"heading","level":1,"text":"Guide"},
+//!   {"id":"paragraph-790f8f2a","type":"paragraph","text":"Install it first."},
+//!   {"id":"image-f247012b","type":"image","text":"Setup screen"}]}
+//! ```
+//!
+//! Each segment's `id` comes from its type and text, so it stays the same
+//! when other parts of the document change; repeated segments get `-2`,
+//! `-3` and so on. Markup, HTML, comments, diagrams and frontmatter are
+//! not voiced.
+
+use super::esc;
+use super::fnv;
+use crate::ast::{Document, Node, NodeKind};
+
+use std::collections::HashMap;
+
+/// One voiced unit.
+struct Segment {
+  kind: &'static str,
+  text: String,
+  /// Extra fields, as (key, JSON value)
+  fields: Vec<(&'static str, String)>,
+}
+
+/// Where in the block structure the walk is.
+#[derive(Clone, Copy, Default)]
+struct Context {
+  /// Nesting depth of lists, 1 for a top-level list
+  list_depth: usize,
+  ordered: bool,
+  /// Inside a block quote
+  quote: bool,
+}
+
+/// Render the document's reading order as JSON.
+pub fn to_segments(doc: &Document) -> String {
+  let mut segments = Vec::new();
+  walk(&doc.nodes, Context::default(), &mut segments);
+
+  let mut seen: HashMap<String, usize> = HashMap::new();
+  let mut s = String::with_capacity(64 + segments.len() * 96);
+  s.push_str(&format!(
+    "{{\"source_path\":\"{}\",\"segments\":[",
+    esc(&doc.source_path)
+  ));
+  for (i, segment) in segments.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    let key = format!("{}\0{}", segment.kind, segment.text);
+    let base = format!("{}-{:08x}", segment.kind, fnv::hash(key.as_bytes()) >> 32);
+    let count = seen.entry(base.clone()).or_insert(0);
+    *count += 1;
+    let id = match *count {
+      1 => base,
+      n => format!("{}-{}", base, n),
+    };
+    s.push_str(&format!(
+      "{{\"id\":\"{}\",\"type\":\"{}\"",
+      id, segment.kind
+    ));
+    for (key, value) in &segment.fields {
+      s.push_str(&format!(",\"{}\":{}", key, value));
+    }
+    s.push_str(&format!(",\"text\":\"{}\"}}", esc(&segment.text)));
+  }
+  s.push_str("]}");
+  s
+}
+
+fn walk(nodes: &[Node], cx: Context, out: &mut Vec<Segment>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Heading { level, .. } => {
+        push_block(node, "heading", vec![("level", level.to_string())], out)
+      }
+      NodeKind::Paragraph => {
+        let kind = if cx.quote { "quote" } else { "paragraph" };
+        push_block(node, kind, Vec::new(), out);
+      }
+      NodeKind::List { ordered, .. } => walk(
+        &node.children,
+        Context {
+          list_depth: cx.list_depth + 1,
+          ordered: *ordered,
+          ..cx
+        },
+        out,
+      ),
+      NodeKind::ListItem { .. } => {
+        let (first, rest) = match node.children.split_first() {
+          Some((first, rest)) if first.kind == NodeKind::Paragraph => (Some(first), rest),
+          _ => (None, &node.children[..]),
+        };
+        let fields = vec![
+          ("depth", cx.list_depth.to_string()),
+          ("ordered", cx.ordered.to_string()),
+        ];
+        match first {
+          Some(paragraph) => push_block(paragraph, "list_item", fields, out),
+          None => out.push(Segment {
+            kind: "list_item",
+            text: String::new(),
+            fields,
+          }),
+        }
+        walk(rest, cx, out);
+      }
+      NodeKind::BlockQuote => walk(&node.children, Context { quote: true, ..cx }, out),
+      NodeKind::Alert { alert_type } => {
+        out.push(Segment {
+          kind: "alert",
+          text: alert_type.to_string(),
+          fields: Vec::new(),
+        });
+        walk(&node.children, cx, out);
+      }
+      NodeKind::CodeBlock { language, .. }
+      | NodeKind::FencedCodeBlock { language, .. }
+      | NodeKind::CodeBlockExt { language, .. } => push_code(node, language.as_deref(), out),
+      NodeKind::IndentedCodeBlock => push_code(node, None, out),
+      NodeKind::MathBlock { content, .. } => out.push(Segment {
+        kind: "math",
+        text: content.trim().to_string(),
+        fields: Vec::new(),
+      }),
+      NodeKind::DefinitionTerm => push_block(node, "term", Vec::new(), out),
+      NodeKind::Frontmatter { .. }
+      | NodeKind::HtmlBlock { .. }
+      | NodeKind::Comment { .. }
+      | NodeKind::DiagramBlock { .. }
+      | NodeKind::LinkDefinition { .. }
+      | NodeKind::ThematicBreak
+      | NodeKind::Toc => {}
+      _ if !node.children.is_empty() && node.children.iter().all(|c| c.kind.is_inline()) => {
+        push_block(node, "paragraph", Vec::new(), out)
+      }
+      _ => walk(&node.children, cx, out),
+    }
+  }
+}
+
+/// A block's text, then an `image` segment for each image in it with alt
+/// text. Blocks with nothing to voice are skipped.
+fn push_block(
+  node: &Node,
+  kind: &'static str,
+  fields: Vec<(&'static str, String)>,
+  out: &mut Vec<Segment>,
+) {
+  let mut text = String::new();
+  let mut images = Vec::new();
+  inline_text(&node.children, &mut text, &mut images);
+  let text = collapse(&text);
+  if !text.is_empty() || kind == "list_item" {
+    out.push(Segment { kind, text, fields });
+  }
+  out.extend(images.into_iter().map(|alt| Segment {
+    kind: "image",
+    text: alt,
+    fields: Vec::new(),
+  }));
+}
+
+fn push_code(node: &Node, language: Option<&str>, out: &mut Vec<Segment>) {
+  let text: String = node
+    .children
+    .iter()
+    .filter_map(|child| match &child.kind {
+      NodeKind::Text { content } => Some(content.as_str()),
+      _ => None,
+    })
+    .collect();
+  let fields = language
+    .filter(|lang| !lang.is_empty())
+    .map(|lang| vec![("language", format!("\"{}\"", esc(lang)))])
+    .unwrap_or_default();
+  out.push(Segment {
+    kind: "code",
+    text: text.trim_end().to_string(),
+    fields,
+  });
+}
+
+/// What a reader says for inline content; images go to `images` instead.
+fn inline_text(nodes: &[Node], text: &mut String, images: &mut Vec<String>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Text { content }
+      | NodeKind::CodeSpan { content }
+      | NodeKind::Code { content }
+      | NodeKind::MathInline { content } => text.push_str(content),
+      NodeKind::SoftBreak | NodeKind::HardBreak => text.push(' '),
+      NodeKind::AutoLink { url } | NodeKind::AutoUrl { url } => text.push_str(url),
+      NodeKind::Mention { username } => {
+        text.push('@');
+        text.push_str(username);
+      }
+      NodeKind::IssueReference { number } => text.push_str(&format!("#{}", number)),
+      NodeKind::Image { alt, .. } => {
+        let alt = collapse(alt);
+        if !alt.is_empty() {
+          images.push(alt);
+        }
+      }
+      NodeKind::HtmlInline { .. }
+      | NodeKind::HtmlTag { .. }
+      | NodeKind::Comment { .. }
+      | NodeKind::Emoji { .. }
+      | NodeKind::FootnoteReference { .. }
+      | NodeKind::TaskListMarker { .. } => {}
+      _ => inline_text(&node.children, text, images),
+    }
+  }
+}
+
+fn collapse(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn segments(input: &str) -> String {
+    let mut doc = MarkdownParser::new(input).parse();
+    doc.source_path = "a.md".to_string();
+    to_segments(&doc)
+  }
+
+  /// (type, text) of each segment.
+  fn voiced(input: &str) -> Vec<(String, String)> {
+    let json = segments(input);
+    json
+      .split("{\"id\":")
+      .skip(1)
+      .map(|seg| {
+        let kind = seg.split("\"type\":\"").nth(1).unwrap();
+        let text = seg.split("\"text\":\"").nth(1).unwrap();
+        (
+          kind[..kind.find('"').unwrap()].to_string(),
+          text[..text.rfind('"').unwrap()].to_string(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_reading_order() {
+    let order = voiced(
+      "---\ntitle: x\n---\n# Guide\n\nRead *this* <b>now</b> ![Setup screen](s.png)\n\n\
+       - one\n\n> quoted\n\n```sh\nrun\n```\n\n<!-- note -->\n\n> [!TIP]\n> hint\n",
+    );
+    let expected = [
+      ("heading", "Guide"),
+      ("paragraph", "Read this now"),
+      ("image", "Setup screen"),
+      ("list_item", "one"),
+      ("quote", "quoted"),
+      ("code", "run"),
+      ("alert", "TIP"),
+      ("paragraph", "hint"),
+    ];
+    let expected: Vec<(String, String)> = expected
+      .iter()
+      .map(|(k, t)| (k.to_string(), t.to_string()))
+      .collect();
+    assert_eq!(order, expected);
+  }
+
+  #[test]
+  fn test_fields_and_ids() {
+    let json = segments("## Same\n\n## Same\n\n- item\n");
+    assert!(json.starts_with("{\"source_path\":\"a.md\",\"segments\":["));
+    assert!(json.contains("\"type\":\"heading\",\"level\":2,\"text\":\"Same\""));
+    assert!(json.contains("\"type\":\"list_item\",\"depth\":1,\"ordered\":false"));
+    let id = |n: usize| {
+      let seg = json.split("{\"id\":\"").nth(n).unwrap();
+      seg[..seg.find('"').unwrap()].to_string()
+    };
+    assert!(id(1).starts_with("heading-"));
+    assert_eq!(id(2), format!("{}-2", id(1)));
+    // Ids depend on content only
+    assert_eq!(
+      segments("Intro\n\n## Same\n")
+        .split("{\"id\":\"")
+        .nth(2)
+        .unwrap()[..id(1).len()],
+      id(1)
+    );
+  }
+}
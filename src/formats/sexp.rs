@@ -0,0 +1,395 @@
+//! S-expression AST dump: one node per line, no spans, meant for diffing.
+//!
+//! ```text
+//! (document markdown
+//!   (heading 1 (text "Title"))
+//!   (paragraph
+//!     (text "Some ")
+//!     (emphasis (text "text"))))
+//! ```
+//!
+//! Each node is `(kind values... :key value... children...)`: required
+//! fields come positionally, optional and secondary fields as keywords
+//! (omitted when unset). A node whose children are all leaves stays on one
+//! line.
+
+use crate::ast::*;
+use std::fmt::Display;
+
+/// Render the document as an s-expression.
+pub fn to_sexp(doc: &Document) -> String {
+  let mut out = String::with_capacity(4096);
+  out.push_str("(document ");
+  out.push_str(doc.doc_type.name());
+  for node in &doc.nodes {
+    out.push_str("\n  ");
+    write_node(&mut out, node, 1);
+  }
+  out.push_str(")\n");
+  out
+}
+
+fn write_node(out: &mut String, node: &Node, depth: usize) {
+  out.push('(');
+  out.push_str(&kebab(node.kind.name()));
+  for atom in atoms(&node.kind).0 {
+    out.push(' ');
+    out.push_str(&atom);
+  }
+  if node.children.iter().all(|c| c.children.is_empty()) {
+    for child in &node.children {
+      out.push(' ');
+      write_node(out, child, depth + 1);
+    }
+  } else {
+    for child in &node.children {
+      out.push('\n');
+      out.push_str(&"  ".repeat(depth + 1));
+      write_node(out, child, depth + 1);
+    }
+  }
+  out.push(')');
+}
+
+/// Field values of one node, already formatted.
+#[derive(Default)]
+struct Atoms(Vec<String>);
+
+impl Atoms {
+  fn str(mut self, s: &str) -> Self {
+    self.0.push(quote(s));
+    self
+  }
+
+  fn sym(mut self, value: impl Display) -> Self {
+    self.0.push(value.to_string());
+    self
+  }
+
+  fn key(mut self, key: &str, value: impl Display) -> Self {
+    self.0.push(format!(":{} {}", key, value));
+    self
+  }
+
+  fn opt(self, key: &str, value: &Option<String>) -> Self {
+    match value {
+      Some(v) => self.key(key, quote(v)),
+      None => self,
+    }
+  }
+
+  /// `:type-source` for types that did not come from the comment.
+  fn source(self, source: &Option<TypeSource>) -> Self {
+    match source {
+      Some(TypeSource::Annotation) => self.key("type-source", "annotation"),
+      _ => self,
+    }
+  }
+
+  fn pairs(self, key: &str, pairs: &[(String, String)]) -> Self {
+    if pairs.is_empty() {
+      return self;
+    }
+    let list: Vec<String> = pairs
+      .iter()
+      .map(|(k, v)| format!("({} {})", quote(k), quote(v)))
+      .collect();
+    self.key(key, format!("({})", list.join(" ")))
+  }
+
+  fn ranges(self, key: &str, ranges: &[(u32, u32)]) -> Self {
+    if ranges.is_empty() {
+      return self;
+    }
+    let list: Vec<String> = ranges
+      .iter()
+      .map(|(s, e)| format!("({} {})", s, e))
+      .collect();
+    self.key(key, format!("({})", list.join(" ")))
+  }
+}
+
+fn atoms(kind: &NodeKind) -> Atoms {
+  let a = Atoms::default();
+  match kind {
+    NodeKind::Heading {
+      level,
+      id,
+      attributes,
+      ..
+    } => a.sym(level).opt("id", id).pairs("attrs", attributes),
+    NodeKind::CodeBlock { language, info } => a.opt("lang", language).opt("info", info),
+    NodeKind::FencedCodeBlock {
+      language,
+      info,
+      attributes,
+    } => a
+      .opt("lang", language)
+      .opt("info", info)
+      .pairs("attrs", attributes),
+    NodeKind::HtmlBlock { block_type } => a.sym(block_type),
+    NodeKind::List {
+      ordered,
+      start,
+      tight,
+    } => {
+      let a = a.sym(if *ordered { "ordered" } else { "bullet" });
+      let a = match start {
+        Some(n) => a.key("start", n),
+        None => a,
+      };
+      if *tight {
+        a
+      } else {
+        a.sym(":loose")
+      }
+    }
+    NodeKind::ListItem { marker, checked } => {
+      let a = match marker {
+        ListMarker::Bullet(c) => a.str(&c.to_string()),
+        ListMarker::Ordered(c) => a.str(&(*c as char).to_string()),
+      };
+      match checked {
+        Some(c) => a.key("checked", c),
+        None => a,
+      }
+    }
+    NodeKind::TableCell {
+      alignment,
+      is_header,
+      colspan,
+    } => {
+      let a = a.sym(kebab(&format!("{:?}", alignment)));
+      let a = if *is_header { a.sym(":header") } else { a };
+      if *colspan > 1 {
+        a.key("colspan", colspan)
+      } else {
+        a
+      }
+    }
+    NodeKind::Text { content }
+    | NodeKind::Code { content }
+    | NodeKind::CodeSpan { content }
+    | NodeKind::HtmlInline { content }
+    | NodeKind::MathInline { content }
+    | NodeKind::DocExample { content }
+    | NodeKind::DocDescription { content }
+    | NodeKind::DocRemarks { content } => a.str(content),
+    NodeKind::MathBlock { content, display } => {
+      let a = a.str(content);
+      if *display {
+        a.sym(":display")
+      } else {
+        a
+      }
+    }
+    NodeKind::DiagramBlock { engine, content } => a.sym(engine).str(content),
+    NodeKind::Comment { content, block } => {
+      let a = a.str(content);
+      if *block {
+        a.sym(":block")
+      } else {
+        a
+      }
+    }
+    NodeKind::Link {
+      url,
+      title,
+      ref_type,
+      attributes,
+    } => a
+      .str(url)
+      .opt("title", title)
+      .key("ref", kebab(&format!("{:?}", ref_type)))
+      .pairs("attrs", attributes),
+    NodeKind::Image {
+      url,
+      alt,
+      title,
+      attributes,
+    } => a
+      .str(url)
+      .str(alt)
+      .opt("title", title)
+      .pairs("attrs", attributes),
+    NodeKind::AutoLink { url } | NodeKind::AutoUrl { url } => a.str(url),
+    NodeKind::HtmlTag {
+      name,
+      tag,
+      attributes,
+    } => a
+      .str(name)
+      .sym(kebab(&format!("{:?}", tag)))
+      .pairs("attrs", attributes),
+    NodeKind::LinkReference { label, ref_type } => {
+      a.str(label).key("ref", kebab(&format!("{:?}", ref_type)))
+    }
+    NodeKind::LinkDefinition { label, url, title } => a.str(label).str(url).opt("title", title),
+    NodeKind::FootnoteReference { label, index }
+    | NodeKind::FootnoteDefinition { label, index } => a.str(label).sym(index),
+    NodeKind::TaskListMarker { checked } => a.sym(checked),
+    NodeKind::Emoji { shortcode } => a.str(shortcode),
+    NodeKind::Mention { username } => a.str(username),
+    NodeKind::IssueReference { number } => a.sym(number),
+    NodeKind::DocComment { style } => a.sym(kebab(&format!("{:?}", style))),
+    NodeKind::DocTag { name, content } | NodeKind::DocInlineTag { name, content } => {
+      a.str(name).opt("content", content)
+    }
+    NodeKind::DocParam {
+      name,
+      param_type,
+      description,
+      type_source,
+    } => a
+      .str(name)
+      .opt("type", param_type)
+      .opt("description", description)
+      .source(type_source),
+    NodeKind::DocProperty {
+      name,
+      prop_type,
+      description,
+    } => a
+      .str(name)
+      .opt("type", prop_type)
+      .opt("description", description),
+    NodeKind::DocReturn {
+      return_type,
+      description,
+      type_source,
+    } => a
+      .opt("type", return_type)
+      .opt("description", description)
+      .source(type_source),
+    NodeKind::DocThrows {
+      exception_type,
+      description,
+    } => a.str(exception_type).opt("description", description),
+    NodeKind::DocSee { reference } => a.str(reference),
+    NodeKind::DocDeprecated { message, version } => {
+      a.opt("message", message).opt("version", version)
+    }
+    NodeKind::DocSince { version } | NodeKind::DocVersion { version } => a.str(version),
+    NodeKind::DocAuthor { name }
+    | NodeKind::DocCallback { name }
+    | NodeKind::DocModifier { name }
+    | NodeKind::Tab { name } => a.str(name),
+    NodeKind::Abbreviation { title } => a.str(title),
+    NodeKind::DocType { type_expr } => a.str(type_expr),
+    NodeKind::DocTypedef { name, type_expr } => a.str(name).opt("type", type_expr),
+    NodeKind::DocTypeParam { name, description } => a.str(name).opt("description", description),
+    NodeKind::DocDefaultValue { value } => a.str(value),
+    NodeKind::DocReleaseTag { stage } => a.sym(kebab(&format!("{:?}", stage))),
+    NodeKind::DocDoctest { source, expected } => a.str(source).opt("expected", expected),
+    NodeKind::Frontmatter { format, content } => {
+      a.sym(kebab(&format!("{:?}", format))).str(content)
+    }
+    NodeKind::Footnote { label } => a.str(label),
+    NodeKind::Alert { alert_type } => a.sym(alert_type.to_string().to_lowercase()),
+    NodeKind::Step { number, title } => a.sym(number).opt("title", title),
+    NodeKind::Tabs { names } => names.iter().fold(a, |a, name| a.str(name)),
+    NodeKind::CodeBlockExt {
+      language,
+      highlight,
+      plusdiff,
+      minusdiff,
+      linenumbers,
+      invalid_ranges,
+      attributes,
+    } => {
+      let a = a
+        .opt("lang", language)
+        .ranges("highlight", highlight)
+        .ranges("plusdiff", plusdiff)
+        .ranges("minusdiff", minusdiff);
+      let a = if *linenumbers {
+        a.sym(":linenumbers")
+      } else {
+        a
+      };
+      a.pairs("invalid", invalid_ranges)
+        .pairs("attrs", attributes)
+    }
+    NodeKind::CustomElement { name, attributes } => a.str(name).pairs("attrs", attributes),
+    NodeKind::Include { src } => a.str(src),
+    NodeKind::Unknown { tag } => a.sym(tag),
+    NodeKind::Document
+    | NodeKind::Paragraph
+    | NodeKind::BlockQuote
+    | NodeKind::IndentedCodeBlock
+    | NodeKind::ThematicBreak
+    | NodeKind::Table
+    | NodeKind::TableHead
+    | NodeKind::TableBody
+    | NodeKind::TableRow
+    | NodeKind::Emphasis
+    | NodeKind::Strong
+    | NodeKind::Strikethrough
+    | NodeKind::HardBreak
+    | NodeKind::SoftBreak
+    | NodeKind::Footnotes
+    | NodeKind::DefinitionList
+    | NodeKind::DefinitionTerm
+    | NodeKind::DefinitionDescription
+    | NodeKind::Steps
+    | NodeKind::Toc => a,
+  }
+}
+
+/// `FencedCodeBlock` → `fenced-code-block`, `JSDoc` → `js-doc`.
+fn kebab(name: &str) -> String {
+  let chars: Vec<char> = name.chars().collect();
+  let mut out = String::with_capacity(name.len() + 4);
+  for (i, &c) in chars.iter().enumerate() {
+    if i > 0 && c.is_ascii_uppercase() {
+      let prev = chars[i - 1];
+      let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+      if !prev.is_ascii_uppercase() || next_lower {
+        out.push('-');
+      }
+    }
+    out.push(c.to_ascii_lowercase());
+  }
+  out
+}
+
+fn quote(s: &str) -> String {
+  let mut out = String::with_capacity(s.len() + 2);
+  out.push('"');
+  for ch in s.chars() {
+    match ch {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\t' => out.push_str("\\t"),
+      '\r' => out.push_str("\\r"),
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_layout() {
+    let doc = MarkdownParser::new("# Title\n\nSome *text* \"q\"").parse();
+    assert_eq!(
+      to_sexp(&doc),
+      "(document markdown\n  (heading 1 (text \"Title\"))\n  (paragraph\n    (text \"Some \")\n    (emphasis (text \"text\"))\n    (text \" \\\"q\\\"\")))\n"
+    );
+  }
+
+  #[test]
+  fn test_kebab() {
+    assert_eq!(kebab("FencedCodeBlock"), "fenced-code-block");
+    assert_eq!(kebab("Text"), "text");
+    assert_eq!(kebab("JSDoc"), "js-doc");
+    assert_eq!(kebab("PyDocGoogle"), "py-doc-google");
+    assert_eq!(kebab("HtmlTag"), "html-tag");
+  }
+}
@@ -66,10 +66,26 @@ pub fn node_kind_u8(k: &NodeKind) -> u8 {
     NodeKind::AutoUrl { .. } => 59,
     NodeKind::Alert { .. } => 60,
     NodeKind::Steps => 61,
-    NodeKind::Step => 62,
+    NodeKind::Step { .. } => 62,
     NodeKind::Toc => 63,
     NodeKind::Tabs { .. } => 64,
     NodeKind::CodeBlockExt { .. } => 65,
+    NodeKind::DocTypeParam { .. } => 66,
+    NodeKind::DocRemarks { .. } => 67,
+    NodeKind::DocDefaultValue { .. } => 68,
+    NodeKind::DocReleaseTag { .. } => 69,
+    NodeKind::DocModifier { .. } => 70,
+    NodeKind::DocInlineTag { .. } => 71,
+    NodeKind::DocDoctest { .. } => 72,
+    NodeKind::CustomElement { .. } => 73,
+    NodeKind::Include { .. } => 74,
+    NodeKind::Tab { .. } => 75,
+    NodeKind::HtmlTag { .. } => 76,
+    NodeKind::Footnotes => 77,
+    NodeKind::Abbreviation { .. } => 78,
+    NodeKind::Comment { .. } => 79,
+    NodeKind::DiagramBlock { .. } => 80,
+    NodeKind::Unknown { tag } => *tag,
   }
 }
 
@@ -107,6 +123,7 @@ pub fn doc_style_u8(ds: &DocStyle) -> u8 {
     DocStyle::PyDoc => 2,
     DocStyle::PyDocGoogle => 3,
     DocStyle::PyDocNumpy => 4,
+    DocStyle::JavaDocMarkdown => 5,
   }
 }
 
@@ -117,5 +134,40 @@ pub fn alert_type_u8(at: &AlertType) -> u8 {
     AlertType::Important => 2,
     AlertType::Warning => 3,
     AlertType::Caution => 4,
+    AlertType::Other(_) => 5,
+  }
+}
+
+pub fn html_tag_kind_u8(kind: &HtmlTagKind) -> u8 {
+  match kind {
+    HtmlTagKind::Open => 0,
+    HtmlTagKind::Close => 1,
+    HtmlTagKind::SelfClosing => 2,
+  }
+}
+
+pub fn release_stage_u8(rs: &ReleaseStage) -> u8 {
+  match rs {
+    ReleaseStage::Alpha => 0,
+    ReleaseStage::Beta => 1,
+    ReleaseStage::Public => 2,
+    ReleaseStage::Internal => 3,
+    ReleaseStage::Experimental => 4,
+  }
+}
+
+pub fn type_source_u8(ts: &Option<TypeSource>) -> u8 {
+  match ts {
+    None => 0,
+    Some(TypeSource::Doc) => 1,
+    Some(TypeSource::Annotation) => 2,
+  }
+}
+
+pub fn frontmatter_format_u8(ff: &FrontmatterFormat) -> u8 {
+  match ff {
+    FrontmatterFormat::Yaml => 0,
+    FrontmatterFormat::Toml => 1,
+    FrontmatterFormat::Json => 2,
   }
 }
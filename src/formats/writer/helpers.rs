@@ -10,6 +10,16 @@ pub fn write_span<W: Write>(span: &Span, w: &mut W) -> io::Result<()> {
   w.write_all(&(span.column as u32).to_le_bytes())
 }
 
+pub fn write_opt_span<W: Write>(span: &Option<Span>, w: &mut W) -> io::Result<()> {
+  match span {
+    Some(span) => {
+      w.write_all(&[1])?;
+      write_span(span, w)
+    }
+    None => w.write_all(&[0]),
+  }
+}
+
 pub fn write_opt_u32<W: Write>(v: &Option<u32>, w: &mut W) -> io::Result<()> {
   match v {
     Some(n) => {
@@ -20,6 +30,26 @@ pub fn write_opt_u32<W: Write>(v: &Option<u32>, w: &mut W) -> io::Result<()> {
   }
 }
 
+pub fn write_opt_u64<W: Write>(v: &Option<u64>, w: &mut W) -> io::Result<()> {
+  match v {
+    Some(n) => {
+      w.write_all(&[1])?;
+      w.write_all(&n.to_le_bytes())
+    }
+    None => w.write_all(&[0]),
+  }
+}
+
+/// Count, then (start, end) pairs.
+pub fn write_ranges<W: Write>(ranges: &[(u32, u32)], w: &mut W) -> io::Result<()> {
+  w.write_all(&(ranges.len() as u32).to_le_bytes())?;
+  for (start, end) in ranges {
+    w.write_all(&start.to_le_bytes())?;
+    w.write_all(&end.to_le_bytes())?;
+  }
+  Ok(())
+}
+
 pub fn write_opt_bool<W: Write>(v: &Option<bool>, w: &mut W) -> io::Result<()> {
   match v {
     Some(b) => w.write_all(&[1, *b as u8]),
@@ -5,77 +5,204 @@ mod helpers;
 mod strings;
 
 use crate::ast::*;
-use std::collections::HashMap;
 use std::io::{self, Write};
 
-use super::{MAGIC, VERSION};
+use super::crc32::Crc32;
+use super::index::{DastIndex, IndexEntry, FLAG_INDEX};
+use super::{FLAG_INLINE_STRINGS, MAGIC, VERSION};
 use encode::*;
 use helpers::*;
+use strings::{inline, StringTable};
 
 /// Writes a Document to DAST binary format.
+///
+/// Nodes are encoded into an internal buffer while their strings are
+/// interned, then the header, string table and nodes go to the output in
+/// two writes, followed by the index if enabled and a CRC-32 of all of
+/// them. The buffers are kept, so reusing one writer across a run avoids
+/// reallocating them for every document.
 pub struct DastWriter {
-  strings: Vec<String>,
-  string_map: HashMap<String, u32>,
+  head: Vec<u8>,
+  body: Vec<u8>,
+  tail: Vec<u8>,
+  sorted_strings: bool,
+  index: bool,
 }
 
 impl DastWriter {
   pub fn new() -> Self {
     Self {
-      strings: Vec::new(),
-      string_map: HashMap::new(),
+      head: Vec::new(),
+      body: Vec::new(),
+      tail: Vec::new(),
+      sorted_strings: false,
+      index: false,
     }
   }
 
-  pub fn write<W: Write>(&mut self, doc: &Document, w: &mut W) -> io::Result<()> {
-    strings::collect_strings(&mut self.strings, &mut self.string_map, doc);
-    self.write_header(w)?;
-    self.write_string_table(w)?;
-    self.write_document(doc, w)
+  /// Sort the string table instead of numbering strings in order of first
+  /// use, so the same AST encodes to the same bytes even if a later
+  /// version walks it in another order.
+  pub fn with_sorted_strings(mut self, sorted: bool) -> Self {
+    self.sorted_strings = sorted;
+    self
   }
 
-  fn write_header<W: Write>(&self, w: &mut W) -> io::Result<()> {
-    w.write_all(MAGIC)?;
-    w.write_all(&[VERSION, 0])
+  /// End the file with the offsets of its top-level nodes and headings
+  /// (see [`DastIndex`]), so readers can decode one section alone.
+  pub fn with_index(mut self, index: bool) -> Self {
+    self.index = index;
+    self
   }
 
-  fn write_string_table<W: Write>(&self, w: &mut W) -> io::Result<()> {
-    w.write_all(&(self.strings.len() as u32).to_le_bytes())?;
-    self.strings.iter().try_for_each(|s| {
-      let b = s.as_bytes();
-      w.write_all(&(b.len() as u32).to_le_bytes())?;
-      w.write_all(b)
-    })
+  pub fn write<W: Write>(&mut self, doc: &Document, w: &mut W) -> io::Result<()> {
+    self.head.clear();
+    self.body.clear();
+    self.tail.clear();
+
+    let mut encoder = Encoder {
+      refs: self.sorted_strings.then(Vec::new),
+      index: self.index.then(Vec::new),
+      ..Encoder::default()
+    };
+    encoder.write_document(doc, &mut self.body)?;
+    if let Some(refs) = &encoder.refs {
+      let remap = encoder.strings.sort();
+      for &at in refs {
+        let slot = &mut self.body[at..at + 4];
+        let old = u32::from_le_bytes([slot[0], slot[1], slot[2], slot[3]]);
+        slot.copy_from_slice(&remap[old as usize].to_le_bytes());
+      }
+    }
+
+    self.head.extend_from_slice(MAGIC);
+    let mut flags = if self.index { FLAG_INDEX } else { 0 };
+    if encoder.inlined {
+      flags |= FLAG_INLINE_STRINGS;
+    }
+    self.head.extend_from_slice(&[VERSION, flags]);
+    encoder.strings.write_to(&mut self.head)?;
+
+    if let Some(mut entries) = encoder.index {
+      // Entries hold body offsets until the head's size is known
+      let base = self.head.len() as u32;
+      entries.iter_mut().for_each(|e| e.offset += base);
+      DastIndex { entries }.write_to(&mut self.tail);
+    }
+
+    let mut crc = Crc32::new();
+    crc.update(&self.head);
+    crc.update(&self.body);
+    crc.update(&self.tail);
+
+    w.write_all(&self.head)?;
+    w.write_all(&self.body)?;
+    w.write_all(&self.tail)?;
+    w.write_all(&crc.finish().to_le_bytes())
   }
+}
 
-  fn write_document<W: Write>(&self, doc: &Document, w: &mut W) -> io::Result<()> {
+/// Encodes one document, interning strings as they are reached.
+#[derive(Default)]
+struct Encoder<'a> {
+  strings: StringTable<'a>,
+  /// Body offsets of every string index, kept to renumber them when the
+  /// table is sorted
+  refs: Option<Vec<usize>>,
+  /// Top-level nodes and headings, at body offsets
+  index: Option<Vec<IndexEntry>>,
+  /// Number of the top-level node being written
+  top: u32,
+  /// Depth of the node being written, 0 at the top level
+  depth: usize,
+  /// A string was stored in place of a reference
+  inlined: bool,
+}
+
+impl<'a> Encoder<'a> {
+  fn write_document(&mut self, doc: &'a Document, w: &mut Vec<u8>) -> io::Result<()> {
     self.write_str(&doc.source_path, w)?;
     w.write_all(&[doc_type_u8(&doc.doc_type)])?;
     self.write_opt_str(&doc.metadata.title, w)?;
     self.write_opt_str(&doc.metadata.description, w)?;
+    self.write_pairs(&doc.metadata.glossary, w)?;
+    self.write_opt_str(&doc.metadata.date, w)?;
+    self.write_opt_str(&doc.metadata.updated, w)?;
+    w.write_all(&(doc.metadata.authors.len() as u32).to_le_bytes())?;
+    for author in &doc.metadata.authors {
+      self.write_str(&author.name, w)?;
+      self.write_opt_str(&author.email, w)?;
+      self.write_opt_str(&author.url, w)?;
+    }
     w.write_all(&(doc.metadata.total_lines as u32).to_le_bytes())?;
     w.write_all(&(doc.metadata.total_nodes as u32).to_le_bytes())?;
     w.write_all(&(doc.nodes.len() as u32).to_le_bytes())?;
-    doc.nodes.iter().try_for_each(|n| self.write_node(n, w))
+    for (i, node) in doc.nodes.iter().enumerate() {
+      self.top = i as u32;
+      self.write_node(node, w)?;
+    }
+    Ok(())
   }
 
-  fn write_node<W: Write>(&self, node: &Node, w: &mut W) -> io::Result<()> {
+  /// Tag, span, length-prefixed payload, then children. The length lets
+  /// readers skip payloads of tags they don't know.
+  fn write_node(&mut self, node: &'a Node, w: &mut Vec<u8>) -> io::Result<()> {
+    if let Some(index) = &mut self.index {
+      let level = match node.kind {
+        NodeKind::Heading { level, .. } => level,
+        _ => 0,
+      };
+      if self.depth == 0 || level > 0 {
+        index.push(IndexEntry {
+          offset: w.len() as u32,
+          node: self.top,
+          level,
+          nested: self.depth > 0,
+        });
+      }
+    }
     w.write_all(&[node_kind_u8(&node.kind)])?;
     write_span(&node.span, w)?;
+    let len_at = w.len();
+    w.extend_from_slice(&[0; 4]);
     self.write_kind_data(&node.kind, w)?;
+    // After the kind's fields, where readers that predate it skip it
+    write_opt_u64(&node.hash, w)?;
+    let len = (w.len() - len_at - 4) as u32;
+    w[len_at..len_at + 4].copy_from_slice(&len.to_le_bytes());
     w.write_all(&(node.children.len() as u32).to_le_bytes())?;
-    node.children.iter().try_for_each(|c| self.write_node(c, w))
+    self.depth += 1;
+    let written = node.children.iter().try_for_each(|c| self.write_node(c, w));
+    self.depth -= 1;
+    written
   }
 
-  fn write_kind_data<W: Write>(&self, kind: &NodeKind, w: &mut W) -> io::Result<()> {
+  fn write_kind_data(&mut self, kind: &'a NodeKind, w: &mut Vec<u8>) -> io::Result<()> {
     match kind {
-      NodeKind::Heading { level, id } => {
+      NodeKind::Heading {
+        level,
+        id,
+        attributes,
+        section,
+      } => {
         w.write_all(&[*level])?;
-        self.write_opt_str(id, w)
+        self.write_opt_str(id, w)?;
+        write_opt_span(section, w)?;
+        self.write_pairs(attributes, w)
       }
-      NodeKind::CodeBlock { language, info } | NodeKind::FencedCodeBlock { language, info } => {
+      NodeKind::CodeBlock { language, info } => {
         self.write_opt_str(language, w)?;
         self.write_opt_str(info, w)
       }
+      NodeKind::FencedCodeBlock {
+        language,
+        info,
+        attributes,
+      } => {
+        self.write_opt_str(language, w)?;
+        self.write_opt_str(info, w)?;
+        self.write_pairs(attributes, w)
+      }
       NodeKind::HtmlBlock { block_type } => w.write_all(&[*block_type]),
       NodeKind::List {
         ordered,
@@ -92,24 +219,45 @@ impl DastWriter {
       NodeKind::TableCell {
         alignment,
         is_header,
-      } => w.write_all(&[alignment_u8(alignment), *is_header as u8]),
+        colspan,
+      } => {
+        w.write_all(&[alignment_u8(alignment), *is_header as u8])?;
+        w.write_all(&colspan.to_le_bytes())
+      }
       NodeKind::Text { content }
       | NodeKind::Code { content }
       | NodeKind::CodeSpan { content }
-      | NodeKind::HtmlInline { content } => self.write_str(content, w),
+      | NodeKind::HtmlInline { content }
+      | NodeKind::MathInline { content } => self.write_str(content, w),
+      NodeKind::MathBlock { content, display } => {
+        self.write_str(content, w)?;
+        w.write_all(&[*display as u8])
+      }
+      NodeKind::DiagramBlock { engine, content } => {
+        self.write_str(engine, w)?;
+        self.write_str(content, w)
+      }
       NodeKind::Link {
         url,
         title,
         ref_type,
+        attributes,
       } => {
         self.write_str(url, w)?;
         self.write_opt_str(title, w)?;
-        w.write_all(&[ref_type_u8(ref_type)])
+        w.write_all(&[ref_type_u8(ref_type)])?;
+        self.write_pairs(attributes, w)
       }
-      NodeKind::Image { url, alt, title } => {
+      NodeKind::Image {
+        url,
+        alt,
+        title,
+        attributes,
+      } => {
         self.write_str(url, w)?;
         self.write_str(alt, w)?;
-        self.write_opt_str(title, w)
+        self.write_opt_str(title, w)?;
+        self.write_pairs(attributes, w)
       }
       NodeKind::AutoLink { url } => self.write_str(url, w),
       NodeKind::LinkReference { label, ref_type } => {
@@ -121,8 +269,10 @@ impl DastWriter {
         self.write_str(url, w)?;
         self.write_opt_str(title, w)
       }
-      NodeKind::FootnoteReference { label } | NodeKind::FootnoteDefinition { label } => {
-        self.write_str(label, w)
+      NodeKind::FootnoteReference { label, index }
+      | NodeKind::FootnoteDefinition { label, index } => {
+        self.write_str(label, w)?;
+        w.write_all(&index.to_le_bytes())
       }
       NodeKind::TaskListMarker { checked } => w.write_all(&[*checked as u8]),
       NodeKind::Emoji { shortcode } => self.write_str(shortcode, w),
@@ -137,17 +287,21 @@ impl DastWriter {
         name,
         param_type,
         description,
+        type_source,
       } => {
         self.write_str(name, w)?;
         self.write_opt_str(param_type, w)?;
-        self.write_opt_str(description, w)
+        self.write_opt_str(description, w)?;
+        w.write_all(&[type_source_u8(type_source)])
       }
       NodeKind::DocReturn {
         return_type,
         description,
+        type_source,
       } => {
         self.write_opt_str(return_type, w)?;
-        self.write_opt_str(description, w)
+        self.write_opt_str(description, w)?;
+        w.write_all(&[type_source_u8(type_source)])
       }
       NodeKind::DocThrows {
         exception_type,
@@ -160,7 +314,10 @@ impl DastWriter {
         self.write_str(content, w)
       }
       NodeKind::DocSee { reference } => self.write_str(reference, w),
-      NodeKind::DocDeprecated { message } => self.write_opt_str(message, w),
+      NodeKind::DocDeprecated { message, version } => {
+        self.write_opt_str(message, w)?;
+        self.write_opt_str(version, w)
+      }
       NodeKind::DocSince { version } | NodeKind::DocVersion { version } => {
         self.write_str(version, w)
       }
@@ -180,7 +337,13 @@ impl DastWriter {
         self.write_str(name, w)?;
         self.write_opt_str(type_expr, w)
       }
-      NodeKind::Alert { alert_type } => w.write_all(&[alert_type_u8(alert_type)]),
+      NodeKind::Alert { alert_type } => {
+        w.write_all(&[alert_type_u8(alert_type)])?;
+        match alert_type {
+          AlertType::Other(name) => self.write_str(name, w),
+          _ => Ok(()),
+        }
+      }
       NodeKind::Tabs { names } => {
         w.write_all(&(names.len() as u32).to_le_bytes())?;
         for name in names {
@@ -194,23 +357,99 @@ impl DastWriter {
         plusdiff,
         minusdiff,
         linenumbers,
+        invalid_ranges,
+        attributes,
       } => {
         self.write_opt_str(language, w)?;
-        self.write_opt_str(highlight, w)?;
-        self.write_opt_str(plusdiff, w)?;
-        self.write_opt_str(minusdiff, w)?;
-        w.write_all(&[*linenumbers as u8])
+        write_ranges(highlight, w)?;
+        write_ranges(plusdiff, w)?;
+        write_ranges(minusdiff, w)?;
+        w.write_all(&[*linenumbers as u8])?;
+        self.write_pairs(invalid_ranges, w)?;
+        self.write_pairs(attributes, w)
       }
-      _ => Ok(()),
+      NodeKind::DocTypeParam { name, description } => {
+        self.write_str(name, w)?;
+        self.write_opt_str(description, w)
+      }
+      NodeKind::DocRemarks { content } => self.write_str(content, w),
+      NodeKind::DocDefaultValue { value } => self.write_str(value, w),
+      NodeKind::DocReleaseTag { stage } => w.write_all(&[release_stage_u8(stage)]),
+      NodeKind::DocModifier { name } => self.write_str(name, w),
+      NodeKind::DocInlineTag { name, content } => {
+        self.write_str(name, w)?;
+        self.write_opt_str(content, w)
+      }
+      NodeKind::DocDoctest { source, expected } => {
+        self.write_str(source, w)?;
+        self.write_opt_str(expected, w)
+      }
+      NodeKind::CustomElement { name, attributes } => {
+        self.write_str(name, w)?;
+        self.write_pairs(attributes, w)
+      }
+      NodeKind::Include { src } => self.write_str(src, w),
+      NodeKind::Tab { name } => self.write_str(name, w),
+      NodeKind::Abbreviation { title } => self.write_str(title, w),
+      NodeKind::Comment { content, block } => {
+        self.write_str(content, w)?;
+        w.write_all(&[*block as u8])
+      }
+      NodeKind::HtmlTag {
+        name,
+        tag,
+        attributes,
+      } => {
+        self.write_str(name, w)?;
+        w.write_all(&[html_tag_kind_u8(tag)])?;
+        self.write_pairs(attributes, w)
+      }
+      NodeKind::Step { number, title } => {
+        w.write_all(&number.to_le_bytes())?;
+        self.write_opt_str(title, w)
+      }
+      NodeKind::Frontmatter { format, content } => {
+        w.write_all(&[frontmatter_format_u8(format)])?;
+        self.write_str(content, w)
+      }
+      NodeKind::Footnote { label } => self.write_str(label, w),
+      NodeKind::AutoUrl { url } => self.write_str(url, w),
+      NodeKind::Document
+      | NodeKind::Paragraph
+      | NodeKind::BlockQuote
+      | NodeKind::IndentedCodeBlock
+      | NodeKind::ThematicBreak
+      | NodeKind::Table
+      | NodeKind::TableHead
+      | NodeKind::TableBody
+      | NodeKind::TableRow
+      | NodeKind::Emphasis
+      | NodeKind::Strong
+      | NodeKind::Strikethrough
+      | NodeKind::HardBreak
+      | NodeKind::SoftBreak
+      | NodeKind::Footnotes
+      | NodeKind::DefinitionList
+      | NodeKind::DefinitionTerm
+      | NodeKind::DefinitionDescription
+      | NodeKind::Steps
+      | NodeKind::Toc
+      | NodeKind::Unknown { .. } => Ok(()),
     }
   }
 
-  fn write_str<W: Write>(&self, s: &str, w: &mut W) -> io::Result<()> {
-    let idx = self.string_map.get(s).copied().unwrap_or(0);
-    w.write_all(&idx.to_le_bytes())
+  fn write_str(&mut self, s: &'a str, w: &mut Vec<u8>) -> io::Result<()> {
+    if let Some(packed) = inline(s) {
+      self.inlined = true;
+      return w.write_all(&packed.to_le_bytes());
+    }
+    if let Some(refs) = &mut self.refs {
+      refs.push(w.len());
+    }
+    w.write_all(&self.strings.intern(s).to_le_bytes())
   }
 
-  fn write_opt_str<W: Write>(&self, s: &Option<String>, w: &mut W) -> io::Result<()> {
+  fn write_opt_str(&mut self, s: &'a Option<String>, w: &mut Vec<u8>) -> io::Result<()> {
     match s {
       Some(s) => {
         w.write_all(&[1])?;
@@ -219,4 +458,14 @@ impl DastWriter {
       None => w.write_all(&[0]),
     }
   }
+
+  /// Count, then each key and value.
+  fn write_pairs(&mut self, pairs: &'a [(String, String)], w: &mut Vec<u8>) -> io::Result<()> {
+    w.write_all(&(pairs.len() as u32).to_le_bytes())?;
+    for (key, value) in pairs {
+      self.write_str(key, w)?;
+      self.write_str(value, w)?;
+    }
+    Ok(())
+  }
 }
@@ -1,176 +1,97 @@
 //! String table and interning for DAST binary format.
 
-use crate::ast::{Document, Node, NodeKind};
 use std::collections::HashMap;
+use std::io::{self, Write};
 
-/// Collect all strings from document into the string table.
-pub fn collect_strings(strings: &mut Vec<String>, map: &mut HashMap<String, u32>, doc: &Document) {
-  let mut intern = |s: &str| {
-    if !map.contains_key(s) {
-      let idx = strings.len() as u32;
-      strings.push(s.to_string());
-      map.insert(s.to_string(), idx);
-    }
-  };
+use crate::formats::{INLINE_MAX, INLINE_STR};
 
-  intern(&doc.source_path);
-  if let Some(s) = doc.metadata.title.as_ref() {
-    intern(s);
-  }
-  if let Some(s) = doc.metadata.description.as_ref() {
-    intern(s);
+/// `s` packed into a string reference, if it is short enough.
+pub fn inline(s: &str) -> Option<u32> {
+  if s.len() > INLINE_MAX {
+    return None;
   }
+  let mut bytes = [0u8; 4];
+  bytes[..s.len()].copy_from_slice(s.as_bytes());
+  Some(INLINE_STR | ((s.len() as u32) << 24) | u32::from_le_bytes(bytes))
+}
 
-  doc
-    .nodes
-    .iter()
-    .for_each(|n| collect_node_strings(n, strings, map));
+/// Strings referenced by one document, numbered in order of first use.
+/// Entries borrow from the document, so interning never copies text.
+#[derive(Default)]
+pub struct StringTable<'a> {
+  strings: Vec<&'a str>,
+  index: HashMap<&'a str, u32>,
 }
 
-fn collect_node_strings(node: &Node, strings: &mut Vec<String>, map: &mut HashMap<String, u32>) {
-  collect_kind_strings(&node.kind, strings, map);
-  node
-    .children
-    .iter()
-    .for_each(|c| collect_node_strings(c, strings, map));
+impl<'a> StringTable<'a> {
+  /// Index of `s`, adding it to the table on first use.
+  pub fn intern(&mut self, s: &'a str) -> u32 {
+    let next = self.strings.len() as u32;
+    *self.index.entry(s).or_insert_with(|| {
+      self.strings.push(s);
+      next
+    })
+  }
+
+  /// Order the entries by their bytes, so the table depends only on which
+  /// strings the document holds. Returns the new index of each old one.
+  pub fn sort(&mut self) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..self.strings.len() as u32).collect();
+    order.sort_unstable_by_key(|&i| self.strings[i as usize]);
+    let mut remap = vec![0; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+      remap[old as usize] = new as u32;
+    }
+    self.strings = order.iter().map(|&i| self.strings[i as usize]).collect();
+    for i in self.index.values_mut() {
+      *i = remap[*i as usize];
+    }
+    remap
+  }
+
+  /// Count, then length-prefixed UTF-8 bytes for each entry.
+  pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    w.write_all(&(self.strings.len() as u32).to_le_bytes())?;
+    self.strings.iter().try_for_each(|s| {
+      w.write_all(&(s.len() as u32).to_le_bytes())?;
+      w.write_all(s.as_bytes())
+    })
+  }
 }
 
-fn collect_kind_strings(
-  kind: &NodeKind,
-  strings: &mut Vec<String>,
-  map: &mut HashMap<String, u32>,
-) {
-  let mut intern = |s: &str| {
-    if !map.contains_key(s) {
-      let idx = strings.len() as u32;
-      strings.push(s.to_string());
-      map.insert(s.to_string(), idx);
-    }
-  };
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-  match kind {
-    NodeKind::Heading { id, .. } => {
-      if let Some(s) = id.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::CodeBlock { language, info } | NodeKind::FencedCodeBlock { language, info } => {
-      if let Some(s) = language.as_ref() {
-        intern(s);
-      }
-      if let Some(s) = info.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::Text { content }
-    | NodeKind::Code { content }
-    | NodeKind::CodeSpan { content }
-    | NodeKind::HtmlInline { content }
-    | NodeKind::DocExample { content }
-    | NodeKind::DocDescription { content } => {
-      intern(content);
-    }
-    NodeKind::Link { url, title, .. } => {
-      intern(url);
-      if let Some(s) = title.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::Image { url, alt, title } => {
-      intern(url);
-      intern(alt);
-      if let Some(s) = title.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::AutoLink { url } => {
-      intern(url);
-    }
-    NodeKind::LinkReference { label, .. }
-    | NodeKind::FootnoteReference { label }
-    | NodeKind::FootnoteDefinition { label } => {
-      intern(label);
-    }
-    NodeKind::LinkDefinition { label, url, title } => {
-      intern(label);
-      intern(url);
-      if let Some(s) = title.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::Emoji { shortcode } => {
-      intern(shortcode);
-    }
-    NodeKind::Mention { username } => {
-      intern(username);
-    }
-    NodeKind::DocTag { name, content } => {
-      intern(name);
-      if let Some(s) = content.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::DocParam {
-      name,
-      param_type,
-      description,
-    }
-    | NodeKind::DocProperty {
-      name,
-      prop_type: param_type,
-      description,
-    } => {
-      intern(name);
-      if let Some(s) = param_type.as_ref() {
-        intern(s);
-      }
-      if let Some(s) = description.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::DocReturn {
-      return_type,
-      description,
-    } => {
-      if let Some(s) = return_type.as_ref() {
-        intern(s);
-      }
-      if let Some(s) = description.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::DocThrows {
-      exception_type,
-      description,
-    } => {
-      intern(exception_type);
-      if let Some(s) = description.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::DocSee { reference } => {
-      intern(reference);
-    }
-    NodeKind::DocDeprecated { message } => {
-      if let Some(s) = message.as_ref() {
-        intern(s);
-      }
-    }
-    NodeKind::DocSince { version } | NodeKind::DocVersion { version } => {
-      intern(version);
-    }
-    NodeKind::DocAuthor { name } | NodeKind::DocCallback { name } => {
-      intern(name);
-    }
-    NodeKind::DocType { type_expr } => {
-      intern(type_expr);
-    }
-    NodeKind::DocTypedef { name, type_expr } => {
-      intern(name);
-      if let Some(s) = type_expr.as_ref() {
-        intern(s);
-      }
-    }
-    _ => {}
+  #[test]
+  fn test_intern_reuses_index() {
+    let mut table = StringTable::default();
+    assert_eq!(table.intern("a"), 0);
+    assert_eq!(table.intern("b"), 1);
+    assert_eq!(table.intern("a"), 0);
+
+    let mut out = Vec::new();
+    table.write_to(&mut out).unwrap();
+    assert_eq!(out, [2, 0, 0, 0, 1, 0, 0, 0, b'a', 1, 0, 0, 0, b'b']);
+  }
+
+  #[test]
+  fn test_inline() {
+    assert_eq!(inline("-"), Some(0x8100_002d));
+    assert_eq!(inline(""), Some(INLINE_STR));
+    assert_eq!(inline("é").unwrap().to_le_bytes(), [0xc3, 0xa9, 0, 0x82]);
+    assert_eq!(inline("abcd"), None);
+  }
+
+  #[test]
+  fn test_sort_remaps() {
+    let mut table = StringTable::default();
+    for s in ["pear", "apple", "fig"] {
+      table.intern(s);
+    }
+    assert_eq!(table.sort(), [2, 0, 1]);
+    assert_eq!(table.strings, ["apple", "fig", "pear"]);
+    assert_eq!(table.intern("pear"), 2);
+    assert_eq!(table.intern("kiwi"), 3);
   }
 }
@@ -0,0 +1,167 @@
+//! Golden-file tests. Each source file in `tests/fixtures` (Markdown,
+//! JavaScript, TypeScript, Java or Python, by extension) goes through the
+//! same parse and built-in transforms as the CLI, and is checked against:
+//!
+//! - `<fixture>.sexp`, the AST as an s-expression (`-f sexp`)
+//! - `<fixture>.json`, the pretty JSON output (`-f json --pretty`)
+//! - its line in `dast.sums`, the CRC-32 of its DAST output, plus a DAST
+//!   write/read round trip
+//!
+//! `schema/ast.schema.json`, the published JSON Schema, is checked against
+//! `bukvar schema` the same way.
+//!
+//! Run `BUKVAR_BLESS=1 cargo test golden` to rewrite the snapshots from the
+//! current output, then review the diff.
+
+use crate::ast::{Document, DocumentType};
+use crate::formats::{json_schema, read_dast, to_json_pretty, to_sexp, write_dast};
+use crate::markdown::{ElementRegistry, ParseOptions};
+use crate::processor::parse_content;
+use crate::processor::transform::Pipeline;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BLESS_VAR: &str = "BUKVAR_BLESS";
+
+fn fixtures_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Source fixtures in name order, with their parser.
+fn fixtures() -> Vec<(PathBuf, DocumentType)> {
+  let dir = fixtures_dir();
+  let mut files: Vec<(PathBuf, DocumentType)> = fs::read_dir(&dir)
+    .unwrap_or_else(|e| panic!("cannot read {}: {}", dir.display(), e))
+    .filter_map(|entry| entry.ok().map(|e| e.path()))
+    .filter_map(|path| {
+      let ext = path.extension()?.to_str()?;
+      let doc_type = DocumentType::from_extension(ext)?;
+      Some((path, doc_type))
+    })
+    .collect();
+  files.sort();
+  files
+}
+
+fn parse_fixture(path: &Path, doc_type: DocumentType, pipeline: &Pipeline) -> Document {
+  let source = fs::read_to_string(path).unwrap();
+  let mut doc = parse_content(
+    &source,
+    doc_type,
+    &ElementRegistry::new(),
+    ParseOptions::new(),
+  );
+  doc.source_path = path.to_string_lossy().into_owned();
+  pipeline.run(&mut doc).unwrap();
+  // Keep machine-specific directories out of the snapshots
+  doc.source_path = format!("tests/fixtures/{}", file_name(path));
+  doc
+}
+
+fn file_name(path: &Path) -> String {
+  path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+fn snapshot_path(path: &Path, suffix: &str) -> PathBuf {
+  let mut snapshot = path.as_os_str().to_owned();
+  snapshot.push(suffix);
+  PathBuf::from(snapshot)
+}
+
+/// First line where the snapshot and the new output disagree.
+fn first_difference(expected: &str, actual: &str) -> String {
+  let mut expected_lines = expected.lines();
+  let mut actual_lines = actual.lines();
+  for line in 1.. {
+    match (expected_lines.next(), actual_lines.next()) {
+      (Some(e), Some(a)) if e == a => continue,
+      (None, None) => break,
+      (e, a) => {
+        return format!(
+          "line {}\n    expected: {}\n    actual:   {}",
+          line,
+          e.unwrap_or("<end>"),
+          a.unwrap_or("<end>")
+        )
+      }
+    }
+  }
+  "differs only in trailing whitespace".to_string()
+}
+
+/// Compare `actual` with the snapshot at `path`, or overwrite it when
+/// blessing. Returns a failure description.
+fn check(path: &Path, actual: &str, bless: bool) -> Option<String> {
+  let expected = fs::read_to_string(path).ok();
+  if expected.as_deref() == Some(actual) {
+    return None;
+  }
+  if bless {
+    fs::write(path, actual).unwrap();
+    return None;
+  }
+  let name = file_name(path);
+  Some(match expected {
+    Some(expected) => format!("{}: {}", name, first_difference(&expected, actual)),
+    None => format!("{}: missing; run with {}=1 to create it", name, BLESS_VAR),
+  })
+}
+
+#[test]
+fn test_golden_fixtures() {
+  let bless = std::env::var_os(BLESS_VAR).is_some();
+  let files = fixtures();
+  assert!(!files.is_empty(), "no fixtures in tests/fixtures");
+
+  let pipeline = Pipeline::builtin(&ElementRegistry::new());
+  let mut failures = Vec::new();
+  let mut sums = String::new();
+  for (path, doc_type) in &files {
+    let doc = parse_fixture(path, *doc_type, &pipeline);
+    let sexp = to_sexp(&doc);
+    failures.extend(check(&snapshot_path(path, ".sexp"), &sexp, bless));
+    failures.extend(check(
+      &snapshot_path(path, ".json"),
+      &(to_json_pretty(&doc) + "\n"),
+      bless,
+    ));
+
+    let dast = write_dast(&doc).unwrap();
+    match read_dast(&dast) {
+      Ok(restored) if to_sexp(&restored) == sexp => {}
+      Ok(restored) => failures.push(format!(
+        "{}: DAST round trip: {}",
+        file_name(path),
+        first_difference(&sexp, &to_sexp(&restored))
+      )),
+      Err(e) => failures.push(format!("{}: DAST round trip: {}", file_name(path), e)),
+    }
+    // The DAST trailer is already a CRC-32 of the rest of the file
+    let trailer = &dast[dast.len() - 4..];
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    sums.push_str(&format!("{:08x}  {}\n", crc, file_name(path)));
+  }
+  failures.extend(check(&fixtures_dir().join("dast.sums"), &sums, bless));
+
+  assert!(
+    failures.is_empty(),
+    "{} snapshot(s) differ from the current output \
+     (run with {}=1 to accept the changes):\n  {}",
+    failures.len(),
+    BLESS_VAR,
+    failures.join("\n  ")
+  );
+}
+
+#[test]
+fn test_golden_schema() {
+  let bless = std::env::var_os(BLESS_VAR).is_some();
+  let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema/ast.schema.json");
+  if let Some(failure) = check(&path, &json_schema(), bless) {
+    panic!(
+      "{} (run with {}=1 to accept the change)",
+      failure, BLESS_VAR
+    );
+  }
+}
@@ -0,0 +1,71 @@
+//! Decode a DAST file back to JSON (`bukvar inspect`).
+
+use crate::cli::Args;
+use crate::formats::{esc, to_json_with, DastIndex, DastReader, StringStats};
+
+use std::fs::File;
+use std::io::BufReader;
+
+/// Print the document stored in `args.input` as JSON. Nodes from newer
+/// format versions are shown as `Unknown` instead of failing the read.
+/// With `--strings`, print how its strings are stored instead, and with
+/// `--index` its index.
+pub fn run(args: &Args) -> Result<(), String> {
+  let path = args.input.display();
+  let file = File::open(&args.input).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+  if args.index_entries {
+    let index = DastReader::new()
+      .with_forward_compat(true)
+      .read_index(&mut BufReader::new(file))
+      .map_err(|e| format!("Failed to read {}: {}", path, e))?
+      .ok_or_else(|| format!("{} has no index (write it with --dast-index)", path))?;
+    println!("{}", index_json(&path.to_string(), &index));
+    return Ok(());
+  }
+  let mut reader = DastReader::new()
+    .with_forward_compat(true)
+    .with_verify(args.verify)
+    .with_string_stats(args.string_stats);
+  let doc = reader
+    .read(&mut BufReader::new(file))
+    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+  match reader.string_stats() {
+    Some(stats) => println!("{}", stats_json(&path.to_string(), stats)),
+    None => println!("{}", to_json_with(&doc, &args.json_options())),
+  }
+  Ok(())
+}
+
+fn stats_json(path: &str, stats: &StringStats) -> String {
+  format!(
+    "{{\"file\":\"{}\",\"table\":{{\"entries\":{},\"bytes\":{}}},\
+     \"references\":{{\"count\":{},\"bytes\":{},\"saved_bytes\":{}}},\
+     \"inline\":{{\"count\":{},\"saved_bytes\":{}}}}}",
+    esc(path),
+    stats.entries,
+    stats.table_bytes,
+    stats.references,
+    stats.referenced_bytes,
+    stats.dedup_saved_bytes(),
+    stats.inline,
+    stats.inline_saved_bytes,
+  )
+}
+
+fn index_json(path: &str, index: &DastIndex) -> String {
+  let entries: Vec<String> = index
+    .entries
+    .iter()
+    .map(|e| {
+      format!(
+        "{{\"offset\":{},\"node\":{},\"level\":{},\"nested\":{}}}",
+        e.offset, e.node, e.level, e.nested
+      )
+    })
+    .collect();
+  format!(
+    "{{\"file\":\"{}\",\"index\":[{}]}}",
+    esc(path),
+    entries.join(",")
+  )
+}
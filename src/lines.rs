@@ -0,0 +1,126 @@
+//! Offsets to lines and back. Spans hold byte offsets, and only block nodes
+//! get a line while scanning; a [`LineIndex`] built once from the source
+//! answers the rest: inline nodes' lines for validation, UTF-16 positions
+//! for source maps, and positions in the parse trace.
+
+use std::ops::Range;
+
+/// Where each line of a source text starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+  /// Byte offset of each line's first character; the first is 0
+  starts: Vec<usize>,
+  len: usize,
+}
+
+/// A position as LSP counts it: 0-based line, and UTF-16 code units from
+/// the start of that line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utf16Position {
+  pub line: usize,
+  pub character: usize,
+}
+
+impl LineIndex {
+  pub fn new(source: &str) -> Self {
+    let mut starts = vec![0];
+    starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+    Self {
+      starts,
+      len: source.len(),
+    }
+  }
+
+  /// Number of lines; text after the last newline, even none, is a line.
+  #[allow(dead_code)]
+  pub fn line_count(&self) -> usize {
+    self.starts.len()
+  }
+
+  /// 1-based line and byte column of `offset`, clamped to the text.
+  pub fn line_col(&self, offset: usize) -> (usize, usize) {
+    let offset = offset.min(self.len);
+    let line = self.starts.partition_point(|&start| start <= offset);
+    (line, offset - self.starts[line - 1] + 1)
+  }
+
+  /// 1-based line of `offset`, clamped to the text.
+  pub fn line(&self, offset: usize) -> usize {
+    self.line_col(offset).0
+  }
+
+  /// Byte offset of a 1-based line and byte column; `None` outside the
+  /// text or past the end of the line.
+  #[allow(dead_code)]
+  pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+    let range = self.line_range(line)?;
+    let offset = range.start + column.checked_sub(1)?;
+    (offset <= range.end).then_some(offset)
+  }
+
+  /// Byte range of a 1-based line, without its line break.
+  #[allow(dead_code)]
+  pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+    let start = *self.starts.get(line.checked_sub(1)?)?;
+    let end = self.starts.get(line).map_or(self.len, |next| next - 1);
+    Some(start..end)
+  }
+
+  /// Position of byte `offset` in UTF-16 code units; `source` must be the
+  /// indexed text. An offset inside a character counts from that
+  /// character's start.
+  pub fn utf16(&self, source: &str, offset: usize) -> Utf16Position {
+    let mut offset = offset.min(self.len);
+    while !source.is_char_boundary(offset) {
+      offset -= 1;
+    }
+    let line = self.line(offset) - 1;
+    let character = source[self.starts[line]..offset]
+      .chars()
+      .map(char::len_utf16)
+      .sum();
+    Utf16Position { line, character }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_line_col() {
+    let index = LineIndex::new("ab\ncd\n\nlast");
+    assert_eq!(index.line_count(), 4);
+    assert_eq!(index.line_col(0), (1, 1));
+    assert_eq!(index.line_col(2), (1, 3));
+    assert_eq!(index.line_col(3), (2, 1));
+    assert_eq!(index.line_col(6), (3, 1));
+    assert_eq!(index.line_col(99), (4, 5));
+    assert_eq!(index.offset(2, 2), Some(4));
+    assert_eq!(index.offset(4, 5), Some(11));
+    assert_eq!(index.offset(1, 4), None);
+    assert_eq!(index.offset(0, 1), None);
+    assert_eq!(index.line_range(2), Some(3..5));
+    assert_eq!(index.line_range(4), Some(7..11));
+    assert_eq!(index.line_range(5), None);
+  }
+
+  #[test]
+  fn test_utf16() {
+    let source = "héllo 😀 x\nñ\n";
+    let index = LineIndex::new(source);
+    let at = |offset| {
+      let p = index.utf16(source, offset);
+      (p.line, p.character)
+    };
+    assert_eq!(at(0), (0, 0));
+    // é is 2 bytes and 1 unit; 😀 is 4 bytes and 2 units
+    assert_eq!(at(3), (0, 2));
+    assert_eq!(at(11), (0, 8));
+    assert_eq!(at(13), (0, 10));
+    assert_eq!(at(14), (1, 0));
+    // Inside ñ, and past the end
+    assert_eq!(at(15), (1, 0));
+    assert_eq!(at(99), (2, 0));
+  }
+}
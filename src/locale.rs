@@ -0,0 +1,187 @@
+//! Translations: a page's locale comes from a suffix in its file name
+//! (`guide.fr.md`) or its frontmatter `lang`, and pages that differ only by
+//! that suffix are translations of each other. `nav.json` lists each page
+//! once with its translations, and with `--locales en,fr,de` and
+//! `--validate`, a page missing one of those locales is a warning.
+//!
+//! Without `--locales`, any suffix that looks like a language tag (`fr`,
+//! `pt-BR`, `zh_TW`) is a locale; with it, only the listed locales are, so
+//! `api.js.md` stays a page of its own. Pages without a locale are in the
+//! first listed locale.
+
+use crate::ast::Document;
+use crate::processor::transform::frontmatter_vars;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Where a page sits among its translations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageLocale {
+  /// Path relative to the input directory
+  pub path: PathBuf,
+  /// Path without the locale suffix, the same for every translation
+  pub group: PathBuf,
+  /// Frontmatter `lang`, else the file name suffix; `None` for neither
+  pub locale: Option<String>,
+}
+
+impl PageLocale {
+  /// Locale of the document at `path`; `locales` is the `--locales` list.
+  pub fn of_document(doc: &Document, path: PathBuf, locales: &[String]) -> Self {
+    let lang = frontmatter_vars(&doc.nodes)
+      .into_iter()
+      .find(|(key, _)| key == "lang")
+      .map(|(_, value)| normalize(&value));
+    Self::detect(path, lang, locales)
+  }
+
+  fn detect(path: PathBuf, lang: Option<String>, locales: &[String]) -> Self {
+    let suffix = path
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .and_then(|stem| stem.rsplit_once('.'))
+      .filter(|(_, suffix)| is_locale(suffix, locales));
+    let (group, suffix) = match suffix {
+      Some((base, suffix)) => {
+        let mut name = base.to_string();
+        if let Some(ext) = path.extension() {
+          name.push('.');
+          name.push_str(&ext.to_string_lossy());
+        }
+        (path.with_file_name(name), Some(normalize(suffix)))
+      }
+      None => (path.clone(), None),
+    };
+    Self {
+      path,
+      group,
+      locale: lang.or(suffix),
+    }
+  }
+}
+
+/// `pt_br` -> `pt-BR`: lowercase language, uppercase two-letter region.
+pub fn normalize(locale: &str) -> String {
+  let mut parts = locale.trim().split(['-', '_']);
+  let mut out = parts.next().unwrap_or_default().to_ascii_lowercase();
+  for part in parts {
+    out.push('-');
+    if part.len() == 2 {
+      out.push_str(&part.to_ascii_uppercase());
+    } else {
+      out.push_str(part);
+    }
+  }
+  out
+}
+
+/// Whether a file name suffix names a locale: one of `locales` if any are
+/// given, else anything shaped like a language tag.
+fn is_locale(suffix: &str, locales: &[String]) -> bool {
+  if !locales.is_empty() {
+    let suffix = normalize(suffix);
+    return locales.iter().any(|locale| normalize(locale) == suffix);
+  }
+  let (language, region) = match suffix.split_once(['-', '_']) {
+    Some((language, region)) => (language, Some(region)),
+    None => (suffix, None),
+  };
+  (2..=3).contains(&language.len())
+    && language.bytes().all(|b| b.is_ascii_lowercase())
+    && region.map_or(true, |region| {
+      (2..=4).contains(&region.len()) && region.bytes().all(|b| b.is_ascii_alphanumeric())
+    })
+}
+
+/// Pages missing translations into `locales`: for each group lacking any,
+/// the page to report it on (the one without a suffix, else the first) and
+/// the missing locales. Pages without a locale count as the first locale.
+pub fn missing(pages: &[PageLocale], locales: &[String]) -> Vec<(PathBuf, Vec<String>)> {
+  let Some(default) = locales.first() else {
+    return Vec::new();
+  };
+  let mut groups: Vec<(&Path, Vec<&PageLocale>)> = Vec::new();
+  let mut index: HashMap<&Path, usize> = HashMap::new();
+  for page in pages {
+    let at = *index.entry(&page.group).or_insert_with(|| {
+      groups.push((&page.group, Vec::new()));
+      groups.len() - 1
+    });
+    groups[at].1.push(page);
+  }
+  groups
+    .into_iter()
+    .filter_map(|(group, pages)| {
+      let present: Vec<String> = pages
+        .iter()
+        .map(|page| normalize(page.locale.as_deref().unwrap_or(default)))
+        .collect();
+      let absent: Vec<String> = locales
+        .iter()
+        .filter(|locale| !present.contains(&normalize(locale)))
+        .cloned()
+        .collect();
+      let page = pages
+        .iter()
+        .find(|page| page.path == group)
+        .unwrap_or(&pages[0]);
+      (!absent.is_empty()).then(|| (page.path.clone(), absent))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn detect(path: &str, lang: Option<&str>, locales: &[&str]) -> (String, Option<String>) {
+    let locales: Vec<String> = locales.iter().map(|l| l.to_string()).collect();
+    let page = PageLocale::detect(PathBuf::from(path), lang.map(normalize), &locales);
+    (page.group.to_string_lossy().into_owned(), page.locale)
+  }
+
+  #[test]
+  fn test_detect() {
+    let fr = Some("fr".to_string());
+    assert_eq!(
+      detect("guide/setup.fr.md", None, &[]),
+      ("guide/setup.md".to_string(), fr.clone())
+    );
+    assert_eq!(
+      detect("setup.md", None, &[]),
+      ("setup.md".to_string(), None)
+    );
+    assert_eq!(
+      detect("a.pt_br.md", None, &[]),
+      ("a.md".to_string(), Some("pt-BR".to_string()))
+    );
+    assert_eq!(detect("a.md", Some("FR"), &[]), ("a.md".to_string(), fr));
+    assert_eq!(detect("api.js.md", None, &[]).0, "api.md");
+    assert_eq!(
+      detect("api.js.md", None, &["en", "fr"]),
+      ("api.js.md".to_string(), None)
+    );
+    assert_eq!(detect("notes.draft.md", None, &[]).0, "notes.draft.md");
+  }
+
+  #[test]
+  fn test_missing() {
+    let locales: Vec<String> = ["en", "fr", "de"].iter().map(|l| l.to_string()).collect();
+    let pages: Vec<PageLocale> = ["a.md", "a.fr.md", "b.de.md", "c.md", "c.fr.md", "c.de.md"]
+      .iter()
+      .map(|path| PageLocale::detect(PathBuf::from(path), None, &locales))
+      .collect();
+    assert_eq!(
+      missing(&pages, &locales),
+      [
+        (PathBuf::from("a.md"), vec!["de".to_string()]),
+        (
+          PathBuf::from("b.de.md"),
+          vec!["en".to_string(), "fr".to_string()]
+        ),
+      ]
+    );
+    assert!(missing(&pages, &[]).is_empty());
+  }
+}
@@ -1,17 +1,38 @@
+mod analyze;
+mod anchors;
 mod ast;
+mod backlinks;
 mod bench;
+mod cancel;
+mod check;
 mod cli;
+mod deprecations;
 mod error;
+mod estimate;
+mod explain;
+mod extract;
 mod formats;
+#[cfg(test)]
+mod golden;
+mod inspect;
+mod lines;
+mod locale;
 mod markdown;
+mod memory;
+mod nav;
 mod parsers;
 mod processor;
+mod releases;
+mod report;
+mod serve;
 mod sourcemap;
 mod streaming;
 mod validate;
+mod xref;
 
 use cli::parse_args;
-use processor::FileProcessor;
+use processor::{FileProcessor, EXIT_IO};
+use report::Reporter;
 use std::time::Instant;
 
 fn main() {
@@ -24,7 +45,7 @@ fn main() {
         std::process::exit(0);
       } else {
         eprintln!("{}", msg);
-        std::process::exit(1);
+        std::process::exit(EXIT_IO);
       }
     }
   };
@@ -35,78 +56,100 @@ fn main() {
     return;
   }
 
-  println!();
-  println!("\x1b[1;36mBukvar v1.0.0\x1b[0m  \x1b[90m(Glagolica Project)\x1b[0m");
-  println!("\x1b[90mUltra-fast zero-dependency markdown parser\x1b[0m");
-  println!();
-  println!(
-    "  Input:  {}",
-    args.input.to_string_lossy().replace('\\', "/")
-  );
-  println!(
-    "  Output: {}",
-    args.output.to_string_lossy().replace('\\', "/")
-  );
-  println!("  Format: {:?}", args.format);
-  println!();
+  // Service mode: stdout carries the protocol, so no banner
+  if let Some(mode) = &args.serve {
+    if let Err(e) = serve::run(mode, &args) {
+      eprintln!("Error: {}", e);
+      std::process::exit(EXIT_IO);
+    }
+    return;
+  }
+
+  if let Some(file) = &args.explain {
+    match explain::run(file, &args) {
+      Ok(path) if !args.quiet => println!("Wrote {}", path.display()),
+      Ok(_) => {}
+      Err(e) => {
+        eprintln!("Error: {}", e);
+        std::process::exit(EXIT_IO);
+      }
+    }
+    return;
+  }
+
+  if args.list_parsers {
+    print!("{}", parsers::ParserRegistry::new().describe());
+    return;
+  }
+
+  if args.schema {
+    print!("{}", formats::json_schema());
+    return;
+  }
+
+  if let Some(sources) = &args.merge {
+    match processor::merge::run(sources, &args) {
+      Ok(path) if !args.quiet => println!("Wrote {}", path.display()),
+      Ok(_) => {}
+      Err(e) => {
+        eprintln!("Error: {}", e);
+        std::process::exit(EXIT_IO);
+      }
+    }
+    return;
+  }
+
+  if args.inspect {
+    if let Err(e) = inspect::run(&args) {
+      eprintln!("Error: {}", e);
+      std::process::exit(EXIT_IO);
+    }
+    return;
+  }
+
+  let reporter = Reporter::from_args(&args);
+  reporter.banner(&args);
 
   let start = Instant::now();
+  cancel::install();
 
   let processor = match FileProcessor::new(&args) {
     Ok(p) => p,
     Err(e) => {
-      eprintln!("\x1b[1;31mError:\x1b[0m {}", e);
-      std::process::exit(1);
+      reporter.error(&e);
+      std::process::exit(EXIT_IO);
     }
   };
 
+  if let Some(samples) = args.estimate {
+    reporter.estimate(&processor.estimate(samples));
+    return;
+  }
+
   let stats = match processor.process_all() {
     Ok(s) => s,
     Err(e) => {
-      eprintln!("\x1b[1;31mError:\x1b[0m {}", e);
-      std::process::exit(1);
+      reporter.error(&e);
+      std::process::exit(EXIT_IO);
     }
   };
 
-  let elapsed = start.elapsed();
-  let total = stats.total_files();
-
-  // Success output
-  println!();
-  println!("\x1b[32m━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\x1b[0m");
-  println!("\x1b[1;32m  ✓ SUCCESS\x1b[0m");
-  println!("\x1b[32m━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\x1b[0m");
-  println!();
-  println!("\x1b[1m  Files Processed\x1b[0m");
-  println!(
-    "    Markdown     \x1b[36m{:>5}\x1b[0m",
-    stats.markdown_files
-  );
-  println!("    JavaScript   \x1b[36m{:>5}\x1b[0m", stats.js_files);
-  println!("    Java         \x1b[36m{:>5}\x1b[0m", stats.java_files);
-  println!("    Python       \x1b[36m{:>5}\x1b[0m", stats.python_files);
-  println!();
-  println!("\x1b[1m  AST Generated\x1b[0m");
-  println!("    Total nodes  \x1b[33m{:>5}\x1b[0m", stats.total_nodes);
-
-  if stats.errors > 0 {
-    println!("    Errors       \x1b[31m{:>5}\x1b[0m", stats.errors);
+  let exit_code = stats.exit_code(&args);
+  reporter.summary(&stats, start.elapsed(), exit_code);
+  if stats.interrupted {
+    reporter.error(&format!(
+      "interrupted; {} file(s) were not processed",
+      stats.cancelled
+    ));
   }
-
-  println!();
-  println!("\x1b[1m  Performance\x1b[0m");
-  println!("    Time         \x1b[32m{:.2?}\x1b[0m", elapsed);
-
-  if elapsed.as_secs_f64() > 0.0 {
-    let throughput = total as f64 / elapsed.as_secs_f64();
-    println!(
-      "    Throughput   \x1b[32m{:.0} files/sec\x1b[0m",
-      throughput
-    );
+  if let Some(percent) = stats.coverage_below(args.min_doc_coverage) {
+    reporter.error(&format!(
+      "doc coverage {:.1}% is below --min-doc-coverage {}",
+      percent,
+      args.min_doc_coverage.unwrap_or_default()
+    ));
   }
-
-  println!("\x1b[32m━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\x1b[0m");
-  println!();
+  std::process::exit(exit_code);
 }
 
 /// Run internal benchmarks.
@@ -206,5 +249,34 @@ Check [link](https://example.com) for more info.
     });
   println!("{}", simple_throughput);
 
+  // DAST serialization of a large parsed corpus
+  let corpus = complex.repeat(2000); // ~460KB document
+  let corpus_ast = MarkdownParser::new(&corpus).parse();
+  let dast_len = formats::write_dast(&corpus_ast).map_or(0, |d| d.len());
+  let dast_throughput = bench_throughput("dast_write_throughput", 50, dast_len, || {
+    let _ = formats::write_dast(&corpus_ast);
+  });
+  println!("{}", dast_throughput);
+
+  // Heap allocations per parse
+  println!("\n=== Allocations ===\n");
+  if !bench::COUNTS_ALLOCATIONS {
+    println!("Not counted; build with `--features bench`\n");
+    return;
+  }
+  for (name, input) in [
+    ("simple_paragraph", simple),
+    ("inline_emphasis", emphasis),
+    ("links", links),
+    ("code_block", code),
+    ("list", list),
+    ("complex_doc", complex),
+  ] {
+    let (count, bytes) = bench::count_allocations(|| {
+      let _ = MarkdownParser::new(input).parse();
+    });
+    println!("{}: {} allocations, {} bytes", name, count, bytes);
+  }
+
   println!();
 }
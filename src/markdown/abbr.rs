@@ -0,0 +1,149 @@
+//! Abbreviations in the PHP Markdown Extra style: a `*[HTML]: Hyper Text
+//! Markup Language` line defines a term, and each whole-word occurrence
+//! of the term in text becomes an `Abbreviation` node.
+
+use crate::ast::{Node, NodeKind, Span};
+
+/// Abbreviation definition: `*[term]: title`
+#[derive(Debug, Clone)]
+pub struct AbbrDef {
+  pub term: String,
+  pub title: String,
+}
+
+/// Parse a definition line at the start of `input`, indented at most three
+/// spaces. The title may be empty. Returns the bytes consumed, through the
+/// newline.
+pub fn parse(input: &str) -> Option<(AbbrDef, usize)> {
+  let len = input.find('\n').map_or(input.len(), |i| i + 1);
+  let line = input[..len].trim_end();
+  let indent = line.bytes().take_while(|&b| b == b' ').count();
+  if indent > 3 {
+    return None;
+  }
+  let (term, title) = line[indent..].strip_prefix("*[")?.split_once("]:")?;
+  let term = term.trim();
+  if term.is_empty() || term.contains('[') {
+    return None;
+  }
+  let def = AbbrDef {
+    term: term.to_string(),
+    title: title.trim().to_string(),
+  };
+  Some((def, len))
+}
+
+/// Wrap each occurrence of a defined term in the text of `nodes` in an
+/// `Abbreviation`. Code, math, HTML and existing abbreviations are left
+/// alone; the longest term wins where several match.
+pub fn annotate(nodes: &mut Vec<Node>, defs: &[AbbrDef]) {
+  let mut by_length: Vec<&AbbrDef> = defs.iter().collect();
+  by_length.sort_by_key(|def| std::cmp::Reverse(def.term.len()));
+  wrap(nodes, &by_length);
+}
+
+fn wrap(nodes: &mut Vec<Node>, defs: &[&AbbrDef]) {
+  let mut out = Vec::with_capacity(nodes.len());
+  for mut node in std::mem::take(nodes) {
+    match &node.kind {
+      NodeKind::Text { content } => {
+        if !split(content, node.span, defs, &mut out) {
+          out.push(node);
+        }
+      }
+      NodeKind::CodeBlock { .. }
+      | NodeKind::FencedCodeBlock { .. }
+      | NodeKind::CodeBlockExt { .. }
+      | NodeKind::IndentedCodeBlock
+      | NodeKind::HtmlBlock { .. }
+      | NodeKind::Abbreviation { .. } => out.push(node),
+      _ => {
+        wrap(&mut node.children, defs);
+        out.push(node);
+      }
+    }
+  }
+  *nodes = out;
+}
+
+/// Push `content` as text and abbreviation nodes, or nothing when no term
+/// occurs in it. A term matches where it is not part of a longer word.
+fn split(content: &str, span: Span, defs: &[&AbbrDef], out: &mut Vec<Node>) -> bool {
+  let mut text_start = 0;
+  let mut i = 0;
+  while i < content.len() {
+    let word_start = !content[..i]
+      .chars()
+      .next_back()
+      .is_some_and(char::is_alphanumeric);
+    let def = defs.iter().find(|def| {
+      word_start
+        && content[i..].starts_with(&def.term)
+        && !content[i + def.term.len()..]
+          .chars()
+          .next()
+          .is_some_and(char::is_alphanumeric)
+    });
+    let Some(def) = def else {
+      i += content[i..].chars().next().map_or(1, char::len_utf8);
+      continue;
+    };
+
+    let end = i + def.term.len();
+    if text_start < i {
+      out.push(text(&content[text_start..i], span, text_start, i));
+    }
+    out.push(Node::with_children(
+      NodeKind::Abbreviation {
+        title: def.title.clone(),
+      },
+      sub_span(span, i, end),
+      vec![text(&def.term, span, i, end)],
+    ));
+    i = end;
+    text_start = i;
+  }
+  if text_start == 0 {
+    return false;
+  }
+  if text_start < content.len() {
+    out.push(text(
+      &content[text_start..],
+      span,
+      text_start,
+      content.len(),
+    ));
+  }
+  true
+}
+
+fn text(content: &str, span: Span, start: usize, end: usize) -> Node {
+  Node::new(
+    NodeKind::Text {
+      content: content.to_string(),
+    },
+    sub_span(span, start, end),
+  )
+}
+
+fn sub_span(span: Span, start: usize, end: usize) -> Span {
+  Span::new(span.start + start, span.start + end, span.line, span.column)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_definition() {
+    let (def, len) = parse("*[HTML]: Hyper Text Markup Language\nnext").unwrap();
+    assert_eq!(
+      (def.term.as_str(), def.title.as_str(), len),
+      ("HTML", "Hyper Text Markup Language", 36)
+    );
+    assert_eq!(parse("   *[W3C]:").unwrap().0.title, "");
+    assert!(parse("    *[HTML]: indented code").is_none());
+    assert!(parse("*[]: empty").is_none());
+    assert!(parse("*[HTML] no colon").is_none());
+  }
+}
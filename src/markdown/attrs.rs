@@ -0,0 +1,144 @@
+//! Pandoc-style attribute lists: `{#id .class key=value key="quoted value"}`
+//! after a heading, on a code fence's info string, or directly after a link
+//! or image.
+//!
+//! Attributes come back as `(key, value)` pairs in the order written: `#id`
+//! becomes `id`, the classes are joined with spaces into one `class`, and a
+//! bare word is a flag with an empty value.
+
+/// Parse the attribute list opening `input`, returning the attributes and
+/// the bytes consumed through the closing `}`. Anything that isn't an id,
+/// class or `key[=value]` makes the whole list invalid.
+pub fn parse(input: &str) -> Option<(Vec<(String, String)>, usize)> {
+  let mut rest = input.strip_prefix('{')?;
+  let mut attributes = Vec::new();
+  loop {
+    rest = rest.trim_start();
+    if let Some(after) = rest.strip_prefix('}') {
+      return Some((attributes, input.len() - after.len()));
+    }
+    let word_end = rest
+      .find(|c: char| c == '=' || c == '}' || c.is_whitespace())
+      .unwrap_or(rest.len());
+    let (word, after) = rest.split_at(word_end);
+    rest = after;
+
+    if let Some(id) = word.strip_prefix('#') {
+      if id.is_empty() {
+        return None;
+      }
+      set(&mut attributes, "id", id.to_string());
+    } else if let Some(class) = word.strip_prefix('.') {
+      if class.is_empty() {
+        return None;
+      }
+      match attributes.iter_mut().find(|(key, _)| key == "class") {
+        Some((_, classes)) => {
+          classes.push(' ');
+          classes.push_str(class);
+        }
+        None => attributes.push(("class".to_string(), class.to_string())),
+      }
+    } else {
+      let valid_key = word
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'));
+      if word.is_empty() || !valid_key {
+        return None;
+      }
+      let value = match rest.strip_prefix('=') {
+        Some(after) => {
+          let (value, after) = value(after)?;
+          rest = after;
+          value
+        }
+        None => "",
+      };
+      set(&mut attributes, word, value.to_string());
+    }
+  }
+}
+
+/// Split an attribute list off the end of `text`, such as a heading's
+/// `Title {#intro .unnumbered}`. Returns the text before it, trimmed.
+pub fn trailing(text: &str) -> Option<(&str, Vec<(String, String)>)> {
+  let text = text.trim_end();
+  if !text.ends_with('}') {
+    return None;
+  }
+  text.match_indices('{').find_map(|(pos, _)| {
+    let (attributes, len) = parse(&text[pos..])?;
+    (pos + len == text.len()).then(|| (text[..pos].trim_end(), attributes))
+  })
+}
+
+/// A `=value`'s value, quoted or up to whitespace or `}`, and the input
+/// after it.
+fn value(input: &str) -> Option<(&str, &str)> {
+  match input.chars().next() {
+    Some(quote @ ('"' | '\'')) => {
+      let end = input[1..].find(quote)? + 1;
+      Some((&input[1..end], &input[end + 1..]))
+    }
+    _ => {
+      let end = input
+        .find(|c: char| c == '}' || c.is_whitespace())
+        .unwrap_or(input.len());
+      (end > 0).then(|| input.split_at(end))
+    }
+  }
+}
+
+/// Replace `key`'s value, or add it.
+fn set(attributes: &mut Vec<(String, String)>, key: &str, value: String) {
+  match attributes.iter_mut().find(|(k, _)| k == key) {
+    Some((_, v)) => *v = value,
+    None => attributes.push((key.to_string(), value)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pairs(attributes: &[(String, String)]) -> Vec<(&str, &str)> {
+    attributes
+      .iter()
+      .map(|(k, v)| (k.as_str(), v.as_str()))
+      .collect()
+  }
+
+  #[test]
+  fn test_parse_attributes() {
+    let (attributes, len) =
+      parse(r#"{#intro .wide .dark lang=en title="a } b" hidden} rest"#).unwrap();
+    assert_eq!(
+      pairs(&attributes),
+      [
+        ("id", "intro"),
+        ("class", "wide dark"),
+        ("lang", "en"),
+        ("title", "a } b"),
+        ("hidden", ""),
+      ]
+    );
+    assert_eq!(len, 49);
+    assert_eq!(parse("{}").unwrap(), (Vec::new(), 2));
+    assert!(parse("{#}").is_none());
+    assert!(parse("{not valid!}").is_none());
+    assert!(parse("{key=}").is_none());
+    assert!(parse("{#open").is_none());
+  }
+
+  #[test]
+  fn test_trailing_attributes() {
+    let (text, attributes) = trailing("Intro {x} {#intro .unnumbered}").unwrap();
+    assert_eq!(text, "Intro {x}");
+    assert_eq!(
+      pairs(&attributes),
+      [("id", "intro"), ("class", "unnumbered")]
+    );
+    assert!(trailing("Set {a, b}").is_none());
+    assert!(trailing("Plain").is_none());
+  }
+}
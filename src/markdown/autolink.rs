@@ -0,0 +1,180 @@
+//! Autolink grammar: CommonMark `<...>` autolinks and GFM extended
+//! autolinks.
+
+use super::unicode::{is_punctuation, is_whitespace};
+
+/// Top-level domains that make a scheme-less bare domain (`docs.rs`) a link.
+const COMMON_TLDS: &[&str] = &[
+  "com", "org", "net", "edu", "gov", "io", "dev", "app", "rs", "co", "ai", "me", "info", "xyz",
+];
+
+/// CommonMark absolute URI: a 2-32 character scheme, `:`, then no spaces,
+/// control characters or angle brackets.
+pub fn is_absolute_uri(s: &str) -> bool {
+  let Some((scheme, rest)) = s.split_once(':') else {
+    return false;
+  };
+  let scheme = scheme.as_bytes();
+  (2..=32).contains(&scheme.len())
+    && scheme[0].is_ascii_alphabetic()
+    && scheme
+      .iter()
+      .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'.' | b'-'))
+    && !rest
+      .bytes()
+      .any(|b| b.is_ascii_control() || matches!(b, b' ' | b'<' | b'>'))
+}
+
+/// CommonMark email address: the HTML5 `type="email"` grammar.
+pub fn is_email(s: &str) -> bool {
+  let Some((local, domain)) = s.split_once('@') else {
+    return false;
+  };
+  !local.is_empty()
+    && local
+      .bytes()
+      .all(|b| b.is_ascii_alphanumeric() || b".!#$%&'*+/=?^_`{|}~-".contains(&b))
+    && domain.split('.').all(is_email_label)
+}
+
+fn is_email_label(label: &str) -> bool {
+  let bytes = label.as_bytes();
+  (1..=63).contains(&bytes.len())
+    && bytes[0].is_ascii_alphanumeric()
+    && bytes[bytes.len() - 1].is_ascii_alphanumeric()
+    && bytes
+      .iter()
+      .all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+}
+
+/// Trim GFM trailing punctuation from an extended autolink: `?!.,:*_~`, a
+/// `)` without a matching `(`, and a trailing entity reference (`&amp;`).
+pub fn trim_trailing(url: &str) -> &str {
+  let mut end = url.len();
+  while let Some(&last) = url.as_bytes()[..end].last() {
+    let s = &url[..end];
+    match last {
+      b'?' | b'!' | b'.' | b',' | b':' | b'*' | b'_' | b'~' => end -= 1,
+      b')' if s.matches(')').count() > s.matches('(').count() => end -= 1,
+      b';' => match s.rfind('&') {
+        Some(amp)
+          if amp + 2 < end
+            && s[amp + 1..end - 1]
+              .bytes()
+              .all(|b| b.is_ascii_alphanumeric()) =>
+        {
+          end = amp
+        }
+        _ => break,
+      },
+      _ => break,
+    }
+  }
+  &url[..end]
+}
+
+/// Length of the URL an `http://` or `https://` autolink at the start of
+/// `input` covers, after trimming; `None` without anything after `//`.
+pub fn url_len(input: &str) -> Option<usize> {
+  let rest = input
+    .strip_prefix("https://")
+    .or_else(|| input.strip_prefix("http://"))?;
+  let end = input.len() - rest.len() + rest.find(is_url_end).unwrap_or(rest.len());
+  let url = trim_trailing(&input[..end]);
+  (url.len() > input.len() - rest.len()).then_some(url.len())
+}
+
+/// Length of a scheme-less extended autolink at the start of `input`:
+/// `www.` followed by a domain, or a bare domain ending in a common
+/// top-level domain (`docs.rs/bukvar`).
+pub fn extended_len(input: &str) -> Option<usize> {
+  let end = input.find(is_url_end).unwrap_or(input.len());
+  let candidate = trim_trailing(&input[..end]);
+  let domain_end = candidate
+    .find(['/', '?', '#', ':'])
+    .unwrap_or(candidate.len());
+  let labels: Vec<&str> = candidate[..domain_end].split('.').collect();
+  if labels.len() < 2
+    || labels.iter().any(|label| {
+      label.is_empty()
+        || !label
+          .bytes()
+          .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_'))
+    })
+    || labels[labels.len() - 2..].iter().any(|l| l.contains('_'))
+  {
+    return None;
+  }
+  let tld = labels[labels.len() - 1].to_ascii_lowercase();
+  if !labels[0].eq_ignore_ascii_case("www") && !COMMON_TLDS.contains(&tld.as_str()) {
+    return None;
+  }
+  Some(candidate.len())
+}
+
+/// Whether an extended autolink may start after `before`, the character
+/// preceding it: at the start of the text, after whitespace, `*`, `_`, `~`
+/// or `(`, or after non-ASCII punctuation such as `«` or `（`.
+pub fn starts_word(before: Option<char>) -> bool {
+  before.map_or(true, |c| {
+    is_whitespace(c) || matches!(c, '*' | '_' | '~' | '(') || (!c.is_ascii() && is_punctuation(c))
+  })
+}
+
+/// Whitespace, `<`, `>` and `]` end a URL, as does non-ASCII punctuation
+/// (`。`, `»`), which running text puts right after a link.
+fn is_url_end(c: char) -> bool {
+  is_whitespace(c) || matches!(c, '<' | '>' | ']') || (!c.is_ascii() && is_punctuation(c))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_commonmark_autolinks() {
+    for uri in [
+      "http://x.dev/a?b",
+      "irc://foo.bar:2233/baz",
+      "a+b:c",
+      "MAILTO:x@y.z",
+    ] {
+      assert!(is_absolute_uri(uri), "{}", uri);
+    }
+    for uri in ["x:y", "1ab:c", "http://a b", "foo.bar", "https://a<b"] {
+      assert!(!is_absolute_uri(uri), "{}", uri);
+    }
+    assert!(is_email("foo.bar+tag@example-1.com"));
+    for email in [
+      "foo@",
+      "@x.com",
+      "a b@x.com",
+      "a@-x.com",
+      "a@x..com",
+      "a@b@c",
+    ] {
+      assert!(!is_email(email), "{}", email);
+    }
+  }
+
+  #[test]
+  fn test_trailing_punctuation() {
+    assert_eq!(trim_trailing("http://x.dev/a."), "http://x.dev/a");
+    assert_eq!(trim_trailing("http://x.dev/a_(b))"), "http://x.dev/a_(b)");
+    assert_eq!(
+      trim_trailing("http://x.dev/q?x=1&amp;"),
+      "http://x.dev/q?x=1"
+    );
+    assert_eq!(url_len("https://x.dev/a?!, more"), Some(15));
+    assert_eq!(url_len("http://"), None);
+  }
+
+  #[test]
+  fn test_extended() {
+    assert_eq!(extended_len("www.commonmark.org/help."), Some(23));
+    assert_eq!(extended_len("docs.rs/bukvar)"), Some(14));
+    assert_eq!(extended_len("main.c and"), None);
+    assert_eq!(extended_len("www."), None);
+    assert_eq!(extended_len("www.a_b.com"), None);
+  }
+}
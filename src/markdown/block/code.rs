@@ -2,16 +2,57 @@
 
 use super::BlockParser;
 use crate::ast::{Node, NodeKind, Span};
+use crate::markdown::attrs;
+
+/// LaTeX environments that start a display math block on their own.
+const MATH_ENVIRONMENTS: &[&str] = &[
+  "equation",
+  "equation*",
+  "align",
+  "align*",
+  "alignat",
+  "alignat*",
+  "gather",
+  "gather*",
+  "multline",
+  "multline*",
+  "flalign",
+  "flalign*",
+  "eqnarray",
+  "eqnarray*",
+  "displaymath",
+  "math",
+];
+
+/// Fence languages that hold diagram source, with the engine they go to.
+const DIAGRAM_ENGINES: &[(&str, &str)] = &[
+  ("mermaid", "mermaid"),
+  ("plantuml", "plantuml"),
+  ("puml", "plantuml"),
+  ("graphviz", "graphviz"),
+  ("dot", "graphviz"),
+];
 
 /// Parsed code block attributes from the info string.
-struct CodeBlockAttrs {
-  language: Option<String>,
-  highlight: Option<String>,
-  plusdiff: Option<String>,
-  minusdiff: Option<String>,
+#[derive(Default)]
+struct CodeBlockAttrs<'a> {
+  language: Option<&'a str>,
+  highlight: Option<&'a str>,
+  plusdiff: Option<&'a str>,
+  minusdiff: Option<&'a str>,
   linenumbers: bool,
 }
 
+impl CodeBlockAttrs<'_> {
+  /// Whether any attribute needs a `CodeBlockExt`.
+  fn has_ext(&self) -> bool {
+    self.highlight.is_some()
+      || self.plusdiff.is_some()
+      || self.minusdiff.is_some()
+      || self.linenumbers
+  }
+}
+
 impl<'a, 'b> BlockParser<'a, 'b> {
   pub fn try_fenced_code(&mut self, line: usize, col: usize) -> Option<Node> {
     let fence_char = self.scanner.peek()?;
@@ -31,33 +72,54 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     let info = self.scan_line_content();
     self.scanner.consume(b'\n');
 
-    let attrs = parse_code_attrs(&info);
+    let (info, mut attributes) = attrs::trailing(info).unwrap_or((info, Vec::new()));
+    let attrs = parse_code_attrs(info);
+    let language = match attrs.language {
+      Some(language) => Some(language.to_string()),
+      None => take_first_class(&mut attributes),
+    };
+    let content_start = self.scanner.pos();
+    let content_line = self.scanner.line();
     let code = self.scan_fenced_content(fence_char, fence_len);
+    let content_span = Span::new(content_start, content_start + code.len(), content_line, 1);
+
+    // Plain diagram fences become DiagramBlock; with attributes they stay
+    // code blocks so nothing is lost
+    let engine = language.as_deref().and_then(diagram_engine);
+    if let Some(engine) = engine.filter(|_| attributes.is_empty() && !attrs.has_ext()) {
+      return Some(Node::new(
+        NodeKind::DiagramBlock {
+          engine: engine.to_string(),
+          content: code,
+        },
+        Span::new(start, self.scanner.pos(), line, col),
+      ));
+    }
 
     // Use CodeBlockExt if any extended attributes are present
-    let kind = if attrs.highlight.is_some()
-      || attrs.plusdiff.is_some()
-      || attrs.minusdiff.is_some()
-      || attrs.linenumbers
-    {
+    let kind = if attrs.has_ext() {
+      let mut invalid_ranges = Vec::new();
       NodeKind::CodeBlockExt {
-        language: attrs.language,
-        highlight: attrs.highlight,
-        plusdiff: attrs.plusdiff,
-        minusdiff: attrs.minusdiff,
+        language,
+        highlight: ranges("highlight", attrs.highlight, &mut invalid_ranges),
+        plusdiff: ranges("plusdiff", attrs.plusdiff, &mut invalid_ranges),
+        minusdiff: ranges("minusdiff", attrs.minusdiff, &mut invalid_ranges),
         linenumbers: attrs.linenumbers,
+        invalid_ranges,
+        attributes,
       }
     } else {
       NodeKind::FencedCodeBlock {
-        language: attrs.language,
+        language,
         info: None,
+        attributes,
       }
     };
 
     Some(Node::with_children(
       kind,
       Span::new(start, self.scanner.pos(), line, col),
-      vec![Node::new(NodeKind::Text { content: code }, Span::empty())],
+      vec![Node::new(NodeKind::Text { content: code }, content_span)],
     ))
   }
 
@@ -106,79 +168,124 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     close_len >= fence_len
   }
 
+  /// `$$ ... $$` (when dollar math is on), `\[ ... \]` (when LaTeX math
+  /// is on) or a LaTeX math environment, as a display `MathBlock`.
   pub fn try_math_block(&mut self, line: usize, col: usize) -> Option<Node> {
-    if !self.scanner.check_str(b"$$") {
-      return None;
-    }
-
     let start = self.scanner.pos();
-    self.scanner.advance_n(2);
+    let (open, close): (&[u8], &[u8]) = if self.options.dollar_math && self.scanner.check_str(b"$$")
+    {
+      (b"$$", b"$$")
+    } else if self.options.latex_math && self.scanner.check_str(b"\\[") {
+      (b"\\[", b"\\]")
+    } else {
+      return self.try_math_environment(line, col);
+    };
+
+    self.scanner.advance_n(open.len());
     self.scanner.consume(b'\n');
 
-    let content = self.scan_math_content()?;
-    if content.is_none() {
+    let Some(content) = self.scan_math_content(close) else {
       self.scanner.set_pos(start);
       return None;
-    }
+    };
 
     Some(Node::new(
       NodeKind::MathBlock {
-        content: content.unwrap(),
+        content,
+        display: true,
       },
       Span::new(start, self.scanner.pos(), line, col),
     ))
   }
 
-  fn scan_math_content(&mut self) -> Option<Option<String>> {
+  /// Content up to `close`, which is consumed along with the rest of its
+  /// line. `None` if the block is never closed.
+  fn scan_math_content(&mut self, close: &[u8]) -> Option<String> {
     let content_start = self.scanner.pos();
 
-    loop {
-      if self.scanner.is_eof() {
-        return Some(None);
-      }
-      if self.scanner.check_str(b"$$") {
+    while !self.scanner.is_eof() {
+      if self.scanner.check_str(close) {
         let content = self
           .scanner
           .slice(content_start, self.scanner.pos())
           .trim_end()
           .to_string();
-        self.scanner.advance_n(2);
+        self.scanner.advance_n(close.len());
+        self.scanner.skip_whitespace_inline();
         self.scanner.consume(b'\n');
-        return Some(Some(content));
+        return Some(content);
       }
       self.scanner.advance();
     }
+    None
+  }
+
+  /// `\begin{align} ... \end{align}` and the other `MATH_ENVIRONMENTS`;
+  /// the content keeps the `\begin`/`\end` lines for the renderer.
+  fn try_math_environment(&mut self, line: usize, col: usize) -> Option<Node> {
+    let start = self.scanner.pos();
+    let rest = self.scanner.remaining();
+    let (name, _) = rest.strip_prefix("\\begin{")?.split_once('}')?;
+    if !MATH_ENVIRONMENTS.contains(&name) {
+      return None;
+    }
+    let end_tag = format!("\\end{{{}}}", name);
+    let len = rest.find(&end_tag)? + end_tag.len();
+    let content = rest[..len].to_string();
+
+    self.scanner.set_pos(start + len);
+    self.scanner.skip_whitespace_inline();
+    self.scanner.consume(b'\n');
+    Some(Node::new(
+      NodeKind::MathBlock {
+        content,
+        display: true,
+      },
+      Span::new(start, self.scanner.pos(), line, col),
+    ))
   }
 
   pub fn parse_indented_code(&mut self, line: usize, col: usize) -> Node {
     let start = self.scanner.pos();
-    let content = self.collect_indented_lines();
+    let (content, content_start, end) = self.collect_indented_lines();
 
     Node::with_children(
       NodeKind::IndentedCodeBlock,
       Span::new(start, self.scanner.pos(), line, col),
-      vec![Node::new(NodeKind::Text { content }, Span::empty())],
+      vec![Node::new(
+        NodeKind::Text { content },
+        Span::new(content_start, end, line, col + content_start - start),
+      )],
     )
   }
 
-  fn collect_indented_lines(&mut self) -> String {
+  /// Lines with their indent removed, plus the offsets where the first
+  /// line's code starts and the last line ends.
+  fn collect_indented_lines(&mut self) -> (String, usize, usize) {
     let mut content = String::new();
+    let mut content_start = None;
+    let mut end = self.scanner.pos();
 
     loop {
       let indent = self.skip_indent(4);
       if indent < 4 && !self.scanner.check(b'\n') && !self.scanner.is_eof() {
         break;
       }
+      let line_start = self.scanner.pos();
+      content_start.get_or_insert(line_start);
 
       self.append_line_chars(&mut content);
       content.push('\n');
+      if self.scanner.pos() > line_start {
+        end = self.scanner.pos();
+      }
 
       if !self.scanner.consume(b'\n') {
         break;
       }
     }
 
-    content
+    (content, content_start.unwrap_or(end), end)
   }
 
   fn skip_indent(&mut self, max: usize) -> usize {
@@ -190,128 +297,121 @@ impl<'a, 'b> BlockParser<'a, 'b> {
   }
 
   fn append_line_chars(&mut self, content: &mut String) {
+    let start = self.scanner.pos();
     while !self.scanner.is_eof() && !self.scanner.check(b'\n') {
-      if let Some(ch) = self
-        .scanner
-        .slice(self.scanner.pos(), self.scanner.pos() + 1)
-        .chars()
-        .next()
-      {
-        content.push(ch);
-      }
-      self.scanner.advance();
+      self.scanner.advance_char();
     }
+    content.push_str(self.scanner.slice(start, self.scanner.pos()));
   }
 }
 
-fn parse_code_attrs(info: &str) -> CodeBlockAttrs {
-  let info = info.trim();
-  if info.is_empty() {
-    return CodeBlockAttrs {
-      language: None,
-      highlight: None,
-      plusdiff: None,
-      minusdiff: None,
-      linenumbers: false,
-    };
-  }
-
-  let mut language = None;
-  let mut highlight = None;
-  let mut plusdiff = None;
-  let mut minusdiff = None;
-  let mut linenumbers = false;
-
-  let mut chars = info.chars().peekable();
-  let mut current = String::new();
+/// A fence with only an attribute list, as in ```` ```{.rust .numberLines} ````,
+/// takes its language from the first class.
+/// Engine for a diagram fence language, matched case-insensitively.
+fn diagram_engine(language: &str) -> Option<&'static str> {
+  DIAGRAM_ENGINES
+    .iter()
+    .find(|(name, _)| language.eq_ignore_ascii_case(name))
+    .map(|&(_, engine)| engine)
+}
 
-  // First token is the language (if not an attribute)
-  while let Some(&ch) = chars.peek() {
-    if ch.is_whitespace() {
-      break;
+fn take_first_class(attributes: &mut Vec<(String, String)>) -> Option<String> {
+  let i = attributes.iter().position(|(key, _)| key == "class")?;
+  let classes = &mut attributes[i].1;
+  match classes.split_once(' ') {
+    Some((first, rest)) => {
+      let first = first.to_string();
+      *classes = rest.to_string();
+      Some(first)
     }
-    current.push(ch);
-    chars.next();
+    None => Some(attributes.remove(i).1),
   }
+}
 
-  if !current.is_empty() && !current.contains('=') {
-    language = Some(current.clone());
-    current.clear();
-  }
+/// Parse a range attribute, recording it in `invalid` when malformed.
+fn ranges(name: &str, spec: Option<&str>, invalid: &mut Vec<(String, String)>) -> Vec<(u32, u32)> {
+  let Some(spec) = spec else {
+    return Vec::new();
+  };
+  parse_line_ranges(spec).unwrap_or_else(|| {
+    invalid.push((name.to_string(), spec.to_string()));
+    Vec::new()
+  })
+}
 
-  // Parse remaining attributes
-  while chars.peek().is_some() {
-    // Skip whitespace
-    while let Some(&ch) = chars.peek() {
-      if !ch.is_whitespace() {
-        break;
-      }
-      chars.next();
-    }
+/// `"3, 5-7"` as inclusive line ranges; `None` when any part is malformed,
+/// zero, or runs backwards.
+fn parse_line_ranges(spec: &str) -> Option<Vec<(u32, u32)>> {
+  spec
+    .split(',')
+    .map(|part| {
+      let part = part.trim();
+      let (start, end) = part.split_once('-').unwrap_or((part, part));
+      let start: u32 = start.trim().parse().ok()?;
+      let end: u32 = end.trim().parse().ok()?;
+      (start > 0 && start <= end).then_some((start, end))
+    })
+    .collect()
+}
 
-    // Read attribute name
-    let mut attr_name = String::new();
-    while let Some(&ch) = chars.peek() {
-      if ch == '=' || ch.is_whitespace() {
-        break;
-      }
-      attr_name.push(ch);
-      chars.next();
-    }
+/// Info string words: a language first, then `name=value` (the value
+/// optionally quoted) and bare `linenumbers` attributes. Borrowed from
+/// the line until the node is built.
+fn parse_code_attrs(info: &str) -> CodeBlockAttrs<'_> {
+  let mut attrs = CodeBlockAttrs::default();
+  let mut rest = info.trim();
 
-    if attr_name.is_empty() {
+  // First token is the language (if not an attribute)
+  let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+  if end > 0 && !rest[..end].contains('=') {
+    attrs.language = Some(&rest[..end]);
+    rest = &rest[end..];
+  }
+
+  loop {
+    rest = rest.trim_start();
+    let end = rest
+      .find(|c: char| c == '=' || c.is_whitespace())
+      .unwrap_or(rest.len());
+    if end == 0 {
       break;
     }
+    let name = &rest[..end];
+    rest = &rest[end..];
 
-    // Check for boolean attribute (no =)
-    if chars.peek() != Some(&'=') {
-      if attr_name.to_lowercase() == "linenumbers" {
-        linenumbers = true;
+    // Boolean attribute (no =)
+    let Some(after) = rest.strip_prefix('=') else {
+      if name.eq_ignore_ascii_case("linenumbers") {
+        attrs.linenumbers = true;
       }
       continue;
-    }
-
-    chars.next(); // skip =
-
-    // Read attribute value
-    let mut attr_value = String::new();
-    let quote_char = chars.peek().copied();
+    };
 
-    if quote_char == Some('"') || quote_char == Some('\'') {
-      chars.next(); // skip opening quote
-      while let Some(&ch) = chars.peek() {
-        if ch == quote_char.unwrap() {
-          chars.next(); // skip closing quote
-          break;
-        }
-        attr_value.push(ch);
-        chars.next();
+    let value;
+    match after.chars().next() {
+      Some(quote @ ('"' | '\'')) => {
+        let quoted = &after[1..];
+        let close = quoted.find(quote).unwrap_or(quoted.len());
+        value = &quoted[..close];
+        rest = quoted.get(close + 1..).unwrap_or("");
       }
-    } else {
-      // Unquoted value
-      while let Some(&ch) = chars.peek() {
-        if ch.is_whitespace() {
-          break;
-        }
-        attr_value.push(ch);
-        chars.next();
+      _ => {
+        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+        value = &after[..end];
+        rest = &after[end..];
       }
     }
 
-    match attr_name.to_lowercase().as_str() {
-      "highlight" => highlight = Some(attr_value),
-      "plusdiff" => plusdiff = Some(attr_value),
-      "minusdiff" => minusdiff = Some(attr_value),
-      "linenumbers" => linenumbers = !attr_value.is_empty() && attr_value != "false",
-      _ => {}
+    if name.eq_ignore_ascii_case("highlight") {
+      attrs.highlight = Some(value);
+    } else if name.eq_ignore_ascii_case("plusdiff") {
+      attrs.plusdiff = Some(value);
+    } else if name.eq_ignore_ascii_case("minusdiff") {
+      attrs.minusdiff = Some(value);
+    } else if name.eq_ignore_ascii_case("linenumbers") {
+      attrs.linenumbers = !value.is_empty() && value != "false";
     }
   }
 
-  CodeBlockAttrs {
-    language,
-    highlight,
-    plusdiff,
-    minusdiff,
-    linenumbers,
-  }
+  attrs
 }
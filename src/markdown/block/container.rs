@@ -1,6 +1,6 @@
-//! Container block elements: blockquotes, lists.
+//! Container block elements: blockquotes, lists, footnote definitions.
 
-use super::BlockParser;
+use super::{BlockParser, NestedContent};
 use crate::ast::{AlertType, ListMarker, Node, NodeKind, Span};
 
 impl<'a, 'b> BlockParser<'a, 'b> {
@@ -8,8 +8,12 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     let start = self.scanner.pos();
     let (content, alert_type) = self.collect_blockquote_content_with_alert();
 
-    let mut inner = super::super::MarkdownParser::new(&content);
-    let inner_doc = inner.parse();
+    let path = if alert_type.is_some() {
+      "Alert"
+    } else {
+      "BlockQuote"
+    };
+    let children = self.parse_nested(&[path], &content);
 
     let kind = match alert_type {
       Some(at) => NodeKind::Alert { alert_type: at },
@@ -19,12 +23,12 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     Node::with_children(
       kind,
       Span::new(start, self.scanner.pos(), line, col),
-      inner_doc.nodes,
+      children,
     )
   }
 
-  fn collect_blockquote_content_with_alert(&mut self) -> (String, Option<AlertType>) {
-    let mut content = String::new();
+  fn collect_blockquote_content_with_alert(&mut self) -> (NestedContent, Option<AlertType>) {
+    let mut content = NestedContent::default();
     let mut alert_type = None;
     let mut first_line = true;
 
@@ -41,13 +45,71 @@ impl<'a, 'b> BlockParser<'a, 'b> {
       }
 
       self.append_line_to(&mut content);
-      content.push('\n');
       self.scanner.consume(b'\n');
     }
 
     (content, alert_type)
   }
 
+  /// `[^label]: text`; following lines indented by four spaces or a tab
+  /// (blank lines between them included) continue the definition.
+  pub fn try_footnote_definition(&mut self, line: usize, col: usize) -> Option<Node> {
+    let start = self.scanner.pos();
+    if !self.scanner.check_str(b"[^") {
+      return None;
+    }
+    self.scanner.advance_n(2);
+    let label = match self.scanner.scan_until(b']') {
+      Some(label) if !label.is_empty() && !label.contains(char::is_whitespace) => label,
+      _ => {
+        self.scanner.set_pos(start);
+        return None;
+      }
+    };
+    self.scanner.advance(); // ]
+    if !self.scanner.consume(b':') {
+      self.scanner.set_pos(start);
+      return None;
+    }
+    self.scanner.skip_whitespace_inline();
+
+    let mut content = NestedContent::default();
+    self.append_line_to(&mut content);
+    self.scanner.consume(b'\n');
+
+    let first_line = self.scanner.line();
+    let mut offset = self.scanner.pos();
+    let mut end = offset;
+    let mut blank = Vec::new();
+    for (i, raw) in self.scanner.remaining().split_inclusive('\n').enumerate() {
+      let source_line = first_line + i;
+      let text = raw.trim_end_matches(['\n', '\r']);
+      if text.trim().is_empty() {
+        blank.push((offset, source_line));
+      } else if let Some(body) = text
+        .strip_prefix("    ")
+        .or_else(|| text.strip_prefix('\t'))
+      {
+        for (blank_offset, blank_line) in blank.drain(..) {
+          content.push_line("", blank_offset, blank_line, 1);
+        }
+        let prefix = text.len() - body.len();
+        content.push_line(body, offset + prefix, source_line, 1 + prefix);
+        end = offset + raw.len();
+      } else {
+        break;
+      }
+      offset += raw.len();
+    }
+    self.scanner.set_pos(end);
+
+    Some(Node::with_children(
+      NodeKind::FootnoteDefinition { label, index: 0 },
+      Span::new(start, self.scanner.pos(), line, col),
+      self.parse_nested(&["FootnoteDefinition"], &content),
+    ))
+  }
+
   fn try_parse_alert_marker(&mut self) -> Option<AlertType> {
     let pos = self.scanner.pos();
 
@@ -61,42 +123,39 @@ impl<'a, 'b> BlockParser<'a, 'b> {
       self.scanner.advance();
     }
 
-    let marker = self.scanner.slice(start, self.scanner.pos()).to_uppercase();
+    let marker = self.scanner.slice(start, self.scanner.pos());
 
     if !self.scanner.consume(b']') {
       self.scanner.set_pos(pos);
       return None;
     }
 
-    match marker.as_str() {
-      "NOTE" => Some(AlertType::Note),
-      "TIP" => Some(AlertType::Tip),
-      "IMPORTANT" => Some(AlertType::Important),
-      "WARNING" => Some(AlertType::Warning),
-      "CAUTION" => Some(AlertType::Caution),
-      _ => {
-        self.scanner.set_pos(pos);
-        None
-      }
+    let alert_type = self.elements.alert(marker);
+    if alert_type.is_none() {
+      self.scanner.set_pos(pos);
     }
+    alert_type
   }
 
-  fn append_line_to(&mut self, content: &mut String) {
+  /// Add the rest of the current line to `content`, leaving the scanner
+  /// at the newline.
+  fn append_line_to(&mut self, content: &mut NestedContent) {
+    let start = self.scanner.pos();
+    let (line, col) = (self.scanner.line(), self.scanner.column());
     while !self.scanner.is_eof() && !self.scanner.check(b'\n') {
-      if let Some(ch) = self
-        .scanner
-        .slice(self.scanner.pos(), self.scanner.pos() + 1)
-        .chars()
-        .next()
-      {
-        content.push(ch);
-      }
       self.scanner.advance();
     }
+    content.push_line(
+      self.scanner.slice(start, self.scanner.pos()),
+      start,
+      line,
+      col,
+    );
   }
 
   pub fn parse_list(&mut self, ordered: bool) -> Node {
     let start = self.scanner.pos();
+    let line = self.scanner.line();
     let items = self.collect_list_items();
 
     Node::with_children(
@@ -105,7 +164,7 @@ impl<'a, 'b> BlockParser<'a, 'b> {
         start: None,
         tight: true,
       },
-      Span::new(start, self.scanner.pos(), 0, 0),
+      Span::new(start, self.scanner.pos(), line, 1),
       items,
     )
   }
@@ -118,10 +177,11 @@ impl<'a, 'b> BlockParser<'a, 'b> {
         break;
       }
 
-      self.scanner.advance(); // skip marker
+      let marker = self.scanner.peek().unwrap_or(b'-') as char;
+      self.scanner.advance();
       self.scanner.consume(b' ');
 
-      items.push(self.parse_list_item());
+      items.push(self.parse_list_item(marker));
     }
 
     items
@@ -131,22 +191,28 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     matches!(self.scanner.peek(), Some(b'-' | b'*' | b'+'))
   }
 
-  fn parse_list_item(&mut self) -> Node {
+  fn parse_list_item(&mut self, marker: char) -> Node {
     let item_start = self.scanner.pos();
-    let content = self.scan_line_content();
+    let (line, col) = (self.scanner.line(), self.scanner.column());
+    let (offset, content) = self.scan_line_text();
     self.scanner.consume(b'\n');
 
-    let inline = self.parse_inline(&content);
+    let inline = self.parse_inline(&["List", "ListItem"], content, offset);
 
     Node::with_children(
       NodeKind::ListItem {
-        marker: ListMarker::Bullet('-'),
+        marker: ListMarker::Bullet(marker),
         checked: None,
       },
-      Span::new(item_start, self.scanner.pos(), 0, 0),
+      Span::new(item_start, self.scanner.pos(), line, col),
       vec![Node::with_children(
         NodeKind::Paragraph,
-        Span::empty(),
+        Span::new(
+          offset,
+          offset + content.len(),
+          line,
+          col + offset - item_start,
+        ),
         inline,
       )],
     )
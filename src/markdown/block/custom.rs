@@ -1,10 +1,11 @@
-//! Custom HTML elements: steps, toc, tabs.
+//! Custom HTML elements: steps, toc, tabs, include, and registry-declared elements.
 
-use super::BlockParser;
+use super::{BlockParser, KindFilter, NestedContent};
 use crate::ast::{Node, NodeKind, Span};
 
 impl<'a, 'b> BlockParser<'a, 'b> {
-  /// Try to parse custom Glagolica elements: `<steps>`, `<toc>`, `<tabs>`.
+  /// Try to parse custom Glagolica elements: `<steps>`, `<toc>`, `<tabs>`,
+  /// `<include>`, then any element declared in the `ElementRegistry`.
   pub fn try_custom_element(&mut self, line: usize, col: usize) -> Option<Node> {
     if !self.scanner.check(b'<') {
       return None;
@@ -25,9 +26,170 @@ impl<'a, 'b> BlockParser<'a, 'b> {
       return Some(node);
     }
 
+    if let Some(node) = self.try_include(start, line, col) {
+      return Some(node);
+    }
+
+    if let Some(node) = self.try_registered(start, line, col) {
+      return Some(node);
+    }
+
+    self.scanner.set_pos(start);
     None
   }
 
+  /// `<include src="..." />`; resolved later by the processor.
+  fn try_include(&mut self, start: usize, line: usize, col: usize) -> Option<Node> {
+    self.scanner.set_pos(start);
+
+    if !self.scanner.check_str(b"<include") {
+      return None;
+    }
+    self.scanner.advance_n(8);
+
+    let (attributes, self_closing) = self.parse_attributes()?;
+    let src = attributes.into_iter().find(|(key, _)| key == "src")?.1;
+
+    self.scanner.skip_whitespace_inline();
+    if !self_closing && self.scanner.check_str(b"</include>") {
+      self.scanner.advance_n(10);
+      self.scanner.skip_whitespace_inline();
+    }
+    self.scanner.consume(b'\n');
+
+    Some(Node::new(
+      NodeKind::Include { src },
+      Span::new(start, self.scanner.pos(), line, col),
+    ))
+  }
+
+  fn try_registered(&mut self, start: usize, line: usize, col: usize) -> Option<Node> {
+    if self.elements.is_empty() {
+      return None;
+    }
+    self.scanner.set_pos(start + 1);
+
+    let name_start = self.scanner.pos();
+    while matches!(self.scanner.peek(), Some(b) if b.is_ascii_alphanumeric() || b == b'-') {
+      self.scanner.advance();
+    }
+    let name = self.scanner.slice(name_start, self.scanner.pos());
+    let def = self.elements.get(name)?;
+
+    let Some((attributes, self_closing)) = self.parse_attributes() else {
+      self.scanner.set_pos(start);
+      return None;
+    };
+    let attributes = attributes
+      .into_iter()
+      .filter(|(key, _)| def.accepts(key))
+      .collect();
+    let kind = NodeKind::CustomElement {
+      name: name.to_string(),
+      attributes,
+    };
+
+    self.scanner.skip_whitespace_inline();
+    self.scanner.consume(b'\n');
+    if self_closing {
+      return Some(Node::new(
+        kind,
+        Span::new(start, self.scanner.pos(), line, col),
+      ));
+    }
+
+    let close_tag = format!("</{}>", name);
+    let content = self.collect_until_close_tag(close_tag.as_bytes());
+    let children = self.parse_nested(&["CustomElement"], &content);
+
+    Some(Node::with_children(
+      kind,
+      Span::new(start, self.scanner.pos(), line, col),
+      children,
+    ))
+  }
+
+  /// Parse `key="value"` pairs up to `>` or `/>` on the opening line.
+  /// Returns the attributes and whether the tag was self-closing.
+  fn parse_attributes(&mut self) -> Option<(Vec<(String, String)>, bool)> {
+    let mut attributes = Vec::new();
+
+    loop {
+      let before = self.scanner.pos();
+      self.scanner.skip_whitespace_inline();
+      let had_space = self.scanner.pos() > before;
+
+      if self.scanner.consume(b'>') {
+        return Some((attributes, false));
+      }
+      if self.scanner.check_str(b"/>") {
+        self.scanner.advance_n(2);
+        return Some((attributes, true));
+      }
+      if !had_space {
+        return None;
+      }
+
+      let key_start = self.scanner.pos();
+      while matches!(
+        self.scanner.peek(),
+        Some(b) if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b':')
+      ) {
+        self.scanner.advance();
+      }
+      if self.scanner.pos() == key_start {
+        return None;
+      }
+      let key = self
+        .scanner
+        .slice(key_start, self.scanner.pos())
+        .to_string();
+
+      let value = if self.scanner.consume(b'=') {
+        self.parse_attribute_value()?
+      } else {
+        String::new()
+      };
+      attributes.push((key, value));
+    }
+  }
+
+  fn parse_attribute_value(&mut self) -> Option<String> {
+    match self.scanner.peek()? {
+      quote @ (b'"' | b'\'') => {
+        self.scanner.advance();
+        let value_start = self.scanner.pos();
+        while !self.scanner.is_eof()
+          && !matches!(self.scanner.peek(), Some(b) if b == quote || b == b'\n')
+        {
+          self.scanner.advance();
+        }
+        if !self.scanner.check(quote) {
+          return None;
+        }
+        let value = self
+          .scanner
+          .slice(value_start, self.scanner.pos())
+          .to_string();
+        self.scanner.advance();
+        Some(value)
+      }
+      _ => {
+        let value_start = self.scanner.pos();
+        while matches!(self.scanner.peek(), Some(b) if !b.is_ascii_whitespace() && b != b'>' && b != b'/')
+        {
+          self.scanner.advance();
+        }
+        Some(
+          self
+            .scanner
+            .slice(value_start, self.scanner.pos())
+            .to_string(),
+        )
+      }
+    }
+  }
+
   fn try_toc(&mut self, start: usize, line: usize, col: usize) -> Option<Node> {
     self.scanner.set_pos(start);
 
@@ -101,7 +263,7 @@ impl<'a, 'b> BlockParser<'a, 'b> {
       }
 
       // Try to parse a <step> element
-      if let Some(step) = self.try_step() {
+      if let Some(step) = self.try_step(steps.len() as u32 + 1) {
         steps.push(step);
       } else {
         // Skip unknown content
@@ -116,37 +278,41 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     ))
   }
 
-  fn try_step(&mut self) -> Option<Node> {
+  /// `<step title="...">` with markdown content, which may hold nested
+  /// `<steps>` and `<tabs>`.
+  fn try_step(&mut self, number: u32) -> Option<Node> {
     let start = self.scanner.pos();
     let line = self.scanner.line();
     let col = self.scanner.column();
 
-    if !self.scanner.check_str(b"<step>") && !self.scanner.check_str(b"<step ") {
+    if !self.at_open_tag(b"<step") {
       return None;
     }
-
-    // Skip <step and find >
     self.scanner.advance_n(5);
-    while !self.scanner.is_eof() && !self.scanner.check(b'>') {
-      self.scanner.advance();
-    }
-    if !self.scanner.consume(b'>') {
+
+    let Some((attributes, self_closing)) = self.parse_attributes() else {
       self.scanner.set_pos(start);
       return None;
-    }
+    };
+    let title = attributes
+      .into_iter()
+      .find(|(key, _)| key == "title")
+      .map(|(_, value)| value);
+    self.scanner.skip_whitespace_inline();
     self.scanner.consume(b'\n');
 
-    // Collect content until </step>
-    let content = self.collect_until_close_tag(b"</step>");
-
-    // Parse the inner content as markdown
-    let mut inner = super::super::MarkdownParser::new(&content);
-    let inner_doc = inner.parse();
+    // Parse the content up to </step> as markdown
+    let children = if self_closing {
+      Vec::new()
+    } else {
+      let content = self.collect_until_close_tag(b"</step>");
+      self.parse_nested(&["Steps", "Step"], &content)
+    };
 
     Some(Node::with_children(
-      NodeKind::Step,
+      NodeKind::Step { number, title },
       Span::new(start, self.scanner.pos(), line, col),
-      inner_doc.nodes,
+      children,
     ))
   }
 
@@ -173,20 +339,88 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     }
     self.scanner.consume(b'\n');
 
-    // Collect content until </tabs>
-    let content = self.collect_until_close_tag(b"</tabs>");
-
-    // Parse inner content (code blocks)
-    let mut inner = super::super::MarkdownParser::new(&content);
-    let inner_doc = inner.parse();
+    let body = self.scanner.pos();
+    self.scanner.skip_blank_lines();
+    self.scanner.skip_whitespace_inline();
+    let explicit = self.at_open_tag(b"<tab");
+    self.scanner.set_pos(body);
+
+    let children = if explicit {
+      self.parse_explicit_tabs(&names)
+    } else {
+      // Collect content until </tabs>, one tab per code block
+      let content = self.collect_until_close_tag(b"</tabs>");
+      split_tabs(self.parse_nested_with(KindFilter::ALL, &content), &names)
+    };
 
     Some(Node::with_children(
       NodeKind::Tabs { names },
       Span::new(start, self.scanner.pos(), line, col),
-      inner_doc.nodes,
+      children,
     ))
   }
 
+  /// `<tab name="...">` children; unnamed tabs take the next `names` entry.
+  fn parse_explicit_tabs(&mut self, names: &[String]) -> Vec<Node> {
+    let mut tabs = Vec::new();
+
+    while !self.scanner.is_eof() {
+      self.scanner.skip_blank_lines();
+      self.scanner.skip_whitespace_inline();
+
+      if self.scanner.check_str(b"</tabs>") {
+        self.scanner.advance_n(7);
+        self.scanner.consume(b'\n');
+        break;
+      }
+
+      let start = self.scanner.pos();
+      let line = self.scanner.line();
+      let col = self.scanner.column();
+      if !self.at_open_tag(b"<tab") {
+        self.scanner.skip_line();
+        continue;
+      }
+      self.scanner.advance_n(4);
+      let Some((attributes, self_closing)) = self.parse_attributes() else {
+        self.scanner.skip_line();
+        continue;
+      };
+      self.scanner.skip_whitespace_inline();
+      self.scanner.consume(b'\n');
+
+      let children = if self_closing {
+        Vec::new()
+      } else {
+        let content = self.collect_until_close_tag(b"</tab>");
+        self.parse_nested(&["Tabs", "Tab"], &content)
+      };
+      let name = attributes
+        .into_iter()
+        .find(|(key, _)| key == "name")
+        .map(|(_, value)| value)
+        .or_else(|| names.get(tabs.len()).cloned())
+        .unwrap_or_default();
+
+      tabs.push(Node::with_children(
+        NodeKind::Tab { name },
+        Span::new(start, self.scanner.pos(), line, col),
+        children,
+      ));
+    }
+
+    tabs
+  }
+
+  /// `tag` followed by whitespace, `>` or `/`, so `<tab` does not match `<tabs>`.
+  fn at_open_tag(&self, tag: &[u8]) -> bool {
+    self.scanner.check_str(tag)
+      && matches!(
+        self.scanner.peek_at(tag.len()),
+        Some(b' ' | b'\t' | b'\n' | b'>' | b'/')
+      )
+  }
+
   fn parse_tabs_names(&mut self) -> Option<Vec<String>> {
     // Look for names="..."
     if !self.scanner.check_str(b"names=") {
@@ -218,8 +452,11 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     Some(names)
   }
 
-  fn collect_until_close_tag(&mut self, close_tag: &[u8]) -> String {
-    let mut content = String::new();
+  /// Body up to the matching `close_tag`, skipping nested same-name tags.
+  fn collect_until_close_tag(&mut self, close_tag: &[u8]) -> NestedContent {
+    let start = self.scanner.pos();
+    let (line, col) = (self.scanner.line(), self.scanner.column());
+    let mut end = None;
     let mut depth = 1;
 
     // Determine the open tag from close tag (e.g., </step> -> <step)
@@ -235,6 +472,7 @@ impl<'a, 'b> BlockParser<'a, 'b> {
         let pos = self.scanner.pos();
         self.scanner.skip_whitespace_inline();
         if self.scanner.check_str(close_tag) {
+          end = Some(pos);
           self.scanner.advance_n(close_tag.len());
           self.scanner.consume(b'\n');
           break;
@@ -243,29 +481,58 @@ impl<'a, 'b> BlockParser<'a, 'b> {
       }
 
       // Track nested tags
-      if self.scanner.check_str(&open_tag) {
+      if self.at_open_tag(&open_tag) {
         depth += 1;
       } else if self.scanner.check_str(close_tag) {
         depth -= 1;
         if depth == 0 {
+          end = Some(self.scanner.pos());
           self.scanner.advance_n(close_tag.len());
           self.scanner.consume(b'\n');
           break;
         }
       }
 
-      // Append current character
-      if let Some(ch) = self
-        .scanner
-        .slice(self.scanner.pos(), self.scanner.pos() + 1)
-        .chars()
-        .next()
-      {
-        content.push(ch);
-      }
       self.scanner.advance();
     }
 
-    content
+    let end = end.unwrap_or(self.scanner.pos());
+    NestedContent::contiguous(self.scanner.slice(start, end), start, line, col)
   }
 }
+
+/// Group implicit tab content: each code block starts a tab, taking any
+/// prose before it; prose after the last code block joins the last tab.
+pub(super) fn split_tabs(nodes: Vec<Node>, names: &[String]) -> Vec<Node> {
+  let mut groups: Vec<Vec<Node>> = Vec::new();
+  let mut pending = Vec::new();
+
+  for node in nodes {
+    let is_code = matches!(
+      node.kind,
+      NodeKind::FencedCodeBlock { .. } | NodeKind::CodeBlockExt { .. } | NodeKind::CodeBlock { .. }
+    );
+    pending.push(node);
+    if is_code {
+      groups.push(std::mem::take(&mut pending));
+    }
+  }
+  match groups.last_mut() {
+    Some(last) => last.append(&mut pending),
+    None if !pending.is_empty() => groups.push(pending),
+    None => {}
+  }
+
+  groups
+    .into_iter()
+    .enumerate()
+    .map(|(i, children)| {
+      let name = names.get(i).cloned().unwrap_or_default();
+      let span = match (children.first(), children.last()) {
+        (Some(first), Some(last)) => first.span.merge(last.span),
+        _ => Span::empty(),
+      };
+      Node::with_children(NodeKind::Tab { name }, span, children)
+    })
+    .collect()
+}
@@ -0,0 +1,223 @@
+//! Fenced directives in the VuePress and Docusaurus style:
+//!
+//! ```text
+//! ::: warning Mind the gap
+//! Markdown content
+//! :::
+//! ```
+//!
+//! Admonition names become an `Alert`, `tabs` and `code-group` a `Tabs`, and
+//! any other name a `CustomElement`. Directives nest, either by using more
+//! colons on the outer fence or by closing each inner one in turn.
+
+use super::custom::split_tabs;
+use super::{BlockParser, KindFilter, NestedContent};
+use crate::ast::{Node, NodeKind, Span};
+use crate::markdown::attrs;
+
+/// Opening line: `::: name title`, `:::name[title]` or either with a
+/// trailing `{#id .class key=value}` list.
+struct Opener<'a> {
+  name: &'a str,
+  /// Title and its offset in the line
+  title: Option<(usize, &'a str)>,
+  attributes: Vec<(String, String)>,
+}
+
+/// One line of a directive body.
+struct BodyLine<'a> {
+  start: usize,
+  line: usize,
+  text: &'a str,
+}
+
+impl<'a, 'b> BlockParser<'a, 'b> {
+  pub fn try_directive(&mut self, line: usize, col: usize) -> Option<Node> {
+    let start = self.scanner.pos();
+    let opener_text = self.scan_line_content();
+    let Some(opener) = parse_opener(opener_text) else {
+      self.scanner.set_pos(start);
+      return None;
+    };
+    self.scanner.consume(b'\n');
+
+    let body = self.collect_directive_body();
+    let span = Span::new(start, self.scanner.pos(), line, col);
+
+    let node = match (self.elements.alert(opener.name), opener.name) {
+      (Some(alert_type), _) => {
+        let mut children = Vec::new();
+        if let Some((offset, title)) = opener.title {
+          let title_span = Span::new(
+            start + offset,
+            start + offset + title.len(),
+            line,
+            col + offset,
+          );
+          children.push(self.title_paragraph(title, title_span));
+        }
+        children.extend(self.parse_nested(&["Alert"], &self.body_content(&body)));
+        Node::with_children(NodeKind::Alert { alert_type }, span, children)
+      }
+      (None, "tabs" | "code-group") => {
+        let tabs = self.parse_directive_tabs(&body);
+        let names = tabs
+          .iter()
+          .map(|tab| match &tab.kind {
+            NodeKind::Tab { name } => name.clone(),
+            _ => String::new(),
+          })
+          .collect();
+        Node::with_children(NodeKind::Tabs { names }, span, tabs)
+      }
+      (None, name) => {
+        let mut attributes = opener.attributes;
+        if let Some((_, title)) = opener.title {
+          attributes.push(("title".to_string(), title.to_string()));
+        }
+        Node::with_children(
+          NodeKind::CustomElement {
+            name: name.to_string(),
+            attributes,
+          },
+          span,
+          self.parse_nested(&["CustomElement"], &self.body_content(&body)),
+        )
+      }
+    };
+    Some(node)
+  }
+
+  /// Lines up to the matching `:::`, which is consumed. Openers inside
+  /// count toward the nesting depth; an unclosed directive runs to the end.
+  fn collect_directive_body(&mut self) -> Vec<BodyLine<'b>> {
+    let mut lines = Vec::new();
+    let mut depth = 1;
+    while !self.scanner.is_eof() {
+      let start = self.scanner.pos();
+      let line = self.scanner.line();
+      self.scanner.skip_line();
+      let text = self.scanner.slice(start, self.scanner.pos());
+      let trimmed = text.trim();
+      if trimmed.len() >= 3 && trimmed.bytes().all(|b| b == b':') {
+        depth -= 1;
+        if depth == 0 {
+          break;
+        }
+      } else if parse_opener(trimmed).is_some() {
+        depth += 1;
+      }
+      lines.push(BodyLine { start, line, text });
+    }
+    lines
+  }
+
+  /// Tabs split at `@tab Name` lines; without any, one tab per code block,
+  /// named after its language. Content before the first `@tab` is an
+  /// unnamed tab of its own.
+  fn parse_directive_tabs(&self, body: &[BodyLine]) -> Vec<Node> {
+    let mut markers: Vec<usize> = (0..body.len())
+      .filter(|&i| tab_name(body[i].text).is_some())
+      .collect();
+    if markers.is_empty() {
+      // Tabs are split at code blocks, so build them all; the tabs are
+      // filtered once built
+      let nodes = self.parse_nested_with(KindFilter::ALL, &self.body_content(body));
+      let names: Vec<String> = nodes
+        .iter()
+        .filter_map(|node| match &node.kind {
+          NodeKind::FencedCodeBlock { language, .. }
+          | NodeKind::CodeBlockExt { language, .. }
+          | NodeKind::CodeBlock { language, .. } => Some(language.clone().unwrap_or_default()),
+          _ => None,
+        })
+        .collect();
+      return split_tabs(nodes, &names);
+    }
+
+    if body[..markers[0]].iter().any(|l| !l.text.trim().is_empty()) {
+      markers.insert(0, 0);
+    }
+    markers
+      .iter()
+      .enumerate()
+      .map(|(n, &i)| {
+        let end = markers.get(n + 1).copied().unwrap_or(body.len());
+        let (name, lines) = match tab_name(body[i].text) {
+          Some(name) => (name.to_string(), &body[i + 1..end]),
+          None => (String::new(), &body[i..end]),
+        };
+        let last = &body[end - 1];
+        let end_pos = last.start + last.text.len();
+        let span = Span::new(body[i].start, end_pos, body[i].line, 1);
+        let children = self.parse_nested(&["Tabs", "Tab"], &self.body_content(lines));
+        Node::with_children(NodeKind::Tab { name }, span, children)
+      })
+      .collect()
+  }
+
+  /// Nested content of consecutive body lines.
+  fn body_content(&self, lines: &[BodyLine]) -> NestedContent {
+    match (lines.first(), lines.last()) {
+      (Some(first), Some(last)) => NestedContent::contiguous(
+        self
+          .scanner
+          .slice(first.start, last.start + last.text.len()),
+        first.start,
+        first.line,
+        1,
+      ),
+      _ => NestedContent::default(),
+    }
+  }
+
+  /// Bold title line of a titled admonition.
+  fn title_paragraph(&self, title: &str, span: Span) -> Node {
+    let strong = Node::with_children(
+      NodeKind::Strong,
+      span,
+      self.parse_inline(&["Alert", "Paragraph", "Strong"], title, span.start),
+    );
+    Node::with_children(NodeKind::Paragraph, span, vec![strong])
+  }
+}
+
+fn parse_opener(line: &str) -> Option<Opener<'_>> {
+  let colons = line.bytes().take_while(|&b| b == b':').count();
+  if colons < 3 {
+    return None;
+  }
+  let rest = line[colons..].trim_start();
+  let name_len = rest
+    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_')))
+    .unwrap_or(rest.len());
+  if name_len == 0 {
+    return None;
+  }
+  let (name, mut rest) = rest.split_at(name_len);
+
+  let offset = |part: &str| line.len() - part.len();
+  let mut title = None;
+  if let Some(bracketed) = rest.strip_prefix('[') {
+    let close = bracketed.find(']')?;
+    title = Some((offset(bracketed), &bracketed[..close]));
+    rest = &bracketed[close + 1..];
+  }
+  let rest = rest.trim_start();
+  let rest_offset = offset(rest);
+  let (rest, attributes) = attrs::trailing(rest).unwrap_or((rest, Vec::new()));
+  if title.is_none() {
+    title = Some((rest_offset, rest.trim_end()));
+  }
+  Some(Opener {
+    name,
+    title: title.filter(|(_, t)| !t.is_empty()),
+    attributes,
+  })
+}
+
+/// Name on a `@tab Name` line.
+fn tab_name(line: &str) -> Option<&str> {
+  let rest = line.trim().strip_prefix("@tab")?;
+  (rest.is_empty() || rest.starts_with([' ', '\t'])).then(|| rest.trim())
+}
@@ -1,7 +1,9 @@
-//! Leaf block elements: headings, thematic breaks, paragraphs.
+//! Leaf block elements: headings, thematic breaks, paragraphs, comments,
+//! link and abbreviation definitions.
 
 use super::BlockParser;
 use crate::ast::{Node, NodeKind, Span};
+use crate::markdown::{abbr, attrs, linkdef};
 
 impl<'a, 'b> BlockParser<'a, 'b> {
   pub fn try_thematic_break(&mut self, line: usize, col: usize) -> Option<Node> {
@@ -48,28 +50,42 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     let start = self.scanner.pos();
     let level = self.count_hashes();
 
-    if level == 0 || !self.is_valid_heading_start() {
+    if !(1..=6).contains(&level) || !self.is_valid_heading_start() {
       self.scanner.set_pos(start);
       return None;
     }
 
     self.scanner.skip_whitespace_inline();
-    let content = self.scan_heading_content();
+    let offset = self.scanner.pos();
+    while !self.scanner.is_eof() && !self.scanner.check(b'\n') {
+      self.scanner.advance();
+    }
+    let content = strip_closing_hashes(self.scanner.slice(offset, self.scanner.pos()));
     self.scanner.consume(b'\n');
 
-    let (text, id) = extract_heading_id(&content);
-    let inline = self.parse_inline(text);
+    let (text, mut attributes) = attrs::trailing(content).unwrap_or((content, Vec::new()));
+    let id = attributes
+      .iter()
+      .position(|(key, _)| key == "id")
+      .map(|i| attributes.remove(i).1);
+    let inline = self.parse_inline(&["Heading"], text, offset);
 
     Some(Node::with_children(
-      NodeKind::Heading { level, id },
+      NodeKind::Heading {
+        level: level as u8,
+        id,
+        attributes,
+        section: None,
+      },
       Span::new(start, self.scanner.pos(), line, col),
       inline,
     ))
   }
 
-  fn count_hashes(&mut self) -> u8 {
-    let mut level = 0u8;
-    while self.scanner.consume(b'#') && level < 6 {
+  /// Length of the opening `#` run; anything over six is not a heading.
+  fn count_hashes(&mut self) -> usize {
+    let mut level = 0;
+    while self.scanner.consume(b'#') {
       level += 1;
     }
     level
@@ -79,35 +95,68 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     self.scanner.is_eof()
       || self.scanner.check(b' ')
       || self.scanner.check(b'\t')
+      || self.scanner.check(b'\r')
       || self.scanner.check(b'\n')
   }
 
-  fn scan_heading_content(&mut self) -> String {
+  /// `<!-- ... -->` on its own lines. It may run over several lines, blank
+  /// ones included; only whitespace may follow it on its closing line.
+  pub fn try_comment(&mut self, line: usize, col: usize) -> Option<Node> {
     let start = self.scanner.pos();
-    let mut end = start;
-
-    while !self.scanner.is_eof() && !self.scanner.check(b'\n') {
-      if !self.scanner.check(b'#') && !matches!(self.scanner.peek(), Some(b' ' | b'\t')) {
-        self.scanner.advance();
-        end = self.scanner.pos();
-      } else {
-        self.scanner.advance();
-      }
+    let rest = self.scanner.slice(start, self.scanner.len());
+    let inner = rest.strip_prefix("<!--")?;
+    let close = inner.find("-->")?;
+    let after = &inner[close + 3..];
+    let line_len = after.find('\n').unwrap_or(after.len());
+    if !after[..line_len].trim().is_empty() {
+      return None;
     }
+    let content = inner[..close].to_string();
+    self.scanner.set_pos(start + 4 + close + 3 + line_len);
+    self.scanner.consume(b'\n');
+    Some(Node::new(
+      NodeKind::Comment {
+        content,
+        block: true,
+      },
+      Span::new(start, self.scanner.pos(), line, col),
+    ))
+  }
+
+  /// `[label]: url "title"`, already collected for inline resolution; kept
+  /// as a `LinkDefinition` node so it doesn't render as a paragraph.
+  pub fn try_link_definition(&mut self, start: usize, line: usize, col: usize) -> Option<Node> {
+    let rest = self.scanner.slice(start, self.scanner.len());
+    let (def, len) = linkdef::parse(rest)?;
+    self.scanner.set_pos(start + len);
+    Some(Node::new(
+      NodeKind::LinkDefinition {
+        label: def.label,
+        url: def.url,
+        title: def.title,
+      },
+      Span::new(start, self.scanner.pos(), line, col),
+    ))
+  }
 
-    self.scanner.slice(start, end).trim().to_string()
+  /// Step over a `*[term]: title` line.
+  pub fn skip_abbreviation_definition(&mut self, start: usize) -> Option<()> {
+    let rest = self.scanner.slice(start, self.scanner.len());
+    let (_, len) = abbr::parse(rest)?;
+    self.scanner.set_pos(start + len);
+    Some(())
   }
 
   pub fn parse_paragraph(&mut self, line: usize, col: usize) -> Option<Node> {
     let start = self.scanner.pos();
-    let content = self.scan_line_content();
+    let (offset, content) = self.scan_line_text();
     self.scanner.consume(b'\n');
 
     if content.trim().is_empty() {
       return None;
     }
 
-    let inline = self.parse_inline(&content);
+    let inline = self.parse_inline(&["Paragraph"], content, offset);
     Some(Node::with_children(
       NodeKind::Paragraph,
       Span::new(start, self.scanner.pos(), line, col),
@@ -117,7 +166,7 @@ impl<'a, 'b> BlockParser<'a, 'b> {
 
   pub fn try_definition_list(&mut self, line: usize, col: usize) -> Option<Node> {
     let start = self.scanner.pos();
-    let term_content = self.scan_line_content();
+    let (term_offset, term_content) = self.scan_line_text();
 
     if term_content.trim().is_empty() {
       return None;
@@ -130,7 +179,7 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     }
 
     self.skip_definition_marker();
-    let items = self.collect_definition_items(&term_content, start, line, col);
+    let items = self.collect_definition_items(term_content, term_offset, start, line, col);
 
     Some(Node::with_children(
       NodeKind::DefinitionList,
@@ -153,6 +202,7 @@ impl<'a, 'b> BlockParser<'a, 'b> {
   fn collect_definition_items(
     &mut self,
     term_content: &str,
+    term_offset: usize,
     start: usize,
     line: usize,
     col: usize,
@@ -160,7 +210,11 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     let mut items = Vec::new();
 
     // Add term node
-    let term_inline = self.parse_inline(term_content);
+    let term_inline = self.parse_inline(
+      &["DefinitionList", "DefinitionTerm"],
+      term_content,
+      term_offset,
+    );
     items.push(Node::with_children(
       NodeKind::DefinitionTerm,
       Span::new(start, self.scanner.pos(), line, col),
@@ -172,10 +226,14 @@ impl<'a, 'b> BlockParser<'a, 'b> {
       let desc_start = self.scanner.pos();
       let desc_line = self.scanner.line();
       let desc_col = self.scanner.column();
-      let desc_content = self.scan_line_content();
+      let (desc_offset, desc_content) = self.scan_line_text();
       self.scanner.consume(b'\n');
 
-      let desc_inline = self.parse_inline(&desc_content);
+      let desc_inline = self.parse_inline(
+        &["DefinitionList", "DefinitionDescription"],
+        desc_content,
+        desc_offset,
+      );
       items.push(Node::with_children(
         NodeKind::DefinitionDescription,
         Span::new(desc_start, self.scanner.pos(), desc_line, desc_col),
@@ -192,13 +250,17 @@ impl<'a, 'b> BlockParser<'a, 'b> {
   }
 }
 
-fn extract_heading_id(content: &str) -> (&str, Option<String>) {
-  content
-    .rfind("{#")
-    .filter(|_| content.ends_with('}'))
-    .map(|pos| {
-      let id = content[pos + 2..content.len() - 1].to_string();
-      (content[..pos].trim(), Some(id))
-    })
-    .unwrap_or((content, None))
+/// Drop an optional closing `#` run. It only counts when preceded by a
+/// space or tab (or when it is the whole line), so `# C#` keeps its hash.
+/// A CRLF line's `\r` goes with the trailing whitespace.
+fn strip_closing_hashes(content: &str) -> &str {
+  let content = content.trim_end_matches([' ', '\t', '\r']);
+  let open = content.trim_end_matches('#');
+  if open.is_empty() {
+    open
+  } else if open.len() < content.len() && open.ends_with([' ', '\t']) {
+    open.trim_end_matches([' ', '\t'])
+  } else {
+    content
+  }
 }
@@ -3,35 +3,104 @@
 mod code;
 mod container;
 mod custom;
+mod directive;
 mod leaf;
+mod nested;
+mod table;
 
-use super::{InlineParser, LinkDef, Scanner};
+use super::{
+  trace, ElementRegistry, InlineParser, KindFilter, LinkDef, MarkdownParser, ParseOptions, Scanner,
+};
 use crate::ast::Node;
+use nested::NestedContent;
 
 /// Parser for block-level elements.
 pub struct BlockParser<'a, 'b> {
   scanner: &'a mut Scanner<'b>,
   link_defs: &'a [LinkDef],
+  elements: &'a ElementRegistry,
+  options: ParseOptions,
 }
 
 impl<'a, 'b> BlockParser<'a, 'b> {
   #[inline]
-  pub fn new(scanner: &'a mut Scanner<'b>, link_defs: &'a [LinkDef]) -> Self {
-    Self { scanner, link_defs }
+  pub fn new(
+    scanner: &'a mut Scanner<'b>,
+    link_defs: &'a [LinkDef],
+    elements: &'a ElementRegistry,
+  ) -> Self {
+    Self {
+      scanner,
+      link_defs,
+      elements,
+      options: ParseOptions::new(),
+    }
+  }
+
+  #[inline]
+  pub fn with_options(mut self, options: ParseOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Parse nested container content as the content of a node at `path`
+  /// below the current block, such as `["Steps", "Step"]`.
+  fn parse_nested(&self, path: &[&str], content: &NestedContent) -> Vec<Node> {
+    self.parse_nested_with(self.options.kinds.within_path(path), content)
+  }
+
+  /// Parse nested container content as its own document, building the
+  /// nodes `kinds` keeps, with spans mapped back to this parser's input.
+  fn parse_nested_with(&self, kinds: KindFilter, content: &NestedContent) -> Vec<Node> {
+    let options = ParseOptions {
+      kinds,
+      ..self.options
+    };
+    let parse = || {
+      MarkdownParser::new(&content.text)
+        .with_elements(self.elements)
+        .with_options(options)
+        .parse()
+        .nodes
+    };
+    let mut nodes = if trace::active() {
+      let (nodes, events) = trace::capture(parse);
+      for mut event in events {
+        event.offset = content.source_offset(event.offset);
+        event.depth += 1;
+        trace::emit(event);
+      }
+      nodes
+    } else {
+      parse()
+    };
+    content.remap(&mut nodes);
+    nodes
   }
 
   /// Parse all blocks until EOF.
   #[inline]
   pub fn parse_blocks(&mut self) -> Vec<Node> {
-    let mut nodes = Vec::with_capacity(32);
+    self.parse_blocks_until(usize::MAX)
+  }
+
+  /// Parse blocks until EOF or until the next block would start at or
+  /// after byte `end`. A block that starts before `end` is parsed whole,
+  /// so the scanner may stop past `end`.
+  pub fn parse_blocks_until(&mut self, end: usize) -> Vec<Node> {
+    // Roughly one block per 64 bytes, so short inputs stay small
+    let bytes = end
+      .min(self.scanner.len())
+      .saturating_sub(self.scanner.pos());
+    let mut nodes = Vec::with_capacity((bytes / 64).clamp(4, 32));
 
-    while !self.scanner.is_eof() {
+    loop {
       self.scanner.skip_blank_lines();
-      if self.scanner.is_eof() {
+      if self.scanner.is_eof() || self.scanner.pos() >= end {
         break;
       }
       if let Some(node) = self.parse_block() {
-        nodes.push(node);
+        self.options.kinds.select(node, &mut nodes);
       }
     }
     nodes
@@ -54,36 +123,105 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     match first_char {
       // Thematic breaks: ---, ***, ___
       Some(b'-' | b'*' | b'_') => {
-        if let Some(node) = self.try_thematic_break(start_line, start_col) {
+        if let Some(node) = trace::block(
+          "thematic_break",
+          start_pos,
+          self.try_thematic_break(start_line, start_col),
+        ) {
           return Some(node);
         }
+        // Abbreviation definitions leave no node; the parser collected them
+        if first_char == Some(b'*')
+          && trace::block(
+            "abbreviation_definition",
+            start_pos,
+            self.skip_abbreviation_definition(start_pos),
+          )
+          .is_some()
+        {
+          return None;
+        }
         // Fall through to check list or emphasis start
       }
-      // Headings: # ## ### etc
-      Some(b'#') => {
-        if let Some(node) = self.try_atx_heading(start_line, start_col) {
+      // Headings: # ## ### etc, indented at most three spaces
+      Some(b'#') if indent < 4 => {
+        if let Some(node) = trace::block(
+          "atx_heading",
+          start_pos,
+          self.try_atx_heading(start_line, start_col),
+        ) {
           return Some(node);
         }
       }
       // Fenced code: ``` or ~~~
       Some(b'`' | b'~') => {
-        if let Some(node) = self.try_fenced_code(start_line, start_col) {
+        if let Some(node) = trace::block(
+          "fenced_code",
+          start_pos,
+          self.try_fenced_code(start_line, start_col),
+        ) {
+          return Some(node);
+        }
+      }
+      // Math blocks: $$, \[ or \begin{env}
+      Some(b'$' | b'\\') => {
+        if let Some(node) = trace::block(
+          "math_block",
+          start_pos,
+          self.try_math_block(start_line, start_col),
+        ) {
           return Some(node);
         }
       }
-      // Math blocks: $$
-      Some(b'$') => {
-        if let Some(node) = self.try_math_block(start_line, start_col) {
+      // Directives: ::: name
+      Some(b':') if indent < 4 => {
+        if let Some(node) = trace::block(
+          "directive",
+          start_pos,
+          self.try_directive(start_line, start_col),
+        ) {
           return Some(node);
         }
       }
       // Blockquotes: >
       Some(b'>') => {
-        return Some(self.parse_blockquote(start_line, start_col));
+        let node = self.parse_blockquote(start_line, start_col);
+        return trace::block("blockquote", start_pos, Some(node));
+      }
+      // Footnote and link definitions: [^label]: / [label]:
+      Some(b'[') => {
+        if let Some(node) = trace::block(
+          "footnote_definition",
+          start_pos,
+          self.try_footnote_definition(start_line, start_col),
+        ) {
+          return Some(node);
+        }
+        if let Some(node) = trace::block(
+          "link_definition",
+          start_pos,
+          self.try_link_definition(start_pos, start_line, start_col),
+        ) {
+          return Some(node);
+        }
       }
-      // Custom elements: <steps>, <toc>, <tabs>
+      // Comments and custom elements: <steps>, <toc>, <tabs>, registered
+      // elements
       Some(b'<') => {
-        if let Some(node) = self.try_custom_element(start_line, start_col) {
+        if indent < 4 {
+          if let Some(node) = trace::block(
+            "comment",
+            start_pos,
+            self.try_comment(start_line, start_col),
+          ) {
+            return Some(node);
+          }
+        }
+        if let Some(node) = trace::block(
+          "custom_element",
+          start_pos,
+          self.try_custom_element(start_line, start_col),
+        ) {
           return Some(node);
         }
       }
@@ -91,34 +229,51 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     }
 
     // Check for lists (-, *, +)
-    if let Some(node) = self.try_list(start_line, start_col) {
+    if let Some(node) = trace::block("list", start_pos, self.try_list(start_line, start_col)) {
       return Some(node);
     }
 
     // HTML blocks
-    if let Some(node) = self.try_html_block(start_line, start_col) {
+    if let Some(node) = trace::block(
+      "html_block",
+      start_pos,
+      self.try_html_block(start_line, start_col),
+    ) {
       return Some(node);
     }
 
     // Tables (GFM)
-    if let Some(node) = self.try_table(start_line, start_col) {
+    if let Some(node) = trace::block(
+      "table",
+      start_pos,
+      self.try_table(indent, start_line, start_col),
+    ) {
       return Some(node);
     }
 
     // Indented code blocks (4+ spaces)
-    if let Some(node) = self.try_indented_code(indent, start_pos, start_line, start_col) {
+    if let Some(node) = trace::block(
+      "indented_code",
+      start_pos,
+      self.try_indented_code(indent, start_pos, start_line, start_col),
+    ) {
       return Some(node);
     }
 
     // Definition lists
     self.scanner.set_pos(start_pos);
-    if let Some(node) = self.try_definition_list(start_line, start_col) {
+    if let Some(node) = trace::block(
+      "definition_list",
+      start_pos,
+      self.try_definition_list(start_line, start_col),
+    ) {
       return Some(node);
     }
 
     // Fall back to paragraph
     self.scanner.set_pos(start_pos);
-    self.parse_paragraph(start_line, start_col)
+    let node = self.parse_paragraph(start_line, start_col);
+    trace::block("paragraph", start_pos, node)
   }
 
   #[inline]
@@ -145,41 +300,50 @@ impl<'a, 'b> BlockParser<'a, 'b> {
     None
   }
 
-  #[inline]
-  fn try_table(&mut self, _line: usize, _col: usize) -> Option<Node> {
-    None
-  }
-
   #[inline]
   fn try_indented_code(
     &mut self,
     indent: usize,
-    _start_pos: usize,
+    start_pos: usize,
     line: usize,
     col: usize,
   ) -> Option<Node> {
     if indent >= 4 {
+      self.scanner.set_pos(start_pos);
       Some(self.parse_indented_code(line, col))
     } else {
       None
     }
   }
 
+  /// Parse `text`, found at `offset` in the input, as the inline content
+  /// of a node at `path` below the current block.
   #[inline]
-  pub(crate) fn parse_inline(&self, text: &str) -> Vec<Node> {
-    InlineParser::new(text, self.link_defs).parse()
+  pub(crate) fn parse_inline(&self, path: &[&str], text: &str, offset: usize) -> Vec<Node> {
+    let kinds = self.options.kinds.within_path(path);
+    InlineParser::new(text, self.link_defs)
+      .with_offset(offset)
+      .with_options(ParseOptions {
+        kinds,
+        ..self.options
+      })
+      .parse()
   }
 
   #[inline]
-  pub(crate) fn scan_line_content(&mut self) -> String {
+  pub(crate) fn scan_line_content(&mut self) -> &'b str {
+    self.scan_line_text().1
+  }
+
+  /// Rest of the line, trimmed, with the offset the trimmed text starts at.
+  #[inline]
+  pub(crate) fn scan_line_text(&mut self) -> (usize, &'b str) {
     let start = self.scanner.pos();
     while !self.scanner.is_eof() && !self.scanner.check(b'\n') {
       self.scanner.advance();
     }
-    self
-      .scanner
-      .slice(start, self.scanner.pos())
-      .trim()
-      .to_string()
+    let raw = self.scanner.slice(start, self.scanner.pos());
+    let leading = raw.len() - raw.trim_start().len();
+    (start + leading, raw.trim())
   }
 }
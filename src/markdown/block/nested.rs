@@ -0,0 +1,78 @@
+//! Container content extracted for a nested parse, with the source position
+//! of each line so nested spans can be mapped back into the document.
+
+use crate::ast::Node;
+
+/// Where one line of nested content starts in the enclosing input.
+struct LineStart {
+  /// Offset of the line in the nested text
+  content: usize,
+  /// Offset of the line in the enclosing input
+  source: usize,
+  line: usize,
+  column: usize,
+}
+
+/// Text of a blockquote, footnote, step or tab body, plus its line map.
+#[derive(Default)]
+pub struct NestedContent {
+  pub text: String,
+  lines: Vec<LineStart>,
+}
+
+impl NestedContent {
+  /// Text copied verbatim from `source` in the enclosing input.
+  pub fn contiguous(text: &str, source: usize, line: usize, column: usize) -> Self {
+    let mut content = Self::default();
+    for (i, raw) in text.split_inclusive('\n').enumerate() {
+      let offset = content.text.len();
+      content.lines.push(LineStart {
+        content: offset,
+        source: source + offset,
+        line: line + i,
+        column: if i == 0 { column } else { 1 },
+      });
+      content.text.push_str(raw);
+    }
+    content
+  }
+
+  /// Append one line taken from `source` (its container prefix already
+  /// stripped), followed by a newline.
+  pub fn push_line(&mut self, text: &str, source: usize, line: usize, column: usize) {
+    self.lines.push(LineStart {
+      content: self.text.len(),
+      source,
+      line,
+      column,
+    });
+    self.text.push_str(text);
+    self.text.push('\n');
+  }
+
+  /// Rewrite spans of nodes parsed from `text` into enclosing-input
+  /// positions. Empty spans are left alone.
+  pub fn remap(&self, nodes: &mut [Node]) {
+    for node in nodes {
+      let span = &mut node.span;
+      if !span.is_empty() {
+        span.start = self.source_offset(span.start);
+        span.end = self.source_offset(span.end);
+        if let Some(start) = span.line.checked_sub(1).and_then(|i| self.lines.get(i)) {
+          span.line = start.line;
+          span.column += start.column - 1;
+        }
+      }
+      self.remap(&mut node.children);
+    }
+  }
+
+  /// Offset in the enclosing input of `pos` in `text`.
+  pub fn source_offset(&self, pos: usize) -> usize {
+    let i = self.lines.partition_point(|l| l.content <= pos);
+    match i.checked_sub(1).map(|i| &self.lines[i]) {
+      Some(start) => start.source + (pos - start.content),
+      None => pos,
+    }
+  }
+}
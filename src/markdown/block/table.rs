@@ -0,0 +1,227 @@
+//! GFM tables: a header row, a delimiter row setting each column's
+//! alignment, then body rows up to a blank line or a line without a pipe.
+//!
+//! Every row comes out with one cell per column. Rows short of the header's
+//! width are padded with empty cells and extra cells are dropped, and each
+//! cell takes the alignment of the column it starts in. With
+//! `ParseOptions::table_colspan`, an empty cell written as `||` joins the
+//! cell before it, which then spans one more column.
+
+use super::BlockParser;
+use crate::ast::{Alignment, Node, NodeKind, Span};
+
+/// A cell as written in a row.
+struct RawCell<'b> {
+  /// Between the pipes
+  start: usize,
+  end: usize,
+  /// Trimmed content and where it starts
+  text: &'b str,
+  offset: usize,
+  colspan: u32,
+}
+
+/// A row: where its line starts, its cells and where it ends.
+struct RawRow<'b> {
+  line: usize,
+  line_start: usize,
+  start: usize,
+  end: usize,
+  cells: Vec<RawCell<'b>>,
+}
+
+impl<'a, 'b> BlockParser<'a, 'b> {
+  pub fn try_table(&mut self, indent: usize, line: usize, col: usize) -> Option<Node> {
+    if indent >= 4 {
+      return None;
+    }
+    let start = self.scanner.pos();
+    let colspan = self.options.table_colspan;
+
+    let (offset, text) = self.scan_line_text();
+    if !has_pipe(text) {
+      self.scanner.set_pos(start);
+      return None;
+    }
+    let header = RawRow {
+      line,
+      line_start: start - indent + 1 - col,
+      start,
+      end: self.scanner.pos(),
+      cells: split_row(text, offset, colspan),
+    };
+    self.scanner.consume(b'\n');
+
+    let (offset, text) = self.scan_line_text();
+    let alignments = match delimiter_row(text, offset) {
+      Some(alignments) if alignments.len() == columns(&header.cells) => alignments,
+      _ => {
+        self.scanner.set_pos(start);
+        return None;
+      }
+    };
+    self.scanner.consume(b'\n');
+    let head_end = self.scanner.pos();
+
+    let mut body = Vec::new();
+    while !self.scanner.is_eof() {
+      let row_start = self.scanner.pos();
+      let row_line = self.scanner.line();
+      let (offset, text) = self.scan_line_text();
+      if !has_pipe(text) {
+        self.scanner.set_pos(row_start);
+        break;
+      }
+      let row = RawRow {
+        line: row_line,
+        line_start: row_start,
+        start: offset,
+        end: self.scanner.pos(),
+        cells: split_row(text, offset, colspan),
+      };
+      self.scanner.consume(b'\n');
+      body.push(self.table_row(row, &alignments, false));
+    }
+
+    let head_row = self.table_row(header, &alignments, true);
+    let mut children = vec![Node::with_children(
+      NodeKind::TableHead,
+      Span::new(start, head_end, line, col),
+      vec![head_row],
+    )];
+    if let Some(first) = body.first() {
+      let span = first.span;
+      children.push(Node::with_children(
+        NodeKind::TableBody,
+        Span::new(span.start, self.scanner.pos(), span.line, span.column),
+        body,
+      ));
+    }
+    Some(Node::with_children(
+      NodeKind::Table,
+      Span::new(start, self.scanner.pos(), line, col),
+      children,
+    ))
+  }
+
+  /// One cell per column: cells past the last column are dropped, missing
+  /// ones added empty at the end of the row.
+  fn table_row(&self, row: RawRow, alignments: &[Alignment], is_header: bool) -> Node {
+    let column_of = |pos: usize| pos - row.line_start + 1;
+    let section = if is_header { "TableHead" } else { "TableBody" };
+    let path = ["Table", section, "TableRow", "TableCell"];
+    let mut cells = Vec::with_capacity(alignments.len());
+    let mut column = 0;
+    for cell in &row.cells {
+      if column >= alignments.len() {
+        break;
+      }
+      let colspan = cell.colspan.min((alignments.len() - column) as u32);
+      cells.push(Node::with_children(
+        NodeKind::TableCell {
+          alignment: alignments[column],
+          is_header,
+          colspan,
+        },
+        Span::new(cell.start, cell.end, row.line, column_of(cell.start)),
+        self.parse_inline(&path, cell.text, cell.offset),
+      ));
+      column += colspan as usize;
+    }
+    for &alignment in &alignments[column..] {
+      cells.push(Node::new(
+        NodeKind::TableCell {
+          alignment,
+          is_header,
+          colspan: 1,
+        },
+        Span::new(row.end, row.end, row.line, column_of(row.end)),
+      ));
+    }
+    Node::with_children(
+      NodeKind::TableRow,
+      Span::new(row.start, row.end, row.line, column_of(row.start)),
+      cells,
+    )
+  }
+}
+
+/// Whether `text` has a pipe that isn't escaped.
+fn has_pipe(text: &str) -> bool {
+  pipes(text).next().is_some()
+}
+
+/// Byte positions of the unescaped pipes in `text`.
+fn pipes(text: &str) -> impl Iterator<Item = usize> + '_ {
+  let bytes = text.as_bytes();
+  let mut escaped = false;
+  (0..bytes.len()).filter(move |&i| {
+    let pipe = bytes[i] == b'|' && !escaped;
+    escaped = bytes[i] == b'\\' && !escaped;
+    pipe
+  })
+}
+
+/// Cells of a trimmed row found at `offset`; leading and trailing pipes
+/// are optional. With `colspan`, an empty cell with nothing between its
+/// pipes widens the cell before it instead.
+fn split_row(text: &str, offset: usize, colspan: bool) -> Vec<RawCell<'_>> {
+  let pipes: Vec<usize> = pipes(text).collect();
+  let starts = std::iter::once(0).chain(pipes.iter().map(|p| p + 1));
+  let ends = pipes.iter().copied().chain(std::iter::once(text.len()));
+  let mut ranges: Vec<(usize, usize)> = starts.zip(ends).collect();
+  let leading = pipes.first() == Some(&0);
+  if leading {
+    ranges.remove(0);
+  }
+  if pipes.last() == Some(&(text.len() - 1)) && (pipes.len() > 1 || !leading) {
+    ranges.pop();
+  }
+  let mut cells: Vec<RawCell> = Vec::with_capacity(ranges.len());
+  for (from, to) in ranges {
+    let raw = &text[from..to];
+    if colspan && raw.is_empty() {
+      if let Some(previous) = cells.last_mut() {
+        previous.colspan += 1;
+        continue;
+      }
+    }
+    cells.push(RawCell {
+      start: offset + from,
+      end: offset + to,
+      text: raw.trim(),
+      offset: offset + from + (raw.len() - raw.trim_start().len()),
+      colspan: 1,
+    });
+  }
+  cells
+}
+
+/// Column alignments of a delimiter row such as `|:--|:-:|--:|`.
+fn delimiter_row(text: &str, offset: usize) -> Option<Vec<Alignment>> {
+  if !has_pipe(text) {
+    return None;
+  }
+  split_row(text, offset, false)
+    .iter()
+    .map(|cell| {
+      let dashes = cell.text.trim_start_matches(':').trim_end_matches(':');
+      if dashes.is_empty() || !dashes.bytes().all(|b| b == b'-') {
+        return None;
+      }
+      Some(
+        match (cell.text.starts_with(':'), cell.text.ends_with(':')) {
+          (true, true) => Alignment::Center,
+          (true, false) => Alignment::Left,
+          (false, true) => Alignment::Right,
+          (false, false) => Alignment::None,
+        },
+      )
+    })
+    .collect()
+}
+
+/// Columns a row's cells cover.
+fn columns(cells: &[RawCell]) -> usize {
+  cells.iter().map(|cell| cell.colspan as usize).sum()
+}
@@ -0,0 +1,218 @@
+//! Registry of user-declared custom elements and alert keywords.
+//!
+//! Config format, one element per line (`#` starts a comment), or an alert
+//! keyword in brackets with the alert class it maps to:
+//!
+//! ```text
+//! callout type
+//! figure src
+//! [!ATTENTION] warning
+//! [!SUCCESS]
+//! ```
+
+use crate::ast::AlertType;
+
+use std::path::Path;
+
+/// Built-in elements handled by `block/custom.rs`.
+const BUILTIN: &[&str] = &["toc", "steps", "step", "tabs", "include"];
+
+/// Alert keywords known without configuration: GitHub's five, and the
+/// `INFO` and `DANGER` other platforms use.
+const BUILTIN_ALERTS: &[(&str, AlertType)] = &[
+  ("NOTE", AlertType::Note),
+  ("TIP", AlertType::Tip),
+  ("IMPORTANT", AlertType::Important),
+  ("WARNING", AlertType::Warning),
+  ("CAUTION", AlertType::Caution),
+  ("INFO", AlertType::Note),
+  ("DANGER", AlertType::Caution),
+];
+
+/// A declared custom element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementDef {
+  pub name: String,
+  /// Accepted attribute names; empty accepts any.
+  pub attributes: Vec<String>,
+}
+
+impl ElementDef {
+  /// Whether `attr` should be kept on a parsed element.
+  pub fn accepts(&self, attr: &str) -> bool {
+    self.attributes.is_empty() || self.attributes.iter().any(|a| a == attr)
+  }
+}
+
+/// Custom elements that parse into `NodeKind::CustomElement`, and alert
+/// keywords beyond the built-in ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementRegistry {
+  elements: Vec<ElementDef>,
+  /// Uppercase keyword and the alert it maps to.
+  alerts: Vec<(String, AlertType)>,
+}
+
+impl ElementRegistry {
+  pub const fn new() -> Self {
+    Self {
+      elements: Vec::new(),
+      alerts: Vec::new(),
+    }
+  }
+
+  /// Declare an element. Re-registering a name replaces its attributes.
+  pub fn register(&mut self, name: &str, attributes: &[&str]) -> Result<&mut Self, String> {
+    validate_name(name)?;
+    let def = ElementDef {
+      name: name.to_string(),
+      attributes: attributes.iter().map(|a| a.to_string()).collect(),
+    };
+    match self.elements.iter_mut().find(|d| d.name == name) {
+      Some(existing) => *existing = def,
+      None => self.elements.push(def),
+    }
+    Ok(self)
+  }
+
+  pub fn get(&self, name: &str) -> Option<&ElementDef> {
+    self.elements.iter().find(|d| d.name == name)
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.elements.is_empty()
+  }
+
+  /// Make `> [!KEYWORD]` (any case) an alert of `alert_type`, replacing a
+  /// built-in or earlier mapping.
+  pub fn register_alert(
+    &mut self,
+    keyword: &str,
+    alert_type: AlertType,
+  ) -> Result<&mut Self, String> {
+    let valid = !keyword.is_empty()
+      && keyword
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if !valid {
+      return Err(format!("invalid alert keyword: {}", keyword));
+    }
+    let keyword = keyword.to_ascii_uppercase();
+    match self.alerts.iter_mut().find(|(k, _)| *k == keyword) {
+      Some(existing) => existing.1 = alert_type,
+      None => self.alerts.push((keyword, alert_type)),
+    }
+    Ok(self)
+  }
+
+  /// The alert a `[!KEYWORD]` marker or `:::keyword` directive names.
+  pub fn alert(&self, keyword: &str) -> Option<AlertType> {
+    let keyword = keyword.to_ascii_uppercase();
+    self
+      .alerts
+      .iter()
+      .map(|(k, at)| (k.as_str(), at))
+      .chain(BUILTIN_ALERTS.iter().map(|(k, at)| (*k, at)))
+      .find(|(k, _)| *k == keyword)
+      .map(|(_, at)| at.clone())
+  }
+
+  /// Parse a registry from config text.
+  pub fn from_config(text: &str) -> Result<Self, String> {
+    let mut registry = Self::new();
+    for (i, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or("").trim();
+      let mut words = line.split_whitespace();
+      let Some(name) = words.next() else {
+        continue;
+      };
+      if let Some(keyword) = name.strip_prefix("[!") {
+        let keyword = keyword
+          .strip_suffix(']')
+          .ok_or_else(|| format!("line {}: expected ] after [!{}", i + 1, keyword))?;
+        // Without a class, the keyword names its own
+        let class = words.next().unwrap_or(keyword);
+        registry
+          .register_alert(keyword, AlertType::from_name(class))
+          .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        continue;
+      }
+      let attributes: Vec<&str> = words.collect();
+      registry
+        .register(name, &attributes)
+        .map_err(|e| format!("line {}: {}", i + 1, e))?;
+    }
+    Ok(registry)
+  }
+
+  /// Load a registry from a config file.
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = std::fs::read_to_string(path)
+      .map_err(|e| format!("Failed to read elements file {}: {}", path.display(), e))?;
+    Self::from_config(&text).map_err(|e| format!("{}: {}", path.display(), e))
+  }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+  if BUILTIN.contains(&name) {
+    return Err(format!("<{}> is a built-in element", name));
+  }
+  let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+    && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+  if !valid {
+    return Err(format!("invalid element name: {}", name));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_from_config() {
+    let registry =
+      ElementRegistry::from_config("# elements\ncallout type\n\nfigure src # file\n").unwrap();
+    assert_eq!(registry.get("callout").unwrap().attributes, vec!["type"]);
+    assert_eq!(registry.get("figure").unwrap().attributes, vec!["src"]);
+    assert!(registry.get("toc").is_none());
+  }
+
+  #[test]
+  fn test_rejects_builtin_and_invalid_names() {
+    assert!(ElementRegistry::from_config("tabs names").is_err());
+    let err = ElementRegistry::from_config("ok\n1bad").unwrap_err();
+    assert!(err.starts_with("line 2:"));
+  }
+
+  #[test]
+  fn test_alerts() {
+    let registry =
+      ElementRegistry::from_config("[!ATTENTION] warning\n[!success]\n[!INFO] tip\ncallout\n")
+        .unwrap();
+    assert_eq!(registry.alert("attention"), Some(AlertType::Warning));
+    assert_eq!(
+      registry.alert("SUCCESS"),
+      Some(AlertType::Other("SUCCESS".to_string()))
+    );
+    assert_eq!(registry.alert("info"), Some(AlertType::Tip));
+    assert_eq!(registry.alert("Danger"), Some(AlertType::Caution));
+    assert_eq!(registry.alert("bogus"), None);
+    assert!(registry.get("callout").is_some());
+    assert!(ElementRegistry::new().alert("SUCCESS").is_none());
+
+    assert!(ElementRegistry::from_config("[!OOPS warning").is_err());
+    assert!(ElementRegistry::from_config("[!] note").is_err());
+  }
+
+  #[test]
+  fn test_accepts_attributes() {
+    let mut registry = ElementRegistry::new();
+    registry.register("badge", &[]).unwrap();
+    registry.register("callout", &["type"]).unwrap();
+    assert!(registry.get("badge").unwrap().accepts("anything"));
+    assert!(registry.get("callout").unwrap().accepts("type"));
+    assert!(!registry.get("callout").unwrap().accepts("src"));
+  }
+}
@@ -3,6 +3,8 @@
 use super::scanner::Scanner;
 use crate::ast::{FrontmatterFormat, Node, NodeKind, Span};
 
+use std::io::BufRead;
+
 /// Try to parse YAML/TOML frontmatter at document start.
 pub fn try_parse(scanner: &mut Scanner) -> Option<Node> {
   if scanner.pos() != 0 {
@@ -14,6 +16,27 @@ pub fn try_parse(scanner: &mut Scanner) -> Option<Node> {
   try_yaml(scanner, input).or_else(|| try_toml(scanner, input))
 }
 
+/// Frontmatter at the start of `reader`, read up to its closing delimiter
+/// and no further, for a look at a file's metadata without parsing it.
+pub fn read(mut reader: impl BufRead) -> Option<Node> {
+  let mut text = String::new();
+  reader.read_line(&mut text).ok()?;
+  let delim = match text.as_str() {
+    "---\n" => "---",
+    "+++\n" => "+++",
+    _ => return None,
+  };
+  loop {
+    let start = text.len();
+    if reader.read_line(&mut text).ok()? == 0 {
+      return None;
+    }
+    if text[start..].starts_with(delim) {
+      return try_parse(&mut Scanner::new(&text));
+    }
+  }
+}
+
 fn try_yaml(scanner: &mut Scanner, input: &str) -> Option<Node> {
   if !input.starts_with("---\n") || input.len() <= 4 {
     return None;
@@ -80,3 +103,100 @@ pub fn skip(scanner: &mut Scanner) {
     }
   }
 }
+
+/// Frontmatter key whose table overrides parser options for the document.
+pub const OPTIONS_KEY: &str = "bukvar";
+
+/// Entries of the `bukvar` table in frontmatter `content`, as `(key, value,
+/// line)` with `line` the 0-based line within `content`. YAML takes
+/// `bukvar: {math: false}` or an indented block under `bukvar:`; TOML takes
+/// `bukvar = { math = false }` or a `[bukvar]` table.
+pub fn option_overrides(format: FrontmatterFormat, content: &str) -> Vec<(String, String, usize)> {
+  let (separator, header) = match format {
+    FrontmatterFormat::Yaml => (':', None),
+    FrontmatterFormat::Toml => ('=', Some("[bukvar]")),
+    FrontmatterFormat::Json => return Vec::new(),
+  };
+  let entry = |text: &str, line: usize| {
+    let (key, value) = text.split_once(separator)?;
+    let value = value.trim().trim_matches(['"', '\'']);
+    Some((key.trim().to_string(), value.to_string(), line))
+  };
+  let mut out = Vec::new();
+  let mut lines = content.lines().enumerate().peekable();
+  while let Some((index, line)) = lines.next() {
+    let line = line.split('#').next().unwrap_or("");
+    if header.is_some_and(|header| line.trim() == header) {
+      // TOML: entries up to the next table header
+      while let Some((index, line)) = lines.next_if(|(_, l)| !l.trim_start().starts_with('[')) {
+        out.extend(entry(line.split('#').next().unwrap_or(""), index));
+      }
+      continue;
+    }
+    let Some((key, value)) = line.split_once(separator) else {
+      continue;
+    };
+    if line.starts_with(char::is_whitespace) || key.trim() != OPTIONS_KEY {
+      continue;
+    }
+    let value = value.trim();
+    if let Some(table) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+      out.extend(table.split(',').filter_map(|item| entry(item, index)));
+    } else if value.is_empty() && header.is_none() {
+      // YAML: the indented block below the key
+      while let Some((index, line)) =
+        lines.next_if(|(_, l)| l.starts_with(char::is_whitespace) || l.trim().is_empty())
+      {
+        out.extend(entry(line.split('#').next().unwrap_or(""), index));
+      }
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_option_overrides() {
+    let pairs =
+      |format, content| -> Vec<(String, String, usize)> { option_overrides(format, content) };
+    let expected = vec![
+      ("math".to_string(), "false".to_string(), 1),
+      ("table_colspan".to_string(), "true".to_string(), 1),
+    ];
+    assert_eq!(
+      pairs(
+        FrontmatterFormat::Yaml,
+        "title: A\nbukvar: {math: false, table_colspan: \"true\"}\n"
+      ),
+      expected
+    );
+    assert_eq!(
+      pairs(
+        FrontmatterFormat::Yaml,
+        "title: A\nbukvar:\n  math: false # no $\n\n  strict_math: yes\ntags: x\n"
+      ),
+      vec![
+        ("math".to_string(), "false".to_string(), 2),
+        ("strict_math".to_string(), "yes".to_string(), 4),
+      ]
+    );
+    assert_eq!(
+      pairs(
+        FrontmatterFormat::Toml,
+        "title = \"A\"\nbukvar = { math = false, table_colspan = true }\n"
+      ),
+      expected
+    );
+    assert_eq!(
+      pairs(
+        FrontmatterFormat::Toml,
+        "title = \"A\"\n[bukvar]\nmath = false\n[extra]\nmath = true\n"
+      ),
+      vec![("math".to_string(), "false".to_string(), 2)]
+    );
+    assert!(pairs(FrontmatterFormat::Yaml, "title: bukvar\n  math: false\n").is_empty());
+  }
+}
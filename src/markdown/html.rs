@@ -0,0 +1,150 @@
+//! Mini tokenizer for inline HTML: tags and comments.
+
+use crate::ast::HtmlTagKind;
+
+/// One tag, e.g. `<img src="a.png" />`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlTag {
+  pub name: String,
+  pub kind: HtmlTagKind,
+  pub attributes: Vec<(String, String)>,
+  /// Bytes consumed, including `<` and `>`.
+  pub len: usize,
+}
+
+/// Length of the inline HTML (tag or comment) at the start of `input`.
+pub fn html_len(input: &str) -> Option<usize> {
+  if let Some(rest) = input.strip_prefix("<!--") {
+    return rest.find("-->").map(|end| 4 + end + 3);
+  }
+  scan_tag(input).map(|tag| tag.len)
+}
+
+/// Tokenize the tag at the start of `input`; `None` if it is not one.
+pub fn scan_tag(input: &str) -> Option<HtmlTag> {
+  let bytes = input.as_bytes();
+  let mut pos = 1;
+  if bytes.first() != Some(&b'<') {
+    return None;
+  }
+
+  let closing = bytes.get(pos) == Some(&b'/');
+  if closing {
+    pos += 1;
+  }
+  if !bytes.get(pos)?.is_ascii_alphabetic() {
+    return None;
+  }
+  let name_start = pos;
+  while matches!(bytes.get(pos), Some(b) if b.is_ascii_alphanumeric() || *b == b'-') {
+    pos += 1;
+  }
+  let name = input[name_start..pos].to_ascii_lowercase();
+
+  let mut attributes = Vec::new();
+  loop {
+    let before = pos;
+    while matches!(bytes.get(pos), Some(b) if b.is_ascii_whitespace()) {
+      pos += 1;
+    }
+    match bytes.get(pos)? {
+      b'>' => {
+        let kind = if closing {
+          HtmlTagKind::Close
+        } else {
+          HtmlTagKind::Open
+        };
+        return Some(HtmlTag {
+          name,
+          kind,
+          attributes,
+          len: pos + 1,
+        });
+      }
+      b'/' if !closing && bytes.get(pos + 1) == Some(&b'>') => {
+        return Some(HtmlTag {
+          name,
+          kind: HtmlTagKind::SelfClosing,
+          attributes,
+          len: pos + 2,
+        });
+      }
+      _ if closing || pos == before => return None,
+      _ => {}
+    }
+
+    let key_start = pos;
+    while matches!(
+      bytes.get(pos),
+      Some(b) if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b':' | b'.')
+    ) {
+      pos += 1;
+    }
+    if pos == key_start {
+      return None;
+    }
+    let key = input[key_start..pos].to_ascii_lowercase();
+
+    let value = if bytes.get(pos) == Some(&b'=') {
+      pos += 1;
+      match *bytes.get(pos)? {
+        quote @ (b'"' | b'\'') => {
+          let end = pos + 1 + input[pos + 1..].find(quote as char)?;
+          let value = &input[pos + 1..end];
+          pos = end + 1;
+          value
+        }
+        _ => {
+          let start = pos;
+          while matches!(
+            bytes.get(pos),
+            Some(b) if !b.is_ascii_whitespace() && !matches!(b, b'"' | b'\'' | b'=' | b'<' | b'>' | b'`')
+          ) {
+            pos += 1;
+          }
+          if pos == start {
+            return None;
+          }
+          &input[start..pos]
+        }
+      }
+    } else {
+      ""
+    };
+    attributes.push((key, value.to_string()));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_scan_tags() {
+    let tag = scan_tag("<IMG src=\"a.png\" alt='x y' hidden width=40 /> rest").unwrap();
+    assert_eq!(tag.name, "img");
+    assert_eq!(tag.kind, HtmlTagKind::SelfClosing);
+    assert_eq!(
+      tag.attributes,
+      vec![
+        ("src".to_string(), "a.png".to_string()),
+        ("alt".to_string(), "x y".to_string()),
+        ("hidden".to_string(), String::new()),
+        ("width".to_string(), "40".to_string()),
+      ]
+    );
+    assert_eq!(tag.len, 45);
+
+    assert_eq!(scan_tag("</span >").unwrap().kind, HtmlTagKind::Close);
+    assert_eq!(scan_tag("<kbd>").unwrap().kind, HtmlTagKind::Open);
+  }
+
+  #[test]
+  fn test_rejects_non_tags() {
+    for input in ["< b >", "<1>", "<a", "<a b=>", "</a x>", "<a\"b>"] {
+      assert_eq!(scan_tag(input), None, "{}", input);
+    }
+    assert_eq!(html_len("<!-- note --> x"), Some(13));
+    assert_eq!(html_len("<!-- open"), None);
+  }
+}
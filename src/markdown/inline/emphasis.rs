@@ -1,44 +1,84 @@
 //! Emphasis and strong parsing (*em*, **strong**, _em_, __strong__).
 
-use super::InlineParser;
-use crate::ast::{Node, NodeKind, Span};
+use super::{trace, InlineParser};
+use crate::ast::{Node, NodeKind};
+use crate::markdown::unicode::{is_punctuation, is_whitespace};
+
+/// A run of `*` or `_`, kept as an empty `Text` node until
+/// `process_emphasis` pairs it with another run.
+pub(super) struct Delimiter {
+  /// Index of the run's `Text` node
+  slot: usize,
+  ch: u8,
+  /// Byte offset of the characters not yet used by emphasis
+  start: usize,
+  /// Characters not yet used
+  count: usize,
+  /// Run length before matching (for the rule of three)
+  original: usize,
+  can_open: bool,
+  can_close: bool,
+  active: bool,
+}
 
 impl<'a> InlineParser<'a> {
-  /// Try to parse emphasis or strong.
-  #[inline]
-  pub fn try_emphasis(&mut self) -> Option<Node> {
+  /// Scan the `*` or `_` run at the current position, classifying it by the
+  /// CommonMark left/right-flanking rules.
+  pub(super) fn scan_delimiter_run(&mut self, slot: usize) -> Delimiter {
     let start = self.pos;
-    let delimiter = self.bytes[self.pos];
-    let count = self.count_delimiters(delimiter);
-
-    // Need at least one delimiter and content after
-    if count == 0 || self.pos >= self.bytes.len() {
-      self.pos = start;
-      return None;
-    }
-
-    let content_start = self.pos;
-
-    // Find closing delimiter - optimized search
-    let close_pos = find_close_fast(&self.bytes[self.pos..], delimiter, count)?;
-    let close_abs = content_start + close_pos;
+    let ch = self.bytes[start];
+    let count = self.count_delimiters(ch);
 
-    self.pos = close_abs + count;
+    let before = self.input[..start].chars().next_back();
+    let after = self.input[self.pos..].chars().next();
+    let space_before = before.map_or(true, is_whitespace);
+    let space_after = after.map_or(true, is_whitespace);
+    let punct_before = before.is_some_and(is_punctuation);
+    let punct_after = after.is_some_and(is_punctuation);
 
-    // Parse nested content recursively
-    let children = InlineParser::new(&self.input[content_start..close_abs], self.link_defs).parse();
-
-    let kind = if count >= 2 {
-      NodeKind::Strong
+    let left = !space_after && (!punct_after || space_before || punct_before);
+    let right = !space_before && (!punct_before || space_after || punct_after);
+    let (can_open, can_close) = if ch == b'_' {
+      (
+        left && (!right || punct_before),
+        right && (!left || punct_after),
+      )
     } else {
-      NodeKind::Emphasis
+      (left, right)
     };
 
-    Some(Node::with_children(
-      kind,
-      Span::new(start, self.pos, 0, 0),
-      children,
-    ))
+    Delimiter {
+      slot,
+      ch,
+      start,
+      count,
+      original: count,
+      can_open,
+      can_close,
+      active: true,
+    }
+  }
+
+  /// Placeholder for the delimiter characters at `s..e`: a `Text` node
+  /// without content, so runs that get paired never allocate.
+  pub(super) fn delimiter_node(&self, s: usize, e: usize) -> Node {
+    Node::new(
+      NodeKind::Text {
+        content: String::new(),
+      },
+      self.span(s, e),
+    )
+  }
+
+  /// Give a delimiter placeholder its characters; other nodes, which are
+  /// never empty text, pass through.
+  fn fill_delimiter(&self, mut node: Node) -> Node {
+    if let NodeKind::Text { content } = &mut node.kind {
+      if content.is_empty() {
+        content.push_str(&self.input[node.span.start - self.base..node.span.end - self.base]);
+      }
+    }
+    node
   }
 
   /// Count consecutive delimiter characters and advance position.
@@ -77,7 +117,7 @@ impl<'a> InlineParser<'a> {
     self.pos = content_start + close_pos + backtick_count;
     Some(Node::new(
       NodeKind::CodeSpan { content },
-      Span::new(start, self.pos, 0, 0),
+      self.span(start, self.pos),
     ))
   }
 
@@ -89,50 +129,155 @@ impl<'a> InlineParser<'a> {
 
     // Fast search for closing ~~
     let remaining = &self.bytes[self.pos..];
-    let close_pos = find_double_tilde(remaining)?;
+    let Some(close_pos) = find_double_tilde(remaining) else {
+      self.pos = start;
+      return None;
+    };
 
-    let children =
-      InlineParser::new(&self.input[self.pos..self.pos + close_pos], self.link_defs).parse();
+    let children = self
+      .nested(
+        "Strikethrough",
+        &self.input[self.pos..self.pos + close_pos],
+        self.pos,
+      )
+      .parse();
 
     self.pos += close_pos + 2;
     Some(Node::with_children(
       NodeKind::Strikethrough,
-      Span::new(start, self.pos, 0, 0),
+      self.span(start, self.pos),
       children,
     ))
   }
 }
 
-/// Find closing delimiter sequence matching opening count.
-///
-/// Optimized for common cases (1-2 delimiters) with early exit.
-#[inline]
-fn find_close_fast(bytes: &[u8], delimiter: u8, count: usize) -> Option<usize> {
-  let len = bytes.len();
-  let mut i = 0;
+/// Pair delimiter runs into `Emphasis` and `Strong` nodes (the CommonMark
+/// "process emphasis" procedure). `nodes` holds each run as an empty
+/// `Text` node at its `slot`; unmatched runs get their characters back.
+pub(super) fn process_emphasis(
+  parser: &InlineParser,
+  nodes: Vec<Node>,
+  delims: &mut [Delimiter],
+) -> Vec<Node> {
+  let mut slots: Vec<Option<Node>> = nodes.into_iter().map(Some).collect();
+  // Per (char, closer can open, length % 3): delimiters below this index
+  // are known not to open for such a closer.
+  let mut bottoms = [0usize; 12];
 
-  while i < len {
-    // Fast scan for potential delimiter
-    if bytes[i] == delimiter {
-      let close_start = i;
-      let mut c = 1;
-      i += 1;
+  let mut c = 0;
+  while c < delims.len() {
+    let closer = &delims[c];
+    if !closer.active || !closer.can_close || closer.count == 0 {
+      c += 1;
+      continue;
+    }
+    let key =
+      usize::from(closer.ch == b'_') * 6 + usize::from(closer.can_open) * 3 + closer.original % 3;
 
-      // Count consecutive delimiters
-      while i < len && bytes[i] == delimiter {
-        c += 1;
-        i += 1;
+    let opener = (bottoms[key]..c).rev().find(|&o| {
+      let opener = &delims[o];
+      opener.active
+        && opener.count > 0
+        && opener.ch == closer.ch
+        && opener.can_open
+        && !breaks_rule_of_three(opener, closer)
+    });
+    let Some(o) = opener else {
+      bottoms[key] = c;
+      if !delims[c].can_open {
+        delims[c].active = false;
       }
+      c += 1;
+      continue;
+    };
 
-      // Match found if we have at least the required count
-      if c >= count {
-        return Some(close_start);
-      }
+    let used = if delims[o].count >= 2 && delims[c].count >= 2 {
+      2
     } else {
-      i += 1;
+      1
+    };
+    delims[o].count -= used;
+    for between in &mut delims[o + 1..c] {
+      between.active = false;
+    }
+    let span_start = delims[o].start + delims[o].count;
+    let span_end = delims[c].start + used;
+    delims[c].start += used;
+    delims[c].count -= used;
+
+    let children = merge_text(
+      slots[delims[o].slot + 1..delims[c].slot]
+        .iter_mut()
+        .filter_map(Option::take)
+        .map(|node| parser.fill_delimiter(node))
+        .collect(),
+    );
+    let (kind, rule) = if used == 2 {
+      (NodeKind::Strong, "strong")
+    } else {
+      (NodeKind::Emphasis, "emphasis")
+    };
+    trace::event(trace::Level::Inline, rule, parser.base + span_start, true);
+    slots[delims[o].slot + 1] = Some(Node::with_children(
+      kind,
+      parser.span(span_start, span_end),
+      children,
+    ));
+    for d in [o, c] {
+      let delim = &delims[d];
+      slots[delim.slot] =
+        (delim.count > 0).then(|| parser.delimiter_node(delim.start, delim.start + delim.count));
+    }
+    if delims[c].count == 0 {
+      c += 1;
     }
   }
-  None
+
+  for delim in delims.iter().filter(|d| d.count == d.original) {
+    trace::event(
+      trace::Level::Inline,
+      "emphasis",
+      parser.base + delim.start,
+      false,
+    );
+  }
+  merge_text(
+    slots
+      .into_iter()
+      .filter_map(|slot| slot.map(|node| parser.fill_delimiter(node)))
+      .collect(),
+  )
+}
+
+/// Runs that can both open and close only match when their combined
+/// length is not a multiple of three, unless both are (`*foo**bar*`).
+fn breaks_rule_of_three(opener: &Delimiter, closer: &Delimiter) -> bool {
+  (opener.can_close || closer.can_open)
+    && (opener.original + closer.original) % 3 == 0
+    && !(opener.original % 3 == 0 && closer.original % 3 == 0)
+}
+
+/// Join adjacent `Text` nodes left over from unmatched delimiter runs, in
+/// place.
+fn merge_text(mut nodes: Vec<Node>) -> Vec<Node> {
+  nodes.dedup_by(|node, prev| {
+    let (
+      NodeKind::Text { content },
+      NodeKind::Text {
+        content: prev_content,
+      },
+    ) = (&node.kind, &mut prev.kind)
+    else {
+      return false;
+    };
+    if prev.span.end != node.span.start {
+      return false;
+    }
+    prev_content.push_str(content);
+    prev.span.end = node.span.end;
+    true
+  });
+  nodes
 }
 
 /// Find matching backtick sequence for code span.
@@ -182,3 +327,80 @@ fn find_double_tilde(bytes: &[u8]) -> Option<usize> {
   }
   None
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn html(nodes: &[Node]) -> String {
+    nodes
+      .iter()
+      .map(|node| match &node.kind {
+        NodeKind::Text { content } => content.clone(),
+        NodeKind::Emphasis => format!("<em>{}</em>", html(&node.children)),
+        NodeKind::Strong => format!("<strong>{}</strong>", html(&node.children)),
+        other => format!("{:?}", other),
+      })
+      .collect()
+  }
+
+  fn render(input: &str) -> String {
+    html(&InlineParser::new(input, &[]).parse())
+  }
+
+  #[test]
+  fn test_flanking_rules() {
+    assert_eq!(render("*foo*"), "<em>foo</em>");
+    assert_eq!(render("* not emphasis *"), "* not emphasis *");
+    assert_eq!(render("a * foo bar*"), "a * foo bar*");
+    assert_eq!(render("foo*bar*baz"), "foo<em>bar</em>baz");
+    assert_eq!(render("foo_bar_baz"), "foo_bar_baz");
+    assert_eq!(render("_foo_bar_"), "<em>foo_bar</em>");
+    assert_eq!(render("*(**foo**)*"), "<em>(<strong>foo</strong>)</em>");
+  }
+
+  #[test]
+  fn test_unicode_flanking() {
+    // Guillemets are punctuation; a no-break space is whitespace
+    assert_eq!(render("«*mot*»"), "«<em>mot</em>»");
+    assert_eq!(render("«\u{a0}*mot*\u{a0}»"), "«\u{a0}<em>mot</em>\u{a0}»");
+    assert_eq!(render("*強調*、です"), "<em>強調</em>、です");
+    assert_eq!(render("文*（注）*です"), "文*（注）*です");
+    // A combining accent or soft hyphen is part of the word, not punctuation
+    assert_eq!(render("*ко\u{301}*шка"), "<em>ко\u{301}</em>шка");
+    assert_eq!(render("**при\u{ad}**вет"), "<strong>при\u{ad}</strong>вет");
+  }
+
+  #[test]
+  fn test_paired_runs_do_not_allocate() {
+    let (count, _) = crate::bench::count_allocations(|| {
+      InlineParser::new("a *b* c", &[]).parse();
+    });
+    // The node list and its growth to five entries, the delimiter list,
+    // three texts and the emphasis children; none for the runs themselves
+    assert_eq!(count, 7);
+  }
+
+  #[test]
+  fn test_nesting_and_leftovers() {
+    assert_eq!(render("***x***"), "<em><strong>x</strong></em>");
+    assert_eq!(
+      render("*foo**bar**baz*"),
+      "<em>foo<strong>bar</strong>baz</em>"
+    );
+    assert_eq!(render("*foo**bar*"), "<em>foo**bar</em>");
+    assert_eq!(render("**foo*"), "*<em>foo</em>");
+    assert_eq!(
+      render("__foo, __bar__, baz__"),
+      "<strong>foo, <strong>bar</strong>, baz</strong>"
+    );
+    assert_eq!(render("*a* **b**"), "<em>a</em> <strong>b</strong>");
+  }
+
+  #[test]
+  fn test_spans() {
+    let nodes = InlineParser::new("x **bold** y", &[]).parse();
+    assert_eq!((nodes[1].span.start, nodes[1].span.end), (2, 10));
+    assert_eq!(nodes[2].span.start, 10);
+  }
+}
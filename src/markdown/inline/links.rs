@@ -1,7 +1,8 @@
 //! Link, image, and reference parsing.
 
 use super::InlineParser;
-use crate::ast::{Node, NodeKind, ReferenceType, Span};
+use crate::ast::{Node, NodeKind, ReferenceType};
+use crate::markdown::{attrs, normalize_label};
 
 impl<'a> InlineParser<'a> {
   /// Try to parse link `[text](url)` or image `![alt](url)`.
@@ -12,22 +13,33 @@ impl<'a> InlineParser<'a> {
     }
     self.pos += 1; // skip [
 
-    let text_end = self.find_bracket()?;
-    let text = self.input[self.pos..text_end].to_string();
+    let Some(text_end) = self.find_bracket() else {
+      self.pos = start;
+      return None;
+    };
+    let input = self.input;
+    let text = &input[self.pos..text_end];
     self.pos = text_end + 1;
 
-    // Try inline destination: (url "title")
-    if self.try_inline_link(&text, start, is_image) {
-      return self.build_inline_link(start, is_image);
-    }
+    // Try inline destination: (url "title"), then reference link from definitions
+    let node = if self.try_inline_link(text, start, is_image) {
+      self.build_inline_link(start, is_image)
+    } else {
+      self.try_reference_link(text, start, is_image)
+    };
+    let Some(mut node) = node else {
+      self.pos = start;
+      return None;
+    };
 
-    // Try reference link from definitions
-    if let Some(node) = self.try_reference_link(&text, start, is_image) {
-      return Some(node);
+    if let NodeKind::Link { attributes, .. } | NodeKind::Image { attributes, .. } = &mut node.kind {
+      if let Some((parsed, len)) = attrs::parse(&self.input[self.pos..]) {
+        *attributes = parsed;
+        self.pos += len;
+        node.span.end = self.span(start, self.pos).end;
+      }
     }
-
-    self.pos = start;
-    None
+    Some(node)
   }
 
   fn try_inline_link(&mut self, _text: &str, _start: usize, _is_image: bool) -> bool {
@@ -48,7 +60,8 @@ impl<'a> InlineParser<'a> {
         return None;
       }
     };
-    let text = self.input[text_start..bracket_pos].to_string();
+    let input = self.input;
+    let text = &input[text_start..bracket_pos];
 
     self.pos += 1; // skip (
     let (url, title) = match self.parse_dest() {
@@ -59,52 +72,59 @@ impl<'a> InlineParser<'a> {
       }
     };
 
-    let children = InlineParser::new(&text, self.link_defs).parse();
+    let children = self
+      .nested(if is_image { "Image" } else { "Link" }, text, text_start)
+      .parse();
     let kind = if is_image {
       NodeKind::Image {
         url,
         title,
-        alt: text,
+        alt: text.to_string(),
+        attributes: Vec::new(),
       }
     } else {
       NodeKind::Link {
         url,
         title,
         ref_type: ReferenceType::Full,
+        attributes: Vec::new(),
       }
     };
 
     Some(Node::with_children(
       kind,
-      Span::new(start, self.pos, 0, 0),
+      self.span(start, self.pos),
       children,
     ))
   }
 
   fn try_reference_link(&self, text: &str, start: usize, is_image: bool) -> Option<Node> {
-    let def = self
-      .link_defs
-      .iter()
-      .find(|d| d.label.eq_ignore_ascii_case(text))?;
-    let children = InlineParser::new(text, self.link_defs).parse();
+    let key = normalize_label(text);
+    let def = self.link_defs.iter().find(|d| d.key == key)?;
+    let text_start = start + if is_image { 2 } else { 1 };
+    let children = self
+      .nested(if is_image { "Image" } else { "Link" }, text, text_start)
+      .parse();
 
     let kind = if is_image {
       NodeKind::Image {
         url: def.url.clone(),
         title: def.title.clone(),
         alt: text.to_string(),
+        attributes: Vec::new(),
       }
     } else {
       NodeKind::Link {
         url: def.url.clone(),
         title: def.title.clone(),
         ref_type: ReferenceType::Shortcut,
+        attributes: Vec::new(),
       }
     };
 
     Some(Node::with_children(
       kind,
-      Span::new(start, self.pos, 0, 0),
+      self.span(start, self.pos),
       children,
     ))
   }
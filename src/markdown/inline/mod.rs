@@ -4,7 +4,7 @@ mod emphasis;
 mod links;
 mod special;
 
-use super::LinkDef;
+use super::{trace, KindFilter, LinkDef, ParseOptions};
 use crate::ast::{Node, NodeKind, Span};
 
 /// Returns true if byte might start a special inline element.
@@ -22,6 +22,9 @@ pub struct InlineParser<'a> {
   bytes: &'a [u8],
   pos: usize,
   link_defs: &'a [LinkDef],
+  /// Offset of `input` within the document, added to every span
+  base: usize,
+  options: ParseOptions,
 }
 
 impl<'a> InlineParser<'a> {
@@ -33,17 +36,52 @@ impl<'a> InlineParser<'a> {
       bytes: input.as_bytes(),
       pos: 0,
       link_defs,
+      base: 0,
+      options: ParseOptions::new(),
     }
   }
 
+  /// Place the input at `base` in the document, so spans point into the
+  /// document rather than the extracted text.
+  #[inline]
+  pub fn with_offset(mut self, base: usize) -> Self {
+    self.base = base;
+    self
+  }
+
+  #[inline]
+  pub fn with_options(mut self, options: ParseOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Parser for `text` found at `start` in this parser's input, such as
+  /// link text, sharing its definitions and options. `kind` is the node
+  /// the text belongs to.
+  #[inline]
+  fn nested<'b>(&'b self, kind: &str, text: &'b str, start: usize) -> InlineParser<'b> {
+    let kinds = self.options.kinds.within(kind);
+    InlineParser::new(text, self.link_defs)
+      .with_offset(self.base + start)
+      .with_options(ParseOptions {
+        kinds,
+        ..self.options
+      })
+  }
+
   /// Parse inline content and return nodes.
   ///
   /// Scans the input accumulating plain text, and when a special
   /// character is found, attempts to parse it as an inline element.
   #[inline]
   pub fn parse(&mut self) -> Vec<Node> {
+    if !self.options.kinds.inline() {
+      self.pos = self.bytes.len();
+      return Vec::new();
+    }
     // Pre-allocate with reasonable estimate (1 node per 50 chars)
     let mut nodes = Vec::with_capacity((self.bytes.len() / 50).max(4));
+    let mut delims = Vec::new();
     let mut text_start = self.pos;
 
     while self.pos < self.bytes.len() {
@@ -55,9 +93,22 @@ impl<'a> InlineParser<'a> {
         continue;
       }
 
+      // Emphasis runs become empty text for now; `process_emphasis` pairs
+      // them and fills in the ones left unpaired.
+      if matches!(b, b'*' | b'_') {
+        self.flush_text(text_start, self.pos, &mut nodes);
+        let run_start = self.pos;
+        let run = self.scan_delimiter_run(nodes.len());
+        nodes.push(self.delimiter_node(run_start, self.pos));
+        delims.push(run);
+        text_start = self.pos;
+        continue;
+      }
+
       // Potential special character - try to parse it
+      let special_start = self.pos;
       if let Some(node) = self.try_special() {
-        self.flush_text(text_start, &mut nodes);
+        self.flush_text(text_start, special_start, &mut nodes);
         nodes.push(node);
         text_start = self.pos;
       } else {
@@ -65,48 +116,61 @@ impl<'a> InlineParser<'a> {
       }
     }
 
-    self.flush_text(text_start, &mut nodes);
-    nodes
+    self.flush_text(text_start, self.pos, &mut nodes);
+    if !delims.is_empty() {
+      nodes = emphasis::process_emphasis(self, nodes, &mut delims);
+    }
+    self.options.kinds.prune(nodes)
   }
 
-  /// Flush accumulated text as a text node.
+  /// Flush accumulated text as a text node, unless text is filtered out.
   #[inline]
-  fn flush_text(&self, start: usize, nodes: &mut Vec<Node>) {
-    if start < self.pos {
-      nodes.push(self.text_node(start, self.pos));
+  fn flush_text(&self, start: usize, end: usize, nodes: &mut Vec<Node>) {
+    if start < end && self.keeps_text() {
+      nodes.push(self.text_node(start, end));
     }
   }
 
+  /// Whether to build plain text: it may be kept, or emphasis may be,
+  /// which is paired around the nodes already built and needs them there
+  /// even when the text itself is pruned afterwards.
+  #[inline]
+  fn keeps_text(&self) -> bool {
+    let kinds = &self.options.kinds;
+    *kinds == KindFilter::ALL
+      || kinds.keeps("Text")
+      || kinds.keeps("Emphasis")
+      || kinds.keeps("Strong")
+  }
+
   /// Try to parse a special inline element at current position.
   #[inline]
   fn try_special(&mut self) -> Option<Node> {
     // SAFETY: We know pos < bytes.len() from the caller's while condition
     let ch = self.bytes[self.pos];
 
+    let at = self.base + self.pos;
     match ch {
-      b'*' | b'_' => self.try_emphasis(),
-      b'`' => self.try_code_span(),
-      b'[' => self.try_link_or_footnote(),
-      b'!' if self.peek_at(1) == Some(b'[') => self.try_link(true),
-      b'~' if self.peek_at(1) == Some(b'~') => self.try_strike(),
-      b'<' => self.try_autolink(),
-      b'\\' => self.try_escape(),
-      b'$' => self.try_math(),
-      b'h' if self.check_autourl() => self.try_autourl(),
+      b'`' => trace::inline("code_span", at, self.try_code_span()),
+      b'[' if self.peek_at(1) == Some(b'^') => {
+        trace::inline("footnote_ref", at, self.try_footnote_ref())
+      }
+      b'[' => trace::inline("link", at, self.try_link(false)),
+      b'!' if self.peek_at(1) == Some(b'[') => trace::inline("image", at, self.try_link(true)),
+      b'~' if self.peek_at(1) == Some(b'~') => {
+        trace::inline("strikethrough", at, self.try_strike())
+      }
+      b'<' => trace::inline("autolink", at, self.try_autolink())
+        .or_else(|| trace::inline("html_inline", at, self.try_html_inline())),
+      b'\\' if self.options.latex_math => trace::inline("latex_math", at, self.try_latex_math())
+        .or_else(|| trace::inline("escape", at, self.try_escape())),
+      b'\\' => trace::inline("escape", at, self.try_escape()),
+      b'$' if self.options.dollar_math => trace::inline("math", at, self.try_math()),
+      b'h' if self.check_autourl() => trace::inline("autourl", at, self.try_autourl()),
       _ => None,
     }
   }
 
-  /// Try link or footnote reference based on next char.
-  #[inline]
-  fn try_link_or_footnote(&mut self) -> Option<Node> {
-    if self.peek_at(1) == Some(b'^') {
-      self.try_footnote_ref()
-    } else {
-      self.try_link(false)
-    }
-  }
-
   /// Create a text node from a slice range.
   #[inline]
   fn text_node(&self, s: usize, e: usize) -> Node {
@@ -114,10 +178,16 @@ impl<'a> InlineParser<'a> {
       NodeKind::Text {
         content: self.input[s..e].to_string(),
       },
-      Span::new(s, e, 0, 0),
+      self.span(s, e),
     )
   }
 
+  /// Span for the input range `s..e`, in document offsets.
+  #[inline(always)]
+  pub(crate) fn span(&self, s: usize, e: usize) -> Span {
+    Span::new(self.base + s, self.base + e, 0, 0)
+  }
+
   /// Peek at byte at offset from current position.
   #[inline(always)]
   pub(crate) fn peek_at(&self, offset: usize) -> Option<u8> {
@@ -155,6 +225,37 @@ mod tests {
     assert!(!nodes.is_empty());
   }
 
+  #[test]
+  fn test_text_around_element_is_not_repeated() {
+    let nodes = InlineParser::new("a **b** c", &[]).parse();
+    let kinds: Vec<_> = nodes.iter().map(|n| &n.kind).collect();
+    assert_eq!(kinds.len(), 3);
+    assert!(matches!(kinds[0], NodeKind::Text { content } if content == "a "));
+    assert!(matches!(kinds[1], NodeKind::Strong));
+    assert!(matches!(kinds[2], NodeKind::Text { content } if content == " c"));
+  }
+
+  #[test]
+  fn test_text_spans_stop_at_elements() {
+    let nodes = InlineParser::new("a * b `c` d", &[]).parse();
+    let spans: Vec<_> = nodes.iter().map(|n| (n.span.start, n.span.end)).collect();
+    // The lone `*` stays in the text; the text before the code span ends where it starts
+    assert_eq!(spans, vec![(0, 6), (6, 9), (9, 11)]);
+    assert!(matches!(&nodes[0].kind, NodeKind::Text { content } if content == "a * b "));
+  }
+
+  #[test]
+  fn test_unclosed_bracket() {
+    for input in ["[", "![", "a [b"] {
+      let nodes = InlineParser::new(input, &[]).parse();
+      assert!(
+        matches!(&nodes[..], [Node { kind: NodeKind::Text { content }, .. }] if content == input),
+        "{:?}",
+        nodes
+      );
+    }
+  }
+
   #[test]
   fn test_code_span() {
     let nodes = InlineParser::new("`code`", &[]).parse();
@@ -175,6 +276,46 @@ mod tests {
       .any(|n| matches!(&n.kind, NodeKind::MathInline { .. })));
   }
 
+  #[test]
+  fn test_latex_math_delimiters() {
+    let latex = ParseOptions {
+      latex_math: true,
+      ..ParseOptions::new()
+    };
+    let parse = |input| InlineParser::new(input, &[]).with_options(latex).parse();
+    let nodes = parse(r"a \(x\) b \[y\] c");
+    assert_eq!(
+      nodes[1].kind,
+      NodeKind::MathInline {
+        content: "x".to_string()
+      }
+    );
+    assert_eq!(
+      nodes[3].kind,
+      NodeKind::MathBlock {
+        content: "y".to_string(),
+        display: false,
+      }
+    );
+
+    // Unclosed: just an escaped parenthesis
+    let nodes = parse(r"\(x");
+    assert!(matches!(&nodes[0].kind, NodeKind::Text { content } if content == "("));
+  }
+
+  #[test]
+  fn test_backslash_brackets_are_escapes_by_default() {
+    let text: String = InlineParser::new(r"See \[1\] and \(a\)", &[])
+      .parse()
+      .into_iter()
+      .map(|n| match n.kind {
+        NodeKind::Text { content } => content,
+        other => panic!("expected text, got {:?}", other),
+      })
+      .collect();
+    assert_eq!(text, "See [1] and (a)");
+  }
+
   #[test]
   fn test_footnote_ref() {
     let nodes = InlineParser::new("Text[^1]", &[]).parse();
@@ -190,4 +331,46 @@ mod tests {
       .iter()
       .any(|n| matches!(&n.kind, NodeKind::AutoUrl { .. })));
   }
+
+  #[test]
+  fn test_autourl_unicode_boundaries() {
+    let urls = |input: &str| -> Vec<String> {
+      InlineParser::new(input, &[])
+        .parse()
+        .into_iter()
+        .filter_map(|n| match n.kind {
+          NodeKind::AutoUrl { url } => Some(url),
+          _ => None,
+        })
+        .collect()
+    };
+    assert_eq!(urls("詳細は（https://x.dev/a）。"), vec!["https://x.dev/a"]);
+    assert_eq!(urls("voir «https://x.dev/a»."), vec!["https://x.dev/a"]);
+    assert_eq!(
+      urls("см.\u{a0}https://x.dev/путь."),
+      vec!["https://x.dev/путь"]
+    );
+    assert!(urls("адресhttps://x.dev").is_empty());
+  }
+
+  #[test]
+  fn test_autolink_validation() {
+    let input = "<irc://x.dev/a> <me@x.dev> <not a link> <a@b@c> (see https://x.dev/a_(b)).";
+    let urls: Vec<String> = InlineParser::new(input, &[])
+      .parse()
+      .into_iter()
+      .filter_map(|n| match n.kind {
+        NodeKind::Link { url, .. } | NodeKind::AutoUrl { url } => Some(url),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      urls,
+      vec!["irc://x.dev/a", "mailto:me@x.dev", "https://x.dev/a_(b)"]
+    );
+    assert!(InlineParser::new("xhttps://x.dev", &[])
+      .parse()
+      .iter()
+      .all(|n| matches!(&n.kind, NodeKind::Text { .. })));
+  }
 }
@@ -1,12 +1,18 @@
 //! Special inline elements: math, footnotes, autolinks, escapes.
 
 use super::InlineParser;
-use crate::ast::{Node, NodeKind, ReferenceType, Span};
+use crate::ast::{Node, NodeKind, ReferenceType};
+use crate::markdown::autolink::{is_absolute_uri, is_email, starts_word, url_len};
+use crate::markdown::html::html_len;
 
 impl<'a> InlineParser<'a> {
-  /// Try to parse inline math $...$ or $$...$$
+  /// Try to parse inline math $...$ or $$...$$; an escaped `\$` opens
+  /// neither.
   pub fn try_math(&mut self) -> Option<Node> {
     let start = self.pos;
+    if self.is_escaped() {
+      return None;
+    }
     let is_block = self.peek_at(1) == Some(b'$');
 
     if is_block {
@@ -17,28 +23,66 @@ impl<'a> InlineParser<'a> {
   }
 
   fn try_math_block(&mut self, start: usize) -> Option<Node> {
-    self.pos += 2;
-    let content_start = self.pos;
-    let end = self.input[self.pos..].find("$$")?;
+    let content_start = start + 2;
+    let end = self.input[content_start..].find("$$")?;
     let content = self.input[content_start..content_start + end].to_string();
     self.pos = content_start + end + 2;
     Some(Node::new(
-      NodeKind::MathBlock { content },
-      Span::new(start, self.pos, 0, 0),
+      NodeKind::MathBlock {
+        content,
+        display: false,
+      },
+      self.span(start, self.pos),
     ))
   }
 
+  /// Try `\(...\)` as inline math or `\[...\]` as display math within
+  /// the line.
+  pub fn try_latex_math(&mut self) -> Option<Node> {
+    let start = self.pos;
+    let close = match self.peek_at(1)? {
+      b'(' => "\\)",
+      b'[' => "\\]",
+      _ => return None,
+    };
+    let content_start = start + 2;
+    let end = content_start + self.input[content_start..].find(close)?;
+    let content = self.input[content_start..end].to_string();
+    self.pos = end + 2;
+
+    let kind = if close == "\\)" {
+      NodeKind::MathInline { content }
+    } else {
+      NodeKind::MathBlock {
+        content,
+        display: false,
+      }
+    };
+    Some(Node::new(kind, self.span(start, self.pos)))
+  }
+
+  /// `$...$`. Unless `strict_math` is set, Pandoc's rules keep currency
+  /// as text: the opening `$` must be followed by a non-space and the
+  /// closing one preceded by a non-space and not followed by a digit, so
+  /// `$5 and $10` is not math.
   fn try_math_inline(&mut self, start: usize) -> Option<Node> {
+    let strict = self.options.strict_math;
+    if !strict && self.peek_at(1).map_or(true, |b| b.is_ascii_whitespace()) {
+      return None;
+    }
     self.pos += 1;
     let content_start = self.pos;
 
     while self.pos < self.bytes.len() {
-      if self.bytes[self.pos] == b'$' && !self.is_escaped() {
+      if self.bytes[self.pos] == b'$'
+        && !self.is_escaped()
+        && (strict || self.closes_math(content_start))
+      {
         let content = self.input[content_start..self.pos].to_string();
         self.pos += 1;
         return Some(Node::new(
           NodeKind::MathInline { content },
-          Span::new(start, self.pos, 0, 0),
+          self.span(start, self.pos),
         ));
       }
       self.pos += 1;
@@ -48,8 +92,23 @@ impl<'a> InlineParser<'a> {
     None
   }
 
+  /// Whether the `$` at the current position can close math opened at
+  /// `content_start`.
+  fn closes_math(&self, content_start: usize) -> bool {
+    self.pos > content_start
+      && !self.bytes[self.pos - 1].is_ascii_whitespace()
+      && !self.peek_at(1).is_some_and(|b| b.is_ascii_digit())
+  }
+
+  /// Whether an odd run of backslashes precedes the current position, so
+  /// `\\$` is a literal backslash before a real `$`.
   fn is_escaped(&self) -> bool {
-    self.pos > 0 && self.bytes[self.pos - 1] == b'\\'
+    let run = self.bytes[..self.pos]
+      .iter()
+      .rev()
+      .take_while(|&&b| b == b'\\')
+      .count();
+    run % 2 == 1
   }
 
   /// Try to parse footnote reference [^label]
@@ -62,36 +121,39 @@ impl<'a> InlineParser<'a> {
     self.pos += label_end + 1;
 
     Some(Node::new(
-      NodeKind::FootnoteReference { label },
-      Span::new(start, self.pos, 0, 0),
+      NodeKind::FootnoteReference { label, index: 0 },
+      self.span(start, self.pos),
     ))
   }
 
-  /// Check if we're at start of a URL (for auto-linking)
+  /// Check if we're at start of a URL (for auto-linking): `http://` or
+  /// `https://` at the start of a word.
   pub fn check_autourl(&self) -> bool {
     let rest = &self.input[self.pos..];
-    rest.starts_with("http://") || rest.starts_with("https://")
+    starts_word(self.input[..self.pos].chars().next_back())
+      && (rest.starts_with("http://") || rest.starts_with("https://"))
   }
 
-  /// Try to parse auto-detected URL
+  /// Try to parse auto-detected URL, without trailing punctuation.
   pub fn try_autourl(&mut self) -> Option<Node> {
     let start = self.pos;
-    while self.pos < self.bytes.len() && !is_url_terminator(self.bytes[self.pos]) {
-      self.pos += 1;
-    }
+    self.pos += url_len(&self.input[start..])?;
     let url = self.input[start..self.pos].to_string();
     Some(Node::new(
       NodeKind::AutoUrl { url },
-      Span::new(start, self.pos, 0, 0),
+      self.span(start, self.pos),
     ))
   }
 
   /// Try to parse autolink (`<url>` or `<email>`).
   pub fn try_autolink(&mut self) -> Option<Node> {
     let start = self.pos;
+    let end = self.bytes[start + 1..]
+      .iter()
+      .position(|&b| matches!(b, b'>' | b'<' | b' ' | b'\n'))
+      .filter(|&end| self.bytes[start + 1 + end] == b'>')?;
     self.pos += 1; // skip <
 
-    let end = self.bytes[self.pos..].iter().position(|&b| b == b'>')?;
     let url = &self.input[self.pos..self.pos + end];
     self.pos += end + 1;
 
@@ -106,11 +168,30 @@ impl<'a> InlineParser<'a> {
         url: full_url,
         title: None,
         ref_type: ReferenceType::Full,
+        attributes: Vec::new(),
       },
-      Span::new(start, self.pos, 0, 0),
+      self.span(start, self.pos),
     ))
   }
 
+  /// Inline HTML tag, kept raw as `HtmlInline`, or a `Comment`.
+  pub fn try_html_inline(&mut self) -> Option<Node> {
+    let start = self.pos;
+    let len = html_len(&self.input[start..])?;
+    self.pos = start + len;
+    let raw = &self.input[start..self.pos];
+    let kind = match raw.strip_prefix("<!--") {
+      Some(comment) => NodeKind::Comment {
+        content: comment[..comment.len() - 3].to_string(),
+        block: false,
+      },
+      None => NodeKind::HtmlInline {
+        content: raw.to_string(),
+      },
+    };
+    Some(Node::new(kind, self.span(start, self.pos)))
+  }
+
   /// Try to parse backslash escape.
   pub fn try_escape(&mut self) -> Option<Node> {
     let start = self.pos;
@@ -121,7 +202,7 @@ impl<'a> InlineParser<'a> {
       self.pos += 1;
       return Some(Node::new(
         NodeKind::Text { content },
-        Span::new(start, self.pos, 0, 0),
+        self.span(start, self.pos),
       ));
     }
 
@@ -130,18 +211,13 @@ impl<'a> InlineParser<'a> {
   }
 }
 
-#[inline(always)]
-fn is_url_terminator(b: u8) -> bool {
-  matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b')' | b']' | b'>')
-}
-
 #[inline(always)]
 fn is_valid_autolink(url: &str) -> bool {
-  url.contains('@') || url.starts_with("http") || url.starts_with("mailto:")
+  is_absolute_uri(url) || is_email(url)
 }
 
 fn normalize_autolink(url: &str) -> String {
-  if url.contains('@') && !url.starts_with("mailto:") {
+  if is_email(url) {
     format!("mailto:{}", url)
   } else {
     url.to_string()
@@ -150,5 +226,70 @@ fn normalize_autolink(url: &str) -> String {
 
 #[inline(always)]
 fn is_escapable(b: u8) -> bool {
-  b"\\`*_{}[]()#+-.!|<>~".contains(&b)
+  b"\\`*_{}[]()#+-.!|<>~$".contains(&b)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::ParseOptions;
+
+  fn math(input: &str, options: ParseOptions) -> Vec<String> {
+    InlineParser::new(input, &[])
+      .with_options(options)
+      .parse()
+      .into_iter()
+      .filter_map(|n| match n.kind {
+        NodeKind::MathInline { content } => Some(content),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_currency_is_not_math() {
+    let options = ParseOptions::new();
+    assert!(math("$5 and $10", options).is_empty());
+    assert!(math("from $20,000 to $30,000", options).is_empty());
+    assert!(math("$ x $", options).is_empty());
+    assert!(math("$x $", options).is_empty());
+    assert!(math(r"\$x\$", options).is_empty());
+  }
+
+  #[test]
+  fn test_escaped_dollar() {
+    let nodes = InlineParser::new(r"Escaped \$5 and $y$.", &[]).parse();
+    let kinds: Vec<String> = nodes
+      .into_iter()
+      .map(|n| match n.kind {
+        NodeKind::Text { content } => format!("text {}", content),
+        NodeKind::MathInline { content } => format!("math {}", content),
+        other => format!("{:?}", other),
+      })
+      .collect();
+    assert_eq!(
+      kinds,
+      vec!["text Escaped ", "text $", "text 5 and ", "math y", "text ."]
+    );
+    assert_eq!(math(r"\\$y$", ParseOptions::new()), vec!["y"]);
+  }
+
+  #[test]
+  fn test_dollar_math() {
+    let options = ParseOptions::new();
+    assert_eq!(math("area $\\pi r^2$.", options), vec!["\\pi r^2"]);
+    assert_eq!(math("$a$ and $b$", options), vec!["a", "b"]);
+    // A closing `$` before a digit is skipped for a later one
+    assert_eq!(math("$x$1 y$", options), vec!["x$1 y"]);
+  }
+
+  #[test]
+  fn test_strict_math() {
+    let options = ParseOptions {
+      strict_math: true,
+      ..ParseOptions::new()
+    };
+    assert_eq!(math("$5 and $10", options), vec!["5 and "]);
+    assert_eq!(math("$ x $", options), vec![" x "]);
+  }
 }
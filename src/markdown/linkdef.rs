@@ -1,77 +1,286 @@
 //! Link definition parsing.
 
+use super::abbr::{self, AbbrDef};
 use super::scanner::Scanner;
+use super::unicode::fold_case;
 
 /// Link reference: `[label]: url "title"`
 #[derive(Debug, Clone)]
 pub struct LinkDef {
   pub label: String,
+  /// `label` as references match it, from [`normalize_label`]
+  pub key: String,
   pub url: String,
   pub title: Option<String>,
 }
 
-/// Collect all link definitions from the document.
-pub fn collect_definitions(scanner: &mut Scanner) -> Vec<LinkDef> {
+/// The CommonMark matching form of a link label: Unicode case folded, with
+/// the ends trimmed and each run of spaces, tabs and line endings inside
+/// collapsed to one space, so `[Straße  Map]` matches `[STRASSE map]`.
+pub fn normalize_label(label: &str) -> String {
+  let mut key = String::with_capacity(label.len());
+  for word in label
+    .split([' ', '\t', '\n', '\r'])
+    .filter(|w| !w.is_empty())
+  {
+    if !key.is_empty() {
+      key.push(' ');
+    }
+    fold_case(word, &mut key);
+  }
+  key
+}
+
+/// Collect all link and abbreviation definitions from the document,
+/// skipping fenced and indented code. A term defined twice keeps its first
+/// abbreviation, as a label keeps its first link: references take the
+/// first definition whose [`LinkDef::key`] matches.
+pub fn collect_definitions(scanner: &mut Scanner) -> (Vec<LinkDef>, Vec<AbbrDef>) {
   let mut defs = Vec::new();
+  let mut abbreviations: Vec<AbbrDef> = Vec::new();
+  let mut fence: Option<&str> = None;
   while !scanner.is_eof() {
-    let start = scanner.pos();
-    scanner.skip_whitespace_inline();
+    let rest = scanner.remaining();
+    let line = rest.split('\n').next().unwrap_or_default();
+    let trimmed = line.trim_start_matches(' ');
+    let indented = line.len() - trimmed.len() >= 4;
 
-    if scanner.check(b'[') {
-      if let Some(def) = try_parse(scanner) {
-        defs.push(def);
-        continue;
+    match fence {
+      Some(marker) => {
+        if !indented && trimmed.starts_with(marker) {
+          fence = None;
+        }
+      }
+      None if indented => {}
+      None if trimmed.starts_with("```") => fence = Some("```"),
+      None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+      None => {
+        if let Some((def, len)) = parse(rest) {
+          defs.push(def);
+          scanner.set_pos(scanner.pos() + len);
+          continue;
+        }
+        if let Some((def, _)) = abbr::parse(rest) {
+          if !abbreviations.iter().any(|known| known.term == def.term) {
+            abbreviations.push(def);
+          }
+        }
       }
     }
-
-    scanner.set_pos(start);
     scanner.skip_line();
   }
-  defs
+  (defs, abbreviations)
 }
 
-fn try_parse(scanner: &mut Scanner) -> Option<LinkDef> {
-  if !scanner.consume(b'[') {
+/// Parse a definition at the start of `input`, following CommonMark: up to
+/// three spaces of indent, `<destination>` or a bare one, and a title on
+/// the same or the following line that may span lines. Returns the bytes
+/// consumed, through the final newline.
+pub fn parse(input: &str) -> Option<(LinkDef, usize)> {
+  let bytes = input.as_bytes();
+  let mut pos = bytes.iter().take_while(|&&b| b == b' ').count();
+  if pos > 3 || bytes.get(pos) != Some(&b'[') {
     return None;
   }
+  pos += 1;
 
-  let label = scanner.scan_until(b']')?;
-  scanner.advance(); // ]
-
-  if !scanner.consume(b':') {
+  let label_end = pos + input[pos..].find(']')?;
+  let label = &input[pos..label_end];
+  if label.trim().is_empty()
+    || label.starts_with('^') // footnote definition
+    || label.contains('[')
+    || has_blank_line(label)
+  {
     return None;
   }
+  pos = label_end + 1;
+  if bytes.get(pos) != Some(&b':') {
+    return None;
+  }
+  pos = skip_space(bytes, pos + 1, true);
 
-  scanner.skip_whitespace_inline();
-  scanner.consume(b'\n');
-  scanner.skip_whitespace_inline();
+  let (url, dest_end) = parse_destination(input, pos)?;
+  let after_dest = skip_space(bytes, dest_end, false);
 
-  let url = parse_url(scanner)?;
-  scanner.skip_whitespace_inline();
-  let title = parse_title(scanner);
+  // A title needs whitespace before it; on a later line it is optional,
+  // so a malformed one there just ends the definition.
+  let title_start = skip_space(bytes, dest_end, true);
+  if title_start > dest_end {
+    if let Some((title, end)) = parse_title(input, title_start) {
+      let end = skip_space(bytes, end, false);
+      if let Some(consumed) = line_end(bytes, end) {
+        return Some((
+          LinkDef {
+            label: label.trim().to_string(),
+            key: normalize_label(label),
+            url,
+            title: Some(title),
+          },
+          consumed,
+        ));
+      }
+    }
+  }
 
-  Some(LinkDef { label, url, title })
+  let consumed = line_end(bytes, after_dest)?;
+  Some((
+    LinkDef {
+      label: label.trim().to_string(),
+      key: normalize_label(label),
+      url,
+      title: None,
+    },
+    consumed,
+  ))
 }
 
-fn parse_url(scanner: &mut Scanner) -> Option<String> {
-  if scanner.consume(b'<') {
-    let url = scanner.scan_until(b'>')?;
-    scanner.advance();
-    Some(url)
-  } else {
-    Some(scanner.scan_non_whitespace())
+/// `<...>` (may be empty, no line breaks) or a run of non-whitespace.
+fn parse_destination(input: &str, pos: usize) -> Option<(String, usize)> {
+  let rest = &input[pos..];
+  if let Some(inner) = rest.strip_prefix('<') {
+    let end = inner.find(['>', '<', '\n'])?;
+    if inner.as_bytes()[end] != b'>' {
+      return None;
+    }
+    return Some((inner[..end].to_string(), pos + end + 2));
+  }
+  let len = rest
+    .find(|c: char| c.is_ascii_whitespace())
+    .unwrap_or(rest.len());
+  if len == 0 {
+    return None;
   }
+  Some((rest[..len].to_string(), pos + len))
 }
 
-fn parse_title(scanner: &mut Scanner) -> Option<String> {
-  let delim = scanner.peek()?;
-  if delim != b'"' && delim != b'\'' && delim != b'(' {
-    return None;
+/// `"..."`, `'...'` or `(...)`; backslash escapes the closing delimiter and
+/// the title may not contain a blank line.
+fn parse_title(input: &str, pos: usize) -> Option<(String, usize)> {
+  let close = match input.as_bytes().get(pos)? {
+    b'"' => '"',
+    b'\'' => '\'',
+    b'(' => ')',
+    _ => return None,
+  };
+  let mut title = String::new();
+  let mut last_break: Option<usize> = None;
+  let mut chars = input[pos + 1..].char_indices();
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some((_, next)) if next.is_ascii_punctuation() => title.push(next),
+        Some((_, next)) => {
+          title.push('\\');
+          title.push(next);
+        }
+        None => return None,
+      },
+      c if c == close => return Some((title, pos + 1 + i + 1)),
+      '\n' => {
+        if last_break.is_some_and(|at| title[at..].trim().is_empty()) {
+          return None;
+        }
+        last_break = Some(title.len());
+        title.push('\n');
+      }
+      c => title.push(c),
+    }
   }
+  None
+}
 
-  let end = if delim == b'(' { b')' } else { delim };
-  scanner.advance();
-  let title = scanner.scan_until(end)?;
-  scanner.advance();
-  Some(title)
+/// Skip spaces and tabs, and with `newline` at most one line break.
+fn skip_space(bytes: &[u8], mut pos: usize, newline: bool) -> usize {
+  let mut seen_newline = !newline;
+  while let Some(&b) = bytes.get(pos) {
+    match b {
+      b' ' | b'\t' => {}
+      b'\n' if !seen_newline => seen_newline = true,
+      _ => break,
+    }
+    pos += 1;
+  }
+  pos
+}
+
+/// Position after the line break if `pos` is at the end of a line.
+fn line_end(bytes: &[u8], pos: usize) -> Option<usize> {
+  match bytes.get(pos) {
+    None => Some(pos),
+    Some(b'\n') => Some(pos + 1),
+    Some(b'\r') if bytes.get(pos + 1) == Some(&b'\n') => Some(pos + 2),
+    _ => None,
+  }
+}
+
+fn has_blank_line(text: &str) -> bool {
+  text.split('\n').skip(1).any(|line| line.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn def(input: &str) -> Option<(String, String, Option<String>, usize)> {
+    parse(input).map(|(d, len)| (d.label, d.url, d.title, len))
+  }
+
+  #[test]
+  fn test_parse_forms() {
+    let full = |label: &str, url: &str, title: Option<&str>, len| {
+      Some((
+        label.to_string(),
+        url.to_string(),
+        title.map(str::to_string),
+        len,
+      ))
+    };
+    assert_eq!(
+      def("[a]: /url \"T\"\nnext"),
+      full("a", "/url", Some("T"), 14)
+    );
+    assert_eq!(def("   [b]:\n  </my url>"), full("b", "/my url", None, 19));
+    assert_eq!(
+      def("[c]: /u\n  'Line one\n  line two'\n"),
+      full("c", "/u", Some("Line one\n  line two"), 32)
+    );
+    assert_eq!(def("[d]: <>"), full("d", "", None, 7));
+    assert_eq!(def("[e]: /u (t \\) x)"), full("e", "/u", Some("t ) x"), 16));
+  }
+
+  #[test]
+  fn test_title_on_next_line_is_optional() {
+    // Not a title: trailing text, so the title line is not consumed.
+    assert_eq!(
+      def("[a]: /u\n\"t\" more"),
+      Some(("a".to_string(), "/u".to_string(), None, 8))
+    );
+    // On the same line trailing text invalidates the definition.
+    assert_eq!(def("[a]: /u \"t\" more"), None);
+  }
+
+  #[test]
+  fn test_normalize_label() {
+    assert_eq!(normalize_label("  Foo \t\n  Bar "), "foo bar");
+    assert_eq!(normalize_label("ẞ"), normalize_label("SS"));
+    assert_eq!(normalize_label("ΟΔΟΣ"), normalize_label("οδος"));
+    assert_eq!(normalize_label("Ёлка"), "ёлка");
+    assert_eq!(parse("[Straße  Map]: /m").unwrap().0.key, "strasse map");
+  }
+
+  #[test]
+  fn test_rejects() {
+    for input in [
+      "    [a]: /u",
+      "[^a]: note",
+      "[]: /u",
+      "[a]:",
+      "[a] /u",
+      "[a]: <b\nc>",
+      "[a]: /u x",
+      "[a]: /u 'open\n\nclosed'",
+    ] {
+      assert_eq!(def(input), None, "{:?}", input);
+    }
+  }
 }
@@ -2,24 +2,46 @@
 //!
 //! Two-pass: first collects link defs, then parses blocks/inlines.
 
+mod abbr;
+mod attrs;
+pub mod autolink;
 mod block;
+mod elements;
 mod frontmatter;
+pub mod html;
 mod inline;
 mod linkdef;
+mod options;
+mod parallel;
 mod scanner;
+pub mod trace;
+mod unicode;
 
-use crate::ast::{Document, DocumentMetadata, DocumentType, Node};
+use crate::ast::{Document, DocumentMetadata, DocumentType, Node, NodeKind};
+use abbr::AbbrDef;
+use parallel::{Segments, MIN_SEGMENT};
 
 pub use block::BlockParser;
+pub use elements::ElementRegistry;
+pub use frontmatter::read as read_frontmatter;
+pub use frontmatter::{option_overrides, OPTIONS_KEY};
 pub use inline::InlineParser;
-pub use linkdef::LinkDef;
+pub use linkdef::{normalize_label, LinkDef};
+pub use options::{KindFilter, ParseOptions};
 pub use scanner::Scanner;
 
+static NO_ELEMENTS: ElementRegistry = ElementRegistry::new();
+
 /// Main parser. Create with `new()`, call `parse()`.
 pub struct MarkdownParser<'a> {
   scanner: Scanner<'a>,
   link_defs: Vec<LinkDef>,
+  abbreviations: Vec<AbbrDef>,
   frontmatter: Option<Node>,
+  elements: &'a ElementRegistry,
+  options: ParseOptions,
+  threads: usize,
+  min_segment: usize,
 }
 
 impl<'a> MarkdownParser<'a> {
@@ -27,25 +49,83 @@ impl<'a> MarkdownParser<'a> {
     Self {
       scanner: Scanner::new(input),
       link_defs: Vec::new(),
+      abbreviations: Vec::new(),
       frontmatter: None,
+      elements: &NO_ELEMENTS,
+      options: ParseOptions::new(),
+      threads: 1,
+      min_segment: MIN_SEGMENT,
     }
   }
 
+  /// Turn optional syntax such as `$` math on or off.
+  pub fn with_options(mut self, options: ParseOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Recognise the registry's custom elements in addition to the built-ins.
+  pub fn with_elements(mut self, elements: &'a ElementRegistry) -> Self {
+    self.elements = elements;
+    self
+  }
+
+  /// Parse blocks on up to `threads` threads when the input is large
+  /// enough. The result is the same as parsing on one.
+  pub fn with_threads(mut self, threads: usize) -> Self {
+    self.threads = threads.max(1);
+    self
+  }
+
+  #[cfg(test)]
+  pub(crate) fn with_min_segment(mut self, min_segment: usize) -> Self {
+    self.min_segment = min_segment;
+    self
+  }
+
   /// Parse input into Document AST.
   pub fn parse(&mut self) -> Document {
     self.frontmatter = frontmatter::try_parse(&mut self.scanner);
-    self.link_defs = linkdef::collect_definitions(&mut self.scanner);
+    if let Some(fm) = &self.frontmatter {
+      trace::event(trace::Level::Block, "frontmatter", 0, true);
+      if let NodeKind::Frontmatter { format, content } = &fm.kind {
+        // Unknown options and bad values are left to `--validate`
+        for (key, value, _) in frontmatter::option_overrides(*format, content) {
+          let _ = self.options.set(&key, &value);
+        }
+      }
+    }
+    (self.link_defs, self.abbreviations) = linkdef::collect_definitions(&mut self.scanner);
     self.scanner.reset();
 
     if self.frontmatter.is_some() {
       frontmatter::skip(&mut self.scanner);
     }
 
-    let mut block_parser = BlockParser::new(&mut self.scanner, &self.link_defs);
-    let mut nodes = block_parser.parse_blocks();
+    let mut nodes = if self.threads > 1 {
+      let input = self.scanner.slice(0, self.scanner.len());
+      let segments = Segments {
+        input,
+        link_defs: &self.link_defs,
+        elements: self.elements,
+        options: self.options,
+      };
+      let nodes = segments.parse(self.scanner.pos(), self.threads, self.min_segment);
+      self.scanner.set_pos(input.len());
+      nodes
+    } else {
+      BlockParser::new(&mut self.scanner, &self.link_defs, self.elements)
+        .with_options(self.options)
+        .parse_blocks()
+    };
 
     if let Some(fm) = self.frontmatter.take() {
-      nodes.insert(0, fm);
+      let mut kept = Vec::new();
+      self.options.kinds.select(fm, &mut kept);
+      nodes.splice(0..0, kept);
+    }
+    if !self.abbreviations.is_empty() {
+      abbr::annotate(&mut nodes, &self.abbreviations);
     }
 
     let total_nodes: usize = nodes.iter().map(|n| n.count_nodes()).sum();
@@ -57,6 +137,14 @@ impl<'a> MarkdownParser<'a> {
       metadata: DocumentMetadata {
         title: None,
         description: None,
+        glossary: self
+          .abbreviations
+          .iter()
+          .map(|def| (def.term.clone(), def.title.clone()))
+          .collect(),
+        date: None,
+        updated: None,
+        authors: Vec::new(),
         total_lines: self.scanner.line(),
         total_nodes,
       },
@@ -67,7 +155,7 @@ impl<'a> MarkdownParser<'a> {
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::ast::NodeKind;
+  use crate::ast::{Alignment, ListMarker, NodeKind, ReferenceType};
 
   #[test]
   fn test_empty_input() {
@@ -203,12 +291,29 @@ mod tests {
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_link_reference_unicode_label() {
+    let input = "[ẞ] and [Ёлка \t Map]\n\n[SS]: /ss\n[ёлка map]: /tree\n[ss]: /later";
+    let doc = MarkdownParser::new(input).parse();
+    let urls: Vec<&str> = doc.nodes[0]
+      .children
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::Link { url, .. } => Some(url.as_str()),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(urls, vec!["/ss", "/tree"]);
+  }
+
   #[test]
   fn test_multiple_paragraphs() {
     let input = "First paragraph.\n\nSecond paragraph.\n\nThird paragraph.";
     let mut parser = MarkdownParser::new(input);
     let doc = parser.parse();
     assert!(doc.nodes.len() >= 3);
+    let lines: Vec<usize> = doc.nodes.iter().map(|n| n.span.line).collect();
+    assert_eq!(lines, vec![1, 3, 5]);
   }
 
   #[test]
@@ -368,6 +473,41 @@ block
     }
   }
 
+  #[test]
+  fn test_alert_keywords() {
+    use crate::ast::AlertType;
+
+    let input = "> [!danger]\n> Hot\n\n> [!SUCCESS]\n> Done\n\n::: success\nDone\n:::\n";
+    let kinds =
+      |doc: Document| -> Vec<NodeKind> { doc.nodes.into_iter().map(|n| n.kind).collect() };
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(
+      kinds(doc)[..2],
+      [
+        NodeKind::Alert {
+          alert_type: AlertType::Caution
+        },
+        NodeKind::BlockQuote,
+      ]
+    );
+
+    let registry = ElementRegistry::from_config("[!SUCCESS]\n[!DANGER] warning\n").unwrap();
+    let doc = MarkdownParser::new(input).with_elements(&registry).parse();
+    let success = NodeKind::Alert {
+      alert_type: AlertType::Other("SUCCESS".to_string()),
+    };
+    assert_eq!(
+      kinds(doc),
+      [
+        NodeKind::Alert {
+          alert_type: AlertType::Warning
+        },
+        success.clone(),
+        success,
+      ]
+    );
+  }
+
   #[test]
   fn test_toc_element() {
     let input = "<toc>";
@@ -395,6 +535,35 @@ block
     assert!(has_steps, "Should parse <steps> element");
   }
 
+  #[test]
+  fn test_steps_titles_and_nesting() {
+    let input = "<steps>\n<step title=\"Install\">\nRun:\n\n<tabs names=\"npm, yarn\">\n```sh\nnpm i\n```\n```sh\nyarn\n```\n</tabs>\n</step>\n<step>\n<steps>\n<step title=\"Inner\">\nx\n</step>\n</steps>\n</step>\n</steps>";
+    let doc = MarkdownParser::new(input).parse();
+    let steps = &doc.nodes[0].children;
+    assert_eq!(steps.len(), 2);
+    assert_eq!(
+      steps[0].kind,
+      NodeKind::Step {
+        number: 1,
+        title: Some("Install".to_string())
+      }
+    );
+    assert!(matches!(steps[0].children[1].kind, NodeKind::Tabs { .. }));
+    assert_eq!(
+      steps[1].kind,
+      NodeKind::Step {
+        number: 2,
+        title: None
+      }
+    );
+    let inner = &steps[1].children[0];
+    assert!(matches!(inner.kind, NodeKind::Steps));
+    assert!(matches!(
+      &inner.children[0].kind,
+      NodeKind::Step { number: 1, title: Some(t) } if t == "Inner"
+    ));
+  }
+
   #[test]
   fn test_tabs_element() {
     let input = "<tabs names=\"JS, Python\">\n```js\nconsole.log()\n```\n</tabs>";
@@ -414,21 +583,97 @@ block
     }
   }
 
+  fn callout_registry() -> ElementRegistry {
+    ElementRegistry::from_config("callout type\nfigure src\nbadge").unwrap()
+  }
+
+  #[test]
+  fn test_custom_element_with_children() {
+    let registry = callout_registry();
+    let input =
+      "<callout type=\"warning\" icon=\"x\">\n**Careful** here.\n\n- one\n</callout>\n\nAfter.";
+    let doc = MarkdownParser::new(input).with_elements(&registry).parse();
+    assert_eq!(doc.nodes.len(), 2);
+    let NodeKind::CustomElement { name, attributes } = &doc.nodes[0].kind else {
+      panic!("expected CustomElement, got {:?}", doc.nodes[0].kind);
+    };
+    assert_eq!(name, "callout");
+    assert_eq!(
+      attributes,
+      &vec![("type".to_string(), "warning".to_string())]
+    );
+    assert!(matches!(doc.nodes[0].children[0].kind, NodeKind::Paragraph));
+    assert!(matches!(
+      doc.nodes[0].children[1].kind,
+      NodeKind::List { .. }
+    ));
+    assert!(matches!(doc.nodes[1].kind, NodeKind::Paragraph));
+  }
+
+  #[test]
+  fn test_custom_element_self_closing() {
+    let registry = callout_registry();
+    let doc = MarkdownParser::new("<figure src='intro.png' />\n<badge new>")
+      .with_elements(&registry)
+      .parse();
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::CustomElement {
+        name: "figure".to_string(),
+        attributes: vec![("src".to_string(), "intro.png".to_string())],
+      }
+    );
+    assert!(doc.nodes[0].children.is_empty());
+    assert_eq!(
+      doc.nodes[1].kind,
+      NodeKind::CustomElement {
+        name: "badge".to_string(),
+        attributes: vec![("new".to_string(), String::new())],
+      }
+    );
+  }
+
+  #[test]
+  fn test_custom_element_nested_in_blockquote() {
+    let registry = callout_registry();
+    let doc = MarkdownParser::new("> <callout type=\"note\">\n> Hi\n> </callout>")
+      .with_elements(&registry)
+      .parse();
+    let inner = &doc.nodes[0].children[0];
+    assert!(matches!(inner.kind, NodeKind::CustomElement { .. }));
+  }
+
+  #[test]
+  fn test_include_element() {
+    let doc = MarkdownParser::new("<include src=\"shared/intro.md\" />\n\nText").parse();
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::Include {
+        src: "shared/intro.md".to_string()
+      }
+    );
+    assert!(matches!(doc.nodes[1].kind, NodeKind::Paragraph));
+  }
+
+  #[test]
+  fn test_custom_element_unregistered_is_html() {
+    let doc = MarkdownParser::new("<callout type=\"note\">\nHi\n</callout>").parse();
+    assert!(!doc
+      .nodes
+      .iter()
+      .any(|n| matches!(n.kind, NodeKind::CustomElement { .. })));
+  }
+
   #[test]
   fn test_code_block_highlight() {
     let input = "```go highlight=\"3, 5-7\"\npackage main\n```";
     let mut parser = MarkdownParser::new(input);
     let doc = parser.parse();
-    let code_ext = doc.nodes.iter().find(|n| {
-      matches!(
-        &n.kind,
-        NodeKind::CodeBlockExt {
-          highlight: Some(_),
-          ..
-        }
-      )
+    let highlight = doc.nodes.iter().find_map(|n| match &n.kind {
+      NodeKind::CodeBlockExt { highlight, .. } => Some(highlight.clone()),
+      _ => None,
     });
-    assert!(code_ext.is_some(), "Should parse highlight attribute");
+    assert_eq!(highlight, Some(vec![(3, 3), (5, 7)]));
   }
 
   #[test]
@@ -436,17 +681,15 @@ block
     let input = "```gleam plusdiff=\"5\" minusdiff=\"4\"\ncode\n```";
     let mut parser = MarkdownParser::new(input);
     let doc = parser.parse();
-    let code_ext = doc.nodes.iter().find(|n| {
-      matches!(
-        &n.kind,
-        NodeKind::CodeBlockExt {
-          plusdiff: Some(_),
-          minusdiff: Some(_),
-          ..
-        }
-      )
+    let diff = doc.nodes.iter().find_map(|n| match &n.kind {
+      NodeKind::CodeBlockExt {
+        plusdiff,
+        minusdiff,
+        ..
+      } => Some((plusdiff.clone(), minusdiff.clone())),
+      _ => None,
     });
-    assert!(code_ext.is_some(), "Should parse diff attributes");
+    assert_eq!(diff, Some((vec![(5, 5)], vec![(4, 4)])));
   }
 
   #[test]
@@ -488,8 +731,75 @@ block
     let input = "####### Seven hashes";
     let mut parser = MarkdownParser::new(input);
     let doc = parser.parse();
-    // More than 6 hashes - behavior varies by parser, just don't crash
-    let _ = doc.nodes.len();
+    assert!(matches!(doc.nodes[0].kind, NodeKind::Paragraph));
+  }
+
+  #[test]
+  fn test_heading_closing_sequence() {
+    let heading_text = |input: &str| {
+      let doc = MarkdownParser::new(input).parse();
+      match &doc.nodes[0].kind {
+        NodeKind::Heading { .. } => doc.nodes[0]
+          .children
+          .iter()
+          .map(|n| match &n.kind {
+            NodeKind::Text { content } => content.as_str(),
+            _ => "",
+          })
+          .collect::<String>(),
+        other => panic!("expected heading, got {:?}", other),
+      }
+    };
+    assert_eq!(heading_text("# Heading #####"), "Heading");
+    assert_eq!(heading_text("## Heading ##   "), "Heading");
+    assert_eq!(heading_text("# C#"), "C#");
+    assert_eq!(heading_text("# a # b"), "a # b");
+    assert_eq!(heading_text("### ###"), "");
+    assert_eq!(heading_text("#"), "");
+  }
+
+  #[test]
+  fn test_heading_crlf() {
+    let doc = MarkdownParser::new("# h\r\n## Closed ##\r\n### Named {#n}\r\n#\r\nnext").parse();
+    let headings: Vec<(String, Option<String>)> = doc
+      .nodes
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::Heading { id, .. } => Some((
+          n.children
+            .iter()
+            .map(|c| match &c.kind {
+              NodeKind::Text { content } => content.as_str(),
+              _ => "",
+            })
+            .collect(),
+          id.clone(),
+        )),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      headings,
+      vec![
+        ("h".to_string(), None),
+        ("Closed".to_string(), None),
+        ("Named".to_string(), Some("n".to_string())),
+        (String::new(), None),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_heading_indentation() {
+    for input in ["   # Three", " ## One"] {
+      let doc = MarkdownParser::new(input).parse();
+      assert!(
+        matches!(doc.nodes[0].kind, NodeKind::Heading { .. }),
+        "{input:?}"
+      );
+    }
+    let doc = MarkdownParser::new("    # Four").parse();
+    assert!(matches!(doc.nodes[0].kind, NodeKind::IndentedCodeBlock));
   }
 
   #[test]
@@ -752,6 +1062,27 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_link_definition_not_a_paragraph() {
+    let input =
+      "[ref]\n\n  [ref]:\n   <http://x.dev/a b>\n   \"Next\n   line\"\n```\n[no]: /code\n```\n";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 3);
+    assert_eq!(
+      doc.nodes[0].children[0].kind,
+      NodeKind::Link {
+        url: "http://x.dev/a b".to_string(),
+        title: Some("Next\n   line".to_string()),
+        ref_type: ReferenceType::Shortcut,
+        attributes: vec![],
+      }
+    );
+    assert!(matches!(
+      &doc.nodes[1].kind,
+      NodeKind::LinkDefinition { label, .. } if label == "ref"
+    ));
+  }
+
   #[test]
   fn test_link_reference_collapsed() {
     let input = "[ref][]\n\n[ref]: http://example.com";
@@ -772,6 +1103,44 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  fn image_attributes(input: &str) -> (Vec<(String, String)>, usize) {
+    let doc = MarkdownParser::new(input).parse();
+    let inline = &doc.nodes[0].children;
+    let attributes = inline
+      .iter()
+      .find_map(|n| match &n.kind {
+        NodeKind::Image { attributes, .. } => Some(attributes.clone()),
+        _ => None,
+      })
+      .expect("image");
+    (attributes, inline.len())
+  }
+
+  #[test]
+  fn test_image_attributes() {
+    let (attributes, nodes) =
+      image_attributes(r#"![Logo](logo.png){width=400 height=200 align="center left" lazy}"#);
+    let pairs: Vec<(&str, &str)> = attributes
+      .iter()
+      .map(|(k, v)| (k.as_str(), v.as_str()))
+      .collect();
+    assert_eq!(
+      pairs,
+      vec![
+        ("width", "400"),
+        ("height", "200"),
+        ("align", "center left"),
+        ("lazy", "")
+      ]
+    );
+    assert_eq!(nodes, 1);
+
+    // Not an attribute block: stays text after the image.
+    let (attributes, nodes) = image_attributes("![a](a.png){not valid!}");
+    assert!(attributes.is_empty());
+    assert_eq!(nodes, 2);
+  }
+
   #[test]
   fn test_image_empty_alt() {
     let input = "![](image.png)";
@@ -788,6 +1157,134 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_attribute_lists() {
+    let input = "## Setup {#install .unnumbered lang=en}\n\n\
+                 ```{.rust .numberLines #main}\nfn main() {}\n```\n\n\
+                 See [docs](https://x.dev){target=_blank .external}.";
+    let doc = MarkdownParser::new(input).parse();
+    let owned = |items: &[(&str, &str)]| -> Vec<(String, String)> {
+      items
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    };
+
+    let NodeKind::Heading { id, attributes, .. } = &doc.nodes[0].kind else {
+      panic!("expected heading");
+    };
+    assert_eq!(id.as_deref(), Some("install"));
+    assert_eq!(
+      *attributes,
+      owned(&[("class", "unnumbered"), ("lang", "en")])
+    );
+    assert_eq!(
+      doc.nodes[0].children[0].kind,
+      NodeKind::Text {
+        content: "Setup".to_string()
+      }
+    );
+
+    let NodeKind::FencedCodeBlock {
+      language,
+      attributes,
+      ..
+    } = &doc.nodes[1].kind
+    else {
+      panic!("expected fenced code");
+    };
+    assert_eq!(language.as_deref(), Some("rust"));
+    assert_eq!(
+      *attributes,
+      owned(&[("class", "numberLines"), ("id", "main")])
+    );
+
+    let link = &doc.nodes[2].children[1];
+    let NodeKind::Link { attributes, .. } = &link.kind else {
+      panic!("expected link");
+    };
+    assert_eq!(
+      *attributes,
+      owned(&[("target", "_blank"), ("class", "external")])
+    );
+    assert_eq!(
+      &input[link.span.start..link.span.end],
+      "[docs](https://x.dev){target=_blank .external}"
+    );
+  }
+
+  #[test]
+  fn test_directives() {
+    let input = "::: warning Mind *the* gap\nBody\n:::\n\n\
+                 :::tip[Hint]\nAdvice\n:::\n\n\
+                 ::: details Click me {.wide}\nHidden\n:::\n";
+    let doc = MarkdownParser::new(input).parse();
+    let kinds: Vec<_> = doc.nodes.iter().map(|n| n.kind.name()).collect();
+    assert_eq!(kinds, ["Alert", "Alert", "CustomElement"]);
+
+    let warning = &doc.nodes[0];
+    assert_eq!(
+      warning.kind,
+      NodeKind::Alert {
+        alert_type: crate::ast::AlertType::Warning
+      }
+    );
+    let title = &warning.children[0];
+    assert_eq!(title.children[0].kind, NodeKind::Strong);
+    assert_eq!(&input[title.span.start..title.span.end], "Mind *the* gap");
+    assert_eq!(title.children[0].children[1].kind, NodeKind::Emphasis);
+    assert_eq!(warning.children[1].kind, NodeKind::Paragraph);
+    assert_eq!(warning.span.end, input.find("\n\n").unwrap() + 1);
+
+    let NodeKind::Text { content } = &doc.nodes[1].children[0].children[0].children[0].kind else {
+      panic!("expected title text");
+    };
+    assert_eq!(content, "Hint");
+
+    assert_eq!(
+      doc.nodes[2].kind,
+      NodeKind::CustomElement {
+        name: "details".to_string(),
+        attributes: vec![
+          ("class".to_string(), "wide".to_string()),
+          ("title".to_string(), "Click me".to_string()),
+        ],
+      }
+    );
+    assert_eq!(doc.nodes[2].children[0].kind, NodeKind::Paragraph);
+  }
+
+  #[test]
+  fn test_directive_tabs_and_nesting() {
+    let input = "::: tabs\n@tab npm\nRun npm.\n\n@tab yarn\nRun yarn.\n:::\n\n\
+                 ::: code-group\n```sh\nnpm i\n```\n```bat\nnpm.cmd i\n```\n:::\n\n\
+                 :::: note\n::: danger\nInner\n:::\n::::\n";
+    let doc = MarkdownParser::new(input).parse();
+    let kinds: Vec<_> = doc.nodes.iter().map(|n| n.kind.name()).collect();
+    assert_eq!(kinds, ["Tabs", "Tabs", "Alert"]);
+
+    let names = |node: &Node| match &node.kind {
+      NodeKind::Tabs { names } => names.clone(),
+      _ => panic!("expected tabs"),
+    };
+    assert_eq!(names(&doc.nodes[0]), ["npm", "yarn"]);
+    let yarn = &doc.nodes[0].children[1];
+    assert_eq!(
+      &input[yarn.span.start..yarn.span.end],
+      "@tab yarn\nRun yarn.\n"
+    );
+    assert_eq!(names(&doc.nodes[1]), ["sh", "bat"]);
+
+    let inner = &doc.nodes[2].children[0];
+    assert_eq!(
+      inner.kind,
+      NodeKind::Alert {
+        alert_type: crate::ast::AlertType::Caution
+      }
+    );
+    assert_eq!(inner.children[0].kind, NodeKind::Paragraph);
+  }
+
   // ============================================
   // EDGE CASES: Lists
   // ============================================
@@ -800,6 +1297,34 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_list_marker_and_line() {
+    let input = "Intro\n\n* asterisk\n+ plus\n- dash";
+    let doc = MarkdownParser::new(input).parse();
+    let list = doc
+      .nodes
+      .iter()
+      .find(|n| matches!(n.kind, NodeKind::List { .. }))
+      .unwrap();
+    assert_eq!((list.span.line, list.span.column), (3, 1));
+    let markers: Vec<_> = list
+      .children
+      .iter()
+      .map(|item| match item.kind {
+        NodeKind::ListItem { marker, .. } => marker,
+        _ => panic!("expected a list item"),
+      })
+      .collect();
+    assert_eq!(
+      markers,
+      vec![
+        ListMarker::Bullet('*'),
+        ListMarker::Bullet('+'),
+        ListMarker::Bullet('-')
+      ]
+    );
+  }
+
   #[test]
   fn test_nested_list() {
     let input = "- item\n  - nested\n    - deeply nested";
@@ -918,9 +1443,6 @@ block
       matches!(
         &n.kind,
         NodeKind::CodeBlockExt {
-          highlight: Some(_),
-          plusdiff: Some(_),
-          minusdiff: Some(_),
           linenumbers: true,
           ..
         }
@@ -929,6 +1451,73 @@ block
     assert!(code_ext.is_some(), "Should parse all code block attributes");
   }
 
+  #[test]
+  fn test_code_block_malformed_ranges() {
+    let input = "```rust highlight=\"2-1\" plusdiff=\"0\" minusdiff=\"1,x\"\ncode\n```";
+    let doc = MarkdownParser::new(input).parse();
+    let NodeKind::CodeBlockExt {
+      highlight,
+      invalid_ranges,
+      ..
+    } = &doc.nodes[0].kind
+    else {
+      panic!("expected CodeBlockExt");
+    };
+    assert!(highlight.is_empty());
+    let names: Vec<_> = invalid_ranges.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, vec!["highlight", "plusdiff", "minusdiff"]);
+  }
+
+  #[test]
+  fn test_abbreviations() {
+    let input = "The HTML and HTML5 specs, not XHTML.\n\n    HTML\n\n*[HTML]: Hyper Text Markup Language\n*[HTML5]: HTML version 5\n*[HTML]: Ignored\n";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 2, "definitions leave no node");
+    let para = &doc.nodes[0].children;
+    let abbrs: Vec<_> = para
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::Abbreviation { title } => {
+          Some((&input[n.span.start..n.span.end], title.as_str()))
+        }
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      abbrs,
+      vec![
+        ("HTML", "Hyper Text Markup Language"),
+        ("HTML5", "HTML version 5")
+      ]
+    );
+    assert!(matches!(&para[4].kind, NodeKind::Text { content } if content == " specs, not XHTML."));
+    assert!(
+      matches!(&doc.nodes[1].children[0].kind, NodeKind::Text { content } if content.starts_with("HTML"))
+    );
+    assert_eq!(
+      doc.metadata.glossary,
+      vec![
+        ("HTML".to_string(), "Hyper Text Markup Language".to_string()),
+        ("HTML5".to_string(), "HTML version 5".to_string())
+      ]
+    );
+  }
+
+  #[test]
+  fn test_code_block_attributes_without_language() {
+    let doc = MarkdownParser::new("```HighLight='2, 4'\ncode\n```").parse();
+    let NodeKind::CodeBlockExt {
+      language,
+      highlight,
+      ..
+    } = &doc.nodes[0].kind
+    else {
+      panic!("expected CodeBlockExt");
+    };
+    assert_eq!(language, &None);
+    assert_eq!(highlight, &vec![(2, 2), (4, 4)]);
+  }
+
   // ============================================
   // EDGE CASES: Blockquotes
   // ============================================
@@ -1059,6 +1648,132 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  /// Each row's cells as (alignment, colspan, text).
+  fn table_rows(doc: &Document) -> Vec<Vec<(Alignment, u32, String)>> {
+    fn rows(node: &Node, out: &mut Vec<Vec<(Alignment, u32, String)>>) {
+      if node.kind == NodeKind::TableRow {
+        out.push(
+          node
+            .children
+            .iter()
+            .map(|cell| match &cell.kind {
+              NodeKind::TableCell {
+                alignment, colspan, ..
+              } => (
+                *alignment,
+                *colspan,
+                crate::processor::transform::text_of(&cell.children),
+              ),
+              other => panic!("{:?} in a row", other),
+            })
+            .collect(),
+        );
+      }
+      node.children.iter().for_each(|child| rows(child, out));
+    }
+    assert_eq!(doc.nodes.len(), 1);
+    assert_eq!(doc.nodes[0].kind, NodeKind::Table);
+    let mut out = Vec::new();
+    rows(&doc.nodes[0], &mut out);
+    out
+  }
+
+  fn cells(row: &[(Alignment, u32, String)]) -> Vec<&str> {
+    row.iter().map(|(_, _, text)| text.as_str()).collect()
+  }
+
+  #[test]
+  fn test_table_structure() {
+    let input = "Intro\n\n  | a | *b* |\n  |:--|--:|\n  | 1 | 2 |\n| 3 \\| 4 | 5 |\n\nAfter\n";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 3);
+    let table = &doc.nodes[1];
+    assert_eq!(table.kind, NodeKind::Table);
+    assert_eq!(
+      &input[table.span.start..table.span.end],
+      "| a | *b* |\n  |:--|--:|\n  | 1 | 2 |\n| 3 \\| 4 | 5 |\n"
+    );
+    let kinds: Vec<&str> = table.children.iter().map(|n| n.kind.name()).collect();
+    assert_eq!(kinds, ["TableHead", "TableBody"]);
+    let head = &table.children[0].children[0];
+    assert!(matches!(
+      head.children[0].kind,
+      NodeKind::TableCell {
+        is_header: true,
+        ..
+      }
+    ));
+    let cell = &table.children[1].children[1].children[0];
+    assert_eq!((cell.span.line, cell.span.column), (6, 2));
+    assert_eq!(&input[cell.span.start..cell.span.end], " 3 \\| 4 ");
+  }
+
+  #[test]
+  fn test_table_alignment_reaches_body() {
+    let doc = MarkdownParser::new("| l | c | r | n |\n|:-|:-:|-:|--|\n| 1 | 2 | 3 | 4 |\n").parse();
+    for row in table_rows(&doc) {
+      let alignments: Vec<Alignment> = row.iter().map(|(a, ..)| *a).collect();
+      assert_eq!(
+        alignments,
+        [
+          Alignment::Left,
+          Alignment::Center,
+          Alignment::Right,
+          Alignment::None
+        ]
+      );
+    }
+  }
+
+  #[test]
+  fn test_table_ragged_rows() {
+    let doc =
+      MarkdownParser::new("| a | b | c |\n|---|---|--:|\n| 1 |\n| 1 | 2 | 3 | 4 |\n").parse();
+    let rows = table_rows(&doc);
+    assert_eq!(cells(&rows[1]), ["1", "", ""]);
+    assert_eq!(rows[1][2].0, Alignment::Right);
+    assert_eq!(cells(&rows[2]), ["1", "2", "3"]);
+  }
+
+  #[test]
+  fn test_table_not_a_table() {
+    for input in [
+      "| a | b |\n|---|\n| 1 | 2 |\n",
+      "a | b\nno delimiter\n",
+      "Title\n---\n",
+      "    | a |\n    |---|\n",
+    ] {
+      let doc = MarkdownParser::new(input).parse();
+      assert!(
+        doc.nodes.iter().all(|n| n.kind != NodeKind::Table),
+        "{:?}",
+        input
+      );
+    }
+  }
+
+  #[test]
+  fn test_table_colspan() {
+    let input = "| a || c |\n|---|:-:|---|\n| 1 ||| \n| 1 | | 3 |\n";
+    let rows = table_rows(&MarkdownParser::new(input).parse());
+    assert!(rows.iter().flatten().all(|(_, colspan, _)| *colspan == 1));
+    assert_eq!(cells(&rows[0]), ["a", "", "c"]);
+
+    let options = ParseOptions {
+      table_colspan: true,
+      ..ParseOptions::new()
+    };
+    let rows = table_rows(&MarkdownParser::new(input).with_options(options).parse());
+    let spans =
+      |row: &[(Alignment, u32, String)]| -> Vec<u32> { row.iter().map(|c| c.1).collect() };
+    assert_eq!(cells(&rows[0]), ["a", "c"]);
+    assert_eq!(spans(&rows[0]), [2, 1]);
+    // Clamped to the columns left
+    assert_eq!(spans(&rows[1]), [3]);
+    // A space between the pipes keeps an empty cell
+    assert_eq!(spans(&rows[2]), [1, 1, 1]);
+  }
+
   // ============================================
   // EDGE CASES: Horizontal Rules
   // ============================================
@@ -1139,6 +1854,173 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_math_bracket_block() {
+    let input = "\\[\nx^2\n\\]\n\nafter";
+    let options = ParseOptions {
+      latex_math: true,
+      ..ParseOptions::new()
+    };
+    let doc = MarkdownParser::new(input).with_options(options).parse();
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::MathBlock {
+        content: "x^2".to_string(),
+        display: true,
+      }
+    );
+    assert!(matches!(doc.nodes[1].kind, NodeKind::Paragraph));
+
+    let doc = MarkdownParser::new(input).parse();
+    assert!(matches!(doc.nodes[0].kind, NodeKind::Paragraph));
+  }
+
+  #[test]
+  fn test_math_environment() {
+    let input = "\\begin{align*}\na &= b \\\\\nc &= d\n\\end{align*}\n\nafter";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::MathBlock {
+        content: "\\begin{align*}\na &= b \\\\\nc &= d\n\\end{align*}".to_string(),
+        display: true,
+      }
+    );
+    assert_eq!(doc.nodes.len(), 2);
+
+    let doc = MarkdownParser::new("\\begin{itemize}\n\\end{itemize}").parse();
+    assert!(matches!(doc.nodes[0].kind, NodeKind::Paragraph));
+  }
+
+  #[test]
+  fn test_diagram_blocks() {
+    let input = "```mermaid\ngraph TD\n  a --> b\n```\n\n~~~ PlantUML\n@startuml\n~~~\n\n\
+                 ```dot\ndigraph {}\n```\n\n```mermaid {#flow}\ngraph LR\n```\n";
+    let doc = MarkdownParser::new(input).parse();
+    let diagram = |engine: &str, content: &str| NodeKind::DiagramBlock {
+      engine: engine.to_string(),
+      content: content.to_string(),
+    };
+    assert_eq!(
+      doc.nodes[0].kind,
+      diagram("mermaid", "graph TD\n  a --> b\n")
+    );
+    assert!(doc.nodes[0].children.is_empty());
+    assert_eq!(doc.nodes[0].span.line, 1);
+    assert_eq!(doc.nodes[1].kind, diagram("plantuml", "@startuml\n"));
+    assert_eq!(doc.nodes[2].kind, diagram("graphviz", "digraph {}\n"));
+    // Attributes keep it a code block
+    assert!(matches!(
+      doc.nodes[3].kind,
+      NodeKind::FencedCodeBlock { .. }
+    ));
+  }
+
+  #[test]
+  fn test_dollar_math_disabled() {
+    let input = "Costs $5 or $10\n\n$$\nx\n$$\n\nand \\(y\\)";
+    let options = ParseOptions {
+      dollar_math: false,
+      latex_math: true,
+      ..ParseOptions::new()
+    };
+    let doc = MarkdownParser::new(input).with_options(options).parse();
+    let mut math = Vec::new();
+    let mut stack: Vec<&Node> = doc.nodes.iter().collect();
+    while let Some(node) = stack.pop() {
+      if matches!(
+        node.kind,
+        NodeKind::MathInline { .. } | NodeKind::MathBlock { .. }
+      ) {
+        math.push(&node.kind);
+      }
+      stack.extend(&node.children);
+    }
+    assert_eq!(
+      math,
+      vec![&NodeKind::MathInline {
+        content: "y".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn test_kind_filter() {
+    fn outline(nodes: &[Node]) -> String {
+      let names: Vec<String> = nodes
+        .iter()
+        .map(|n| match n.children.is_empty() {
+          true => n.kind.name().to_string(),
+          false => format!("{}({})", n.kind.name(), outline(&n.children)),
+        })
+        .collect();
+      names.join(" ")
+    }
+    fn parse(input: &str, only: &[&str], skip: &[&str]) -> Document {
+      let names = |kinds: &[&str]| kinds.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+      let options = ParseOptions {
+        kinds: KindFilter::ALL
+          .with_only(&names(only))
+          .with_skip(&names(skip)),
+        ..ParseOptions::new()
+      };
+      MarkdownParser::new(input).with_options(options).parse()
+    }
+
+    let input = "# Title *x*\n\nSee [docs](u) and ~~[old](v)~~.\n\n```rust\ncode\n```\n\n| a | b |\n|---|---|\n| [c](d) | e |\n\n> ## Quoted\n";
+    let doc = parse(input, &["Heading", "Link"], &[]);
+    assert_eq!(
+      outline(&doc.nodes),
+      "Heading(Text Emphasis(Text)) Link(Text) Link(Text) Link(Text) Heading(Text)"
+    );
+    assert_eq!(doc.metadata.total_nodes, 12);
+
+    // Tables keep their cells' text; skipped kinds go with their content
+    let doc = parse(input, &["Table"], &["Link"]);
+    assert_eq!(
+      outline(&doc.nodes),
+      "Table(TableHead(TableRow(TableCell(Text) TableCell(Text))) TableBody(TableRow(TableCell TableCell(Text))))"
+    );
+    let doc = parse(input, &[], &["FencedCodeBlock", "Text", "BlockQuote"]);
+    assert_eq!(
+      outline(&doc.nodes[..2]),
+      "Heading(Emphasis) Paragraph(Link Strikethrough(Link))"
+    );
+    assert_eq!(doc.nodes.len(), 3);
+
+    // Nothing inline is wanted, so paragraphs are not parsed inline
+    let doc = parse("Some *text* and [a](b)\n", &["Paragraph"], &[]);
+    assert_eq!(
+      outline(&doc.nodes),
+      "Paragraph(Text Emphasis(Text) Text Link(Text))"
+    );
+    let doc = parse("Some *text* and [a](b)\n", &["Heading", "ListItem"], &[]);
+    assert!(doc.nodes.is_empty());
+  }
+
+  #[test]
+  fn test_frontmatter_options() {
+    use crate::formats::to_json;
+
+    let input = "---\nbukvar: {math: false, table_colspan: true}\n---\nCosts $5 or $10\n\n| a | b |\n|---|---|\n| x ||\n";
+    let doc = MarkdownParser::new(input).parse();
+    assert!(!to_json(&doc).contains("MathInline"));
+    assert!(to_json(&doc).contains("\"colspan\":2"));
+
+    // Without the override, the same body parses math and no spans
+    let doc = MarkdownParser::new("Costs $5 or $10 then $x$\n").parse();
+    assert!(to_json(&doc).contains("MathInline"));
+
+    let mut options = ParseOptions::new();
+    assert!(options.set("strict_math", "on").is_ok());
+    assert!(options.strict_math);
+    assert_eq!(
+      options.set("smart_quotes", "true").unwrap_err(),
+      "unknown parser option: smart_quotes"
+    );
+    assert!(options.set("math", "maybe").is_err());
+  }
+
   #[test]
   fn test_math_inline_unclosed() {
     let input = "$unclosed math";
@@ -1159,6 +2041,22 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_footnote_definition_block() {
+    let input = "Text[^n]\n\n[^n]: First\n\n    Second\nAfter";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 3);
+    assert_eq!(
+      doc.nodes[1].kind,
+      NodeKind::FootnoteDefinition {
+        label: "n".to_string(),
+        index: 0
+      }
+    );
+    assert_eq!(doc.nodes[1].children.len(), 2);
+    assert_eq!(doc.nodes[2].kind, NodeKind::Paragraph);
+  }
+
   #[test]
   fn test_footnote_with_code() {
     let input = "Text[^1]\n\n[^1]: Contains `code`";
@@ -1318,6 +2216,38 @@ block
     assert!(!doc.nodes.is_empty());
   }
 
+  fn tab_names(tabs: &Node) -> Vec<(String, usize)> {
+    tabs
+      .children
+      .iter()
+      .map(|tab| match &tab.kind {
+        NodeKind::Tab { name } => (name.clone(), tab.children.len()),
+        other => panic!("expected Tab, got {:?}", other),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_tabs_split_on_code_blocks() {
+    let input = "<tabs names=\"JS, Python\">\nRun it:\n\n```js\nrun()\n```\n\n```py\nrun()\n```\nDone.\n</tabs>";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(
+      tab_names(&doc.nodes[0]),
+      vec![("JS".to_string(), 2), ("Python".to_string(), 2)]
+    );
+  }
+
+  #[test]
+  fn test_tabs_explicit_children() {
+    let input = "<tabs names=\"A, B\">\n<tab>\nFirst\n</tab>\n<tab name=\"Custom\">\n\n```sh\nls\n```\n\n<tabs names=\"X\">\n```js\nx\n```\n</tabs>\n</tab>\n</tabs>\nAfter";
+    let doc = MarkdownParser::new(input).parse();
+    assert_eq!(
+      tab_names(&doc.nodes[0]),
+      vec![("A".to_string(), 1), ("Custom".to_string(), 2)]
+    );
+    assert!(matches!(doc.nodes[1].kind, NodeKind::Paragraph));
+  }
+
   #[test]
   fn test_tabs_single_tab() {
     let input = "<tabs names=\"Single\">\ncontent\n</tabs>";
@@ -1447,7 +2377,39 @@ block
     let input = "<!-- comment -->\n\nContent";
     let mut parser = MarkdownParser::new(input);
     let doc = parser.parse();
-    assert!(!doc.nodes.is_empty());
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::Comment {
+        content: " comment ".to_string(),
+        block: true
+      }
+    );
+    assert_eq!(doc.nodes[0].span.end, 17);
+  }
+
+  #[test]
+  fn test_comment_variants() {
+    let doc =
+      MarkdownParser::new("<!--\nfirst\n\nsecond\n-->\nText <!--x--> end\n\n<!-- a --> b\n")
+        .parse();
+    let kinds: Vec<_> = doc.nodes.iter().map(|n| n.kind.name()).collect();
+    assert_eq!(kinds, ["Comment", "Paragraph", "Paragraph"]);
+    assert!(matches!(
+      &doc.nodes[0].kind,
+      NodeKind::Comment { content, block: true } if content == "\nfirst\n\nsecond\n"
+    ));
+    assert_eq!(
+      doc.nodes[1].children[1].kind,
+      NodeKind::Comment {
+        content: "x".to_string(),
+        block: false
+      }
+    );
+    // Text after the comment on its line makes it part of a paragraph.
+    assert!(matches!(
+      doc.nodes[2].children[0].kind,
+      NodeKind::Comment { block: false, .. }
+    ));
   }
 
   #[test]
@@ -1525,4 +2487,127 @@ block
     let doc = parser.parse();
     assert!(!doc.nodes.is_empty());
   }
+
+  // ============================================
+  // EDGE CASES: Spans Inside Containers
+  // ============================================
+
+  /// Source text under the first `Strong` node, depth first.
+  fn strong_source(input: &str) -> &str {
+    fn find(nodes: &[Node]) -> Option<&Node> {
+      nodes.iter().find_map(|node| match node.kind {
+        NodeKind::Strong => Some(node),
+        _ => find(&node.children),
+      })
+    }
+    let doc = MarkdownParser::new(input).parse();
+    let span = find(&doc.nodes).expect("strong node").span;
+    &input[span.start..span.end]
+  }
+
+  #[test]
+  fn test_inline_spans_in_paragraph_and_heading() {
+    assert_eq!(strong_source("intro\n\na **b** c"), "**b**");
+    assert_eq!(strong_source("## Title **b**"), "**b**");
+  }
+
+  #[test]
+  fn test_inline_spans_in_list_and_quote() {
+    assert_eq!(strong_source("- one\n- two **b**"), "**b**");
+    assert_eq!(strong_source("> first\n> second **b**"), "**b**");
+    assert_eq!(strong_source("> > deep **b**"), "**b**");
+  }
+
+  #[test]
+  fn test_inline_spans_in_footnote_steps_and_tabs() {
+    assert_eq!(
+      strong_source("Text[^1]\n\n[^1]: note\n\n    more **b**"),
+      "**b**"
+    );
+    assert_eq!(
+      strong_source("<steps>\n<step title=\"One\">\nDo **b**\n</step>\n</steps>"),
+      "**b**"
+    );
+    assert_eq!(
+      strong_source("<tabs names=\"a\">\n<tab>\nSee **b**\n</tab>\n</tabs>"),
+      "**b**"
+    );
+  }
+
+  #[test]
+  fn test_code_content_spans() {
+    let input =
+      "# Code\n\n```rust {highlight=\"2\"}\nlet a = 1;\nlet b = 2;\n```\n\n    indented\n";
+    let doc = MarkdownParser::new(input).parse();
+
+    let text = &doc.nodes[1].children[0];
+    assert_eq!(
+      &input[text.span.start..text.span.end],
+      "let a = 1;\nlet b = 2;\n"
+    );
+    assert_eq!((text.span.line, text.span.column), (4, 1));
+    // Highlighted line 2 of the block is source line 5
+    let highlighted = text.span.line + 2 - 1;
+    assert_eq!(input.lines().nth(highlighted - 1), Some("let b = 2;"));
+
+    let text = &doc.nodes[2].children[0];
+    assert_eq!(&input[text.span.start..text.span.end], "indented");
+    assert_eq!((text.span.line, text.span.column), (8, 5));
+  }
+
+  #[test]
+  fn test_code_content_spans_in_quote() {
+    let input = "> ```\n> x = 1\n> ```\n";
+    let doc = MarkdownParser::new(input).parse();
+    let text = &doc.nodes[0].children[0].children[0];
+    assert_eq!(&input[text.span.start..text.span.start + 5], "x = 1");
+    assert_eq!((text.span.line, text.span.column), (2, 3));
+  }
+
+  #[test]
+  fn test_nested_block_lines() {
+    let input = "# Doc\n\n> quote\n>\n> ## Inner\n";
+    let doc = MarkdownParser::new(input).parse();
+    let heading = &doc.nodes[1].children[1];
+    assert!(matches!(heading.kind, NodeKind::Heading { level: 2, .. }));
+    assert_eq!(heading.span.line, 5);
+    assert_eq!(heading.span.column, 3);
+    assert_eq!(
+      &input[heading.span.start..heading.span.start + 8],
+      "## Inner"
+    );
+  }
+
+  /// Multi-byte characters anywhere in the input: no panic, and every
+  /// span starts and ends on a character boundary.
+  #[test]
+  fn test_multibyte_everywhere() {
+    let corpus = "---\ntitle: t\n---\n# H *e* `c`\n\nSetext\n===\n\n> q [!NOTE]\n> > n\n\n- a\n  - b\n1. c\n- [x] d\n\n```go highlight=\"1\"\nx\n```\n\n    ind\n\n~~~\nt\n~~~\n\n| a | b |\n|---|:-:|\n| c | d |\n\nT\n: D\n\n<div>\nh\n</div>\n\n<tabs names=\"A, B\">\n```a\n1\n```\n```b\n2\n```\n</tabs>\n\n<steps>\n<step title=\"s\">\nx\n</step>\n</steps>\n\n[l](u \"t\") ![i](p.png){width=4} <http://a.b> **s** ~~d~~ \\* $m$ \\(n\\) <b>x</b> [^1] [r]\n\n$$\nm\n$$\n\n\\begin{align}\na\n\\end{align}\n\n[r]: http://r\n[^1]: f\n  g\n\n***\n";
+    fn check(node: &Node, input: &str) {
+      let crate::ast::Span { start, end, .. } = node.span;
+      assert!(
+        input.is_char_boundary(start.min(input.len()))
+          && input.is_char_boundary(end.min(input.len())),
+        "{:?} splits a character in {:?}",
+        node.kind,
+        input
+      );
+      node.children.iter().for_each(|c| check(c, input));
+    }
+    for insert in ["日", "é", "😀"] {
+      for at in (0..=corpus.len()).filter(|&i| corpus.is_char_boundary(i)) {
+        let input = format!("{}{}{}", &corpus[..at], insert, &corpus[at..]);
+        let doc = MarkdownParser::new(&input).parse();
+        doc.nodes.iter().for_each(|n| check(n, &input));
+        let sexp = crate::formats::to_sexp(&doc);
+        assert!(
+          !sexp
+            .chars()
+            .any(|c| ('\u{80}'..'\u{100}').contains(&c) && c != 'é'),
+          "bytes decoded as characters for {:?}",
+          input
+        );
+      }
+    }
+  }
 }
@@ -0,0 +1,193 @@
+//! Switches for optional Markdown syntax and Python docstring detection,
+//! and the node kinds the Markdown parser builds.
+
+use crate::ast::Node;
+use crate::formats::kind_index;
+
+/// Parser settings shared by the block and inline parsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+  /// Parse `$...$` and `$$...$$` as math. Off for documents where `$`
+  /// means currency; LaTeX environments still parse.
+  pub dollar_math: bool,
+  /// Parse `\(...\)` and `\[...\]` as math, like Pandoc's
+  /// `tex_math_single_backslash`. Off by default, since CommonMark reads
+  /// `\[1\]` as the escaped text `[1]`.
+  pub latex_math: bool,
+  /// Treat every `$...$` pair as math, skipping the checks that keep
+  /// amounts such as `$5 and $10` as text.
+  pub strict_math: bool,
+  /// In tables, let an empty cell written `||` join the cell before it,
+  /// which then spans one more column.
+  pub table_colspan: bool,
+  /// Python: collect every triple-quoted string, not only docstrings.
+  pub all_strings: bool,
+  /// Node kinds to build (`--only-kinds`, `--skip-kinds`).
+  pub kinds: KindFilter,
+}
+
+impl ParseOptions {
+  pub const fn new() -> Self {
+    Self {
+      dollar_math: true,
+      latex_math: false,
+      strict_math: false,
+      table_colspan: false,
+      all_strings: false,
+      kinds: KindFilter::ALL,
+    }
+  }
+}
+
+impl ParseOptions {
+  /// Set the option a document's frontmatter names, as `math: false` under
+  /// `bukvar`: `math`, `latex_math`, `strict_math` or `table_colspan`, to
+  /// `true`/`false`
+  /// (or `yes`/`no`, `on`/`off`).
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+    let flag = match value.to_ascii_lowercase().as_str() {
+      "true" | "yes" | "on" => true,
+      "false" | "no" | "off" => false,
+      _ => return Err(format!("expected true or false for {}: \"{}\"", key, value)),
+    };
+    match key {
+      "math" => self.dollar_math = flag,
+      "latex_math" => self.latex_math = flag,
+      "strict_math" => self.strict_math = flag,
+      "table_colspan" => self.table_colspan = flag,
+      _ => return Err(format!("unknown parser option: {}", key)),
+    }
+    Ok(())
+  }
+}
+
+impl Default for ParseOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Kinds that only the inline parser builds.
+const INLINE_KINDS: &[&str] = &[
+  "Text",
+  "Emphasis",
+  "Strong",
+  "Strikethrough",
+  "CodeSpan",
+  "Link",
+  "Image",
+  "AutoUrl",
+  "FootnoteReference",
+  "HtmlInline",
+  "Comment",
+  "MathInline",
+  "MathBlock",
+];
+
+/// Node kinds the Markdown parser builds, as bits indexed like the JSON
+/// schema's kinds. A node of a skipped kind is dropped with everything
+/// under it. With `only` set, a node of a listed kind is kept whole and
+/// a node of any other kind gives way to its kept descendants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KindFilter {
+  only: u128,
+  skip: u128,
+}
+
+impl KindFilter {
+  /// Build every node.
+  pub const ALL: Self = Self { only: 0, skip: 0 };
+  /// Build no node.
+  const NONE: Self = Self {
+    only: 0,
+    skip: u128::MAX,
+  };
+
+  /// Keep only nodes of `names` (`NodeKind::name`) and what they contain.
+  pub fn with_only(mut self, names: &[String]) -> Self {
+    self.only = bits(names.iter().map(String::as_str));
+    self
+  }
+
+  /// Drop nodes of `names` and what they contain.
+  pub fn with_skip(mut self, names: &[String]) -> Self {
+    self.skip = bits(names.iter().map(String::as_str));
+    self
+  }
+
+  /// Whether a node of kind `name` is built.
+  pub fn keeps(&self, name: &str) -> bool {
+    let bit = bit(name);
+    self.skip & bit == 0 && (self.only == 0 || self.only & bit != 0)
+  }
+
+  /// Filter for the content of a node of kind `name`: nothing under a
+  /// skipped node, and everything not skipped under a kept one.
+  pub fn within(self, name: &str) -> Self {
+    if self.skip & bit(name) != 0 {
+      Self::NONE
+    } else if self.only & bit(name) != 0 {
+      Self { only: 0, ..self }
+    } else {
+      self
+    }
+  }
+
+  /// `within` each kind of `path` in turn, outermost first.
+  pub fn within_path(self, path: &[&str]) -> Self {
+    path.iter().fold(self, |kinds, name| kinds.within(name))
+  }
+
+  /// Whether inline content could yield any node, so it is worth parsing.
+  pub fn inline(&self) -> bool {
+    let inline = bits(INLINE_KINDS.iter().copied());
+    inline & !self.skip != 0 && (self.only == 0 || self.only & inline != 0)
+  }
+
+  /// Add `node` to `out` if it is kept, or else its kept descendants.
+  pub fn select(&self, mut node: Node, out: &mut Vec<Node>) {
+    if *self == Self::ALL {
+      out.push(node);
+      return;
+    }
+    let children = std::mem::take(&mut node.children);
+    let name = node.kind.name();
+    if self.keeps(name) {
+      let within = self.within(name);
+      for child in children {
+        within.select(child, &mut node.children);
+      }
+      out.push(node);
+    } else if self.skip & bit(name) == 0 {
+      for child in children {
+        self.select(child, out);
+      }
+    }
+  }
+
+  /// `select` over `nodes`.
+  pub fn prune(&self, nodes: Vec<Node>) -> Vec<Node> {
+    if *self == Self::ALL {
+      return nodes;
+    }
+    let mut out = Vec::with_capacity(nodes.len());
+    for node in nodes {
+      self.select(node, &mut out);
+    }
+    out
+  }
+}
+
+impl Default for KindFilter {
+  fn default() -> Self {
+    Self::ALL
+  }
+}
+
+fn bit(name: &str) -> u128 {
+  kind_index(name).map_or(0, |i| 1 << i)
+}
+
+fn bits<'n>(names: impl Iterator<Item = &'n str>) -> u128 {
+  names.fold(0, |bits, name| bits | bit(name))
+}
@@ -0,0 +1,195 @@
+//! Block parsing of one large input on several threads
+//! (`--parallel-blocks`).
+//!
+//! The input is cut at blank lines followed by an unindented line of text,
+//! and each segment gets its own thread and a block parser that scans the
+//! whole input from the segment's first byte, so spans and line numbers
+//! need no correcting afterwards. A cut is only a guess at a block
+//! boundary: a fence, math block or custom element can run across it.
+//! Segments are merged in order, and when the segment before a cut ran past
+//! it, the next segment's nodes are dropped and that stretch is parsed
+//! again from where the previous one stopped. The result is always exactly
+//! the sequential parse.
+
+use super::{BlockParser, ElementRegistry, LinkDef, ParseOptions, Scanner};
+use crate::ast::Node;
+
+use std::thread;
+
+/// Inputs smaller than this per thread are not worth splitting.
+pub(super) const MIN_SEGMENT: usize = 64 * 1024;
+
+/// Shared parser state for every segment.
+pub(super) struct Segments<'a> {
+  pub input: &'a str,
+  pub link_defs: &'a [LinkDef],
+  pub elements: &'a ElementRegistry,
+  pub options: ParseOptions,
+}
+
+impl Segments<'_> {
+  /// Parse the blocks from byte `start` to the end of the input on up to
+  /// `threads` threads, with segments of at least `min_segment` bytes.
+  pub fn parse(&self, start: usize, threads: usize, min_segment: usize) -> Vec<Node> {
+    let mut bounds = vec![start];
+    bounds.extend(split_points(self.input, start, threads, min_segment));
+    bounds.push(self.input.len());
+
+    let parsed: Vec<(Vec<Node>, usize)> = thread::scope(|scope| {
+      let handles: Vec<_> = bounds
+        .windows(2)
+        .map(|range| scope.spawn(move || self.parse_range(range[0], range[1])))
+        .collect();
+      handles
+        .into_iter()
+        .map(|handle| handle.join().expect("block parser thread panicked"))
+        .collect()
+    });
+
+    let mut nodes = Vec::new();
+    let mut pos = start;
+    for (range, (segment, stop)) in bounds.windows(2).zip(parsed) {
+      if range[0] == pos {
+        nodes.extend(segment);
+        pos = stop;
+      } else if pos < range[1] {
+        let (segment, stop) = self.parse_range(pos, range[1]);
+        nodes.extend(segment);
+        pos = stop;
+      }
+    }
+    nodes
+  }
+
+  /// Blocks starting in `from..end`, and where the scanner stopped.
+  fn parse_range(&self, from: usize, end: usize) -> (Vec<Node>, usize) {
+    let mut scanner = Scanner::new(self.input);
+    scanner.set_pos(from);
+    let nodes = BlockParser::new(&mut scanner, self.link_defs, self.elements)
+      .with_options(self.options)
+      .parse_blocks_until(end);
+    (nodes, scanner.pos())
+  }
+}
+
+/// Cut points after `start` splitting the input into up to `threads`
+/// segments of roughly equal size.
+fn split_points(input: &str, start: usize, threads: usize, min_segment: usize) -> Vec<usize> {
+  let len = input.len().saturating_sub(start);
+  let segments = threads.min(len / min_segment.max(1));
+  let mut cuts = Vec::new();
+  if segments < 2 {
+    return cuts;
+  }
+
+  let step = len / segments;
+  let mut from = start;
+  for i in 1..segments {
+    match next_cut(input, (start + i * step).max(from)) {
+      Some(cut) => {
+        cuts.push(cut);
+        from = cut;
+      }
+      None => break,
+    }
+  }
+  cuts
+}
+
+/// Start of the first line after `from` that follows a blank line and
+/// starts with a letter, digit, `#` or non-ASCII character at column one,
+/// which is where a new top-level block usually begins.
+fn next_cut(input: &str, from: usize) -> Option<usize> {
+  // `from` need not be a char boundary, so look for the newline in bytes
+  let newline = input.as_bytes()[from..].iter().position(|&b| b == b'\n')?;
+  let mut line_start = from + newline + 1;
+  let mut after_blank = false;
+  while line_start < input.len() {
+    let rest = &input[line_start..];
+    let line = rest.split('\n').next().unwrap_or(rest);
+    if line.trim().is_empty() {
+      after_blank = true;
+    } else {
+      let first = line.as_bytes()[0];
+      if after_blank && (first.is_ascii_alphanumeric() || first == b'#' || !first.is_ascii()) {
+        return Some(line_start);
+      }
+      after_blank = false;
+    }
+    line_start += line.len() + 1;
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::formats::to_json;
+  use crate::markdown::MarkdownParser;
+
+  use std::fs;
+  use std::path::Path;
+
+  /// Parse sequentially and with a cut at every possible place, then
+  /// compare the two.
+  fn assert_same_parse(source: &str) {
+    let sequential = MarkdownParser::new(source).parse();
+    let parallel = MarkdownParser::new(source)
+      .with_threads(source.len().max(2))
+      .with_min_segment(1)
+      .parse();
+    assert_eq!(
+      to_json(&parallel),
+      to_json(&sequential),
+      "parallel parse differs for:\n{}",
+      source
+    );
+  }
+
+  #[test]
+  fn test_split_points() {
+    let input = "# A\n\ntext\n\n  indented\n\n- item\n\nB\n";
+    assert_eq!(next_cut(input, 0), Some(5));
+    assert_eq!(next_cut(input, 5), Some(31));
+    assert_eq!(next_cut(input, 31), None);
+    assert_eq!(split_points(input, 0, 2, 1), vec![31]);
+    assert_eq!(split_points("A\n\nB\n\nC\n\nD\n", 0, 3, 1), vec![6, 9]);
+    assert!(split_points(input, 0, 8, 1024).is_empty());
+    assert!(split_points(input, 0, 1, 1).is_empty());
+  }
+
+  #[test]
+  fn test_blocks_across_cuts() {
+    assert_same_parse("# Title\n\nOne\n\nTwo *em*\n\n## Next\n\nThree\n");
+    assert_same_parse("Intro\n\n```\ncode\n\nnot a paragraph\n\n# not a heading\n```\n\nAfter\n");
+    assert_same_parse("Intro\n\n$$\nx\n\ny\n\nz $$ tail\n\nAfter\n");
+    assert_same_parse("A\n\n\\begin{align}\nx\n\nb\n\\end{align}\n\nC\n");
+    assert_same_parse("<steps>\n<step title=\"a\">\n\nText\n\nMore\n</step>\n</steps>\n\nEnd\n");
+    assert_same_parse("Text[^1]\n\n[^1]: note\n\n    more\n\nAfter\n\n[x]: /url\n\nsee [x]\n");
+    assert_same_parse("---\ntitle: t\n---\n\n# A\n\nB\n\nC\n");
+    assert_same_parse("A\n\n> quote\n> more\n\nB\n\n- a\n- b\n\nC\n\nTerm\n: def\n\nD");
+    assert_same_parse("é\n\nü\n\n日本\n\n$$ unclosed\n\nE\n");
+  }
+
+  #[test]
+  fn test_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    for entry in fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+      if path.extension().is_some_and(|ext| ext == "md") {
+        assert_same_parse(&fs::read_to_string(&path).unwrap());
+      }
+    }
+  }
+
+  #[test]
+  fn test_total_lines() {
+    let source = "A\n\nB\n\nC\n";
+    let parallel = MarkdownParser::new(source)
+      .with_threads(4)
+      .with_min_segment(1)
+      .parse();
+    assert_eq!(parallel.metadata.total_lines, 6);
+    assert_eq!(parallel.nodes.len(), 3);
+  }
+}
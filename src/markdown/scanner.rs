@@ -1,4 +1,9 @@
 //! Low-level byte scanner for parsing.
+//!
+//! Positions and columns count bytes. Parsers mostly step over ASCII
+//! syntax, where a byte is a character; to cross arbitrary text they use
+//! [`Scanner::advance_char`], and [`Scanner::slice`] widens a range that
+//! would split a character, so multi-byte content never panics.
 
 /// Scanner for byte-level parsing with position tracking.
 pub struct Scanner<'a> {
@@ -37,8 +42,21 @@ impl<'a> Scanner<'a> {
     self.pos
   }
 
-  #[inline(always)]
+  /// Move to `pos`, keeping the line count in step. The column is only
+  /// exact when moving within one line.
+  #[inline]
   pub fn set_pos(&mut self, pos: usize) {
+    let pos = pos.min(self.bytes.len());
+    let newlines = |range: &[u8]| range.iter().filter(|&&b| b == b'\n').count();
+    if pos < self.pos {
+      self.line -= newlines(&self.bytes[pos..self.pos]);
+    } else {
+      self.line += newlines(&self.bytes[self.pos..pos]);
+    }
+    self.column = match self.bytes[..pos].iter().rposition(|&b| b == b'\n') {
+      Some(nl) => pos - nl,
+      None => pos + 1,
+    };
     self.pos = pos;
   }
 
@@ -79,6 +97,13 @@ impl<'a> Scanner<'a> {
     self.bytes.get(self.pos + offset).copied()
   }
 
+  /// Peek at the character at the current position, `None` at the end
+  /// or inside a character.
+  #[inline]
+  pub fn peek_char(&self) -> Option<char> {
+    self.input.get(self.pos..)?.chars().next()
+  }
+
   /// Check if current byte matches expected.
   #[inline(always)]
   pub fn check(&self, expected: u8) -> bool {
@@ -111,9 +136,16 @@ impl<'a> Scanner<'a> {
     }
   }
 
+  /// Advance over one whole character, or one byte when not at the start
+  /// of one.
+  #[inline]
+  pub fn advance_char(&mut self) {
+    let len = self.peek_char().map_or(1, char::len_utf8);
+    self.advance_n(len);
+  }
+
   /// Advance n bytes (optimized for small n).
   #[inline]
-  #[allow(dead_code)]
   pub fn advance_n(&mut self, n: usize) {
     for _ in 0..n {
       self.advance();
@@ -180,7 +212,7 @@ impl<'a> Scanner<'a> {
       let start = self.pos;
       self.skip_whitespace_inline();
       if !self.consume(b'\n') {
-        self.pos = start;
+        self.set_pos(start);
         break;
       }
     }
@@ -218,6 +250,7 @@ impl<'a> Scanner<'a> {
 
   /// Scan non-whitespace characters.
   #[inline]
+  #[allow(dead_code)]
   pub fn scan_non_whitespace(&mut self) -> String {
     let start = self.pos;
     while self.pos < self.bytes.len() && !self.bytes[self.pos].is_ascii_whitespace() {
@@ -227,19 +260,19 @@ impl<'a> Scanner<'a> {
     self.input[start..self.pos].to_string()
   }
 
-  /// Get a slice of the input between positions.
+  /// Get a slice of the input between positions, widened to whole
+  /// characters if either end falls inside one.
   #[inline(always)]
   pub fn slice(&self, start: usize, end: usize) -> &'a str {
-    let end = end.min(self.input.len());
-    // SAFETY: start/end come from our byte positions which respect char boundaries
+    let end = ceil_char_boundary(self.input, end);
+    let start = floor_char_boundary(self.input, start.min(end));
     &self.input[start..end]
   }
 
-  /// Get remaining unparsed input.
+  /// Get remaining unparsed input, from the start of the current character.
   #[inline(always)]
-  #[allow(dead_code)]
   pub fn remaining(&self) -> &'a str {
-    &self.input[self.pos..]
+    &self.input[floor_char_boundary(self.input, self.pos)..]
   }
 
   /// Scan and return current line, advancing past it.
@@ -264,6 +297,24 @@ impl<'a> Scanner<'a> {
   }
 }
 
+/// `index`, or the start of the character it falls inside.
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+  let mut index = index.min(s.len());
+  while !s.is_char_boundary(index) {
+    index -= 1;
+  }
+  index
+}
+
+/// `index`, or the end of the character it falls inside.
+fn ceil_char_boundary(s: &str, index: usize) -> usize {
+  let mut index = index.min(s.len());
+  while !s.is_char_boundary(index) {
+    index += 1;
+  }
+  index
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -302,4 +353,36 @@ mod tests {
     s.advance(); // \n
     assert_eq!(s.line(), 2);
   }
+
+  #[test]
+  fn test_set_pos_tracks_lines() {
+    let mut s = Scanner::new("ab\ncd\n\nef");
+    s.set_pos(4);
+    assert_eq!((s.line(), s.column()), (2, 2));
+    s.set_pos(7);
+    assert_eq!((s.line(), s.column()), (4, 1));
+    // Backtracking, as lookahead does
+    s.set_pos(1);
+    assert_eq!((s.line(), s.column()), (1, 2));
+    s.set_pos(100);
+    assert_eq!((s.pos(), s.line(), s.column()), (9, 4, 3));
+  }
+
+  #[test]
+  fn test_multibyte() {
+    let mut s = Scanner::new("日本\n😀x");
+    assert_eq!(s.peek_char(), Some('日'));
+    s.advance_char();
+    assert_eq!((s.pos(), s.column()), (3, 4));
+    s.advance();
+    assert_eq!(s.peek_char(), None);
+    assert_eq!(s.slice(1, 4), "日本");
+    assert_eq!(s.remaining(), "本\n😀x");
+    s.advance_char();
+    s.advance_char();
+    s.advance_char();
+    assert_eq!((s.line(), s.column(), s.peek_char()), (2, 1, Some('😀')));
+    s.advance_char();
+    assert_eq!(s.peek(), Some(b'x'));
+  }
 }
@@ -0,0 +1,200 @@
+//! Parse tracing for `--trace-parse`: each block and inline rule the parser
+//! tries, where, and whether it matched, so a user can see why a line
+//! became a paragraph rather than a table.
+//!
+//! A rule is logged when it finishes, so the blocks inside a container
+//! and the inlines of a paragraph come before the container or paragraph.
+//! Events go to a per-thread capture stack and cost nothing when no
+//! capture is running. Release builds leave tracing out unless built with
+//! the `trace` feature: [`ENABLED`] is false and [`capture`] sees nothing.
+
+use crate::lines::LineIndex;
+
+use std::cell::RefCell;
+use std::fmt::Write;
+
+/// Whether this build records trace events.
+pub const ENABLED: bool = cfg!(any(debug_assertions, feature = "trace"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+  Block,
+  Inline,
+}
+
+impl Level {
+  pub fn name(self) -> &'static str {
+    match self {
+      Level::Block => "block",
+      Level::Inline => "inline",
+    }
+  }
+}
+
+/// One rule tried at one position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+  pub level: Level,
+  /// Rule name, such as `atx_heading` or `code_span`
+  pub rule: &'static str,
+  /// Byte offset in the input the capture started on
+  pub offset: usize,
+  pub accepted: bool,
+  /// Container nesting (blockquote, footnote, step, tab) the rule ran in
+  pub depth: usize,
+}
+
+/// Receives the events of a [`record`]ed parse.
+pub trait TraceSink {
+  fn event(&mut self, event: TraceEvent);
+}
+
+impl TraceSink for Vec<TraceEvent> {
+  fn event(&mut self, event: TraceEvent) {
+    self.push(event);
+  }
+}
+
+thread_local! {
+  static CAPTURES: RefCell<Vec<Vec<TraceEvent>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `f`, returning the events it emitted on this thread.
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<TraceEvent>) {
+  if !ENABLED {
+    return (f(), Vec::new());
+  }
+  CAPTURES.with(|captures| captures.borrow_mut().push(Vec::new()));
+  let result = f();
+  let events = CAPTURES.with(|captures| captures.borrow_mut().pop().unwrap_or_default());
+  (result, events)
+}
+
+/// Run `f`, handing the events it emitted to `sink`.
+pub fn record<R>(sink: &mut dyn TraceSink, f: impl FnOnce() -> R) -> R {
+  let (result, events) = capture(f);
+  for event in events {
+    sink.event(event);
+  }
+  result
+}
+
+/// Whether a capture is running on this thread.
+#[inline]
+pub fn active() -> bool {
+  ENABLED && CAPTURES.with(|captures| !captures.borrow().is_empty())
+}
+
+/// Add an event to the innermost capture, if any.
+#[inline]
+pub fn emit(event: TraceEvent) {
+  if ENABLED {
+    CAPTURES.with(|captures| {
+      if let Some(events) = captures.borrow_mut().last_mut() {
+        events.push(event);
+      }
+    });
+  }
+}
+
+#[inline]
+pub fn event(level: Level, rule: &'static str, offset: usize, accepted: bool) {
+  emit(TraceEvent {
+    level,
+    rule,
+    offset,
+    accepted,
+    depth: 0,
+  });
+}
+
+/// Trace a block rule's result and pass it on.
+#[inline]
+pub fn block<T>(rule: &'static str, offset: usize, result: Option<T>) -> Option<T> {
+  event(Level::Block, rule, offset, result.is_some());
+  result
+}
+
+/// Trace an inline rule's result and pass it on.
+#[inline]
+pub fn inline<T>(rule: &'static str, offset: usize, result: Option<T>) -> Option<T> {
+  event(Level::Inline, rule, offset, result.is_some());
+  result
+}
+
+/// Trace file text: one `line:column level rule accepted|rejected` line per
+/// event, indented by depth, with positions in `source`.
+pub struct TraceLog {
+  lines: LineIndex,
+  out: String,
+}
+
+impl TraceLog {
+  pub fn new(source: &str) -> Self {
+    Self {
+      lines: LineIndex::new(source),
+      out: String::new(),
+    }
+  }
+
+  pub fn finish(self) -> String {
+    self.out
+  }
+}
+
+impl TraceSink for TraceLog {
+  fn event(&mut self, event: TraceEvent) {
+    let (line, column) = self.lines.line_col(event.offset);
+    let _ = writeln!(
+      self.out,
+      "{:indent$}{}:{} {} {} {}",
+      "",
+      line,
+      column,
+      event.level.name(),
+      event.rule,
+      if event.accepted {
+        "accepted"
+      } else {
+        "rejected"
+      },
+      indent = event.depth * 2
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_capture_nests() {
+    let (inner, outer) = capture(|| {
+      event(Level::Block, "a", 0, true);
+      let ((), inner) = capture(|| event(Level::Inline, "b", 1, false));
+      inner
+    });
+    assert_eq!(outer.len(), 1);
+    assert_eq!(inner[0].rule, "b");
+    assert!(!active());
+  }
+
+  #[test]
+  fn test_trace_log() {
+    let mut log = TraceLog::new("# a\n> b\n");
+    record(&mut log, || {
+      event(Level::Block, "atx_heading", 0, true);
+      emit(TraceEvent {
+        level: Level::Inline,
+        rule: "code_span",
+        offset: 6,
+        accepted: false,
+        depth: 1,
+      });
+    });
+    assert_eq!(
+      log.finish(),
+      "1:1 block atx_heading accepted\n  2:3 inline code_span rejected\n"
+    );
+  }
+}
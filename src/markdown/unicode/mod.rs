@@ -0,0 +1,107 @@
+//! Unicode character classes for the inline parser, as CommonMark defines
+//! them: emphasis flanking and autolink boundaries, and the case folding
+//! that link labels match under.
+//!
+//! The tables in `tables.rs` are generated; see `scripts/unicode_tables.py`.
+
+mod tables;
+
+/// CommonMark Unicode whitespace: tab, line feed, form feed, carriage
+/// return, or a space separator (Zs), which includes the no-break spaces.
+pub fn is_whitespace(c: char) -> bool {
+  match c {
+    ' ' | '\t' | '\n' | '\x0C' | '\r' => true,
+    _ if c.is_ascii() => false,
+    _ => contains(tables::WHITESPACE, c),
+  }
+}
+
+/// CommonMark Unicode punctuation: ASCII punctuation, or a character in
+/// the general categories P (punctuation) or S (symbols). Letters, digits,
+/// combining marks and format characters such as the soft hyphen are not.
+pub fn is_punctuation(c: char) -> bool {
+  if c.is_ascii() {
+    c.is_ascii_punctuation()
+  } else {
+    contains(tables::PUNCTUATION, c)
+  }
+}
+
+/// Append the full Unicode case folding of `s` to `out`: lowercase, except
+/// where folding differs (`ß` and `ẞ` fold to `ss`, final `ς` to `σ`).
+pub fn fold_case(s: &str, out: &mut String) {
+  for c in s.chars() {
+    if c.is_ascii() {
+      out.push(c.to_ascii_lowercase());
+    } else if let Ok(i) = tables::CASE_FOLD.binary_search_by_key(&c, |&(from, _)| from) {
+      out.push_str(tables::CASE_FOLD[i].1);
+    } else {
+      out.extend(c.to_lowercase());
+    }
+  }
+}
+
+/// Whether `c` falls in one of the first, last pairs of `table`.
+fn contains(table: &[u32], c: char) -> bool {
+  let c = c as u32;
+  // Index of the first bound above `c`: odd when it closes a range.
+  match table.binary_search(&c) {
+    Ok(_) => true,
+    Err(i) => i % 2 == 1,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_tables_sorted() {
+    for table in [tables::PUNCTUATION, tables::WHITESPACE] {
+      assert_eq!(table.len() % 2, 0);
+      assert!(table.windows(2).all(|w| w[0] <= w[1]));
+    }
+    assert!(tables::CASE_FOLD.windows(2).all(|w| w[0].0 < w[1].0));
+  }
+
+  #[test]
+  fn test_whitespace() {
+    for c in [
+      ' ', '\t', '\n', '\u{a0}', '\u{202f}', '\u{3000}', '\u{2009}',
+    ] {
+      assert!(is_whitespace(c), "{:?}", c);
+    }
+    // Not space separators
+    for c in ['a', '\x0B', '\u{85}', '\u{200b}', '\u{2028}', '\u{ad}'] {
+      assert!(!is_whitespace(c), "{:?}", c);
+    }
+  }
+
+  #[test]
+  fn test_punctuation() {
+    for c in [
+      '*', '~', '«', '»', '—', '…', '。', '、', '（', '）', '：', '「', '€', '©', '¡',
+    ] {
+      assert!(is_punctuation(c), "{:?}", c);
+    }
+    for c in [
+      'a', 'é', 'ж', 'あ', '漢', '٣', '\u{301}', '\u{ad}', ' ', '\u{a0}',
+    ] {
+      assert!(!is_punctuation(c), "{:?}", c);
+    }
+  }
+
+  #[test]
+  fn test_fold_case() {
+    let fold = |s: &str| {
+      let mut out = String::new();
+      fold_case(s, &mut out);
+      out
+    };
+    assert_eq!(fold("Foo BAR"), "foo bar");
+    assert_eq!(fold("ẞ Straße"), "ss strasse");
+    assert_eq!(fold("ΣΊΣΥΦΟΣ"), fold("σίσυφος"));
+    assert_eq!(fold("ПРИВЕТ Ёлка"), "привет ёлка");
+    assert_eq!(fold("ﬁle"), "file");
+  }
+}
@@ -0,0 +1,374 @@
+//! Generated by `scripts/unicode_tables.py` from Unicode 14.0.0. Do not edit.
+//!
+//! The class tables list inclusive ranges of code points as first, last
+//! pairs, sorted, for binary search; the folding table is sorted by
+//! character.
+
+/// General categories P and S
+pub const PUNCTUATION: &[u32] = &[
+  0xa1, 0xa9, 0xab, 0xac, 0xae, 0xb1, 0xb4, 0xb4, 0xb6, 0xb8, 0xbb, 0xbb, 0xbf, 0xbf, 0xd7, 0xd7,
+  0xf7, 0xf7, 0x2c2, 0x2c5, 0x2d2, 0x2df, 0x2e5, 0x2eb, 0x2ed, 0x2ed, 0x2ef, 0x2ff, 0x375, 0x375,
+  0x37e, 0x37e, 0x384, 0x385, 0x387, 0x387, 0x3f6, 0x3f6, 0x482, 0x482, 0x55a, 0x55f, 0x589, 0x58a,
+  0x58d, 0x58f, 0x5be, 0x5be, 0x5c0, 0x5c0, 0x5c3, 0x5c3, 0x5c6, 0x5c6, 0x5f3, 0x5f4, 0x606, 0x60f,
+  0x61b, 0x61b, 0x61d, 0x61f, 0x66a, 0x66d, 0x6d4, 0x6d4, 0x6de, 0x6de, 0x6e9, 0x6e9, 0x6fd, 0x6fe,
+  0x700, 0x70d, 0x7f6, 0x7f9, 0x7fe, 0x7ff, 0x830, 0x83e, 0x85e, 0x85e, 0x888, 0x888, 0x964, 0x965,
+  0x970, 0x970, 0x9f2, 0x9f3, 0x9fa, 0x9fb, 0x9fd, 0x9fd, 0xa76, 0xa76, 0xaf0, 0xaf1, 0xb70, 0xb70,
+  0xbf3, 0xbfa, 0xc77, 0xc77, 0xc7f, 0xc7f, 0xc84, 0xc84, 0xd4f, 0xd4f, 0xd79, 0xd79, 0xdf4, 0xdf4,
+  0xe3f, 0xe3f, 0xe4f, 0xe4f, 0xe5a, 0xe5b, 0xf01, 0xf17, 0xf1a, 0xf1f, 0xf34, 0xf34, 0xf36, 0xf36,
+  0xf38, 0xf38, 0xf3a, 0xf3d, 0xf85, 0xf85, 0xfbe, 0xfc5, 0xfc7, 0xfcc, 0xfce, 0xfda, 0x104a,
+  0x104f, 0x109e, 0x109f, 0x10fb, 0x10fb, 0x1360, 0x1368, 0x1390, 0x1399, 0x1400, 0x1400, 0x166d,
+  0x166e, 0x169b, 0x169c, 0x16eb, 0x16ed, 0x1735, 0x1736, 0x17d4, 0x17d6, 0x17d8, 0x17db, 0x1800,
+  0x180a, 0x1940, 0x1940, 0x1944, 0x1945, 0x19de, 0x19ff, 0x1a1e, 0x1a1f, 0x1aa0, 0x1aa6, 0x1aa8,
+  0x1aad, 0x1b5a, 0x1b6a, 0x1b74, 0x1b7e, 0x1bfc, 0x1bff, 0x1c3b, 0x1c3f, 0x1c7e, 0x1c7f, 0x1cc0,
+  0x1cc7, 0x1cd3, 0x1cd3, 0x1fbd, 0x1fbd, 0x1fbf, 0x1fc1, 0x1fcd, 0x1fcf, 0x1fdd, 0x1fdf, 0x1fed,
+  0x1fef, 0x1ffd, 0x1ffe, 0x2010, 0x2027, 0x2030, 0x205e, 0x207a, 0x207e, 0x208a, 0x208e, 0x20a0,
+  0x20c0, 0x2100, 0x2101, 0x2103, 0x2106, 0x2108, 0x2109, 0x2114, 0x2114, 0x2116, 0x2118, 0x211e,
+  0x2123, 0x2125, 0x2125, 0x2127, 0x2127, 0x2129, 0x2129, 0x212e, 0x212e, 0x213a, 0x213b, 0x2140,
+  0x2144, 0x214a, 0x214d, 0x214f, 0x214f, 0x218a, 0x218b, 0x2190, 0x2426, 0x2440, 0x244a, 0x249c,
+  0x24e9, 0x2500, 0x2775, 0x2794, 0x2b73, 0x2b76, 0x2b95, 0x2b97, 0x2bff, 0x2ce5, 0x2cea, 0x2cf9,
+  0x2cfc, 0x2cfe, 0x2cff, 0x2d70, 0x2d70, 0x2e00, 0x2e2e, 0x2e30, 0x2e5d, 0x2e80, 0x2e99, 0x2e9b,
+  0x2ef3, 0x2f00, 0x2fd5, 0x2ff0, 0x2ffb, 0x3001, 0x3004, 0x3008, 0x3020, 0x3030, 0x3030, 0x3036,
+  0x3037, 0x303d, 0x303f, 0x309b, 0x309c, 0x30a0, 0x30a0, 0x30fb, 0x30fb, 0x3190, 0x3191, 0x3196,
+  0x319f, 0x31c0, 0x31e3, 0x3200, 0x321e, 0x322a, 0x3247, 0x3250, 0x3250, 0x3260, 0x327f, 0x328a,
+  0x32b0, 0x32c0, 0x33ff, 0x4dc0, 0x4dff, 0xa490, 0xa4c6, 0xa4fe, 0xa4ff, 0xa60d, 0xa60f, 0xa673,
+  0xa673, 0xa67e, 0xa67e, 0xa6f2, 0xa6f7, 0xa700, 0xa716, 0xa720, 0xa721, 0xa789, 0xa78a, 0xa828,
+  0xa82b, 0xa836, 0xa839, 0xa874, 0xa877, 0xa8ce, 0xa8cf, 0xa8f8, 0xa8fa, 0xa8fc, 0xa8fc, 0xa92e,
+  0xa92f, 0xa95f, 0xa95f, 0xa9c1, 0xa9cd, 0xa9de, 0xa9df, 0xaa5c, 0xaa5f, 0xaa77, 0xaa79, 0xaade,
+  0xaadf, 0xaaf0, 0xaaf1, 0xab5b, 0xab5b, 0xab6a, 0xab6b, 0xabeb, 0xabeb, 0xfb29, 0xfb29, 0xfbb2,
+  0xfbc2, 0xfd3e, 0xfd4f, 0xfdcf, 0xfdcf, 0xfdfc, 0xfdff, 0xfe10, 0xfe19, 0xfe30, 0xfe52, 0xfe54,
+  0xfe66, 0xfe68, 0xfe6b, 0xff01, 0xff0f, 0xff1a, 0xff20, 0xff3b, 0xff40, 0xff5b, 0xff65, 0xffe0,
+  0xffe6, 0xffe8, 0xffee, 0xfffc, 0xfffd, 0x10100, 0x10102, 0x10137, 0x1013f, 0x10179, 0x10189,
+  0x1018c, 0x1018e, 0x10190, 0x1019c, 0x101a0, 0x101a0, 0x101d0, 0x101fc, 0x1039f, 0x1039f,
+  0x103d0, 0x103d0, 0x1056f, 0x1056f, 0x10857, 0x10857, 0x10877, 0x10878, 0x1091f, 0x1091f,
+  0x1093f, 0x1093f, 0x10a50, 0x10a58, 0x10a7f, 0x10a7f, 0x10ac8, 0x10ac8, 0x10af0, 0x10af6,
+  0x10b39, 0x10b3f, 0x10b99, 0x10b9c, 0x10ead, 0x10ead, 0x10f55, 0x10f59, 0x10f86, 0x10f89,
+  0x11047, 0x1104d, 0x110bb, 0x110bc, 0x110be, 0x110c1, 0x11140, 0x11143, 0x11174, 0x11175,
+  0x111c5, 0x111c8, 0x111cd, 0x111cd, 0x111db, 0x111db, 0x111dd, 0x111df, 0x11238, 0x1123d,
+  0x112a9, 0x112a9, 0x1144b, 0x1144f, 0x1145a, 0x1145b, 0x1145d, 0x1145d, 0x114c6, 0x114c6,
+  0x115c1, 0x115d7, 0x11641, 0x11643, 0x11660, 0x1166c, 0x116b9, 0x116b9, 0x1173c, 0x1173f,
+  0x1183b, 0x1183b, 0x11944, 0x11946, 0x119e2, 0x119e2, 0x11a3f, 0x11a46, 0x11a9a, 0x11a9c,
+  0x11a9e, 0x11aa2, 0x11c41, 0x11c45, 0x11c70, 0x11c71, 0x11ef7, 0x11ef8, 0x11fd5, 0x11ff1,
+  0x11fff, 0x11fff, 0x12470, 0x12474, 0x12ff1, 0x12ff2, 0x16a6e, 0x16a6f, 0x16af5, 0x16af5,
+  0x16b37, 0x16b3f, 0x16b44, 0x16b45, 0x16e97, 0x16e9a, 0x16fe2, 0x16fe2, 0x1bc9c, 0x1bc9c,
+  0x1bc9f, 0x1bc9f, 0x1cf50, 0x1cfc3, 0x1d000, 0x1d0f5, 0x1d100, 0x1d126, 0x1d129, 0x1d164,
+  0x1d16a, 0x1d16c, 0x1d183, 0x1d184, 0x1d18c, 0x1d1a9, 0x1d1ae, 0x1d1ea, 0x1d200, 0x1d241,
+  0x1d245, 0x1d245, 0x1d300, 0x1d356, 0x1d6c1, 0x1d6c1, 0x1d6db, 0x1d6db, 0x1d6fb, 0x1d6fb,
+  0x1d715, 0x1d715, 0x1d735, 0x1d735, 0x1d74f, 0x1d74f, 0x1d76f, 0x1d76f, 0x1d789, 0x1d789,
+  0x1d7a9, 0x1d7a9, 0x1d7c3, 0x1d7c3, 0x1d800, 0x1d9ff, 0x1da37, 0x1da3a, 0x1da6d, 0x1da74,
+  0x1da76, 0x1da83, 0x1da85, 0x1da8b, 0x1e14f, 0x1e14f, 0x1e2ff, 0x1e2ff, 0x1e95e, 0x1e95f,
+  0x1ecac, 0x1ecac, 0x1ecb0, 0x1ecb0, 0x1ed2e, 0x1ed2e, 0x1eef0, 0x1eef1, 0x1f000, 0x1f02b,
+  0x1f030, 0x1f093, 0x1f0a0, 0x1f0ae, 0x1f0b1, 0x1f0bf, 0x1f0c1, 0x1f0cf, 0x1f0d1, 0x1f0f5,
+  0x1f10d, 0x1f1ad, 0x1f1e6, 0x1f202, 0x1f210, 0x1f23b, 0x1f240, 0x1f248, 0x1f250, 0x1f251,
+  0x1f260, 0x1f265, 0x1f300, 0x1f6d7, 0x1f6dd, 0x1f6ec, 0x1f6f0, 0x1f6fc, 0x1f700, 0x1f773,
+  0x1f780, 0x1f7d8, 0x1f7e0, 0x1f7eb, 0x1f7f0, 0x1f7f0, 0x1f800, 0x1f80b, 0x1f810, 0x1f847,
+  0x1f850, 0x1f859, 0x1f860, 0x1f887, 0x1f890, 0x1f8ad, 0x1f8b0, 0x1f8b1, 0x1f900, 0x1fa53,
+  0x1fa60, 0x1fa6d, 0x1fa70, 0x1fa74, 0x1fa78, 0x1fa7c, 0x1fa80, 0x1fa86, 0x1fa90, 0x1faac,
+  0x1fab0, 0x1faba, 0x1fac0, 0x1fac5, 0x1fad0, 0x1fad9, 0x1fae0, 0x1fae7, 0x1faf0, 0x1faf6,
+  0x1fb00, 0x1fb92, 0x1fb94, 0x1fbca,
+];
+
+/// General category Zs
+pub const WHITESPACE: &[u32] = &[
+  0xa0, 0xa0, 0x1680, 0x1680, 0x2000, 0x200a, 0x202f, 0x202f, 0x205f, 0x205f, 0x3000, 0x3000,
+];
+
+/// Full case folding where it differs from `char::to_lowercase`
+pub const CASE_FOLD: &[(char, &str)] = &[
+  ('\u{b5}', "\u{3bc}"),
+  ('\u{df}', "ss"),
+  ('\u{149}', "\u{2bc}n"),
+  ('\u{17f}', "s"),
+  ('\u{1f0}', "j\u{30c}"),
+  ('\u{345}', "\u{3b9}"),
+  ('\u{390}', "\u{3b9}\u{308}\u{301}"),
+  ('\u{3b0}', "\u{3c5}\u{308}\u{301}"),
+  ('\u{3c2}', "\u{3c3}"),
+  ('\u{3d0}', "\u{3b2}"),
+  ('\u{3d1}', "\u{3b8}"),
+  ('\u{3d5}', "\u{3c6}"),
+  ('\u{3d6}', "\u{3c0}"),
+  ('\u{3f0}', "\u{3ba}"),
+  ('\u{3f1}', "\u{3c1}"),
+  ('\u{3f5}', "\u{3b5}"),
+  ('\u{587}', "\u{565}\u{582}"),
+  ('\u{13a0}', "\u{13a0}"),
+  ('\u{13a1}', "\u{13a1}"),
+  ('\u{13a2}', "\u{13a2}"),
+  ('\u{13a3}', "\u{13a3}"),
+  ('\u{13a4}', "\u{13a4}"),
+  ('\u{13a5}', "\u{13a5}"),
+  ('\u{13a6}', "\u{13a6}"),
+  ('\u{13a7}', "\u{13a7}"),
+  ('\u{13a8}', "\u{13a8}"),
+  ('\u{13a9}', "\u{13a9}"),
+  ('\u{13aa}', "\u{13aa}"),
+  ('\u{13ab}', "\u{13ab}"),
+  ('\u{13ac}', "\u{13ac}"),
+  ('\u{13ad}', "\u{13ad}"),
+  ('\u{13ae}', "\u{13ae}"),
+  ('\u{13af}', "\u{13af}"),
+  ('\u{13b0}', "\u{13b0}"),
+  ('\u{13b1}', "\u{13b1}"),
+  ('\u{13b2}', "\u{13b2}"),
+  ('\u{13b3}', "\u{13b3}"),
+  ('\u{13b4}', "\u{13b4}"),
+  ('\u{13b5}', "\u{13b5}"),
+  ('\u{13b6}', "\u{13b6}"),
+  ('\u{13b7}', "\u{13b7}"),
+  ('\u{13b8}', "\u{13b8}"),
+  ('\u{13b9}', "\u{13b9}"),
+  ('\u{13ba}', "\u{13ba}"),
+  ('\u{13bb}', "\u{13bb}"),
+  ('\u{13bc}', "\u{13bc}"),
+  ('\u{13bd}', "\u{13bd}"),
+  ('\u{13be}', "\u{13be}"),
+  ('\u{13bf}', "\u{13bf}"),
+  ('\u{13c0}', "\u{13c0}"),
+  ('\u{13c1}', "\u{13c1}"),
+  ('\u{13c2}', "\u{13c2}"),
+  ('\u{13c3}', "\u{13c3}"),
+  ('\u{13c4}', "\u{13c4}"),
+  ('\u{13c5}', "\u{13c5}"),
+  ('\u{13c6}', "\u{13c6}"),
+  ('\u{13c7}', "\u{13c7}"),
+  ('\u{13c8}', "\u{13c8}"),
+  ('\u{13c9}', "\u{13c9}"),
+  ('\u{13ca}', "\u{13ca}"),
+  ('\u{13cb}', "\u{13cb}"),
+  ('\u{13cc}', "\u{13cc}"),
+  ('\u{13cd}', "\u{13cd}"),
+  ('\u{13ce}', "\u{13ce}"),
+  ('\u{13cf}', "\u{13cf}"),
+  ('\u{13d0}', "\u{13d0}"),
+  ('\u{13d1}', "\u{13d1}"),
+  ('\u{13d2}', "\u{13d2}"),
+  ('\u{13d3}', "\u{13d3}"),
+  ('\u{13d4}', "\u{13d4}"),
+  ('\u{13d5}', "\u{13d5}"),
+  ('\u{13d6}', "\u{13d6}"),
+  ('\u{13d7}', "\u{13d7}"),
+  ('\u{13d8}', "\u{13d8}"),
+  ('\u{13d9}', "\u{13d9}"),
+  ('\u{13da}', "\u{13da}"),
+  ('\u{13db}', "\u{13db}"),
+  ('\u{13dc}', "\u{13dc}"),
+  ('\u{13dd}', "\u{13dd}"),
+  ('\u{13de}', "\u{13de}"),
+  ('\u{13df}', "\u{13df}"),
+  ('\u{13e0}', "\u{13e0}"),
+  ('\u{13e1}', "\u{13e1}"),
+  ('\u{13e2}', "\u{13e2}"),
+  ('\u{13e3}', "\u{13e3}"),
+  ('\u{13e4}', "\u{13e4}"),
+  ('\u{13e5}', "\u{13e5}"),
+  ('\u{13e6}', "\u{13e6}"),
+  ('\u{13e7}', "\u{13e7}"),
+  ('\u{13e8}', "\u{13e8}"),
+  ('\u{13e9}', "\u{13e9}"),
+  ('\u{13ea}', "\u{13ea}"),
+  ('\u{13eb}', "\u{13eb}"),
+  ('\u{13ec}', "\u{13ec}"),
+  ('\u{13ed}', "\u{13ed}"),
+  ('\u{13ee}', "\u{13ee}"),
+  ('\u{13ef}', "\u{13ef}"),
+  ('\u{13f0}', "\u{13f0}"),
+  ('\u{13f1}', "\u{13f1}"),
+  ('\u{13f2}', "\u{13f2}"),
+  ('\u{13f3}', "\u{13f3}"),
+  ('\u{13f4}', "\u{13f4}"),
+  ('\u{13f5}', "\u{13f5}"),
+  ('\u{13f8}', "\u{13f0}"),
+  ('\u{13f9}', "\u{13f1}"),
+  ('\u{13fa}', "\u{13f2}"),
+  ('\u{13fb}', "\u{13f3}"),
+  ('\u{13fc}', "\u{13f4}"),
+  ('\u{13fd}', "\u{13f5}"),
+  ('\u{1c80}', "\u{432}"),
+  ('\u{1c81}', "\u{434}"),
+  ('\u{1c82}', "\u{43e}"),
+  ('\u{1c83}', "\u{441}"),
+  ('\u{1c84}', "\u{442}"),
+  ('\u{1c85}', "\u{442}"),
+  ('\u{1c86}', "\u{44a}"),
+  ('\u{1c87}', "\u{463}"),
+  ('\u{1c88}', "\u{a64b}"),
+  ('\u{1e96}', "h\u{331}"),
+  ('\u{1e97}', "t\u{308}"),
+  ('\u{1e98}', "w\u{30a}"),
+  ('\u{1e99}', "y\u{30a}"),
+  ('\u{1e9a}', "a\u{2be}"),
+  ('\u{1e9b}', "\u{1e61}"),
+  ('\u{1e9e}', "ss"),
+  ('\u{1f50}', "\u{3c5}\u{313}"),
+  ('\u{1f52}', "\u{3c5}\u{313}\u{300}"),
+  ('\u{1f54}', "\u{3c5}\u{313}\u{301}"),
+  ('\u{1f56}', "\u{3c5}\u{313}\u{342}"),
+  ('\u{1f80}', "\u{1f00}\u{3b9}"),
+  ('\u{1f81}', "\u{1f01}\u{3b9}"),
+  ('\u{1f82}', "\u{1f02}\u{3b9}"),
+  ('\u{1f83}', "\u{1f03}\u{3b9}"),
+  ('\u{1f84}', "\u{1f04}\u{3b9}"),
+  ('\u{1f85}', "\u{1f05}\u{3b9}"),
+  ('\u{1f86}', "\u{1f06}\u{3b9}"),
+  ('\u{1f87}', "\u{1f07}\u{3b9}"),
+  ('\u{1f88}', "\u{1f00}\u{3b9}"),
+  ('\u{1f89}', "\u{1f01}\u{3b9}"),
+  ('\u{1f8a}', "\u{1f02}\u{3b9}"),
+  ('\u{1f8b}', "\u{1f03}\u{3b9}"),
+  ('\u{1f8c}', "\u{1f04}\u{3b9}"),
+  ('\u{1f8d}', "\u{1f05}\u{3b9}"),
+  ('\u{1f8e}', "\u{1f06}\u{3b9}"),
+  ('\u{1f8f}', "\u{1f07}\u{3b9}"),
+  ('\u{1f90}', "\u{1f20}\u{3b9}"),
+  ('\u{1f91}', "\u{1f21}\u{3b9}"),
+  ('\u{1f92}', "\u{1f22}\u{3b9}"),
+  ('\u{1f93}', "\u{1f23}\u{3b9}"),
+  ('\u{1f94}', "\u{1f24}\u{3b9}"),
+  ('\u{1f95}', "\u{1f25}\u{3b9}"),
+  ('\u{1f96}', "\u{1f26}\u{3b9}"),
+  ('\u{1f97}', "\u{1f27}\u{3b9}"),
+  ('\u{1f98}', "\u{1f20}\u{3b9}"),
+  ('\u{1f99}', "\u{1f21}\u{3b9}"),
+  ('\u{1f9a}', "\u{1f22}\u{3b9}"),
+  ('\u{1f9b}', "\u{1f23}\u{3b9}"),
+  ('\u{1f9c}', "\u{1f24}\u{3b9}"),
+  ('\u{1f9d}', "\u{1f25}\u{3b9}"),
+  ('\u{1f9e}', "\u{1f26}\u{3b9}"),
+  ('\u{1f9f}', "\u{1f27}\u{3b9}"),
+  ('\u{1fa0}', "\u{1f60}\u{3b9}"),
+  ('\u{1fa1}', "\u{1f61}\u{3b9}"),
+  ('\u{1fa2}', "\u{1f62}\u{3b9}"),
+  ('\u{1fa3}', "\u{1f63}\u{3b9}"),
+  ('\u{1fa4}', "\u{1f64}\u{3b9}"),
+  ('\u{1fa5}', "\u{1f65}\u{3b9}"),
+  ('\u{1fa6}', "\u{1f66}\u{3b9}"),
+  ('\u{1fa7}', "\u{1f67}\u{3b9}"),
+  ('\u{1fa8}', "\u{1f60}\u{3b9}"),
+  ('\u{1fa9}', "\u{1f61}\u{3b9}"),
+  ('\u{1faa}', "\u{1f62}\u{3b9}"),
+  ('\u{1fab}', "\u{1f63}\u{3b9}"),
+  ('\u{1fac}', "\u{1f64}\u{3b9}"),
+  ('\u{1fad}', "\u{1f65}\u{3b9}"),
+  ('\u{1fae}', "\u{1f66}\u{3b9}"),
+  ('\u{1faf}', "\u{1f67}\u{3b9}"),
+  ('\u{1fb2}', "\u{1f70}\u{3b9}"),
+  ('\u{1fb3}', "\u{3b1}\u{3b9}"),
+  ('\u{1fb4}', "\u{3ac}\u{3b9}"),
+  ('\u{1fb6}', "\u{3b1}\u{342}"),
+  ('\u{1fb7}', "\u{3b1}\u{342}\u{3b9}"),
+  ('\u{1fbc}', "\u{3b1}\u{3b9}"),
+  ('\u{1fbe}', "\u{3b9}"),
+  ('\u{1fc2}', "\u{1f74}\u{3b9}"),
+  ('\u{1fc3}', "\u{3b7}\u{3b9}"),
+  ('\u{1fc4}', "\u{3ae}\u{3b9}"),
+  ('\u{1fc6}', "\u{3b7}\u{342}"),
+  ('\u{1fc7}', "\u{3b7}\u{342}\u{3b9}"),
+  ('\u{1fcc}', "\u{3b7}\u{3b9}"),
+  ('\u{1fd2}', "\u{3b9}\u{308}\u{300}"),
+  ('\u{1fd3}', "\u{3b9}\u{308}\u{301}"),
+  ('\u{1fd6}', "\u{3b9}\u{342}"),
+  ('\u{1fd7}', "\u{3b9}\u{308}\u{342}"),
+  ('\u{1fe2}', "\u{3c5}\u{308}\u{300}"),
+  ('\u{1fe3}', "\u{3c5}\u{308}\u{301}"),
+  ('\u{1fe4}', "\u{3c1}\u{313}"),
+  ('\u{1fe6}', "\u{3c5}\u{342}"),
+  ('\u{1fe7}', "\u{3c5}\u{308}\u{342}"),
+  ('\u{1ff2}', "\u{1f7c}\u{3b9}"),
+  ('\u{1ff3}', "\u{3c9}\u{3b9}"),
+  ('\u{1ff4}', "\u{3ce}\u{3b9}"),
+  ('\u{1ff6}', "\u{3c9}\u{342}"),
+  ('\u{1ff7}', "\u{3c9}\u{342}\u{3b9}"),
+  ('\u{1ffc}', "\u{3c9}\u{3b9}"),
+  ('\u{ab70}', "\u{13a0}"),
+  ('\u{ab71}', "\u{13a1}"),
+  ('\u{ab72}', "\u{13a2}"),
+  ('\u{ab73}', "\u{13a3}"),
+  ('\u{ab74}', "\u{13a4}"),
+  ('\u{ab75}', "\u{13a5}"),
+  ('\u{ab76}', "\u{13a6}"),
+  ('\u{ab77}', "\u{13a7}"),
+  ('\u{ab78}', "\u{13a8}"),
+  ('\u{ab79}', "\u{13a9}"),
+  ('\u{ab7a}', "\u{13aa}"),
+  ('\u{ab7b}', "\u{13ab}"),
+  ('\u{ab7c}', "\u{13ac}"),
+  ('\u{ab7d}', "\u{13ad}"),
+  ('\u{ab7e}', "\u{13ae}"),
+  ('\u{ab7f}', "\u{13af}"),
+  ('\u{ab80}', "\u{13b0}"),
+  ('\u{ab81}', "\u{13b1}"),
+  ('\u{ab82}', "\u{13b2}"),
+  ('\u{ab83}', "\u{13b3}"),
+  ('\u{ab84}', "\u{13b4}"),
+  ('\u{ab85}', "\u{13b5}"),
+  ('\u{ab86}', "\u{13b6}"),
+  ('\u{ab87}', "\u{13b7}"),
+  ('\u{ab88}', "\u{13b8}"),
+  ('\u{ab89}', "\u{13b9}"),
+  ('\u{ab8a}', "\u{13ba}"),
+  ('\u{ab8b}', "\u{13bb}"),
+  ('\u{ab8c}', "\u{13bc}"),
+  ('\u{ab8d}', "\u{13bd}"),
+  ('\u{ab8e}', "\u{13be}"),
+  ('\u{ab8f}', "\u{13bf}"),
+  ('\u{ab90}', "\u{13c0}"),
+  ('\u{ab91}', "\u{13c1}"),
+  ('\u{ab92}', "\u{13c2}"),
+  ('\u{ab93}', "\u{13c3}"),
+  ('\u{ab94}', "\u{13c4}"),
+  ('\u{ab95}', "\u{13c5}"),
+  ('\u{ab96}', "\u{13c6}"),
+  ('\u{ab97}', "\u{13c7}"),
+  ('\u{ab98}', "\u{13c8}"),
+  ('\u{ab99}', "\u{13c9}"),
+  ('\u{ab9a}', "\u{13ca}"),
+  ('\u{ab9b}', "\u{13cb}"),
+  ('\u{ab9c}', "\u{13cc}"),
+  ('\u{ab9d}', "\u{13cd}"),
+  ('\u{ab9e}', "\u{13ce}"),
+  ('\u{ab9f}', "\u{13cf}"),
+  ('\u{aba0}', "\u{13d0}"),
+  ('\u{aba1}', "\u{13d1}"),
+  ('\u{aba2}', "\u{13d2}"),
+  ('\u{aba3}', "\u{13d3}"),
+  ('\u{aba4}', "\u{13d4}"),
+  ('\u{aba5}', "\u{13d5}"),
+  ('\u{aba6}', "\u{13d6}"),
+  ('\u{aba7}', "\u{13d7}"),
+  ('\u{aba8}', "\u{13d8}"),
+  ('\u{aba9}', "\u{13d9}"),
+  ('\u{abaa}', "\u{13da}"),
+  ('\u{abab}', "\u{13db}"),
+  ('\u{abac}', "\u{13dc}"),
+  ('\u{abad}', "\u{13dd}"),
+  ('\u{abae}', "\u{13de}"),
+  ('\u{abaf}', "\u{13df}"),
+  ('\u{abb0}', "\u{13e0}"),
+  ('\u{abb1}', "\u{13e1}"),
+  ('\u{abb2}', "\u{13e2}"),
+  ('\u{abb3}', "\u{13e3}"),
+  ('\u{abb4}', "\u{13e4}"),
+  ('\u{abb5}', "\u{13e5}"),
+  ('\u{abb6}', "\u{13e6}"),
+  ('\u{abb7}', "\u{13e7}"),
+  ('\u{abb8}', "\u{13e8}"),
+  ('\u{abb9}', "\u{13e9}"),
+  ('\u{abba}', "\u{13ea}"),
+  ('\u{abbb}', "\u{13eb}"),
+  ('\u{abbc}', "\u{13ec}"),
+  ('\u{abbd}', "\u{13ed}"),
+  ('\u{abbe}', "\u{13ee}"),
+  ('\u{abbf}', "\u{13ef}"),
+  ('\u{fb00}', "ff"),
+  ('\u{fb01}', "fi"),
+  ('\u{fb02}', "fl"),
+  ('\u{fb03}', "ffi"),
+  ('\u{fb04}', "ffl"),
+  ('\u{fb05}', "st"),
+  ('\u{fb06}', "st"),
+  ('\u{fb13}', "\u{574}\u{576}"),
+  ('\u{fb14}', "\u{574}\u{565}"),
+  ('\u{fb15}', "\u{574}\u{56b}"),
+  ('\u{fb16}', "\u{57e}\u{576}"),
+  ('\u{fb17}', "\u{574}\u{56d}"),
+];
@@ -0,0 +1,339 @@
+//! Memory use for `--mem-stats`: the process's peak resident set size,
+//! and an estimate of what each document's AST occupies.
+//!
+//! The estimate counts every node at `size_of::<Node>()` plus the bytes of
+//! the strings and lists it owns. Allocator overhead and spare capacity
+//! are left out, so real use is somewhat higher.
+
+use crate::ast::{AlertType, Author, Document, Node, NodeKind};
+use crate::formats::esc;
+
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+
+/// Memory figures of one processed file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileMemory {
+  /// Size of the source file
+  pub source_bytes: usize,
+  /// Estimated size of the finished AST
+  pub ast_bytes: usize,
+}
+
+/// Memory figures of a run: totals and the files with the largest ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+  /// Peak resident set size of the process, where the OS reports it
+  pub peak_rss: Option<u64>,
+  pub total_ast_bytes: usize,
+  pub largest_ast: Option<(PathBuf, FileMemory)>,
+  pub largest_source: Option<(PathBuf, FileMemory)>,
+}
+
+impl MemoryStats {
+  pub fn add_file(&mut self, path: &Path, memory: FileMemory) {
+    self.total_ast_bytes += memory.ast_bytes;
+    if self
+      .largest_ast
+      .as_ref()
+      .map_or(true, |(_, m)| memory.ast_bytes > m.ast_bytes)
+    {
+      self.largest_ast = Some((path.to_path_buf(), memory));
+    }
+    if self
+      .largest_source
+      .as_ref()
+      .map_or(true, |(_, m)| memory.source_bytes > m.source_bytes)
+    {
+      self.largest_source = Some((path.to_path_buf(), memory));
+    }
+  }
+
+  /// `{"peak_rss_bytes":..,"ast_bytes":..,"largest_ast":{..},"largest_source":{..}}`
+  pub fn to_json(&self) -> String {
+    let file = |entry: &Option<(PathBuf, FileMemory)>| match entry {
+      Some((path, memory)) => format!(
+        "{{\"path\":\"{}\",\"source_bytes\":{},\"ast_bytes\":{}}}",
+        esc(&path.to_string_lossy().replace('\\', "/")),
+        memory.source_bytes,
+        memory.ast_bytes
+      ),
+      None => "null".to_string(),
+    };
+    format!(
+      "{{\"peak_rss_bytes\":{},\"ast_bytes\":{},\"largest_ast\":{},\"largest_source\":{}}}",
+      self
+        .peak_rss
+        .map_or("null".to_string(), |bytes| bytes.to_string()),
+      self.total_ast_bytes,
+      file(&self.largest_ast),
+      file(&self.largest_source)
+    )
+  }
+}
+
+/// Estimated bytes held by `doc` and its nodes.
+pub fn ast_bytes(doc: &Document) -> usize {
+  let meta = &doc.metadata;
+  let authors: usize = meta
+    .authors
+    .iter()
+    .map(|a| size_of::<Author>() + a.name.len() + opt(&a.email) + opt(&a.url))
+    .sum();
+  size_of::<Document>()
+    + doc.source_path.len()
+    + opt(&meta.title)
+    + opt(&meta.description)
+    + pairs(&meta.glossary)
+    + opt(&meta.date)
+    + opt(&meta.updated)
+    + authors
+    + doc.nodes.iter().map(node_bytes).sum::<usize>()
+}
+
+fn node_bytes(node: &Node) -> usize {
+  size_of::<Node>() + kind_bytes(&node.kind) + node.children.iter().map(node_bytes).sum::<usize>()
+}
+
+fn opt(s: &Option<String>) -> usize {
+  s.as_ref().map_or(0, String::len)
+}
+
+fn pairs(pairs: &[(String, String)]) -> usize {
+  pairs
+    .iter()
+    .map(|(k, v)| size_of::<(String, String)>() + k.len() + v.len())
+    .sum()
+}
+
+/// Heap bytes owned by a node's kind.
+fn kind_bytes(kind: &NodeKind) -> usize {
+  match kind {
+    NodeKind::Heading { id, attributes, .. } => opt(id) + pairs(attributes),
+    NodeKind::CodeBlock { language, info } => opt(language) + opt(info),
+    NodeKind::FencedCodeBlock {
+      language,
+      info,
+      attributes,
+    } => opt(language) + opt(info) + pairs(attributes),
+    NodeKind::Text { content }
+    | NodeKind::Code { content }
+    | NodeKind::CodeSpan { content }
+    | NodeKind::HtmlInline { content }
+    | NodeKind::MathInline { content }
+    | NodeKind::MathBlock { content, .. }
+    | NodeKind::Comment { content, .. }
+    | NodeKind::Frontmatter { content, .. }
+    | NodeKind::DocExample { content }
+    | NodeKind::DocDescription { content }
+    | NodeKind::DocRemarks { content } => content.len(),
+    NodeKind::DiagramBlock { engine, content } => engine.len() + content.len(),
+    NodeKind::Link {
+      url,
+      title,
+      attributes,
+      ..
+    } => url.len() + opt(title) + pairs(attributes),
+    NodeKind::Image {
+      url,
+      alt,
+      title,
+      attributes,
+    } => url.len() + alt.len() + opt(title) + pairs(attributes),
+    NodeKind::AutoLink { url } | NodeKind::AutoUrl { url } => url.len(),
+    NodeKind::LinkReference { label, .. }
+    | NodeKind::FootnoteReference { label, .. }
+    | NodeKind::FootnoteDefinition { label, .. }
+    | NodeKind::Footnote { label } => label.len(),
+    NodeKind::LinkDefinition { label, url, title } => label.len() + url.len() + opt(title),
+    NodeKind::Emoji { shortcode } => shortcode.len(),
+    NodeKind::Mention { username } => username.len(),
+    NodeKind::DocTag { name, content } | NodeKind::DocInlineTag { name, content } => {
+      name.len() + opt(content)
+    }
+    NodeKind::DocParam {
+      name,
+      param_type,
+      description,
+      ..
+    } => name.len() + opt(param_type) + opt(description),
+    NodeKind::DocReturn {
+      return_type,
+      description,
+      ..
+    } => opt(return_type) + opt(description),
+    NodeKind::DocThrows {
+      exception_type,
+      description,
+    } => exception_type.len() + opt(description),
+    NodeKind::DocSee { reference } => reference.len(),
+    NodeKind::DocDeprecated { message, version } => opt(message) + opt(version),
+    NodeKind::DocSince { version } | NodeKind::DocVersion { version } => version.len(),
+    NodeKind::DocType { type_expr } => type_expr.len(),
+    NodeKind::DocProperty {
+      name,
+      prop_type,
+      description,
+    } => name.len() + opt(prop_type) + opt(description),
+    NodeKind::DocTypedef { name, type_expr } => name.len() + opt(type_expr),
+    NodeKind::DocTypeParam { name, description } => name.len() + opt(description),
+    NodeKind::DocDefaultValue { value } => value.len(),
+    NodeKind::DocDoctest { source, expected } => source.len() + opt(expected),
+    NodeKind::DocAuthor { name }
+    | NodeKind::DocCallback { name }
+    | NodeKind::DocModifier { name }
+    | NodeKind::Tab { name } => name.len(),
+    NodeKind::Alert { alert_type } => match alert_type {
+      AlertType::Other(name) => name.len(),
+      _ => 0,
+    },
+    NodeKind::Tabs { names } => names.iter().map(|n| size_of::<String>() + n.len()).sum(),
+    NodeKind::CodeBlockExt {
+      language,
+      highlight,
+      plusdiff,
+      minusdiff,
+      invalid_ranges,
+      attributes,
+      ..
+    } => {
+      let ranges = highlight.len() + plusdiff.len() + minusdiff.len();
+      opt(language) + ranges * size_of::<(u32, u32)>() + pairs(invalid_ranges) + pairs(attributes)
+    }
+    NodeKind::CustomElement { name, attributes }
+    | NodeKind::HtmlTag {
+      name, attributes, ..
+    } => name.len() + pairs(attributes),
+    NodeKind::Include { src } => src.len(),
+    NodeKind::Abbreviation { title } => title.len(),
+    NodeKind::Step { title, .. } => opt(title),
+    NodeKind::Document
+    | NodeKind::Paragraph
+    | NodeKind::BlockQuote
+    | NodeKind::IndentedCodeBlock
+    | NodeKind::HtmlBlock { .. }
+    | NodeKind::ThematicBreak
+    | NodeKind::List { .. }
+    | NodeKind::ListItem { .. }
+    | NodeKind::Table
+    | NodeKind::TableHead
+    | NodeKind::TableBody
+    | NodeKind::TableRow
+    | NodeKind::TableCell { .. }
+    | NodeKind::Emphasis
+    | NodeKind::Strong
+    | NodeKind::Strikethrough
+    | NodeKind::HardBreak
+    | NodeKind::SoftBreak
+    | NodeKind::TaskListMarker { .. }
+    | NodeKind::IssueReference { .. }
+    | NodeKind::DocComment { .. }
+    | NodeKind::DocReleaseTag { .. }
+    | NodeKind::Footnotes
+    | NodeKind::DefinitionList
+    | NodeKind::DefinitionTerm
+    | NodeKind::DefinitionDescription
+    | NodeKind::Steps
+    | NodeKind::Toc
+    | NodeKind::Unknown { .. } => 0,
+  }
+}
+
+/// Peak resident set size of this process in bytes: `VmHWM` from
+/// `/proc/self/status` on Linux.
+#[cfg(target_os = "linux")]
+pub fn peak_rss() -> Option<u64> {
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+  let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+  let kb: u64 = line["VmHWM:".len()..]
+    .trim()
+    .trim_end_matches("kB")
+    .trim()
+    .parse()
+    .ok()?;
+  Some(kb * 1024)
+}
+
+/// Peak resident set size of this process in bytes: `ru_maxrss` from
+/// `getrusage`, which macOS reports in bytes.
+#[cfg(target_os = "macos")]
+pub fn peak_rss() -> Option<u64> {
+  use std::os::raw::{c_int, c_long};
+  // struct rusage: two timevals, then 14 longs starting with ru_maxrss
+  extern "C" {
+    fn getrusage(who: c_int, usage: *mut [c_long; 18]) -> c_int;
+  }
+  let mut usage = [0; 18];
+  // SAFETY: `usage` is as large as `struct rusage`, which getrusage fills.
+  let status = unsafe { getrusage(0, &mut usage) };
+  (status == 0).then_some(usage[4] as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn peak_rss() -> Option<u64> {
+  None
+}
+
+/// `1.5 MB` style sizes for the summary.
+pub fn human(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+  let mut size = bytes as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{} B", bytes)
+  } else {
+    format!("{:.1} {}", size, UNITS[unit])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_ast_bytes() {
+    let small = MarkdownParser::new("# A\n").parse();
+    let large =
+      MarkdownParser::new("# A\n\nSome text with a [link](https://example.com).\n").parse();
+    let base = ast_bytes(&small);
+    assert!(base >= size_of::<Document>() + 2 * size_of::<Node>());
+    assert!(ast_bytes(&large) > base + "https://example.com".len());
+  }
+
+  #[test]
+  fn test_maxima() {
+    let mut stats = MemoryStats::default();
+    let file = |source_bytes, ast_bytes| FileMemory {
+      source_bytes,
+      ast_bytes,
+    };
+    stats.add_file(Path::new("a.md"), file(100, 900));
+    stats.add_file(Path::new("b.md"), file(300, 700));
+    stats.add_file(Path::new("c.md"), file(50, 200));
+    assert_eq!(stats.total_ast_bytes, 1800);
+    assert_eq!(
+      stats.to_json(),
+      "{\"peak_rss_bytes\":null,\"ast_bytes\":1800,\
+       \"largest_ast\":{\"path\":\"a.md\",\"source_bytes\":100,\"ast_bytes\":900},\
+       \"largest_source\":{\"path\":\"b.md\",\"source_bytes\":300,\"ast_bytes\":700}}"
+    );
+  }
+
+  #[test]
+  fn test_human() {
+    assert_eq!(human(512), "512 B");
+    assert_eq!(human(1536), "1.5 KB");
+    assert_eq!(human(3 * 1024 * 1024), "3.0 MB");
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn test_peak_rss() {
+    assert!(peak_rss().is_some_and(|bytes| bytes > 0));
+  }
+}
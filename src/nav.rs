@@ -0,0 +1,423 @@
+//! Navigation tree (`--nav`): the processed documents arranged by
+//! directory, with their titles and heading outlines, collected into
+//! `nav.json` for site shells to build sidebars from. The translations of
+//! a page (see `locale`) are listed with it rather than as pages of their
+//! own.
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::esc;
+use crate::locale::PageLocale;
+use crate::processor::transform::{frontmatter_vars, is_draft, text_of};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// One document in the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPage {
+  /// Path relative to the input directory
+  pub path: PathBuf,
+  /// Frontmatter `nav_title` or `title`, else the document title or first
+  /// H1, else the file name without its extension
+  pub title: String,
+  /// Frontmatter `order`; ordered entries come first, lowest first
+  pub order: Option<i64>,
+  /// Marked `draft: true` or `publish: false`, kept with `--drafts=include`
+  /// or `only`
+  pub draft: bool,
+  /// Level 2-6 headings outside containers, nested by level
+  pub headings: Vec<NavHeading>,
+  /// Frontmatter `lang` or file name locale, set with `with_locale`
+  pub locale: Option<String>,
+  /// Path shared by the page's translations, its own path without one
+  pub group: PathBuf,
+}
+
+/// One heading of a page's outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavHeading {
+  pub level: u8,
+  pub text: String,
+  pub id: Option<String>,
+  pub children: Vec<NavHeading>,
+}
+
+impl NavPage {
+  pub fn from_document(doc: &Document, path: PathBuf) -> Self {
+    let vars = frontmatter_vars(&doc.nodes);
+    let var = |name: &str| {
+      vars
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+    };
+    let mut headings = Vec::new();
+    collect_headings(&doc.nodes, &mut headings);
+    let title = var("nav_title")
+      .or_else(|| var("title"))
+      .or_else(|| doc.metadata.title.clone())
+      .or_else(|| {
+        headings
+          .iter()
+          .find(|(level, ..)| *level == 1)
+          .map(|(_, text, _)| text.clone())
+      })
+      .unwrap_or_else(|| stem(&path).to_string());
+    Self {
+      order: var("order").and_then(|order| order.parse().ok()),
+      draft: is_draft(&vars),
+      headings: outline(headings.into_iter().filter(|(level, ..)| *level > 1)),
+      title,
+      locale: None,
+      group: path.clone(),
+      path,
+    }
+  }
+
+  /// Place the page among its translations.
+  pub fn with_locale(mut self, locale: PageLocale) -> Self {
+    self.locale = locale.locale;
+    self.group = locale.group;
+    self
+  }
+}
+
+/// (level, text, id) of each heading outside containers; included files
+/// count as part of the page.
+fn collect_headings(nodes: &[Node], out: &mut Vec<(u8, String, Option<String>)>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Heading { level, id, .. } => {
+        let text = text_of(&node.children);
+        out.push((*level, collapse(&text), id.clone()));
+      }
+      NodeKind::Include { .. } => collect_headings(&node.children, out),
+      _ => {}
+    }
+  }
+}
+
+/// Nest headings under the closest preceding heading of a lower level.
+fn outline(headings: impl Iterator<Item = (u8, String, Option<String>)>) -> Vec<NavHeading> {
+  let mut roots: Vec<NavHeading> = Vec::new();
+  for (level, text, id) in headings {
+    let heading = NavHeading {
+      level,
+      text,
+      id,
+      children: Vec::new(),
+    };
+    let mut siblings = &mut roots;
+    while siblings.last().is_some_and(|last| last.level < level) {
+      siblings = &mut siblings.last_mut().unwrap().children;
+    }
+    siblings.push(heading);
+  }
+  roots
+}
+
+/// A directory of the tree; its `index.md` or `README.md` gives it a title,
+/// order and headings and is not listed among its children.
+#[derive(Default)]
+struct Dir<'a> {
+  name: String,
+  index: Option<&'a NavPage>,
+  dirs: Vec<Dir<'a>>,
+  pages: Vec<&'a NavPage>,
+}
+
+impl<'a> Dir<'a> {
+  fn insert(&mut self, page: &'a NavPage) {
+    let dirs: Vec<String> = page
+      .path
+      .parent()
+      .into_iter()
+      .flat_map(Path::components)
+      .map(|c| c.as_os_str().to_string_lossy().into_owned())
+      .collect();
+    let mut dir = self;
+    for name in dirs {
+      let at = match dir.dirs.iter().position(|d| d.name == name) {
+        Some(at) => at,
+        None => {
+          dir.dirs.push(Dir {
+            name,
+            ..Dir::default()
+          });
+          dir.dirs.len() - 1
+        }
+      };
+      dir = &mut dir.dirs[at];
+    }
+    let is_index = matches!(
+      stem(&page.path).to_ascii_lowercase().as_str(),
+      "index" | "readme"
+    );
+    if is_index && dir.index.is_none() {
+      dir.index = Some(page);
+    } else {
+      dir.pages.push(page);
+    }
+  }
+
+  fn order(&self) -> Option<i64> {
+    self.index.and_then(|page| page.order)
+  }
+
+  fn write_json(&self, translations: &Translations, s: &mut String) {
+    let title = self.index.map_or(self.name.as_str(), |page| &page.title);
+    s.push_str(&format!(
+      "{{\"type\":\"dir\",\"name\":\"{}\",\"title\":\"{}\",\"path\":{},\"order\":{},\"headings\":",
+      esc(&self.name),
+      esc(title),
+      self
+        .index
+        .map_or_else(|| "null".to_string(), |page| quoted(&display(&page.path))),
+      json_order(self.order())
+    ));
+    write_headings(self.index.map_or(&[][..], |page| &page.headings), s);
+    if let Some(page) = self.index {
+      write_translations(page, translations, s);
+    }
+    s.push_str(",\"children\":[");
+    let mut items: Vec<(Option<i64>, String, Item)> = self
+      .dirs
+      .iter()
+      .map(|dir| (dir.order(), dir.name.clone(), Item::Dir(dir)))
+      .chain(
+        self
+          .pages
+          .iter()
+          .map(|page| (page.order, display(&page.path), Item::Page(page))),
+      )
+      .collect();
+    items.sort_by(|a, b| {
+      let key = |item: &(Option<i64>, String, Item)| (item.0.is_none(), item.0, item.1.clone());
+      key(a).cmp(&key(b))
+    });
+    for (i, (_, _, item)) in items.iter().enumerate() {
+      if i > 0 {
+        s.push(',');
+      }
+      match item {
+        Item::Dir(dir) => dir.write_json(translations, s),
+        Item::Page(page) => write_page(page, translations, s),
+      }
+    }
+    s.push_str("]}");
+  }
+}
+
+enum Item<'d, 'a> {
+  Dir(&'d Dir<'a>),
+  Page(&'a NavPage),
+}
+
+/// The other translations of each page listed in the tree, by its path.
+type Translations<'a> = HashMap<&'a Path, Vec<&'a NavPage>>;
+
+/// The tree as JSON: the input directory as the root `dir`, holding
+/// `dir` and `page` entries. Of a page's translations, the one without a
+/// locale suffix is listed, else the first, with the rest under its
+/// `translations`.
+pub fn to_json(pages: &[NavPage]) -> String {
+  let mut groups: Vec<Vec<&NavPage>> = Vec::new();
+  let mut index: HashMap<&Path, usize> = HashMap::new();
+  for page in pages {
+    let at = *index.entry(&page.group).or_insert_with(|| {
+      groups.push(Vec::new());
+      groups.len() - 1
+    });
+    groups[at].push(page);
+  }
+  let mut root = Dir::default();
+  let mut translations = Translations::new();
+  for mut group in groups {
+    let listed = group
+      .iter()
+      .position(|page| page.path == page.group)
+      .unwrap_or(0);
+    let page = group.remove(listed);
+    root.insert(page);
+    if !group.is_empty() {
+      group.sort_by(|a, b| a.path.cmp(&b.path));
+      translations.insert(&page.path, group);
+    }
+  }
+  let mut s = String::with_capacity(64 + pages.len() * 256);
+  root.write_json(&translations, &mut s);
+  s
+}
+
+/// `,"locale":...,"translations":[{"locale","title","path"}]` of a listed
+/// page that has translations.
+fn write_translations(page: &NavPage, translations: &Translations, s: &mut String) {
+  let Some(others) = translations.get(page.path.as_path()) else {
+    return;
+  };
+  s.push_str(&format!(
+    ",\"locale\":{},\"translations\":[",
+    json_locale(page)
+  ));
+  for (i, other) in others.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    s.push_str(&format!(
+      "{{\"locale\":{},\"title\":\"{}\",\"path\":\"{}\"}}",
+      json_locale(other),
+      esc(&other.title),
+      esc(&display(&other.path))
+    ));
+  }
+  s.push(']');
+}
+
+fn json_locale(page: &NavPage) -> String {
+  page
+    .locale
+    .as_deref()
+    .map_or_else(|| "null".to_string(), quoted)
+}
+
+fn write_page(page: &NavPage, translations: &Translations, s: &mut String) {
+  s.push_str(&format!(
+    "{{\"type\":\"page\",\"title\":\"{}\",\"path\":\"{}\",\"order\":{},",
+    esc(&page.title),
+    esc(&display(&page.path)),
+    json_order(page.order)
+  ));
+  if page.draft {
+    s.push_str("\"draft\":true,");
+  }
+  s.push_str("\"headings\":");
+  write_headings(&page.headings, s);
+  write_translations(page, translations, s);
+  s.push('}');
+}
+
+fn write_headings(headings: &[NavHeading], s: &mut String) {
+  s.push('[');
+  for (i, heading) in headings.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    s.push_str(&format!(
+      "{{\"level\":{},\"text\":\"{}\",\"id\":{},\"children\":",
+      heading.level,
+      esc(&heading.text),
+      heading
+        .id
+        .as_deref()
+        .map_or_else(|| "null".to_string(), quoted)
+    ));
+    write_headings(&heading.children, s);
+    s.push('}');
+  }
+  s.push(']');
+}
+
+fn json_order(order: Option<i64>) -> String {
+  order.map_or_else(|| "null".to_string(), |n| n.to_string())
+}
+
+fn quoted(text: &str) -> String {
+  format!("\"{}\"", esc(text))
+}
+
+fn collapse(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn stem(path: &Path) -> &str {
+  path.file_stem().and_then(|s| s.to_str()).unwrap_or("")
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+  use crate::processor::transform::{Slugs, Transform};
+
+  fn page(path: &str, source: &str) -> NavPage {
+    let mut doc = MarkdownParser::new(source).parse();
+    Slugs.transform(&mut doc).unwrap();
+    NavPage::from_document(&doc, PathBuf::from(path))
+  }
+
+  #[test]
+  fn test_page() {
+    let page = page(
+      "guide/setup.md",
+      "---\nnav_title: Set up\norder: 2\n---\n# Setup\n\n## Install *it*\n\n### Linux\n\n## Run\n\n> ## Quoted\n",
+    );
+    assert_eq!(page.title, "Set up");
+    assert_eq!(page.order, Some(2));
+    let outline: Vec<(&str, usize)> = page
+      .headings
+      .iter()
+      .map(|h| (h.text.as_str(), h.children.len()))
+      .collect();
+    assert_eq!(outline, [("Install it", 1), ("Run", 0)]);
+    assert_eq!(page.headings[0].id.as_deref(), Some("install-it"));
+    assert_eq!(page.headings[0].children[0].text, "Linux");
+  }
+
+  #[test]
+  fn test_titles() {
+    assert_eq!(page("a.md", "---\ntitle: T\n---\n# H\n").title, "T");
+    assert_eq!(page("a.md", "## Sub\n\n# Main\n").title, "Main");
+    assert_eq!(page("dir/notes.md", "Text\n").title, "notes");
+  }
+
+  #[test]
+  fn test_translations() {
+    let translated = |path: &str, source: &str| {
+      let page = page(path, source);
+      let locale =
+        PageLocale::of_document(&MarkdownParser::new(source).parse(), page.path.clone(), &[]);
+      page.with_locale(locale)
+    };
+    let pages = vec![
+      translated("setup.fr.md", "# Installation\n"),
+      translated("setup.md", "# Setup\n"),
+      translated("setup.de.md", "---\nlang: de\n---\n# Einrichtung\n"),
+      translated("faq.fr.md", "# FAQ\n"),
+    ];
+    assert_eq!(
+      to_json(&pages),
+      "{\"type\":\"dir\",\"name\":\"\",\"title\":\"\",\"path\":null,\"order\":null,\
+       \"headings\":[],\"children\":[\
+       {\"type\":\"page\",\"title\":\"FAQ\",\"path\":\"faq.fr.md\",\"order\":null,\"headings\":[]},\
+       {\"type\":\"page\",\"title\":\"Setup\",\"path\":\"setup.md\",\"order\":null,\"headings\":[],\
+       \"locale\":null,\"translations\":[\
+       {\"locale\":\"de\",\"title\":\"Einrichtung\",\"path\":\"setup.de.md\"},\
+       {\"locale\":\"fr\",\"title\":\"Installation\",\"path\":\"setup.fr.md\"}]}]}"
+    );
+  }
+
+  #[test]
+  fn test_tree() {
+    let pages = vec![
+      page("zeta.md", "# Zeta\n"),
+      page("guide/b.md", "---\ndraft: true\n---\n# B\n"),
+      page("guide/index.md", "---\ntitle: Guide\norder: 1\n---\n"),
+      page("guide/a.md", "---\norder: 5\n---\n# A\n"),
+      page("README.md", "# Home\n"),
+    ];
+    assert_eq!(
+      to_json(&pages),
+      "{\"type\":\"dir\",\"name\":\"\",\"title\":\"Home\",\"path\":\"README.md\",\"order\":null,\
+       \"headings\":[],\"children\":[\
+       {\"type\":\"dir\",\"name\":\"guide\",\"title\":\"Guide\",\"path\":\"guide/index.md\",\
+       \"order\":1,\"headings\":[],\"children\":[\
+       {\"type\":\"page\",\"title\":\"A\",\"path\":\"guide/a.md\",\"order\":5,\"headings\":[]},\
+       {\"type\":\"page\",\"title\":\"B\",\"path\":\"guide/b.md\",\"order\":null,\"draft\":true,\"headings\":[]}]},\
+       {\"type\":\"page\",\"title\":\"Zeta\",\"path\":\"zeta.md\",\"order\":null,\"headings\":[]}]}"
+    );
+  }
+}
@@ -0,0 +1,367 @@
+//! Documentation coverage: declarations found in a source file and whether
+//! each one carries a doc comment.
+//!
+//! Detection is line-based and deliberately conservative: it recognizes the
+//! usual declaration shapes (functions, classes, methods, types) and skips
+//! anything it cannot classify rather than guessing.
+
+use crate::ast::DocumentType;
+
+/// A function, class, method or type found in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+  pub kind: &'static str,
+  pub name: String,
+  pub line: usize,
+  pub documented: bool,
+}
+
+/// Declarations of one source file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+  pub declarations: Vec<Declaration>,
+}
+
+impl Coverage {
+  pub fn total(&self) -> usize {
+    self.declarations.len()
+  }
+
+  pub fn documented(&self) -> usize {
+    self.declarations.iter().filter(|d| d.documented).count()
+  }
+
+  pub fn undocumented(&self) -> impl Iterator<Item = &Declaration> {
+    self.declarations.iter().filter(|d| !d.documented)
+  }
+}
+
+/// Percentage of documented declarations; 100 when there are none.
+pub fn percent(documented: usize, total: usize) -> f64 {
+  if total == 0 {
+    100.0
+  } else {
+    documented as f64 * 100.0 / total as f64
+  }
+}
+
+/// Measure coverage for a source file; `None` for Markdown.
+pub fn measure(content: &str, doc_type: DocumentType) -> Option<Coverage> {
+  let declarations = match doc_type {
+    DocumentType::Markdown => return None,
+    DocumentType::JavaScript | DocumentType::TypeScript => c_style(content, js_declaration, false),
+    DocumentType::Java => c_style(content, java_declaration, true),
+    DocumentType::Python => python(content),
+  };
+  Some(Coverage { declarations })
+}
+
+/// Scan a `/** */` language. A declaration is documented when a doc comment
+/// precedes it, with only blank lines, line comments and annotations between.
+/// With `markdown_docs`, `///` line comments are doc comments too (Java).
+fn c_style(
+  content: &str,
+  classify: fn(&str, bool) -> Option<(&'static str, String)>,
+  markdown_docs: bool,
+) -> Vec<Declaration> {
+  let mut out = Vec::new();
+  let mut pending_doc = false;
+  let mut in_comment: Option<bool> = None;
+
+  for (index, raw) in content.lines().enumerate() {
+    let mut line = raw.trim();
+
+    if let Some(is_doc) = in_comment {
+      let Some(end) = line.find("*/") else {
+        continue;
+      };
+      in_comment = None;
+      pending_doc |= is_doc;
+      line = line[end + 2..].trim();
+    } else if line.starts_with("/*") {
+      let is_doc = line.starts_with("/**") && !line.starts_with("/**/");
+      match line[2..].find("*/") {
+        Some(end) => {
+          pending_doc |= is_doc;
+          line = line[end + 4..].trim();
+        }
+        None => {
+          in_comment = Some(is_doc);
+          continue;
+        }
+      }
+    }
+
+    if markdown_docs && line.starts_with("///") && !line.starts_with("////") {
+      pending_doc = true;
+      continue;
+    }
+    let annotation = line.starts_with('@') && !line.contains('{');
+    if line.is_empty() || line.starts_with("//") || annotation {
+      continue;
+    }
+    let indented = raw.starts_with(char::is_whitespace);
+    if let Some((kind, name)) = classify(line, indented) {
+      out.push(Declaration {
+        kind,
+        name,
+        line: index + 1,
+        documented: pending_doc,
+      });
+    }
+    pending_doc = false;
+  }
+  out
+}
+
+fn js_declaration(line: &str, indented: bool) -> Option<(&'static str, String)> {
+  const MODIFIERS: &[&str] = &[
+    "export",
+    "default",
+    "declare",
+    "async",
+    "abstract",
+    "public",
+    "private",
+    "protected",
+    "static",
+    "readonly",
+    "override",
+    "get",
+    "set",
+  ];
+  let (rest, modifiers) = strip_words(line, MODIFIERS);
+
+  if let Some(after) = rest.strip_prefix("function") {
+    if after.starts_with(|c: char| c.is_whitespace() || c == '*') {
+      let after = after.trim_start_matches('*').trim_start();
+      return ident(after).map(|name| ("function", name));
+    }
+  }
+  for (keyword, kind) in [
+    ("class", "class"),
+    ("interface", "interface"),
+    ("enum", "enum"),
+    ("type", "type"),
+  ] {
+    if let Some(after) = word(rest, keyword) {
+      let name = ident(after)?;
+      let tail = after[name.len()..].trim_start();
+      if kind == "type" && !(tail.starts_with('=') || tail.starts_with('<')) {
+        return None;
+      }
+      return Some((kind, name));
+    }
+  }
+  for keyword in ["const", "let", "var"] {
+    if let Some(after) = word(rest, keyword) {
+      let name = ident(after)?;
+      let value = after[name.len()..].split_once('=')?.1.trim_start();
+      let is_function = value.starts_with("function")
+        || value.starts_with("async")
+        || (value.starts_with('(') || value.starts_with('<')) && value.contains("=>");
+      return is_function.then_some(("function", name));
+    }
+  }
+
+  // Class members: `name(args) {` inside a body.
+  if !(indented || modifiers > 0) || !rest.ends_with('{') || rest.contains("=>") {
+    return None;
+  }
+  let name = ident(rest)?;
+  let tail = &rest[name.len()..];
+  let is_call = tail.starts_with('(') || tail.starts_with('<');
+  (is_call && !is_keyword(&name)).then_some(("method", name))
+}
+
+fn java_declaration(line: &str, _indented: bool) -> Option<(&'static str, String)> {
+  const MODIFIERS: &[&str] = &[
+    "public",
+    "private",
+    "protected",
+    "static",
+    "final",
+    "abstract",
+    "synchronized",
+    "native",
+    "default",
+    "sealed",
+    "non-sealed",
+    "strictfp",
+  ];
+  let (rest, modifiers) = strip_words(line, MODIFIERS);
+
+  for keyword in ["class", "interface", "enum", "record", "@interface"] {
+    if let Some(after) = word(rest, keyword) {
+      let kind = keyword.trim_start_matches('@');
+      return ident(after).map(|name| (kind, name));
+    }
+  }
+
+  let (head, _) = rest.split_once('(')?;
+  let tail = rest.trim_end();
+  // A one-line body, as in `void m() {}` or `int n(int x) { return x; }`
+  let one_line = tail.ends_with('}')
+    && rest.split_once('{').is_some_and(|(sig, _)| {
+      let sig = sig.trim_end();
+      sig.ends_with(')') || sig.contains(" throws ")
+    });
+  let ends_like_signature = one_line
+    || tail.ends_with('{')
+    || tail.ends_with(';')
+    || tail.ends_with(',')
+    || tail.ends_with(')');
+  if head.contains('=') || !ends_like_signature {
+    return None;
+  }
+  let mut words: Vec<&str> = head.split_whitespace().collect();
+  let name = words.pop()?;
+  // A bare `name(...)` is a call unless modifiers made it a constructor.
+  if words.is_empty() && modifiers == 0 {
+    return None;
+  }
+  if words.iter().chain([&name]).any(|w| is_keyword(w)) {
+    return None;
+  }
+  let name = ident(name).filter(|n| n.len() == name.len())?;
+  Some(("method", name))
+}
+
+/// A `def` or `class` is documented when its body starts with a string.
+fn python(content: &str) -> Vec<Declaration> {
+  let lines: Vec<&str> = content.lines().collect();
+  let mut out = Vec::new();
+  let mut in_string: Option<&str> = None;
+  let mut i = 0;
+
+  while i < lines.len() {
+    let line = lines[i].trim();
+    if let Some(delimiter) = in_string {
+      if line.contains(delimiter) {
+        in_string = None;
+      }
+      i += 1;
+      continue;
+    }
+
+    let declaration = line
+      .strip_prefix("async ")
+      .unwrap_or(line)
+      .strip_prefix("def ")
+      .map(|rest| ("function", rest))
+      .or_else(|| line.strip_prefix("class ").map(|rest| ("class", rest)));
+
+    let Some((kind, rest)) = declaration else {
+      in_string = opens_triple_quote(line);
+      i += 1;
+      continue;
+    };
+    let Some(name) = ident(rest) else {
+      i += 1;
+      continue;
+    };
+
+    // Find the `:` that ends the (possibly multi-line) signature.
+    let mut depth = 0i32;
+    let mut body_inline = None;
+    let mut end = i;
+    'signature: while end < lines.len() {
+      for (pos, c) in lines[end].char_indices() {
+        match c {
+          '(' | '[' | '{' => depth += 1,
+          ')' | ']' | '}' => depth -= 1,
+          ':' if depth == 0 && (end > i || pos > lines[i].find(&name).unwrap_or(0)) => {
+            body_inline = Some(lines[end][pos + 1..].trim());
+            break 'signature;
+          }
+          _ => {}
+        }
+      }
+      end += 1;
+    }
+
+    let body = match body_inline {
+      Some(inline) if !inline.is_empty() && !inline.starts_with('#') => Some(inline),
+      _ => lines
+        .iter()
+        .skip(end + 1)
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty() && !l.starts_with('#')),
+    };
+    out.push(Declaration {
+      kind: if kind == "function" && lines[i].starts_with(char::is_whitespace) {
+        "method"
+      } else {
+        kind
+      },
+      name,
+      line: i + 1,
+      documented: body.is_some_and(is_string_start),
+    });
+    i = end + 1;
+  }
+  out
+}
+
+fn is_string_start(line: &str) -> bool {
+  let unprefixed = line.trim_start_matches(['r', 'R', 'u', 'U']);
+  unprefixed.starts_with('"') || unprefixed.starts_with('\'')
+}
+
+fn opens_triple_quote(line: &str) -> Option<&'static str> {
+  ["\"\"\"", "'''"]
+    .into_iter()
+    .find(|d| line.matches(d).count() % 2 == 1)
+}
+
+/// Strip leading modifier words; returns the rest and how many were removed.
+fn strip_words<'a>(mut line: &'a str, words: &[&str]) -> (&'a str, usize) {
+  let mut count = 0;
+  while let Some(rest) = words.iter().find_map(|w| word(line, w)) {
+    line = rest;
+    count += 1;
+  }
+  (line, count)
+}
+
+/// `line` without a leading `keyword` followed by whitespace.
+fn word<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+  let rest = line.strip_prefix(keyword)?;
+  rest
+    .starts_with(char::is_whitespace)
+    .then(|| rest.trim_start())
+}
+
+/// Leading identifier of `s`.
+fn ident(s: &str) -> Option<String> {
+  let end = s
+    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+    .unwrap_or(s.len());
+  let name = &s[..end];
+  (!name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit())).then(|| name.to_string())
+}
+
+fn is_keyword(word: &str) -> bool {
+  matches!(
+    word,
+    "if"
+      | "for"
+      | "while"
+      | "switch"
+      | "catch"
+      | "return"
+      | "function"
+      | "new"
+      | "throw"
+      | "else"
+      | "case"
+      | "do"
+      | "try"
+      | "assert"
+      | "yield"
+      | "await"
+      | "typeof"
+      | "super"
+      | "this"
+  )
+}
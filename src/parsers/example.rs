@@ -0,0 +1,155 @@
+//! Code blocks for `@example` tags and Python Example sections.
+//!
+//! The example text stays in `DocExample.content` as written; its children
+//! get the code as `FencedCodeBlock` nodes tagged with the file's language.
+//! An example that already fences its code keeps those fences and their
+//! languages, with the text around them parsed as Markdown; bare fences
+//! get the file's language. A leading JSDoc `<caption>` becomes a paragraph.
+
+use crate::ast::*;
+use crate::markdown::MarkdownParser;
+
+/// Build a `DocExample` for `content` in a file of type `doc_type`.
+pub fn node(content: &str, doc_type: DocumentType) -> Node {
+  Node::with_children(
+    NodeKind::DocExample {
+      content: content.trim().to_string(),
+    },
+    Span::empty(),
+    code_blocks(content, doc_type),
+  )
+}
+
+/// The caption and code of an example.
+pub fn code_blocks(content: &str, doc_type: DocumentType) -> Vec<Node> {
+  let language = doc_type.name();
+  let mut out = Vec::new();
+  let mut body = content.trim_start_matches(['\n', '\r']);
+  if let Some(rest) = body.trim_start().strip_prefix("<caption>") {
+    if let Some((caption, rest)) = rest.split_once("</caption>") {
+      out.push(Node::with_children(
+        NodeKind::Paragraph,
+        Span::empty(),
+        vec![Node::new(
+          NodeKind::Text {
+            content: caption.trim().to_string(),
+          },
+          Span::empty(),
+        )],
+      ));
+      body = rest;
+    }
+  }
+
+  let body = dedent(body);
+  if body.lines().any(|l| l.trim_start().starts_with("```")) {
+    out.extend(
+      MarkdownParser::new(&body)
+        .parse()
+        .nodes
+        .into_iter()
+        .map(|mut n| {
+          if let NodeKind::FencedCodeBlock {
+            language: lang @ None,
+            ..
+          } = &mut n.kind
+          {
+            *lang = Some(language.to_string());
+          }
+          n
+        }),
+    );
+  } else if !body.is_empty() {
+    out.push(Node::with_children(
+      NodeKind::FencedCodeBlock {
+        language: Some(language.to_string()),
+        info: None,
+        attributes: Vec::new(),
+      },
+      Span::empty(),
+      vec![Node::new(
+        NodeKind::Text {
+          content: format!("{}\n", body),
+        },
+        Span::empty(),
+      )],
+    ));
+  }
+  out
+}
+
+/// Text without its blank edges, trailing spaces and common indentation.
+pub fn dedent(content: &str) -> String {
+  let content = content.trim_matches(|c: char| c == '\n' || c == '\r');
+  let indent = content
+    .lines()
+    .filter(|l| !l.trim().is_empty())
+    .map(|l| l.len() - l.trim_start().len())
+    .min()
+    .unwrap_or(0);
+  content
+    .lines()
+    .map(|l| l.get(indent..).unwrap_or("").trim_end())
+    .collect::<Vec<_>>()
+    .join("\n")
+    .trim_end()
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::formats::to_sexp;
+
+  fn sexp(content: &str, doc_type: DocumentType) -> String {
+    let doc = Document {
+      source_path: String::new(),
+      doc_type,
+      nodes: code_blocks(content, doc_type),
+      metadata: DocumentMetadata {
+        title: None,
+        description: None,
+        glossary: Vec::new(),
+        date: None,
+        updated: None,
+        authors: Vec::new(),
+        total_lines: 0,
+        total_nodes: 0,
+      },
+    };
+    to_sexp(&doc)
+  }
+
+  #[test]
+  fn test_wraps_in_file_language() {
+    assert_eq!(
+      sexp("\n  if (a) {\n    b();\n  }\n", DocumentType::Java),
+      "(document java\n  (fenced-code-block :lang \"java\" (text \"if (a) {\\n  b();\\n}\\n\")))\n"
+    );
+    assert!(code_blocks("  \n", DocumentType::Python).is_empty());
+  }
+
+  #[test]
+  fn test_keeps_explicit_fences() {
+    let out = sexp(
+      "Call it:\n```ts\nf(1)\n```\n```\ng()\n```",
+      DocumentType::JavaScript,
+    );
+    assert!(out.contains("(paragraph (text \"Call it:\"))"), "{}", out);
+    assert!(out.contains(":lang \"ts\" (text \"f(1)\\n\")"), "{}", out);
+    assert!(
+      out.contains(":lang \"javascript\" (text \"g()\\n\")"),
+      "{}",
+      out
+    );
+  }
+
+  #[test]
+  fn test_caption() {
+    assert_eq!(
+      sexp("<caption>Named</caption>\nf();", DocumentType::JavaScript),
+      "(document javascript\n  (paragraph (text \"Named\"))\n  \
+       (fenced-code-block :lang \"javascript\" (text \"f();\\n\")))\n"
+    );
+  }
+}
@@ -0,0 +1,399 @@
+//! JavaDoc HTML, so descriptions get the same block structure as JSDoc and
+//! PyDoc ones. `<p>` and `<br>` break paragraphs and lines, `<pre>` becomes
+//! a fenced code block, and `<code>`, `<b>`, `<i>` their inline Markdown
+//! forms; the result is parsed as Markdown, so Markdown in the text still
+//! applies. `<ul>`/`<ol>` become `List` nodes directly, nested lists
+//! included, with each item's text parsed as Markdown. Other tags are kept
+//! as inline HTML.
+//!
+//! Text outside `<pre>` has its indentation dropped, and inside lists its
+//! line breaks too, so an item stays one item.
+
+use crate::ast::{ListMarker, Node, NodeKind, Span};
+use crate::markdown::html::scan_tag;
+use crate::markdown::MarkdownParser;
+
+/// A run of Markdown or an HTML list.
+#[derive(Debug, PartialEq)]
+pub enum Block {
+  Markdown(String),
+  List(List),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct List {
+  pub ordered: bool,
+  pub items: Vec<Item>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Item {
+  /// Markdown text of the item, on one line
+  pub text: String,
+  pub lists: Vec<List>,
+}
+
+/// Split a JavaDoc description into Markdown and lists.
+pub fn parse(html: &str) -> Vec<Block> {
+  let mut conv = Converter::default();
+  let mut rest = html;
+  while let Some(c) = rest.chars().next() {
+    if c == '<' {
+      if let Some(tag) = scan_tag(rest) {
+        let raw = &rest[..tag.len];
+        rest = &rest[tag.len..];
+        let name = tag.name.to_ascii_lowercase();
+        if !conv.tag(&name, raw.starts_with("</")) {
+          conv.push_str(raw);
+        }
+        continue;
+      }
+    }
+    if c == '&' {
+      if let Some((decoded, len)) = entity(rest) {
+        conv.text(decoded);
+        rest = &rest[len..];
+        continue;
+      }
+    }
+    conv.text(c);
+    rest = &rest[c.len_utf8()..];
+  }
+  conv.finish()
+}
+
+/// The blocks as Markdown text, lists written with `-` or `1.` markers.
+pub fn to_markdown(blocks: &[Block]) -> String {
+  let mut out = String::new();
+  for block in blocks {
+    match block {
+      Block::Markdown(text) => out.push_str(text),
+      Block::List(list) => write_list(&mut out, list, 0),
+    }
+    out.push_str("\n\n");
+  }
+  out.trim_end().to_string()
+}
+
+fn write_list(out: &mut String, list: &List, indent: usize) {
+  for (i, item) in list.items.iter().enumerate() {
+    let marker = if list.ordered {
+      format!("{}. ", i + 1)
+    } else {
+      "- ".to_string()
+    };
+    out.push_str(&" ".repeat(indent));
+    out.push_str(&marker);
+    out.push_str(&item.text);
+    out.push('\n');
+    for sub in &item.lists {
+      write_list(out, sub, indent + marker.len());
+    }
+  }
+  if indent == 0 {
+    out.truncate(out.trim_end().len());
+  }
+}
+
+/// Parse the blocks into nodes: Markdown runs through the Markdown parser,
+/// lists into `List` nodes.
+pub fn to_nodes(blocks: &[Block]) -> Vec<Node> {
+  blocks
+    .iter()
+    .flat_map(|block| match block {
+      Block::Markdown(text) => MarkdownParser::new(text).parse().nodes,
+      Block::List(list) => vec![list_node(list)],
+    })
+    .collect()
+}
+
+fn list_node(list: &List) -> Node {
+  let marker = if list.ordered {
+    ListMarker::Ordered(b'.')
+  } else {
+    ListMarker::Bullet('-')
+  };
+  let items = list
+    .items
+    .iter()
+    .map(|item| {
+      let mut children = MarkdownParser::new(&item.text).parse().nodes;
+      children.extend(item.lists.iter().map(list_node));
+      Node::with_children(
+        NodeKind::ListItem {
+          marker,
+          checked: None,
+        },
+        Span::empty(),
+        children,
+      )
+    })
+    .collect();
+  Node::with_children(
+    NodeKind::List {
+      ordered: list.ordered,
+      start: list.ordered.then_some(1),
+      tight: true,
+    },
+    Span::empty(),
+    items,
+  )
+}
+
+#[derive(Default)]
+struct Converter {
+  blocks: Vec<Block>,
+  /// Markdown since the last list
+  out: String,
+  /// Lists open at this point, innermost last
+  lists: Vec<List>,
+  pre: bool,
+}
+
+impl Converter {
+  /// Apply a known tag; false for tags kept as HTML.
+  fn tag(&mut self, name: &str, closing: bool) -> bool {
+    if self.pre {
+      if name == "pre" && closing {
+        self.close_pre();
+        return true;
+      }
+      return false;
+    }
+    let in_list = !self.lists.is_empty();
+    match (name, closing) {
+      ("p" | "br", _) if in_list => self.space(),
+      ("p", _) => self.block_break(),
+      ("br", _) => self.line_break(),
+      ("ul" | "ol", false) => {
+        if !in_list {
+          self.flush();
+        }
+        self.lists.push(List {
+          ordered: name == "ol",
+          items: Vec::new(),
+        });
+      }
+      ("ul" | "ol", true) if in_list => self.close_list(),
+      ("li", false) if in_list => {
+        if let Some(list) = self.lists.last_mut() {
+          list.items.push(Item::default());
+        }
+      }
+      ("li", true) => {}
+      ("pre", false) if in_list => self.push_str("`"),
+      ("pre", true) if in_list => self.push_str("`"),
+      ("pre", false) => {
+        self.block_break();
+        self.out.push_str("```\n");
+        self.pre = true;
+      }
+      ("code" | "tt", _) => self.push_str("`"),
+      ("b" | "strong", _) => self.push_str("**"),
+      ("i" | "em", _) => self.push_str("*"),
+      _ => return false,
+    }
+    true
+  }
+
+  fn text(&mut self, c: char) {
+    if self.pre {
+      // The line break right after `<pre>` is not part of the code
+      if !(c == '\n' && self.out.ends_with("```\n")) {
+        self.out.push(c);
+      }
+      return;
+    }
+    match c {
+      '\n' if self.lists.is_empty() => {
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+          self.trim_end();
+          self.out.push('\n');
+        }
+      }
+      '\n' | ' ' | '\t' => self.space(),
+      c => self.push_str(c.encode_utf8(&mut [0; 4])),
+    }
+  }
+
+  /// Where text goes: the open list's last item, or the Markdown run.
+  fn target(&mut self) -> &mut String {
+    match self.lists.last_mut() {
+      Some(list) => {
+        if list.items.is_empty() {
+          list.items.push(Item::default());
+        }
+        &mut list.items.last_mut().unwrap().text
+      }
+      None => &mut self.out,
+    }
+  }
+
+  fn push_str(&mut self, s: &str) {
+    self.target().push_str(s);
+  }
+
+  /// A space, unless at the start of a line or after another space.
+  fn space(&mut self) {
+    if self.lists.last().is_some_and(|l| l.items.is_empty()) {
+      return;
+    }
+    let target = self.target();
+    if !target.is_empty() && !target.ends_with([' ', '\n']) {
+      target.push(' ');
+    }
+  }
+
+  fn close_list(&mut self) {
+    let Some(mut list) = self.lists.pop() else {
+      return;
+    };
+    for item in &mut list.items {
+      item.text.truncate(item.text.trim_end().len());
+    }
+    match self.lists.last_mut() {
+      Some(parent) => {
+        if parent.items.is_empty() {
+          parent.items.push(Item::default());
+        }
+        parent.items.last_mut().unwrap().lists.push(list);
+      }
+      None => self.blocks.push(Block::List(list)),
+    }
+  }
+
+  fn close_pre(&mut self) {
+    self.trim_end();
+    if !self.out.ends_with('\n') {
+      self.out.push('\n');
+    }
+    self.out.push_str("```");
+    self.pre = false;
+    self.block_break();
+  }
+
+  /// End the Markdown run, if there is one.
+  fn flush(&mut self) {
+    let text = self.out.trim();
+    if !text.is_empty() {
+      self.blocks.push(Block::Markdown(text.to_string()));
+    }
+    self.out.clear();
+  }
+
+  fn finish(mut self) -> Vec<Block> {
+    if self.pre {
+      self.close_pre();
+    }
+    while !self.lists.is_empty() {
+      self.close_list();
+    }
+    self.flush();
+    self.blocks
+  }
+
+  fn trim_end(&mut self) {
+    let end = self.out.trim_end_matches([' ', '\t']).len();
+    self.out.truncate(end);
+  }
+
+  fn line_break(&mut self) {
+    self.trim_end();
+    if !self.out.is_empty() && !self.out.ends_with('\n') {
+      self.out.push('\n');
+    }
+  }
+
+  fn block_break(&mut self) {
+    self.line_break();
+    if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+      self.out.push('\n');
+    }
+  }
+}
+
+/// Decode a named or numeric character reference at the start of `s`.
+fn entity(s: &str) -> Option<(char, usize)> {
+  let end = s.find(';').filter(|&end| end <= 10)?;
+  let name = &s[1..end];
+  let c = match name {
+    "lt" => '<',
+    "gt" => '>',
+    "amp" => '&',
+    "quot" => '"',
+    "apos" => '\'',
+    "nbsp" => '\u{a0}',
+    _ => {
+      let code = match name.strip_prefix('#')? {
+        hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+        dec => dec.parse().ok()?,
+      };
+      char::from_u32(code)?
+    }
+  };
+  Some((c, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn markdown(html: &str) -> String {
+    to_markdown(&parse(html))
+  }
+
+  #[test]
+  fn test_paragraphs_and_inline() {
+    assert_eq!(
+      markdown("First <b>bold</b> and <code>x</code>.\n<p>Second\n  line.</p>\n\nThird"),
+      "First **bold** and `x`.\n\nSecond\nline.\n\nThird"
+    );
+    assert_eq!(
+      markdown("a &lt;T&gt; &amp; &#65;&#x42; &bogus"),
+      "a <T> & AB &bogus"
+    );
+    assert_eq!(
+      markdown("<table><tr><td>x</td></tr></table>"),
+      "<table><tr><td>x</td></tr></table>"
+    );
+  }
+
+  #[test]
+  fn test_lists() {
+    let html = "Intro:\n<ul>\n  <li>One\n    <i>more</i></li>\n  <li>Two<ol><li>A</li><li>B</li></ol></li>\n</ul>\nAfter";
+    let blocks = parse(html);
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(
+      to_markdown(&blocks),
+      "Intro:\n\n- One *more*\n- Two\n  1. A\n  2. B\n\nAfter"
+    );
+
+    let nodes = to_nodes(&blocks);
+    let NodeKind::List { ordered, .. } = nodes[1].kind else {
+      panic!("expected List, got {:?}", nodes[1].kind);
+    };
+    assert!(!ordered);
+    let two = &nodes[1].children[1];
+    assert!(matches!(two.children[0].kind, NodeKind::Paragraph));
+    assert!(matches!(
+      two.children[1].kind,
+      NodeKind::List {
+        ordered: true,
+        start: Some(1),
+        ..
+      }
+    ));
+    assert_eq!(two.children[1].children.len(), 2);
+  }
+
+  #[test]
+  fn test_pre() {
+    let html = "Use:\n<pre>\nif (a &lt; b) {\n  <b>x</b>();\n}\n</pre>\nDone";
+    assert_eq!(
+      markdown(html),
+      "Use:\n\n```\nif (a < b) {\n  <b>x</b>();\n}\n```\n\nDone"
+    );
+    assert_eq!(markdown("<pre>open"), "```\nopen\n```");
+    assert_eq!(markdown("<ul><li>unclosed"), "- unclosed");
+  }
+}
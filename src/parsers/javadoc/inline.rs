@@ -0,0 +1,258 @@
+//! JavaDoc inline tags: `{@code}`, `{@link}`, `{@linkplain}`, `{@literal}`, `{@value}`.
+//!
+//! Tags are swapped for placeholders before HTML/markdown processing so their
+//! bodies stay verbatim, then spliced back in as `CodeSpan`/`Link`/`Text` nodes.
+
+use crate::ast::{Node, NodeKind, ReferenceType, Span};
+
+const PLACEHOLDER_OPEN: char = '\u{E000}';
+const PLACEHOLDER_CLOSE: char = '\u{E001}';
+
+/// A recognised inline tag.
+pub struct InlineTag {
+  name: &'static str,
+  body: String,
+}
+
+impl InlineTag {
+  /// Plain text used where nodes can't be placed (code blocks, code spans).
+  fn plain_text(&self) -> String {
+    match self.name {
+      "link" | "linkplain" => {
+        let (reference, label) = split_link(&self.body);
+        label.unwrap_or(reference).to_string()
+      }
+      _ => self.body.clone(),
+    }
+  }
+
+  fn to_node(&self) -> Node {
+    let kind = match self.name {
+      "code" | "value" => NodeKind::CodeSpan {
+        content: self.body.clone(),
+      },
+      "literal" => NodeKind::Text {
+        content: self.body.clone(),
+      },
+      _ => return self.link_node(),
+    };
+    Node::new(kind, Span::empty())
+  }
+
+  fn link_node(&self) -> Node {
+    let (reference, label) = split_link(&self.body);
+    let child = match (label, self.name) {
+      (Some(label), _) => NodeKind::Text {
+        content: label.to_string(),
+      },
+      (None, "linkplain") => NodeKind::Text {
+        content: reference.to_string(),
+      },
+      (None, _) => NodeKind::CodeSpan {
+        content: reference.to_string(),
+      },
+    };
+    Node::with_children(
+      NodeKind::Link {
+        url: reference.to_string(),
+        title: None,
+        ref_type: ReferenceType::Full,
+        attributes: Vec::new(),
+      },
+      Span::empty(),
+      vec![Node::new(child, Span::empty())],
+    )
+  }
+}
+
+/// Replace supported inline tags with placeholders.
+///
+/// Unsupported tags (`{@inheritDoc}`, `{@docRoot}`, ...) are left as text.
+pub fn mask(text: &str) -> (String, Vec<InlineTag>) {
+  let mut out = String::with_capacity(text.len());
+  let mut tags = Vec::new();
+  let mut rest = text;
+
+  while let Some(start) = rest.find("{@") {
+    out.push_str(&rest[..start]);
+    let tail = &rest[start..];
+
+    match parse_tag(tail) {
+      Some((tag, consumed)) => {
+        out.push(PLACEHOLDER_OPEN);
+        out.push_str(&tags.len().to_string());
+        out.push(PLACEHOLDER_CLOSE);
+        tags.push(tag);
+        rest = &tail[consumed..];
+      }
+      None => {
+        out.push_str("{@");
+        rest = &tail[2..];
+      }
+    }
+  }
+
+  out.push_str(rest);
+  (out, tags)
+}
+
+/// Render masked text back with each tag in its plain form.
+pub fn unmask_plain(text: &str, tags: &[InlineTag]) -> String {
+  let mut out = String::with_capacity(text.len());
+  for_each_segment(text, |segment| match segment {
+    Segment::Text(t) => out.push_str(t),
+    Segment::Tag(i) => match tags.get(i) {
+      Some(tag) => out.push_str(&tag.plain_text()),
+      None => out.push_str(&format!("{}{}{}", PLACEHOLDER_OPEN, i, PLACEHOLDER_CLOSE)),
+    },
+  });
+  out
+}
+
+/// Splice inline tag nodes back into a parsed markdown tree.
+pub fn restore(nodes: Vec<Node>, tags: &[InlineTag]) -> Vec<Node> {
+  if tags.is_empty() {
+    return nodes;
+  }
+  nodes
+    .into_iter()
+    .flat_map(|node| restore_node(node, tags, false))
+    .collect()
+}
+
+fn restore_node(mut node: Node, tags: &[InlineTag], in_code: bool) -> Vec<Node> {
+  let in_code = in_code || is_code_container(&node.kind);
+
+  match &mut node.kind {
+    NodeKind::Text { content } if content.contains(PLACEHOLDER_OPEN) => {
+      if in_code {
+        *content = unmask_plain(content, tags);
+      } else {
+        return split_text(content, node.span, tags);
+      }
+    }
+    NodeKind::CodeSpan { content } | NodeKind::Code { content } => {
+      *content = unmask_plain(content, tags);
+    }
+    _ => {}
+  }
+
+  node.children = std::mem::take(&mut node.children)
+    .into_iter()
+    .flat_map(|child| restore_node(child, tags, in_code))
+    .collect();
+  vec![node]
+}
+
+fn is_code_container(kind: &NodeKind) -> bool {
+  matches!(
+    kind,
+    NodeKind::CodeBlock { .. }
+      | NodeKind::FencedCodeBlock { .. }
+      | NodeKind::IndentedCodeBlock
+      | NodeKind::CodeBlockExt { .. }
+  )
+}
+
+fn split_text(content: &str, span: Span, tags: &[InlineTag]) -> Vec<Node> {
+  let mut nodes = Vec::new();
+  for_each_segment(content, |segment| match segment {
+    Segment::Text(t) if !t.is_empty() => nodes.push(Node::new(
+      NodeKind::Text {
+        content: t.to_string(),
+      },
+      span,
+    )),
+    Segment::Text(_) => {}
+    Segment::Tag(i) => {
+      if let Some(tag) = tags.get(i) {
+        nodes.push(tag.to_node());
+      }
+    }
+  });
+  nodes
+}
+
+enum Segment<'s> {
+  Text(&'s str),
+  Tag(usize),
+}
+
+fn for_each_segment<'s>(text: &'s str, mut f: impl FnMut(Segment<'s>)) {
+  let mut rest = text;
+  while let Some(open) = rest.find(PLACEHOLDER_OPEN) {
+    let after = &rest[open + PLACEHOLDER_OPEN.len_utf8()..];
+    let Some(close) = after.find(PLACEHOLDER_CLOSE) else {
+      break;
+    };
+    let Ok(index) = after[..close].parse::<usize>() else {
+      break;
+    };
+    f(Segment::Text(&rest[..open]));
+    f(Segment::Tag(index));
+    rest = &after[close + PLACEHOLDER_CLOSE.len_utf8()..];
+  }
+  f(Segment::Text(rest));
+}
+
+/// Parse a tag at the start of `text`, returning it and the bytes consumed.
+fn parse_tag(text: &str) -> Option<(InlineTag, usize)> {
+  let body = &text[2..];
+  let name_len = body
+    .find(|c: char| c.is_whitespace() || c == '}')
+    .unwrap_or(body.len());
+  let name = match &body[..name_len] {
+    "code" => "code",
+    "link" => "link",
+    "linkplain" => "linkplain",
+    "literal" => "literal",
+    "value" => "value",
+    _ => return None,
+  };
+
+  let close = find_closing_brace(body)?;
+  let inner = &body[name_len..close];
+  // One separator space is part of the syntax; anything further is content.
+  let inner = inner
+    .strip_prefix(|c: char| c.is_whitespace())
+    .unwrap_or(inner);
+  let body = match name {
+    // A body spanning lines (`<pre>{@code ...}</pre>`) ends at its last line
+    "code" | "literal" if inner.contains('\n') => inner.trim_end().to_string(),
+    "code" | "literal" => inner.to_string(),
+    _ => inner.trim().to_string(),
+  };
+
+  Some((InlineTag { name, body }, 2 + close + 1))
+}
+
+/// Find the `}` closing the tag, honouring balanced braces in the body.
+fn find_closing_brace(body: &str) -> Option<usize> {
+  let mut depth = 0usize;
+  for (i, b) in body.bytes().enumerate() {
+    match b {
+      b'{' => depth += 1,
+      b'}' if depth == 0 => return Some(i),
+      b'}' => depth -= 1,
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Split `Class#method(int, String) label` into reference and optional label.
+fn split_link(body: &str) -> (&str, Option<&str>) {
+  let mut parens = 0usize;
+  for (i, c) in body.char_indices() {
+    match c {
+      '(' => parens += 1,
+      ')' => parens = parens.saturating_sub(1),
+      c if c.is_whitespace() && parens == 0 => {
+        let label = body[i..].trim();
+        return (&body[..i], (!label.is_empty()).then_some(label));
+      }
+      _ => {}
+    }
+  }
+  (body, None)
+}
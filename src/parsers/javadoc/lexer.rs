@@ -0,0 +1,78 @@
+//! Just enough of a Java lexer to find doc comments: string and character
+//! literals, text blocks and other comments are stepped over whole, so a
+//! `/**` inside them is not taken for a doc comment.
+
+/// End of the literal, text block or non-doc comment starting at `pos`, if
+/// one starts there.
+pub(super) fn token_end(bytes: &[u8], pos: usize) -> Option<usize> {
+  match bytes[pos] {
+    b'"' if bytes[pos..].starts_with(b"\"\"\"") => Some(text_block_end(bytes, pos)),
+    b'"' | b'\'' => Some(literal_end(bytes, pos)),
+    b'/' => match bytes.get(pos + 1) {
+      Some(b'/') => Some(find(bytes, pos, b"\n").unwrap_or(bytes.len())),
+      Some(b'*') if bytes.get(pos + 2) == Some(&b'*') && bytes.get(pos + 3) != Some(&b'*') => None,
+      Some(b'*') => Some(find(bytes, pos + 2, b"*/").map_or(bytes.len(), |end| end + 2)),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// `"..."` or `'c'`; an unterminated literal ends at the line break.
+fn literal_end(bytes: &[u8], pos: usize) -> usize {
+  let quote = bytes[pos];
+  let mut i = pos + 1;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'\n' => return i,
+      b if b == quote => return i + 1,
+      _ => {}
+    }
+    i += 1;
+  }
+  bytes.len()
+}
+
+/// `"""` text block `"""`, where `\"""` does not close it.
+fn text_block_end(bytes: &[u8], pos: usize) -> usize {
+  let mut i = pos + 3;
+  while i < bytes.len() {
+    if bytes[i] == b'\\' {
+      i += 2;
+      continue;
+    }
+    if bytes[i..].starts_with(b"\"\"\"") {
+      return i + 3;
+    }
+    i += 1;
+  }
+  bytes.len()
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+  bytes[from..]
+    .windows(needle.len())
+    .position(|w| w == needle)
+    .map(|i| from + i)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn end(source: &str) -> Option<usize> {
+    token_end(source.as_bytes(), 0)
+  }
+
+  #[test]
+  fn test_token_end() {
+    assert_eq!(end(r#""a\"/**" x"#), Some(8));
+    assert_eq!(end("'\\'' x"), Some(4));
+    assert_eq!(end("\"\"\"\n  /** \\\"\"\" */\n  \"\"\" x"), Some(23));
+    assert_eq!(end("// /** x\ny"), Some(8));
+    assert_eq!(end("/* a */ b"), Some(7));
+    assert_eq!(end("/** doc */"), None);
+    assert_eq!(end("/ 2"), None);
+  }
+}
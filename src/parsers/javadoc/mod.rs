@@ -1,9 +1,16 @@
 //! JavaDoc parser for Java files
+//!
+//! Besides `/** */` comments with HTML, runs of `///` lines (JDK 23) are
+//! doc comments written in Markdown; block tags work the same in both.
 
+mod html;
+mod inline;
+mod lexer;
 mod tags;
 
 use crate::ast::*;
 use crate::markdown::MarkdownParser;
+use crate::parsers::example;
 
 pub struct JavaDocParser<'a> {
   input: &'a str,
@@ -35,6 +42,10 @@ impl<'a> JavaDocParser<'a> {
       metadata: DocumentMetadata {
         title: None,
         description: None,
+        glossary: Vec::new(),
+        date: None,
+        updated: None,
+        authors: Vec::new(),
         total_lines: self.line,
         total_nodes,
       },
@@ -48,6 +59,10 @@ impl<'a> JavaDocParser<'a> {
         if let Some(n) = self.parse_javadoc_comment() {
           nodes.push(n);
         }
+      } else if self.at_markdown_comment() {
+        nodes.push(self.parse_markdown_comment());
+      } else if let Some(end) = lexer::token_end(self.bytes, self.pos) {
+        self.advance_n(end - self.pos);
       } else {
         self.advance();
       }
@@ -63,7 +78,7 @@ impl<'a> JavaDocParser<'a> {
     self.advance_n(3); // Skip /**
 
     let content = self.extract_comment_content()?;
-    let children = self.parse_javadoc_content(&content);
+    let children = self.parse_javadoc_content(&content, false);
 
     Some(Node::with_children(
       NodeKind::DocComment {
@@ -74,6 +89,57 @@ impl<'a> JavaDocParser<'a> {
     ))
   }
 
+  /// Whether a `///` (but not `////`) comment starts a line here.
+  fn at_markdown_comment(&self) -> bool {
+    let line_start = self.bytes[..self.pos]
+      .iter()
+      .rposition(|&b| b == b'\n')
+      .map_or(0, |i| i + 1);
+    self.check_str(b"///")
+      && !self.check_str(b"////")
+      && self.bytes[line_start..self.pos]
+        .iter()
+        .all(|&b| b == b' ' || b == b'\t')
+  }
+
+  /// A run of consecutive `///` lines, parsed as Markdown.
+  fn parse_markdown_comment(&mut self) -> Node {
+    let (start_pos, start_line, start_col) = (self.pos, self.line, self.column);
+    let mut lines = Vec::new();
+    let mut end_pos;
+    loop {
+      self.advance_n(3);
+      let text_start = self.pos;
+      while !self.is_eof() && !self.check(b'\n') {
+        self.advance();
+      }
+      lines.push(&self.input[text_start..self.pos]);
+      end_pos = self.pos;
+
+      let next = self.pos + 1;
+      let indent = self.bytes[next.min(self.bytes.len())..]
+        .iter()
+        .take_while(|&&b| b == b' ' || b == b'\t')
+        .count();
+      let rest = &self.bytes[(next + indent).min(self.bytes.len())..];
+      let continues = rest.starts_with(b"///") && !rest.starts_with(b"////");
+      if !continues {
+        break;
+      }
+      self.advance_n(1 + indent);
+    }
+
+    let content = example::dedent(&lines.join("\n"));
+    let children = self.parse_javadoc_content(&content, true);
+    Node::with_children(
+      NodeKind::DocComment {
+        style: DocStyle::JavaDocMarkdown,
+      },
+      Span::new(start_pos, end_pos, start_line, start_col),
+      children,
+    )
+  }
+
   fn extract_comment_content(&mut self) -> Option<String> {
     let mut content = String::new();
 
@@ -108,43 +174,65 @@ impl<'a> JavaDocParser<'a> {
     }
   }
 
-  fn parse_javadoc_content(&self, content: &str) -> Vec<Node> {
+  /// Description and block tags of a comment; `markdown` for `///`
+  /// comments, whose description is Markdown rather than HTML.
+  fn parse_javadoc_content(&self, content: &str, markdown: bool) -> Vec<Node> {
     let mut nodes = Vec::new();
     let mut description = String::new();
     let mut in_description = true;
+    let mut in_fence = false;
     let lines: Vec<&str> = content.lines().collect();
     let mut i = 0;
 
     while i < lines.len() {
       let line = lines[i].trim();
+      if markdown && (line.starts_with("```") || line.starts_with("~~~")) {
+        in_fence = !in_fence;
+      }
 
-      if line.starts_with('@') {
-        self.flush_description(&mut description, &mut nodes, &mut in_description);
+      if line.starts_with('@') && !in_fence {
+        self.flush_description(&mut description, &mut nodes, &mut in_description, markdown);
         if let Some(n) = tags::parse_tag(line, &lines, &mut i) {
           nodes.push(n);
         }
       } else if in_description {
+        // Keep indentation: it matters inside `<pre>`
         if !description.is_empty() {
           description.push('\n');
         }
-        description.push_str(line);
+        description.push_str(lines[i].trim_end());
       }
 
       i += 1;
     }
 
-    self.flush_description(&mut description, &mut nodes, &mut in_description);
+    self.flush_description(&mut description, &mut nodes, &mut in_description, markdown);
     nodes
   }
 
-  fn flush_description(&self, desc: &mut String, nodes: &mut Vec<Node>, in_desc: &mut bool) {
+  fn flush_description(
+    &self,
+    desc: &mut String,
+    nodes: &mut Vec<Node>,
+    in_desc: &mut bool,
+    markdown: bool,
+  ) {
     if *in_desc && !desc.trim().is_empty() {
-      let content = process_html_content(desc);
-      let desc_nodes = self.parse_markdown_inline(&content);
+      let (masked, inline_tags) = inline::mask(desc);
+      let (content, desc_nodes) = if markdown {
+        let desc_nodes = MarkdownParser::new(masked.trim()).parse().nodes;
+        (
+          inline::unmask_plain(masked.trim(), &inline_tags),
+          desc_nodes,
+        )
+      } else {
+        let blocks = html::parse(&masked);
+        let content = inline::unmask_plain(&html::to_markdown(&blocks), &inline_tags);
+        (content, html::to_nodes(&blocks))
+      };
+      let desc_nodes = inline::restore(desc_nodes, &inline_tags);
       nodes.push(Node::with_children(
-        NodeKind::DocDescription {
-          content: content.clone(),
-        },
+        NodeKind::DocDescription { content },
         Span::empty(),
         desc_nodes,
       ));
@@ -153,10 +241,6 @@ impl<'a> JavaDocParser<'a> {
     *in_desc = false;
   }
 
-  fn parse_markdown_inline(&self, content: &str) -> Vec<Node> {
-    MarkdownParser::new(content).parse().nodes
-  }
-
   #[inline(always)]
   fn is_eof(&self) -> bool {
     self.pos >= self.bytes.len()
@@ -198,34 +282,3 @@ impl<'a> JavaDocParser<'a> {
     }
   }
 }
-
-fn process_html_content(content: &str) -> String {
-  content
-    .replace("<p>", "\n\n")
-    .replace("</p>", "")
-    .replace("<br>", "\n")
-    .replace("<br/>", "\n")
-    .replace("<br />", "\n")
-    .replace("<code>", "`")
-    .replace("</code>", "`")
-    .replace("<pre>", "```\n")
-    .replace("</pre>", "\n```")
-    .replace("<b>", "**")
-    .replace("</b>", "**")
-    .replace("<strong>", "**")
-    .replace("</strong>", "**")
-    .replace("<i>", "*")
-    .replace("</i>", "*")
-    .replace("<em>", "*")
-    .replace("</em>", "*")
-    .replace("<ul>", "")
-    .replace("</ul>", "")
-    .replace("<ol>", "")
-    .replace("</ol>", "")
-    .replace("<li>", "- ")
-    .replace("</li>", "\n")
-    .replace("&lt;", "<")
-    .replace("&gt;", ">")
-    .replace("&amp;", "&")
-    .replace("&quot;", "\"")
-}
@@ -1,12 +1,18 @@
 //! JavaDoc tag parsing.
 
 use crate::ast::*;
+use crate::parsers::example;
 
 pub fn parse_tag(line: &str, lines: &[&str], index: &mut usize) -> Option<Node> {
   let parts: Vec<&str> = line[1..].splitn(2, char::is_whitespace).collect();
   let tag_name = parts[0].to_lowercase();
   let rest = parts.get(1).map(|s| s.trim()).unwrap_or("");
 
+  if tag_name == "example" {
+    let content = collect_block(rest, lines, index);
+    return Some(example::node(&content, DocumentType::Java));
+  }
+
   let content = collect_continuation(rest, lines, index);
 
   match tag_name.as_str() {
@@ -36,6 +42,21 @@ fn collect_continuation(initial: &str, lines: &[&str], index: &mut usize) -> Str
   content
 }
 
+/// Collect lines up to the next tag, keeping line breaks and blank lines.
+fn collect_block(initial: &str, lines: &[&str], index: &mut usize) -> String {
+  let mut content = initial.to_string();
+  while *index + 1 < lines.len() {
+    let next_line = lines[*index + 1].trim_end();
+    if next_line.trim_start().starts_with('@') {
+      break;
+    }
+    content.push('\n');
+    content.push_str(next_line);
+    *index += 1;
+  }
+  content.trim().to_string()
+}
+
 fn parse_param(content: &str) -> Option<Node> {
   let parts: Vec<&str> = content.splitn(2, char::is_whitespace).collect();
   Some(Node::new(
@@ -43,6 +64,7 @@ fn parse_param(content: &str) -> Option<Node> {
       name: parts[0].to_string(),
       param_type: None,
       description: parts.get(1).map(|s| s.trim().to_string()),
+      type_source: None,
     },
     Span::empty(),
   ))
@@ -64,6 +86,7 @@ fn make_return(content: &str) -> Node {
     NodeKind::DocReturn {
       return_type: None,
       description: Some(content.to_string()),
+      type_source: None,
     },
     Span::empty(),
   )
@@ -82,6 +105,7 @@ fn make_deprecated(content: &str) -> Node {
   Node::new(
     NodeKind::DocDeprecated {
       message: non_empty_str(content),
+      version: None,
     },
     Span::empty(),
   )
@@ -0,0 +1,182 @@
+//! Just enough of a JavaScript lexer to find doc comments: strings,
+//! template literals, regex literals and other comments are stepped over
+//! whole, so a `/**` inside them is not taken for a doc comment.
+
+/// End of the string, template literal, regex literal or non-doc comment
+/// starting at `pos`, if one starts there.
+pub(super) fn token_end(bytes: &[u8], pos: usize) -> Option<usize> {
+  match bytes[pos] {
+    b'"' | b'\'' => Some(string_end(bytes, pos)),
+    b'`' => Some(template_end(bytes, pos)),
+    b'/' => match bytes.get(pos + 1) {
+      Some(b'/') => Some(line_end(bytes, pos)),
+      Some(b'*') if bytes.get(pos + 2) == Some(&b'*') && bytes.get(pos + 3) != Some(&b'*') => None,
+      Some(b'*') => Some(find(bytes, pos + 2, b"*/").map_or(bytes.len(), |end| end + 2)),
+      _ if regex_allowed(bytes, pos) => regex_end(bytes, pos),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// `'...'` or `"..."`; an unterminated string ends at the line break.
+fn string_end(bytes: &[u8], pos: usize) -> usize {
+  let quote = bytes[pos];
+  let mut i = pos + 1;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'\n' => return i,
+      b if b == quote => return i + 1,
+      _ => {}
+    }
+    i += 1;
+  }
+  bytes.len()
+}
+
+/// `` `...` `` with any `${expression}`s in it.
+fn template_end(bytes: &[u8], pos: usize) -> usize {
+  let mut i = pos + 1;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'`' => return i + 1,
+      b'$' if bytes.get(i + 1) == Some(&b'{') => {
+        i = expression_end(bytes, i + 2);
+        continue;
+      }
+      _ => {}
+    }
+    i += 1;
+  }
+  bytes.len()
+}
+
+/// Just past the `}` closing a template expression whose code starts at `pos`.
+fn expression_end(bytes: &[u8], pos: usize) -> usize {
+  let mut depth = 0;
+  let mut i = pos;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'"' | b'\'' => {
+        i = string_end(bytes, i);
+        continue;
+      }
+      b'`' => {
+        i = template_end(bytes, i);
+        continue;
+      }
+      b'{' => depth += 1,
+      b'}' if depth == 0 => return i + 1,
+      b'}' => depth -= 1,
+      _ => {}
+    }
+    i += 1;
+  }
+  bytes.len()
+}
+
+/// `/pattern/flags`, or `None` when no closing `/` follows on the line.
+fn regex_end(bytes: &[u8], pos: usize) -> Option<usize> {
+  let mut in_class = false;
+  let mut i = pos + 1;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'\n' => return None,
+      b'[' => in_class = true,
+      b']' => in_class = false,
+      b'/' if !in_class => {
+        let flags = bytes[i + 1..]
+          .iter()
+          .take_while(|b| b.is_ascii_alphabetic())
+          .count();
+        return Some(i + 1 + flags);
+      }
+      _ => {}
+    }
+    i += 1;
+  }
+  None
+}
+
+/// Whether a `/` at `pos` starts a regex rather than dividing: it does at
+/// the start of an expression, i.e. after an operator, an opening bracket
+/// or a keyword such as `return`, and not after a value.
+fn regex_allowed(bytes: &[u8], pos: usize) -> bool {
+  let before = &bytes[..pos];
+  let Some(last) = before.iter().rposition(|b| !b.is_ascii_whitespace()) else {
+    return true;
+  };
+  let b = before[last];
+  if b.is_ascii_alphanumeric() || b == b'_' || b == b'$' {
+    let start = before[..=last]
+      .iter()
+      .rposition(|b| !(b.is_ascii_alphanumeric() || *b == b'_' || *b == b'$'))
+      .map_or(0, |i| i + 1);
+    return matches!(
+      &before[start..=last],
+      b"return"
+        | b"typeof"
+        | b"instanceof"
+        | b"in"
+        | b"of"
+        | b"new"
+        | b"delete"
+        | b"void"
+        | b"throw"
+        | b"case"
+        | b"do"
+        | b"else"
+        | b"yield"
+        | b"await"
+    );
+  }
+  !matches!(b, b')' | b']' | b'"' | b'\'' | b'`')
+}
+
+fn line_end(bytes: &[u8], pos: usize) -> usize {
+  find(bytes, pos, b"\n").unwrap_or(bytes.len())
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+  bytes[from..]
+    .windows(needle.len())
+    .position(|w| w == needle)
+    .map(|i| from + i)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn end(source: &str) -> Option<usize> {
+    token_end(source.as_bytes(), 0)
+  }
+
+  #[test]
+  fn test_token_end() {
+    assert_eq!(end(r#""a\"/**" x"#), Some(8));
+    assert_eq!(end("'a\nb'"), Some(2));
+    assert_eq!(end("`a ${`b ${'}'}`} /** c` x"), Some(23));
+    assert_eq!(end("/[/]\\//g.test(x)"), Some(8));
+    assert_eq!(end("// x /** y\nz"), Some(10));
+    assert_eq!(end("/* a */ b"), Some(7));
+    assert_eq!(end("/** doc */"), None);
+    assert_eq!(end("/ 2"), None);
+  }
+
+  #[test]
+  fn test_regex_allowed() {
+    let at = |s: &str| regex_allowed(s.as_bytes(), s.len());
+    assert!(at(""));
+    assert!(at("x = "));
+    assert!(at("return "));
+    assert!(at("f(a, "));
+    assert!(!at("a "));
+    assert!(!at("f(x) "));
+    assert!(!at("items[0]"));
+    assert!(!at("returned "));
+  }
+}
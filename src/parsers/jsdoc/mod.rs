@@ -1,6 +1,11 @@
 //! JSDoc parser for JavaScript/TypeScript files
+//!
+//! TypeScript sources additionally get TSDoc tags (`@remarks`,
+//! `@typeParam`, release tags, `{@inheritDoc}`) as dedicated nodes.
 
+mod lexer;
 mod tags;
+mod tsdoc;
 
 use crate::ast::*;
 use crate::markdown::MarkdownParser;
@@ -11,6 +16,7 @@ pub struct JsDocParser<'a> {
   pos: usize,
   line: usize,
   column: usize,
+  doc_type: DocumentType,
 }
 
 impl<'a> JsDocParser<'a> {
@@ -21,20 +27,39 @@ impl<'a> JsDocParser<'a> {
       pos: 0,
       line: 1,
       column: 1,
+      doc_type: DocumentType::JavaScript,
+    }
+  }
+
+  /// Create a parser for TypeScript sources with TSDoc tags enabled.
+  pub fn typescript(input: &'a str) -> Self {
+    Self {
+      doc_type: DocumentType::TypeScript,
+      ..Self::new(input)
     }
   }
 
+  /// Whether TSDoc-specific tags should be recognized.
+  #[inline]
+  pub(crate) fn is_typescript(&self) -> bool {
+    self.doc_type == DocumentType::TypeScript
+  }
+
   pub fn parse(&mut self) -> Document {
     let nodes = self.collect_comments();
     let total_nodes: usize = nodes.iter().map(|n| n.count_nodes()).sum();
 
     Document {
       source_path: String::new(),
-      doc_type: DocumentType::JavaScript,
+      doc_type: self.doc_type,
       nodes,
       metadata: DocumentMetadata {
         title: None,
         description: None,
+        glossary: Vec::new(),
+        date: None,
+        updated: None,
+        authors: Vec::new(),
         total_lines: self.line,
         total_nodes,
       },
@@ -48,6 +73,8 @@ impl<'a> JsDocParser<'a> {
         if let Some(n) = self.parse_jsdoc_comment() {
           nodes.push(n);
         }
+      } else if let Some(end) = lexer::token_end(self.bytes, self.pos) {
+        self.advance_n(end - self.pos);
       } else {
         self.advance();
       }
@@ -139,19 +166,32 @@ impl<'a> JsDocParser<'a> {
 
   fn flush_description(&self, desc: &mut String, nodes: &mut Vec<Node>, in_desc: &mut bool) {
     if *in_desc && !desc.trim().is_empty() {
-      let desc_nodes = self.parse_markdown_inline(desc);
-      nodes.push(Node::with_children(
-        NodeKind::DocDescription {
-          content: desc.trim().to_string(),
-        },
-        Span::empty(),
-        desc_nodes,
-      ));
+      let (text, inline_tags) = self.split_inline_tags(desc);
+      if !text.trim().is_empty() {
+        let desc_nodes = self.parse_markdown_inline(&text);
+        nodes.push(Node::with_children(
+          NodeKind::DocDescription {
+            content: text.trim().to_string(),
+          },
+          Span::empty(),
+          desc_nodes,
+        ));
+      }
+      nodes.extend(inline_tags);
       desc.clear();
     }
     *in_desc = false;
   }
 
+  /// Pull TSDoc inline tags out of free text (TypeScript only).
+  pub(crate) fn split_inline_tags(&self, text: &str) -> (String, Vec<Node>) {
+    if self.is_typescript() {
+      tsdoc::extract_inline_tags(text)
+    } else {
+      (text.to_string(), Vec::new())
+    }
+  }
+
   pub(crate) fn parse_markdown_inline(&self, content: &str) -> Vec<Node> {
     MarkdownParser::new(content).parse().nodes
   }
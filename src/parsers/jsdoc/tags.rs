@@ -2,9 +2,10 @@
 
 use super::JsDocParser;
 use crate::ast::*;
+use crate::parsers::example;
 
 pub fn parse_tag(
-  _parser: &JsDocParser,
+  parser: &JsDocParser,
   line: &str,
   lines: &[&str],
   index: &mut usize,
@@ -13,6 +14,17 @@ pub fn parse_tag(
   let tag_name = parts[0].to_lowercase();
   let rest = parts.get(1).map(|s| s.trim()).unwrap_or("");
 
+  if parser.is_typescript() {
+    if let Some(node) = super::tsdoc::parse_tag(parser, &tag_name, rest, lines, index) {
+      return Some(node);
+    }
+  }
+
+  if tag_name == "example" {
+    let content = collect_block(rest, lines, index);
+    return Some(example::node(&content, parser.doc_type));
+  }
+
   let content = collect_continuation(rest, lines, index);
 
   match tag_name.as_str() {
@@ -23,7 +35,6 @@ pub fn parse_tag(
     "typedef" => Some(make_typedef(&content)),
     "callback" => Some(make_callback(&content)),
     "property" | "prop" => parse_property(&content),
-    "example" => Some(make_example(&content)),
     "see" => Some(make_see(&content)),
     "deprecated" => Some(make_deprecated(&content)),
     "since" => Some(make_since(&content)),
@@ -33,7 +44,7 @@ pub fn parse_tag(
   }
 }
 
-fn collect_continuation(initial: &str, lines: &[&str], index: &mut usize) -> String {
+pub(super) fn collect_continuation(initial: &str, lines: &[&str], index: &mut usize) -> String {
   let mut content = initial.to_string();
   while *index + 1 < lines.len() {
     let next_line = lines[*index + 1].trim();
@@ -47,6 +58,21 @@ fn collect_continuation(initial: &str, lines: &[&str], index: &mut usize) -> Str
   content
 }
 
+/// Collect lines up to the next tag, keeping line breaks and blank lines.
+pub(super) fn collect_block(initial: &str, lines: &[&str], index: &mut usize) -> String {
+  let mut content = initial.to_string();
+  while *index + 1 < lines.len() {
+    let next_line = lines[*index + 1].trim_end();
+    if next_line.trim_start().starts_with('@') {
+      break;
+    }
+    content.push('\n');
+    content.push_str(next_line);
+    *index += 1;
+  }
+  content.trim().to_string()
+}
+
 fn parse_param(content: &str) -> Option<Node> {
   let (param_type, rest) = extract_type_prefix(content);
   let parts: Vec<&str> = rest
@@ -56,6 +82,7 @@ fn parse_param(content: &str) -> Option<Node> {
   Some(Node::new(
     NodeKind::DocParam {
       name: parts[0].trim().to_string(),
+      type_source: TypeSource::of_doc(&param_type),
       param_type,
       description: parts
         .get(1)
@@ -69,6 +96,7 @@ fn parse_return(content: &str) -> Option<Node> {
   let (return_type, description) = extract_type_prefix(content);
   Some(Node::new(
     NodeKind::DocReturn {
+      type_source: TypeSource::of_doc(&return_type),
       return_type,
       description: non_empty_str(description),
     },
@@ -132,15 +160,6 @@ fn make_callback(content: &str) -> Node {
   )
 }
 
-fn make_example(content: &str) -> Node {
-  Node::new(
-    NodeKind::DocExample {
-      content: content.to_string(),
-    },
-    Span::empty(),
-  )
-}
-
 fn make_see(content: &str) -> Node {
   Node::new(
     NodeKind::DocSee {
@@ -154,6 +173,7 @@ fn make_deprecated(content: &str) -> Node {
   Node::new(
     NodeKind::DocDeprecated {
       message: non_empty_str(content),
+      version: None,
     },
     Span::empty(),
   )
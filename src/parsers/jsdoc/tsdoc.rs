@@ -0,0 +1,184 @@
+//! TSDoc tag parsing (TypeScript sources only).
+
+use super::JsDocParser;
+use crate::ast::*;
+
+/// Content-less modifier tags, in canonical TSDoc spelling.
+const MODIFIERS: &[&str] = &[
+  "packageDocumentation",
+  "readonly",
+  "override",
+  "sealed",
+  "virtual",
+  "eventProperty",
+];
+
+/// Inline tags lifted out of description text into their own nodes.
+const INLINE_TAGS: &[&str] = &["inheritDoc", "label"];
+
+/// Parse a TSDoc-specific block or modifier tag.
+///
+/// Returns `None` for tags that should fall through to plain JSDoc handling.
+pub fn parse_tag(
+  parser: &JsDocParser,
+  tag_name: &str,
+  rest: &str,
+  lines: &[&str],
+  index: &mut usize,
+) -> Option<Node> {
+  match tag_name {
+    "remarks" => Some(make_remarks(
+      parser,
+      &super::tags::collect_block(rest, lines, index),
+    )),
+    "typeparam" => Some(make_type_param(&super::tags::collect_continuation(
+      rest, lines, index,
+    ))),
+    "defaultvalue" => Some(make_default_value(&super::tags::collect_block(
+      rest, lines, index,
+    ))),
+    _ => make_release_or_modifier(tag_name),
+  }
+}
+
+fn make_remarks(parser: &JsDocParser, content: &str) -> Node {
+  let (text, inline_tags) = extract_inline_tags(content);
+  let mut children = parser.parse_markdown_inline(&text);
+  children.extend(inline_tags);
+  Node::with_children(
+    NodeKind::DocRemarks {
+      content: text.trim().to_string(),
+    },
+    Span::empty(),
+    children,
+  )
+}
+
+fn make_type_param(content: &str) -> Node {
+  let content = content.trim();
+  let (name, description) = match content.split_once(char::is_whitespace) {
+    Some((name, rest)) => (name, rest.trim().trim_start_matches('-').trim()),
+    None => (content, ""),
+  };
+  Node::new(
+    NodeKind::DocTypeParam {
+      name: name.to_string(),
+      description: (!description.is_empty()).then(|| description.to_string()),
+    },
+    Span::empty(),
+  )
+}
+
+fn make_default_value(content: &str) -> Node {
+  Node::new(
+    NodeKind::DocDefaultValue {
+      value: content.to_string(),
+    },
+    Span::empty(),
+  )
+}
+
+fn make_release_or_modifier(tag_name: &str) -> Option<Node> {
+  if let Some(stage) = ReleaseStage::from_tag(tag_name) {
+    return Some(Node::new(NodeKind::DocReleaseTag { stage }, Span::empty()));
+  }
+  MODIFIERS
+    .iter()
+    .find(|m| m.eq_ignore_ascii_case(tag_name))
+    .map(|m| {
+      Node::new(
+        NodeKind::DocModifier {
+          name: m.to_string(),
+        },
+        Span::empty(),
+      )
+    })
+}
+
+/// Remove `{@inheritDoc ...}` / `{@label ...}` from text.
+///
+/// Returns the remaining text, with the blanks around each removed tag
+/// collapsed, and one `DocInlineTag` node per tag found.
+/// Other inline tags such as `{@link}` are left untouched.
+pub fn extract_inline_tags(text: &str) -> (String, Vec<Node>) {
+  let mut out = String::with_capacity(text.len());
+  let mut nodes = Vec::new();
+  let mut rest = text;
+
+  while let Some(start) = rest.find("{@") {
+    out.push_str(&rest[..start]);
+    let tag = &rest[start..];
+
+    match parse_inline_tag(tag) {
+      Some((node, consumed)) => {
+        nodes.push(node);
+        rest = &tag[consumed..];
+        // A tag between words leaves one space, not the two around it
+        if out.ends_with([' ', '\t']) {
+          rest = rest.trim_start_matches([' ', '\t']);
+        }
+      }
+      None => {
+        out.push_str("{@");
+        rest = &tag[2..];
+      }
+    }
+  }
+
+  out.push_str(rest);
+  (out, nodes)
+}
+
+/// Parse one inline tag at the start of `tag`, returning the node and bytes consumed.
+fn parse_inline_tag(tag: &str) -> Option<(Node, usize)> {
+  let body = &tag[2..];
+  let name_len = body
+    .find(|c: char| c.is_whitespace() || c == '}')
+    .unwrap_or(body.len());
+  let name = INLINE_TAGS
+    .iter()
+    .find(|n| n.eq_ignore_ascii_case(&body[..name_len]))?;
+
+  let close = find_closing_brace(body)?;
+  let content = body[name_len..close].trim();
+
+  let node = Node::new(
+    NodeKind::DocInlineTag {
+      name: name.to_string(),
+      content: (!content.is_empty()).then(|| content.to_string()),
+    },
+    Span::empty(),
+  );
+  Some((node, 2 + close + 1))
+}
+
+/// Find the `}` closing an inline tag body, honouring nested braces.
+fn find_closing_brace(body: &str) -> Option<usize> {
+  let mut depth = 0usize;
+  for (i, b) in body.bytes().enumerate() {
+    match b {
+      b'{' => depth += 1,
+      b'}' if depth == 0 => return Some(i),
+      b'}' => depth -= 1,
+      _ => {}
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_inline_tag_spacing() {
+    let text = |input: &str| extract_inline_tags(input).0;
+    assert_eq!(text("a {@label X} b"), "a b");
+    assert_eq!(text("a \t{@label X}\t b"), "a \tb");
+    assert_eq!(text("a{@label X} b"), "a b");
+    assert_eq!(text("a {@label X}b"), "a b");
+    assert_eq!(text("a {@label X}\nb"), "a \nb");
+    // Tags left in the text keep their surroundings
+    assert_eq!(text("a  {@link X}  b"), "a  {@link X}  b");
+  }
+}
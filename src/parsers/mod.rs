@@ -1,17 +1,24 @@
 //! Documentation comment parsers for JSDoc, JavaDoc, and PyDoc
 
+pub mod coverage;
+pub mod example;
 pub mod javadoc;
 pub mod jsdoc;
 pub mod pydoc;
+pub mod reference;
+pub mod registry;
+pub mod sniff;
+pub mod version;
 
 pub use javadoc::JavaDocParser;
 pub use jsdoc::JsDocParser;
 pub use pydoc::PyDocParser;
+pub use registry::{ParseContext, Parser, ParserRegistry};
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::ast::{DocumentType, NodeKind};
+  use crate::ast::{DocStyle, Document, DocumentType, NodeKind, ReleaseStage, TypeSource};
 
   #[test]
   fn test_jsdoc_basic() {
@@ -63,6 +70,21 @@ function second() {}
     assert_eq!(doc.nodes.len(), 1);
   }
 
+  #[test]
+  fn test_jsdoc_skip_strings_and_regexes() {
+    let input = r#"
+const a = "/** not a comment */";
+const b = `docs: ${x ? '/**' : `/** ${y} */`} /** still text */`;
+const c = /\/\*\*/g.test(s), d = total / 2 / count;
+// a line comment with /** inside */
+/** Real. */
+function real() {}
+"#;
+    let doc = JsDocParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 1);
+    assert_eq!(doc.nodes[0].span.line, 6);
+  }
+
   #[test]
   fn test_javadoc_basic() {
     let input = r#"
@@ -94,6 +116,74 @@ public void test() {}
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_javadoc_skip_strings_and_text_blocks() {
+    let input = r#"
+String a = "/** not a comment */";
+char b = '"';
+String c = """
+    /** sample */
+    \""" still inside
+    """;
+// see /** here */
+/** Real. */
+void real() {}
+"#;
+    let doc = JavaDocParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 1);
+    assert_eq!(doc.nodes[0].span.line, 9);
+  }
+
+  #[test]
+  fn test_javadoc_markdown_comment() {
+    let input = r#"
+class A {
+    /// Returns the **sum**, see {@link Math#addExact}.
+    ///
+    /// ```
+    /// @Override int x;
+    /// ```
+    ///
+    /// @param a first operand
+    /// @return the sum
+    int add(int a, int b) { return a + b; } //// not docs
+}
+"#;
+    let doc = JavaDocParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 1);
+    let comment = &doc.nodes[0];
+    assert!(matches!(
+      comment.kind,
+      NodeKind::DocComment {
+        style: DocStyle::JavaDocMarkdown
+      }
+    ));
+    assert_eq!((comment.span.line, comment.span.column), (3, 5));
+    assert_eq!(
+      &input[comment.span.start..comment.span.end],
+      &input[input.find("///").unwrap()..input.find("\n    int add").unwrap()]
+    );
+    let out = crate::formats::to_sexp(&doc);
+    for part in [
+      "(doc-description \"Returns the **sum**, see Math#addExact.\\n\\n```\\n@Override int x;\\n```\"",
+      "(strong (text \"sum\"))",
+      "(link \"Math#addExact\" :ref full (code-span \"Math#addExact\"))",
+      "(fenced-code-block (text \"@Override int x;\\n\"))",
+      "(doc-param \"a\" :description \"first operand\")",
+      "(doc-return :description \"the sum\")",
+    ] {
+      assert!(out.contains(part), "{:?} missing in\n{}", part, out);
+    }
+    let source = "class A {\n    /// Docs.\n    int add(int a, int b) {\n    }\n    //// Not docs.\n    int sub(int a, int b) {\n    }\n}\n";
+    let coverage = coverage::measure(source, DocumentType::Java).unwrap();
+    let documented: Vec<_> = coverage
+      .declarations
+      .iter()
+      .map(|d| (d.name.as_str(), d.documented))
+      .collect();
+    assert_eq!(documented, [("A", false), ("add", true), ("sub", false)]);
+  }
+
   #[test]
   fn test_javadoc_empty() {
     let input = "public class Test {}";
@@ -380,7 +470,49 @@ Description without leading stars
 "#;
     let mut parser = JsDocParser::new(input);
     let doc = parser.parse();
-    assert!(!doc.nodes.is_empty());
+    let examples: Vec<_> = doc.nodes[0]
+      .children
+      .iter()
+      .filter(|n| matches!(n.kind, NodeKind::DocExample { .. }))
+      .collect();
+    assert_eq!(examples.len(), 2);
+    let code = &examples[0].children[0];
+    assert_eq!(
+      code.kind,
+      NodeKind::FencedCodeBlock {
+        language: Some("javascript".to_string()),
+        info: None,
+        attributes: vec![],
+      }
+    );
+    assert_eq!(
+      code.children[0].kind,
+      NodeKind::Text {
+        content: "// This is a comment\nconst x = myFunc({\n  name: \"test\",\n  value: 42\n});\n"
+          .to_string()
+      }
+    );
+    assert!(matches!(examples[1].children[0].kind, NodeKind::Paragraph));
+    assert!(matches!(
+      examples[1].children[1].kind,
+      NodeKind::FencedCodeBlock { .. }
+    ));
+  }
+
+  #[test]
+  fn test_javadoc_example_fence() {
+    let input = "/**\n * @example\n * ```kotlin\n * greet()\n * ```\n */";
+    let doc = JavaDocParser::new(input).parse();
+    let example = &doc.nodes[0].children[0];
+    assert!(matches!(example.kind, NodeKind::DocExample { .. }));
+    assert_eq!(
+      example.children[0].kind,
+      NodeKind::FencedCodeBlock {
+        language: Some("kotlin".to_string()),
+        info: None,
+        attributes: vec![],
+      }
+    );
   }
 
   #[test]
@@ -519,6 +651,115 @@ Description without leading stars
     assert!(!doc.nodes.is_empty());
   }
 
+  // ============================================
+  // TSDOC
+  // ============================================
+
+  #[test]
+  fn test_tsdoc_sets_typescript_type() {
+    let doc = JsDocParser::typescript("/** Hello */").parse();
+    assert_eq!(doc.doc_type, DocumentType::TypeScript);
+  }
+
+  #[test]
+  fn test_tsdoc_block_tags() {
+    let input = r#"
+/**
+ * Summary line.
+ *
+ * @remarks
+ * First paragraph.
+ *
+ * Second paragraph.
+ *
+ * @typeParam T - The element type
+ * @defaultValue `true`
+ */
+"#;
+    let doc = JsDocParser::typescript(input).parse();
+    let children = &doc.nodes[0].children;
+
+    let remarks = children
+      .iter()
+      .find_map(|n| match &n.kind {
+        NodeKind::DocRemarks { content } => Some(content.clone()),
+        _ => None,
+      })
+      .unwrap();
+    assert_eq!(remarks, "First paragraph.\n\nSecond paragraph.");
+
+    assert!(children.iter().any(|n| matches!(
+      &n.kind,
+      NodeKind::DocTypeParam { name, description }
+        if name == "T" && description.as_deref() == Some("The element type")
+    )));
+    assert!(children.iter().any(|n| matches!(
+      &n.kind,
+      NodeKind::DocDefaultValue { value } if value == "`true`"
+    )));
+  }
+
+  #[test]
+  fn test_tsdoc_release_and_modifier_tags() {
+    let input = "/**\n * Entry point.\n * @packageDocumentation\n * @beta\n */";
+    let doc = JsDocParser::typescript(input).parse();
+    let children = &doc.nodes[0].children;
+    assert!(children.iter().any(|n| matches!(
+      &n.kind,
+      NodeKind::DocReleaseTag {
+        stage: ReleaseStage::Beta
+      }
+    )));
+    assert!(children.iter().any(|n| matches!(
+      &n.kind,
+      NodeKind::DocModifier { name } if name == "packageDocumentation"
+    )));
+  }
+
+  #[test]
+  fn test_tsdoc_inline_tags() {
+    let input = "/** {@inheritDoc Base.run} Runs {@label FAST} quickly. */";
+    let doc = JsDocParser::typescript(input).parse();
+    let children = &doc.nodes[0].children;
+
+    let desc = children
+      .iter()
+      .find_map(|n| match &n.kind {
+        NodeKind::DocDescription { content } => Some(content.clone()),
+        _ => None,
+      })
+      .unwrap();
+    assert_eq!(desc, "Runs quickly.");
+
+    let tags: Vec<_> = children
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::DocInlineTag { name, content } => Some((name.clone(), content.clone())),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      tags,
+      vec![
+        ("inheritDoc".to_string(), Some("Base.run".to_string())),
+        ("label".to_string(), Some("FAST".to_string())),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_tsdoc_tags_ignored_for_javascript() {
+    let input = "/**\n * {@inheritDoc Base}\n * @beta\n */";
+    let doc = JsDocParser::new(input).parse();
+    let children = &doc.nodes[0].children;
+    assert!(!children
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::DocReleaseTag { .. })));
+    assert!(children
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::DocTag { name, .. } if name == "beta")));
+  }
+
   // ============================================
   // JAVADOC EDGE CASES
   // ============================================
@@ -625,6 +866,91 @@ Description without leading stars
     assert!(!doc.nodes.is_empty());
   }
 
+  fn flatten(nodes: &[crate::ast::Node]) -> Vec<&crate::ast::Node> {
+    nodes
+      .iter()
+      .flat_map(|n| std::iter::once(n).chain(flatten(&n.children)))
+      .collect()
+  }
+
+  #[test]
+  fn test_javadoc_inline_code_nodes() {
+    let input = "/** Use {@code Map<String, List<Integer>>} or {@literal <T>}. */";
+    let doc = JavaDocParser::new(input).parse();
+    let all = flatten(&doc.nodes);
+
+    assert!(all.iter().any(|n| matches!(
+      &n.kind,
+      NodeKind::CodeSpan { content } if content == "Map<String, List<Integer>>"
+    )));
+    assert!(all
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::Text { content } if content == "<T>")));
+    assert!(!all
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::Text { content } if content.contains("{@"))));
+  }
+
+  #[test]
+  fn test_javadoc_inline_nested_braces() {
+    let input = "/** Returns {@code new int[]{1, 2}} always. */";
+    let doc = JavaDocParser::new(input).parse();
+    assert!(flatten(&doc.nodes).iter().any(|n| matches!(
+      &n.kind,
+      NodeKind::CodeSpan { content } if content == "new int[]{1, 2}"
+    )));
+  }
+
+  #[test]
+  fn test_javadoc_inline_link_nodes() {
+    let input = "/** See {@link Object#equals(Object) equality} and {@linkplain String}. */";
+    let doc = JavaDocParser::new(input).parse();
+    let links: Vec<_> = flatten(&doc.nodes)
+      .into_iter()
+      .filter(|n| matches!(n.kind, NodeKind::Link { .. }))
+      .collect();
+
+    assert_eq!(links.len(), 2);
+    assert!(matches!(
+      &links[0].kind,
+      NodeKind::Link { url, .. } if url == "Object#equals(Object)"
+    ));
+    assert!(matches!(
+      &links[0].children[0].kind,
+      NodeKind::Text { content } if content == "equality"
+    ));
+    assert!(matches!(
+      &links[1].children[0].kind,
+      NodeKind::Text { content } if content == "String"
+    ));
+  }
+
+  #[test]
+  fn test_javadoc_inline_value_and_unknown() {
+    let input = "/** Default is {@value #MAX}. {@inheritDoc} */";
+    let doc = JavaDocParser::new(input).parse();
+    let all = flatten(&doc.nodes);
+    assert!(all
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::CodeSpan { content } if content == "#MAX")));
+    assert!(all
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::Text { content } if content.contains("{@inheritDoc}"))));
+  }
+
+  #[test]
+  fn test_javadoc_inline_code_in_pre_block() {
+    let input = "/**\n * <pre>{@code\n * if (a < b) {}\n * }</pre>\n */";
+    let doc = JavaDocParser::new(input).parse();
+    let all = flatten(&doc.nodes);
+    assert!(all
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::Text { content } if content.contains("if (a < b) {}"))));
+    assert!(!all
+      .iter()
+      .any(|n| matches!(&n.kind, NodeKind::Text { content } if content.contains('\u{E000}'))));
+  }
+
   #[test]
   fn test_javadoc_html_content() {
     let input = r#"
@@ -645,6 +971,35 @@ Description without leading stars
     let mut parser = JavaDocParser::new(input);
     let doc = parser.parse();
     assert!(!doc.nodes.is_empty());
+
+    let description = &doc.nodes[0].children[0].children;
+    let kinds: Vec<&str> = description.iter().map(|n| n.kind.name()).collect();
+    assert_eq!(kinds[..3], ["Paragraph", "List", "FencedCodeBlock"]);
+    assert_eq!(description[1].children.len(), 2);
+    assert_eq!(
+      description[2].children[0].kind,
+      NodeKind::Text {
+        content: "String s = \"example\";\n".to_string()
+      }
+    );
+  }
+
+  #[test]
+  fn test_javadoc_html_decoded_once() {
+    let input = "/**\n * Compares &amp;lt;T&amp;gt; values.\n */\nint compare();\n";
+    let doc = JavaDocParser::new(input).parse();
+    let description = &doc.nodes[0].children[0];
+    let NodeKind::DocDescription { content } = &description.kind else {
+      panic!("expected DocDescription, got {:?}", description.kind);
+    };
+    assert_eq!(content, "Compares &lt;T&gt; values.");
+    let text = &description.children[0].children[0];
+    assert_eq!(
+      text.kind,
+      NodeKind::Text {
+        content: "Compares &lt;T&gt; values.".to_string()
+      }
+    );
   }
 
   #[test]
@@ -789,6 +1144,13 @@ def test():
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_pydoc_unicode_identifiers() {
+    let input = "café = 1\nnaïve = 'é'\n\ndef f():\n    \"\"\"Docs.\"\"\"\n";
+    let doc = PyDocParser::new(input).parse();
+    assert_eq!(doc.nodes.len(), 1);
+  }
+
   #[test]
   fn test_pydoc_single_quotes() {
     let input = r#"
@@ -991,6 +1353,45 @@ def sphinx_func():
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_pydoc_sphinx_directives() {
+    let input = r#"
+def old():
+    """Short description.
+
+    .. note:: Cached per *process*.
+
+    More description.
+
+    :param x: The value
+    .. versionadded:: 1.0
+    .. deprecated:: 3.0
+       Use :func:`new` instead.
+    .. warning::
+
+       Slow.
+    """
+"#;
+    let out = crate::formats::to_sexp(&PyDocParser::new(input).parse());
+    let expected = [
+      "(doc-description \"Short description.\"",
+      "(alert note\n      (paragraph\n        (text \"Cached per \")\n        \
+       (emphasis (text \"process\"))\n        (text \".\")))",
+      "(doc-description \"More description.\"",
+      "(doc-param \"x\" :description \"The value\")",
+      "(doc-since \"1.0\")",
+      "(doc-deprecated :message \"Use :func:`new` instead.\" :version \"3.0\")",
+      "(alert warning\n      (paragraph (text \"Slow.\")))",
+    ];
+    let mut rest = out.as_str();
+    for part in expected {
+      let at = rest
+        .find(part)
+        .unwrap_or_else(|| panic!("{:?} missing or out of order in\n{}", part, out));
+      rest = &rest[at + part.len()..];
+    }
+  }
+
   #[test]
   fn test_pydoc_empty_docstring() {
     let input = r#"
@@ -1074,8 +1475,101 @@ def test():
 "#;
     let mut parser = PyDocParser::new(input);
     let doc = parser.parse();
-    // Raw strings might not be recognized, but shouldn't crash
-    let _ = doc.nodes.len();
+    assert_eq!(doc.nodes.len(), 1);
+    assert_eq!(doc.nodes[0].span.column, 5);
+    assert!(matches!(
+      &doc.nodes[0].children[0].kind,
+      NodeKind::DocDescription { content } if content == "Raw docstring with \\n and \\t."
+    ));
+  }
+
+  #[test]
+  fn test_pydoc_string_prefixes() {
+    let input = r#"
+def a():
+    Rb'''Bytes.'''
+
+def b():
+    f"""Total {", ".join(f'{x}"""' for x in xs)} and {{braces}}."""
+
+def c():
+    """Ends with \"""."""
+
+class D:
+    bR"""x"""
+"#;
+    let doc = PyDocParser::new(input).parse();
+    let descriptions: Vec<&str> = doc
+      .nodes
+      .iter()
+      .map(|n| match &n.children[0].kind {
+        NodeKind::DocDescription { content } => content.as_str(),
+        _ => "",
+      })
+      .collect();
+    assert_eq!(
+      descriptions,
+      vec![
+        "Bytes.",
+        "Total {\", \".join(f'{x}\"\"\"' for x in xs)} and {{braces}}.",
+        "Ends with \\\"\"\".",
+        "x",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_pydoc_only_docstrings() {
+    let input = r#"#!/usr/bin/env python
+# -*- coding: utf-8 -*-
+"""Module."""
+
+QUERY = """not a docstring"""
+note = '"""'  # """ neither
+
+class Point(Base,  # comment
+            metaclass=Meta):
+    # comment
+    """Class."""
+
+    def f(self): """Method."""
+
+    @property
+    async def g(
+        self,
+    ) -> int:  # comment
+        """Async method."""
+        x = 1
+        """Not after the first statement."""
+        if x:
+            """Not a def body."""
+"#;
+    let contents = |doc: Document| -> Vec<String> {
+      doc
+        .nodes
+        .iter()
+        .map(|n| match &n.children[0].kind {
+          NodeKind::DocDescription { content } => content.clone(),
+          _ => String::new(),
+        })
+        .collect()
+    };
+    assert_eq!(
+      contents(PyDocParser::new(input).parse()),
+      vec!["Module.", "Class.", "Method.", "Async method."]
+    );
+    assert_eq!(
+      contents(PyDocParser::new(input).with_all_strings(true).parse()),
+      vec![
+        "Module.",
+        "not a docstring",
+        "Class.",
+        "Method.",
+        "Async method.",
+        "Not after the first statement.",
+        "Not a def body."
+      ]
+    );
   }
 
   #[test]
@@ -1140,6 +1634,50 @@ def test():
     assert!(!doc.nodes.is_empty());
   }
 
+  #[test]
+  fn test_pydoc_signature_type_hints() {
+    let input = r#"
+def scale(x: float, factor: int = 2, *rest: float) -> float:
+    """Scale a value.
+
+    Args:
+        x: The value.
+        factor (float): Overrides the hint.
+        rest: Ignored values.
+
+    Returns:
+        The scaled value.
+    """
+"#;
+    let doc = PyDocParser::new(input).parse();
+    let types: Vec<(Option<&str>, Option<TypeSource>)> = doc.nodes[0]
+      .children
+      .iter()
+      .filter_map(|c| match &c.kind {
+        NodeKind::DocParam {
+          param_type,
+          type_source,
+          ..
+        } => Some((param_type.as_deref(), *type_source)),
+        NodeKind::DocReturn {
+          return_type,
+          type_source,
+          ..
+        } => Some((return_type.as_deref(), *type_source)),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      types,
+      vec![
+        (Some("float"), Some(TypeSource::Annotation)),
+        (Some("float"), Some(TypeSource::Doc)),
+        (Some("float"), Some(TypeSource::Annotation)),
+        (Some("float"), Some(TypeSource::Annotation)),
+      ]
+    );
+  }
+
   #[test]
   fn test_pydoc_indentation_variations() {
     let input = r#"
@@ -1240,6 +1778,256 @@ class Outer:
     assert!(doc.nodes.len() >= 2);
   }
 
+  fn doctests(nodes: &[crate::ast::Node]) -> Vec<(String, Option<String>)> {
+    flatten(nodes)
+      .into_iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::DocDoctest { source, expected } => Some((source.clone(), expected.clone())),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_pydoc_google_example_doctests() {
+    let input = r#"
+def add(a, b):
+    """Add numbers.
+
+    Example:
+        >>> add(1, 2)
+        3
+        >>> for i in range(2):
+        ...     print(i)
+        0
+        1
+        >>> x = add(0, 0)
+    """
+"#;
+    let doc = PyDocParser::new(input).parse();
+    let tests = doctests(&doc.nodes);
+    assert_eq!(tests.len(), 3);
+    assert_eq!(tests[0], ("add(1, 2)".to_string(), Some("3".to_string())));
+    assert_eq!(
+      tests[1],
+      (
+        "for i in range(2):\n    print(i)".to_string(),
+        Some("0\n1".to_string())
+      )
+    );
+    assert_eq!(tests[2], ("x = add(0, 0)".to_string(), None));
+  }
+
+  #[test]
+  fn test_pydoc_numpy_example_doctests() {
+    let input = r#"
+def add(a, b):
+    """Add numbers.
+
+    Examples
+    --------
+    >>> add(2, 2)
+    4
+    """
+"#;
+    let doc = PyDocParser::new(input).parse();
+    let tests = doctests(&doc.nodes);
+    assert_eq!(
+      tests,
+      vec![("add(2, 2)".to_string(), Some("4".to_string()))]
+    );
+  }
+
+  #[test]
+  fn test_pydoc_loose_doctests_become_example() {
+    let input = r#"
+def square(x):
+    """Square a number.
+
+    >>> square(3)
+    9
+
+    Works for floats too.
+    """
+"#;
+    let doc = PyDocParser::new(input).parse();
+    let example = flatten(&doc.nodes).into_iter().find_map(|n| match &n.kind {
+      NodeKind::DocExample { content } => Some(content.clone()),
+      _ => None,
+    });
+    assert_eq!(example.as_deref(), Some(">>> square(3)\n9"));
+    assert_eq!(doctests(&doc.nodes).len(), 1);
+  }
+
+  #[test]
+  fn test_pydoc_doctest_render() {
+    let input = r#"
+def square(x):
+    """Square a number.
+
+    >>> square(3)
+    9
+    """
+"#;
+    let mut doc = PyDocParser::new(input).parse();
+    doc.source_path = "pkg/maths.py".to_string();
+    let text = pydoc::doctest::render(&doc, "maths").unwrap();
+    assert!(text.contains(">>> from maths import *\n"));
+    assert!(text.ends_with("\n>>> square(3)\n9\n"));
+  }
+
+  #[test]
+  fn test_pydoc_doctest_render_none_without_examples() {
+    let doc = PyDocParser::new("def f():\n    \"\"\"Nothing to run.\"\"\"\n").parse();
+    assert!(pydoc::doctest::render(&doc, "f").is_none());
+  }
+
+  // ============================================
+  // DOC COVERAGE
+  // ============================================
+
+  fn coverage_of(input: &str, doc_type: DocumentType) -> Vec<(&'static str, String, bool)> {
+    coverage::measure(input, doc_type)
+      .unwrap()
+      .declarations
+      .into_iter()
+      .map(|d| (d.kind, d.name, d.documented))
+      .collect()
+  }
+
+  #[test]
+  fn test_coverage_js() {
+    let input = r#"
+/** Documented. */
+export function a() {}
+
+function b() {
+  if (x) {
+    call(y);
+  }
+}
+
+/**
+ * A class.
+ */
+@decorated
+class C {
+  /** Method. */
+  run(x) {
+  }
+
+  static async stop() {
+  }
+}
+
+// plain comment
+const d = (x) => x;
+const e = 42;
+/* not a doc comment */
+export type F = string;
+"#;
+    assert_eq!(
+      coverage_of(input, DocumentType::TypeScript),
+      vec![
+        ("function", "a".to_string(), true),
+        ("function", "b".to_string(), false),
+        ("class", "C".to_string(), true),
+        ("method", "run".to_string(), true),
+        ("method", "stop".to_string(), false),
+        ("function", "d".to_string(), false),
+        ("type", "F".to_string(), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_coverage_java() {
+    let input = r#"
+/** A service. */
+public class Service {
+  /** Builds it. */
+  public Service(int x) {
+    this.x = x;
+  }
+
+  @Override
+  public String toString() {
+    return helper(x);
+  }
+
+  /** Abstract op. */
+  abstract void run();
+
+  private static Map<String, Integer> counts(List<String> items,
+      boolean flag) {
+    int n = compute(items);
+    for (String s : items) {
+      System.out.println(s);
+    }
+  }
+
+  /** No-op. */
+  void m() {}
+
+  int n(int x) { return x; }
+
+  public Service copy() throws IOException { return this; }
+
+  synchronized (lock) { notify(); }
+}
+"#;
+    assert_eq!(
+      coverage_of(input, DocumentType::Java),
+      vec![
+        ("class", "Service".to_string(), true),
+        ("method", "Service".to_string(), true),
+        ("method", "toString".to_string(), false),
+        ("method", "run".to_string(), true),
+        ("method", "counts".to_string(), false),
+        ("method", "m".to_string(), true),
+        ("method", "n".to_string(), false),
+        ("method", "copy".to_string(), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_coverage_python() {
+    let input = r#"
+def documented(a,
+               b: int) -> dict[str, int]:
+    """Docs."""
+    return {}
+
+class Thing(Base):
+    # comment first
+    r'''Raw docstring.'''
+
+    async def method(self):
+        pass
+
+def one_liner(): return 1
+
+def text():
+    """
+    def not_a_function():
+    """
+"#;
+    assert_eq!(
+      coverage_of(input, DocumentType::Python),
+      vec![
+        ("function", "documented".to_string(), true),
+        ("class", "Thing".to_string(), true),
+        ("method", "method".to_string(), false),
+        ("function", "one_liner".to_string(), false),
+        ("function", "text".to_string(), true),
+      ]
+    );
+    assert!(coverage::measure("# Title", DocumentType::Markdown).is_none());
+    assert_eq!(coverage::percent(3, 4), 75.0);
+    assert_eq!(coverage::percent(0, 0), 100.0);
+  }
+
   // ============================================
   // CROSS-PARSER EDGE CASES
   // ============================================
@@ -0,0 +1,121 @@
+//! Doctest extraction from `>>>` example blocks.
+
+use crate::ast::{Document, Node, NodeKind, Span};
+
+/// Split `>>>` blocks into `DocDoctest` nodes (source + expected output).
+pub fn parse(content: &str) -> Vec<Node> {
+  let mut tests = Vec::new();
+  let mut lines = content.lines().peekable();
+
+  while let Some(line) = lines.next() {
+    let Some(first) = strip_prompt(line, ">>>") else {
+      continue;
+    };
+    let indent = line.len() - line.trim_start().len();
+
+    let mut source = first.to_string();
+    while let Some(cont) = lines.peek().and_then(|l| strip_prompt(l, "...")) {
+      source.push('\n');
+      source.push_str(cont);
+      lines.next();
+    }
+
+    let mut expected: Vec<&str> = Vec::new();
+    while let Some(next) = lines.peek() {
+      let trimmed = next.trim();
+      if trimmed.is_empty() || trimmed.starts_with(">>>") {
+        break;
+      }
+      expected.push(strip_indent(next, indent));
+      lines.next();
+    }
+
+    tests.push(Node::new(
+      NodeKind::DocDoctest {
+        source,
+        expected: (!expected.is_empty()).then(|| expected.join("\n")),
+      },
+      Span::empty(),
+    ));
+  }
+
+  tests
+}
+
+/// Collect only the lines that belong to doctests, for docstrings
+/// without an explicit Example section.
+pub fn extract_block(content: &str) -> Option<String> {
+  let mut block: Vec<&str> = Vec::new();
+  let mut in_test = false;
+
+  for line in content.lines() {
+    let trimmed = line.trim();
+    if trimmed.starts_with(">>>") {
+      in_test = true;
+    } else if trimmed.is_empty() {
+      if in_test {
+        block.push("");
+      }
+      in_test = false;
+      continue;
+    }
+    if in_test {
+      block.push(line);
+    }
+  }
+
+  let block = block.join("\n");
+  let block = block.trim();
+  (!block.is_empty()).then(|| block.to_string())
+}
+
+/// Render every doctest in the document as a file runnable with
+/// `python -m doctest`, importing `module` first.
+pub fn render(doc: &Document, module: &str) -> Option<String> {
+  let mut tests = Vec::new();
+  collect_tests(&doc.nodes, &mut tests);
+  if tests.is_empty() {
+    return None;
+  }
+
+  let mut out = format!(
+    "Doctests extracted from {} by bukvar.\n\n>>> from {} import *\n",
+    doc.source_path, module
+  );
+  for (source, expected) in tests {
+    out.push('\n');
+    for (i, line) in source.lines().enumerate() {
+      out.push_str(if i == 0 { ">>> " } else { "... " });
+      out.push_str(line);
+      out.push('\n');
+    }
+    for line in expected.iter().flat_map(|e| e.lines()) {
+      out.push_str(line);
+      out.push('\n');
+    }
+  }
+  Some(out)
+}
+
+fn collect_tests<'d>(nodes: &'d [Node], out: &mut Vec<(&'d str, Option<&'d str>)>) {
+  for node in nodes {
+    if let NodeKind::DocDoctest { source, expected } = &node.kind {
+      out.push((source, expected.as_deref()));
+    }
+    collect_tests(&node.children, out);
+  }
+}
+
+fn strip_prompt<'l>(line: &'l str, prompt: &str) -> Option<&'l str> {
+  let rest = line.trim_start().strip_prefix(prompt)?;
+  if rest.is_empty() {
+    Some(rest)
+  } else {
+    rest.strip_prefix(' ')
+  }
+}
+
+fn strip_indent(line: &str, indent: usize) -> &str {
+  let ws = line.len() - line.trim_start().len();
+  &line[ws.min(indent)..]
+}
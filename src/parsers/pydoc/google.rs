@@ -1,7 +1,8 @@
 //! Google-style docstring parser.
 
-use super::{parse_markdown_inline, DocItem};
-use crate::ast::{Node, NodeKind, Span};
+use super::{doctest, parse_markdown_inline, DocItem};
+use crate::ast::{DocumentType, Node, NodeKind, Span, TypeSource};
+use crate::parsers::example;
 
 /// Parse Google-style docstring content.
 pub fn parse(content: &str) -> Vec<Node> {
@@ -87,6 +88,7 @@ fn process_section(section: &str, content: &str) -> Vec<Node> {
         Node::new(
           NodeKind::DocParam {
             name: item.name,
+            type_source: TypeSource::of_doc(&item.item_type),
             param_type: item.item_type,
             description: item.description,
           },
@@ -99,6 +101,7 @@ fn process_section(section: &str, content: &str) -> Vec<Node> {
       let (return_type, desc) = parse_return_content(content);
       vec![Node::new(
         NodeKind::DocReturn {
+          type_source: TypeSource::of_doc(&return_type),
           return_type,
           description: desc,
         },
@@ -119,12 +122,11 @@ fn process_section(section: &str, content: &str) -> Vec<Node> {
       })
       .collect(),
 
-    "example" => vec![Node::new(
-      NodeKind::DocExample {
-        content: content.trim().to_string(),
-      },
-      Span::empty(),
-    )],
+    "example" => {
+      let mut node = example::node(content, DocumentType::Python);
+      node.children.extend(doctest::parse(content));
+      vec![node]
+    }
 
     _ => vec![Node::new(
       NodeKind::DocTag {
@@ -154,7 +156,8 @@ fn parse_items(content: &str) -> Vec<DocItem> {
   let mut items = Vec::new();
   let mut current: Option<DocItem> = None;
 
-  for line in content.lines() {
+  // Items sit one level under the section heading
+  for line in example::dedent(content).lines() {
     let trimmed = line.trim();
     let is_continuation = line.starts_with("    ") || line.starts_with("\t");
 
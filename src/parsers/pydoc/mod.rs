@@ -1,14 +1,22 @@
 //! PyDoc parser for Python files
 //! Supports standard docstrings, Google style, and NumPy style
+//!
+//! Only docstrings are collected: a triple-quoted string that is the first
+//! statement of the module or of a `def` or `class` body. Comments and
+//! other strings are skipped.
 
+pub mod doctest;
 mod google;
 mod item;
 mod numpy;
+mod signature;
 mod sphinx;
 
 use crate::ast::*;
+use crate::parsers::example;
 
 pub use self::item::DocItem;
+use self::signature::Signature;
 
 /// PyDoc parser for extracting documentation from Python source files.
 pub struct PyDocParser<'a> {
@@ -17,6 +25,7 @@ pub struct PyDocParser<'a> {
   pos: usize,
   line: usize,
   column: usize,
+  all_strings: bool,
 }
 
 impl<'a> PyDocParser<'a> {
@@ -27,9 +36,16 @@ impl<'a> PyDocParser<'a> {
       pos: 0,
       line: 1,
       column: 1,
+      all_strings: false,
     }
   }
 
+  /// Collect every triple-quoted string, wherever it appears.
+  pub fn with_all_strings(mut self, enabled: bool) -> Self {
+    self.all_strings = enabled;
+    self
+  }
+
   pub fn parse(&mut self) -> Document {
     let nodes = self.collect_docstrings();
     let total_nodes: usize = nodes.iter().map(Node::count_nodes).sum();
@@ -41,6 +57,10 @@ impl<'a> PyDocParser<'a> {
       metadata: DocumentMetadata {
         title: None,
         description: None,
+        glossary: Vec::new(),
+        date: None,
+        updated: None,
+        authors: Vec::new(),
         total_lines: self.line,
         total_nodes,
       },
@@ -50,38 +70,102 @@ impl<'a> PyDocParser<'a> {
   fn collect_docstrings(&mut self) -> Vec<Node> {
     let mut nodes = Vec::new();
     while !self.is_eof() {
+      let start = self.pos;
       if let Some(node) = self.try_parse_docstring() {
         nodes.push(node);
-      } else {
-        self.advance();
+      } else if self.pos == start {
+        self.skip_token();
       }
     }
     nodes
   }
 
+  /// Step over a comment or a one-line string, or else one character.
+  fn skip_token(&mut self) {
+    match self.bytes[self.pos] {
+      b'#' => {
+        while !self.is_eof() && self.bytes[self.pos] != b'\n' {
+          self.advance();
+        }
+      }
+      quote @ (b'"' | b'\'') => {
+        self.advance();
+        while !self.is_eof() && !matches!(self.bytes[self.pos], b'\n') {
+          let byte = self.bytes[self.pos];
+          self.advance();
+          if byte == b'\\' {
+            self.advance();
+          } else if byte == quote {
+            break;
+          }
+        }
+      }
+      _ => {
+        self.advance();
+        while !self.input.is_char_boundary(self.pos) {
+          self.advance();
+        }
+      }
+    }
+  }
+
   fn try_parse_docstring(&mut self) -> Option<Node> {
-    let delimiter = self.detect_delimiter()?;
-    self.parse_docstring_with_delimiter(delimiter)
+    let prefix = self.detect_prefix();
+    let delimiter = self.detect_delimiter(prefix.len())?;
+    self.parse_docstring_with_delimiter(prefix, delimiter)
   }
 
-  fn detect_delimiter(&self) -> Option<&'static [u8]> {
-    if self.check_str(b"\"\"\"") {
+  /// A string prefix (`r`, `b`, `u`, `f` or a combination such as `rb`)
+  /// at the current position, empty if there is none.
+  fn detect_prefix(&self) -> &'a str {
+    let at_word_start = self.pos == 0 || {
+      let before = self.bytes[self.pos - 1];
+      !(before.is_ascii_alphanumeric() || before == b'_')
+    };
+    if !at_word_start {
+      return "";
+    }
+    let len = self.bytes[self.pos..]
+      .iter()
+      .take_while(|b| b.is_ascii_alphabetic())
+      .count();
+    let prefix = &self.input[self.pos..self.pos + len];
+    match prefix.to_ascii_lowercase().as_str() {
+      "r" | "u" | "b" | "f" | "br" | "rb" | "fr" | "rf" => prefix,
+      _ => "",
+    }
+  }
+
+  fn detect_delimiter(&self, offset: usize) -> Option<&'static [u8]> {
+    let rest = &self.bytes[self.pos + offset..];
+    if rest.starts_with(b"\"\"\"") {
       Some(b"\"\"\"")
-    } else if self.check_str(b"'''") {
+    } else if rest.starts_with(b"'''") {
       Some(b"'''")
     } else {
       None
     }
   }
 
-  fn parse_docstring_with_delimiter(&mut self, delimiter: &[u8]) -> Option<Node> {
+  fn parse_docstring_with_delimiter(&mut self, prefix: &str, delimiter: &[u8]) -> Option<Node> {
     let (start_pos, start_line, start_col) = (self.pos, self.line, self.column);
-    self.advance_n(3);
+    self.advance_n(prefix.len() + 3);
 
-    let content = self.consume_until_delimiter(delimiter)?;
+    let formatted = prefix.contains(['f', 'F']);
+    let content = self.consume_until_delimiter(delimiter, formatted)?;
     self.advance_n(3);
 
-    let (style, children) = self.detect_and_parse_style(&content);
+    let code = code_before(&self.input[..start_pos]);
+    let signature = Signature::preceding(code);
+    let is_docstring = code.is_empty() || signature.is_some() || signature::is_class_header(code);
+    if !is_docstring && !self.all_strings {
+      return None;
+    }
+
+    let (style, mut children) = self.detect_and_parse_style(&content);
+    if let Some(signature) = signature {
+      signature.apply(&mut children);
+    }
     Some(Node::with_children(
       NodeKind::DocComment { style },
       Span::new(start_pos, self.pos, start_line, start_col),
@@ -89,9 +173,20 @@ impl<'a> PyDocParser<'a> {
     ))
   }
 
-  fn consume_until_delimiter(&mut self, delimiter: &[u8]) -> Option<String> {
+  /// Content up to the closing `delimiter`. Escaped characters never
+  /// close the string, and neither does anything inside an f-string's
+  /// `{expression}`.
+  fn consume_until_delimiter(&mut self, delimiter: &[u8], formatted: bool) -> Option<String> {
     let content_start = self.pos;
-    while !self.is_eof() && !self.check_str(delimiter) {
+    let mut depth = 0usize;
+    while !self.is_eof() && (depth > 0 || !self.check_str(delimiter)) {
+      match self.bytes[self.pos] {
+        b'\\' => self.advance(),
+        b'{' if formatted && self.check_str(b"{{") && depth == 0 => self.advance(),
+        b'{' if formatted => depth += 1,
+        b'}' if formatted => depth = depth.saturating_sub(1),
+        _ => {}
+      }
       self.advance();
     }
     if self.is_eof() {
@@ -103,17 +198,20 @@ impl<'a> PyDocParser<'a> {
   fn detect_and_parse_style(&self, content: &str) -> (DocStyle, Vec<Node>) {
     let content = dedent(content);
 
-    if is_google_style(&content) {
-      return (DocStyle::PyDocGoogle, google::parse(&content));
-    }
-    if is_numpy_style(&content) {
-      return (DocStyle::PyDocNumpy, numpy::parse(&content));
-    }
-    if is_sphinx_style(&content) {
-      return (DocStyle::PyDoc, sphinx::parse(&content));
-    }
+    let (style, mut children) = if is_google_style(&content) {
+      (DocStyle::PyDocGoogle, google::parse(&content))
+    } else if is_numpy_style(&content) {
+      (DocStyle::PyDocNumpy, numpy::parse(&content))
+    } else if is_sphinx_style(&content) {
+      (DocStyle::PyDoc, sphinx::parse(&content))
+    } else {
+      (DocStyle::PyDoc, parse_plain_docstring(&content))
+    };
 
-    (DocStyle::PyDoc, parse_plain_docstring(&content))
+    if let Some(example) = loose_doctest_example(&children, &content) {
+      children.push(example);
+    }
+    (style, children)
   }
 
   #[inline(always)]
@@ -147,6 +245,27 @@ impl<'a> PyDocParser<'a> {
   }
 }
 
+/// `source` without trailing blank lines, comment lines and a comment after
+/// a closing `:`.
+fn code_before(source: &str) -> &str {
+  let mut text = source.trim_end();
+  loop {
+    let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..];
+    if line.trim_start().starts_with('#') {
+      text = text[..line_start].trim_end();
+      continue;
+    }
+    if let Some(hash) = line.find('#') {
+      let code = text[..line_start + hash].trim_end();
+      if code.ends_with(':') {
+        return code;
+      }
+    }
+    return text;
+  }
+}
+
 // Style detection functions
 fn is_google_style(content: &str) -> bool {
   const MARKERS: [&str; 6] = [
@@ -170,7 +289,12 @@ fn is_numpy_style(content: &str) -> bool {
 }
 
 fn is_sphinx_style(content: &str) -> bool {
-  content.contains(":param ") || content.contains(":returns:") || content.contains(":raises:")
+  content.contains(":param ")
+    || content.contains(":returns:")
+    || content.contains(":raises:")
+    || content
+      .lines()
+      .any(|l| sphinx::known_directive(l).is_some())
 }
 
 /// Remove common leading indentation from docstring content.
@@ -208,6 +332,20 @@ pub fn dedent(content: &str) -> String {
     .to_string()
 }
 
+/// Build a `DocExample` for `>>>` blocks outside any Example section.
+fn loose_doctest_example(children: &[Node], content: &str) -> Option<Node> {
+  let has_example = children
+    .iter()
+    .any(|n| matches!(n.kind, NodeKind::DocExample { .. }));
+  if has_example {
+    return None;
+  }
+  let block = doctest::extract_block(content)?;
+  let mut node = example::node(&block, DocumentType::Python);
+  node.children.extend(doctest::parse(&block));
+  Some(node)
+}
+
 fn parse_plain_docstring(content: &str) -> Vec<Node> {
   use crate::markdown::MarkdownParser;
   let mut parser = MarkdownParser::new(content);
@@ -1,7 +1,8 @@
 //! NumPy-style docstring parser.
 
-use super::{parse_markdown_inline, DocItem};
-use crate::ast::{Node, NodeKind, Span};
+use super::{doctest, parse_markdown_inline, DocItem};
+use crate::ast::{DocumentType, Node, NodeKind, Span, TypeSource};
+use crate::parsers::example;
 
 /// Parse NumPy-style docstring content.
 pub fn parse(content: &str) -> Vec<Node> {
@@ -99,6 +100,7 @@ fn process_section(section: &str, content: &str) -> Vec<Node> {
         Node::new(
           NodeKind::DocParam {
             name: item.name,
+            type_source: TypeSource::of_doc(&item.item_type),
             param_type: item.item_type,
             description: item.description,
           },
@@ -113,6 +115,7 @@ fn process_section(section: &str, content: &str) -> Vec<Node> {
       .map(|item| {
         Node::new(
           NodeKind::DocReturn {
+            type_source: TypeSource::of_doc(&item.item_type),
             return_type: item.item_type,
             description: item.description,
           },
@@ -134,12 +137,11 @@ fn process_section(section: &str, content: &str) -> Vec<Node> {
       })
       .collect(),
 
-    "example" => vec![Node::new(
-      NodeKind::DocExample {
-        content: content.trim().to_string(),
-      },
-      Span::empty(),
-    )],
+    "example" => {
+      let mut node = example::node(content, DocumentType::Python);
+      node.children.extend(doctest::parse(content));
+      vec![node]
+    }
 
     "see_also" => content
       .lines()
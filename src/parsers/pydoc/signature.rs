@@ -0,0 +1,244 @@
+//! Type hints from the `def` a docstring belongs to.
+//!
+//! A docstring that directly follows `def name(a: int, *args: str) -> bool:`
+//! gets those annotations for the parameters and return value it documents
+//! without a type. Types the docstring gives always win.
+
+use crate::ast::{Node, NodeKind, TypeSource};
+
+/// Annotations of one function signature.
+#[derive(Debug, Default, PartialEq)]
+pub struct Signature {
+  /// Annotated parameters, names without `*`/`**`
+  pub params: Vec<(String, String)>,
+  /// The `->` annotation
+  pub returns: Option<String>,
+}
+
+impl Signature {
+  /// The signature `source` ends with, if its last statement before the
+  /// docstring is a `def` header.
+  pub fn preceding(source: &str) -> Option<Self> {
+    let source = source.trim_end().strip_suffix(':')?;
+    let lines: Vec<&str> = source.lines().collect();
+    let start = lines.iter().rposition(|l| {
+      let l = l.trim_start();
+      l.starts_with("def ") || l.starts_with("async def ")
+    })?;
+    let header: String = lines[start..]
+      .iter()
+      .map(|l| strip_comment(l))
+      .collect::<Vec<_>>()
+      .join(" ");
+    parse_header(&header)
+  }
+
+  /// Fill in untyped `DocParam`s and `DocReturn`s of a docstring.
+  pub fn apply(&self, children: &mut [Node]) {
+    let typed_return = children.iter().any(|c| {
+      matches!(
+        &c.kind,
+        NodeKind::DocReturn {
+          return_type: Some(_),
+          ..
+        }
+      )
+    });
+    for child in children {
+      match &mut child.kind {
+        NodeKind::DocParam {
+          name,
+          param_type: param_type @ None,
+          type_source,
+          ..
+        } => {
+          let name = name.trim_start_matches('*');
+          if let Some((_, hint)) = self.params.iter().find(|(n, _)| n == name) {
+            *param_type = Some(hint.clone());
+            *type_source = Some(TypeSource::Annotation);
+          }
+        }
+        NodeKind::DocReturn {
+          return_type: return_type @ None,
+          type_source,
+          ..
+        } if !typed_return => {
+          if let Some(hint) = &self.returns {
+            *return_type = Some(hint.clone());
+            *type_source = Some(TypeSource::Annotation);
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Whether `source` ends with a `class Name(Bases):` header.
+pub fn is_class_header(source: &str) -> bool {
+  let Some(source) = source.trim_end().strip_suffix(':') else {
+    return false;
+  };
+  let start = source
+    .lines()
+    .rev()
+    .position(|l| l.trim_start().starts_with("class "));
+  let Some(start) = start else {
+    return false;
+  };
+  let lines: Vec<&str> = source.lines().collect();
+  let header: String = lines[lines.len() - 1 - start..]
+    .iter()
+    .map(|l| strip_comment(l))
+    .collect::<Vec<_>>()
+    .join(" ");
+  let rest = header.trim().trim_start_matches("class ").trim_start();
+  let name_len = rest
+    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+    .unwrap_or(rest.len());
+  if name_len == 0 {
+    return false;
+  }
+  // Type parameters and bases: `[T]`, `(Base)`
+  let mut rest = rest[name_len..].trim_start();
+  while !rest.is_empty() {
+    if !rest.starts_with(['(', '[']) {
+      return false;
+    }
+    match matching_paren(rest) {
+      Some(close) => rest = rest[close + 1..].trim_start(),
+      None => return false,
+    }
+  }
+  true
+}
+
+/// `def name(params) -> ret` with the trailing colon already removed.
+fn parse_header(header: &str) -> Option<Signature> {
+  let header = header.trim();
+  let header = header.strip_prefix("async").map_or(header, str::trim_start);
+  let rest = header.strip_prefix("def ")?;
+  let open = rest.find('(')?;
+  let close = open + matching_paren(&rest[open..])?;
+  let tail = rest[close + 1..].trim();
+  let returns = if tail.is_empty() {
+    None
+  } else {
+    let hint = tail.strip_prefix("->")?;
+    // A `:` here ends the header, so more statements follow it
+    if split_top_level(hint, ':').len() > 1 {
+      return None;
+    }
+    Some(normalize(hint))
+  };
+
+  let params = split_top_level(&rest[open + 1..close], ',')
+    .into_iter()
+    .filter_map(|param| {
+      let param = split_top_level(param, '=')[0];
+      let (name, hint) = param.split_once(':')?;
+      let name = name.trim().trim_start_matches('*');
+      let hint = normalize(hint);
+      (!name.is_empty() && !hint.is_empty()).then(|| (name.to_string(), hint))
+    })
+    .collect();
+  Some(Signature { params, returns })
+}
+
+/// Offset of the `)` closing the `(` that `text` starts with.
+fn matching_paren(text: &str) -> Option<usize> {
+  let mut depth = 0;
+  let mut quote = None;
+  for (i, c) in text.char_indices() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), _) => {}
+      (None, '"' | '\'') => quote = Some(c),
+      (None, '(' | '[' | '{') => depth += 1,
+      (None, ')' | ']' | '}') => {
+        depth -= 1;
+        if depth == 0 {
+          return Some(i);
+        }
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Split at `sep` outside brackets and string literals.
+fn split_top_level(text: &str, sep: char) -> Vec<&str> {
+  let mut parts = Vec::new();
+  let (mut depth, mut quote, mut start) = (0i32, None, 0);
+  for (i, c) in text.char_indices() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), _) => {}
+      (None, '"' | '\'') => quote = Some(c),
+      (None, '(' | '[' | '{') => depth += 1,
+      (None, ')' | ']' | '}') => depth -= 1,
+      (None, c) if c == sep && depth == 0 => {
+        parts.push(&text[start..i]);
+        start = i + c.len_utf8();
+      }
+      _ => {}
+    }
+  }
+  parts.push(&text[start..]);
+  parts
+}
+
+fn strip_comment(line: &str) -> &str {
+  line.split_once('#').map_or(line, |(code, _)| code)
+}
+
+/// An annotation on one line with single spaces.
+fn normalize(hint: &str) -> String {
+  hint.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hints(params: &[(&str, &str)]) -> Vec<(String, String)> {
+    params
+      .iter()
+      .map(|(n, t)| (n.to_string(), t.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn test_preceding() {
+    let sig = Signature::preceding(
+      "import os\n\nasync def fetch(\n    self,\n    url: str,  # where\n    *args: int,\n    \
+       opts: dict[str, int] = {\"a\": 1},\n    /,\n    **kw: Any,\n) -> tuple[int,\n    str]:\n    ",
+    )
+    .unwrap();
+    assert_eq!(
+      sig.params,
+      hints(&[
+        ("url", "str"),
+        ("args", "int"),
+        ("opts", "dict[str, int]"),
+        ("kw", "Any")
+      ])
+    );
+    assert_eq!(sig.returns.as_deref(), Some("tuple[int, str]"));
+
+    assert_eq!(
+      Signature::preceding("def f(x): "),
+      Some(Signature::default())
+    );
+    assert_eq!(Signature::preceding("class A:\n    "), None);
+    assert_eq!(Signature::preceding("def f(x):\n    x = 1\n    "), None);
+    assert_eq!(
+      Signature::preceding("def f(x) -> int:\n    if x:\n    "),
+      None
+    );
+    assert!(is_class_header("class A[T](B,\n        C):  "));
+    assert!(!is_class_header("class A:\n    if x:"));
+    assert_eq!(Signature::preceding("x = "), None);
+  }
+}
@@ -1,7 +1,15 @@
 //! Sphinx/reST-style docstring parser.
+//!
+//! Field lists (`:param x:`) become tags. The admonitions `note`, `tip`,
+//! `hint`, `important`, `warning`, `attention`, `caution` and `danger`
+//! become `Alert`s, `.. deprecated:: 1.2` a `DocDeprecated` and
+//! `.. versionadded:: 1.2` a `DocSince`; their indented bodies are parsed
+//! as Markdown. Other directives stay description text.
 
 use super::parse_markdown_inline;
-use crate::ast::{Node, NodeKind, Span};
+use crate::ast::{AlertType, Node, NodeKind, Span, TypeSource};
+use crate::markdown::MarkdownParser;
+use crate::parsers::example;
 
 /// Parse Sphinx/reST-style docstring content.
 pub fn parse(content: &str) -> Vec<Node> {
@@ -14,7 +22,14 @@ pub fn parse(content: &str) -> Vec<Node> {
   while i < lines.len() {
     let line = lines[i].trim();
 
-    if line.starts_with(':') {
+    if let Some((name, arg)) = known_directive(line) {
+      if !description.trim().is_empty() {
+        nodes.push(make_description_node(&description));
+        description.clear();
+      }
+      let body = collect_body(&lines, &mut i);
+      nodes.push(create_directive_node(name, arg, &body));
+    } else if line.starts_with(':') {
       if in_description && !description.trim().is_empty() {
         nodes.push(make_description_node(&description));
         description.clear();
@@ -90,7 +105,93 @@ fn collect_content(initial: &str, lines: &[&str], index: &mut usize) -> String {
 
 fn is_content_continuation(line: &str) -> bool {
   let trimmed = line.trim();
-  !trimmed.is_empty() && !trimmed.starts_with(':')
+  !trimmed.is_empty() && !trimmed.starts_with(':') && known_directive(trimmed).is_none()
+}
+
+const DIRECTIVES: &[&str] = &[
+  "note",
+  "tip",
+  "hint",
+  "important",
+  "warning",
+  "attention",
+  "caution",
+  "danger",
+  "deprecated",
+  "versionadded",
+];
+
+/// Name and argument of a `.. name:: argument` line this parser converts.
+pub(super) fn known_directive(line: &str) -> Option<(&str, &str)> {
+  let (name, arg) = line.trim().strip_prefix("..")?.split_once("::")?;
+  let name = name.trim();
+  DIRECTIVES.contains(&name).then(|| (name, arg.trim()))
+}
+
+/// The lines indented under the directive at `lines[*index]`, dedented.
+fn collect_body(lines: &[&str], index: &mut usize) -> String {
+  let indent = |l: &str| l.len() - l.trim_start().len();
+  let own = indent(lines[*index]);
+  let mut end = *index + 1;
+  while end < lines.len() && (lines[end].trim().is_empty() || indent(lines[end]) > own) {
+    end += 1;
+  }
+  // Keep the blank lines after the body out of it
+  while end > *index + 1 && lines[end - 1].trim().is_empty() {
+    end -= 1;
+  }
+  let body = example::dedent(&lines[*index + 1..end].join("\n"));
+  *index = end - 1;
+  body
+}
+
+fn create_directive_node(name: &str, arg: &str, body: &str) -> Node {
+  let alert_type = match name {
+    "note" => AlertType::Note,
+    "tip" | "hint" => AlertType::Tip,
+    "important" => AlertType::Important,
+    "warning" | "attention" => AlertType::Warning,
+    "deprecated" => {
+      return Node::new(
+        NodeKind::DocDeprecated {
+          message: (!body.is_empty()).then(|| unwrap(body)),
+          version: (!arg.is_empty()).then(|| arg.to_string()),
+        },
+        Span::empty(),
+      );
+    }
+    "versionadded" => {
+      return Node::with_children(
+        NodeKind::DocSince {
+          version: arg.to_string(),
+        },
+        Span::empty(),
+        blocks(body),
+      );
+    }
+    _ => AlertType::Caution,
+  };
+  // `.. note:: text` starts the body on the directive line
+  let body = if arg.is_empty() {
+    body.to_string()
+  } else if body.is_empty() {
+    arg.to_string()
+  } else {
+    format!("{}\n{}", arg, body)
+  };
+  Node::with_children(NodeKind::Alert { alert_type }, Span::empty(), blocks(&body))
+}
+
+fn blocks(body: &str) -> Vec<Node> {
+  if body.trim().is_empty() {
+    return Vec::new();
+  }
+  MarkdownParser::new(body).parse().nodes
+}
+
+/// Body text on one line.
+fn unwrap(body: &str) -> String {
+  body.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 fn create_node(name: &str, arg: Option<String>, content: String) -> Option<Node> {
@@ -100,6 +201,7 @@ fn create_node(name: &str, arg: Option<String>, content: String) -> Option<Node>
         name: arg.unwrap_or_default(),
         param_type: None,
         description: Some(content),
+        type_source: None,
       },
       Span::empty(),
     ),
@@ -110,6 +212,7 @@ fn create_node(name: &str, arg: Option<String>, content: String) -> Option<Node>
       NodeKind::DocReturn {
         return_type: None,
         description: Some(content),
+        type_source: None,
       },
       Span::empty(),
     ),
@@ -118,6 +221,7 @@ fn create_node(name: &str, arg: Option<String>, content: String) -> Option<Node>
       NodeKind::DocReturn {
         return_type: Some(content),
         description: None,
+        type_source: Some(TypeSource::Doc),
       },
       Span::empty(),
     ),
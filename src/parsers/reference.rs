@@ -0,0 +1,507 @@
+//! API reference projection (`--api-reference`): a source file's doc
+//! comments as a Markdown document, so reference pages render like any
+//! other Markdown.
+//!
+//! ```text
+//! # `Greeter.java`
+//! ## class `Greeter`
+//! description
+//! ### Parameters        (a definition list: `name` (type) → description)
+//! ### Returns / Throws / Properties / Type parameters
+//! ### Examples          (fenced code in the file's language)
+//! ### See also
+//! ```
+//!
+//! Each doc comment is matched to the declaration the coverage scan found
+//! right after it (before it, for Python docstrings). Comments without one,
+//! such as a module docstring or a file header, keep their place in the
+//! source, under the section before them. Section headings keep the doc comment's span so they can link
+//! back to the source; copied description nodes get empty spans, since
+//! their positions were relative to the comment text.
+
+use super::coverage::{Coverage, Declaration};
+use crate::ast::*;
+use crate::markdown::MarkdownParser;
+
+/// A definition-list term and its description.
+type Entry<'a> = (Vec<Node>, Option<&'a String>);
+
+/// Build the reference document for a parsed source file. `coverage` is the
+/// file's declaration scan; without it every comment is unattached.
+pub fn project(doc: &Document, coverage: Option<&Coverage>) -> Document {
+  let declarations = coverage.map_or(&[][..], |c| &c.declarations[..]);
+  let comments: Vec<&Node> = doc
+    .nodes
+    .iter()
+    .filter(|n| matches!(n.kind, NodeKind::DocComment { .. }))
+    .collect();
+  let symbols = match_symbols(&comments, declarations, doc.doc_type);
+
+  let mut nodes = Vec::new();
+  if let Some(name) = doc.source_path.rsplit('/').next().filter(|n| !n.is_empty()) {
+    nodes.push(heading(1, vec![code(name)], Span::empty()));
+  }
+  for (comment, symbol) in comments.iter().zip(&symbols) {
+    let title = match symbol {
+      Some(decl) => Some(vec![text(&format!("{} ", decl.kind)), code(&decl.name)]),
+      None => symbol_name(comment),
+    };
+    if let Some(title) = title {
+      nodes.push(heading(2, title, comment.span));
+    }
+    nodes.extend(body(comment));
+  }
+
+  let total_nodes = nodes.iter().map(|n| n.count_nodes()).sum();
+  Document {
+    source_path: doc.source_path.clone(),
+    doc_type: DocumentType::Markdown,
+    nodes,
+    metadata: DocumentMetadata {
+      title: None,
+      description: None,
+      glossary: Vec::new(),
+      date: None,
+      updated: None,
+      authors: Vec::new(),
+      total_lines: doc.metadata.total_lines,
+      total_nodes,
+    },
+  }
+}
+
+/// The declaration documented by each comment. A `/** */` comment documents
+/// the first documented declaration after it and before the next comment;
+/// a docstring, the nearest declaration before it with nothing in between.
+pub fn match_symbols<'a>(
+  comments: &[&Node],
+  declarations: &'a [Declaration],
+  doc_type: DocumentType,
+) -> Vec<Option<&'a Declaration>> {
+  let mut claimed = vec![false; declarations.len()];
+  let mut out = Vec::with_capacity(comments.len());
+  for (i, comment) in comments.iter().enumerate() {
+    let line = comment.span.line;
+    let found = if doc_type == DocumentType::Python {
+      declarations
+        .iter()
+        .rposition(|d| d.line < line)
+        .filter(|&d| declarations[d].documented)
+    } else {
+      let next = comments.get(i + 1).map_or(usize::MAX, |c| c.span.line);
+      declarations
+        .iter()
+        .position(|d| d.line > line && d.line < next && d.documented)
+    };
+    match found.filter(|&d| !claimed[d]) {
+      Some(d) => {
+        claimed[d] = true;
+        out.push(Some(&declarations[d]));
+      }
+      None => out.push(None),
+    }
+  }
+  out
+}
+
+/// Title from a JSDoc `@typedef` or `@callback`, which name a symbol that
+/// has no declaration in the source.
+fn symbol_name(comment: &Node) -> Option<Vec<Node>> {
+  comment.children.iter().find_map(|n| match &n.kind {
+    NodeKind::DocTypedef { name, .. } => Some(vec![text("type "), code(name)]),
+    NodeKind::DocCallback { name } => Some(vec![text("callback "), code(name)]),
+    _ => None,
+  })
+}
+
+/// Blocks for one doc comment, in a fixed order whatever the tag order.
+fn body(comment: &Node) -> Vec<Node> {
+  let tags = &comment.children;
+  let mut out = Vec::new();
+
+  for tag in tags {
+    if let NodeKind::DocDeprecated { message, version } = &tag.kind {
+      let mut inline = vec![strong("Deprecated")];
+      if let Some(version) = version {
+        inline.push(text(&format!(" in {}", version)));
+      }
+      if let Some(message) = message {
+        inline.push(text(": "));
+        inline.extend(inline_markdown(message));
+      }
+      out.push(Node::with_children(
+        NodeKind::Alert {
+          alert_type: AlertType::Warning,
+        },
+        Span::empty(),
+        vec![paragraph(inline)],
+      ));
+    }
+  }
+  for tag in tags {
+    if let NodeKind::DocDescription { .. } | NodeKind::DocRemarks { .. } = tag.kind {
+      out.extend(tag.children.iter().map(detach));
+    }
+  }
+
+  let entries = |f: &dyn Fn(&NodeKind) -> Option<Entry>| {
+    tags.iter().filter_map(|t| f(&t.kind)).collect::<Vec<_>>()
+  };
+  let named = |name: &str, ty: &Option<String>| {
+    let mut term = vec![code(name)];
+    if let Some(ty) = ty {
+      term.push(text(&format!(" ({})", ty)));
+    }
+    term
+  };
+  let lists = [
+    (
+      "Type parameters",
+      entries(&|k| match k {
+        NodeKind::DocTypeParam { name, description } => {
+          Some((vec![code(name)], description.as_ref()))
+        }
+        _ => None,
+      }),
+    ),
+    (
+      "Parameters",
+      entries(&|k| match k {
+        NodeKind::DocParam {
+          name,
+          param_type,
+          description,
+          ..
+        } => Some((named(name, param_type), description.as_ref())),
+        _ => None,
+      }),
+    ),
+    (
+      "Properties",
+      entries(&|k| match k {
+        NodeKind::DocProperty {
+          name,
+          prop_type,
+          description,
+        } => Some((named(name, prop_type), description.as_ref())),
+        _ => None,
+      }),
+    ),
+    (
+      "Returns",
+      entries(&|k| match k {
+        NodeKind::DocReturn {
+          return_type: Some(ty),
+          description,
+          ..
+        } => Some((vec![code(ty)], description.as_ref())),
+        _ => None,
+      }),
+    ),
+    (
+      "Throws",
+      entries(&|k| match k {
+        NodeKind::DocThrows {
+          exception_type,
+          description,
+        } => Some((vec![code(exception_type)], description.as_ref())),
+        _ => None,
+      }),
+    ),
+  ];
+  // JavaDoc `@return` and untyped JSDoc returns have no term to list
+  let returns: Vec<Node> = tags
+    .iter()
+    .filter_map(|t| match &t.kind {
+      NodeKind::DocReturn {
+        return_type: None,
+        description: Some(description),
+        ..
+      } => Some(paragraph(inline_markdown(description))),
+      _ => None,
+    })
+    .collect();
+  for (title, items) in lists {
+    let prose = if title == "Returns" {
+      &returns[..]
+    } else {
+      &[]
+    };
+    if !items.is_empty() || !prose.is_empty() {
+      out.push(heading(3, vec![text(title)], Span::empty()));
+      out.extend(prose.iter().cloned());
+    }
+    if !items.is_empty() {
+      out.push(definition_list(items));
+    }
+  }
+
+  // Example children are its caption and code; doctests repeat the code
+  let examples: Vec<Node> = tags
+    .iter()
+    .filter(|t| matches!(t.kind, NodeKind::DocExample { .. }))
+    .flat_map(|t| &t.children)
+    .filter(|n| !matches!(n.kind, NodeKind::DocDoctest { .. }))
+    .map(detach)
+    .collect();
+  if !examples.is_empty() {
+    out.push(heading(3, vec![text("Examples")], Span::empty()));
+    out.extend(examples);
+  }
+
+  let see: Vec<Node> = tags
+    .iter()
+    .filter_map(|t| match &t.kind {
+      NodeKind::DocSee { reference } => Some(list_item(vec![paragraph(vec![code(reference)])])),
+      _ => None,
+    })
+    .collect();
+  if !see.is_empty() {
+    out.push(heading(3, vec![text("See also")], Span::empty()));
+    out.push(Node::with_children(
+      NodeKind::List {
+        ordered: false,
+        start: None,
+        tight: true,
+      },
+      Span::empty(),
+      see,
+    ));
+  }
+
+  for tag in tags {
+    let (label, value) = match &tag.kind {
+      NodeKind::DocSince { version } => ("Since", version),
+      NodeKind::DocVersion { version } => ("Version", version),
+      NodeKind::DocAuthor { name } => ("Author", name),
+      NodeKind::DocDefaultValue { value } => ("Default", value),
+      _ => continue,
+    };
+    out.push(paragraph(vec![strong(label), text(": "), text(value)]));
+  }
+  out
+}
+
+fn definition_list(items: Vec<Entry>) -> Node {
+  let mut children = Vec::new();
+  for (term, description) in items {
+    children.push(Node::with_children(
+      NodeKind::DefinitionTerm,
+      Span::empty(),
+      term,
+    ));
+    if let Some(description) = description.filter(|d| !d.trim().is_empty()) {
+      children.push(Node::with_children(
+        NodeKind::DefinitionDescription,
+        Span::empty(),
+        inline_markdown(description),
+      ));
+    }
+  }
+  Node::with_children(NodeKind::DefinitionList, Span::empty(), children)
+}
+
+/// Inline nodes of Markdown text, paragraphs joined by spaces.
+fn inline_markdown(text_: &str) -> Vec<Node> {
+  let mut out = Vec::new();
+  for block in MarkdownParser::new(text_).parse().nodes {
+    if !out.is_empty() {
+      out.push(text(" "));
+    }
+    match block.kind {
+      NodeKind::Paragraph => out.extend(block.children.iter().map(detach)),
+      _ => out.push(detach(&block)),
+    }
+  }
+  out
+}
+
+/// Copy of `node` with every span cleared.
+fn detach(node: &Node) -> Node {
+  Node::with_children(
+    node.kind.clone(),
+    Span::empty(),
+    node.children.iter().map(detach).collect(),
+  )
+}
+
+fn heading(level: u8, children: Vec<Node>, span: Span) -> Node {
+  Node::with_children(
+    NodeKind::Heading {
+      level,
+      id: None,
+      attributes: Vec::new(),
+      section: None,
+    },
+    span,
+    children,
+  )
+}
+
+fn paragraph(children: Vec<Node>) -> Node {
+  Node::with_children(NodeKind::Paragraph, Span::empty(), children)
+}
+
+fn list_item(children: Vec<Node>) -> Node {
+  Node::with_children(
+    NodeKind::ListItem {
+      marker: ListMarker::Bullet('-'),
+      checked: None,
+    },
+    Span::empty(),
+    children,
+  )
+}
+
+fn strong(content: &str) -> Node {
+  Node::with_children(NodeKind::Strong, Span::empty(), vec![text(content)])
+}
+
+fn code(content: &str) -> Node {
+  Node::new(
+    NodeKind::CodeSpan {
+      content: content.to_string(),
+    },
+    Span::empty(),
+  )
+}
+
+fn text(content: &str) -> Node {
+  Node::new(
+    NodeKind::Text {
+      content: content.to_string(),
+    },
+    Span::empty(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::formats::to_sexp;
+  use crate::parsers::coverage;
+  use crate::parsers::{JavaDocParser, JsDocParser, PyDocParser};
+
+  fn reference(source: &str, doc_type: DocumentType, path: &str) -> String {
+    let mut doc = match doc_type {
+      DocumentType::Java => JavaDocParser::new(source).parse(),
+      DocumentType::Python => PyDocParser::new(source).parse(),
+      _ => JsDocParser::new(source).parse(),
+    };
+    doc.source_path = path.to_string();
+    let coverage = coverage::measure(source, doc_type);
+    to_sexp(&project(&doc, coverage.as_ref()))
+  }
+
+  #[test]
+  fn test_java_sections() {
+    let source = "/** File header. */\npackage x;\n\n/**\n * Greets people.\n * @param name who to greet\n \
+                  * @return the greeting\n * @throws IllegalStateException when closed\n * @since 1.2\n */\n\
+                  public String greet(String name) {\n}\n\n/** @deprecated use greet */\npublic void hi() {\n}\n";
+    let sexp = reference(source, DocumentType::Java, "src/Greeter.java");
+    assert_eq!(
+      sexp,
+      r#"(document markdown
+  (heading 1 (code-span "Greeter.java"))
+  (paragraph (text "File header."))
+  (heading 2 (text "method ") (code-span "greet"))
+  (paragraph (text "Greets people."))
+  (heading 3 (text "Parameters"))
+  (definition-list
+    (definition-term (code-span "name"))
+    (definition-description (text "who to greet")))
+  (heading 3 (text "Returns"))
+  (paragraph (text "the greeting"))
+  (heading 3 (text "Throws"))
+  (definition-list
+    (definition-term (code-span "IllegalStateException"))
+    (definition-description (text "when closed")))
+  (paragraph
+    (strong (text "Since"))
+    (text ": ")
+    (text "1.2"))
+  (heading 2 (text "method ") (code-span "hi"))
+  (alert warning
+    (paragraph
+      (strong (text "Deprecated"))
+      (text ": ")
+      (text "use greet"))))
+"#
+    );
+  }
+
+  #[test]
+  fn test_loose_comment_keeps_position() {
+    let source = "class A {\n  /** First. */\n  void a() {}\n\n  /** Loose note. */\n\n  \
+                  /** Second. */\n  int b(int x) { return x; }\n}\n";
+    let sexp = reference(source, DocumentType::Java, "");
+    assert_eq!(
+      sexp,
+      r#"(document markdown
+  (heading 2 (text "method ") (code-span "a"))
+  (paragraph (text "First."))
+  (paragraph (text "Loose note."))
+  (heading 2 (text "method ") (code-span "b"))
+  (paragraph (text "Second.")))
+"#
+    );
+  }
+
+  #[test]
+  fn test_js_example_and_typedef() {
+    let source = "/**\n * @typedef {Object} Point\n * @property {number} x - across\n */\n\n\
+                  /**\n * Add.\n * @param {number} a\n * @example\n * add(1)\n */\nfunction add(a) {}\n";
+    let sexp = reference(source, DocumentType::JavaScript, "");
+    assert!(
+      sexp.contains("(heading 2 (text \"type \") (code-span \"Point\"))"),
+      "{}",
+      sexp
+    );
+    assert!(
+      sexp.contains("(definition-term (code-span \"x\") (text \" (number)\"))"),
+      "{}",
+      sexp
+    );
+    assert!(
+      sexp.contains("(heading 2 (text \"function \") (code-span \"add\"))"),
+      "{}",
+      sexp
+    );
+    assert!(
+      sexp.contains("(fenced-code-block :lang \"javascript\" (text \"add(1)\\n\"))"),
+      "{}",
+      sexp
+    );
+    assert!(!sexp.contains("(heading 1"));
+  }
+
+  #[test]
+  fn test_deprecated_version() {
+    let source =
+      "def f():\n    \"\"\"Do f.\n\n    .. deprecated:: 2.0\n       Use g.\n    \"\"\"\n";
+    let sexp = reference(source, DocumentType::Python, "");
+    assert!(
+      sexp.contains(
+        "(alert warning\n    (paragraph\n      (strong (text \"Deprecated\"))\n      (text \" in 2.0\")\n      (text \": \")\n      (text \"Use g.\")))"
+      ),
+      "{}",
+      sexp
+    );
+  }
+
+  #[test]
+  fn test_python_docstrings() {
+    let source =
+      "\"\"\"Module doc.\"\"\"\n\n\ndef f(a):\n    \"\"\"Do f.\"\"\"\n\n\nclass C:\n    pass\n";
+    let sexp = reference(source, DocumentType::Python, "pkg/mod.py");
+    assert_eq!(
+      sexp,
+      r#"(document markdown
+  (heading 1 (code-span "mod.py"))
+  (paragraph (text "Module doc."))
+  (heading 2 (text "function ") (code-span "f"))
+  (paragraph (text "Do f.")))
+"#
+    );
+  }
+}
@@ -0,0 +1,218 @@
+//! Which parser reads a file, by extension.
+//!
+//! A [`ParserRegistry`] starts with the built-in parsers for every
+//! [`DocumentType`] and can take more through [`ParserRegistry::register`],
+//! so a new language needs a parser and a registration but no change to
+//! the processor.
+
+use crate::ast::{Document, DocumentType};
+use crate::markdown::{ElementRegistry, MarkdownParser, ParseOptions};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::{JavaDocParser, JsDocParser, PyDocParser};
+
+/// Everything a parser gets besides the source text.
+#[derive(Clone, Copy)]
+pub struct ParseContext<'a> {
+  /// Custom elements for Markdown
+  pub elements: &'a ElementRegistry,
+  pub options: ParseOptions,
+  /// Threads a parser may use for one large file
+  pub threads: usize,
+}
+
+impl<'a> ParseContext<'a> {
+  pub fn new(elements: &'a ElementRegistry, options: ParseOptions) -> Self {
+    Self {
+      elements,
+      options,
+      threads: 1,
+    }
+  }
+
+  pub fn with_threads(mut self, threads: usize) -> Self {
+    self.threads = threads.max(1);
+    self
+  }
+}
+
+type ParseFn = dyn Fn(&str, &ParseContext) -> Document + Send + Sync;
+
+/// A named parser and the document type it produces.
+#[derive(Clone)]
+pub struct Parser {
+  pub name: String,
+  /// Decides coverage, lint and the other per-type steps for its documents
+  pub doc_type: DocumentType,
+  parse: Arc<ParseFn>,
+}
+
+impl Parser {
+  pub fn new(
+    name: &str,
+    doc_type: DocumentType,
+    parse: impl Fn(&str, &ParseContext) -> Document + Send + Sync + 'static,
+  ) -> Self {
+    Self {
+      name: name.to_string(),
+      doc_type,
+      parse: Arc::new(parse),
+    }
+  }
+
+  /// The bundled parser for `doc_type`.
+  pub fn builtin(doc_type: DocumentType) -> Self {
+    let name = match doc_type {
+      DocumentType::Markdown => "markdown",
+      DocumentType::JavaScript => "jsdoc",
+      DocumentType::TypeScript => "tsdoc",
+      DocumentType::Java => "javadoc",
+      DocumentType::Python => "pydoc",
+    };
+    Self::new(name, doc_type, move |source, cx| match doc_type {
+      DocumentType::Markdown => MarkdownParser::new(source)
+        .with_elements(cx.elements)
+        .with_options(cx.options)
+        .with_threads(cx.threads)
+        .parse(),
+      DocumentType::JavaScript => JsDocParser::new(source).parse(),
+      DocumentType::TypeScript => JsDocParser::typescript(source).parse(),
+      DocumentType::Java => JavaDocParser::new(source).parse(),
+      DocumentType::Python => PyDocParser::new(source)
+        .with_all_strings(cx.options.all_strings)
+        .parse(),
+    })
+  }
+
+  pub fn parse(&self, source: &str, cx: &ParseContext) -> Document {
+    (self.parse)(source, cx)
+  }
+}
+
+/// Parsers by file extension.
+#[derive(Clone)]
+pub struct ParserRegistry {
+  parsers: Vec<Parser>,
+  /// Lowercase extension to index in `parsers`
+  extensions: BTreeMap<String, usize>,
+}
+
+impl ParserRegistry {
+  /// The built-in parsers with their extensions.
+  pub fn new() -> Self {
+    let mut registry = Self {
+      parsers: Vec::new(),
+      extensions: BTreeMap::new(),
+    };
+    for doc_type in DocumentType::ALL {
+      registry.register(doc_type.extensions(), Parser::builtin(doc_type));
+    }
+    registry
+  }
+
+  /// Read files with these extensions (without the dot) using `parser`.
+  /// An extension that already had a parser is taken over.
+  pub fn register(&mut self, extensions: &[&str], parser: Parser) -> &mut Self {
+    let index = self.parsers.len();
+    self.parsers.push(parser);
+    for ext in extensions {
+      let ext = ext.trim_start_matches('.').to_lowercase();
+      self.extensions.insert(ext, index);
+    }
+    self
+  }
+
+  /// The parser for a file extension, in any case.
+  pub fn get(&self, extension: &str) -> Option<&Parser> {
+    let index = self.extensions.get(&extension.to_lowercase())?;
+    Some(&self.parsers[*index])
+  }
+
+  /// Each parser still reached by some extension, in registration order,
+  /// with its sorted extensions.
+  pub fn list(&self) -> Vec<(&Parser, Vec<&str>)> {
+    let mut by_parser: Vec<Vec<&str>> = vec![Vec::new(); self.parsers.len()];
+    for (ext, index) in &self.extensions {
+      by_parser[*index].push(ext);
+    }
+    self
+      .parsers
+      .iter()
+      .zip(by_parser)
+      .filter(|(_, extensions)| !extensions.is_empty())
+      .collect()
+  }
+
+  /// The `--list-parsers` table.
+  pub fn describe(&self) -> String {
+    let mut out = String::new();
+    for (parser, extensions) in self.list() {
+      out.push_str(&format!(
+        "{:<12} {:<12} {}\n",
+        parser.name,
+        parser.doc_type.name(),
+        extensions.join(", ")
+      ));
+    }
+    out
+  }
+}
+
+impl Default for ParserRegistry {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ast::{Node, NodeKind, Span};
+
+  #[test]
+  fn test_builtin_extensions() {
+    let registry = ParserRegistry::new();
+    assert_eq!(registry.get("MD").unwrap().name, "markdown");
+    assert_eq!(
+      registry.get("tsx").unwrap().doc_type,
+      DocumentType::TypeScript
+    );
+    assert_eq!(registry.get("pyi").unwrap().name, "pydoc");
+    assert!(registry.get("txt").is_none());
+  }
+
+  #[test]
+  fn test_register_parser() {
+    let mut registry = ParserRegistry::new();
+    let text = Parser::new("text", DocumentType::Markdown, |source, _| {
+      let mut doc = Document::new(DocumentType::Markdown);
+      doc.nodes.push(Node::new(
+        NodeKind::Text {
+          content: source.to_string(),
+        },
+        Span::default(),
+      ));
+      doc
+    });
+    registry.register(&[".txt", "markdown"], text);
+
+    let elements = ElementRegistry::new();
+    let cx = ParseContext::new(&elements, ParseOptions::new());
+    let doc = registry.get("TXT").unwrap().parse("# hi", &cx);
+    assert!(matches!(&doc.nodes[0].kind, NodeKind::Text { content } if content == "# hi"));
+    assert_eq!(registry.get("markdown").unwrap().name, "text");
+    assert_eq!(registry.get("md").unwrap().name, "markdown");
+
+    let list: Vec<_> = registry
+      .list()
+      .into_iter()
+      .map(|(p, extensions)| (p.name.as_str(), extensions))
+      .collect();
+    assert_eq!(list[0], ("markdown", vec!["md", "mdown", "mkd"]));
+    assert_eq!(list.last().unwrap(), &("text", vec!["markdown", "txt"]));
+    assert!(registry
+      .describe()
+      .contains("text         markdown     markdown, txt\n"));
+  }
+}
@@ -0,0 +1,124 @@
+//! Document type from file content, for files whose extension is missing
+//! or says nothing (`--detect=content|both`).
+//!
+//! Only the start of a file is looked at: a shebang names the interpreter,
+//! a leading `/**` marks a Java or JavaScript source, and YAML frontmatter
+//! or a leading heading marks Markdown.
+
+use crate::ast::DocumentType;
+
+/// How much of a file [`sniff`] needs.
+pub const SNIFF_BYTES: usize = 4096;
+
+/// The document type `source` looks like, if any.
+pub fn sniff(source: &str) -> Option<DocumentType> {
+  let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+  if let Some(line) = source.strip_prefix("#!") {
+    return shebang(line.lines().next().unwrap_or(""));
+  }
+  let text = source.trim_start();
+  if source.starts_with("---\n") || source.starts_with("---\r\n") {
+    return Some(DocumentType::Markdown);
+  }
+  if text.starts_with("/**") {
+    return Some(if looks_like_java(text) {
+      DocumentType::Java
+    } else {
+      DocumentType::JavaScript
+    });
+  }
+  if text.starts_with("\"\"\"") || text.starts_with("'''") {
+    return Some(DocumentType::Python);
+  }
+  if starts_with_heading(text) && !looks_like_python(text) {
+    return Some(DocumentType::Markdown);
+  }
+  None
+}
+
+/// `#!/usr/bin/env -S node --flags` or `#!/usr/bin/python3`, without `#!`.
+fn shebang(line: &str) -> Option<DocumentType> {
+  let mut words = line.split_whitespace();
+  let mut program = basename(words.next()?);
+  if program == "env" {
+    program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+  }
+  let program = basename(program);
+  let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+  match name {
+    "python" | "pypy" => Some(DocumentType::Python),
+    "node" | "nodejs" | "deno" | "bun" => Some(DocumentType::JavaScript),
+    "ts-node" | "tsx" => Some(DocumentType::TypeScript),
+    "java" => Some(DocumentType::Java),
+    _ => None,
+  }
+}
+
+fn basename(path: &str) -> &str {
+  path.rsplit('/').next().unwrap_or(path)
+}
+
+/// A `package` or `import` declaration in Java's form: ending in `;`, with
+/// no quoted module as in JavaScript.
+fn looks_like_java(text: &str) -> bool {
+  text.lines().map(str::trim).any(|line| {
+    (line.starts_with("package ") || line.starts_with("import "))
+      && line.ends_with(';')
+      && !line.contains(['"', '\''])
+  })
+}
+
+/// `# Title` (ATX) or a line underlined with `===`.
+fn starts_with_heading(text: &str) -> bool {
+  let mut lines = text.lines();
+  let first = lines.next().unwrap_or("");
+  let hashes = first.bytes().take_while(|&b| b == b'#').count();
+  if (1..=6).contains(&hashes) && first[hashes..].starts_with(' ') {
+    return true;
+  }
+  let underline = lines.next().unwrap_or("").trim_end();
+  !underline.is_empty() && underline.bytes().all(|b| b == b'=')
+}
+
+/// `# comment` lines followed by Python statements.
+fn looks_like_python(text: &str) -> bool {
+  text.lines().take(20).any(|line| {
+    ["import ", "from ", "def ", "class ", "if __name__"]
+      .iter()
+      .any(|keyword| line.starts_with(keyword))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use DocumentType::*;
+
+  #[test]
+  fn test_sniff_shebang() {
+    assert_eq!(sniff("#!/usr/bin/env python3\nprint(1)"), Some(Python));
+    assert_eq!(sniff("#!/usr/bin/python3.12\n"), Some(Python));
+    assert_eq!(
+      sniff("#!/usr/bin/env -S node --no-warnings\n"),
+      Some(JavaScript)
+    );
+    assert_eq!(sniff("#!/usr/bin/env ts-node\n"), Some(TypeScript));
+    assert_eq!(sniff("#!/bin/sh\n# Title\n"), None);
+  }
+
+  #[test]
+  fn test_sniff_content() {
+    assert_eq!(sniff("---\ntitle: A\n---\ntext"), Some(Markdown));
+    assert_eq!(sniff("# Readme\n\nHello"), Some(Markdown));
+    assert_eq!(sniff("Readme\n======\n"), Some(Markdown));
+    assert_eq!(sniff("# Copyright\n\nimport os\n"), None);
+    assert_eq!(sniff("#include <stdio.h>\n"), None);
+    assert_eq!(
+      sniff("/** Doc */\npackage a.b;\n\nimport java.util.List;\n"),
+      Some(Java)
+    );
+    assert_eq!(sniff("/** Doc */\nimport x from 'x';\n"), Some(JavaScript));
+    assert_eq!(sniff("\"\"\"Module docs.\"\"\"\n"), Some(Python));
+    assert_eq!(sniff("plain text"), None);
+  }
+}
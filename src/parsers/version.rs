@@ -0,0 +1,142 @@
+//! Versions in `@since` and `@version` tags, compared the semver way.
+//!
+//! Accepted: an optional `v`, one to four dot-separated numbers (missing
+//! ones count as 0, so `1.2` equals `1.2.0`), an optional `-pre.release`
+//! and an optional `+build`. A pre-release sorts before its release;
+//! build metadata is ignored.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Equality and order ignore `raw` and build metadata.
+#[derive(Debug, Clone)]
+pub struct Version {
+  /// Major, minor, patch and an optional fourth number
+  pub numbers: [u64; 4],
+  /// Pre-release identifiers, e.g. `["beta", "2"]` for `-beta.2`
+  pub pre: Vec<String>,
+  /// The text it was parsed from
+  pub raw: String,
+}
+
+impl Version {
+  pub fn parse(text: &str) -> Option<Self> {
+    let raw = text.trim();
+    let core = raw.strip_prefix(['v', 'V']).unwrap_or(raw);
+    let core = core.split_once('+').map_or(core, |(core, _)| core);
+    let (core, pre) = match core.split_once('-') {
+      Some((core, pre)) => (core, Some(pre)),
+      None => (core, None),
+    };
+
+    let mut numbers = [0; 4];
+    for (index, part) in core.split('.').enumerate() {
+      if index == 4 || part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+      }
+      numbers[index] = part.parse().ok()?;
+    }
+    let pre: Vec<String> = match pre {
+      Some(pre) => pre.split('.').map(str::to_string).collect(),
+      None => Vec::new(),
+    };
+    let valid_ident = |p: &String| !p.is_empty() && p.bytes().all(|b| b.is_ascii_alphanumeric());
+    if !pre.iter().all(valid_ident) {
+      return None;
+    }
+    Some(Self {
+      numbers,
+      pre,
+      raw: raw.to_string(),
+    })
+  }
+}
+
+impl Ord for Version {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.numbers.cmp(&other.numbers).then_with(|| {
+      match (self.pre.is_empty(), other.pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => compare_pre(&self.pre, &other.pre),
+      }
+    })
+  }
+}
+
+impl PartialEq for Version {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Display for Version {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.raw)
+  }
+}
+
+/// Numeric identifiers compare as numbers and sort before alphanumeric
+/// ones; a shorter list that is a prefix of a longer one sorts first.
+fn compare_pre(a: &[String], b: &[String]) -> Ordering {
+  for (x, y) in a.iter().zip(b) {
+    let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+      (Ok(x), Ok(y)) => x.cmp(&y),
+      (Ok(_), Err(_)) => Ordering::Less,
+      (Err(_), Ok(_)) => Ordering::Greater,
+      (Err(_), Err(_)) => x.cmp(y),
+    };
+    if order != Ordering::Equal {
+      return order;
+    }
+  }
+  a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn v(text: &str) -> Version {
+    Version::parse(text).unwrap()
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(v("v1.2").numbers, [1, 2, 0, 0]);
+    assert_eq!(v(" 2.0.1-beta.2+build.5 ").pre, vec!["beta", "2"]);
+    assert_eq!(v("1.2.3.4").numbers, [1, 2, 3, 4]);
+    for bad in [
+      "",
+      "next",
+      "1..2",
+      "1.2.3.4.5",
+      "1.x",
+      "JDK1.0",
+      "1.0-",
+      "1.0-a..b",
+    ] {
+      assert!(Version::parse(bad).is_none(), "{}", bad);
+    }
+  }
+
+  #[test]
+  fn test_ordering() {
+    assert_eq!(v("1.2"), v("v1.2.0"));
+    assert!(v("1.10") > v("1.9"));
+    assert!(v("2.0.0-rc.1") < v("2.0.0"));
+    assert!(v("1.0.0-alpha") < v("1.0.0-alpha.1"));
+    assert!(v("1.0.0-alpha.1") < v("1.0.0-alpha.beta"));
+    assert!(v("1.0.0-beta.2") < v("1.0.0-beta.11"));
+    assert_eq!(v("1.0+a"), v("1.0+b"));
+  }
+}
@@ -1,37 +1,104 @@
 //! File collection utilities.
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-/// Collect files matching extensions from directory.
-pub fn collect_files(
-  dir: &Path,
-  extensions: &[String],
-  recursive: bool,
-) -> Result<Vec<PathBuf>, String> {
+/// Files to process, plus everything passed over and why.
+#[derive(Debug, Default)]
+pub struct Collected {
+  pub files: Vec<PathBuf>,
+  pub skipped: Vec<(PathBuf, &'static str)>,
+}
+
+/// How `collect_files` walks the input directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Walk {
+  pub recursive: bool,
+  /// Follow symlinked files and directories; each real directory is
+  /// entered once, so symlink cycles end
+  pub follow_symlinks: bool,
+  /// Include files and directories whose name starts with `.`
+  pub hidden: bool,
+  /// Directory levels to read, 1 being the input directory alone
+  pub max_depth: Option<usize>,
+  /// Also collect files without an extension, for content detection
+  pub extensionless: bool,
+}
+
+impl Default for Walk {
+  fn default() -> Self {
+    Self {
+      recursive: true,
+      follow_symlinks: true,
+      hidden: true,
+      max_depth: None,
+      extensionless: false,
+    }
+  }
+}
+
+/// Collect files matching extensions from directory, sorted by path so
+/// processing and reporting order never depends on the filesystem.
+pub fn collect_files(dir: &Path, extensions: &[String], walk: Walk) -> Result<Collected, String> {
   let mut files = Vec::new();
+  let mut skipped = Vec::new();
+  let mut visited = HashSet::new();
   let mut queue = VecDeque::new();
-  queue.push_back(dir.to_path_buf());
+  visited.insert(canonical(dir));
+  queue.push_back((dir.to_path_buf(), 1));
 
-  while let Some(current_dir) = queue.pop_front() {
+  while let Some((current_dir, depth)) = queue.pop_front() {
     let entries = fs::read_dir(&current_dir)
       .map_err(|e| format!("Failed to read directory {}: {}", current_dir.display(), e))?;
 
     for entry in entries.flatten() {
       let path = entry.path();
+      let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
 
-      if path.is_dir() {
-        if recursive && !should_skip_dir(&path) {
-          queue.push_back(path);
+      if !walk.hidden && is_hidden(&path) {
+        skipped.push((path, "hidden"));
+      } else if is_symlink && !walk.follow_symlinks {
+        skipped.push((path, "symlink not followed"));
+      } else if path.is_dir() {
+        if !walk.recursive {
+          skipped.push((path, "recursion disabled"));
+        } else if should_skip_dir(&path) {
+          skipped.push((path, "ignored directory"));
+        } else if walk.max_depth.is_some_and(|max| depth >= max) {
+          skipped.push((path, "below --max-depth"));
+        } else if !visited.insert(canonical(&path)) {
+          skipped.push((path, "directory already visited"));
+        } else {
+          queue.push_back((path, depth + 1));
+        }
+      } else if path.is_file() {
+        if matches_extension(&path, extensions)
+          || (walk.extensionless && path.extension().is_none())
+        {
+          files.push(path);
+        } else {
+          skipped.push((path, "extension not in --extensions"));
         }
-      } else if path.is_file() && matches_extension(&path, extensions) {
-        files.push(path);
       }
     }
   }
 
-  Ok(files)
+  files.sort();
+  skipped.sort();
+  Ok(Collected { files, skipped })
+}
+
+/// The real path of a directory, or the path itself if it cannot be resolved.
+fn canonical(path: &Path) -> PathBuf {
+  fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_hidden(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|n| n.to_str())
+    .is_some_and(|n| n.starts_with('.'))
 }
 
 fn matches_extension(path: &Path, extensions: &[String]) -> bool {
@@ -73,3 +140,137 @@ fn is_ignored_dir(name: &str) -> bool {
   ];
   IGNORED.contains(&name)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bukvar-files-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_collect_sorted_with_skip_reasons() {
+    let dir = temp_dir("sorted");
+    fs::create_dir_all(dir.join("node_modules")).unwrap();
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    for name in ["b.md", "a.md", "notes.txt", "sub/c.md", "README"] {
+      fs::write(dir.join(name), "x").unwrap();
+    }
+
+    let md = ["md".to_string()];
+    let collected = collect_files(&dir, &md, Walk::default()).unwrap();
+    assert_eq!(
+      collected.files,
+      vec![dir.join("a.md"), dir.join("b.md"), dir.join("sub/c.md")]
+    );
+    assert_eq!(
+      collected.skipped,
+      vec![
+        (dir.join("README"), "extension not in --extensions"),
+        (dir.join("node_modules"), "ignored directory"),
+        (dir.join("notes.txt"), "extension not in --extensions"),
+      ]
+    );
+
+    let walk = Walk {
+      recursive: false,
+      extensionless: true,
+      ..Walk::default()
+    };
+    let collected = collect_files(&dir, &md, walk).unwrap();
+    assert_eq!(
+      collected.files,
+      vec![dir.join("README"), dir.join("a.md"), dir.join("b.md")]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_collect_hidden_and_max_depth() {
+    let dir = temp_dir("depth");
+    fs::create_dir_all(dir.join(".github")).unwrap();
+    fs::create_dir_all(dir.join("a/b")).unwrap();
+    for name in [
+      "top.md",
+      ".hidden.md",
+      ".github/ci.md",
+      "a/one.md",
+      "a/b/two.md",
+    ] {
+      fs::write(dir.join(name), "x").unwrap();
+    }
+    let md = ["md".to_string()];
+
+    let walk = Walk {
+      hidden: false,
+      max_depth: Some(2),
+      ..Walk::default()
+    };
+    let collected = collect_files(&dir, &md, walk).unwrap();
+    assert_eq!(
+      collected.files,
+      vec![dir.join("a/one.md"), dir.join("top.md")]
+    );
+    assert_eq!(
+      collected.skipped,
+      vec![
+        (dir.join(".github"), "hidden"),
+        (dir.join(".hidden.md"), "hidden"),
+        (dir.join("a/b"), "below --max-depth"),
+      ]
+    );
+
+    let collected = collect_files(&dir, &md, Walk::default()).unwrap();
+    assert_eq!(collected.files.len(), 5);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_collect_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let dir = temp_dir("symlinks");
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::write(dir.join("docs/a.md"), "x").unwrap();
+    symlink(&dir, dir.join("docs/loop")).unwrap();
+    symlink(dir.join("docs"), dir.join("alias")).unwrap();
+    let md = ["md".to_string()];
+
+    // Each real directory once: `alias` and `docs/loop` lead back
+    let collected = collect_files(&dir, &md, Walk::default()).unwrap();
+    let names: Vec<_> = collected
+      .files
+      .iter()
+      .map(|f| f.strip_prefix(&dir).unwrap())
+      .collect();
+    assert_eq!(names.len(), 1, "{:?}", names);
+    assert_eq!(
+      collected
+        .skipped
+        .iter()
+        .filter(|(_, reason)| *reason == "directory already visited")
+        .count(),
+      2
+    );
+
+    let walk = Walk {
+      follow_symlinks: false,
+      ..Walk::default()
+    };
+    let collected = collect_files(&dir, &md, walk).unwrap();
+    assert_eq!(collected.files, vec![dir.join("docs/a.md")]);
+    assert_eq!(
+      collected.skipped,
+      vec![
+        (dir.join("alias"), "symlink not followed"),
+        (dir.join("docs/loop"), "symlink not followed"),
+      ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,182 @@
+//! Include resolution: splice `<include src="...">` targets into the document.
+
+use super::transform::Transform;
+use crate::ast::{Document, DocumentType, Node, NodeKind};
+use crate::markdown::{ElementRegistry, MarkdownParser};
+
+use std::path::{Path, PathBuf};
+
+/// Transform pass resolving includes in markdown documents, relative to
+/// `Document::source_path`.
+pub struct Includes {
+  elements: ElementRegistry,
+}
+
+impl Includes {
+  pub fn new(elements: ElementRegistry) -> Self {
+    Self { elements }
+  }
+}
+
+impl Transform for Includes {
+  fn name(&self) -> &str {
+    "includes"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    if doc.doc_type != DocumentType::Markdown || doc.source_path.is_empty() {
+      return Ok(());
+    }
+    let path = PathBuf::from(&doc.source_path);
+    resolve_includes(doc, &path, &self.elements)
+  }
+}
+
+/// Parse every included file and attach its nodes as the `Include` node's
+/// children. Each resolved `src` is rewritten to the included file's path.
+pub fn resolve_includes(
+  doc: &mut Document,
+  file_path: &Path,
+  elements: &ElementRegistry,
+) -> Result<(), String> {
+  // In-memory documents (e.g. `bukvar serve`) may not exist on disk.
+  let root = file_path
+    .canonicalize()
+    .unwrap_or_else(|_| file_path.to_path_buf());
+  let mut stack = vec![root];
+
+  resolve_nodes(&mut doc.nodes, file_path, elements, &mut stack)?;
+  doc.metadata.total_nodes = doc.nodes.iter().map(|n| n.count_nodes()).sum();
+  Ok(())
+}
+
+fn resolve_nodes(
+  nodes: &mut [Node],
+  file_path: &Path,
+  elements: &ElementRegistry,
+  stack: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+  for node in nodes {
+    if let NodeKind::Include { src } = &mut node.kind {
+      let target = file_path.parent().unwrap_or(Path::new("")).join(&*src);
+      node.children = load(&target, elements, stack)?;
+      *src = target.to_string_lossy().replace('\\', "/");
+    } else {
+      resolve_nodes(&mut node.children, file_path, elements, stack)?;
+    }
+  }
+  Ok(())
+}
+
+fn load(
+  path: &Path,
+  elements: &ElementRegistry,
+  stack: &mut Vec<PathBuf>,
+) -> Result<Vec<Node>, String> {
+  let canonical = path
+    .canonicalize()
+    .map_err(|e| format!("Failed to include {}: {}", path.display(), e))?;
+
+  if stack.contains(&canonical) {
+    let chain: Vec<String> = stack
+      .iter()
+      .chain(std::iter::once(&canonical))
+      .map(|p| p.display().to_string())
+      .collect();
+    return Err(format!("Include cycle: {}", chain.join(" -> ")));
+  }
+
+  let content = std::fs::read_to_string(&canonical)
+    .map_err(|e| format!("Failed to include {}: {}", path.display(), e))?;
+  let mut nodes = MarkdownParser::new(&content)
+    .with_elements(elements)
+    .parse()
+    .nodes;
+  nodes.retain(|n| !matches!(n.kind, NodeKind::Frontmatter { .. }));
+
+  stack.push(canonical);
+  let result = resolve_nodes(&mut nodes, path, elements, stack);
+  stack.pop();
+  result.map(|_| nodes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bukvar-include-{}-{}", name, std::process::id()));
+    fs::create_dir_all(dir.join("shared")).unwrap();
+    dir
+  }
+
+  fn parse(path: &Path) -> Document {
+    let content = fs::read_to_string(path).unwrap();
+    MarkdownParser::new(&content).parse()
+  }
+
+  #[test]
+  fn test_resolve_nested_includes() {
+    let dir = fixture_dir("nested");
+    fs::write(
+      dir.join("index.md"),
+      "# Guide\n\n<include src=\"shared/a.md\" />\n",
+    )
+    .unwrap();
+    fs::write(
+      dir.join("shared/a.md"),
+      "---\ntitle: A\n---\nA text\n\n<include src=\"b.md\" />\n",
+    )
+    .unwrap();
+    fs::write(dir.join("shared/b.md"), "B text\n").unwrap();
+
+    let index = dir.join("index.md");
+    let mut doc = parse(&index);
+    resolve_includes(&mut doc, &index, &ElementRegistry::new()).unwrap();
+
+    let include = &doc.nodes[1];
+    let NodeKind::Include { src } = &include.kind else {
+      panic!("expected Include, got {:?}", include.kind);
+    };
+    assert!(src.ends_with("shared/a.md"));
+    assert_eq!(include.children.len(), 2);
+    assert!(matches!(include.children[0].kind, NodeKind::Paragraph));
+    let nested = &include.children[1];
+    assert!(matches!(&nested.kind, NodeKind::Include { src } if src.ends_with("shared/b.md")));
+    assert_eq!(nested.children.len(), 1);
+    assert_eq!(
+      doc.metadata.total_nodes,
+      doc.nodes.iter().map(|n| n.count_nodes()).sum()
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_resolve_cycle_is_error() {
+    let dir = fixture_dir("cycle");
+    fs::write(dir.join("a.md"), "<include src=\"b.md\" />\n").unwrap();
+    fs::write(dir.join("b.md"), "<include src=\"a.md\" />\n").unwrap();
+
+    let a = dir.join("a.md");
+    let mut doc = parse(&a);
+    let err = resolve_includes(&mut doc, &a, &ElementRegistry::new()).unwrap_err();
+    assert!(err.starts_with("Include cycle:"), "{}", err);
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_resolve_missing_file_is_error() {
+    let dir = fixture_dir("missing");
+    fs::write(dir.join("a.md"), "<include src=\"nope.md\" />\n").unwrap();
+
+    let a = dir.join("a.md");
+    let mut doc = parse(&a);
+    let err = resolve_includes(&mut doc, &a, &ElementRegistry::new()).unwrap_err();
+    assert!(err.contains("nope.md"), "{}", err);
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,326 @@
+//! Combine several Markdown files into one document (`bukvar merge`), for
+//! book-style output built from chapters.
+//!
+//! Each chapter is parsed and transformed on its own, as in a normal run,
+//! and becomes an `Include { src }` node holding its nodes, so the chapter
+//! path travels with every span the same way it does for `<include>`.
+
+use super::build_pipeline;
+use super::parse::detect_parser;
+use super::transform::{dedupe, Pipeline};
+use super::write::write_output;
+use super::writer::Output;
+use crate::ast::{Document, DocumentMetadata, DocumentType, Node, NodeKind, Span};
+use crate::cli::Args;
+use crate::markdown::ElementRegistry;
+use crate::parsers::{ParseContext, ParserRegistry};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Chapter list looked up in a directory given to `bukvar merge`.
+pub const TOC_FILE: &str = "toc.txt";
+
+/// Merge the chapters named by `sources` and write the result to
+/// `<output>/<name>.<format>`, where the name is the directory's when a
+/// directory was given and `merged` otherwise. Returns the written path.
+pub fn run(sources: &[PathBuf], args: &Args) -> Result<PathBuf, String> {
+  let (chapters, name) = chapters(sources)?;
+  let elements = match &args.elements {
+    Some(path) => ElementRegistry::load(path)?,
+    None => ElementRegistry::new(),
+  };
+  let pipeline = build_pipeline(args, &elements)?;
+  let mut doc = merge(&chapters, args, &elements, &pipeline)?;
+  if let [dir] = sources {
+    if dir.is_dir() {
+      doc.source_path = normalize(&dir.join(TOC_FILE));
+    }
+  }
+
+  let args = Args {
+    flat: true,
+    ..args.clone()
+  };
+  let target = Path::new(&name);
+  write_output(&doc, target, &args, &Output::direct())?;
+  Ok(super::write::compute_output_path(target, &args))
+}
+
+/// Chapter paths in order, and the name of the merged output. A single
+/// directory is read through its `toc.txt`: one path per line, relative to
+/// the directory, with blank lines and `#` comments ignored.
+fn chapters(sources: &[PathBuf]) -> Result<(Vec<PathBuf>, String), String> {
+  let dir = match sources {
+    [dir] if dir.is_dir() => dir,
+    _ => {
+      if let Some(dir) = sources.iter().find(|path| path.is_dir()) {
+        return Err(format!(
+          "{} is a directory; merge takes files or a single directory",
+          dir.display()
+        ));
+      }
+      return Ok((sources.to_vec(), "merged".to_string()));
+    }
+  };
+
+  let toc = dir.join(TOC_FILE);
+  let list =
+    fs::read_to_string(&toc).map_err(|e| format!("Failed to read {}: {}", toc.display(), e))?;
+  let chapters: Vec<PathBuf> = list
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(|line| dir.join(line))
+    .collect();
+  if chapters.is_empty() {
+    return Err(format!("{} lists no files", toc.display()));
+  }
+  let name = dir
+    .canonicalize()
+    .ok()
+    .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+    .unwrap_or_else(|| "merged".to_string());
+  Ok((chapters, name))
+}
+
+/// Parse and transform each chapter, then wrap its nodes in an `Include`
+/// node naming the file. Chapter frontmatter is dropped, as for includes.
+/// Heading ids an earlier chapter already uses get a `-N` suffix, and the
+/// chapter's own `#id` links follow them.
+pub fn merge(
+  chapters: &[PathBuf],
+  args: &Args,
+  elements: &ElementRegistry,
+  pipeline: &Pipeline,
+) -> Result<Document, String> {
+  let mut nodes = Vec::with_capacity(chapters.len());
+  let mut total_lines = 0;
+  let mut glossary: Vec<(String, String)> = Vec::new();
+  let mut ids = HashMap::new();
+  for path in chapters {
+    let parser = detect_parser(&ParserRegistry::new(), path, args.unknown, args.detect)?;
+    if parser.doc_type != DocumentType::Markdown {
+      return Err(format!(
+        "{}: only Markdown files can be merged",
+        path.display()
+      ));
+    }
+    let source =
+      fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut doc = parser.parse(&source, &ParseContext::new(elements, args.parse_options()));
+    doc.source_path = normalize(path);
+    pipeline
+      .run(&mut doc)
+      .map_err(|e| format!("{}: {}", path.display(), e))?;
+    doc
+      .nodes
+      .retain(|n| !matches!(n.kind, NodeKind::Frontmatter { .. }));
+    rename_taken_ids(&mut doc.nodes, &mut ids);
+
+    total_lines += doc.metadata.total_lines;
+    for (term, title) in doc.metadata.glossary {
+      if !glossary.iter().any(|(known, _)| *known == term) {
+        glossary.push((term, title));
+      }
+    }
+    nodes.push(Node::with_children(
+      NodeKind::Include {
+        src: doc.source_path,
+      },
+      Span::default(),
+      doc.nodes,
+    ));
+  }
+
+  let total_nodes = nodes.iter().map(|n| n.count_nodes()).sum();
+  Ok(Document {
+    source_path: String::new(),
+    doc_type: DocumentType::Markdown,
+    nodes,
+    metadata: DocumentMetadata {
+      title: None,
+      description: None,
+      glossary,
+      date: None,
+      updated: None,
+      authors: Vec::new(),
+      total_lines,
+      total_nodes,
+    },
+  })
+}
+
+/// Give the headings of one chapter ids no earlier chapter uses, and
+/// point the chapter's `#id` links at the renamed ones. `taken` holds the
+/// ids of the chapters before and gains this chapter's.
+fn rename_taken_ids(nodes: &mut [Node], taken: &mut HashMap<String, usize>) {
+  let mut own = Vec::new();
+  heading_ids(nodes, &mut own);
+  let (clashes, fresh): (Vec<_>, Vec<_>) = own.into_iter().partition(|id| taken.contains_key(id));
+  for id in fresh {
+    taken.entry(id).or_insert(0);
+  }
+  let mut renames = HashMap::new();
+  for id in clashes {
+    let base = id.clone();
+    renames.entry(id).or_insert_with(|| dedupe(base, taken));
+  }
+  if !renames.is_empty() {
+    apply_renames(nodes, &renames);
+  }
+}
+
+fn heading_ids(nodes: &[Node], out: &mut Vec<String>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Heading { id: Some(id), .. } => out.push(id.clone()),
+      _ => heading_ids(&node.children, out),
+    }
+  }
+}
+
+fn apply_renames(nodes: &mut [Node], renames: &HashMap<String, String>) {
+  for node in nodes {
+    match &mut node.kind {
+      NodeKind::Heading { id: Some(id), .. } => {
+        if let Some(renamed) = renames.get(id.as_str()) {
+          *id = renamed.clone();
+        }
+      }
+      NodeKind::Link { url, .. } | NodeKind::LinkDefinition { url, .. } => {
+        let target = url.strip_prefix('#').and_then(|id| renames.get(id));
+        if let Some(renamed) = target {
+          *url = format!("#{}", renamed);
+        }
+      }
+      _ => {}
+    }
+    apply_renames(&mut node.children, renames);
+  }
+}
+
+fn normalize(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn book_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bukvar-merge-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("part")).unwrap();
+    fs::write(
+      dir.join("intro.md"),
+      "---\ntitle: Intro\n---\n# Intro\n\nHello\n",
+    )
+    .unwrap();
+    fs::write(
+      dir.join("part/one.md"),
+      "# One\n\nSee [intro](../intro.md)\n",
+    )
+    .unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_merge_toc_directory() {
+    let dir = book_dir("toc");
+    fs::write(dir.join(TOC_FILE), "# chapters\nintro.md\n\npart/one.md\n").unwrap();
+    let out = dir.join("out");
+    let args = Args {
+      output: out.clone(),
+      format: crate::cli::OutputFormat::Sexp,
+      ..Args::default()
+    };
+
+    let path = run(std::slice::from_ref(&dir), &args).unwrap();
+    let name = dir.file_name().unwrap().to_string_lossy();
+    assert_eq!(path, out.join(format!("{}.sexp", name)));
+    let sexp = fs::read_to_string(&path).unwrap();
+    let intro = format!("(include \"{}\"", normalize(&dir.join("intro.md")));
+    let one = format!("(include \"{}\"", normalize(&dir.join("part/one.md")));
+    assert!(sexp.contains(&intro), "{}", sexp);
+    assert!(sexp.find(&intro) < sexp.find(&one));
+    assert!(!sexp.contains("frontmatter"));
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_merge_files() {
+    let dir = book_dir("files");
+    let files = [dir.join("part/one.md"), dir.join("intro.md")];
+    let elements = ElementRegistry::new();
+    let doc = merge(
+      &files,
+      &Args::default(),
+      &elements,
+      &Pipeline::builtin(&elements),
+    )
+    .unwrap();
+
+    assert_eq!(doc.nodes.len(), 2);
+    match &doc.nodes[0].kind {
+      NodeKind::Include { src } => assert!(src.ends_with("part/one.md")),
+      other => panic!("expected Include, got {:?}", other),
+    }
+    // Spans stay relative to each chapter
+    assert_eq!(doc.nodes[1].children[0].span.line, 4);
+    assert_eq!(doc.metadata.total_lines, 4 + 7);
+    assert_eq!(
+      doc.metadata.total_nodes,
+      doc.nodes.iter().map(|n| n.count_nodes()).sum::<usize>()
+    );
+    // A directory needs its toc.txt
+    assert!(chapters(std::slice::from_ref(&dir)).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_merge_dedupes_heading_ids() {
+    let dir = book_dir("ids");
+    fs::write(dir.join("a.md"), "# A\n\n## Setup\n").unwrap();
+    fs::write(
+      dir.join("b.md"),
+      "# B\n\n## Setup\n\nSee [s](#setup) and [a](#a).\n",
+    )
+    .unwrap();
+    let elements = ElementRegistry::new();
+    let doc = merge(
+      &[dir.join("a.md"), dir.join("b.md")],
+      &Args::default(),
+      &elements,
+      &Pipeline::builtin(&elements),
+    )
+    .unwrap();
+
+    let mut ids = Vec::new();
+    heading_ids(&doc.nodes, &mut ids);
+    assert_eq!(ids, ["a", "setup", "b", "setup-1"]);
+    let links: Vec<_> = doc.nodes[1].children[2]
+      .children
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::Link { url, .. } => Some(url.as_str()),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(links, ["#setup-1", "#a"]);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_merge_rejects_other_languages() {
+    let err = merge(
+      &[PathBuf::from("lib.py")],
+      &Args::default(),
+      &ElementRegistry::new(),
+      &Pipeline::sandboxed(),
+    )
+    .unwrap_err();
+    assert!(err.contains("only Markdown"), "{}", err);
+  }
+}
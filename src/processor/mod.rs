@@ -1,106 +1,518 @@
 //! File processor - handles directory traversal and parallel processing
 
 mod files;
+mod include;
+pub mod merge;
 mod parse;
 mod stats;
+pub mod transform;
 mod write;
+mod writer;
 
-use crate::cli::Args;
+use crate::analyze;
+use crate::ast::DocumentType;
+use crate::backlinks::BacklinkIndex;
+use crate::cancel;
+use crate::check::{self, CheckConfig};
+use crate::cli::{Args, DetectMode, DraftPolicy, UnknownPolicy};
+use crate::deprecations;
+use crate::estimate::{self, Estimate, Sample};
+use crate::extract;
+use crate::locale;
+use crate::markdown::ElementRegistry;
+use crate::memory;
+use crate::nav;
+use crate::parsers::ParserRegistry;
+use crate::releases;
+use crate::report::Reporter;
+use crate::validate::lint::LintConfig;
+use crate::xref::{Entry, XrefTable};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub use self::files::collect_files;
-pub use self::stats::ProcessingStats;
+pub use self::files::{collect_files, Collected, Walk};
+pub use self::parse::{detect_parser, parse_content};
+pub use self::stats::{
+  FileReport, Finding, ProcessingStats, Severity, EXIT_INTERRUPTED, EXIT_IO, EXIT_PARSE_ERRORS,
+  EXIT_SUCCESS, EXIT_VALIDATION,
+};
+use self::transform::{
+  ContentHashes, ExtendedAutolinks, HtmlTags, Metadata, Pipeline, Rewrite, RewriteRules, Sanitize,
+  SanitizePolicy, Sections, StripComments, Transform,
+};
+use self::writer::{Output, WriterPool, QUEUE_DEPTH, WRITER_THREADS};
+
+type FileResult = Result<FileReport, FileError>;
+/// `None` for a file left out because the run was cancelled.
+type FileOutcome = Option<FileResult>;
+
+/// Why a file failed.
+#[derive(Debug)]
+struct FileError {
+  message: String,
+  /// An output could not be written, as opposed to the file failing to
+  /// read or parse
+  write: bool,
+}
+
+/// The result of processing a file through `output`, telling failed
+/// writes apart.
+fn file_result(result: Result<FileReport, String>, output: &Output) -> FileResult {
+  result.map_err(|message| FileError {
+    message,
+    write: output.failed(),
+  })
+}
 
 /// Main file processor.
 pub struct FileProcessor {
   args: Args,
   files: Vec<PathBuf>,
+  parsers: ParserRegistry,
+  elements: ElementRegistry,
+  pipeline: Arc<Pipeline>,
+  lint: Option<LintConfig>,
+  reporter: Reporter,
 }
 
 impl FileProcessor {
   pub fn new(args: &Args) -> Result<Self, String> {
+    Self::with_parsers(args, ParserRegistry::new())
+  }
+
+  /// Like `new`, reading files with the parsers in `parsers`. Files are
+  /// still selected by `--extensions`, which must include any extension
+  /// registered for a new parser.
+  pub fn with_parsers(args: &Args, parsers: ParserRegistry) -> Result<Self, String> {
     validate_input(args)?;
-    let files = collect_files(&args.input, &args.extensions, args.recursive)?;
+    let reporter = Reporter::from_args(args);
+    let Collected {
+      mut files,
+      mut skipped,
+    } = collect_files(&args.input, &args.extensions, walk(args))?;
+    if args.unknown == UnknownPolicy::Skip {
+      files.retain(|file| {
+        let known = parse::detect_parser(&parsers, file, args.unknown, args.detect).is_ok();
+        if !known {
+          let reason = match args.detect {
+            DetectMode::Extension => "no parser for this extension",
+            _ => "no parser for this file",
+          };
+          skipped.push((file.clone(), reason));
+        }
+        known
+      });
+    }
+    if args.drafts != DraftPolicy::Include {
+      let only = args.drafts == DraftPolicy::Only;
+      files.retain(|file| {
+        let draft = parse::is_draft_file(&parsers, file, args);
+        if draft != only {
+          let reason = if draft { "draft" } else { "not a draft" };
+          skipped.push((file.clone(), reason));
+        }
+        draft == only
+      });
+    }
+    skipped.sort();
+    for (path, reason) in &skipped {
+      reporter.file_skipped(path, reason);
+    }
     validate_files(&files, args)?;
+    check_output_collisions(&files, args)?;
+    let elements = match &args.elements {
+      Some(path) => ElementRegistry::load(path)?,
+      None => ElementRegistry::new(),
+    };
+    let lint = match (&args.lint_config, args.lint) {
+      (Some(path), _) => Some(LintConfig::load(path)?),
+      (None, true) => Some(LintConfig::default()),
+      (None, false) => None,
+    };
+    let pipeline = build_pipeline(args, &elements)?;
     Ok(Self {
       args: args.clone(),
       files,
+      parsers,
+      pipeline: Arc::new(pipeline),
+      elements,
+      lint,
+      reporter,
     })
   }
 
+  /// Register a pass to run after the built-in transforms.
+  #[allow(dead_code)]
+  pub fn add_transform(&mut self, pass: impl Transform + 'static) -> &mut Self {
+    if let Some(pipeline) = Arc::get_mut(&mut self.pipeline) {
+      pipeline.add(pass);
+    }
+    self
+  }
+
+  /// Process `samples` files spread over the input, counting their outputs
+  /// instead of writing them, and scale the results up to every file
+  /// (`--estimate`).
+  pub fn estimate(&self, samples: usize) -> Estimate {
+    let corpus: Vec<(DocumentType, u64)> = self
+      .files
+      .iter()
+      .map(|file| {
+        let doc_type =
+          parse::detect_parser(&self.parsers, file, self.args.unknown, self.args.detect)
+            .map_or(DocumentType::Markdown, |parser| parser.doc_type);
+        (doc_type, fs::metadata(file).map_or(0, |meta| meta.len()))
+      })
+      .collect();
+    let mut sampled = Vec::new();
+    let mut failed = 0;
+    for index in estimate::sample_indices(self.files.len(), samples) {
+      let file_path = &self.files[index];
+      let output = Output::counting();
+      let start = Instant::now();
+      let result = parse::process_single_file(
+        file_path,
+        &self.args,
+        &self.parsers,
+        &self.elements,
+        &self.pipeline,
+        self.lint.as_ref(),
+        &output,
+      );
+      match result {
+        Ok(report) => sampled.push(Sample {
+          doc_type: report.doc_type,
+          bytes: corpus[index].1,
+          nodes: report.node_count,
+          output_bytes: output.counted(),
+          elapsed: start.elapsed(),
+        }),
+        Err(e) => {
+          failed += 1;
+          self.reporter.file_error(file_path, &e);
+        }
+      }
+    }
+    let threads = if self.args.parallel {
+      self.args.thread_count()
+    } else {
+      1
+    };
+    Estimate::extrapolate(&sampled, failed, &corpus, threads)
+  }
+
   pub fn process_all(&self) -> Result<ProcessingStats, String> {
     fs::create_dir_all(&self.args.output)
       .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
-    if self.args.parallel && self.files.len() > 1 {
-      self.process_parallel()
-    } else {
-      self.process_sequential()
+    if self.args.nice {
+      lower_priority();
     }
-  }
-
-  fn process_sequential(&self) -> Result<ProcessingStats, String> {
-    let mut stats = ProcessingStats::default();
+    let (results, threads) = if self.args.parallel && self.files.len() > 1 {
+      self.process_parallel()?
+    } else {
+      (self.process_sequential(), 1)
+    };
 
-    for file_path in &self.files {
-      match parse::process_single_file(file_path, &self.args) {
-        Ok((doc_type, node_count)) => {
-          stats.add_file(doc_type, node_count);
-          self.log_success(file_path, node_count);
+    // Results line up with `self.files`, so reporting follows input order.
+    let mut stats = ProcessingStats {
+      threads,
+      ..ProcessingStats::default()
+    };
+    for (file_path, result) in self.files.iter().zip(results) {
+      match result {
+        Some(Ok(report)) => {
+          self.reporter.file_done(file_path, &report);
+          self.reporter.findings(file_path, &report.findings);
+          stats.add_file(file_path, report);
         }
-        Err(e) => {
+        Some(Err(e)) => {
           stats.errors += 1;
-          self.log_error(file_path, &e);
+          stats.write_errors += usize::from(e.write);
+          self.reporter.file_error(file_path, &e.message);
         }
+        None => stats.cancelled += 1,
       }
     }
-
+    stats.interrupted = cancel::is_cancelled();
+    if self.args.xref {
+      self.write_xref(&mut stats)?;
+    }
+    if self.args.deprecations {
+      let output = Output::direct();
+      let path = self.args.output.join("deprecations.json");
+      let json = deprecations::to_json(&stats.deprecations);
+      output.write(path, json.into_bytes(), "deprecation report")?;
+      let path = self.args.output.join("deprecations.md");
+      let markdown = deprecations::to_markdown(&stats.deprecations);
+      output.write(path, markdown.into_bytes(), "deprecation report")?;
+    }
+    if self.args.analyze {
+      let path = self.args.output.join("analysis.json");
+      let json = analyze::to_json(&stats.analysis);
+      Output::direct().write(path, json.into_bytes(), "text statistics")?;
+    }
+    if self.args.nav {
+      let path = self.args.output.join("nav.json");
+      let json = nav::to_json(&stats.nav);
+      Output::direct().write(path, json.into_bytes(), "navigation tree")?;
+    }
+    if self.args.validate && !self.args.locales.is_empty() {
+      self.check_translations(&mut stats);
+    }
+    if self.args.backlinks {
+      let path = self.args.output.join("backlinks.json");
+      let json = BacklinkIndex::build(&stats.links).to_json();
+      Output::direct().write(path, json.into_bytes(), "backlinks index")?;
+    }
+    if self.args.releases {
+      let path = self.args.output.join("releases.json");
+      let json = releases::to_json(&stats.releases);
+      Output::direct().write(path, json.into_bytes(), "release notes")?;
+    }
+    if self.args.extract_code.is_some() {
+      let path = self
+        .args
+        .output
+        .join(extract::CODE_DIR)
+        .join("manifest.json");
+      let json = extract::manifest_json(&stats.code);
+      Output::direct().write(path, json.into_bytes(), "code manifest")?;
+    }
+    if let Some(command) = &self.args.check_code {
+      self.check_code(command, &mut stats)?;
+    }
+    if self.args.mem_stats {
+      stats.memory.get_or_insert_with(Default::default).peak_rss = memory::peak_rss();
+    }
     Ok(stats)
   }
 
-  fn process_parallel(&self) -> Result<ProcessingStats, String> {
+  /// Run `--check-code` on the extracted blocks, with the results in
+  /// `code/check.json`. Each block that fails its check is a validation
+  /// error of the file it came from.
+  fn check_code(&self, command: &str, stats: &mut ProcessingStats) -> Result<(), String> {
+    let config = CheckConfig {
+      command: command.to_string(),
+      timeout: Duration::from_secs(self.args.check_timeout),
+      jobs: self.args.thread_count(),
+    };
+    let results = check::run_checks(&stats.code, &self.args.output, &config);
+    let path = self.args.output.join(extract::CODE_DIR).join("check.json");
+    let json = check::to_json(command, &results);
+    Output::direct().write(path, json.into_bytes(), "code check results")?;
+    let mut failed = results.iter().filter(|r| r.status.failed()).peekable();
+    while let Some(first) = failed.next() {
+      let source = &first.block.source;
+      let mut findings = vec![Finding::error(first.message(), first.block.line)];
+      while let Some(result) = failed.next_if(|r| &r.block.source == source) {
+        findings.push(Finding::error(result.message(), result.block.line));
+      }
+      self.add_findings(stats, &self.args.input.join(source), findings);
+    }
+    Ok(())
+  }
+
+  /// With `--locales`, each page missing a translation into one of them is
+  /// a validation warning of the page.
+  fn check_translations(&self, stats: &mut ProcessingStats) {
+    for (path, missing) in locale::missing(&stats.locales, &self.args.locales) {
+      let message = format!("missing translations: {}", missing.join(", "));
+      self.add_findings(
+        stats,
+        &self.args.input.join(path),
+        vec![Finding::warning(message, 0)],
+      );
+    }
+  }
+
+  /// Resolve the references collected with `--xref` into `xref.json` in
+  /// the output directory. With `--validate`, each unresolved reference is
+  /// a validation warning of the file it appears in.
+  fn write_xref(&self, stats: &mut ProcessingStats) -> Result<(), String> {
+    let table = XrefTable::build(&stats.xref);
+    let path = self.args.output.join("xref.json");
+    Output::direct().write(path, table.to_json().into_bytes(), "cross-reference table")?;
+    if !self.args.validate {
+      return Ok(());
+    }
+    let warning = |entry: &Entry| {
+      Finding::warning(
+        format!("unresolved reference: {}", entry.reference.target),
+        entry.reference.line,
+      )
+    };
+    let mut unresolved = table.unresolved().peekable();
+    while let Some(first) = unresolved.next() {
+      let mut findings = vec![warning(first)];
+      while let Some(entry) = unresolved.next_if(|e| e.source == first.source) {
+        findings.push(warning(entry));
+      }
+      self.add_findings(stats, &first.source, findings);
+    }
+    Ok(())
+  }
+
+  /// Report the findings of the file at `path` and count them.
+  fn add_findings(&self, stats: &mut ProcessingStats, path: &Path, findings: Vec<Finding>) {
+    self.reporter.findings(path, &findings);
+    stats.add_findings(path, findings);
+  }
+
+  fn process_sequential(&self) -> Vec<FileOutcome> {
+    self
+      .files
+      .iter()
+      .map(|file_path| {
+        if cancel::is_cancelled() {
+          return None;
+        }
+        let output = Output::direct();
+        let result = parse::process_single_file(
+          file_path,
+          &self.args,
+          &self.parsers,
+          &self.elements,
+          &self.pipeline,
+          self.lint.as_ref(),
+          &output,
+        );
+        Some(file_result(result, &output))
+      })
+      .collect()
+  }
+
+  /// Process the files on `--threads` threads (at most one per file),
+  /// with outputs written by a `WriterPool`. Returns the results and the
+  /// number of parse threads used.
+  fn process_parallel(&self) -> Result<(Vec<FileOutcome>, usize), String> {
     use std::thread;
 
-    let num_threads = thread::available_parallelism()
-      .map(|n| n.get())
-      .unwrap_or(4);
-    let counters = ParallelCounters::new();
+    let num_threads = self.args.thread_count();
     let chunk_size = (self.files.len() + num_threads - 1) / num_threads;
+    let writers = WriterPool::new(WRITER_THREADS, QUEUE_DEPTH);
     let mut handles = Vec::new();
 
-    for chunk in self.files.chunks(chunk_size) {
+    for (n, chunk) in self.files.chunks(chunk_size).enumerate() {
       let chunk: Vec<PathBuf> = chunk.to_vec();
+      let first = n * chunk_size;
+      let outputs: Vec<Output> = (first..first + chunk.len())
+        .map(|index| writers.output(index))
+        .collect();
       let args = self.args.clone();
-      let c = counters.clone();
+      let parsers = self.parsers.clone();
+      let elements = self.elements.clone();
+      let pipeline = Arc::clone(&self.pipeline);
+      let lint = self.lint.clone();
 
       handles.push(thread::spawn(move || {
-        for file_path in chunk {
-          match parse::process_single_file(&file_path, &args) {
-            Ok((doc_type, count)) => c.add_success(doc_type, count),
-            Err(_) => c.add_error(),
-          }
-        }
+        chunk
+          .iter()
+          .zip(&outputs)
+          .map(|(file_path, output)| {
+            if cancel::is_cancelled() {
+              return None;
+            }
+            let result = parse::process_single_file(
+              file_path,
+              &args,
+              &parsers,
+              &elements,
+              &pipeline,
+              lint.as_ref(),
+              output,
+            );
+            Some(file_result(result, output))
+          })
+          .collect::<Vec<_>>()
       }));
     }
 
+    // Chunks are contiguous, so joining in spawn order keeps input order.
+    let threads = handles.len();
+    let mut results = Vec::with_capacity(self.files.len());
     for handle in handles {
-      handle.join().map_err(|_| "Thread panicked")?;
+      results.extend(handle.join().map_err(|_| "Thread panicked")?);
+    }
+    // A file whose output could not be written counts as failed
+    for (index, error) in writers.finish()? {
+      if let Some(Some(result @ Ok(_))) = results.get_mut(index) {
+        *result = Err(FileError {
+          message: error,
+          write: true,
+        });
+      }
     }
+    Ok((results, threads))
+  }
+}
 
-    Ok(counters.into_stats())
+/// Lower the scheduling priority of this thread and the threads it starts
+/// afterwards (`--nice`), so a run shares a busy machine politely.
+#[cfg(unix)]
+fn lower_priority() {
+  extern "C" {
+    fn nice(inc: std::os::raw::c_int) -> std::os::raw::c_int;
+  }
+  // SAFETY: `nice` only adjusts the scheduling priority. A failure (-1 with
+  // errno set) leaves the priority as it was, which is fine to ignore.
+  unsafe {
+    nice(10);
   }
+}
 
-  fn log_success(&self, path: &Path, node_count: usize) {
-    if self.args.verbose {
-      println!("  Processed: {} ({} nodes)", path.display(), node_count);
-    }
+#[cfg(not(unix))]
+fn lower_priority() {}
+
+/// The built-in transforms plus the optional ones selected on the command
+/// line.
+fn build_pipeline(args: &Args, elements: &ElementRegistry) -> Result<Pipeline, String> {
+  let mut pipeline = Pipeline::builtin(elements);
+  if args.vars {
+    pipeline.with_variables();
+  }
+  if args.html_tags {
+    pipeline.add(HtmlTags);
+  }
+  if args.extended_autolinks {
+    pipeline.add(ExtendedAutolinks);
   }
+  if let Some(path) = &args.rewrite_config {
+    pipeline.add(Rewrite {
+      rules: RewriteRules::load(path)?,
+    });
+  }
+  if let Some(mode) = args.sanitize {
+    pipeline.add(Sanitize {
+      policy: SanitizePolicy::new(mode),
+    });
+  }
+  if args.extract_metadata {
+    pipeline.add(Metadata);
+  }
+  if args.section_spans {
+    pipeline.add(Sections);
+  }
+  if args.strip_comments {
+    pipeline.add(StripComments);
+  }
+  if args.content_hashes {
+    pipeline.add(ContentHashes);
+  }
+  Ok(pipeline)
+}
 
-  fn log_error(&self, path: &Path, error: &str) {
-    if self.args.verbose {
-      eprintln!("  Error processing {}: {}", path.display(), error);
-    }
+/// Traversal settings from the command line.
+fn walk(args: &Args) -> Walk {
+  Walk {
+    recursive: args.recursive,
+    follow_symlinks: args.follow_symlinks,
+    hidden: args.hidden,
+    max_depth: args.max_depth,
+    extensionless: args.detect != DetectMode::Extension,
   }
 }
 
@@ -131,59 +543,284 @@ fn validate_files(files: &[PathBuf], args: &Args) -> Result<(), String> {
   Ok(())
 }
 
-#[derive(Clone)]
-struct ParallelCounters {
-  markdown: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-  js: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-  java: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-  python: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-  nodes: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-  errors: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+/// Refuse to run when two inputs would overwrite the same output file.
+fn check_output_collisions(files: &[PathBuf], args: &Args) -> Result<(), String> {
+  let mut seen: HashMap<PathBuf, &Path> = HashMap::with_capacity(files.len());
+  for file in files {
+    let output = write::compute_output_path(file, args);
+    if let Some(previous) = seen.insert(output.clone(), file) {
+      return Err(format!(
+        "Output collision: {} and {} both map to {}{}",
+        previous.display(),
+        file.display(),
+        output.display(),
+        if args.flat { " (drop --flat)" } else { "" }
+      ));
+    }
+  }
+  Ok(())
 }
 
-impl ParallelCounters {
-  fn new() -> Self {
-    use std::sync::atomic::AtomicUsize;
-    use std::sync::Arc;
-    Self {
-      markdown: Arc::new(AtomicUsize::new(0)),
-      js: Arc::new(AtomicUsize::new(0)),
-      java: Arc::new(AtomicUsize::new(0)),
-      python: Arc::new(AtomicUsize::new(0)),
-      nodes: Arc::new(AtomicUsize::new(0)),
-      errors: Arc::new(AtomicUsize::new(0)),
-    }
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_output_collisions() {
+    let files = vec![PathBuf::from("in/a/x.md"), PathBuf::from("in/b/x.md")];
+    let mut args = Args {
+      input: PathBuf::from("in"),
+      output: PathBuf::from("out"),
+      ..Args::default()
+    };
+    assert!(check_output_collisions(&files, &args).is_ok());
+
+    args.flat = true;
+    let err = check_output_collisions(&files, &args).unwrap_err();
+    assert!(
+      err.starts_with("Output collision: in/a/x.md and in/b/x.md"),
+      "{}",
+      err
+    );
   }
 
-  fn add_success(&self, doc_type: crate::ast::DocumentType, node_count: usize) {
-    use crate::ast::DocumentType;
-    use std::sync::atomic::Ordering;
+  #[test]
+  fn test_registered_parser() {
+    use crate::ast::{Document, DocumentType};
+    use crate::parsers::Parser;
 
-    match doc_type {
-      DocumentType::Markdown => self.markdown.fetch_add(1, Ordering::Relaxed),
-      DocumentType::JavaScript | DocumentType::TypeScript => {
-        self.js.fetch_add(1, Ordering::Relaxed)
-      }
-      DocumentType::Java => self.java.fetch_add(1, Ordering::Relaxed),
-      DocumentType::Python => self.python.fetch_add(1, Ordering::Relaxed),
+    let dir = std::env::temp_dir().join(format!("bukvar-parsers-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("notes.txt"), "plain").unwrap();
+    fs::write(dir.join("a.md"), "# A\n").unwrap();
+    let args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      format: crate::cli::OutputFormat::Sexp,
+      extensions: vec!["md".to_string(), "txt".to_string()],
+      parallel: false,
+      quiet: true,
+      ..Args::default()
     };
-    self.nodes.fetch_add(node_count, Ordering::Relaxed);
+
+    let mut parsers = ParserRegistry::new();
+    let empty = Parser::new("empty", DocumentType::Markdown, |_, _| {
+      Document::new(DocumentType::Markdown)
+    });
+    parsers.register(&["txt"], empty);
+    let stats = FileProcessor::with_parsers(&args, parsers)
+      .unwrap()
+      .process_all()
+      .unwrap();
+
+    assert_eq!(stats.errors, 0);
+    assert_eq!(stats.files.get(&DocumentType::Markdown), Some(&2));
+    let notes = fs::read_to_string(dir.join("out/notes.txt.sexp")).unwrap();
+    assert!(!notes.contains("plain"), "{}", notes);
+    fs::remove_dir_all(&dir).unwrap();
   }
 
-  fn add_error(&self) {
-    use std::sync::atomic::Ordering;
-    self.errors.fetch_add(1, Ordering::Relaxed);
+  #[test]
+  fn test_streaming_threshold() {
+    let dir = std::env::temp_dir().join(format!("bukvar-threshold-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("small.md"), "# Small\n").unwrap();
+    fs::write(dir.join("large.md"), "# Large\n\n".repeat(100)).unwrap();
+    let mut args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      parallel: false,
+      quiet: true,
+      streaming_threshold: Some(64),
+      ..Args::default()
+    };
+
+    let stats = FileProcessor::new(&args).unwrap().process_all().unwrap();
+    assert_eq!((stats.total_files(), stats.streamed), (2, 1));
+
+    args.streaming_threshold = None;
+    let stats = FileProcessor::new(&args).unwrap().process_all().unwrap();
+    assert_eq!(stats.streamed, 0);
+    fs::remove_dir_all(&dir).unwrap();
   }
 
-  fn into_stats(self) -> ProcessingStats {
-    use std::sync::atomic::Ordering;
-    ProcessingStats {
-      markdown_files: self.markdown.load(Ordering::Relaxed),
-      js_files: self.js.load(Ordering::Relaxed),
-      java_files: self.java.load(Ordering::Relaxed),
-      python_files: self.python.load(Ordering::Relaxed),
-      total_nodes: self.nodes.load(Ordering::Relaxed),
-      errors: self.errors.load(Ordering::Relaxed),
+  #[test]
+  fn test_drafts() {
+    let dir = std::env::temp_dir().join(format!("bukvar-drafts-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("draft.md"), "---\ndraft: true\n---\n# Draft\n").unwrap();
+    fs::write(
+      dir.join("hidden.md"),
+      "+++\npublish = false\n+++\n# Hidden\n",
+    )
+    .unwrap();
+    fs::write(dir.join("done.md"), "---\ndraft: false\n---\n# Done\n").unwrap();
+    fs::write(dir.join("plain.md"), "# Plain\n").unwrap();
+    let mut args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      parallel: false,
+      quiet: true,
+      ..Args::default()
+    };
+    let processed = |args: &Args| {
+      let processor = FileProcessor::new(args).unwrap();
+      let mut names: Vec<String> = processor
+        .files
+        .iter()
+        .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+      names.sort();
+      names
+    };
+
+    assert_eq!(processed(&args).len(), 4);
+    args.drafts = DraftPolicy::Skip;
+    assert_eq!(processed(&args), ["done.md", "plain.md"]);
+    args.drafts = DraftPolicy::Only;
+    assert_eq!(processed(&args), ["draft.md", "hidden.md"]);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_validate_duplicate_explicit_ids() {
+    let dir = std::env::temp_dir().join(format!("bukvar-dup-ids-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("a.md"),
+      "# Intro {#intro}\n\nx\n\n## One {#intro}\n\n## Two {#intro}\n",
+    )
+    .unwrap();
+    let args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      parallel: false,
+      quiet: true,
+      validate: true,
+      fail_on_warning: true,
+      ..Args::default()
+    };
+
+    // The slug pass runs first and must not have renamed the repeats
+    let stats = FileProcessor::new(&args).unwrap().process_all().unwrap();
+    assert_eq!(stats.validation_warnings, 2);
+    assert_eq!(stats.exit_code(&args), stats::EXIT_VALIDATION);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_validate_api_reference_source() {
+    let dir = std::env::temp_dir().join(format!("bukvar-ref-validate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("A.java"),
+      "public class A {\n  /**\n   * Runs.\n   * @since 3.0\n   */\n  void run() {}\n}\n",
+    )
+    .unwrap();
+    let args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      parallel: false,
+      quiet: true,
+      validate: true,
+      api_reference: true,
+      current_version: crate::parsers::version::Version::parse("2.0"),
+      ..Args::default()
+    };
+
+    // The projection's headings jump levels and have no source lines
+    let stats = FileProcessor::new(&args).unwrap().process_all().unwrap();
+    let messages: Vec<_> = stats
+      .findings
+      .iter()
+      .flat_map(|(_, findings)| findings)
+      .map(|f| (f.message.as_str(), f.line))
+      .collect();
+    assert_eq!(
+      messages,
+      [("@since 3.0 is newer than the current version 2.0", 2)]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_api_reference_keeps_doctests() {
+    let dir = std::env::temp_dir().join(format!("bukvar-ref-doctest-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("m.py"),
+      "def f():\n    \"\"\"Double.\n\n    >>> f()\n    2\n    \"\"\"\n",
+    )
+    .unwrap();
+    let args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      parallel: false,
+      quiet: true,
+      api_reference: true,
+      extract_doctests: true,
+      ..Args::default()
+    };
+
+    FileProcessor::new(&args).unwrap().process_all().unwrap();
+    let doctests = fs::read_to_string(dir.join("out/m.py.doctest.txt")).unwrap();
+    assert!(doctests.contains(">>> f()"), "{}", doctests);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_write_errors_exit_io() {
+    let dir = std::env::temp_dir().join(format!("bukvar-write-err-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("in/sub")).unwrap();
+    fs::write(dir.join("in/sub/a.md"), "# A\n").unwrap();
+    fs::write(dir.join("in/b.md"), "# B\n").unwrap();
+    // A file where the output directory for `sub` belongs
+    fs::create_dir_all(dir.join("out")).unwrap();
+    fs::write(dir.join("out/sub"), "").unwrap();
+
+    for parallel in [false, true] {
+      let args = Args {
+        input: dir.join("in"),
+        output: dir.join("out"),
+        parallel,
+        quiet: true,
+        ..Args::default()
+      };
+      let stats = FileProcessor::new(&args).unwrap().process_all().unwrap();
+      assert_eq!((stats.errors, stats.write_errors), (1, 1));
+      assert_eq!(stats.exit_code(&args), EXIT_IO);
     }
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_trace_parse() {
+    let dir = std::env::temp_dir().join(format!("bukvar-trace-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.md"), "> `x`\n\n| a |\n").unwrap();
+    let args = Args {
+      input: dir.clone(),
+      output: dir.join("out"),
+      parallel: false,
+      quiet: true,
+      trace_parse: true,
+      ..Args::default()
+    };
+
+    FileProcessor::new(&args).unwrap().process_all().unwrap();
+    let trace = fs::read_to_string(dir.join("out/a.md.trace.txt")).unwrap();
+    assert!(trace.contains("  1:3 inline code_span accepted\n"));
+    assert!(trace.contains("1:1 block blockquote accepted\n"));
+    assert!(trace.contains("3:1 block table rejected\n3:1 block indented_code rejected\n"));
+    assert!(trace.ends_with("3:1 block paragraph accepted\n"));
+    fs::remove_dir_all(&dir).unwrap();
   }
 }
@@ -1,32 +1,153 @@
 //! File parsing utilities.
 
+use crate::analyze::Analysis;
+use crate::anchors::AnchorMap;
 use crate::ast::{Document, DocumentType};
-use crate::cli::Args;
-use crate::markdown::MarkdownParser;
-use crate::parsers::{JavaDocParser, JsDocParser, PyDocParser};
+use crate::backlinks;
+use crate::cli::{Args, DetectMode, UnknownPolicy};
+use crate::deprecations;
+use crate::extract::{self, ExtractedCode};
+use crate::lines::LineIndex;
+use crate::locale::PageLocale;
+use crate::markdown::trace::{self, TraceLog};
+use crate::markdown::{read_frontmatter, ElementRegistry, ParseOptions};
+use crate::memory::{self, FileMemory};
+use crate::nav::NavPage;
+use crate::parsers::coverage::{self, Coverage};
+use crate::parsers::pydoc::doctest;
+use crate::parsers::reference;
+use crate::parsers::sniff::{sniff, SNIFF_BYTES};
+use crate::parsers::{ParseContext, Parser, ParserRegistry};
+use crate::releases;
 use crate::sourcemap::SourceMap;
 use crate::streaming;
 use crate::validate;
+use crate::validate::lint::{self, LintConfig};
+use crate::xref::FileXref;
 
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
 
+use super::stats::{FileReport, Finding};
+use super::transform::{frontmatter_vars, is_draft, PassTiming, Pipeline};
 use super::write;
+use super::writer::Output;
 
-/// Parse a single file and write output.
-pub fn process_single_file(file_path: &Path, args: &Args) -> Result<(DocumentType, usize), String> {
-  let doc_type = detect_doc_type(file_path)?;
-  let mut doc = parse_file(file_path, doc_type, args)?;
+/// Parse a single file and hand its outputs to `output`.
+pub fn process_single_file(
+  file_path: &Path,
+  args: &Args,
+  parsers: &ParserRegistry,
+  elements: &ElementRegistry,
+  pipeline: &Pipeline,
+  lint: Option<&LintConfig>,
+  output: &Output,
+) -> Result<FileReport, String> {
+  let parser = detect_parser(parsers, file_path, args.unknown, args.detect)?;
+  let doc_type = parser.doc_type;
+  let streamed = doc_type == DocumentType::Markdown && use_streaming(file_path, args);
+  let (mut doc, coverage, trace) = parse_file(file_path, &parser, streamed, args, elements)?;
 
   doc.source_path = normalize_path(file_path);
+  let xref = (args.xref && doc_type != DocumentType::Markdown)
+    .then(|| FileXref::collect(&doc, coverage.as_ref()));
+  let deprecations = if args.deprecations {
+    deprecations::collect(&doc, coverage.as_ref())
+  } else {
+    Vec::new()
+  };
+  let analysis = args.analyze.then(|| {
+    let bytes = fs::metadata(file_path).map_or(0, |meta| meta.len() as usize);
+    Analysis::of(&doc, bytes)
+  });
+  let links = args.backlinks.then(|| backlinks::collect(&doc));
+  let releases = if args.releases && doc_type == DocumentType::Markdown {
+    releases::collect(&doc)
+  } else {
+    Vec::new()
+  };
+  let code = match &args.extract_code {
+    Some(languages) => write_code(&doc, file_path, args, languages, output)?,
+    None => Vec::new(),
+  };
+  // Validation and doctests use the parsed comments, whose spans point
+  // into the source, rather than the projection built from them
+  let mut parsed = None;
+  if args.api_reference && doc_type != DocumentType::Markdown {
+    let projected = reference::project(&doc, coverage.as_ref());
+    parsed = Some(std::mem::replace(&mut doc, projected));
+  }
+  let timings = run_transforms(&mut doc, pipeline, args)?;
+  let locale = (args.nav || !args.locales.is_empty())
+    .then(|| PageLocale::of_document(&doc, relative_path(file_path, args), &args.locales));
+  let nav = locale
+    .clone()
+    .filter(|_| args.nav)
+    .map(|locale| NavPage::from_document(&doc, locale.path.clone()).with_locale(locale));
+  let locale = locale.filter(|_| !args.locales.is_empty());
   let node_count = doc.metadata.total_nodes;
+  let memory = args.mem_stats.then(|| FileMemory {
+    source_bytes: fs::metadata(file_path).map_or(0, |meta| meta.len() as usize),
+    ast_bytes: memory::ast_bytes(&doc),
+  });
+
+  // Read once for the passes that need the text, and indexed so findings
+  // in inline nodes get their line
+  let lint = lint.filter(|_| doc_type == DocumentType::Markdown);
+  let source = if args.validate || args.sourcemap || lint.is_some() {
+    Some(read_file_content(file_path)?)
+  } else {
+    None
+  };
+  let lines = source.as_deref().map(LineIndex::new);
 
-  run_validation_if_enabled(&doc, file_path, args);
-  write_sourcemap_if_enabled(&doc, file_path, args)?;
-  write::write_output(&doc, file_path, args)?;
+  let source_doc = parsed.as_ref().unwrap_or(&doc);
+  let mut findings = run_validation_if_enabled(source_doc, args, lines.as_ref());
+  if let (Some(config), Some(source)) = (lint, &source) {
+    findings.extend(run_lint(&doc, source, config));
+  }
+  if let Some(trace) = trace {
+    let trace_path = write::output_path(file_path, args, "trace.txt");
+    output.write(trace_path, trace.into_bytes(), "parse trace")?;
+  }
+  write_sourcemap_if_enabled(
+    &doc,
+    file_path,
+    args,
+    source.as_deref().zip(lines.as_ref()),
+    output,
+  )?;
+  write_anchors_if_enabled(&doc, file_path, args, output)?;
+  write_doctests_if_enabled(source_doc, file_path, args, output)?;
+  write::write_output(&doc, file_path, args, output)?;
 
-  Ok((doc_type, node_count))
+  Ok(FileReport {
+    doc_type,
+    node_count,
+    streamed,
+    findings,
+    coverage,
+    xref,
+    deprecations,
+    analysis,
+    nav,
+    locale,
+    links,
+    releases,
+    code,
+    memory,
+    timings,
+  })
+}
+
+/// Path of a file relative to the input directory, as the navigation tree
+/// and the code manifest list it.
+fn relative_path(file_path: &Path, args: &Args) -> PathBuf {
+  match file_path.strip_prefix(&args.input) {
+    Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+    _ => PathBuf::from(file_path.file_name().unwrap_or_default()),
+  }
 }
 
 /// Normalize path separators to forward slashes.
@@ -34,42 +155,128 @@ fn normalize_path(path: &Path) -> String {
   path.to_string_lossy().replace('\\', "/")
 }
 
-fn detect_doc_type(file_path: &Path) -> Result<DocumentType, String> {
+/// Parser for a file by its extension, its content or both; with
+/// `--unknown=text` anything unrecognized is Markdown.
+pub fn detect_parser(
+  parsers: &ParserRegistry,
+  file_path: &Path,
+  unknown: UnknownPolicy,
+  detect: DetectMode,
+) -> Result<Parser, String> {
   let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-  DocumentType::from_extension(extension).ok_or_else(|| {
-    format!(
+  let by_extension = || parsers.get(extension).cloned();
+  let by_content = || {
+    let doc_type = sniff(&read_file_head(file_path)?)?;
+    Some(
+      parsers
+        .get(doc_type.extension())
+        .cloned()
+        .unwrap_or_else(|| Parser::builtin(doc_type)),
+    )
+  };
+  let found = match detect {
+    DetectMode::Extension => by_extension(),
+    DetectMode::Content => by_content(),
+    DetectMode::Both => by_extension().or_else(by_content),
+  };
+  match found {
+    Some(parser) => Ok(parser),
+    None if unknown == UnknownPolicy::Text => Ok(Parser::builtin(DocumentType::Markdown)),
+    None if detect == DetectMode::Extension => Err(format!(
       "Unknown file extension: {} in {}",
       extension,
       file_path.display()
-    )
-  })
+    )),
+    None => Err(format!("Unrecognized file type: {}", file_path.display())),
+  }
 }
 
-fn parse_file(file_path: &Path, doc_type: DocumentType, args: &Args) -> Result<Document, String> {
-  match (args.streaming, doc_type) {
-    (true, DocumentType::Markdown) => parse_streaming(file_path),
-    _ => parse_normal(file_path, doc_type),
+/// Whether a file is Markdown marked as a draft in its frontmatter; only
+/// the frontmatter is read.
+pub fn is_draft_file(parsers: &ParserRegistry, file_path: &Path, args: &Args) -> bool {
+  let markdown = detect_parser(parsers, file_path, args.unknown, args.detect)
+    .is_ok_and(|parser| parser.doc_type == DocumentType::Markdown);
+  markdown
+    && File::open(file_path)
+      .ok()
+      .and_then(|file| read_frontmatter(BufReader::new(file)))
+      .is_some_and(|frontmatter| is_draft(&frontmatter_vars(&[frontmatter])))
+}
+
+/// The first `SNIFF_BYTES` of a file, enough for `sniff`.
+fn read_file_head(file_path: &Path) -> Option<String> {
+  let mut head = Vec::with_capacity(SNIFF_BYTES);
+  File::open(file_path)
+    .ok()?
+    .take(SNIFF_BYTES as u64)
+    .read_to_end(&mut head)
+    .ok()?;
+  Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Whether a Markdown file takes the streaming path: always with
+/// `--streaming`, and above `--streaming-threshold` bytes otherwise.
+fn use_streaming(file_path: &Path, args: &Args) -> bool {
+  args.streaming
+    || args
+      .streaming_threshold
+      .is_some_and(|threshold| fs::metadata(file_path).is_ok_and(|meta| meta.len() > threshold))
+}
+
+/// The document, its doc coverage for sources, and the `--trace-parse`
+/// log for Markdown that is not streamed.
+type Parsed = (Document, Option<Coverage>, Option<String>);
+
+fn parse_file(
+  file_path: &Path,
+  parser: &Parser,
+  streamed: bool,
+  args: &Args,
+  elements: &ElementRegistry,
+) -> Result<Parsed, String> {
+  let options = args.parse_options();
+  if streamed {
+    return Ok((parse_streaming(file_path, elements, options)?, None, None));
   }
+  let cx = ParseContext::new(elements, options).with_threads(args.block_threads());
+  let trace = args.trace_parse && parser.doc_type == DocumentType::Markdown;
+  parse_normal(file_path, parser, &cx, trace)
 }
 
-fn parse_streaming(file_path: &Path) -> Result<Document, String> {
+fn parse_streaming(
+  file_path: &Path,
+  elements: &ElementRegistry,
+  options: ParseOptions,
+) -> Result<Document, String> {
   let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
-  Ok(streaming::parse_streaming(file))
+  Ok(streaming::parse_streaming(file, elements, options))
 }
 
-fn parse_normal(file_path: &Path, doc_type: DocumentType) -> Result<Document, String> {
+fn parse_normal(
+  file_path: &Path,
+  parser: &Parser,
+  cx: &ParseContext,
+  trace: bool,
+) -> Result<Parsed, String> {
   let content = read_file_content(file_path)?;
+  let coverage = coverage::measure(&content, parser.doc_type);
+  if !trace {
+    return Ok((parser.parse(&content, cx), coverage, None));
+  }
+  let mut log = TraceLog::new(&content);
+  let doc = trace::record(&mut log, || parser.parse(&content, cx));
+  Ok((doc, coverage, Some(log.finish())))
+}
 
-  Ok(match doc_type {
-    DocumentType::Markdown => MarkdownParser::new(&content).parse(),
-    DocumentType::JavaScript | DocumentType::TypeScript => {
-      let mut doc = JsDocParser::new(&content).parse();
-      doc.doc_type = doc_type;
-      doc
-    }
-    DocumentType::Java => JavaDocParser::new(&content).parse(),
-    DocumentType::Python => PyDocParser::new(&content).parse(),
-  })
+/// Parse in-memory source with the parser for `doc_type`; `options` affect
+/// Markdown and, with `all_strings`, Python.
+pub fn parse_content(
+  content: &str,
+  doc_type: DocumentType,
+  elements: &ElementRegistry,
+  options: ParseOptions,
+) -> Document {
+  Parser::builtin(doc_type).parse(content, &ParseContext::new(elements, options))
 }
 
 fn read_file_content(file_path: &Path) -> Result<String, String> {
@@ -81,43 +288,148 @@ fn read_file_content(file_path: &Path) -> Result<String, String> {
   Ok(content)
 }
 
-fn run_validation_if_enabled(doc: &Document, file_path: &Path, args: &Args) {
-  if !args.validate {
-    return;
-  }
-
-  let result = validate::validate(doc);
+/// Run the pipeline, returning its pass timings for `--verbose`.
+fn run_transforms(
+  doc: &mut Document,
+  pipeline: &Pipeline,
+  args: &Args,
+) -> Result<Vec<PassTiming>, String> {
+  let timings = pipeline.run(doc)?;
+  Ok(if args.verbose { timings } else { Vec::new() })
+}
 
-  if !result.is_ok() {
-    eprintln!("Validation errors in {}:", file_path.display());
-    result
-      .errors
-      .iter()
-      .for_each(|e| eprintln!("  [ERROR] {} at line {}", e.message, e.line));
+/// Validation findings, errors first; empty without `--validate`.
+fn run_validation_if_enabled(
+  doc: &Document,
+  args: &Args,
+  lines: Option<&LineIndex>,
+) -> Vec<Finding> {
+  if !args.validate {
+    return Vec::new();
   }
 
-  if result.has_warnings() {
-    eprintln!("Validation warnings in {}:", file_path.display());
+  let mut result = match lines {
+    Some(lines) => validate::validate_with_lines(doc, lines),
+    None => validate::validate(doc),
+  };
+  if let Some(current) = &args.current_version {
     result
       .warnings
-      .iter()
-      .for_each(|w| eprintln!("  [WARN] {} at line {}", w.message, w.line));
+      .extend(validate::check_versions(doc, current));
   }
+
+  let errors = result
+    .errors
+    .into_iter()
+    .map(|e| Finding::error(e.message, e.line));
+  let warnings = result
+    .warnings
+    .into_iter()
+    .map(|w| Finding::warning(w.message, w.line));
+  errors.chain(warnings).collect()
+}
+
+/// Lint findings, all warnings.
+fn run_lint(doc: &Document, source: &str, config: &LintConfig) -> Vec<Finding> {
+  lint::lint(doc, source, config)
+    .into_iter()
+    .map(|w| Finding {
+      rule: Some(w.rule.unwrap_or("lint")),
+      ..Finding::warning(w.message, w.line)
+    })
+    .collect()
 }
 
-fn write_sourcemap_if_enabled(doc: &Document, file_path: &Path, args: &Args) -> Result<(), String> {
+/// `source` is the file's text and index, which give inline nodes their
+/// line and column.
+fn write_sourcemap_if_enabled(
+  doc: &Document,
+  file_path: &Path,
+  args: &Args,
+  source: Option<(&str, &LineIndex)>,
+  output: &Output,
+) -> Result<(), String> {
   if !args.sourcemap {
     return Ok(());
   }
 
-  let map = SourceMap::from_document(doc);
+  let mut map = SourceMap::from_document(doc);
+  if let Some((source, lines)) = source {
+    map = map.with_lines(lines);
+    if args.sourcemap_utf16 {
+      map = map.with_utf16(source, lines);
+    }
+  }
   let json = map.to_json();
 
-  let file_name = file_path
-    .file_name()
-    .and_then(|s| s.to_str())
-    .unwrap_or("output");
-  let map_path = args.output.join(format!("{}.map.json", file_name));
+  let map_path = write::output_path(file_path, args, "map.json");
+  output.write(map_path, json.into_bytes(), "sourcemap")
+}
+
+/// Write the file's code blocks under `code/`; returns their manifest
+/// entries.
+fn write_code(
+  doc: &Document,
+  file_path: &Path,
+  args: &Args,
+  languages: &[String],
+  output: &Output,
+) -> Result<Vec<ExtractedCode>, String> {
+  let blocks = extract::extract(doc, &relative_path(file_path, args), languages);
+  let mut code = Vec::with_capacity(blocks.len());
+  for (block, content) in blocks {
+    output.write(
+      args.output.join(&block.path),
+      content.into_bytes(),
+      "code block",
+    )?;
+    code.push(block);
+  }
+  Ok(code)
+}
+
+fn write_anchors_if_enabled(
+  doc: &Document,
+  file_path: &Path,
+  args: &Args,
+  output: &Output,
+) -> Result<(), String> {
+  if !args.anchors {
+    return Ok(());
+  }
+
+  let json = AnchorMap::from_document(doc).to_json();
+  let anchors_path = write::output_path(file_path, args, "anchors.json");
+  output.write(anchors_path, json.into_bytes(), "anchor map")
+}
+
+fn write_doctests_if_enabled(
+  doc: &Document,
+  file_path: &Path,
+  args: &Args,
+  output: &Output,
+) -> Result<(), String> {
+  if !args.extract_doctests || doc.doc_type != DocumentType::Python {
+    return Ok(());
+  }
+
+  let Some(text) = doctest::render(doc, &module_name(file_path)) else {
+    return Ok(());
+  };
+
+  let doctest_path = write::output_path(file_path, args, "doctest.txt");
+  output.write(doctest_path, text.into_bytes(), "doctests")
+}
 
-  std::fs::write(&map_path, json).map_err(|e| format!("Failed to write sourcemap: {}", e))
+/// Importable module name: the file stem, or the package for `__init__.py`.
+fn module_name(file_path: &Path) -> String {
+  let stem = file_path.file_stem().and_then(|s| s.to_str());
+  let name = match stem {
+    Some("__init__") => file_path
+      .parent()
+      .and_then(|p| p.file_name())
+      .and_then(|s| s.to_str()),
+    other => other,
+  };
+  name.unwrap_or("module").to_string()
 }
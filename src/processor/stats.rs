@@ -1,29 +1,332 @@
-//! Processing statistics.
+//! Processing statistics and the exit codes derived from them.
 
+use crate::analyze::Analysis;
 use crate::ast::DocumentType;
+use crate::backlinks::OutgoingLink;
+use crate::cli::Args;
+use crate::deprecations::Deprecation;
+use crate::extract::ExtractedCode;
+use crate::locale::PageLocale;
+use crate::memory::{FileMemory, MemoryStats};
+use crate::nav::NavPage;
+use crate::parsers::coverage::{self, Coverage};
+use crate::releases::Release;
+use crate::xref::FileXref;
+
+use super::transform::PassTiming;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Everything processed without (failing) problems.
+pub const EXIT_SUCCESS: i32 = 0;
+/// One or more files failed to parse.
+pub const EXIT_PARSE_ERRORS: i32 = 2;
+/// Validation found errors (`--fail-on-error`) or warnings (`--fail-on-warning`),
+/// or documentation coverage is below `--min-doc-coverage`.
+pub const EXIT_VALIDATION: i32 = 3;
+/// Bad arguments or configuration, the input could not be read, or an
+/// output could not be written.
+pub const EXIT_IO: i32 = 4;
+/// Stopped by Ctrl-C (128 + SIGINT, as shells report it).
+pub const EXIT_INTERRUPTED: i32 = 130;
 
 #[derive(Debug, Default)]
 pub struct ProcessingStats {
-  pub markdown_files: usize,
-  pub js_files: usize,
-  pub java_files: usize,
-  pub python_files: usize,
+  /// Files processed per document type.
+  pub files: BTreeMap<DocumentType, usize>,
   pub total_nodes: usize,
+  /// Markdown files parsed on the streaming path.
+  pub streamed: usize,
   pub errors: usize,
+  /// Of `errors`, files whose outputs could not be written.
+  pub write_errors: usize,
+  pub validation_errors: usize,
+  pub validation_warnings: usize,
+  /// Validation and lint findings of each file that has any, in the order
+  /// they were reported.
+  pub findings: Vec<(PathBuf, Vec<Finding>)>,
+  /// Documentation coverage of each source file, in input order.
+  pub coverage: Vec<(PathBuf, Coverage)>,
+  /// Symbols and references of each source file with `--xref`, in input order.
+  pub xref: Vec<(PathBuf, FileXref)>,
+  /// Deprecations of each source file that has any, with `--deprecations`.
+  pub deprecations: Vec<(PathBuf, Vec<Deprecation>)>,
+  /// Text statistics of each file with `--analyze`, in input order.
+  pub analysis: Vec<(PathBuf, Analysis)>,
+  /// Navigation entry of each file with `--nav`, in input order.
+  pub nav: Vec<NavPage>,
+  /// Locale of each file with `--locales`, in input order.
+  pub locales: Vec<PageLocale>,
+  /// Relative links of each file with `--backlinks`, in input order.
+  pub links: Vec<(PathBuf, Vec<OutgoingLink>)>,
+  /// Releases of each changelog with `--releases`, in input order.
+  pub releases: Vec<(PathBuf, Vec<Release>)>,
+  /// Code blocks written with `--extract-code`, in input order.
+  pub code: Vec<ExtractedCode>,
+  /// Peak memory and AST sizes with `--mem-stats`.
+  pub memory: Option<MemoryStats>,
+  /// Worker threads the files were processed on.
+  pub threads: usize,
+  /// The run was stopped by Ctrl-C.
+  pub interrupted: bool,
+  /// Files not started because the run was stopped.
+  pub cancelled: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+impl Severity {
+  pub fn name(self) -> &'static str {
+    match self {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+    }
+  }
+}
+
+/// One validation or lint finding, kept for the reporter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+  pub severity: Severity,
+  /// Lint rule; `None` for validation findings.
+  pub rule: Option<&'static str>,
+  pub message: String,
+  /// 1-based; 0 when the finding is about the whole file.
+  pub line: usize,
+}
+
+impl Finding {
+  pub fn error(message: impl Into<String>, line: usize) -> Self {
+    Self {
+      severity: Severity::Error,
+      rule: None,
+      message: message.into(),
+      line,
+    }
+  }
+
+  pub fn warning(message: impl Into<String>, line: usize) -> Self {
+    Self {
+      severity: Severity::Warning,
+      rule: None,
+      message: message.into(),
+      line,
+    }
+  }
+}
+
+/// Outcome of one successfully processed file.
+#[derive(Debug, Clone)]
+pub struct FileReport {
+  pub doc_type: DocumentType,
+  pub node_count: usize,
+  /// Parsed by the streaming parser rather than in memory.
+  pub streamed: bool,
+  /// Validation and lint findings; empty without `--validate` or `--lint`.
+  pub findings: Vec<Finding>,
+  /// `None` for Markdown.
+  pub coverage: Option<Coverage>,
+  /// `None` for Markdown and without `--xref`.
+  pub xref: Option<FileXref>,
+  /// Empty without `--deprecations`.
+  pub deprecations: Vec<Deprecation>,
+  /// `None` without `--analyze`.
+  pub analysis: Option<Analysis>,
+  /// `None` without `--nav`.
+  pub nav: Option<NavPage>,
+  /// `None` without `--locales`.
+  pub locale: Option<PageLocale>,
+  /// `None` without `--backlinks`.
+  pub links: Option<Vec<OutgoingLink>>,
+  /// Empty without `--releases` and for documents that are not changelogs.
+  pub releases: Vec<Release>,
+  /// Empty without `--extract-code`.
+  pub code: Vec<ExtractedCode>,
+  /// `None` without `--mem-stats`.
+  pub memory: Option<FileMemory>,
+  /// Time spent in each transform pass; empty without `--verbose`.
+  pub timings: Vec<PassTiming>,
 }
 
 impl ProcessingStats {
   pub fn total_files(&self) -> usize {
-    self.markdown_files + self.js_files + self.java_files + self.python_files
+    self.files.values().sum()
+  }
+
+  pub fn files_of(&self, doc_type: DocumentType) -> usize {
+    self.files.get(&doc_type).copied().unwrap_or(0)
+  }
+
+  pub fn add_file(&mut self, path: &Path, report: FileReport) {
+    *self.files.entry(report.doc_type).or_insert(0) += 1;
+    self.total_nodes += report.node_count;
+    self.streamed += usize::from(report.streamed);
+    self.add_findings(path, report.findings);
+    if let Some(coverage) = report.coverage {
+      self.coverage.push((path.to_path_buf(), coverage));
+    }
+    if let Some(xref) = report.xref {
+      self.xref.push((path.to_path_buf(), xref));
+    }
+    if !report.deprecations.is_empty() {
+      self
+        .deprecations
+        .push((path.to_path_buf(), report.deprecations));
+    }
+    if let Some(analysis) = report.analysis {
+      self.analysis.push((path.to_path_buf(), analysis));
+    }
+    if let Some(page) = report.nav {
+      self.nav.push(page);
+    }
+    if let Some(locale) = report.locale {
+      self.locales.push(locale);
+    }
+    if let Some(links) = report.links {
+      self.links.push((path.to_path_buf(), links));
+    }
+    if !report.releases.is_empty() {
+      self.releases.push((path.to_path_buf(), report.releases));
+    }
+    self.code.extend(report.code);
+    if let Some(memory) = report.memory {
+      self
+        .memory
+        .get_or_insert_with(MemoryStats::default)
+        .add_file(path, memory);
+    }
+  }
+
+  /// Count the findings of the file at `path` and keep them for the
+  /// summary.
+  pub fn add_findings(&mut self, path: &Path, findings: Vec<Finding>) {
+    if findings.is_empty() {
+      return;
+    }
+    let errors = findings
+      .iter()
+      .filter(|f| f.severity == Severity::Error)
+      .count();
+    self.validation_errors += errors;
+    self.validation_warnings += findings.len() - errors;
+    self.findings.push((path.to_path_buf(), findings));
+  }
+
+  /// Aggregate (documented, total) declarations; `None` without source files.
+  pub fn doc_coverage(&self) -> Option<(usize, usize)> {
+    if self.coverage.is_empty() {
+      return None;
+    }
+    Some(
+      self
+        .coverage
+        .iter()
+        .fold((0, 0), |(documented, total), (_, c)| {
+          (documented + c.documented(), total + c.total())
+        }),
+    )
   }
 
-  pub fn add_file(&mut self, doc_type: DocumentType, node_count: usize) {
-    match doc_type {
-      DocumentType::Markdown => self.markdown_files += 1,
-      DocumentType::JavaScript | DocumentType::TypeScript => self.js_files += 1,
-      DocumentType::Java => self.java_files += 1,
-      DocumentType::Python => self.python_files += 1,
+  /// Aggregate coverage percentage when it is below `min`.
+  pub fn coverage_below(&self, min: Option<f64>) -> Option<f64> {
+    let (documented, total) = self.doc_coverage()?;
+    let percent = coverage::percent(documented, total);
+    (percent < min?).then_some(percent)
+  }
+
+  /// Process exit code for these results; an interruption takes
+  /// precedence, then parse errors, then write errors.
+  pub fn exit_code(&self, args: &Args) -> i32 {
+    let failing_findings = if args.fail_on_warning {
+      self.validation_errors + self.validation_warnings
+    } else if args.fail_on_error {
+      self.validation_errors
+    } else {
+      0
+    };
+
+    if self.interrupted {
+      EXIT_INTERRUPTED
+    } else if self.errors > self.write_errors {
+      EXIT_PARSE_ERRORS
+    } else if self.write_errors > 0 {
+      EXIT_IO
+    } else if failing_findings > 0 || self.coverage_below(args.min_doc_coverage).is_some() {
+      EXIT_VALIDATION
+    } else {
+      EXIT_SUCCESS
     }
-    self.total_nodes += node_count;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_exit_code() {
+    let mut stats = ProcessingStats {
+      validation_warnings: 1,
+      ..ProcessingStats::default()
+    };
+    let mut args = Args::default();
+    assert_eq!(stats.exit_code(&args), EXIT_SUCCESS);
+
+    args.fail_on_error = true;
+    assert_eq!(stats.exit_code(&args), EXIT_SUCCESS);
+    args.fail_on_warning = true;
+    assert_eq!(stats.exit_code(&args), EXIT_VALIDATION);
+
+    stats.errors = 1;
+    stats.write_errors = 1;
+    assert_eq!(stats.exit_code(&args), EXIT_IO);
+    stats.errors = 2;
+    assert_eq!(stats.exit_code(&args), EXIT_PARSE_ERRORS);
+    stats.interrupted = true;
+    assert_eq!(stats.exit_code(&args), EXIT_INTERRUPTED);
+  }
+
+  #[test]
+  fn test_min_doc_coverage() {
+    let mut stats = ProcessingStats::default();
+    let mut args = Args {
+      min_doc_coverage: Some(80.0),
+      ..Args::default()
+    };
+    // Markdown-only runs have no coverage to enforce.
+    assert_eq!(stats.exit_code(&args), EXIT_SUCCESS);
+
+    let source = "/** Doc. */\nfunction a() {}\nfunction b() {}\n";
+    stats.add_file(
+      Path::new("a.js"),
+      FileReport {
+        doc_type: DocumentType::JavaScript,
+        node_count: 1,
+        streamed: false,
+        findings: Vec::new(),
+        coverage: coverage::measure(source, DocumentType::JavaScript),
+        xref: None,
+        deprecations: Vec::new(),
+        analysis: None,
+        nav: None,
+        locale: None,
+        links: None,
+        releases: Vec::new(),
+        code: Vec::new(),
+        memory: None,
+        timings: Vec::new(),
+      },
+    );
+    assert_eq!(stats.doc_coverage(), Some((1, 2)));
+    assert_eq!(stats.coverage_below(args.min_doc_coverage), Some(50.0));
+    assert_eq!(stats.exit_code(&args), EXIT_VALIDATION);
+
+    args.min_doc_coverage = Some(50.0);
+    assert_eq!(stats.exit_code(&args), EXIT_SUCCESS);
   }
 }
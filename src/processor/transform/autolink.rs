@@ -0,0 +1,147 @@
+//! GFM extended autolinks for `www.` and bare domains
+//! (`--extended-autolinks`).
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind, ReferenceType, Span};
+use crate::markdown::autolink::{extended_len, starts_word};
+
+/// Turn `www.example.com` and bare domains such as `docs.rs/bukvar` in text
+/// into links to `http://...`, keeping the text as written. Text already
+/// inside a link or image is left alone.
+pub struct ExtendedAutolinks;
+
+impl Transform for ExtendedAutolinks {
+  fn name(&self) -> &str {
+    "autolinks"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    link(&mut doc.nodes);
+    Ok(())
+  }
+}
+
+fn link(nodes: &mut Vec<Node>) {
+  let mut out = Vec::with_capacity(nodes.len());
+  for mut node in std::mem::take(nodes) {
+    match &node.kind {
+      NodeKind::Text { content } => split(content, node.span, &mut out),
+      NodeKind::Link { .. } | NodeKind::Image { .. } => out.push(node),
+      _ => {
+        link(&mut node.children);
+        out.push(node);
+      }
+    }
+  }
+  *nodes = out;
+}
+
+/// Push `content` as text and link nodes; a link starts a word or follows
+/// `*`, `_`, `~`, `(` or non-ASCII punctuation.
+fn split(content: &str, span: Span, out: &mut Vec<Node>) {
+  let mut text_start = 0;
+  let mut i = 0;
+  while i < content.len() {
+    let word_start = starts_word(content[..i].chars().next_back());
+    let len = match content[i..].chars().next() {
+      Some(c) if word_start && c.is_ascii_alphanumeric() => extended_len(&content[i..]),
+      _ => None,
+    };
+    let Some(len) = len else {
+      i += content[i..].chars().next().map_or(1, char::len_utf8);
+      continue;
+    };
+
+    if text_start < i {
+      out.push(text(&content[text_start..i], span, text_start, i));
+    }
+    let shown = &content[i..i + len];
+    out.push(Node::with_children(
+      NodeKind::Link {
+        url: format!("http://{}", shown),
+        title: None,
+        ref_type: ReferenceType::Full,
+        attributes: Vec::new(),
+      },
+      sub_span(span, i, i + len),
+      vec![text(shown, span, i, i + len)],
+    ));
+    i += len;
+    text_start = i;
+  }
+  if text_start < content.len() {
+    out.push(text(
+      &content[text_start..],
+      span,
+      text_start,
+      content.len(),
+    ));
+  }
+}
+
+fn text(content: &str, span: Span, start: usize, end: usize) -> Node {
+  Node::new(
+    NodeKind::Text {
+      content: content.to_string(),
+    },
+    sub_span(span, start, end),
+  )
+}
+
+fn sub_span(span: Span, start: usize, end: usize) -> Span {
+  Span::new(span.start + start, span.start + end, span.line, span.column)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn linked(input: &str) -> Vec<String> {
+    let mut doc = MarkdownParser::new(input).parse();
+    ExtendedAutolinks.transform(&mut doc).unwrap();
+    doc.nodes[0]
+      .children
+      .iter()
+      .map(|n| match &n.kind {
+        NodeKind::Text { content } => format!("text {}", content),
+        NodeKind::Link { url, .. } => format!("link {}", url),
+        other => format!("{:?}", other),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_links_www_and_bare_domains() {
+    assert_eq!(
+      linked("See www.commonmark.org/help. or (docs.rs/bukvar)!"),
+      vec![
+        "text See ",
+        "link http://www.commonmark.org/help",
+        "text . or (",
+        "link http://docs.rs/bukvar",
+        "text )!",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_unicode_boundaries() {
+    assert_eq!(
+      linked("詳細は（docs.rs/bukvar）。"),
+      vec!["text 詳細は（", "link http://docs.rs/bukvar", "text ）。"]
+    );
+    assert_eq!(
+      linked("voir\u{a0}www.x.com»"),
+      vec!["text voir\u{a0}", "link http://www.x.com", "text »"]
+    );
+  }
+
+  #[test]
+  fn test_leaves_other_text_alone() {
+    assert_eq!(
+      linked("main.c v1.2.3 file.md [www.b.com](/b)"),
+      vec!["text main.c v1.2.3 file.md ", "link /b"]
+    );
+  }
+}
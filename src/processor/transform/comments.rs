@@ -0,0 +1,48 @@
+//! Comment removal (`--strip-comments`).
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind};
+
+/// Drop every `Comment`, block and inline, for output that should not carry
+/// authoring notes. A paragraph left with nothing in it goes too.
+pub struct StripComments;
+
+impl Transform for StripComments {
+  fn name(&self) -> &str {
+    "strip-comments"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    strip(&mut doc.nodes);
+    Ok(())
+  }
+}
+
+fn strip(nodes: &mut Vec<Node>) {
+  nodes.retain_mut(|node| {
+    if matches!(node.kind, NodeKind::Comment { .. }) {
+      return false;
+    }
+    let had_children = !node.children.is_empty();
+    strip(&mut node.children);
+    !(had_children && node.children.is_empty() && node.kind == NodeKind::Paragraph)
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_strips_comments() {
+    let mut doc =
+      MarkdownParser::new("<!-- draft\n\nnote -->\n\nText <!-- todo --> here\n\n> <!-- x -->\n")
+        .parse();
+    StripComments.transform(&mut doc).unwrap();
+    let kinds: Vec<_> = doc.nodes.iter().map(|n| n.kind.name()).collect();
+    assert_eq!(kinds, ["Paragraph", "BlockQuote"]);
+    assert_eq!(doc.nodes[0].children.len(), 2);
+    assert!(doc.nodes[1].children.is_empty());
+  }
+}
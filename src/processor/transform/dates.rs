@@ -0,0 +1,177 @@
+//! Frontmatter dates normalized to ISO 8601.
+
+const MONTHS: [&str; 12] = [
+  "january",
+  "february",
+  "march",
+  "april",
+  "may",
+  "june",
+  "july",
+  "august",
+  "september",
+  "october",
+  "november",
+  "december",
+];
+
+/// `value` as an ISO 8601 date (`2024-03-05`) or date and time
+/// (`2024-03-05T14:30:00`, ending in `Z` or an offset when the value has
+/// one), or `None` when it is not a valid date. Takes ISO dates with `-` or
+/// `/`, with an optional time after `T` or a space, and dates written out
+/// in English: `March 5, 2024`, `5 Mar 2024`.
+pub(crate) fn iso_date(value: &str) -> Option<String> {
+  let value = value.trim();
+  let starts_numeric = value.len() >= 5
+    && value.as_bytes()[..4].iter().all(u8::is_ascii_digit)
+    && matches!(value.as_bytes()[4], b'-' | b'/');
+  if starts_numeric {
+    numeric(value)
+  } else {
+    let (year, month, day) = written(value)?;
+    Some(format_date(year, month, day))
+  }
+}
+
+/// `2024-03-05`, `2024/3/5`, `2024-03-05T14:30:00Z`, `2024-03-05 14:30 +0200`
+fn numeric(value: &str) -> Option<String> {
+  let (date, time) = match value.find(['T', 't', ' ']) {
+    Some(at) => (&value[..at], Some(value[at + 1..].trim())),
+    None => (value, None),
+  };
+  let separator = if date.contains('/') { '/' } else { '-' };
+  let mut parts = date.split(separator);
+  let year = number(parts.next()?, 4, 4)?;
+  let month = number(parts.next()?, 1, 2)?;
+  let day = number(parts.next()?, 1, 2)?;
+  if parts.next().is_some() || !valid_date(year, month, day) {
+    return None;
+  }
+  let date = format_date(year, month, day);
+  match time.filter(|time| !time.is_empty()) {
+    Some(time) => Some(format!("{}T{}", date, clock(time)?)),
+    None => Some(date),
+  }
+}
+
+/// `14:30`, `14:30:05.250`, with `Z`, `UTC` or an offset, as
+/// `HH:MM:SS` and the zone. Fractions of a second are dropped.
+fn clock(time: &str) -> Option<String> {
+  let end = time
+    .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.'))
+    .unwrap_or(time.len());
+  let (clock, zone) = (&time[..end], time[end..].trim());
+  let mut parts = clock.split(':');
+  let hour = number(parts.next()?, 1, 2)?;
+  let minute = number(parts.next()?, 2, 2)?;
+  let second = match parts.next() {
+    Some(second) => number(second.split('.').next()?, 2, 2)?,
+    None => 0,
+  };
+  if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+    return None;
+  }
+  let zone = match zone {
+    "" => String::new(),
+    "Z" | "z" | "UTC" | "GMT" => "Z".to_string(),
+    _ => offset(zone)?,
+  };
+  Some(format!("{:02}:{:02}:{:02}{}", hour, minute, second, zone))
+}
+
+/// `+02:00`, `+0200` or `-05` as `+HH:MM`.
+fn offset(zone: &str) -> Option<String> {
+  let sign = zone.chars().next().filter(|c| matches!(c, '+' | '-'))?;
+  let digits: String = zone[1..].chars().filter(|&c| c != ':').collect();
+  let (hours, minutes) = match digits.len() {
+    2 => (number(&digits, 2, 2)?, 0),
+    4 => (number(&digits[..2], 2, 2)?, number(&digits[2..], 2, 2)?),
+    _ => return None,
+  };
+  (hours <= 14 && minutes <= 59).then(|| format!("{}{:02}:{:02}", sign, hours, minutes))
+}
+
+/// `March 5, 2024`, `Mar 5th 2024` or `5 March 2024` as (year, month, day).
+fn written(value: &str) -> Option<(u32, u32, u32)> {
+  let words: Vec<&str> = value
+    .split(|c: char| c.is_whitespace() || c == ',')
+    .filter(|word| !word.is_empty())
+    .collect();
+  let [first, second, year] = words[..] else {
+    return None;
+  };
+  let (month, day) = match month(first) {
+    Some(month) => (month, second),
+    None => (month(second)?, first),
+  };
+  let day = day.trim_end_matches(['s', 't', 'n', 'd', 'r', 'h']);
+  let (year, day) = (number(year, 4, 4)?, number(day, 1, 2)?);
+  valid_date(year, month, day).then_some((year, month, day))
+}
+
+/// `Mar`, `march` or `Sept.` as 3.
+fn month(word: &str) -> Option<u32> {
+  let word = word.trim_end_matches('.').to_ascii_lowercase();
+  if word.len() < 3 {
+    return None;
+  }
+  MONTHS
+    .iter()
+    .position(|name| name.starts_with(&word))
+    .map(|i| i as u32 + 1)
+}
+
+/// `text` as a number of `min` to `max` digits.
+fn number(text: &str, min: usize, max: usize) -> Option<u32> {
+  ((min..=max).contains(&text.len()) && text.bytes().all(|b| b.is_ascii_digit()))
+    .then(|| text.parse().ok())
+    .flatten()
+}
+
+fn valid_date(year: u32, month: u32, day: u32) -> bool {
+  let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+  let days = match month {
+    1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+    4 | 6 | 9 | 11 => 30,
+    2 if leap => 29,
+    2 => 28,
+    _ => return false,
+  };
+  (1..=days).contains(&day)
+}
+
+fn format_date(year: u32, month: u32, day: u32) -> String {
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_iso_date() {
+    let cases = [
+      ("2024-03-05", Some("2024-03-05")),
+      ("2024/3/5", Some("2024-03-05")),
+      ("2024-03-05T14:30", Some("2024-03-05T14:30:00")),
+      ("2024-03-05 14:30:05.25Z", Some("2024-03-05T14:30:05Z")),
+      (
+        "2024-03-05T09:00:00+0200",
+        Some("2024-03-05T09:00:00+02:00"),
+      ),
+      ("2024-03-05 9:00 -05", Some("2024-03-05T09:00:00-05:00")),
+      ("March 5, 2024", Some("2024-03-05")),
+      ("5 Sept. 2024", Some("2024-09-05")),
+      ("Feb 29th 2024", Some("2024-02-29")),
+      ("2023-02-29", None),
+      ("2024-13-01", None),
+      ("2024-03-05T25:00", None),
+      ("next week", None),
+      ("Ma 5 2024", None),
+      ("", None),
+    ];
+    for (value, expected) in cases {
+      assert_eq!(iso_date(value).as_deref(), expected, "{}", value);
+    }
+  }
+}
@@ -0,0 +1,182 @@
+//! Footnote numbering and the document-level footnotes section.
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind, Span};
+
+use std::collections::HashMap;
+
+/// Number footnote references in order of first appearance and move every
+/// definition into a `Footnotes` node appended to the document.
+///
+/// Repeated references share a number; labels match case-insensitively and
+/// the first definition of a label wins. References inside a footnote are
+/// numbered after the ones in the body. Unreferenced and duplicate
+/// definitions keep index 0 and go last; references without a definition
+/// keep index 0.
+pub struct Footnotes;
+
+impl Transform for Footnotes {
+  fn name(&self) -> &str {
+    "footnotes"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    let mut definitions = Vec::new();
+    take_definitions(&mut doc.nodes, &mut definitions);
+
+    let mut first = HashMap::new();
+    for (slot, def) in definitions.iter().enumerate() {
+      if let NodeKind::FootnoteDefinition { label, .. } = &def.kind {
+        first.entry(label.to_lowercase()).or_insert(slot);
+      }
+    }
+
+    let mut numbering = Numbering {
+      first,
+      numbers: HashMap::new(),
+      order: Vec::new(),
+    };
+    numbering.number(&mut doc.nodes);
+
+    let mut slots: Vec<Option<Node>> = definitions.into_iter().map(Some).collect();
+    let mut section = Vec::with_capacity(slots.len());
+    let mut i = 0;
+    while i < numbering.order.len() {
+      let Some(mut def) = slots[numbering.order[i]].take() else {
+        unreachable!("each definition is numbered once");
+      };
+      numbering.number(&mut def.children);
+      set_index(&mut def, i as u32 + 1);
+      section.push(def);
+      i += 1;
+    }
+    for mut def in slots.into_iter().flatten() {
+      set_index(&mut def, 0);
+      section.push(def);
+    }
+
+    if !section.is_empty() {
+      doc.nodes.push(Node::with_children(
+        NodeKind::Footnotes,
+        Span::empty(),
+        section,
+      ));
+    }
+    Ok(())
+  }
+}
+
+struct Numbering {
+  /// Lowercase label to the slot of its first definition
+  first: HashMap<String, usize>,
+  numbers: HashMap<String, u32>,
+  /// Definition slots in footnote number order
+  order: Vec<usize>,
+}
+
+impl Numbering {
+  fn number(&mut self, nodes: &mut [Node]) {
+    for node in nodes {
+      if let NodeKind::FootnoteReference { label, index } = &mut node.kind {
+        let key = label.to_lowercase();
+        *index = match (self.numbers.get(&key), self.first.get(&key)) {
+          (Some(&number), _) => number,
+          (None, Some(&slot)) => {
+            self.order.push(slot);
+            let number = self.order.len() as u32;
+            self.numbers.insert(key, number);
+            number
+          }
+          (None, None) => 0,
+        };
+      }
+      self.number(&mut node.children);
+    }
+  }
+}
+
+/// Remove definitions from the tree, including a `Footnotes` section left
+/// by an earlier run.
+fn take_definitions(nodes: &mut Vec<Node>, out: &mut Vec<Node>) {
+  let mut kept = Vec::with_capacity(nodes.len());
+  for mut node in std::mem::take(nodes) {
+    match node.kind {
+      NodeKind::FootnoteDefinition { .. } => out.push(node),
+      NodeKind::Footnotes => out.extend(node.children),
+      _ => {
+        take_definitions(&mut node.children, out);
+        kept.push(node);
+      }
+    }
+  }
+  *nodes = kept;
+}
+
+fn set_index(def: &mut Node, number: u32) {
+  if let NodeKind::FootnoteDefinition { index, .. } = &mut def.kind {
+    *index = number;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn refs(nodes: &[Node], out: &mut Vec<(String, u32)>) {
+    for node in nodes {
+      if let NodeKind::FootnoteReference { label, index } = &node.kind {
+        out.push((label.clone(), *index));
+      }
+      refs(&node.children, out);
+    }
+  }
+
+  fn section(doc: &Document) -> Vec<(String, u32)> {
+    let last = doc.nodes.last().unwrap();
+    assert_eq!(last.kind, NodeKind::Footnotes);
+    last
+      .children
+      .iter()
+      .map(|def| match &def.kind {
+        NodeKind::FootnoteDefinition { label, index } => (label.clone(), *index),
+        other => panic!("unexpected {:?}", other),
+      })
+      .collect()
+  }
+
+  fn pair(label: &str, index: u32) -> (String, u32) {
+    (label.to_string(), index)
+  }
+
+  #[test]
+  fn test_numbers_in_appearance_order() {
+    let input = "A[^z] b[^a] c[^Z] d[^gone].\n\n[^a]: Ay\n\n[^z]: Zed\n\n[^unused]: Never";
+    let mut doc = MarkdownParser::new(input).parse();
+    Footnotes.transform(&mut doc).unwrap();
+
+    let mut found = Vec::new();
+    refs(&doc.nodes, &mut found);
+    assert_eq!(
+      found,
+      vec![pair("z", 1), pair("a", 2), pair("Z", 1), pair("gone", 0)]
+    );
+    assert_eq!(doc.nodes.len(), 2, "definitions moved out of the body");
+    assert_eq!(
+      section(&doc),
+      vec![pair("z", 1), pair("a", 2), pair("unused", 0)]
+    );
+  }
+
+  #[test]
+  fn test_nested_references_and_rerun() {
+    let input = "> Quote[^q]\n\n[^n]: Nested\n\n[^q]: See[^n]\n    More";
+    let mut doc = MarkdownParser::new(input).parse();
+    Footnotes.transform(&mut doc).unwrap();
+    Footnotes.transform(&mut doc).unwrap();
+
+    assert_eq!(section(&doc), vec![pair("q", 1), pair("n", 2)]);
+    let q = &doc.nodes.last().unwrap().children[0];
+    assert_eq!(q.children.len(), 2, "indented line continues the footnote");
+  }
+}
@@ -0,0 +1,91 @@
+//! Content hashes for block-level nodes (`--content-hashes`).
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::{fnv, kind_to_json};
+
+/// Set `hash` on every block-level node to a 64-bit FNV-1a hash of its
+/// content: its kind's fields and, recursively, its children. Spans are left
+/// out, as is a heading's `section`, so a block keeps its hash when edits
+/// elsewhere move it. Incremental renderers can compare hashes between runs
+/// and re-render only the blocks that changed.
+pub struct ContentHashes;
+
+impl Transform for ContentHashes {
+  fn name(&self) -> &str {
+    "content-hashes"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    doc.nodes.iter_mut().for_each(|node| {
+      hash(node);
+    });
+    Ok(())
+  }
+}
+
+fn hash(node: &mut Node) -> u64 {
+  let mut h = fnv::hash(content_json(&node.kind).as_bytes());
+  for child in &mut node.children {
+    h = fnv::update(h, &hash(child).to_le_bytes());
+  }
+  if !node.kind.is_inline() {
+    node.hash = Some(h);
+  }
+  h
+}
+
+/// The kind's JSON without position-dependent fields.
+fn content_json(kind: &NodeKind) -> String {
+  match kind {
+    NodeKind::Heading {
+      section: Some(_), ..
+    } => {
+      let mut kind = kind.clone();
+      if let NodeKind::Heading { section, .. } = &mut kind {
+        *section = None;
+      }
+      kind_to_json(&kind)
+    }
+    _ => kind_to_json(kind),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+  use crate::processor::transform::Sections;
+
+  fn hashed(input: &str) -> Document {
+    let mut doc = MarkdownParser::new(input).parse();
+    Sections.transform(&mut doc).unwrap();
+    ContentHashes.transform(&mut doc).unwrap();
+    doc
+  }
+
+  #[test]
+  fn test_hashes_ignore_position() {
+    let a = hashed("# Title\n\nSame *text*.\n");
+    let b = hashed("Intro.\n\n# Title\n\nSame *text*.\n\n## More\n");
+    assert_eq!(a.nodes[0].hash, b.nodes[1].hash);
+    assert_eq!(a.nodes[1].hash, b.nodes[2].hash);
+    assert!(a.nodes[1].hash.is_some());
+    assert_ne!(a.nodes[0].hash, a.nodes[1].hash);
+
+    let changed = hashed("# Title\n\nSame *text!*\n");
+    assert_eq!(a.nodes[0].hash, changed.nodes[0].hash);
+    assert_ne!(a.nodes[1].hash, changed.nodes[1].hash);
+  }
+
+  #[test]
+  fn test_only_blocks_hashed() {
+    let doc = hashed("- one\n- *two*\n");
+    let list = &doc.nodes[0];
+    assert!(list.hash.is_some());
+    assert!(list.children.iter().all(|item| item.hash.is_some()));
+    let paragraph = &list.children[1].children[0];
+    assert!(paragraph.hash.is_some());
+    assert!(paragraph.children.iter().all(|n| n.hash.is_none()));
+  }
+}
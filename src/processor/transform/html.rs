@@ -0,0 +1,70 @@
+//! Inline HTML tokenizing (`--html-tags`).
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind};
+use crate::markdown::html::scan_tag;
+
+/// Replace each `HtmlInline` tag with an `HtmlTag` node carrying its name,
+/// side and attributes. Anything that does not tokenize as a single tag
+/// stays `HtmlInline`.
+pub struct HtmlTags;
+
+impl Transform for HtmlTags {
+  fn name(&self) -> &str {
+    "html-tags"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    tokenize(&mut doc.nodes);
+    Ok(())
+  }
+}
+
+fn tokenize(nodes: &mut [Node]) {
+  for node in nodes {
+    if let NodeKind::HtmlInline { content } = &node.kind {
+      if let Some(tag) = scan_tag(content).filter(|t| t.len == content.len()) {
+        node.kind = NodeKind::HtmlTag {
+          name: tag.name,
+          tag: tag.kind,
+          attributes: tag.attributes,
+        };
+      }
+    }
+    tokenize(&mut node.children);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ast::HtmlTagKind;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_tokenizes_inline_tags() {
+    let mut doc = MarkdownParser::new("Press <kbd class=\"key\">Ctrl</kbd><br/>").parse();
+    HtmlTags.transform(&mut doc).unwrap();
+    let kinds: Vec<_> = doc.nodes[0]
+      .children
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::HtmlTag {
+          name,
+          tag,
+          attributes,
+        } => Some(format!("{} {} {:?}", tag, name, attributes)),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      kinds,
+      vec![
+        "open kbd [(\"class\", \"key\")]",
+        "close kbd []",
+        "self-closing br []",
+      ]
+    );
+    assert_eq!(HtmlTagKind::SelfClosing.to_string(), "self-closing");
+  }
+}
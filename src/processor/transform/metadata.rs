@@ -0,0 +1,274 @@
+//! Document title, description, dates and authors (`--extract-metadata`).
+
+use super::dates::iso_date;
+use super::vars::frontmatter_vars;
+use super::{text_of, Transform};
+use crate::ast::{Author, Document, DocumentType, FrontmatterFormat, Node, NodeKind};
+
+/// Longest description kept, in characters; the usual limit for search
+/// result snippets and link cards.
+pub const DESCRIPTION_CHARS: usize = 160;
+
+/// Fill in `DocumentMetadata::title` from the frontmatter `title` or the
+/// first level-1 heading, and `description` from the frontmatter
+/// `description` or the first paragraph, cut at a word boundary to
+/// `DESCRIPTION_CHARS`. Values a parser already set are kept.
+///
+/// `date` and `updated` come from the frontmatter keys of the same name,
+/// normalized to ISO 8601 and left out when they can't be read, and
+/// `authors` from `authors` or `author`: a list, or one value holding
+/// comma-separated authors, each written `Name <email> (url)` or as a
+/// `name`/`email`/`url` mapping.
+pub struct Metadata;
+
+impl Transform for Metadata {
+  fn name(&self) -> &str {
+    "metadata"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    if doc.doc_type != DocumentType::Markdown {
+      return Ok(());
+    }
+    let vars = frontmatter_vars(&doc.nodes);
+    let var = |name: &str| {
+      vars
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+    };
+
+    if doc.metadata.title.is_none() {
+      doc.metadata.title = var("title").or_else(|| {
+        find(&doc.nodes, &|kind| {
+          matches!(kind, NodeKind::Heading { level: 1, .. })
+        })
+      });
+    }
+    if doc.metadata.description.is_none() {
+      doc.metadata.description = var("description")
+        .or_else(|| find(&doc.nodes, &|kind| matches!(kind, NodeKind::Paragraph)))
+        .map(|text| shorten(&text));
+    }
+    doc.metadata.date = var("date").and_then(|date| iso_date(&date));
+    doc.metadata.updated = var("updated").and_then(|date| iso_date(&date));
+    doc.metadata.authors = authors(&doc.nodes);
+    Ok(())
+  }
+}
+
+/// Text of the first node, in document order, whose kind matches and whose
+/// text is not blank. Code and frontmatter are not searched.
+fn find(nodes: &[Node], wanted: &dyn Fn(&NodeKind) -> bool) -> Option<String> {
+  nodes.iter().find_map(|node| {
+    if wanted(&node.kind) {
+      let text = collapse(&text_of(&node.children));
+      return (!text.is_empty()).then_some(text);
+    }
+    match node.kind {
+      NodeKind::Frontmatter { .. }
+      | NodeKind::CodeBlock { .. }
+      | NodeKind::FencedCodeBlock { .. }
+      | NodeKind::CodeBlockExt { .. }
+      | NodeKind::IndentedCodeBlock => None,
+      _ => find(&node.children, wanted),
+    }
+  })
+}
+
+/// Authors listed in the frontmatter under `authors` or `author`.
+fn authors(nodes: &[Node]) -> Vec<Author> {
+  let Some((format, content)) = nodes.iter().find_map(|n| match &n.kind {
+    NodeKind::Frontmatter { format, content } => Some((*format, content.as_str())),
+    _ => None,
+  }) else {
+    return Vec::new();
+  };
+  let separator = match format {
+    FrontmatterFormat::Yaml => ':',
+    FrontmatterFormat::Toml => '=',
+    FrontmatterFormat::Json => return Vec::new(),
+  };
+  let mut lines = content.lines();
+  let Some(value) = lines.by_ref().find_map(|line| {
+    let (key, value) = line.split_once(separator)?;
+    matches!(key.trim_end(), "authors" | "author").then_some(value.trim())
+  }) else {
+    return Vec::new();
+  };
+  if let Some(list) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+    return list
+      .split(',')
+      .filter_map(|item| parse_author(unquote(item)))
+      .collect();
+  }
+  if !value.is_empty() {
+    return unquote(value).split(',').filter_map(parse_author).collect();
+  }
+  // A YAML block list; items are strings or mappings
+  let mut authors: Vec<Author> = Vec::new();
+  for line in lines.take_while(|line| line.starts_with(char::is_whitespace)) {
+    let line = line.trim();
+    let (entry, new_item) = match line.strip_prefix("- ") {
+      Some(entry) => (entry.trim(), true),
+      None => (line, false),
+    };
+    let field = entry
+      .split_once(':')
+      .map(|(key, value)| (key.trim(), unquote(value)))
+      .filter(|(key, _)| matches!(*key, "name" | "email" | "url"));
+    match field {
+      Some((key, value)) => {
+        if new_item {
+          authors.push(Author::default());
+        }
+        let Some(author) = authors.last_mut() else {
+          continue;
+        };
+        match key {
+          "name" => author.name = value.to_string(),
+          "email" => author.email = Some(value.to_string()),
+          _ => author.url = Some(value.to_string()),
+        }
+      }
+      None if new_item => authors.extend(parse_author(unquote(entry))),
+      None => {}
+    }
+  }
+  authors.retain(|author| !author.name.is_empty());
+  authors
+}
+
+/// `Ada Lovelace <ada@example.com> (https://ada.dev)`, email and url optional.
+fn parse_author(text: &str) -> Option<Author> {
+  let mut rest = text.trim();
+  let mut url = None;
+  if let Some(open) = rest.strip_suffix(')').and_then(|r| r.rfind('(')) {
+    url = Some(rest[open + 1..rest.len() - 1].trim().to_string());
+    rest = rest[..open].trim_end();
+  }
+  let mut email = None;
+  if let (Some(open), Some(close)) = (rest.find('<'), rest.rfind('>')) {
+    if open < close {
+      email = Some(rest[open + 1..close].trim().to_string());
+      rest = rest[..open].trim_end();
+    }
+  }
+  let name = unquote(rest);
+  (!name.is_empty()).then(|| Author {
+    name: name.to_string(),
+    email,
+    url,
+  })
+}
+
+fn unquote(text: &str) -> &str {
+  let text = text.trim();
+  text
+    .strip_prefix('"')
+    .and_then(|t| t.strip_suffix('"'))
+    .or_else(|| text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))
+    .unwrap_or(text)
+}
+
+fn collapse(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` cut to `DESCRIPTION_CHARS` at the last space, with an ellipsis.
+fn shorten(text: &str) -> String {
+  let Some((end, _)) = text.char_indices().nth(DESCRIPTION_CHARS) else {
+    return text.to_string();
+  };
+  let cut = text[..end].rfind(' ').unwrap_or(end);
+  format!("{}…", text[..cut].trim_end_matches([',', ';', ':', '.']))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn metadata(input: &str) -> (Option<String>, Option<String>) {
+    let mut doc = MarkdownParser::new(input).parse();
+    Metadata.transform(&mut doc).unwrap();
+    (doc.metadata.title, doc.metadata.description)
+  }
+
+  #[test]
+  fn test_from_content() {
+    let (title, description) =
+      metadata("```\ncode\n```\n\n## Sub\n\nFirst *para*\n\n# Main `title`\n\nSecond");
+    assert_eq!(title.as_deref(), Some("Main title"));
+    assert_eq!(description.as_deref(), Some("First para"));
+    assert_eq!(
+      metadata("Just code:\n\n    x"),
+      (None, Some("Just code:".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_frontmatter_wins() {
+    let (title, description) =
+      metadata("---\ntitle: \"Guide\"\ndescription: Short\n---\n# Heading\n\nText");
+    assert_eq!(title.as_deref(), Some("Guide"));
+    assert_eq!(description.as_deref(), Some("Short"));
+  }
+
+  #[test]
+  fn test_dates_and_authors() {
+    let parse = |input: &str| {
+      let mut doc = MarkdownParser::new(input).parse();
+      Metadata.transform(&mut doc).unwrap();
+      doc.metadata
+    };
+    let author = |name: &str, email: Option<&str>, url: Option<&str>| Author {
+      name: name.to_string(),
+      email: email.map(str::to_string),
+      url: url.map(str::to_string),
+    };
+
+    let meta = parse(
+      "---\ndate: March 5, 2024\nupdated: 2024-04-01 09:30Z\nauthors:\n  - Ada <ada@example.com>\n\
+       \x20 - name: \"Bob\"\n    url: https://bob.dev\ntags: [a]\n---\n",
+    );
+    assert_eq!(meta.date.as_deref(), Some("2024-03-05"));
+    assert_eq!(meta.updated.as_deref(), Some("2024-04-01T09:30:00Z"));
+    assert_eq!(
+      meta.authors,
+      [
+        author("Ada", Some("ada@example.com"), None),
+        author("Bob", None, Some("https://bob.dev")),
+      ]
+    );
+
+    let meta = parse("---\ndate: someday\nauthor: Ada, Bob (https://bob.dev)\n---\n");
+    assert_eq!(meta.date, None);
+    assert_eq!(
+      meta.authors,
+      [
+        author("Ada", None, None),
+        author("Bob", None, Some("https://bob.dev"))
+      ]
+    );
+    let meta = parse("+++\nauthors = [\"Ada\", \"Bob <b@x.org>\"]\n+++\n");
+    assert_eq!(
+      meta.authors,
+      [
+        author("Ada", None, None),
+        author("Bob", Some("b@x.org"), None)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_shorten() {
+    let long = "word ".repeat(40);
+    let short = shorten(long.trim());
+    assert!(short.ends_with("word…"), "{}", short);
+    assert!(short.chars().count() <= DESCRIPTION_CHARS + 1);
+    assert_eq!(shorten("brief"), "brief");
+    let (_, description) = metadata(&format!("{}, and more", "a".repeat(200)));
+    assert_eq!(description.unwrap().chars().count(), DESCRIPTION_CHARS + 1);
+  }
+}
@@ -0,0 +1,240 @@
+//! AST transform passes and the pipeline that runs them.
+//!
+//! Built-in order: includes, variables, slugs, TOC, footnotes; opt-in passes
+//! (`html-tags`, `autolinks`, `rewrite`, `sanitize`, `metadata`, `sections`,
+//! `strip-comments`, `content-hashes`) and user passes run after.
+
+mod autolink;
+mod comments;
+mod dates;
+mod footnotes;
+mod hash;
+mod html;
+mod metadata;
+mod rewrite;
+mod sanitize;
+mod sections;
+mod slug;
+mod toc;
+mod vars;
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::markdown::ElementRegistry;
+
+use std::time::{Duration, Instant};
+
+pub use self::autolink::ExtendedAutolinks;
+pub use self::comments::StripComments;
+pub(crate) use self::dates::iso_date;
+pub use self::footnotes::Footnotes;
+pub use self::hash::ContentHashes;
+pub use self::html::HtmlTags;
+pub use self::metadata::Metadata;
+pub use self::rewrite::{Rewrite, RewriteRules};
+pub use self::sanitize::{Sanitize, SanitizeMode, SanitizePolicy};
+pub use self::sections::Sections;
+pub(crate) use self::slug::dedupe;
+pub use self::slug::Slugs;
+pub use self::toc::TableOfContents;
+pub use self::vars::Variables;
+pub(crate) use self::vars::{frontmatter_vars, is_draft};
+pub use super::include::Includes;
+
+/// A pass that rewrites a parsed document in place.
+pub trait Transform: Send + Sync {
+  /// Short name used in timing reports.
+  fn name(&self) -> &str;
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String>;
+}
+
+/// Wall-clock time spent in one pass.
+#[derive(Debug, Clone)]
+pub struct PassTiming {
+  pub name: String,
+  pub elapsed: Duration,
+}
+
+/// Ordered list of transforms.
+#[derive(Default)]
+pub struct Pipeline {
+  passes: Vec<Box<dyn Transform>>,
+}
+
+impl Pipeline {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The built-in passes in their defined order.
+  pub fn builtin(elements: &ElementRegistry) -> Self {
+    let mut pipeline = Self::new();
+    pipeline.add(Includes::new(elements.clone()));
+    pipeline.passes.extend(Self::sandboxed().passes);
+    pipeline
+  }
+
+  /// The built-in passes that never touch the filesystem (no includes).
+  pub fn sandboxed() -> Self {
+    let mut pipeline = Self::new();
+    pipeline.add(Slugs).add(TableOfContents).add(Footnotes);
+    pipeline
+  }
+
+  /// Add `{{name}}` substitution (`--vars`) ahead of the slugs pass, so
+  /// heading ids come from the substituted text.
+  pub fn with_variables(&mut self) -> &mut Self {
+    let at = self
+      .passes
+      .iter()
+      .position(|p| p.name() == "slugs")
+      .unwrap_or(self.passes.len());
+    self.passes.insert(at, Box::new(Variables::default()));
+    self
+  }
+
+  /// Append a pass; it runs after every pass already added.
+  pub fn add(&mut self, pass: impl Transform + 'static) -> &mut Self {
+    self.passes.push(Box::new(pass));
+    self
+  }
+
+  #[allow(dead_code)]
+  pub fn len(&self) -> usize {
+    self.passes.len()
+  }
+
+  #[allow(dead_code)]
+  pub fn is_empty(&self) -> bool {
+    self.passes.is_empty()
+  }
+
+  /// Run every pass in order, stopping at the first error.
+  pub fn run(&self, doc: &mut Document) -> Result<Vec<PassTiming>, String> {
+    let mut timings = Vec::with_capacity(self.passes.len());
+    for pass in &self.passes {
+      let start = Instant::now();
+      pass
+        .transform(doc)
+        .map_err(|e| format!("{} pass: {}", pass.name(), e))?;
+      timings.push(PassTiming {
+        name: pass.name().to_string(),
+        elapsed: start.elapsed(),
+      });
+    }
+    doc.metadata.total_nodes = doc.nodes.iter().map(|n| n.count_nodes()).sum();
+    Ok(timings)
+  }
+}
+
+/// Plain text of a node's inline content.
+pub(crate) fn text_of(nodes: &[Node]) -> String {
+  let mut out = String::new();
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Text { content } | NodeKind::CodeSpan { content } => out.push_str(content),
+      NodeKind::SoftBreak | NodeKind::HardBreak => out.push(' '),
+      _ => out.push_str(&text_of(&node.children)),
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  struct Upper;
+
+  impl Transform for Upper {
+    fn name(&self) -> &str {
+      "upper"
+    }
+
+    fn transform(&self, doc: &mut Document) -> Result<(), String> {
+      fn walk(nodes: &mut [Node]) {
+        for node in nodes {
+          if let NodeKind::Text { content } = &mut node.kind {
+            *content = content.to_uppercase();
+          }
+          walk(&mut node.children);
+        }
+      }
+      walk(&mut doc.nodes);
+      Ok(())
+    }
+  }
+
+  struct Fail;
+
+  impl Transform for Fail {
+    fn name(&self) -> &str {
+      "fail"
+    }
+
+    fn transform(&self, _doc: &mut Document) -> Result<(), String> {
+      Err("boom".to_string())
+    }
+  }
+
+  #[test]
+  fn test_builtin_order() {
+    let pipeline = Pipeline::builtin(&ElementRegistry::new());
+    let mut doc = MarkdownParser::new("# Hi").parse();
+    let timings = pipeline.run(&mut doc).unwrap();
+    let names: Vec<_> = timings.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["includes", "slugs", "toc", "footnotes"]);
+  }
+
+  #[test]
+  fn test_variables_are_opt_in() {
+    let source = "---\ntitle: Home\n---\n# {{title}}\n\nIn Jinja write {{ title }}.\n";
+    let mut plain = MarkdownParser::new(source).parse();
+    Pipeline::sandboxed().run(&mut plain).unwrap();
+    assert_eq!(text_of(&plain.nodes[1].children), "{{title}}");
+
+    let mut pipeline = Pipeline::sandboxed();
+    pipeline.with_variables();
+    let mut doc = MarkdownParser::new(source).parse();
+    let timings = pipeline.run(&mut doc).unwrap();
+    assert_eq!(timings[0].name, "variables");
+    assert_eq!(
+      doc.nodes[1].kind,
+      NodeKind::Heading {
+        level: 1,
+        id: Some("home".to_string()),
+        attributes: vec![],
+        section: None,
+      }
+    );
+  }
+
+  #[test]
+  fn test_user_pass_runs_after_builtins() {
+    let mut pipeline = Pipeline::builtin(&ElementRegistry::new());
+    pipeline.add(Upper);
+    let mut doc = MarkdownParser::new("# Hello there").parse();
+    pipeline.run(&mut doc).unwrap();
+    // Slug was computed before the user pass upper-cased the text.
+    assert_eq!(
+      doc.nodes[0].kind,
+      NodeKind::Heading {
+        level: 1,
+        id: Some("hello-there".to_string()),
+        attributes: vec![],
+        section: None,
+      }
+    );
+    assert_eq!(text_of(&doc.nodes[0].children), "HELLO THERE");
+  }
+
+  #[test]
+  fn test_error_names_pass() {
+    let mut pipeline = Pipeline::new();
+    pipeline.add(Fail).add(Upper);
+    let mut doc = MarkdownParser::new("text").parse();
+    assert_eq!(pipeline.run(&mut doc).unwrap_err(), "fail pass: boom");
+    assert_eq!(text_of(&doc.nodes[0].children), "text");
+  }
+}
@@ -0,0 +1,179 @@
+//! URL rewriting for links and images (`--rewrite-config`).
+//!
+//! Rules come from a plain-text file, one per line, applied in this order:
+//!
+//! ```text
+//! # rewrite.conf
+//! prefix /old-docs/ /docs/
+//! extension .md .html
+//! base https://example.com/docs/
+//! ```
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind};
+
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewriteRules {
+  /// `(from, to)` URL prefixes; the first match wins
+  pub prefixes: Vec<(String, String)>,
+  /// `(from, to)` path extensions of relative URLs, e.g. `.md` → `.html`
+  pub extensions: Vec<(String, String)>,
+  /// Base prepended to relative URLs
+  pub base: Option<String>,
+}
+
+impl RewriteRules {
+  /// Parse `prefix <from> <to>`, `extension <from> <to>` and `base <url>`
+  /// lines; `#` at the start of a line starts a comment.
+  pub fn from_config(text: &str) -> Result<Self, String> {
+    let mut rules = Self::default();
+    for (index, line) in text.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let err = |msg: String| format!("line {}: {}", index + 1, msg);
+      let words: Vec<&str> = line.split_whitespace().collect();
+      match words.as_slice() {
+        ["prefix", from, to] => rules.prefixes.push((from.to_string(), to.to_string())),
+        ["extension", from, to] if from.starts_with('.') && to.starts_with('.') => {
+          rules.extensions.push((from.to_string(), to.to_string()))
+        }
+        ["base", url] => rules.base = Some(url.to_string()),
+        [rule @ ("prefix" | "extension" | "base"), ..] => {
+          return Err(err(format!("invalid {} rule: {}", rule, line)))
+        }
+        [other, ..] => return Err(err(format!("unknown rewrite rule: {}", other))),
+        [] => {}
+      }
+    }
+    Ok(rules)
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = fs::read_to_string(path)
+      .map_err(|e| format!("Failed to read rewrite config {}: {}", path.display(), e))?;
+    Self::from_config(&text).map_err(|e| format!("{}: {}", path.display(), e))
+  }
+
+  /// Apply every rule to one URL.
+  pub fn apply(&self, url: &str) -> String {
+    let mut url = url.to_string();
+    if let Some((from, to)) = self.prefixes.iter().find(|(from, _)| url.starts_with(from)) {
+      url = format!("{}{}", to, &url[from.len()..]);
+    }
+    if !is_relative(&url) {
+      return url;
+    }
+
+    let split = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(split);
+    if let Some((from, to)) = self
+      .extensions
+      .iter()
+      .find(|(from, _)| path.ends_with(from))
+    {
+      url = format!("{}{}{}", &path[..path.len() - from.len()], to, suffix);
+    }
+
+    match &self.base {
+      Some(base) => format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        url.trim_start_matches("./").trim_start_matches('/')
+      ),
+      None => url,
+    }
+  }
+}
+
+/// Relative to the current page: no scheme, not protocol-relative and not
+/// a bare `#fragment`.
+fn is_relative(url: &str) -> bool {
+  if url.is_empty() || url.starts_with('#') || url.starts_with("//") {
+    return false;
+  }
+  let scheme_end = url.find([':', '/', '?', '#']);
+  !matches!(scheme_end, Some(i) if i > 0 && url.as_bytes()[i] == b':')
+}
+
+/// Rewrite `Link`, `Image`, `AutoLink`, `AutoUrl` and link definition URLs.
+pub struct Rewrite {
+  pub rules: RewriteRules,
+}
+
+impl Transform for Rewrite {
+  fn name(&self) -> &str {
+    "rewrite"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    rewrite(&mut doc.nodes, &self.rules);
+    Ok(())
+  }
+}
+
+fn rewrite(nodes: &mut [Node], rules: &RewriteRules) {
+  for node in nodes {
+    match &mut node.kind {
+      NodeKind::Link { url, .. }
+      | NodeKind::Image { url, .. }
+      | NodeKind::AutoLink { url }
+      | NodeKind::AutoUrl { url }
+      | NodeKind::LinkDefinition { url, .. } => *url = rules.apply(url),
+      _ => {}
+    }
+    rewrite(&mut node.children, rules);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  #[test]
+  fn test_apply_rules_in_order() {
+    let rules = RewriteRules::from_config(
+      "# docs\nprefix /old/ /guide/\nextension .md .html\nbase https://x.dev/docs/\n",
+    )
+    .unwrap();
+    assert_eq!(
+      rules.apply("/old/setup.md#install"),
+      "https://x.dev/docs/guide/setup.html#install"
+    );
+    assert_eq!(rules.apply("./a.md?v=1"), "https://x.dev/docs/a.html?v=1");
+    assert_eq!(rules.apply("#top"), "#top");
+    assert_eq!(rules.apply("https://y.dev/a.md"), "https://y.dev/a.md");
+    assert_eq!(rules.apply("mailto:me@x.dev"), "mailto:me@x.dev");
+  }
+
+  #[test]
+  fn test_rewrites_document_urls() {
+    let mut doc = MarkdownParser::new("[a](intro.md) ![b](img/b.png)").parse();
+    let rules = RewriteRules::from_config("extension .md .html\nbase /site").unwrap();
+    Rewrite { rules }.transform(&mut doc).unwrap();
+    let urls: Vec<_> = doc.nodes[0]
+      .children
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::Link { url, .. } | NodeKind::Image { url, .. } => Some(url.as_str()),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(urls, vec!["/site/intro.html", "/site/img/b.png"]);
+  }
+
+  #[test]
+  fn test_config_errors() {
+    assert_eq!(
+      RewriteRules::from_config("\nredirect a b").unwrap_err(),
+      "line 2: unknown rewrite rule: redirect"
+    );
+    assert!(RewriteRules::from_config("extension md html").is_err());
+    assert!(RewriteRules::from_config("base").is_err());
+  }
+}
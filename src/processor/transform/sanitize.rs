@@ -0,0 +1,421 @@
+//! HTML sanitization for untrusted Markdown (`--sanitize`).
+
+use super::Transform;
+use crate::ast::{Document, HtmlTagKind, Node, NodeKind, Span};
+use crate::markdown::html::{html_len, scan_tag};
+
+/// What happens to a blocked tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeMode {
+  /// Remove the tag, and for paired tags everything up to the closing tag
+  Strip,
+  /// Keep the tag as literal text so it displays instead of running
+  Escape,
+}
+
+/// Which HTML is dangerous. Event-handler (`on*`) attributes and attributes
+/// holding a URL with a blocked scheme are always removed from kept tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizePolicy {
+  pub mode: SanitizeMode,
+  /// Lowercase tag names that are never kept
+  pub blocked_tags: Vec<String>,
+  /// Lowercase URL schemes, without the `:`
+  pub blocked_schemes: Vec<String>,
+}
+
+impl SanitizePolicy {
+  pub fn new(mode: SanitizeMode) -> Self {
+    let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+    Self {
+      mode,
+      blocked_tags: strings(&[
+        "script", "style", "iframe", "frame", "frameset", "object", "embed", "applet", "base",
+        "meta", "link", "form",
+      ]),
+      blocked_schemes: strings(&["javascript", "vbscript", "data"]),
+    }
+  }
+
+  fn blocks_tag(&self, name: &str) -> bool {
+    self.blocked_tags.iter().any(|t| t == name)
+  }
+
+  /// Compares the scheme with character references decoded and
+  /// whitespace and control characters removed, as a browser reads an
+  /// `href` (`java&#115;cript:`, `javascript&colon;`, `java\tscript:`).
+  fn blocks_url(&self, url: &str) -> bool {
+    let compact: String = decode_references(url)
+      .chars()
+      .filter(|c| !c.is_whitespace() && !c.is_control())
+      .collect::<String>()
+      .to_ascii_lowercase();
+    let Some((scheme, _)) = compact.split_once(':') else {
+      return false;
+    };
+    self.blocked_schemes.iter().any(|s| s == scheme)
+  }
+
+  fn blocks_attribute(&self, key: &str, value: &str) -> bool {
+    const URL_ATTRIBUTES: &[&str] = &[
+      "href",
+      "src",
+      "action",
+      "formaction",
+      "poster",
+      "background",
+      "xlink:href",
+    ];
+    key.starts_with("on") || URL_ATTRIBUTES.contains(&key) && self.blocks_url(value)
+  }
+}
+
+impl Default for SanitizePolicy {
+  fn default() -> Self {
+    Self::new(SanitizeMode::Strip)
+  }
+}
+
+/// Strip or escape dangerous HTML and URLs. Runs after `html-tags` when both
+/// are enabled; raw `HtmlInline` tags are handled too.
+pub struct Sanitize {
+  pub policy: SanitizePolicy,
+}
+
+impl Transform for Sanitize {
+  fn name(&self) -> &str {
+    "sanitize"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    let nodes = std::mem::take(&mut doc.nodes);
+    doc.nodes = self.clean(nodes);
+    Ok(())
+  }
+}
+
+impl Sanitize {
+  fn clean(&self, nodes: Vec<Node>) -> Vec<Node> {
+    let mut out = Vec::with_capacity(nodes.len());
+    // Name of a blocked paired tag whose content is being stripped.
+    let mut skipping: Option<String> = None;
+
+    for mut node in nodes {
+      if let Some(name) = &skipping {
+        if closes(&node, name) {
+          skipping = None;
+        }
+        continue;
+      }
+
+      node.kind = match node.kind {
+        NodeKind::HtmlInline { content } => match self.clean_raw(&content) {
+          Raw::Keep(content) => NodeKind::HtmlInline { content },
+          Raw::Blocked { name, paired } => {
+            match self.policy.mode {
+              SanitizeMode::Escape => out.push(text(content, node.span)),
+              SanitizeMode::Strip if paired => skipping = Some(name),
+              SanitizeMode::Strip => {}
+            }
+            continue;
+          }
+        },
+        NodeKind::HtmlTag {
+          name,
+          tag,
+          mut attributes,
+        } => {
+          if self.policy.blocks_tag(&name) {
+            match self.policy.mode {
+              SanitizeMode::Escape => {
+                out.push(text(render_tag(&name, tag, &attributes), node.span))
+              }
+              SanitizeMode::Strip if tag == HtmlTagKind::Open => skipping = Some(name),
+              SanitizeMode::Strip => {}
+            }
+            continue;
+          }
+          attributes.retain(|(key, value)| !self.policy.blocks_attribute(key, value));
+          NodeKind::HtmlTag {
+            name,
+            tag,
+            attributes,
+          }
+        }
+        NodeKind::Link { ref url, .. } if self.policy.blocks_url(url) => {
+          // Keep the link text, drop the link.
+          out.extend(self.clean(node.children));
+          continue;
+        }
+        NodeKind::Image {
+          ref url, ref alt, ..
+        } if self.policy.blocks_url(url) => {
+          if self.policy.mode == SanitizeMode::Escape && !alt.is_empty() {
+            out.push(text(alt.clone(), node.span));
+          }
+          continue;
+        }
+        // Nothing to show; its references were already resolved to links.
+        NodeKind::LinkDefinition { ref url, .. } if self.policy.blocks_url(url) => continue,
+        NodeKind::AutoLink { ref url } if self.policy.blocks_url(url) => {
+          if self.policy.mode == SanitizeMode::Escape {
+            out.push(text(url.clone(), node.span));
+          }
+          continue;
+        }
+        kind => kind,
+      };
+      node.children = self.clean(node.children);
+      out.push(node);
+    }
+    out
+  }
+
+  /// Sanitize raw inline HTML: a single tag loses dangerous attributes.
+  fn clean_raw(&self, content: &str) -> Raw {
+    if html_len(content) != Some(content.len()) || content.starts_with("<!--") {
+      return Raw::Keep(content.to_string());
+    }
+    let Some(tag) = scan_tag(content) else {
+      return Raw::Keep(content.to_string());
+    };
+    if self.policy.blocks_tag(&tag.name) {
+      return Raw::Blocked {
+        paired: tag.kind == HtmlTagKind::Open,
+        name: tag.name,
+      };
+    }
+    if !tag
+      .attributes
+      .iter()
+      .any(|(key, value)| self.policy.blocks_attribute(key, value))
+    {
+      return Raw::Keep(content.to_string());
+    }
+    let kept: Vec<_> = tag
+      .attributes
+      .into_iter()
+      .filter(|(key, value)| !self.policy.blocks_attribute(key, value))
+      .collect();
+    Raw::Keep(render_tag(&tag.name, tag.kind, &kept))
+  }
+}
+
+enum Raw {
+  Keep(String),
+  Blocked { name: String, paired: bool },
+}
+
+/// Whether `node` is the closing tag for `name`, raw or tokenized.
+fn closes(node: &Node, name: &str) -> bool {
+  match &node.kind {
+    NodeKind::HtmlTag {
+      name: n,
+      tag: HtmlTagKind::Close,
+      ..
+    } => n == name,
+    NodeKind::HtmlInline { content } => {
+      scan_tag(content).is_some_and(|t| t.kind == HtmlTagKind::Close && t.name == name)
+    }
+    _ => false,
+  }
+}
+
+/// Decode the HTML character references a browser would in an attribute:
+/// numeric ones, with or without the `;`, and the named ones that can
+/// spell out a scheme. Anything else stays as written.
+fn decode_references(s: &str) -> String {
+  const NAMED: &[(&str, char)] = &[
+    ("colon;", ':'),
+    ("Tab;", '\t'),
+    ("NewLine;", '\n'),
+    ("amp;", '&'),
+    ("sol;", '/'),
+    ("lpar;", '('),
+    ("rpar;", ')'),
+    ("period;", '.'),
+    ("nbsp;", '\u{a0}'),
+  ];
+  let mut out = String::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(amp) = rest.find('&') {
+    out.push_str(&rest[..amp]);
+    let after = &rest[amp + 1..];
+    let decoded = match after.strip_prefix('#') {
+      Some(num) => {
+        let (digits, radix, prefix) = match num.strip_prefix(['x', 'X']) {
+          Some(hex) => (hex, 16, 2),
+          None => (num, 10, 1),
+        };
+        let len = digits
+          .find(|c: char| !c.is_digit(radix))
+          .unwrap_or(digits.len());
+        u32::from_str_radix(&digits[..len], radix)
+          .ok()
+          .filter(|_| len > 0)
+          .map(|code| {
+            let c = char::from_u32(code).unwrap_or('\u{fffd}');
+            let semi = digits[len..].starts_with(';') as usize;
+            (c, prefix + len + semi)
+          })
+      }
+      None => NAMED
+        .iter()
+        .find(|(name, _)| after.starts_with(name))
+        .map(|&(name, c)| (c, name.len())),
+    };
+    match decoded {
+      Some((c, len)) => {
+        out.push(c);
+        rest = &after[len..];
+      }
+      None => {
+        out.push('&');
+        rest = after;
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+fn text(content: String, span: Span) -> Node {
+  Node::new(NodeKind::Text { content }, span)
+}
+
+fn render_tag(name: &str, tag: HtmlTagKind, attributes: &[(String, String)]) -> String {
+  if tag == HtmlTagKind::Close {
+    return format!("</{}>", name);
+  }
+  let mut out = format!("<{}", name);
+  for (key, value) in attributes {
+    if value.is_empty() {
+      out.push_str(&format!(" {}", key));
+    } else {
+      out.push_str(&format!(" {}=\"{}\"", key, value.replace('"', "&quot;")));
+    }
+  }
+  out.push_str(if tag == HtmlTagKind::SelfClosing {
+    " />"
+  } else {
+    ">"
+  });
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+  use crate::processor::transform::HtmlTags;
+
+  fn sanitize(input: &str, mode: SanitizeMode, tokenize: bool) -> Vec<String> {
+    let mut doc = MarkdownParser::new(input).parse();
+    if tokenize {
+      HtmlTags.transform(&mut doc).unwrap();
+    }
+    Sanitize {
+      policy: SanitizePolicy::new(mode),
+    }
+    .transform(&mut doc)
+    .unwrap();
+    doc.nodes[0]
+      .children
+      .iter()
+      .map(|n| match &n.kind {
+        NodeKind::Text { content } => format!("text {}", content),
+        NodeKind::HtmlInline { content } => format!("html {}", content),
+        NodeKind::HtmlTag {
+          name, attributes, ..
+        } => format!("tag {} {:?}", name, attributes),
+        NodeKind::Link { url, .. } => format!("link {}", url),
+        other => format!("{:?}", other),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_strips_blocked_tags_and_content() {
+    let input = "a<script>alert(1)</script>b <b onclick=\"x()\" title=\"t\">c</b>";
+    for tokenize in [false, true] {
+      let out = sanitize(input, SanitizeMode::Strip, tokenize);
+      assert_eq!(out[0], "text a");
+      assert_eq!(out[1], "text b ");
+      assert!(!out
+        .iter()
+        .any(|s| s.contains("alert") || s.contains("onclick")));
+    }
+    assert_eq!(
+      sanitize(input, SanitizeMode::Strip, false)[2],
+      "html <b title=\"t\">"
+    );
+  }
+
+  #[test]
+  fn test_escape_keeps_tags_as_text() {
+    let out = sanitize("<iframe src=\"x\"></iframe>", SanitizeMode::Escape, true);
+    assert_eq!(out, vec!["text <iframe src=\"x\">", "text </iframe>"]);
+  }
+
+  #[test]
+  fn test_dangerous_urls() {
+    let out = sanitize(
+      "[click](JavaScript:run) [ok](https://x.dev) ![img](data:text/html,x)",
+      SanitizeMode::Strip,
+      false,
+    );
+    assert_eq!(
+      out,
+      vec!["text click", "text  ", "link https://x.dev", "text  "]
+    );
+    let policy = SanitizePolicy::default();
+    assert!(policy.blocks_url(" java\tscript:x"));
+    assert!(!policy.blocks_url("/docs/javascript:notes"));
+  }
+
+  #[test]
+  fn test_character_references_in_urls() {
+    let policy = SanitizePolicy::default();
+    for url in [
+      "java&#115;cript:alert(3)",
+      "javascript&colon;alert(1)",
+      "&#x6A;avascript:x",
+      "&#106avascript:x",
+      "java&Tab;script:x",
+      "&#0000106;avascript:x",
+    ] {
+      assert!(policy.blocks_url(url), "{}", url);
+    }
+    assert!(!policy.blocks_url("/a?b=1&amp;c=2"));
+    assert!(!policy.blocks_url("https://x.dev/&#58;"));
+
+    let out = sanitize(
+      "<a href=\"java&#115;cript:alert(3)\">x</a> [y](javascript&colon;run)",
+      SanitizeMode::Strip,
+      false,
+    );
+    assert_eq!(
+      out,
+      vec!["html <a>", "text x", "html </a>", "text  ", "text y"]
+    );
+  }
+
+  #[test]
+  fn test_link_definitions() {
+    let mut doc = MarkdownParser::new("[a]: javascript&colon;x\n[b]: /ok\n").parse();
+    Sanitize {
+      policy: SanitizePolicy::default(),
+    }
+    .transform(&mut doc)
+    .unwrap();
+    let urls: Vec<&str> = doc
+      .nodes
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::LinkDefinition { url, .. } => Some(url.as_str()),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(urls, vec!["/ok"]);
+  }
+}
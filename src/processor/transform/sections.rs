@@ -0,0 +1,102 @@
+//! Section spans for headings (`--section-spans`).
+
+use super::Transform;
+use crate::ast::{Document, Node, NodeKind, Span};
+
+/// Set each heading's `section` to the span of the content it governs: from
+/// the heading to the next heading of the same or a higher level among its
+/// siblings, or to the end of the last sibling when there is none. Slicing
+/// the source with it gives the heading, its text and all its subsections,
+/// so consumers can extract or re-render one section alone.
+pub struct Sections;
+
+impl Transform for Sections {
+  fn name(&self) -> &str {
+    "sections"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    assign(&mut doc.nodes);
+    Ok(())
+  }
+}
+
+fn assign(nodes: &mut [Node]) {
+  let last_end = nodes.iter().map(|n| n.span.end).max().unwrap_or(0);
+  for i in 0..nodes.len() {
+    let NodeKind::Heading { level, .. } = nodes[i].kind else {
+      assign(&mut nodes[i].children);
+      continue;
+    };
+    let end = nodes[i + 1..]
+      .iter()
+      .find(|n| matches!(n.kind, NodeKind::Heading { level: l, .. } if l <= level))
+      .map_or(last_end, |n| n.span.start);
+    let span = nodes[i].span;
+    if let NodeKind::Heading { section, .. } = &mut nodes[i].kind {
+      *section = Some(Span::new(span.start, end, span.line, span.column));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn sections(input: &str) -> Vec<(u8, &str)> {
+    let mut doc = MarkdownParser::new(input).parse();
+    Sections.transform(&mut doc).unwrap();
+    let mut out = Vec::new();
+    collect(&doc.nodes, input, &mut out);
+    out
+  }
+
+  fn collect<'a>(nodes: &[Node], input: &'a str, out: &mut Vec<(u8, &'a str)>) {
+    for node in nodes {
+      if let NodeKind::Heading {
+        level,
+        section: Some(s),
+        ..
+      } = node.kind
+      {
+        out.push((level, &input[s.start..s.end]));
+      }
+      collect(&node.children, input, out);
+    }
+  }
+
+  #[test]
+  fn test_nested_sections() {
+    let input = "Intro\n\n# A\n\nText\n\n## B\n\nMore\n\n### C\n\n## D\n\nEnd\n\n# E\n\nLast";
+    assert_eq!(
+      sections(input),
+      vec![
+        (1, "# A\n\nText\n\n## B\n\nMore\n\n### C\n\n## D\n\nEnd\n\n"),
+        (2, "## B\n\nMore\n\n### C\n\n"),
+        (3, "### C\n\n"),
+        (2, "## D\n\nEnd\n\n"),
+        (1, "# E\n\nLast"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_section_line_and_containers() {
+    let input = "> # Quoted\n> text\n\n# Top\n";
+    assert_eq!(
+      sections(input),
+      vec![(1, "# Quoted\n> text\n"), (1, "# Top\n")]
+    );
+
+    let mut doc = MarkdownParser::new("Text\n\n## Sub\n").parse();
+    Sections.transform(&mut doc).unwrap();
+    match &doc.nodes[1].kind {
+      NodeKind::Heading {
+        section: Some(section),
+        ..
+      } => assert_eq!((section.line, section.column), (3, 1)),
+      other => panic!("expected Heading, got {:?}", other),
+    }
+  }
+}
@@ -0,0 +1,166 @@
+//! Heading slug generation.
+
+use super::{text_of, Transform};
+use crate::ast::{Document, Node, NodeKind};
+
+use std::collections::HashMap;
+
+/// Fill missing heading ids with GitHub-style slugs, de-duplicated with
+/// `-1`, `-2`, ... suffixes, or `section` when the text has nothing to slug.
+/// Explicit `{#id}`s are kept as written, repeats included, so `--validate`
+/// can report them; generated slugs avoid them.
+pub struct Slugs;
+
+impl Transform for Slugs {
+  fn name(&self) -> &str {
+    "slugs"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    let mut seen = HashMap::new();
+    reserve_explicit(&doc.nodes, &mut seen);
+    assign(&mut doc.nodes, &mut seen);
+    Ok(())
+  }
+}
+
+/// Mark every explicit id as taken before any slug is generated.
+fn reserve_explicit(nodes: &[Node], seen: &mut HashMap<String, usize>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Heading { id: Some(id), .. } => {
+        seen.entry(id.clone()).or_insert(0);
+      }
+      NodeKind::Heading { .. } => {}
+      _ => reserve_explicit(&node.children, seen),
+    }
+  }
+}
+
+fn assign(nodes: &mut [Node], seen: &mut HashMap<String, usize>) {
+  for node in nodes {
+    if let NodeKind::Heading { id, .. } = &mut node.kind {
+      if id.is_none() {
+        let slug = slugify(&text_of(&node.children));
+        let base = if slug.is_empty() {
+          "section".to_string()
+        } else {
+          slug
+        };
+        *id = Some(dedupe(base, seen));
+      }
+      continue;
+    }
+    assign(&mut node.children, seen);
+  }
+}
+
+/// `base`, or `base-N` with the first `N` not already taken. `seen` maps
+/// each id to the number of suffixes tried on it.
+pub(crate) fn dedupe(base: String, seen: &mut HashMap<String, usize>) -> String {
+  if !seen.contains_key(&base) {
+    seen.insert(base.clone(), 0);
+    return base;
+  }
+  loop {
+    let count = seen.get_mut(&base).expect("base is seen");
+    *count += 1;
+    let slug = format!("{}-{}", base, count);
+    if !seen.contains_key(&slug) {
+      seen.insert(slug.clone(), 0);
+      return slug;
+    }
+  }
+}
+
+/// Lowercase, drop punctuation, spaces to hyphens.
+pub fn slugify(text: &str) -> String {
+  text
+    .trim()
+    .chars()
+    .filter_map(|c| match c {
+      c if c.is_alphanumeric() => Some(c.to_lowercase().next().unwrap_or(c)),
+      ' ' | '-' => Some('-'),
+      '_' => Some('_'),
+      _ => None,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn ids(input: &str) -> Vec<Option<String>> {
+    let mut doc = MarkdownParser::new(input).parse();
+    Slugs.transform(&mut doc).unwrap();
+    doc
+      .nodes
+      .iter()
+      .filter_map(|n| match &n.kind {
+        NodeKind::Heading { id, .. } => Some(id.clone()),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_slugify() {
+    assert_eq!(slugify("Hello, World!"), "hello-world");
+    assert_eq!(slugify("Use `foo_bar` now"), "use-foo_bar-now");
+    assert_eq!(slugify("Über Straße"), "über-straße");
+  }
+
+  #[test]
+  fn test_duplicate_headings() {
+    assert_eq!(
+      ids("# Intro\n## Intro\n## Intro\n## Intro 1"),
+      vec![
+        Some("intro".to_string()),
+        Some("intro-1".to_string()),
+        Some("intro-2".to_string()),
+        Some("intro-1-1".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_explicit_ids_are_kept() {
+    let some = |id: &str| Some(id.to_string());
+    assert_eq!(
+      ids(
+        "# Intro
+# A {#intro}
+# B {#intro}
+# Intro
+# A {#intro-1}"
+      ),
+      vec![
+        some("intro-2"),
+        some("intro"),
+        some("intro"),
+        some("intro-3"),
+        some("intro-1"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_empty_slug_falls_back() {
+    assert_eq!(
+      ids(
+        "# !!!
+# ???
+#
+# Section"
+      ),
+      vec![
+        Some("section".to_string()),
+        Some("section-1".to_string()),
+        Some("section-2".to_string()),
+        Some("section-3".to_string()),
+      ]
+    );
+  }
+}
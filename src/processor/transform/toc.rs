@@ -0,0 +1,150 @@
+//! Table of contents building for `<toc>` placeholders.
+
+use super::{text_of, Transform};
+use crate::ast::{Document, ListMarker, Node, NodeKind, ReferenceType, Span};
+
+/// Fill each `Toc` node with a nested list linking to the document's
+/// headings. Runs after `Slugs` so every heading has an id.
+pub struct TableOfContents;
+
+struct Entry {
+  level: u8,
+  id: String,
+  text: String,
+}
+
+impl Transform for TableOfContents {
+  fn name(&self) -> &str {
+    "toc"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    let mut entries = Vec::new();
+    collect_headings(&doc.nodes, &mut entries);
+    if !entries.is_empty() {
+      fill_tocs(&mut doc.nodes, &entries);
+    }
+    Ok(())
+  }
+}
+
+fn collect_headings(nodes: &[Node], out: &mut Vec<Entry>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Heading {
+        level,
+        id: Some(id),
+        ..
+      } => out.push(Entry {
+        level: *level,
+        id: id.clone(),
+        text: text_of(&node.children),
+      }),
+      NodeKind::Toc => {}
+      _ => collect_headings(&node.children, out),
+    }
+  }
+}
+
+fn fill_tocs(nodes: &mut [Node], entries: &[Entry]) {
+  for node in nodes {
+    if matches!(node.kind, NodeKind::Toc) {
+      node.children = vec![build_list(entries)];
+    } else {
+      fill_tocs(&mut node.children, entries);
+    }
+  }
+}
+
+/// Each entry opens an item; deeper entries that follow become its sublist.
+fn build_list(entries: &[Entry]) -> Node {
+  let mut items = Vec::new();
+  let mut i = 0;
+  while i < entries.len() {
+    let entry = &entries[i];
+    let end = entries[i + 1..]
+      .iter()
+      .position(|e| e.level <= entry.level)
+      .map_or(entries.len(), |p| i + 1 + p);
+
+    let link = Node::with_children(
+      NodeKind::Link {
+        url: format!("#{}", entry.id),
+        title: None,
+        ref_type: ReferenceType::Full,
+        attributes: Vec::new(),
+      },
+      Span::empty(),
+      vec![Node::new(
+        NodeKind::Text {
+          content: entry.text.clone(),
+        },
+        Span::empty(),
+      )],
+    );
+    let mut children = vec![Node::with_children(
+      NodeKind::Paragraph,
+      Span::empty(),
+      vec![link],
+    )];
+    if end > i + 1 {
+      children.push(build_list(&entries[i + 1..end]));
+    }
+    items.push(Node::with_children(
+      NodeKind::ListItem {
+        marker: ListMarker::Bullet('-'),
+        checked: None,
+      },
+      Span::empty(),
+      children,
+    ));
+    i = end;
+  }
+
+  Node::with_children(
+    NodeKind::List {
+      ordered: false,
+      start: None,
+      tight: true,
+    },
+    Span::empty(),
+    items,
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+  use crate::processor::transform::Slugs;
+
+  fn link_urls(node: &Node, out: &mut Vec<String>) {
+    if let NodeKind::Link { url, .. } = &node.kind {
+      out.push(url.clone());
+    }
+    node.children.iter().for_each(|c| link_urls(c, out));
+  }
+
+  #[test]
+  fn test_nested_toc() {
+    let mut doc = MarkdownParser::new("<toc />\n\n# A\n## A1\n### A1a\n## A2\n# B").parse();
+    Slugs.transform(&mut doc).unwrap();
+    TableOfContents.transform(&mut doc).unwrap();
+
+    let list = &doc.nodes[0].children[0];
+    assert_eq!(list.children.len(), 2, "two top-level items: A and B");
+    let a = &list.children[0];
+    assert_eq!(a.children[1].children.len(), 2, "A has A1 and A2");
+
+    let mut urls = Vec::new();
+    link_urls(&doc.nodes[0], &mut urls);
+    assert_eq!(urls, vec!["#a", "#a1", "#a1a", "#a2", "#b"]);
+  }
+
+  #[test]
+  fn test_toc_without_headings_stays_empty() {
+    let mut doc = MarkdownParser::new("<toc />\n\ntext").parse();
+    TableOfContents.transform(&mut doc).unwrap();
+    assert!(doc.nodes[0].children.is_empty());
+  }
+}
@@ -0,0 +1,159 @@
+//! `{{name}}` variable substitution.
+
+use super::Transform;
+use crate::ast::{Document, FrontmatterFormat, Node, NodeKind};
+
+/// Replace `{{name}}` in text with a value from the document's frontmatter
+/// (top-level `key: value` / `key = value` scalars) or from the globals
+/// given to `new`. Frontmatter wins; unknown names are left untouched.
+#[derive(Default)]
+pub struct Variables {
+  globals: Vec<(String, String)>,
+}
+
+impl Variables {
+  #[allow(dead_code)]
+  pub fn new(globals: Vec<(String, String)>) -> Self {
+    Self { globals }
+  }
+}
+
+impl Transform for Variables {
+  fn name(&self) -> &str {
+    "variables"
+  }
+
+  fn transform(&self, doc: &mut Document) -> Result<(), String> {
+    let mut vars = frontmatter_vars(&doc.nodes);
+    vars.extend(self.globals.iter().cloned());
+    if !vars.is_empty() {
+      substitute(&mut doc.nodes, &vars);
+    }
+    Ok(())
+  }
+}
+
+pub(crate) fn frontmatter_vars(nodes: &[Node]) -> Vec<(String, String)> {
+  let Some((format, content)) = nodes.iter().find_map(|n| match &n.kind {
+    NodeKind::Frontmatter { format, content } => Some((*format, content)),
+    _ => None,
+  }) else {
+    return Vec::new();
+  };
+  let separator = match format {
+    FrontmatterFormat::Yaml => ':',
+    FrontmatterFormat::Toml => '=',
+    FrontmatterFormat::Json => return Vec::new(),
+  };
+
+  content
+    .lines()
+    .filter(|line| !line.starts_with(char::is_whitespace))
+    .filter_map(|line| {
+      let (key, value) = line.split_once(separator)?;
+      let value = value.trim();
+      let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+      (!value.is_empty()).then(|| (key.trim().to_string(), value.to_string()))
+    })
+    .collect()
+}
+
+/// Whether frontmatter values mark a document as a draft: `draft: true` or
+/// `publish: false`.
+pub(crate) fn is_draft(vars: &[(String, String)]) -> bool {
+  vars.iter().any(|(key, value)| match key.as_str() {
+    "draft" => value.eq_ignore_ascii_case("true"),
+    "publish" => value.eq_ignore_ascii_case("false"),
+    _ => false,
+  })
+}
+
+fn substitute(nodes: &mut [Node], vars: &[(String, String)]) {
+  for node in nodes {
+    match &mut node.kind {
+      NodeKind::Text { content } if content.contains("{{") => {
+        *content = replace_vars(content, vars);
+      }
+      NodeKind::Frontmatter { .. }
+      | NodeKind::CodeBlock { .. }
+      | NodeKind::FencedCodeBlock { .. }
+      | NodeKind::CodeBlockExt { .. }
+      | NodeKind::IndentedCodeBlock => continue,
+      _ => {}
+    }
+    substitute(&mut node.children, vars);
+  }
+}
+
+fn replace_vars(text: &str, vars: &[(String, String)]) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(open) = rest.find("{{") {
+    let Some(close) = rest[open + 2..].find("}}") else {
+      break;
+    };
+    let name = rest[open + 2..open + 2 + close].trim();
+    out.push_str(&rest[..open]);
+    match vars.iter().find(|(k, _)| k == name) {
+      Some((_, value)) => out.push_str(value),
+      None => out.push_str(&rest[open..open + 4 + close]),
+    }
+    rest = &rest[open + 4 + close..];
+  }
+  out.push_str(rest);
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn texts(nodes: &[Node], out: &mut Vec<String>) {
+    for node in nodes {
+      if let NodeKind::Text { content } = &node.kind {
+        out.push(content.clone());
+      }
+      texts(&node.children, out);
+    }
+  }
+
+  #[test]
+  fn test_frontmatter_and_globals() {
+    let input = "---\nproduct: \"Bukvar\"\nnested:\n  skip: me\n---\n\nUse {{ product }} {{version}} on {{os}}.";
+    let mut doc = MarkdownParser::new(input).parse();
+    Variables::new(vec![
+      ("version".to_string(), "1.0".to_string()),
+      ("product".to_string(), "ignored".to_string()),
+    ])
+    .transform(&mut doc)
+    .unwrap();
+
+    let mut out = Vec::new();
+    texts(&doc.nodes, &mut out);
+    assert_eq!(out.concat(), "Use Bukvar 1.0 on {{os}}.");
+  }
+
+  #[test]
+  fn test_is_draft() {
+    let draft =
+      |input: &str| is_draft(&frontmatter_vars(&MarkdownParser::new(input).parse().nodes));
+    assert!(draft("---\ndraft: true\n---\n# A\n"));
+    assert!(draft("+++\npublish = false\n+++\n"));
+    assert!(!draft("---\ndraft: false\npublish: true\n---\n"));
+    assert!(!draft("# draft: true\n"));
+  }
+
+  #[test]
+  fn test_code_is_untouched() {
+    let input = "---\nname: x\n---\n\n```\n{{name}}\n```";
+    let mut doc = MarkdownParser::new(input).parse();
+    let before = format!("{:?}", doc.nodes[1]);
+    Variables::default().transform(&mut doc).unwrap();
+    assert_eq!(format!("{:?}", doc.nodes[1]), before);
+  }
+}
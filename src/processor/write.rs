@@ -2,32 +2,51 @@
 
 use crate::ast::Document;
 use crate::cli::{Args, OutputFormat};
-use crate::formats::{to_json, to_json_pretty, write_dast};
+use crate::formats::{to_dot, to_json_with, to_segments, to_sexp, DastWriter};
 
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::Path;
+use super::writer::Output;
 
-/// Write document output to file.
-pub fn write_output(doc: &Document, file_path: &Path, args: &Args) -> Result<(), String> {
-  let output_path = compute_output_path(file_path, args);
-  ensure_parent_dir(&output_path)?;
-  write_content(&output_path, doc, args)
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Serialize the document and hand it to `output` for writing.
+pub fn write_output(
+  doc: &Document,
+  file_path: &Path,
+  args: &Args,
+  output: &Output,
+) -> Result<(), String> {
+  let data = serialize(doc, args)?;
+  output.write(compute_output_path(file_path, args), data, "output")
 }
 
-fn compute_output_path(file_path: &Path, args: &Args) -> std::path::PathBuf {
+/// Where the AST for `file_path` is written.
+pub fn compute_output_path(file_path: &Path, args: &Args) -> PathBuf {
+  let extension = match args.format {
+    OutputFormat::Json => "json",
+    OutputFormat::Dast => "dast",
+    OutputFormat::Dot => "dot",
+    OutputFormat::Sexp => "sexp",
+    OutputFormat::Segments => "segments.json",
+  };
+  output_path(file_path, args, extension)
+}
+
+/// `<output>/<dir relative to input>/<file name>.<suffix>`; with `--flat`
+/// the relative directory is dropped.
+pub fn output_path(file_path: &Path, args: &Args, suffix: &str) -> PathBuf {
   let file_name = file_path
     .file_name()
     .and_then(|s| s.to_str())
     .unwrap_or("output");
-  let extension = match args.format {
-    OutputFormat::Json => "json",
-    OutputFormat::Dast => "dast",
+  let dir = match file_path.strip_prefix(&args.input) {
+    Ok(relative) if !args.flat => args.output.join(relative.parent().unwrap_or(Path::new(""))),
+    _ => args.output.clone(),
   };
-  args.output.join(format!("{}.{}", file_name, extension))
+  dir.join(format!("{}.{}", file_name, suffix))
 }
 
-fn ensure_parent_dir(path: &Path) -> Result<(), String> {
+pub fn ensure_parent_dir(path: &Path) -> Result<(), String> {
   path
     .parent()
     .map(|p| fs::create_dir_all(p).map_err(|e| format!("Failed to create output directory: {}", e)))
@@ -35,33 +54,48 @@ fn ensure_parent_dir(path: &Path) -> Result<(), String> {
     .map(|_| ())
 }
 
-fn write_content(path: &Path, doc: &Document, args: &Args) -> Result<(), String> {
+fn serialize(doc: &Document, args: &Args) -> Result<Vec<u8>, String> {
   match args.format {
-    OutputFormat::Json => write_json(path, doc, args.pretty),
-    OutputFormat::Dast => write_binary(path, doc),
+    OutputFormat::Json => Ok(to_json_with(doc, &args.json_options()).into_bytes()),
+    OutputFormat::Dast => {
+      let mut data = Vec::new();
+      DastWriter::new()
+        .with_sorted_strings(args.sort_strings)
+        .with_index(args.dast_index)
+        .write(doc, &mut data)
+        .map_err(|e| format!("Failed to serialize DAST: {}", e))?;
+      Ok(data)
+    }
+    OutputFormat::Dot => Ok(to_dot(doc, args.dot_depth).into_bytes()),
+    OutputFormat::Sexp => Ok(to_sexp(doc).into_bytes()),
+    OutputFormat::Segments => Ok(to_segments(doc).into_bytes()),
   }
 }
 
-fn write_json(path: &Path, doc: &Document, pretty: bool) -> Result<(), String> {
-  let content = if pretty {
-    to_json_pretty(doc)
-  } else {
-    to_json(doc)
-  };
-  write_string_to_file(path, &content)
-}
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-fn write_binary(path: &Path, doc: &Document) -> Result<(), String> {
-  let data = write_dast(doc).map_err(|e| format!("Failed to serialize DAST: {}", e))?;
-  let mut file = File::create(path).map_err(|e| format!("Failed to create output file: {}", e))?;
-  file
-    .write_all(&data)
-    .map_err(|e| format!("Failed to write output: {}", e))
-}
+  fn args(flat: bool) -> Args {
+    Args {
+      input: PathBuf::from("docs"),
+      output: PathBuf::from("out"),
+      flat,
+      ..Args::default()
+    }
+  }
+
+  #[test]
+  fn test_output_mirrors_input_tree() {
+    let path = compute_output_path(Path::new("docs/a/b.md"), &args(false));
+    assert_eq!(path, PathBuf::from("out/a/b.md.dast"));
+    let top = output_path(Path::new("docs/c.py"), &args(false), "map.json");
+    assert_eq!(top, PathBuf::from("out/c.py.map.json"));
+  }
 
-fn write_string_to_file(path: &Path, content: &str) -> Result<(), String> {
-  let mut file = File::create(path).map_err(|e| format!("Failed to create output file: {}", e))?;
-  file
-    .write_all(content.as_bytes())
-    .map_err(|e| format!("Failed to write output: {}", e))
+  #[test]
+  fn test_flat_output() {
+    let path = compute_output_path(Path::new("docs/a/b.md"), &args(true));
+    assert_eq!(path, PathBuf::from("out/b.md.dast"));
+  }
 }
@@ -0,0 +1,242 @@
+//! Output writer pool. Parse threads serialize each output and hand the
+//! buffer to a few writer threads over a bounded queue, so slow disks stall
+//! the parsers once the queue is full instead of letting buffers pile up.
+
+use super::write::ensure_parent_dir;
+
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Writer threads per pool; writing is I/O bound, so a couple suffice.
+pub const WRITER_THREADS: usize = 2;
+/// Serialized outputs that may wait in the queue before parsers block.
+pub const QUEUE_DEPTH: usize = 16;
+
+/// One serialized output of input file `index`.
+struct Job {
+  index: usize,
+  path: PathBuf,
+  data: Vec<u8>,
+  what: &'static str,
+}
+
+/// Where the outputs of one input file go: written on the spot, queued
+/// for a `WriterPool`, or only counted.
+pub struct Output {
+  index: usize,
+  queue: Option<SyncSender<Job>>,
+  /// Bytes handed to a counting output, which writes nothing
+  counted: Option<Cell<usize>>,
+  /// A write on the spot failed, or the queue was gone
+  failed: Cell<bool>,
+}
+
+impl Output {
+  /// Write on the calling thread.
+  pub const fn direct() -> Self {
+    Self {
+      index: 0,
+      queue: None,
+      counted: None,
+      failed: Cell::new(false),
+    }
+  }
+
+  /// Write nothing, only adding up the size of each output.
+  pub const fn counting() -> Self {
+    Self {
+      index: 0,
+      queue: None,
+      counted: Some(Cell::new(0)),
+      failed: Cell::new(false),
+    }
+  }
+
+  /// Bytes handed to a counting output so far.
+  pub fn counted(&self) -> usize {
+    self.counted.as_ref().map_or(0, Cell::get)
+  }
+
+  /// Whether a write through this output failed; failures of queued
+  /// writes come from `WriterPool::finish` instead.
+  pub fn failed(&self) -> bool {
+    self.failed.get()
+  }
+
+  /// Write `data` to `path`; `what` names the output in error messages.
+  /// Queued writes report their errors through `WriterPool::finish`.
+  pub fn write(&self, path: PathBuf, data: Vec<u8>, what: &'static str) -> Result<(), String> {
+    if let Some(counted) = &self.counted {
+      counted.set(counted.get() + data.len());
+      return Ok(());
+    }
+    let Some(queue) = &self.queue else {
+      return write_file(&path, &data, what).map_err(|e| self.fail(e));
+    };
+    let job = Job {
+      index: self.index,
+      path,
+      data,
+      what,
+    };
+    queue
+      .send(job)
+      .map_err(|_| self.fail(format!("Failed to write {}: output writer stopped", what)))
+  }
+
+  fn fail(&self, error: String) -> String {
+    self.failed.set(true);
+    error
+  }
+}
+
+/// Writer threads draining a bounded queue of serialized outputs.
+pub struct WriterPool {
+  queue: SyncSender<Job>,
+  workers: Vec<JoinHandle<Vec<(usize, String)>>>,
+}
+
+impl WriterPool {
+  pub fn new(threads: usize, depth: usize) -> Self {
+    let (queue, jobs) = mpsc::sync_channel(depth);
+    let jobs = Arc::new(Mutex::new(jobs));
+    let workers = (0..threads.max(1))
+      .map(|_| {
+        let jobs = Arc::clone(&jobs);
+        thread::spawn(move || drain(&jobs))
+      })
+      .collect();
+    Self { queue, workers }
+  }
+
+  /// Output handle for input file `index`.
+  pub fn output(&self, index: usize) -> Output {
+    Output {
+      index,
+      queue: Some(self.queue.clone()),
+      counted: None,
+      failed: Cell::new(false),
+    }
+  }
+
+  /// Wait until every queued output is written. Returns the failures as
+  /// `(file index, message)`, in no particular order.
+  pub fn finish(self) -> Result<Vec<(usize, String)>, String> {
+    drop(self.queue);
+    let mut failures = Vec::new();
+    for worker in self.workers {
+      failures.extend(worker.join().map_err(|_| "Output writer panicked")?);
+    }
+    Ok(failures)
+  }
+}
+
+/// Write jobs until every sender is gone.
+fn drain(jobs: &Mutex<Receiver<Job>>) -> Vec<(usize, String)> {
+  let mut failures = Vec::new();
+  loop {
+    // Hold the lock only while taking a job, not while writing it
+    let job = match jobs.lock() {
+      Ok(jobs) => jobs.recv(),
+      Err(_) => break,
+    };
+    let Ok(job) = job else {
+      break;
+    };
+    if let Err(e) = write_file(&job.path, &job.data, job.what) {
+      failures.push((job.index, e));
+    }
+  }
+  failures
+}
+
+/// Write through `<path>.partial` and rename it into place, so an
+/// interrupted or failed write never leaves a truncated output behind.
+fn write_file(path: &Path, data: &[u8], what: &str) -> Result<(), String> {
+  ensure_parent_dir(path)?;
+  let mut partial = path.as_os_str().to_owned();
+  partial.push(".partial");
+  let partial = PathBuf::from(partial);
+  fs::write(&partial, data)
+    .and_then(|_| fs::rename(&partial, path))
+    .map_err(|e| {
+      let _ = fs::remove_file(&partial);
+      format!("Failed to write {}: {}", what, e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_pool_writes_and_reports_failures() {
+    let dir = std::env::temp_dir().join(format!("bukvar-writer-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("blocker"), "").unwrap();
+
+    // A queue of one makes the senders wait for the writers
+    let pool = WriterPool::new(2, 1);
+    for i in 0..20 {
+      let path = dir.join(format!("sub/{}.txt", i));
+      pool
+        .output(i)
+        .write(path, i.to_string().into_bytes(), "output")
+        .unwrap();
+    }
+    let bad = dir.join("blocker/x.txt");
+    pool.output(7).write(bad, Vec::new(), "sourcemap").unwrap();
+    let failures = pool.finish().unwrap();
+
+    for i in 0..20 {
+      let written = fs::read_to_string(dir.join(format!("sub/{}.txt", i))).unwrap();
+      assert_eq!(written, i.to_string());
+    }
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, 7);
+    assert!(failures[0]
+      .1
+      .starts_with("Failed to create output directory"));
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_direct_output() {
+    let dir = std::env::temp_dir().join(format!("bukvar-direct-{}", std::process::id()));
+    let path = dir.join("a/b.json");
+    Output::direct()
+      .write(path.clone(), b"{}".to_vec(), "output")
+      .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+    assert!(!dir.join("a/b.json.partial").exists());
+
+    // A failed write leaves neither the output nor its temporary file
+    fs::create_dir_all(dir.join("a/c.json")).unwrap();
+    let err = Output::direct()
+      .write(dir.join("a/c.json"), b"{}".to_vec(), "output")
+      .unwrap_err();
+    assert!(err.starts_with("Failed to write output"));
+    assert!(!dir.join("a/c.json.partial").exists());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_counting_output() {
+    let output = Output::counting();
+    let path = PathBuf::from("/nonexistent/a.json");
+    output
+      .write(path.clone(), b"{}".to_vec(), "output")
+      .unwrap();
+    output
+      .write(path.clone(), vec![0; 10], "sourcemap")
+      .unwrap();
+    assert_eq!(output.counted(), 12);
+    assert!(!path.exists());
+    assert_eq!(Output::direct().counted(), 0);
+  }
+}
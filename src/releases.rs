@@ -0,0 +1,289 @@
+//! Release notes (`--releases`): changelogs in the
+//! [Keep a Changelog](https://keepachangelog.com) layout read into
+//! `releases.json`, one entry per release with its version, date and
+//! changes by category.
+//!
+//! A release is a level 2 heading naming a version, as in
+//! `## [1.2.3] - 2024-01-05`, `## 1.2.3 (2024-01-05)` or `## [Unreleased]`;
+//! a trailing `[YANKED]` marks it yanked, and a version linked through a
+//! link definition keeps the link as its `url`. Level 3 headings under a
+//! release (`### Added`, `### Fixed`, ...) name categories, and every list
+//! item up to the next heading is one change, nested items included.
+//! Documents without a release heading are not changelogs and are left out.
+
+use crate::ast::{Document, Node, NodeKind};
+use crate::formats::esc;
+use crate::processor::transform::{iso_date, text_of};
+
+use std::path::{Path, PathBuf};
+
+/// Category for changes listed before any category heading.
+const UNCATEGORIZED: &str = "Changed";
+
+/// One release of a changelog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+  /// `1.2.3`, without brackets or a leading `v`, or `Unreleased`
+  pub version: String,
+  /// ISO 8601 date, when the heading has one that can be read
+  pub date: Option<String>,
+  /// Target of the version's link, if it is linked
+  pub url: Option<String>,
+  pub yanked: bool,
+  /// Line of the release heading
+  pub line: usize,
+  /// Categories in document order, each with its changes
+  pub sections: Vec<(String, Vec<String>)>,
+}
+
+/// Releases of a changelog, in document order; empty when the document
+/// has no release headings.
+pub fn collect(doc: &Document) -> Vec<Release> {
+  let mut releases: Vec<Release> = Vec::new();
+  let mut after_list = false;
+  for node in &doc.nodes {
+    let list = matches!(node.kind, NodeKind::List { .. });
+    match &node.kind {
+      NodeKind::Heading { level: 2, .. } => match release(node) {
+        Some(release) => releases.push(release),
+        // Any other level 2 heading ends the release before it
+        None if !releases.is_empty() => break,
+        None => {}
+      },
+      NodeKind::Heading { level, .. } if *level <= 1 => {}
+      NodeKind::Heading { .. } => {
+        if let Some(release) = releases.last_mut() {
+          let name = collapse(&text_of(&node.children));
+          release.sections.push((name, Vec::new()));
+        }
+      }
+      NodeKind::List { .. } => {
+        if let Some(release) = releases.last_mut() {
+          if release.sections.is_empty() {
+            release
+              .sections
+              .push((UNCATEGORIZED.to_string(), Vec::new()));
+          }
+          let (_, changes) = release.sections.last_mut().unwrap();
+          list_items(node, changes);
+        }
+      }
+      // List items end at their line, so the wrapped rest of a change
+      // follows its list as an indented paragraph
+      NodeKind::Paragraph if after_list && indented(node) => {
+        let change = releases
+          .last_mut()
+          .and_then(|release| release.sections.last_mut())
+          .and_then(|(_, changes)| changes.last_mut());
+        if let Some(change) = change {
+          change.push(' ');
+          change.push_str(&collapse(&text_of(&node.children)));
+        }
+        continue;
+      }
+      _ => {}
+    }
+    after_list = list;
+  }
+  for release in &mut releases {
+    release.sections.retain(|(_, changes)| !changes.is_empty());
+  }
+  releases
+}
+
+/// Whether a paragraph's text starts after its first column.
+fn indented(paragraph: &Node) -> bool {
+  paragraph
+    .children
+    .first()
+    .is_some_and(|child| child.span.start > paragraph.span.start)
+}
+
+/// The release a level 2 heading names, if it names one.
+fn release(heading: &Node) -> Option<Release> {
+  let url = heading.children.iter().find_map(|child| match &child.kind {
+    NodeKind::Link { url, .. } => Some(url.clone()),
+    _ => None,
+  });
+  let text = collapse(&text_of(&heading.children));
+  let (text, yanked) = match strip_suffix_ignore_case(&text, "[YANKED]") {
+    Some(rest) => (rest.trim_end(), true),
+    None => (text.as_str(), false),
+  };
+  let (version, rest) = match text.split_once(|c: char| c.is_whitespace()) {
+    Some((version, rest)) => (version, rest.trim()),
+    None => (text, ""),
+  };
+  let version = version.trim_start_matches('[').trim_end_matches(']');
+  let version = if version.eq_ignore_ascii_case("unreleased") {
+    "Unreleased"
+  } else {
+    let version = version
+      .strip_prefix(['v', 'V'])
+      .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+      .unwrap_or(version);
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+      return None;
+    }
+    version
+  };
+  let date = rest
+    .trim_start_matches(['-', '–', '—'])
+    .trim()
+    .trim_start_matches('(')
+    .trim_end_matches(')');
+  Some(Release {
+    version: version.to_string(),
+    date: iso_date(date),
+    url,
+    yanked,
+    line: heading.span.line,
+    sections: Vec::new(),
+  })
+}
+
+/// Each item of `list` as one change, items of nested lists after their
+/// parent.
+fn list_items(list: &Node, out: &mut Vec<String>) {
+  for item in &list.children {
+    let (text, nested): (Vec<&Node>, Vec<&Node>) = item
+      .children
+      .iter()
+      .partition(|child| !matches!(child.kind, NodeKind::List { .. }));
+    let text = text
+      .iter()
+      .map(|node| collapse(&text_of(std::slice::from_ref(*node))))
+      .collect::<Vec<_>>()
+      .join(" ");
+    if !text.is_empty() {
+      out.push(text);
+    }
+    for list in nested {
+      list_items(list, out);
+    }
+  }
+}
+
+fn strip_suffix_ignore_case<'t>(text: &'t str, suffix: &str) -> Option<&'t str> {
+  let at = text.len().checked_sub(suffix.len())?;
+  (text.is_char_boundary(at) && text[at..].eq_ignore_ascii_case(suffix)).then(|| &text[..at])
+}
+
+fn collapse(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `{"changelogs":[{"source","releases":[{"version","date","url","yanked",
+/// "line","sections":[{"name","changes"}]}]}]}`, paths with `/`.
+pub fn to_json(changelogs: &[(PathBuf, Vec<Release>)]) -> String {
+  let mut s = String::with_capacity(32 + changelogs.len() * 1024);
+  s.push_str("{\"changelogs\":[");
+  for (i, (source, releases)) in changelogs.iter().enumerate() {
+    if i > 0 {
+      s.push(',');
+    }
+    s.push_str(&format!(
+      "{{\"source\":\"{}\",\"releases\":[",
+      esc(&display(source))
+    ));
+    for (j, release) in releases.iter().enumerate() {
+      if j > 0 {
+        s.push(',');
+      }
+      s.push_str(&format!(
+        "{{\"version\":\"{}\",\"date\":{},\"url\":{},\"yanked\":{},\"line\":{},\"sections\":[",
+        esc(&release.version),
+        json_opt(&release.date),
+        json_opt(&release.url),
+        release.yanked,
+        release.line
+      ));
+      for (k, (name, changes)) in release.sections.iter().enumerate() {
+        if k > 0 {
+          s.push(',');
+        }
+        let changes: Vec<String> = changes
+          .iter()
+          .map(|change| format!("\"{}\"", esc(change)))
+          .collect();
+        s.push_str(&format!(
+          "{{\"name\":\"{}\",\"changes\":[{}]}}",
+          esc(name),
+          changes.join(",")
+        ));
+      }
+      s.push_str("]}");
+    }
+    s.push_str("]}");
+  }
+  s.push_str("]}");
+  s
+}
+
+fn json_opt(value: &Option<String>) -> String {
+  value
+    .as_deref()
+    .map_or_else(|| "null".to_string(), |v| format!("\"{}\"", esc(v)))
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  const CHANGELOG: &str = "# Changelog\n\nIntro\n\n## [Unreleased]\n\n### Added\n\n\
+                           - New *thing*\n  wrapped\n  - and its part\n\n## [1.2.0] - 2024-01-05\n\n\
+                           ### Fixed\n\n- Bug `x`\n\n### Removed\n\n## v1.0.0 (Dec 1, 2023) [YANKED]\n\n\
+                           - Initial\n\n## Links\n\n- not a change\n\n\
+                           [1.2.0]: https://example.com/v1.2.0\n";
+
+  #[test]
+  fn test_collect() {
+    let releases = collect(&MarkdownParser::new(CHANGELOG).parse());
+    let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+    assert_eq!(versions, ["Unreleased", "1.2.0", "1.0.0"]);
+    let dates: Vec<Option<&str>> = releases.iter().map(|r| r.date.as_deref()).collect();
+    assert_eq!(dates, [None, Some("2024-01-05"), Some("2023-12-01")]);
+    assert_eq!(
+      releases[1].url.as_deref(),
+      Some("https://example.com/v1.2.0")
+    );
+    assert!(releases[0].url.is_none());
+    assert!(releases[2].yanked && !releases[1].yanked);
+    assert_eq!(releases[1].line, 13);
+    let section = |r: usize| releases[r].sections.clone();
+    assert_eq!(
+      section(0),
+      [(
+        "Added".to_string(),
+        vec!["New thing wrapped".to_string(), "and its part".to_string()]
+      )]
+    );
+    // Empty categories are dropped
+    assert_eq!(
+      section(1),
+      [("Fixed".to_string(), vec!["Bug x".to_string()])]
+    );
+    assert_eq!(
+      section(2),
+      [(UNCATEGORIZED.to_string(), vec!["Initial".to_string()])]
+    );
+    assert!(collect(&MarkdownParser::new("# Notes\n\n## Setup\n\n- step\n").parse()).is_empty());
+  }
+
+  #[test]
+  fn test_to_json() {
+    let releases =
+      collect(&MarkdownParser::new("## 2.0.0 - 2024-02-01\n\n### Added\n\n- \"x\"\n").parse());
+    assert_eq!(
+      to_json(&[(PathBuf::from("CHANGELOG.md"), releases)]),
+      "{\"changelogs\":[{\"source\":\"CHANGELOG.md\",\"releases\":[{\"version\":\"2.0.0\",\
+       \"date\":\"2024-02-01\",\"url\":null,\"yanked\":false,\"line\":1,\"sections\":[\
+       {\"name\":\"Added\",\"changes\":[\"\\\"x\\\"\"]}]}]}]}"
+    );
+  }
+}
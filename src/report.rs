@@ -0,0 +1,567 @@
+//! Console reporting: banner, per-file progress and the final summary.
+//!
+//! Colors are used only when the stream is a terminal and `NO_COLOR` is
+//! unset. `--quiet` prints nothing but errors; `--json-output` prints a
+//! single JSON summary to stdout.
+
+use crate::ast::DocumentType;
+use crate::cli::Args;
+use crate::estimate::Estimate;
+use crate::formats::esc;
+use crate::memory::{human, MemoryStats};
+use crate::parsers::coverage::percent;
+use crate::processor::transform::PassTiming;
+use crate::processor::{
+  FileReport, Finding, ProcessingStats, Severity, EXIT_INTERRUPTED, EXIT_IO, EXIT_PARSE_ERRORS,
+  EXIT_SUCCESS, EXIT_VALIDATION,
+};
+
+use std::env;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportMode {
+  Normal,
+  Quiet,
+  Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct Reporter {
+  mode: ReportMode,
+  verbose: bool,
+  color: bool,
+  err_color: bool,
+}
+
+impl Reporter {
+  pub fn from_args(args: &Args) -> Self {
+    let mode = if args.json_output {
+      ReportMode::Json
+    } else if args.quiet {
+      ReportMode::Quiet
+    } else {
+      ReportMode::Normal
+    };
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    Self {
+      mode,
+      verbose: args.verbose,
+      color: !no_color && io::stdout().is_terminal(),
+      err_color: !no_color && io::stderr().is_terminal(),
+    }
+  }
+
+  /// Plain reporter for tests.
+  #[cfg(test)]
+  fn plain(mode: ReportMode) -> Self {
+    Self {
+      mode,
+      verbose: false,
+      color: false,
+      err_color: false,
+    }
+  }
+
+  fn paint(&self, code: &str, text: &str) -> String {
+    paint(self.color, code, text)
+  }
+
+  pub fn banner(&self, args: &Args) {
+    if self.mode != ReportMode::Normal {
+      return;
+    }
+    println!();
+    println!(
+      "{}  {}",
+      self.paint("1;36", "Bukvar v1.0.0"),
+      self.paint("90", "(Glagolica Project)")
+    );
+    println!(
+      "{}",
+      self.paint("90", "Ultra-fast zero-dependency markdown parser")
+    );
+    println!();
+    println!(
+      "  Input:  {}",
+      args.input.to_string_lossy().replace('\\', "/")
+    );
+    println!(
+      "  Output: {}",
+      args.output.to_string_lossy().replace('\\', "/")
+    );
+    println!("  Format: {:?}", args.format);
+    println!();
+  }
+
+  /// One processed file, shown with `--verbose` along with its
+  /// undocumented declarations.
+  pub fn file_done(&self, path: &Path, report: &FileReport) {
+    if !self.verbose || self.mode != ReportMode::Normal {
+      return;
+    }
+    match &report.coverage {
+      Some(coverage) => {
+        println!(
+          "  Processed: {} ({} nodes, {}/{} documented)",
+          path.display(),
+          report.node_count,
+          coverage.documented(),
+          coverage.total()
+        );
+        for decl in coverage.undocumented() {
+          println!(
+            "    {} {} {} (line {})",
+            self.paint("33", "undocumented"),
+            decl.kind,
+            decl.name,
+            decl.line
+          );
+        }
+      }
+      None => println!(
+        "  Processed: {} ({} nodes{})",
+        path.display(),
+        report.node_count,
+        if report.streamed { ", streamed" } else { "" }
+      ),
+    }
+    print!("{}", timings_text(&report.timings));
+  }
+
+  /// One failed file; shown with `--verbose` and in quiet/JSON modes.
+  pub fn file_error(&self, path: &Path, error: &str) {
+    if self.verbose || self.mode != ReportMode::Normal {
+      eprintln!("  Error processing {}: {}", path.display(), error);
+    }
+  }
+
+  /// Validation and lint findings of one file, on stderr. `--quiet`
+  /// shows only the errors; `--json-output` lists them in the summary.
+  pub fn findings(&self, path: &Path, findings: &[Finding]) {
+    let text = match self.mode {
+      ReportMode::Normal => findings_text(path, findings, Severity::Warning),
+      ReportMode::Quiet => findings_text(path, findings, Severity::Error),
+      ReportMode::Json => return,
+    };
+    eprint!("{}", text);
+  }
+
+  /// A file or directory left out of the run, shown with `--verbose`.
+  pub fn file_skipped(&self, path: &Path, reason: &str) {
+    if self.verbose && self.mode == ReportMode::Normal {
+      println!("  Skipped: {} ({})", path.display(), reason);
+    }
+  }
+
+  /// A fatal error; always shown.
+  pub fn error(&self, message: &str) {
+    eprintln!("{} {}", paint(self.err_color, "1;31", "Error:"), message);
+  }
+
+  /// The `--estimate` result; printed even with `--quiet`, as it is all
+  /// the run produces.
+  pub fn estimate(&self, estimate: &Estimate) {
+    match self.mode {
+      ReportMode::Json => println!("{}", estimate.to_json()),
+      _ => print!("{}", estimate.to_text()),
+    }
+  }
+
+  /// The final summary of a run that exits with `exit_code`.
+  pub fn summary(&self, stats: &ProcessingStats, elapsed: Duration, exit_code: i32) {
+    match self.mode {
+      ReportMode::Quiet => {}
+      ReportMode::Json => println!("{}", summary_json(stats, elapsed)),
+      ReportMode::Normal => self.summary_text(stats, elapsed, exit_code),
+    }
+  }
+
+  fn summary_text(&self, stats: &ProcessingStats, elapsed: Duration, exit_code: i32) {
+    let (color, headline) = headline(exit_code);
+    let rule = self.paint(color, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    let count = |n: usize| self.paint("36", &format!("{:>5}", n));
+
+    println!();
+    println!("{}", rule);
+    println!("{}", self.paint(color, &format!("  {}", headline)));
+    println!("{}", rule);
+    println!();
+    println!("{}", self.paint("1", "  Files Processed"));
+    for doc_type in DocumentType::ALL {
+      println!(
+        "    {:<12} {}",
+        format!("{:?}", doc_type),
+        count(stats.files_of(doc_type))
+      );
+    }
+    if stats.cancelled > 0 {
+      println!(
+        "    {:<12} {}",
+        "Not started",
+        self.paint("33", &format!("{:>5}", stats.cancelled))
+      );
+    }
+    println!();
+    println!("{}", self.paint("1", "  AST Generated"));
+    println!(
+      "    Total nodes  {}",
+      self.paint("33", &format!("{:>5}", stats.total_nodes))
+    );
+    if stats.streamed > 0 {
+      println!(
+        "    Streamed     {}",
+        self.paint("36", &format!("{:>5}", stats.streamed))
+      );
+    }
+
+    if stats.errors > 0 {
+      println!(
+        "    Errors       {}",
+        self.paint("31", &format!("{:>5}", stats.errors))
+      );
+    }
+    if stats.validation_errors + stats.validation_warnings > 0 {
+      println!(
+        "    Validation   {} errors, {} warnings",
+        self.paint("31", &stats.validation_errors.to_string()),
+        self.paint("33", &stats.validation_warnings.to_string())
+      );
+    }
+
+    if let Some((documented, total)) = stats.doc_coverage() {
+      println!();
+      println!("{}", self.paint("1", "  Doc Coverage"));
+      println!(
+        "    Documented   {} ({}/{})",
+        self.paint("36", &format!("{:.1}%", percent(documented, total))),
+        documented,
+        total
+      );
+    }
+
+    if let Some(memory) = &stats.memory {
+      self.memory_text(memory);
+    }
+
+    println!();
+    println!("{}", self.paint("1", "  Performance"));
+    println!(
+      "    Time         {}",
+      self.paint("32", &format!("{:.2?}", elapsed))
+    );
+    println!(
+      "    Threads      {}",
+      self.paint("32", &stats.threads.to_string())
+    );
+
+    if elapsed.as_secs_f64() > 0.0 {
+      let throughput = stats.total_files() as f64 / elapsed.as_secs_f64();
+      println!(
+        "    Throughput   {}",
+        self.paint("32", &format!("{:.0} files/sec", throughput))
+      );
+    }
+
+    println!("{}", rule);
+    println!();
+  }
+
+  fn memory_text(&self, memory: &MemoryStats) {
+    let size = |bytes: usize| self.paint("36", &human(bytes as u64));
+    println!();
+    println!("{}", self.paint("1", "  Memory"));
+    if let Some(peak) = memory.peak_rss {
+      println!("    Peak RSS     {}", self.paint("36", &human(peak)));
+    }
+    println!(
+      "    AST total    {} (estimated)",
+      size(memory.total_ast_bytes)
+    );
+    if let Some((path, file)) = &memory.largest_ast {
+      println!(
+        "    Largest AST  {} {} (source {})",
+        size(file.ast_bytes),
+        path.display(),
+        human(file.source_bytes as u64)
+      );
+    }
+    if let Some((path, file)) = &memory.largest_source {
+      println!(
+        "    Largest file {} {} (AST {})",
+        size(file.source_bytes),
+        path.display(),
+        human(file.ast_bytes as u64)
+      );
+    }
+  }
+}
+
+/// Color and text of the summary headline for `exit_code`.
+fn headline(exit_code: i32) -> (&'static str, &'static str) {
+  match exit_code {
+    EXIT_SUCCESS => ("1;32", "✓ SUCCESS"),
+    EXIT_INTERRUPTED => ("1;33", "⚠ INTERRUPTED"),
+    EXIT_PARSE_ERRORS => ("1;31", "✗ FAILED: files did not parse"),
+    EXIT_VALIDATION => ("1;31", "✗ FAILED: validation"),
+    EXIT_IO => ("1;31", "✗ FAILED: outputs not written"),
+    _ => ("1;31", "✗ FAILED"),
+  }
+}
+
+fn paint(color: bool, code: &str, text: &str) -> String {
+  if color {
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+  } else {
+    text.to_string()
+  }
+}
+
+/// One line per transform pass of a file.
+fn timings_text(timings: &[PassTiming]) -> String {
+  timings
+    .iter()
+    .map(|t| format!("    {:<10} {:.2?}\n", t.name, t.elapsed))
+    .collect()
+}
+
+/// Findings of one file at `min` severity or above, grouped under a
+/// header per kind: validation errors, validation warnings, then lint.
+fn findings_text(path: &Path, findings: &[Finding], min: Severity) -> String {
+  // (header, severity, lint)
+  let groups = [
+    ("Validation errors", Severity::Error, false),
+    ("Validation warnings", Severity::Warning, false),
+    ("Lint warnings", Severity::Warning, true),
+  ];
+  let mut text = String::new();
+  for (header, severity, lint) in groups {
+    if severity == Severity::Warning && min == Severity::Error {
+      continue;
+    }
+    let mut group = findings
+      .iter()
+      .filter(|f| f.severity == severity && f.rule.is_some() == lint)
+      .peekable();
+    if group.peek().is_none() {
+      continue;
+    }
+    text.push_str(&format!("{} in {}:\n", header, path.display()));
+    for finding in group {
+      let tag = match (finding.rule, finding.severity) {
+        (Some(rule), _) => rule,
+        (None, Severity::Error) => "ERROR",
+        (None, Severity::Warning) => "WARN",
+      };
+      text.push_str(&format!("  [{}] {}", tag, finding.message));
+      if finding.line > 0 {
+        text.push_str(&format!(" at line {}", finding.line));
+      }
+      text.push('\n');
+    }
+  }
+  text
+}
+
+/// `[{"path":..,"line":..,"severity":..,"rule":..,"message":..}]`
+fn findings_json(stats: &ProcessingStats) -> String {
+  let findings = stats
+    .findings
+    .iter()
+    .flat_map(|(path, findings)| findings.iter().map(move |f| (path, f)))
+    .map(|(path, f)| {
+      format!(
+        "{{\"path\":\"{}\",\"line\":{},\"severity\":\"{}\",\"rule\":{},\"message\":\"{}\"}}",
+        esc(&path.to_string_lossy().replace('\\', "/")),
+        f.line,
+        f.severity.name(),
+        f.rule.map_or("null".to_string(), |r| format!("\"{}\"", r)),
+        esc(&f.message)
+      )
+    })
+    .collect::<Vec<_>>()
+    .join(",");
+  format!("[{}]", findings)
+}
+
+fn summary_json(stats: &ProcessingStats, elapsed: Duration) -> String {
+  let files = DocumentType::ALL
+    .iter()
+    .map(|t| format!("\"{}\":{}", t.name(), stats.files_of(*t)))
+    .collect::<Vec<_>>()
+    .join(",");
+  format!(
+    "{{\"files\":{{{}}},\"total_files\":{},\"total_nodes\":{},\"streamed\":{},\"errors\":{},\"interrupted\":{},\"cancelled\":{},\"validation_errors\":{},\"validation_warnings\":{},\"findings\":{},\"doc_coverage\":{},\"memory\":{},\"threads\":{},\"elapsed_ms\":{:.3}}}",
+    files,
+    stats.total_files(),
+    stats.total_nodes,
+    stats.streamed,
+    stats.errors,
+    stats.interrupted,
+    stats.cancelled,
+    stats.validation_errors,
+    stats.validation_warnings,
+    findings_json(stats),
+    coverage_json(stats),
+    stats
+      .memory
+      .as_ref()
+      .map_or("null".to_string(), MemoryStats::to_json),
+    stats.threads,
+    elapsed.as_secs_f64() * 1000.0
+  )
+}
+
+/// `{"percent":..,"documented":..,"total":..,"files":[..]}`, or `null`
+/// when no source files were processed.
+fn coverage_json(stats: &ProcessingStats) -> String {
+  let Some((documented, total)) = stats.doc_coverage() else {
+    return "null".to_string();
+  };
+  let files = stats
+    .coverage
+    .iter()
+    .map(|(path, coverage)| {
+      let undocumented = coverage
+        .undocumented()
+        .map(|d| {
+          format!(
+            "{{\"kind\":\"{}\",\"name\":\"{}\",\"line\":{}}}",
+            d.kind,
+            esc(&d.name),
+            d.line
+          )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+      format!(
+        "{{\"path\":\"{}\",\"documented\":{},\"total\":{},\"undocumented\":[{}]}}",
+        esc(&path.to_string_lossy().replace('\\', "/")),
+        coverage.documented(),
+        coverage.total(),
+        undocumented
+      )
+    })
+    .collect::<Vec<_>>()
+    .join(",");
+  format!(
+    "{{\"percent\":{:.1},\"documented\":{},\"total\":{},\"files\":[{}]}}",
+    percent(documented, total),
+    documented,
+    total,
+    files
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_paint_respects_color() {
+    assert_eq!(paint(true, "31", "x"), "\x1b[31mx\x1b[0m");
+    assert_eq!(Reporter::plain(ReportMode::Normal).paint("31", "x"), "x");
+  }
+
+  #[test]
+  fn test_headline_follows_exit_code() {
+    assert_eq!(headline(EXIT_SUCCESS).1, "✓ SUCCESS");
+    assert_eq!(headline(EXIT_INTERRUPTED).1, "⚠ INTERRUPTED");
+    for code in [EXIT_PARSE_ERRORS, EXIT_VALIDATION, EXIT_IO] {
+      assert!(headline(code).1.starts_with("✗ FAILED"), "{}", code);
+    }
+  }
+
+  #[test]
+  fn test_summary_json() {
+    let stats = ProcessingStats {
+      files: [(DocumentType::Markdown, 2), (DocumentType::Python, 1)].into(),
+      total_nodes: 40,
+      streamed: 1,
+      errors: 1,
+      threads: 4,
+      ..ProcessingStats::default()
+    };
+    assert_eq!(
+      summary_json(&stats, Duration::from_millis(5)),
+      "{\"files\":{\"markdown\":2,\"javascript\":0,\"typescript\":0,\"java\":0,\"python\":1},\"total_files\":3,\"total_nodes\":40,\"streamed\":1,\"errors\":1,\"interrupted\":false,\"cancelled\":0,\"validation_errors\":0,\"validation_warnings\":0,\"findings\":[],\"doc_coverage\":null,\"memory\":null,\"threads\":4,\"elapsed_ms\":5.000}"
+    );
+  }
+
+  #[test]
+  fn test_timings_text() {
+    let timings = [
+      PassTiming {
+        name: "slugs".to_string(),
+        elapsed: Duration::from_micros(1500),
+      },
+      PassTiming {
+        name: "footnotes".to_string(),
+        elapsed: Duration::from_millis(2),
+      },
+    ];
+    assert_eq!(
+      timings_text(&timings),
+      "    slugs      1.50ms\n    footnotes  2.00ms\n"
+    );
+  }
+
+  #[test]
+  fn test_findings_text() {
+    let findings = [
+      Finding::warning("heading level jumps", 3),
+      Finding::error("empty link", 5),
+      Finding {
+        rule: Some("line-length"),
+        ..Finding::warning("line is 120 characters", 7)
+      },
+      Finding::warning("missing translations: de", 0),
+    ];
+    let path = Path::new("a.md");
+    assert_eq!(
+      findings_text(path, &findings, Severity::Warning),
+      "Validation errors in a.md:\n  [ERROR] empty link at line 5\nValidation warnings in a.md:\n  [WARN] heading level jumps at line 3\n  [WARN] missing translations: de\nLint warnings in a.md:\n  [line-length] line is 120 characters at line 7\n"
+    );
+    // --quiet keeps only the errors
+    assert_eq!(
+      findings_text(path, &findings, Severity::Error),
+      "Validation errors in a.md:\n  [ERROR] empty link at line 5\n"
+    );
+    assert_eq!(findings_text(path, &findings[..1], Severity::Error), "");
+  }
+
+  #[test]
+  fn test_findings_json() {
+    let mut stats = ProcessingStats::default();
+    stats.add_findings(
+      Path::new("docs/a.md"),
+      vec![
+        Finding::error("bad \"id\"", 2),
+        Finding {
+          rule: Some("no-bare-urls"),
+          ..Finding::warning("bare URL", 4)
+        },
+      ],
+    );
+    assert_eq!((stats.validation_errors, stats.validation_warnings), (1, 1));
+    assert_eq!(
+      findings_json(&stats),
+      "[{\"path\":\"docs/a.md\",\"line\":2,\"severity\":\"error\",\"rule\":null,\"message\":\"bad \\\"id\\\"\"},{\"path\":\"docs/a.md\",\"line\":4,\"severity\":\"warning\",\"rule\":\"no-bare-urls\",\"message\":\"bare URL\"}]"
+    );
+  }
+
+  #[test]
+  fn test_coverage_json() {
+    let mut stats = ProcessingStats::default();
+    stats.coverage.push((
+      "src/a.py".into(),
+      crate::parsers::coverage::measure("def f():\n    pass\n", DocumentType::Python).unwrap(),
+    ));
+    assert_eq!(
+      coverage_json(&stats),
+      "{\"percent\":0.0,\"documented\":0,\"total\":1,\"files\":[{\"path\":\"src/a.py\",\"documented\":0,\"total\":1,\"undocumented\":[{\"kind\":\"function\",\"name\":\"f\",\"line\":1}]}]}"
+    );
+  }
+}
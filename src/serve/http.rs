@@ -0,0 +1,273 @@
+//! Minimal HTTP/1.1 transport.
+//!
+//! `POST /parse` and `POST /validate` take either a JSON request
+//! (`Content-Type: application/json`) or the raw source as the body, with
+//! `?type=` and `?path=` query parameters. `GET /health` reports liveness.
+//! One request per connection; responses always close the connection.
+
+use super::request::{doc_type_from_name, error_json, Handler, Request, MAX_BODY};
+use crate::ast::DocumentType;
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn serve(handler: Handler, addr: &str) -> Result<(), String> {
+  let listener = TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+  let local = listener.local_addr().map_err(|e| e.to_string())?;
+  eprintln!("bukvar listening on http://{}", local);
+
+  let handler = Arc::new(handler);
+  for stream in listener.incoming() {
+    let Ok(stream) = stream else {
+      continue;
+    };
+    let handler = Arc::clone(&handler);
+    thread::spawn(move || {
+      let _ = handle_connection(&handler, stream);
+    });
+  }
+  Ok(())
+}
+
+fn handle_connection(handler: &Handler, stream: TcpStream) -> io::Result<()> {
+  stream.set_read_timeout(Some(READ_TIMEOUT))?;
+  let mut reader = BufReader::new(&stream);
+  answer(handler, &mut reader).write_to(&mut &stream)
+}
+
+/// Read one request and route it. A handler panic is answered with a 500
+/// rather than a dropped connection.
+fn answer<R: BufRead>(handler: &Handler, reader: &mut R) -> Response {
+  let (mut request, length) = match read_head(reader) {
+    Ok(head) => head,
+    Err(e) => return Response::error(400, &e.to_string()),
+  };
+  if length > MAX_BODY {
+    return Response::error(413, "request body too large");
+  }
+  request.body = vec![0; length];
+  if let Err(e) = reader.read_exact(&mut request.body) {
+    return Response::error(400, &e.to_string());
+  }
+  panic::catch_unwind(AssertUnwindSafe(|| route(handler, &request)))
+    .unwrap_or_else(|_| Response::error(500, "internal error"))
+}
+
+struct HttpRequest {
+  method: String,
+  path: String,
+  query: String,
+  content_type: Option<String>,
+  body: Vec<u8>,
+}
+
+impl HttpRequest {
+  fn query_param(&self, name: &str) -> Option<&str> {
+    self
+      .query
+      .split('&')
+      .filter_map(|pair| pair.split_once('='))
+      .find(|(key, _)| *key == name)
+      .map(|(_, value)| value)
+  }
+}
+
+struct Response {
+  status: u16,
+  body: String,
+}
+
+impl Response {
+  fn ok(body: String) -> Self {
+    Self { status: 200, body }
+  }
+
+  fn error(status: u16, message: &str) -> Self {
+    Self {
+      status,
+      body: error_json(message),
+    }
+  }
+
+  fn reason(&self) -> &'static str {
+    match self.status {
+      200 => "OK",
+      400 => "Bad Request",
+      404 => "Not Found",
+      405 => "Method Not Allowed",
+      413 => "Payload Too Large",
+      _ => "Internal Server Error",
+    }
+  }
+
+  fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write!(
+      w,
+      "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      self.status,
+      self.reason(),
+      self.body.len(),
+      self.body
+    )?;
+    w.flush()
+  }
+}
+
+fn route(handler: &Handler, request: &HttpRequest) -> Response {
+  let result = match (request.method.as_str(), request.path.as_str()) {
+    ("GET", "/health") => return Response::ok("{\"status\":\"ok\"}".to_string()),
+    ("POST", "/parse") => decode(request).and_then(|r| handler.parse(r)),
+    ("POST", "/validate") => decode(request).and_then(|r| handler.validate(r)),
+    (_, "/health" | "/parse" | "/validate") => return Response::error(405, "method not allowed"),
+    _ => return Response::error(404, "not found"),
+  };
+  match result {
+    Ok(body) => Response::ok(body),
+    Err(e) => Response::error(400, &e),
+  }
+}
+
+fn decode(request: &HttpRequest) -> Result<Request, String> {
+  let body = std::str::from_utf8(&request.body).map_err(|_| "body is not valid UTF-8")?;
+  let is_json = request
+    .content_type
+    .as_deref()
+    .is_some_and(|ct| ct.starts_with("application/json"));
+  if is_json {
+    return Request::from_json(body);
+  }
+
+  let doc_type = match request.query_param("type") {
+    Some(name) => doc_type_from_name(name).ok_or(format!("unknown type: {}", name))?,
+    None => DocumentType::Markdown,
+  };
+  Ok(Request {
+    content: body.to_string(),
+    doc_type,
+    path: request.query_param("path").map(str::to_string),
+  })
+}
+
+/// The request line and headers, with the body length they announce.
+fn read_head<R: BufRead>(reader: &mut R) -> io::Result<(HttpRequest, usize)> {
+  let mut line = String::new();
+  reader.read_line(&mut line)?;
+  let mut parts = line.split_whitespace();
+  let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+    return Err(invalid("malformed request line"));
+  };
+  let (path, query) = target.split_once('?').unwrap_or((target, ""));
+  let mut request = HttpRequest {
+    method: method.to_string(),
+    path: path.to_string(),
+    query: query.to_string(),
+    content_type: None,
+    body: Vec::new(),
+  };
+
+  let mut length = 0;
+  loop {
+    line.clear();
+    if reader.read_line(&mut line)? == 0 {
+      return Err(invalid("unexpected EOF in headers"));
+    }
+    let header = line.trim_end();
+    if header.is_empty() {
+      break;
+    }
+    let Some((name, value)) = header.split_once(':') else {
+      continue;
+    };
+    let value = value.trim();
+    if name.eq_ignore_ascii_case("content-length") {
+      length = value.parse().map_err(|_| invalid("bad Content-Length"))?;
+    } else if name.eq_ignore_ascii_case("content-type") {
+      request.content_type = Some(value.to_ascii_lowercase());
+    }
+  }
+
+  Ok((request, length))
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::ElementRegistry;
+
+  fn respond(raw: &str) -> String {
+    let handler = Handler::sandboxed(ElementRegistry::new());
+    let mut reader = io::Cursor::new(raw.as_bytes().to_vec());
+    let mut out = Vec::new();
+    answer(&handler, &mut reader).write_to(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn test_health() {
+    let out = respond("GET /health HTTP/1.1\r\nHost: x\r\n\r\n");
+    assert!(out.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(out.ends_with("\r\n\r\n{\"status\":\"ok\"}"));
+  }
+
+  #[test]
+  fn test_parse_raw_and_json() {
+    let raw =
+      respond("POST /parse?type=markdown&path=a.md HTTP/1.1\r\nContent-Length: 5\r\n\r\n# Hi\n");
+    assert!(raw.starts_with("HTTP/1.1 200 OK"));
+    assert!(raw.contains("{\"schema_version\":4,\"source_path\":\"a.md\""));
+
+    let body = r#"{"content":"def f():\n    \"\"\"Doc.\"\"\"","type":"python"}"#;
+    let json = respond(&format!(
+      "POST /parse HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+      body.len(),
+      body
+    ));
+    assert!(json.contains("\"doc_type\":\"Python\""), "{}", json);
+  }
+
+  #[test]
+  fn test_includes_are_not_resolved() {
+    let body = "<include src=\"/etc/hostname\" />";
+    let out = respond(&format!(
+      "POST /parse?path=/tmp/x.md HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+      body.len(),
+      body
+    ));
+    assert!(out.contains("\"src\":\"/etc/hostname\"}"), "{}", out);
+    assert!(!out.contains("\"children\""), "{}", out);
+  }
+
+  #[test]
+  fn test_validate() {
+    let out = respond("POST /validate HTTP/1.1\r\nContent-Length: 8\r\n\r\n[text]()");
+    assert!(out.contains("\"message\":\"empty link URL\""), "{}", out);
+  }
+
+  #[test]
+  fn test_errors() {
+    assert!(respond("GET /nope HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+    assert!(respond("GET /parse HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405"));
+    assert!(
+      respond("POST /parse?type=cobol HTTP/1.1\r\nContent-Length: 1\r\n\r\nx")
+        .starts_with("HTTP/1.1 400")
+    );
+    assert!(respond("garbage\r\n\r\n").starts_with("HTTP/1.1 400"));
+    assert!(
+      respond("POST /parse HTTP/1.1\r\nContent-Length: 18446744073709551000\r\n\r\n")
+        .starts_with("HTTP/1.1 413 Payload Too Large\r\n")
+    );
+    assert!(
+      respond("POST /parse HTTP/1.1\r\nContent-Length: 5\r\n\r\nx").starts_with("HTTP/1.1 400")
+    );
+  }
+}
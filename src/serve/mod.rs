@@ -0,0 +1,23 @@
+//! Long-lived parsing service (`bukvar serve`).
+
+mod http;
+mod request;
+mod stdio;
+
+use crate::cli::{Args, ServeMode};
+use crate::markdown::ElementRegistry;
+
+use self::request::Handler;
+
+/// Run the service until its input closes.
+pub fn run(mode: &ServeMode, args: &Args) -> Result<(), String> {
+  let elements = match &args.elements {
+    Some(path) => ElementRegistry::load(path)?,
+    None => ElementRegistry::new(),
+  };
+  match mode {
+    ServeMode::Stdio => stdio::serve(&Handler::new(elements)),
+    // Network clients must not be able to read server files via includes.
+    ServeMode::Http(addr) => http::serve(Handler::sandboxed(elements), addr),
+  }
+}
@@ -0,0 +1,351 @@
+//! Parse requests: `{"content": "...", "type": "markdown"}`.
+
+use crate::ast::{Document, DocumentType};
+use crate::formats::{esc, to_json};
+use crate::lines::LineIndex;
+use crate::markdown::{ElementRegistry, ParseOptions};
+use crate::processor::parse_content;
+use crate::processor::transform::Pipeline;
+use crate::validate::{validate_with_lines, ValidationResult};
+
+/// Largest accepted request body.
+pub const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// A single parse request.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+  pub content: String,
+  pub doc_type: DocumentType,
+  /// Reported as `source_path`; also the base for `<include>` resolution.
+  pub path: Option<String>,
+}
+
+impl Request {
+  /// Decode a request body. `type` defaults to markdown and accepts
+  /// language names or file extensions.
+  pub fn from_json(body: &str) -> Result<Self, String> {
+    let fields = parse_object(body)?;
+    let field = |name: &str| {
+      fields
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.clone())
+    };
+
+    let content = field("content").ok_or("missing \"content\"")?;
+    let doc_type = match field("type") {
+      Some(name) => doc_type_from_name(&name).ok_or(format!("unknown type: {}", name))?,
+      None => DocumentType::Markdown,
+    };
+
+    Ok(Self {
+      content,
+      doc_type,
+      path: field("path"),
+    })
+  }
+}
+
+/// Shared state for answering requests.
+pub struct Handler {
+  elements: ElementRegistry,
+  pipeline: Pipeline,
+}
+
+impl Handler {
+  pub fn new(elements: ElementRegistry) -> Self {
+    Self {
+      pipeline: Pipeline::builtin(&elements),
+      elements,
+    }
+  }
+
+  /// A handler that leaves `<include>` unresolved, for untrusted clients.
+  pub fn sandboxed(elements: ElementRegistry) -> Self {
+    Self {
+      pipeline: Pipeline::sandboxed(),
+      elements,
+    }
+  }
+
+  /// Answer a JSON request body with the JSON AST, or `{"error": "..."}`.
+  pub fn handle(&self, body: &str) -> String {
+    match Request::from_json(body).and_then(|r| self.parse(r)) {
+      Ok(json) => json,
+      Err(e) => error_json(&e),
+    }
+  }
+
+  /// JSON AST for a request.
+  pub fn parse(&self, request: Request) -> Result<String, String> {
+    Ok(to_json(&self.document(request)?))
+  }
+
+  /// Validation report for a request.
+  pub fn validate(&self, request: Request) -> Result<String, String> {
+    let lines = LineIndex::new(&request.content);
+    let doc = self.document(request)?;
+    Ok(validation_json(&validate_with_lines(&doc, &lines)))
+  }
+
+  fn document(&self, request: Request) -> Result<Document, String> {
+    let mut doc = parse_content(
+      &request.content,
+      request.doc_type,
+      &self.elements,
+      ParseOptions::new(),
+    );
+    doc.source_path = request.path.unwrap_or_default();
+    self.pipeline.run(&mut doc)?;
+    Ok(doc)
+  }
+}
+
+pub fn error_json(message: &str) -> String {
+  format!("{{\"error\":\"{}\"}}", esc(message))
+}
+
+fn validation_json(result: &ValidationResult) -> String {
+  let issues = |items: Vec<(usize, &str)>| {
+    items
+      .iter()
+      .map(|(line, message)| format!("{{\"line\":{},\"message\":\"{}\"}}", line, esc(message)))
+      .collect::<Vec<_>>()
+      .join(",")
+  };
+  format!(
+    "{{\"ok\":{},\"errors\":[{}],\"warnings\":[{}]}}",
+    result.is_ok(),
+    issues(
+      result
+        .errors
+        .iter()
+        .map(|e| (e.line, e.message.as_str()))
+        .collect()
+    ),
+    issues(
+      result
+        .warnings
+        .iter()
+        .map(|w| (w.line, w.message.as_str()))
+        .collect()
+    ),
+  )
+}
+
+pub fn doc_type_from_name(name: &str) -> Option<DocumentType> {
+  match name.to_lowercase().as_str() {
+    "markdown" => Some(DocumentType::Markdown),
+    "javascript" | "jsdoc" => Some(DocumentType::JavaScript),
+    "typescript" | "tsdoc" => Some(DocumentType::TypeScript),
+    "javadoc" => Some(DocumentType::Java),
+    "python" | "pydoc" => Some(DocumentType::Python),
+    other => DocumentType::from_extension(other),
+  }
+}
+
+/// Parse a flat JSON object whose values are strings, `null`, numbers or
+/// booleans. Non-string values are kept as their literal text.
+fn parse_object(text: &str) -> Result<Vec<(String, String)>, String> {
+  let mut p = JsonCursor {
+    bytes: text.as_bytes(),
+    text,
+    pos: 0,
+  };
+  let mut fields = Vec::new();
+
+  p.expect(b'{')?;
+  if p.eat(b'}') {
+    return p.finish(fields);
+  }
+  loop {
+    let key = p.string()?;
+    p.expect(b':')?;
+    p.skip_ws();
+    let value = match p.peek() {
+      Some(b'"') => p.string()?,
+      _ => p.literal()?,
+    };
+    fields.push((key, value));
+    if p.eat(b',') {
+      continue;
+    }
+    p.expect(b'}')?;
+    return p.finish(fields);
+  }
+}
+
+struct JsonCursor<'a> {
+  bytes: &'a [u8],
+  text: &'a str,
+  pos: usize,
+}
+
+impl JsonCursor<'_> {
+  fn peek(&self) -> Option<u8> {
+    self.bytes.get(self.pos).copied()
+  }
+
+  fn skip_ws(&mut self) {
+    while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+      self.pos += 1;
+    }
+  }
+
+  fn eat(&mut self, b: u8) -> bool {
+    self.skip_ws();
+    if self.peek() == Some(b) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, b: u8) -> Result<(), String> {
+    if self.eat(b) {
+      Ok(())
+    } else {
+      Err(format!("expected '{}' at byte {}", b as char, self.pos))
+    }
+  }
+
+  fn finish<T>(&mut self, value: T) -> Result<T, String> {
+    self.skip_ws();
+    if self.pos == self.bytes.len() {
+      Ok(value)
+    } else {
+      Err(format!("trailing data at byte {}", self.pos))
+    }
+  }
+
+  fn literal(&mut self) -> Result<String, String> {
+    let start = self.pos;
+    while matches!(self.peek(), Some(b) if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.'))
+    {
+      self.pos += 1;
+    }
+    if self.pos == start {
+      return Err(format!("unsupported value at byte {}", start));
+    }
+    Ok(self.text[start..self.pos].to_string())
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    self.expect(b'"')?;
+    let mut out = String::new();
+    loop {
+      let start = self.pos;
+      while !matches!(self.peek(), Some(b'"' | b'\\') | None) {
+        self.pos += 1;
+      }
+      out.push_str(&self.text[start..self.pos]);
+      match self.peek() {
+        Some(b'"') => {
+          self.pos += 1;
+          return Ok(out);
+        }
+        Some(b'\\') => {
+          self.pos += 1;
+          self.escape(&mut out)?;
+        }
+        _ => return Err("unterminated string".to_string()),
+      }
+    }
+  }
+
+  fn escape(&mut self, out: &mut String) -> Result<(), String> {
+    let b = self.peek().ok_or("unterminated escape")?;
+    self.pos += 1;
+    match b {
+      b'"' => out.push('"'),
+      b'\\' => out.push('\\'),
+      b'/' => out.push('/'),
+      b'b' => out.push('\u{8}'),
+      b'f' => out.push('\u{c}'),
+      b'n' => out.push('\n'),
+      b'r' => out.push('\r'),
+      b't' => out.push('\t'),
+      b'u' => {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) && self.text[self.pos..].starts_with("\\u") {
+          self.pos += 2;
+          let low = self.hex4()?;
+          0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+        } else {
+          high
+        };
+        out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+      }
+      other => return Err(format!("invalid escape '\\{}'", other as char)),
+    }
+    Ok(())
+  }
+
+  fn hex4(&mut self) -> Result<u32, String> {
+    let digits = self
+      .text
+      .get(self.pos..self.pos + 4)
+      .ok_or("short \\u escape")?;
+    let value = u32::from_str_radix(digits, 16).map_err(|_| "invalid \\u escape")?;
+    self.pos += 4;
+    Ok(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request_from_json() {
+    let req = Request::from_json(
+      r##" {"content": "# Hi\n\"q\" \u00e9\ud83d\ude00", "type": "py", "n": 1} "##,
+    )
+    .unwrap();
+    assert_eq!(req.content, "# Hi\n\"q\" é😀");
+    assert_eq!(req.doc_type, DocumentType::Python);
+    assert_eq!(req.path, None);
+  }
+
+  #[test]
+  fn test_request_errors() {
+    assert_eq!(
+      Request::from_json(r#"{"type":"markdown"}"#).unwrap_err(),
+      "missing \"content\""
+    );
+    assert!(Request::from_json(r#"{"content":"x","type":"cobol"}"#).is_err());
+    assert!(Request::from_json(r#"{"content":"x""#).is_err());
+    assert!(Request::from_json(r#"{"content":"x"} extra"#).is_err());
+  }
+
+  #[test]
+  fn test_handle() {
+    let handler = Handler::new(ElementRegistry::new());
+    let out = handler.handle(r##"{"content":"# Title","path":"a.md"}"##);
+    assert!(
+      out.starts_with("{\"schema_version\":4,\"source_path\":\"a.md\",\"doc_type\":\"Markdown\"")
+    );
+    assert!(out.contains("\"id\":\"title\""));
+    let report = handler
+      .validate(Request {
+        content: "[broken][nowhere]".to_string(),
+        doc_type: DocumentType::Markdown,
+        path: None,
+      })
+      .unwrap();
+    assert!(report.starts_with("{\"ok\":"), "{}", report);
+    let report = handler
+      .validate(Request {
+        content: "# A\n\nSee\nalso [^gone].".to_string(),
+        doc_type: DocumentType::Markdown,
+        path: None,
+      })
+      .unwrap();
+    assert!(report.contains("{\"line\":4,"), "{}", report);
+    assert_eq!(
+      handler.handle("{}"),
+      "{\"error\":\"missing \\\"content\\\"\"}"
+    );
+  }
+}
@@ -0,0 +1,140 @@
+//! stdin/stdout transport.
+//!
+//! Each request and response is framed by a `Content-Length: <bytes>` header
+//! and a blank line, as in the Language Server Protocol.
+
+use super::request::{error_json, MAX_BODY};
+use super::Handler;
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+pub fn serve(handler: &Handler) -> Result<(), String> {
+  let mut input = BufReader::new(io::stdin().lock());
+  let mut output = io::stdout().lock();
+  serve_on(handler, &mut input, &mut output).map_err(|e| format!("stdio: {}", e))
+}
+
+fn serve_on<R: BufRead, W: Write>(
+  handler: &Handler,
+  input: &mut R,
+  output: &mut W,
+) -> io::Result<()> {
+  while let Some(length) = read_headers(input)? {
+    let response = if length > MAX_BODY {
+      io::copy(&mut input.by_ref().take(length as u64), &mut io::sink())?;
+      error_json("request body too large")
+    } else {
+      let mut body = vec![0; length];
+      input.read_exact(&mut body)?;
+      respond(handler, body)
+    };
+    write_message(output, &response)?;
+  }
+  Ok(())
+}
+
+/// The handler's response, or an error if the request is not UTF-8 or the
+/// handler panics, so one bad request doesn't end the session.
+fn respond(handler: &Handler, body: Vec<u8>) -> String {
+  let Ok(body) = String::from_utf8(body) else {
+    return error_json("request is not valid UTF-8");
+  };
+  panic::catch_unwind(AssertUnwindSafe(|| handler.handle(&body)))
+    .unwrap_or_else(|_| error_json("internal error"))
+}
+
+/// Read the headers of one message, returning its body length; `None` on a
+/// clean EOF between messages.
+fn read_headers<R: BufRead>(input: &mut R) -> io::Result<Option<usize>> {
+  let mut length = None;
+  let mut line = String::new();
+  loop {
+    line.clear();
+    if input.read_line(&mut line)? == 0 {
+      return match length {
+        None => Ok(None),
+        Some(_) => Err(invalid("unexpected EOF in headers")),
+      };
+    }
+    let header = line.trim_end();
+    if header.is_empty() {
+      if length.is_some() {
+        return Ok(length);
+      }
+      continue;
+    }
+    if let Some((name, value)) = header.split_once(':') {
+      if name.trim().eq_ignore_ascii_case("content-length") {
+        let value = value
+          .trim()
+          .parse::<usize>()
+          .map_err(|_| invalid("bad Content-Length"))?;
+        length = Some(value);
+      }
+    }
+  }
+}
+
+fn write_message<W: Write>(output: &mut W, body: &str) -> io::Result<()> {
+  write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+  output.flush()
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::ElementRegistry;
+
+  fn frame(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+  }
+
+  fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let Some(length) = read_headers(input)? else {
+      return Ok(None);
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8(body).unwrap()))
+  }
+
+  #[test]
+  fn test_serves_each_request() {
+    let handler = Handler::new(ElementRegistry::new());
+    let input = frame(r##"{"content":"# A"}"##) + &frame(r#"{"content":"é","type":"bogus"}"#);
+    let mut reader = io::Cursor::new(input.into_bytes());
+    let mut out = Vec::new();
+    serve_on(&handler, &mut reader, &mut out).unwrap();
+
+    let mut responses = io::Cursor::new(out);
+    let first = read_message(&mut responses).unwrap().unwrap();
+    assert!(first.contains("\"type\":\"Heading\""));
+    let second = read_message(&mut responses).unwrap().unwrap();
+    assert_eq!(second, "{\"error\":\"unknown type: bogus\"}");
+    assert!(read_message(&mut responses).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_truncated_headers_are_an_error() {
+    let mut reader = io::Cursor::new(b"Content-Length: 5\r\n".to_vec());
+    assert!(read_message(&mut reader).is_err());
+  }
+
+  #[test]
+  fn test_oversized_body_is_refused() {
+    let handler = Handler::new(ElementRegistry::new());
+    let input = "Content-Length: 18446744073709551000\r\n\r\n{}".to_string();
+    let mut reader = io::Cursor::new(input.into_bytes());
+    let mut out = Vec::new();
+    serve_on(&handler, &mut reader, &mut out).unwrap();
+
+    let mut responses = io::Cursor::new(out);
+    let response = read_message(&mut responses).unwrap().unwrap();
+    assert_eq!(response, "{\"error\":\"request body too large\"}");
+  }
+}
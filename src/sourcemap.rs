@@ -1,9 +1,12 @@
 //! Source map generation for mapping AST positions to source.
 //!
 //! Provides bidirectional mapping between AST node positions
-//! and original source file locations.
+//! and original source file locations. Spans are byte offsets; with
+//! [`SourceMap::with_utf16`], entries also carry the 0-based line and UTF-16
+//! code unit positions that editors (LSP) and JavaScript strings use.
 
-use crate::ast::{Document, Node};
+use crate::ast::{Document, Node, NodeKind};
+use crate::lines::{LineIndex, Utf16Position};
 
 /// A single source map entry.
 #[derive(Debug, Clone)]
@@ -18,6 +21,11 @@ pub struct SourceMapEntry {
   pub column: usize,
   /// Node type name
   pub node_type: String,
+  /// Included file the span belongs to; `None` for the document itself
+  pub source: Option<String>,
+  /// Start and end in UTF-16 code units; `None` until computed, and for
+  /// spans in included files
+  pub utf16: Option<(Utf16Position, Utf16Position)>,
 }
 
 /// Source map for a document.
@@ -36,12 +44,12 @@ impl SourceMap {
       source_path: doc.source_path.clone(),
       entries: Vec::new(),
     };
-    map.collect_entries(&doc.nodes);
+    map.collect_entries(&doc.nodes, None);
     map
   }
 
   /// Collect entries from nodes recursively.
-  fn collect_entries(&mut self, nodes: &[Node]) {
+  fn collect_entries(&mut self, nodes: &[Node], source: Option<&str>) {
     for node in nodes {
       let span = &node.span;
       if !span.is_empty() {
@@ -50,13 +58,45 @@ impl SourceMap {
           source_end: span.end,
           line: span.line,
           column: span.column,
-          node_type: node_type_name(&node.kind),
+          node_type: node.kind.name().to_string(),
+          source: source.map(str::to_string),
+          utf16: None,
         });
       }
-      self.collect_entries(&node.children);
+      let child_source = match &node.kind {
+        NodeKind::Include { src } => Some(src.as_str()),
+        _ => source,
+      };
+      self.collect_entries(&node.children, child_source);
     }
   }
 
+  /// Fill in the line and column of entries from the document itself that
+  /// have none, as inline nodes do: their spans are only byte offsets.
+  pub fn with_lines(mut self, lines: &LineIndex) -> Self {
+    for entry in self
+      .entries
+      .iter_mut()
+      .filter(|e| e.source.is_none() && e.line == 0)
+    {
+      (entry.line, entry.column) = lines.line_col(entry.source_start);
+    }
+    self
+  }
+
+  /// Fill in the UTF-16 positions of entries from the document itself;
+  /// `source` is the text the document was parsed from, and `lines` its
+  /// index.
+  pub fn with_utf16(mut self, source: &str, lines: &LineIndex) -> Self {
+    for entry in self.entries.iter_mut().filter(|e| e.source.is_none()) {
+      entry.utf16 = Some((
+        lines.utf16(source, entry.source_start),
+        lines.utf16(source, entry.source_end),
+      ));
+    }
+    self
+  }
+
   /// Find node at a given source offset.
   #[allow(dead_code)]
   pub fn find_at_offset(&self, offset: usize) -> Option<&SourceMapEntry> {
@@ -83,66 +123,25 @@ impl SourceMap {
         s.push(',');
       }
       s.push_str(&format!(
-        "{{\"start\":{},\"end\":{},\"line\":{},\"col\":{},\"type\":\"{}\"}}",
+        "{{\"start\":{},\"end\":{},\"line\":{},\"col\":{},\"type\":\"{}\"",
         entry.source_start, entry.source_end, entry.line, entry.column, entry.node_type
       ));
+      if let Some(source) = &entry.source {
+        s.push_str(&format!(",\"source\":\"{}\"", escape_json(source)));
+      }
+      if let Some((start, end)) = entry.utf16 {
+        s.push_str(&format!(
+          ",\"range\":{{\"start\":{{\"line\":{},\"character\":{}}},\"end\":{{\"line\":{},\"character\":{}}}}}",
+          start.line, start.character, end.line, end.character
+        ));
+      }
+      s.push('}');
     }
     s.push_str("]}");
     s
   }
 }
 
-/// Get node type name for source map.
-fn node_type_name(kind: &crate::ast::NodeKind) -> String {
-  use crate::ast::NodeKind::*;
-  match kind {
-    Document => "Document",
-    Heading { .. } => "Heading",
-    Paragraph => "Paragraph",
-    Text { .. } => "Text",
-    Emphasis => "Emphasis",
-    Strong => "Strong",
-    Link { .. } => "Link",
-    Image { .. } => "Image",
-    CodeSpan { .. } => "CodeSpan",
-    CodeBlock { .. } => "CodeBlock",
-    FencedCodeBlock { .. } => "FencedCodeBlock",
-    IndentedCodeBlock => "IndentedCodeBlock",
-    BlockQuote => "BlockQuote",
-    List { .. } => "List",
-    ListItem { .. } => "ListItem",
-    ThematicBreak => "ThematicBreak",
-    HardBreak => "HardBreak",
-    SoftBreak => "SoftBreak",
-    HtmlBlock { .. } => "HtmlBlock",
-    Table => "Table",
-    TableHead => "TableHead",
-    TableBody => "TableBody",
-    TableRow => "TableRow",
-    TableCell { .. } => "TableCell",
-    Strikethrough => "Strikethrough",
-    AutoLink { .. } => "AutoLink",
-    MathInline { .. } => "MathInline",
-    MathBlock { .. } => "MathBlock",
-    Frontmatter { .. } => "Frontmatter",
-    Footnote { .. } => "Footnote",
-    FootnoteReference { .. } => "FootnoteReference",
-    FootnoteDefinition { .. } => "FootnoteDefinition",
-    DefinitionList => "DefinitionList",
-    DefinitionTerm => "DefinitionTerm",
-    DefinitionDescription => "DefinitionDescription",
-    AutoUrl { .. } => "AutoUrl",
-    Alert { .. } => "Alert",
-    Steps => "Steps",
-    Step => "Step",
-    Toc => "Toc",
-    Tabs { .. } => "Tabs",
-    CodeBlockExt { .. } => "CodeBlockExt",
-    _ => "Unknown",
-  }
-  .to_string()
-}
-
 /// Escape string for JSON.
 fn escape_json(s: &str) -> String {
   let mut result = String::with_capacity(s.len());
@@ -171,7 +170,12 @@ mod tests {
       metadata: DocumentMetadata::default(),
       nodes: vec![
         Node::new(
-          NodeKind::Heading { level: 1, id: None },
+          NodeKind::Heading {
+            level: 1,
+            id: None,
+            attributes: vec![],
+            section: None,
+          },
           Span::new(0, 10, 1, 1),
         ),
         Node::new(NodeKind::Paragraph, Span::new(12, 50, 3, 1)),
@@ -240,4 +244,54 @@ mod tests {
     let entries = map.find_at_line(2);
     assert!(entries.is_empty());
   }
+
+  #[test]
+  fn test_utf16_ranges() {
+    let source = "héllo 😀 x\nñ\n";
+    let doc = crate::markdown::MarkdownParser::new(source).parse();
+    let map = SourceMap::from_document(&doc).with_utf16(source, &LineIndex::new(source));
+    assert!(map.to_json().contains(
+      "\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":10}}"
+    ));
+  }
+
+  #[test]
+  fn test_inline_lines() {
+    let source = "# Title\n\nSome *emphasis* and `code`.\n";
+    let doc = crate::markdown::MarkdownParser::new(source).parse();
+    let map = SourceMap::from_document(&doc).with_lines(&LineIndex::new(source));
+    let emphasis = map
+      .entries
+      .iter()
+      .find(|e| e.node_type == "Emphasis")
+      .unwrap();
+    assert_eq!((emphasis.line, emphasis.column), (3, 6));
+    let code = map
+      .entries
+      .iter()
+      .find(|e| e.node_type == "CodeSpan")
+      .unwrap();
+    assert_eq!((code.line, code.column), (3, 21));
+    assert!(map.entries.iter().all(|e| e.line > 0 && e.column > 0));
+  }
+
+  #[test]
+  fn test_included_spans_record_source() {
+    let mut doc = create_test_doc();
+    doc.nodes.push(Node::with_children(
+      NodeKind::Include {
+        src: "shared/a.md".to_string(),
+      },
+      Span::new(70, 100, 7, 1),
+      vec![Node::new(NodeKind::Paragraph, Span::new(0, 6, 1, 1))],
+    ));
+    let map = SourceMap::from_document(&doc);
+    let include = &map.entries[3];
+    assert_eq!(include.node_type, "Include");
+    assert_eq!(include.source, None);
+    assert_eq!(map.entries[4].source.as_deref(), Some("shared/a.md"));
+    assert!(map
+      .to_json()
+      .contains("\"type\":\"Paragraph\",\"source\":\"shared/a.md\"}"));
+  }
 }
@@ -3,6 +3,7 @@
 //! Processes input in chunks to handle files that don't fit in memory.
 
 use crate::ast::Document;
+use crate::markdown::{ElementRegistry, ParseOptions};
 use std::io::{BufRead, BufReader, Read};
 
 /// Buffer size for streaming (64KB)
@@ -91,15 +92,21 @@ impl<R: Read> StreamingParser<R> {
 ///
 /// This reads and parses the entire input but does so efficiently
 /// by using buffered I/O.
-pub fn parse_streaming<R: Read>(reader: R) -> Document {
+pub fn parse_streaming<R: Read>(
+  reader: R,
+  elements: &ElementRegistry,
+  options: ParseOptions,
+) -> Document {
   use crate::markdown::MarkdownParser;
 
   let mut content = String::new();
   let mut buf_reader = BufReader::with_capacity(BUFFER_SIZE, reader);
   let _ = buf_reader.read_to_string(&mut content);
 
-  let mut parser = MarkdownParser::new(&content);
-  parser.parse()
+  MarkdownParser::new(&content)
+    .with_elements(elements)
+    .with_options(options)
+    .parse()
 }
 
 /// Iterator over blocks in streaming input.
@@ -199,7 +206,7 @@ mod tests {
   fn test_parse_streaming() {
     let input = "# Hello\n\nThis is a paragraph.";
     let reader = Cursor::new(input);
-    let doc = parse_streaming(reader);
+    let doc = parse_streaming(reader, &ElementRegistry::new(), ParseOptions::new());
 
     assert!(doc.metadata.total_nodes > 0);
   }
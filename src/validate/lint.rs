@@ -0,0 +1,615 @@
+//! Opt-in style lint rules for Markdown (`--lint`).
+//!
+//! Rules are configured with a plain-text file, one setting per line:
+//!
+//! ```text
+//! # lint.conf
+//! trailing-whitespace off
+//! line-length 120
+//! languages svelte hcl
+//! heading-case sentence
+//! proper-nouns GitHub Markdown
+//! ```
+
+use super::ValidationWarning;
+use crate::ast::{Document, DocumentType, ListMarker, Node, NodeKind};
+
+use std::fs;
+use std::path::Path;
+
+/// Every rule ID, in the order findings are documented.
+pub const RULES: &[&str] = &[
+  "trailing-whitespace",
+  "line-length",
+  "bare-url",
+  "code-fence-language",
+  "unknown-language",
+  "list-marker-style",
+  "emphasis-as-heading",
+  "heading-case",
+  "heading-punctuation",
+  "heading-length",
+];
+
+/// Rules that stay off until the config turns them on.
+const OFF_BY_DEFAULT: &[&str] = &["heading-case"];
+
+const DEFAULT_LINE_LENGTH: usize = 100;
+
+const DEFAULT_HEADING_LENGTH: usize = 60;
+
+/// Closing punctuation `heading-punctuation` reports; `?` is allowed for
+/// FAQ-style headings.
+const HEADING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!'];
+
+/// Words title case leaves lowercase unless they start or end the heading.
+const MINOR_WORDS: &[&str] = &[
+  "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "nor", "of", "on", "or",
+  "per", "so", "than", "the", "to", "up", "via", "vs", "with", "yet",
+];
+
+/// Capitalization `heading-case` enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadingCase {
+  /// `Getting started with the CLI`: only the first word and names capitalized
+  Sentence,
+  /// `Getting Started with the CLI`: every word but short minor ones
+  Title,
+}
+
+/// Fence languages accepted by `unknown-language`; extend with `languages`.
+const KNOWN_LANGUAGES: &[&str] = &[
+  "asm",
+  "bash",
+  "bat",
+  "c",
+  "c++",
+  "clojure",
+  "cmake",
+  "console",
+  "cpp",
+  "cs",
+  "csharp",
+  "css",
+  "csv",
+  "dart",
+  "diff",
+  "dockerfile",
+  "elixir",
+  "erlang",
+  "go",
+  "graphql",
+  "groovy",
+  "haskell",
+  "html",
+  "http",
+  "ini",
+  "java",
+  "javascript",
+  "js",
+  "json",
+  "json5",
+  "jsonc",
+  "jsx",
+  "kotlin",
+  "kt",
+  "latex",
+  "less",
+  "lua",
+  "make",
+  "makefile",
+  "markdown",
+  "math",
+  "md",
+  "mermaid",
+  "nix",
+  "objc",
+  "ocaml",
+  "perl",
+  "php",
+  "plaintext",
+  "powershell",
+  "proto",
+  "ps1",
+  "py",
+  "python",
+  "r",
+  "rb",
+  "regex",
+  "ruby",
+  "rs",
+  "rust",
+  "scala",
+  "scss",
+  "sh",
+  "shell",
+  "sql",
+  "swift",
+  "tex",
+  "text",
+  "toml",
+  "ts",
+  "tsx",
+  "txt",
+  "typescript",
+  "vue",
+  "xml",
+  "yaml",
+  "yml",
+  "zig",
+  "zsh",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfig {
+  disabled: Vec<&'static str>,
+  pub max_line_length: usize,
+  /// Fence languages accepted in addition to the built-in list.
+  pub languages: Vec<String>,
+  pub heading_case: HeadingCase,
+  pub max_heading_length: usize,
+  /// Words sentence case accepts capitalized anywhere, such as product names.
+  pub proper_nouns: Vec<String>,
+}
+
+impl Default for LintConfig {
+  fn default() -> Self {
+    Self {
+      disabled: OFF_BY_DEFAULT.to_vec(),
+      max_line_length: DEFAULT_LINE_LENGTH,
+      languages: Vec::new(),
+      heading_case: HeadingCase::Sentence,
+      max_heading_length: DEFAULT_HEADING_LENGTH,
+      proper_nouns: Vec::new(),
+    }
+  }
+}
+
+impl LintConfig {
+  /// Parse `<rule> on|off`, `line-length <N>`, `heading-length <N>`,
+  /// `heading-case sentence|title`, `languages <name>...` and
+  /// `proper-nouns <word>...` lines; `#` starts a comment.
+  pub fn from_config(text: &str) -> Result<Self, String> {
+    let mut config = Self::default();
+    for (index, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or("").trim();
+      if line.is_empty() {
+        continue;
+      }
+      let err = |msg: String| format!("line {}: {}", index + 1, msg);
+      let mut words = line.split_whitespace();
+      let name = words.next().unwrap_or("");
+      if name == "languages" {
+        config
+          .languages
+          .extend(words.map(|w| w.trim_matches(',').to_lowercase()));
+        continue;
+      }
+      if name == "proper-nouns" {
+        config
+          .proper_nouns
+          .extend(words.map(|w| w.trim_matches(',').to_string()));
+        continue;
+      }
+      let value = words.next().unwrap_or("");
+      let rule = RULES
+        .iter()
+        .find(|r| **r == name)
+        .ok_or_else(|| err(format!("unknown lint rule: {}", name)))?;
+
+      match (*rule, value) {
+        (_, "off") => config.disabled.push(rule),
+        (_, "on") => config.disabled.retain(|r| r != rule),
+        ("line-length", n) => {
+          config.max_line_length = n
+            .parse()
+            .map_err(|_| err(format!("invalid line length: {}", n)))?;
+        }
+        ("heading-length", n) => {
+          config.max_heading_length = n
+            .parse()
+            .map_err(|_| err(format!("invalid heading length: {}", n)))?;
+        }
+        ("heading-case", style) => {
+          config.heading_case = match style {
+            "sentence" => HeadingCase::Sentence,
+            "title" => HeadingCase::Title,
+            _ => return Err(err(format!("expected sentence or title for {}", rule))),
+          };
+          config.disabled.retain(|r| r != rule);
+        }
+        _ => return Err(err(format!("expected on/off for {}", rule))),
+      }
+    }
+    Ok(config)
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = fs::read_to_string(path)
+      .map_err(|e| format!("Failed to read lint config {}: {}", path.display(), e))?;
+    Self::from_config(&text).map_err(|e| format!("{}: {}", path.display(), e))
+  }
+
+  pub fn enabled(&self, rule: &str) -> bool {
+    !self.disabled.contains(&rule)
+  }
+
+  /// Case-insensitive; only the name before any `,` or `{` options counts.
+  pub fn knows_language(&self, language: &str) -> bool {
+    let name = language
+      .split([',', '{', ' '])
+      .next()
+      .unwrap_or("")
+      .to_lowercase();
+    KNOWN_LANGUAGES.contains(&name.as_str()) || self.languages.contains(&name)
+  }
+}
+
+/// Lint a Markdown document against its source text. Other document types
+/// produce no findings.
+pub fn lint(doc: &Document, source: &str, config: &LintConfig) -> Vec<ValidationWarning> {
+  if doc.doc_type != DocumentType::Markdown {
+    return Vec::new();
+  }
+  let mut linter = Linter {
+    config,
+    warnings: Vec::new(),
+    first_bullet: None,
+  };
+  linter.source_rules(source);
+  linter.walk(&doc.nodes, 1);
+  linter.warnings.sort_by_key(|w| w.line);
+  linter.warnings
+}
+
+struct Linter<'a> {
+  config: &'a LintConfig,
+  warnings: Vec<ValidationWarning>,
+  first_bullet: Option<(char, usize)>,
+}
+
+impl Linter<'_> {
+  fn warn(&mut self, rule: &'static str, line: usize, message: String) {
+    if self.config.enabled(rule) {
+      self.warnings.push(ValidationWarning {
+        line,
+        message,
+        rule: Some(rule),
+      });
+    }
+  }
+
+  fn source_rules(&mut self, source: &str) {
+    let max = self.config.max_line_length;
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in source.lines().enumerate() {
+      let number = index + 1;
+      let trimmed = line.trim_start();
+
+      let stripped = line.trim_end_matches([' ', '\t']);
+      let trailing = &line[stripped.len()..];
+      // Exactly two spaces after text is a hard line break.
+      let hard_break = trailing == "  " && !stripped.is_empty();
+      if !trailing.is_empty() && !hard_break {
+        self.warn(
+          "trailing-whitespace",
+          number,
+          "trailing whitespace".to_string(),
+        );
+      }
+
+      let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+      match (fence, marker) {
+        (None, Some(m)) => fence = Some(m),
+        (Some(open), Some(m)) if open == m => fence = None,
+        _ => {}
+      }
+      let length = line.chars().count();
+      // Code, tables and unbreakable tokens (long URLs) are exempt.
+      let exempt = fence.is_some() || marker.is_some() || trimmed.starts_with('|');
+      if length > max && !exempt && trimmed.contains(char::is_whitespace) {
+        self.warn(
+          "line-length",
+          number,
+          format!("line is {} characters (max {})", length, max),
+        );
+      }
+    }
+  }
+
+  /// Inline spans carry no line, so findings use the nearest block's line.
+  fn walk(&mut self, nodes: &[Node], parent_line: usize) {
+    for node in nodes {
+      let line = if node.span.line > 0 {
+        node.span.line
+      } else {
+        parent_line
+      };
+      match &node.kind {
+        NodeKind::AutoUrl { url } => self.warn(
+          "bare-url",
+          line,
+          format!("bare URL should be a link: {}", url),
+        ),
+        NodeKind::FencedCodeBlock { language, .. } | NodeKind::CodeBlockExt { language, .. } => {
+          match language.as_deref().filter(|l| !l.is_empty()) {
+            None => self.warn(
+              "code-fence-language",
+              line,
+              "fenced code block without a language".to_string(),
+            ),
+            Some(l) if !self.config.knows_language(l) => self.warn(
+              "unknown-language",
+              line,
+              format!("unknown code block language: {}", l),
+            ),
+            Some(_) => {}
+          }
+        }
+        NodeKind::List { ordered: false, .. } => self.check_bullets(node, line),
+        NodeKind::Heading { .. } => self.check_heading(node, line),
+        NodeKind::Paragraph if is_emphasis_heading(node) => self.warn(
+          "emphasis-as-heading",
+          line,
+          "emphasis used instead of a heading".to_string(),
+        ),
+        _ => {}
+      }
+      self.walk(&node.children, line);
+    }
+  }
+
+  fn check_heading(&mut self, heading: &Node, line: usize) {
+    let text = text_of(&heading.children);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+      return;
+    }
+    if text.ends_with(HEADING_PUNCTUATION) {
+      self.warn(
+        "heading-punctuation",
+        line,
+        format!("heading ends with punctuation: \"{}\"", text),
+      );
+    }
+    let length = text.chars().count();
+    let max = self.config.max_heading_length;
+    if length > max {
+      self.warn(
+        "heading-length",
+        line,
+        format!(
+          "heading is {} characters (max {}): \"{}\"",
+          length, max, text
+        ),
+      );
+    }
+    let case = self.config.heading_case;
+    if let Some(word) = miscased_word(&heading_words(&heading.children), case, self.config) {
+      let style = match case {
+        HeadingCase::Sentence => "sentence",
+        HeadingCase::Title => "title",
+      };
+      self.warn(
+        "heading-case",
+        line,
+        format!(
+          "heading is not in {} case (\"{}\"): \"{}\"",
+          style, word, text
+        ),
+      );
+    }
+  }
+
+  fn check_bullets(&mut self, list: &Node, line: usize) {
+    let Some(marker) = list.children.iter().find_map(|item| match item.kind {
+      NodeKind::ListItem {
+        marker: ListMarker::Bullet(c),
+        ..
+      } => Some(c),
+      _ => None,
+    }) else {
+      return;
+    };
+    match self.first_bullet {
+      None => self.first_bullet = Some((marker, line)),
+      Some((first, first_line)) if first != marker => self.warn(
+        "list-marker-style",
+        line,
+        format!(
+          "list marker '{}' differs from '{}' used at line {}",
+          marker, first, first_line
+        ),
+      ),
+      Some(_) => {}
+    }
+  }
+}
+
+/// A paragraph that is nothing but one bold or italic phrase without
+/// closing punctuation.
+fn is_emphasis_heading(paragraph: &Node) -> bool {
+  let [only] = paragraph.children.as_slice() else {
+    return false;
+  };
+  if !matches!(only.kind, NodeKind::Strong | NodeKind::Emphasis) {
+    return false;
+  }
+  let text = text_of(&only.children);
+  let text = text.trim();
+  !text.is_empty() && !text.ends_with(['.', ',', ';', ':', '!', '?'])
+}
+
+/// Words of a heading's prose, `None` standing in for code, math and other
+/// spans whose case is not the author's choice.
+fn heading_words(nodes: &[Node]) -> Vec<Option<String>> {
+  let mut words = Vec::new();
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Text { content } => {
+        words.extend(content.split_whitespace().map(|w| Some(w.to_string())))
+      }
+      NodeKind::SoftBreak | NodeKind::HardBreak => {}
+      _ if node.children.is_empty() => words.push(None),
+      _ => words.extend(heading_words(&node.children)),
+    }
+  }
+  words
+}
+
+/// The first word breaking `case`, if any. Only words written all lowercase
+/// or capitalized are judged; acronyms, `camelCase` and words starting with
+/// a digit or symbol could be right either way.
+fn miscased_word(
+  words: &[Option<String>],
+  case: HeadingCase,
+  config: &LintConfig,
+) -> Option<String> {
+  let last = words.len().checked_sub(1)?;
+  let mut after_colon = false;
+  for (index, word) in words.iter().enumerate() {
+    let Some(word) = word else {
+      after_colon = false;
+      continue;
+    };
+    let core = word.trim_matches(|c: char| !c.is_alphanumeric());
+    let starts_subtitle = after_colon;
+    after_colon = word.ends_with(':');
+    let mut chars = core.chars();
+    let Some(first) = chars.next().filter(|c| c.is_alphabetic()) else {
+      continue;
+    };
+    let rest_lower = chars.all(|c| !c.is_uppercase());
+    let capitalized = first.is_uppercase() && rest_lower;
+    let lowercase = first.is_lowercase() && rest_lower;
+    let opening = index == 0 || starts_subtitle;
+    let wrong = match case {
+      HeadingCase::Sentence if opening => lowercase,
+      HeadingCase::Sentence => {
+        capitalized && core != "I" && !config.proper_nouns.iter().any(|name| name == core)
+      }
+      HeadingCase::Title if opening || index == last => lowercase,
+      HeadingCase::Title => lowercase && !MINOR_WORDS.contains(&core),
+    };
+    if wrong {
+      return Some(core.to_string());
+    }
+  }
+  None
+}
+
+fn text_of(nodes: &[Node]) -> String {
+  nodes
+    .iter()
+    .map(|node| match &node.kind {
+      NodeKind::Text { content } | NodeKind::CodeSpan { content } => content.clone(),
+      NodeKind::SoftBreak | NodeKind::HardBreak => "\n".to_string(),
+      _ => text_of(&node.children),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::markdown::MarkdownParser;
+
+  fn findings(source: &str, config: &LintConfig) -> Vec<(usize, &'static str)> {
+    let doc = MarkdownParser::new(source).parse();
+    lint(&doc, source, config)
+      .into_iter()
+      .map(|w| (w.line, w.rule.unwrap()))
+      .collect()
+  }
+
+  #[test]
+  fn test_lint_rules() {
+    let source = "# Title \n\nHard break  \nnext\n\n**Overview**\n\nSee https://example.com now.\n\n```\ncode\n```\n\n- a\n- b\n\n* c\n";
+    assert_eq!(
+      findings(source, &LintConfig::default()),
+      vec![
+        (1, "trailing-whitespace"),
+        (6, "emphasis-as-heading"),
+        (8, "bare-url"),
+        (10, "code-fence-language"),
+        (17, "list-marker-style"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_line_length_and_config() {
+    let long = format!(
+      "{} end\n\n```text\n{}\n```\n",
+      "word ".repeat(30),
+      "x ".repeat(80).trim_end()
+    );
+    let config = LintConfig::from_config("# tweak\nline-length 200\n").unwrap();
+    assert!(findings(&long, &config).is_empty());
+    assert_eq!(
+      findings(&long, &LintConfig::default()),
+      vec![(1, "line-length")]
+    );
+
+    let off = LintConfig::from_config("bare-url off").unwrap();
+    assert!(findings("Go to https://x.dev now", &off).is_empty());
+
+    assert_eq!(
+      LintConfig::from_config("\nnope off").unwrap_err(),
+      "line 2: unknown lint rule: nope"
+    );
+    assert!(LintConfig::from_config("line-length many").is_err());
+    assert!(LintConfig::from_config("languages").is_ok());
+    assert!(LintConfig::from_config("bare-url 5").is_err());
+  }
+
+  #[test]
+  fn test_heading_rules() {
+    let source = "# Getting started with `cargo`\n\n## Setup:\n\n## What is DAST?\n\n\
+                  ## Using the GitHub API\n\n## Reading From Markdown\n";
+    assert_eq!(
+      findings(source, &LintConfig::default()),
+      vec![(3, "heading-punctuation")]
+    );
+
+    let sentence = LintConfig::from_config("heading-case sentence\nproper-nouns GitHub").unwrap();
+    assert_eq!(
+      findings(source, &sentence),
+      vec![(3, "heading-punctuation"), (9, "heading-case")]
+    );
+    let title = LintConfig::from_config("heading-case title\nheading-punctuation off").unwrap();
+    assert_eq!(
+      findings(source, &title),
+      vec![(1, "heading-case"), (5, "heading-case")]
+    );
+    let doc = MarkdownParser::new(source).parse();
+    let messages: Vec<String> = lint(&doc, source, &title)
+      .into_iter()
+      .map(|w| w.message)
+      .collect();
+    assert_eq!(
+      messages[0],
+      "heading is not in title case (\"started\"): \"Getting started with cargo\""
+    );
+
+    let short = LintConfig::from_config("heading-length 20").unwrap();
+    assert_eq!(
+      findings("## A heading that runs long\n\n## Short\n", &short),
+      vec![(1, "heading-length")]
+    );
+    assert!(LintConfig::from_config("heading-case upper").is_err());
+    assert!(LintConfig::from_config("heading-length x").is_err());
+  }
+
+  #[test]
+  fn test_unknown_language() {
+    let source = "```pyton\nx\n```\n\n```Rust,ignore\nx\n```\n\n```hcl\nx\n```\n";
+    assert_eq!(
+      findings(source, &LintConfig::default()),
+      vec![(1, "unknown-language"), (9, "unknown-language")]
+    );
+    let config = LintConfig::from_config("languages HCL, pyton").unwrap();
+    assert!(findings(source, &config).is_empty());
+  }
+}
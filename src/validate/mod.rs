@@ -0,0 +1,934 @@
+//! AST validation - check for broken links, missing refs
+
+pub mod lint;
+
+use crate::ast::{Document, DocumentType, FrontmatterFormat, Node, NodeKind};
+use crate::lines::LineIndex;
+use crate::markdown::{normalize_label, option_overrides, ParseOptions, OPTIONS_KEY};
+use crate::parsers::version::Version;
+use crate::processor::transform::iso_date;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Default)]
+pub struct ValidationResult {
+  pub warnings: Vec<ValidationWarning>,
+  pub errors: Vec<ValidationError>,
+}
+
+#[derive(Debug)]
+pub struct ValidationWarning {
+  pub line: usize,
+  pub message: String,
+  /// Lint rule that produced the warning, if any.
+  pub rule: Option<&'static str>,
+}
+
+#[derive(Debug)]
+pub struct ValidationError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl ValidationResult {
+  pub fn is_ok(&self) -> bool {
+    self.errors.is_empty()
+  }
+}
+
+/// Validate a document for common issues
+pub fn validate(doc: &Document) -> ValidationResult {
+  check(doc, None)
+}
+
+/// [`validate`] a document parsed from the text `lines` indexes, so findings
+/// in inline nodes get their own line rather than their block's.
+pub fn validate_with_lines(doc: &Document, lines: &LineIndex) -> ValidationResult {
+  check(doc, Some(lines))
+}
+
+fn check(doc: &Document, lines: Option<&LineIndex>) -> ValidationResult {
+  let mut result = ValidationResult::default();
+  let mut link_defs = HashSet::new();
+  let mut footnote_defs = HashSet::new();
+  let mut link_refs = Vec::new();
+  let mut footnote_refs = Vec::new();
+
+  // Collect definitions and references
+  collect_refs(
+    &doc.nodes,
+    Position { lines, parent: 1 },
+    &mut link_defs,
+    &mut footnote_defs,
+    &mut link_refs,
+    &mut footnote_refs,
+  );
+
+  // Check for undefined link references
+  for (label, line) in link_refs {
+    if !link_defs.contains(&normalize_label(&label)) {
+      result.warnings.push(ValidationWarning {
+        line,
+        message: format!("undefined link reference: [{}]", label),
+        rule: None,
+      });
+    }
+  }
+
+  // Check for undefined footnote references
+  for (label, line) in footnote_refs {
+    if !footnote_defs.contains(&label.to_lowercase()) {
+      result.warnings.push(ValidationWarning {
+        line,
+        message: format!("undefined footnote: [^{}]", label),
+        rule: None,
+      });
+    }
+  }
+
+  // Check for empty links
+  check_empty_links(&doc.nodes, Position { lines, parent: 1 }, &mut result);
+
+  // Check heading structure and frontmatter dates
+  if doc.doc_type == DocumentType::Markdown {
+    check_headings(&doc.nodes, &mut result);
+    check_dates(&doc.nodes, &mut result);
+    check_parser_options(&doc.nodes, &mut result);
+  }
+
+  check_code_ranges(&doc.nodes, &mut result);
+  check_tabs(&doc.nodes, &mut result);
+  check_diagrams(&doc.nodes, &mut result);
+
+  result
+}
+
+/// Finds the line of a node: blocks have one, and inline nodes take theirs
+/// from the index, else from the nearest block.
+#[derive(Clone, Copy)]
+struct Position<'a> {
+  /// `None` within included content, whose offsets are in another file
+  lines: Option<&'a LineIndex>,
+  parent: usize,
+}
+
+impl Position<'_> {
+  fn line(&self, node: &Node) -> usize {
+    match self.lines {
+      _ if node.span.line > 0 => node.span.line,
+      Some(lines) => lines.line(node.span.start),
+      None => self.parent,
+    }
+  }
+
+  /// Position for the children of `node`.
+  fn within(&self, node: &Node) -> Self {
+    Self {
+      lines: match node.kind {
+        NodeKind::Include { .. } => None,
+        _ => self.lines,
+      },
+      parent: self.line(node),
+    }
+  }
+}
+
+fn collect_refs(
+  nodes: &[Node],
+  at: Position,
+  link_defs: &mut HashSet<String>,
+  footnote_defs: &mut HashSet<String>,
+  link_refs: &mut Vec<(String, usize)>,
+  footnote_refs: &mut Vec<(String, usize)>,
+) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::LinkDefinition { label, .. } => {
+        link_defs.insert(normalize_label(label));
+      }
+      NodeKind::LinkReference { label, .. } => {
+        link_refs.push((label.clone(), at.line(node)));
+      }
+      NodeKind::FootnoteDefinition { label, .. } => {
+        footnote_defs.insert(label.to_lowercase());
+      }
+      NodeKind::FootnoteReference { label, .. } => {
+        footnote_refs.push((label.clone(), at.line(node)));
+      }
+      NodeKind::Footnote { label } => {
+        footnote_defs.insert(label.to_lowercase());
+      }
+      _ => {}
+    }
+    collect_refs(
+      &node.children,
+      at.within(node),
+      link_defs,
+      footnote_defs,
+      link_refs,
+      footnote_refs,
+    );
+  }
+}
+
+fn check_empty_links(nodes: &[Node], at: Position, result: &mut ValidationResult) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Link { url, .. } if url.is_empty() => {
+        result.warnings.push(ValidationWarning {
+          line: at.line(node),
+          message: "empty link URL".to_string(),
+          rule: None,
+        });
+      }
+      NodeKind::Image { url, .. } if url.is_empty() => {
+        result.warnings.push(ValidationWarning {
+          line: at.line(node),
+          message: "empty image URL".to_string(),
+          rule: None,
+        });
+      }
+      _ => {}
+    }
+    check_empty_links(&node.children, at.within(node), result);
+  }
+}
+
+/// Range attributes must parse and point at lines inside the code block.
+fn check_code_ranges(nodes: &[Node], result: &mut ValidationResult) {
+  for node in nodes {
+    if let NodeKind::CodeBlockExt {
+      highlight,
+      plusdiff,
+      minusdiff,
+      invalid_ranges,
+      ..
+    } = &node.kind
+    {
+      let mut warn = |message: String| {
+        result.warnings.push(ValidationWarning {
+          line: node.span.line,
+          message,
+          rule: None,
+        })
+      };
+      for (name, value) in invalid_ranges {
+        warn(format!("malformed {} range: \"{}\"", name, value));
+      }
+      let lines = plain_text(&node.children).lines().count() as u32;
+      for (name, ranges) in [
+        ("highlight", highlight),
+        ("plusdiff", plusdiff),
+        ("minusdiff", minusdiff),
+      ] {
+        for &(start, end) in ranges.iter().filter(|r| r.1 > lines) {
+          warn(format!(
+            "{} range {} is outside the code block (lines 1-{})",
+            name,
+            range_text(start, end),
+            lines
+          ));
+        }
+      }
+    }
+    check_code_ranges(&node.children, result);
+  }
+}
+
+/// A `names` list must name every tab, and only those.
+fn check_tabs(nodes: &[Node], result: &mut ValidationResult) {
+  for node in nodes {
+    if let NodeKind::Tabs { names } = &node.kind {
+      let tabs = node
+        .children
+        .iter()
+        .filter(|c| matches!(c.kind, NodeKind::Tab { .. }))
+        .count();
+      if !names.is_empty() && names.len() != tabs {
+        result.warnings.push(ValidationWarning {
+          line: node.span.line,
+          message: format!("tabs declares {} names but has {} tabs", names.len(), tabs),
+          rule: None,
+        });
+      }
+    }
+    check_tabs(&node.children, result);
+  }
+}
+
+/// Frontmatter `date` and `updated` must be dates `--extract-metadata` can
+/// read.
+fn check_dates(nodes: &[Node], result: &mut ValidationResult) {
+  let Some((node, format, content)) = nodes.iter().find_map(|n| match &n.kind {
+    NodeKind::Frontmatter { format, content } => Some((n, *format, content)),
+    _ => None,
+  }) else {
+    return;
+  };
+  let separator = match format {
+    FrontmatterFormat::Yaml => ':',
+    FrontmatterFormat::Toml => '=',
+    FrontmatterFormat::Json => return,
+  };
+  for (i, line) in content.lines().enumerate() {
+    let Some((key, value)) = line.split_once(separator) else {
+      continue;
+    };
+    let key = key.trim_end();
+    let value = value.trim().trim_matches(['"', '\'']);
+    if matches!(key, "date" | "updated") && !value.is_empty() && iso_date(value).is_none() {
+      result.warnings.push(ValidationWarning {
+        // Content starts on the line after the opening delimiter
+        line: node.span.line + 1 + i,
+        message: format!("unparseable {} in frontmatter: \"{}\"", key, value),
+        rule: None,
+      });
+    }
+  }
+}
+
+/// Frontmatter `bukvar` entries that don't set a parser option.
+fn check_parser_options(nodes: &[Node], result: &mut ValidationResult) {
+  let Some((node, format, content)) = nodes.iter().find_map(|n| match &n.kind {
+    NodeKind::Frontmatter { format, content } => Some((n, *format, content)),
+    _ => None,
+  }) else {
+    return;
+  };
+  for (key, value, i) in option_overrides(format, content) {
+    if let Err(e) = ParseOptions::new().set(&key, &value) {
+      result.warnings.push(ValidationWarning {
+        line: node.span.line + 1 + i,
+        message: format!("frontmatter {}: {}", OPTIONS_KEY, e),
+        rule: None,
+      });
+    }
+  }
+}
+
+/// Diagram types a mermaid diagram can start with.
+const MERMAID_DIAGRAMS: &[&str] = &[
+  "graph",
+  "flowchart",
+  "sequenceDiagram",
+  "classDiagram",
+  "classDiagram-v2",
+  "stateDiagram",
+  "stateDiagram-v2",
+  "erDiagram",
+  "journey",
+  "gantt",
+  "pie",
+  "quadrantChart",
+  "requirementDiagram",
+  "gitGraph",
+  "C4Context",
+  "C4Container",
+  "C4Component",
+  "C4Dynamic",
+  "C4Deployment",
+  "mindmap",
+  "timeline",
+  "zenuml",
+  "sankey-beta",
+  "xychart-beta",
+  "block-beta",
+  "packet-beta",
+  "architecture-beta",
+  "kanban",
+  "radar-beta",
+];
+
+/// Mermaid diagrams must start with a known diagram type, and a flowchart's
+/// direction must be one mermaid accepts.
+fn check_diagrams(nodes: &[Node], result: &mut ValidationResult) {
+  for node in nodes {
+    if let NodeKind::DiagramBlock { engine, content } = &node.kind {
+      if engine == "mermaid" {
+        if let Some((offset, message)) = mermaid_header(content) {
+          result.warnings.push(ValidationWarning {
+            line: node.span.line + offset,
+            message,
+            rule: None,
+          });
+        }
+      }
+    }
+    check_diagrams(&node.children, result);
+  }
+}
+
+/// What is wrong with a mermaid diagram's header line, and that line's
+/// offset from the opening fence. Blank lines, `%%` comments and a leading
+/// `---` config block come before the header.
+fn mermaid_header(content: &str) -> Option<(usize, String)> {
+  let mut lines = content
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i + 1, line.trim()));
+  let mut in_config = false;
+  let header = lines.find(|&(i, line)| {
+    if line == "---" && (in_config || i == 1) {
+      in_config = !in_config;
+      return false;
+    }
+    !in_config && !line.is_empty() && !line.starts_with("%%")
+  });
+  let Some((offset, line)) = header else {
+    return Some((0, "empty mermaid diagram".to_string()));
+  };
+  let mut words = line.split_whitespace();
+  let kind = words.next().unwrap_or_default();
+  if !MERMAID_DIAGRAMS.contains(&kind) {
+    return Some((
+      offset,
+      format!("unknown mermaid diagram type: \"{}\"", kind),
+    ));
+  }
+  if matches!(kind, "graph" | "flowchart") {
+    let direction = words.next().map(|word| word.trim_end_matches(';'));
+    if let Some(direction) = direction.filter(|d| !matches!(*d, "TD" | "TB" | "BT" | "RL" | "LR")) {
+      return Some((
+        offset,
+        format!("unknown {} direction: \"{}\"", kind, direction),
+      ));
+    }
+  }
+  None
+}
+
+/// Check `@since` and `@version` tags for `--current-version`: a `@since`
+/// newer than the current release documents something not released yet,
+/// and a value that is not a version can't be compared at all.
+pub fn check_versions(doc: &Document, current: &Version) -> Vec<ValidationWarning> {
+  let mut warnings = Vec::new();
+  for comment in &doc.nodes {
+    for tag in &comment.children {
+      let (name, value) = match &tag.kind {
+        NodeKind::DocSince { version } => ("@since", version),
+        NodeKind::DocVersion { version } => ("@version", version),
+        _ => continue,
+      };
+      let message = match Version::parse(value) {
+        None => format!("{} {:?} is not a version", name, value),
+        Some(v) if name == "@since" && v > *current => {
+          format!("@since {} is newer than the current version {}", v, current)
+        }
+        Some(_) => continue,
+      };
+      warnings.push(ValidationWarning {
+        line: comment.span.line,
+        message,
+        rule: None,
+      });
+    }
+  }
+  warnings
+}
+
+fn range_text(start: u32, end: u32) -> String {
+  if start == end {
+    start.to_string()
+  } else {
+    format!("{}-{}", start, end)
+  }
+}
+
+struct Heading {
+  level: u8,
+  id: Option<String>,
+  text: String,
+  line: usize,
+}
+
+/// Duplicate text or ids, level jumps, extra H1s and empty headings. Ids
+/// are the ones the author wrote: the slug pass fills in missing ids but
+/// never renames explicit ones.
+fn check_headings(nodes: &[Node], result: &mut ValidationResult) {
+  let mut headings = Vec::new();
+  collect_headings(nodes, &mut headings);
+
+  let mut ids: HashMap<&str, usize> = HashMap::new();
+  let mut texts: HashMap<String, usize> = HashMap::new();
+  let mut first_h1 = None;
+  let mut previous_level = None;
+
+  for heading in &headings {
+    let mut warn = |message: String| {
+      result.warnings.push(ValidationWarning {
+        line: heading.line,
+        message,
+        rule: None,
+      })
+    };
+
+    let text = heading.text.trim();
+    if text.is_empty() {
+      warn("empty heading".to_string());
+    }
+
+    let duplicate_id = heading
+      .id
+      .as_deref()
+      .and_then(|id| ids.insert(id, heading.line).map(|first| (id, first)));
+    let duplicate_text = (!text.is_empty())
+      .then(|| texts.insert(text.to_lowercase(), heading.line))
+      .flatten();
+    if let Some((id, first)) = duplicate_id {
+      warn(format!(
+        "duplicate heading id: #{} (first at line {})",
+        id, first
+      ));
+    } else if let Some(first) = duplicate_text {
+      warn(format!(
+        "duplicate heading text: \"{}\" (first at line {})",
+        text, first
+      ));
+    }
+
+    if heading.level == 1 {
+      match first_h1 {
+        Some(first) => warn(format!("multiple H1 headings (first at line {})", first)),
+        None => first_h1 = Some(heading.line),
+      }
+    }
+    if let Some(previous) = previous_level {
+      if heading.level > previous + 1 {
+        warn(format!(
+          "heading level jumps from H{} to H{}",
+          previous, heading.level
+        ));
+      }
+    }
+    previous_level = Some(heading.level);
+  }
+}
+
+fn collect_headings(nodes: &[Node], out: &mut Vec<Heading>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Heading { level, id, .. } => out.push(Heading {
+        level: *level,
+        id: id.clone(),
+        text: plain_text(&node.children),
+        line: node.span.line,
+      }),
+      NodeKind::Toc => {}
+      _ => collect_headings(&node.children, out),
+    }
+  }
+}
+
+fn plain_text(nodes: &[Node]) -> String {
+  nodes
+    .iter()
+    .map(|node| match &node.kind {
+      NodeKind::Text { content } | NodeKind::CodeSpan { content } => content.clone(),
+      NodeKind::SoftBreak | NodeKind::HardBreak => " ".to_string(),
+      _ => plain_text(&node.children),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ast::{Document, DocumentMetadata, DocumentType};
+
+  fn empty_doc() -> Document {
+    Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![],
+      metadata: DocumentMetadata::default(),
+    }
+  }
+
+  #[test]
+  fn test_valid_doc() {
+    let doc = empty_doc();
+    let result = validate(&doc);
+    assert!(result.is_ok());
+    assert!(result.warnings.is_empty());
+  }
+
+  #[test]
+  fn test_validation_result_errors() {
+    let mut result = ValidationResult::default();
+    assert!(result.errors.is_empty());
+    result.errors.push(ValidationError {
+      line: 1,
+      message: "Test error".to_string(),
+    });
+    assert!(!result.is_ok());
+  }
+
+  #[test]
+  fn test_validation_result_warnings() {
+    let mut result = ValidationResult::default();
+    assert!(result.warnings.is_empty());
+    result.warnings.push(ValidationWarning {
+      line: 1,
+      message: "Test warning".to_string(),
+      rule: None,
+    });
+    assert!(!result.warnings.is_empty());
+    assert!(result.is_ok()); // warnings don't make it not ok
+  }
+
+  #[test]
+  fn test_broken_link_reference() {
+    use crate::ast::{Node, NodeKind, ReferenceType, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::new(
+        NodeKind::LinkReference {
+          label: "nonexistent".to_string(),
+          ref_type: ReferenceType::Full,
+        },
+        Span::empty(),
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    assert!(!result.errors.is_empty() || !result.warnings.is_empty());
+  }
+
+  #[test]
+  fn test_broken_footnote_reference() {
+    use crate::ast::{Node, NodeKind, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::new(
+        NodeKind::FootnoteReference {
+          label: "missing".to_string(),
+          index: 0,
+        },
+        Span::empty(),
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    assert!(!result.errors.is_empty() || !result.warnings.is_empty());
+  }
+
+  #[test]
+  fn test_empty_link() {
+    use crate::ast::{Node, NodeKind, ReferenceType, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::new(
+        NodeKind::Link {
+          url: "".to_string(),
+          title: None,
+          ref_type: ReferenceType::Full,
+          attributes: vec![],
+        },
+        Span::empty(),
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    assert!(!result.warnings.is_empty());
+  }
+
+  #[test]
+  fn test_valid_link() {
+    use crate::ast::{Node, NodeKind, ReferenceType, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::new(
+        NodeKind::Link {
+          url: "https://example.com".to_string(),
+          title: Some("Example".to_string()),
+          ref_type: ReferenceType::Full,
+          attributes: vec![],
+        },
+        Span::empty(),
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    assert!(result.is_ok());
+  }
+
+  #[test]
+  fn test_matching_link_definition() {
+    use crate::ast::{Node, NodeKind, ReferenceType, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![
+        Node::new(
+          NodeKind::LinkReference {
+            label: "example".to_string(),
+            ref_type: ReferenceType::Full,
+          },
+          Span::empty(),
+        ),
+        Node::new(
+          NodeKind::LinkDefinition {
+            label: "example".to_string(),
+            url: "https://example.com".to_string(),
+            title: None,
+          },
+          Span::empty(),
+        ),
+      ],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    assert!(result.is_ok());
+  }
+
+  #[test]
+  fn test_link_definition_matches_folded_label() {
+    use crate::ast::{Node, NodeKind, ReferenceType, Span};
+    let doc = |reference: &str| Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![
+        Node::new(
+          NodeKind::LinkReference {
+            label: reference.to_string(),
+            ref_type: ReferenceType::Full,
+          },
+          Span::empty(),
+        ),
+        Node::new(
+          NodeKind::LinkDefinition {
+            label: "Straße  Map".to_string(),
+            url: "https://example.com".to_string(),
+            title: None,
+          },
+          Span::empty(),
+        ),
+      ],
+      metadata: DocumentMetadata::default(),
+    };
+    assert!(validate(&doc("STRASSE\nmap")).warnings.is_empty());
+    assert_eq!(validate(&doc("Strasse")).warnings.len(), 1);
+  }
+
+  #[test]
+  fn test_matching_footnote() {
+    use crate::ast::{Node, NodeKind, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![
+        Node::new(
+          NodeKind::FootnoteReference {
+            label: "1".to_string(),
+            index: 0,
+          },
+          Span::empty(),
+        ),
+        Node::new(
+          NodeKind::FootnoteDefinition {
+            label: "1".to_string(),
+            index: 0,
+          },
+          Span::empty(),
+        ),
+      ],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    assert!(result.is_ok());
+  }
+
+  #[test]
+  fn test_nested_validation() {
+    use crate::ast::{Node, NodeKind, Span};
+    let doc = Document {
+      source_path: String::new(),
+      doc_type: DocumentType::Markdown,
+      nodes: vec![Node::with_children(
+        NodeKind::Paragraph,
+        Span::empty(),
+        vec![Node::new(
+          NodeKind::FootnoteReference {
+            label: "missing".to_string(),
+            index: 0,
+          },
+          Span::empty(),
+        )],
+      )],
+      metadata: DocumentMetadata::default(),
+    };
+    let result = validate(&doc);
+    // Should find the broken reference in children
+    assert!(!result.errors.is_empty() || !result.warnings.is_empty());
+  }
+
+  fn warnings_for(input: &str) -> Vec<String> {
+    let doc = crate::markdown::MarkdownParser::new(input).parse();
+    validate(&doc)
+      .warnings
+      .into_iter()
+      .map(|w| format!("{}: {}", w.line, w.message))
+      .collect()
+  }
+
+  #[test]
+  fn test_heading_structure() {
+    let input = "# Title\n\n### Deep\n\n## Setup\n\n## setup\n\n# Again\n\n##\n";
+    assert_eq!(
+      warnings_for(input),
+      vec![
+        "3: heading level jumps from H1 to H3",
+        "7: duplicate heading text: \"setup\" (first at line 5)",
+        "9: multiple H1 headings (first at line 1)",
+        "11: empty heading",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_duplicate_heading_ids() {
+    let doc = Document {
+      nodes: vec![
+        Node::new(
+          NodeKind::Heading {
+            level: 1,
+            id: Some("intro".to_string()),
+            attributes: vec![],
+            section: None,
+          },
+          crate::ast::Span::new(0, 1, 1, 1),
+        ),
+        Node::new(
+          NodeKind::Heading {
+            level: 2,
+            id: Some("intro".to_string()),
+            attributes: vec![],
+            section: None,
+          },
+          crate::ast::Span::new(2, 3, 2, 1),
+        ),
+      ],
+      ..empty_doc()
+    };
+    let messages: Vec<_> = validate(&doc)
+      .warnings
+      .into_iter()
+      .map(|w| w.message)
+      .collect();
+    assert!(messages.contains(&"duplicate heading id: #intro (first at line 1)".to_string()));
+  }
+
+  #[test]
+  fn test_heading_checks_skip_source_docs() {
+    let mut doc = crate::markdown::MarkdownParser::new("# A\n\n# A").parse();
+    doc.doc_type = DocumentType::Python;
+    assert!(validate(&doc).warnings.is_empty());
+  }
+
+  #[test]
+  fn test_code_ranges_inside_block() {
+    let input = "```rust highlight=\"1, 3-4\" plusdiff=\"2\" minusdiff=\"x\"\na\nb\nc\n```\n";
+    assert_eq!(
+      warnings_for(input),
+      vec![
+        "1: malformed minusdiff range: \"x\"",
+        "1: highlight range 3-4 is outside the code block (lines 1-3)",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_tab_count_matches_names() {
+    let input = "<tabs names=\"JS, Python\">\n```js\nx\n```\n</tabs>\n";
+    assert_eq!(
+      warnings_for(input),
+      vec!["1: tabs declares 2 names but has 1 tabs"]
+    );
+    assert!(warnings_for("<tabs names=\"A\">\n```js\nx\n```\n</tabs>\n").is_empty());
+  }
+
+  #[test]
+  fn test_frontmatter_dates() {
+    let input = "---\ntitle: A\ndate: 2024-02-30\nupdated: \"May 1, 2024\"\n---\n# A\n";
+    assert_eq!(
+      warnings_for(input),
+      vec!["3: unparseable date in frontmatter: \"2024-02-30\""]
+    );
+    assert!(warnings_for("+++\nupdated = \"soon\"\n+++\n# A\n")[0].starts_with("2: "));
+  }
+
+  #[test]
+  fn test_frontmatter_parser_options() {
+    let input =
+      "---\ntitle: A\nbukvar:\n  math: false\n  smart_quotes: true\n  table_colspan: 1\n---\n# A\n";
+    assert_eq!(
+      warnings_for(input),
+      vec![
+        "5: frontmatter bukvar: unknown parser option: smart_quotes",
+        "6: frontmatter bukvar: expected true or false for table_colspan: \"1\"",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_inline_lines() {
+    let input = "# A\n\n> See\n> also [^lost]\n\n| a |\n|---|\n| [^gone] |\n";
+    let doc = crate::markdown::MarkdownParser::new(input).parse();
+    let lines = |result: ValidationResult| {
+      let mut lines: Vec<usize> = result.warnings.iter().map(|w| w.line).collect();
+      lines.extend(result.errors.iter().map(|e| e.line));
+      lines.sort_unstable();
+      lines
+    };
+    assert_eq!(
+      lines(validate_with_lines(&doc, &LineIndex::new(input))),
+      vec![4, 8]
+    );
+    // Without the source, inline findings take the nearest block's line
+    assert_eq!(lines(validate(&doc)), vec![4, 8]);
+  }
+
+  #[test]
+  fn test_mermaid_headers() {
+    let input = "```mermaid\nflowchart LR\n  a --> b\n```\n\n\
+                 ```mermaid\n---\ntitle: Flow\n---\n%% note\n\ngrpah TD\n```\n\n\
+                 ```mermaid\ngraph XY;\n```\n\n```mermaid\n```\n\n\
+                 ```dot\nnot checked\n```\n";
+    assert_eq!(
+      warnings_for(input),
+      vec![
+        "12: unknown mermaid diagram type: \"grpah\"",
+        "16: unknown graph direction: \"XY\"",
+        "19: empty mermaid diagram",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_check_versions() {
+    use crate::parsers::JavaDocParser;
+    let source =
+      "/** @since 1.2 */\nclass A {}\n/**\n * @since 2.0-beta\n * @version next\n */\nclass B {}\n";
+    let doc = JavaDocParser::new(source).parse();
+    let warnings = |current| {
+      check_versions(&doc, &Version::parse(current).unwrap())
+        .into_iter()
+        .map(|w| format!("{}: {}", w.line, w.message))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      warnings("1.9"),
+      vec![
+        "3: @since 2.0-beta is newer than the current version 1.9",
+        "3: @version \"next\" is not a version",
+      ]
+    );
+    assert_eq!(
+      warnings("v2.0.0"),
+      vec!["3: @version \"next\" is not a version"]
+    );
+  }
+}
@@ -0,0 +1,485 @@
+//! Cross-reference table (`--xref`): `{@link}` and `@see` targets across
+//! all processed source files, resolved to the file and line of the doc
+//! comment documenting the referenced symbol.
+//!
+//! Targets are matched by name: `Greeter#greet(String)`, `Greeter.greet`
+//! and `com.acme.Greeter#greet` all name the `greet` method of a class
+//! `Greeter`, `#greet` a `greet` in the referencing file, and `greet` or
+//! `Greeter` any symbol of that name. A symbol in the referencing file wins
+//! over one elsewhere. URLs, quoted titles and HTML links are not
+//! references and are skipped.
+
+use crate::ast::{Document, DocumentType, Node, NodeKind};
+use crate::formats::esc;
+use crate::parsers::coverage::Coverage;
+use crate::parsers::reference::match_symbols;
+
+use std::path::{Path, PathBuf};
+
+/// A declaration other files can refer to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+  pub name: String,
+  /// Enclosing class, for methods
+  pub owner: Option<String>,
+  /// Line of the symbol's doc comment, or of the declaration when it has none
+  pub line: usize,
+}
+
+impl Symbol {
+  /// `Class#method`, or the bare name
+  pub fn qualified(&self) -> String {
+    match &self.owner {
+      Some(owner) => format!("{}#{}", owner, self.name),
+      None => self.name.clone(),
+    }
+  }
+}
+
+/// A `{@link}` or `@see` in a doc comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+  /// `link`, `linkplain`, `linkcode` or `see`
+  pub tag: &'static str,
+  pub target: String,
+  /// Line of the doc comment holding the reference
+  pub line: usize,
+}
+
+/// Symbols and references of one source file.
+#[derive(Debug, Clone, Default)]
+pub struct FileXref {
+  pub symbols: Vec<Symbol>,
+  pub references: Vec<Reference>,
+}
+
+impl FileXref {
+  /// Collect from a parsed source file and its declaration scan.
+  pub fn collect(doc: &Document, coverage: Option<&Coverage>) -> Self {
+    let mut xref = Self::default();
+    let comments: Vec<&Node> = doc
+      .nodes
+      .iter()
+      .filter(|n| matches!(n.kind, NodeKind::DocComment { .. }))
+      .collect();
+
+    let documented = comment_symbols(&comments, coverage, doc.doc_type);
+    if let Some(coverage) = coverage {
+      let owners = owners(coverage);
+      for (decl, owner) in coverage.declarations.iter().zip(owners) {
+        let symbol = Symbol {
+          name: decl.name.clone(),
+          owner,
+          line: decl.line,
+        };
+        let comment = documented
+          .iter()
+          .flatten()
+          .find(|s| s.name == symbol.name && s.owner == symbol.owner);
+        xref.symbols.push(comment.cloned().unwrap_or(symbol));
+      }
+    }
+
+    for (comment, symbol) in comments.iter().zip(documented) {
+      // Declarations are in already; this adds `@typedef` and `@callback`
+      if let Some(symbol) = symbol.filter(|s| !xref.symbols.contains(s)) {
+        xref.symbols.push(symbol);
+      }
+      let mut references = Vec::new();
+      collect_references(&comment.children, doc.doc_type, &mut references);
+      xref
+        .references
+        .extend(references.into_iter().map(|(tag, target)| Reference {
+          tag,
+          target,
+          line: comment.span.line,
+        }));
+    }
+    xref
+  }
+}
+
+/// The symbol each doc comment documents: the declaration after it (before
+/// it, for docstrings) or the name of a JSDoc `@typedef` or `@callback`.
+/// Symbols get the line of their comment.
+pub fn comment_symbols(
+  comments: &[&Node],
+  coverage: Option<&Coverage>,
+  doc_type: DocumentType,
+) -> Vec<Option<Symbol>> {
+  let declarations = coverage.map_or(&[][..], |c| &c.declarations[..]);
+  let owners = coverage.map(owners).unwrap_or_default();
+  let matched = match_symbols(comments, declarations, doc_type);
+  comments
+    .iter()
+    .zip(matched)
+    .map(|(comment, decl)| {
+      let line = comment.span.line;
+      if let Some(decl) = decl {
+        let index = declarations.iter().position(|d| std::ptr::eq(d, decl))?;
+        return Some(Symbol {
+          name: decl.name.clone(),
+          owner: owners[index].clone(),
+          line,
+        });
+      }
+      comment.children.iter().find_map(|tag| match &tag.kind {
+        NodeKind::DocTypedef { name, .. } | NodeKind::DocCallback { name } => Some(Symbol {
+          name: name.clone(),
+          owner: None,
+          line,
+        }),
+        _ => None,
+      })
+    })
+    .collect()
+}
+
+/// Enclosing class of each declaration that is a method.
+fn owners(coverage: &Coverage) -> Vec<Option<String>> {
+  let mut owner = None;
+  coverage
+    .declarations
+    .iter()
+    .map(|decl| {
+      if matches!(decl.kind, "class" | "interface" | "enum" | "record") {
+        owner = Some(decl.name.clone());
+      }
+      (decl.kind == "method").then(|| owner.clone()).flatten()
+    })
+    .collect()
+}
+
+fn collect_references(
+  nodes: &[Node],
+  doc_type: DocumentType,
+  out: &mut Vec<(&'static str, String)>,
+) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::DocSee { reference } => {
+        if let Some(target) = target(reference) {
+          out.push(("see", target));
+        }
+      }
+      // JavaDoc descriptions have their `{@link}` tags as `Link` nodes
+      NodeKind::Link { url, .. } if doc_type == DocumentType::Java => {
+        if let Some(target) = target(url) {
+          out.push(("link", target));
+        }
+      }
+      kind => {
+        for text in doc_text(kind) {
+          out.extend(inline_links(text));
+        }
+      }
+    }
+    collect_references(&node.children, doc_type, out);
+  }
+}
+
+/// Tag text that may hold raw `{@link}` tags.
+fn doc_text(kind: &NodeKind) -> Vec<&str> {
+  match kind {
+    NodeKind::DocDescription { content }
+    | NodeKind::DocRemarks { content }
+    | NodeKind::DocTag {
+      content: Some(content),
+      ..
+    }
+    | NodeKind::DocDeprecated {
+      message: Some(content),
+      ..
+    } => vec![content],
+    NodeKind::DocParam { description, .. }
+    | NodeKind::DocReturn { description, .. }
+    | NodeKind::DocThrows { description, .. }
+    | NodeKind::DocProperty { description, .. }
+    | NodeKind::DocTypeParam { description, .. } => {
+      description.iter().map(String::as_str).collect()
+    }
+    _ => Vec::new(),
+  }
+}
+
+/// `{@link X}`, `{@linkplain X label}` and `{@linkcode X|label}` targets.
+fn inline_links(text: &str) -> Vec<(&'static str, String)> {
+  let mut out = Vec::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("{@link") {
+    rest = &rest[start + 2..];
+    let Some(end) = rest.find('}') else {
+      break;
+    };
+    let body = &rest[..end];
+    rest = &rest[end..];
+    let (name, reference) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let tag = match name {
+      "link" => "link",
+      "linkplain" => "linkplain",
+      "linkcode" => "linkcode",
+      _ => continue,
+    };
+    if let Some(target) = target(reference.split('|').next().unwrap_or("")) {
+      out.push((tag, target));
+    }
+  }
+  out
+}
+
+/// The symbol a reference names, without its label; `None` for URLs and
+/// `@see "Title"` / `@see <a href>` forms.
+fn target(reference: &str) -> Option<String> {
+  let target = reference.split_whitespace().next()?;
+  let skip =
+    target.starts_with(['"', '<']) || target.contains("://") || target.starts_with("mailto:");
+  (!skip).then(|| target.to_string())
+}
+
+/// One reference and where its target is documented.
+#[derive(Debug)]
+pub struct Entry {
+  pub source: PathBuf,
+  pub reference: Reference,
+  /// File, doc comment line and qualified name of the target
+  pub resolved: Option<(PathBuf, usize, String)>,
+}
+
+/// Every reference of a run, in input order.
+#[derive(Debug, Default)]
+pub struct XrefTable {
+  pub entries: Vec<Entry>,
+  pub symbols: usize,
+}
+
+impl XrefTable {
+  pub fn build(files: &[(PathBuf, FileXref)]) -> Self {
+    let mut table = Self {
+      entries: Vec::new(),
+      symbols: files.iter().map(|(_, f)| f.symbols.len()).sum(),
+    };
+    for (source, file) in files {
+      for reference in &file.references {
+        table.entries.push(Entry {
+          source: source.clone(),
+          reference: reference.clone(),
+          resolved: resolve(&reference.target, source, files),
+        });
+      }
+    }
+    table
+  }
+
+  /// References whose target was not found.
+  pub fn unresolved(&self) -> impl Iterator<Item = &Entry> {
+    self.entries.iter().filter(|e| e.resolved.is_none())
+  }
+
+  /// Convert to JSON format.
+  pub fn to_json(&self) -> String {
+    let mut s = String::with_capacity(64 + self.entries.len() * 96);
+    s.push_str(&format!("{{\"symbols\":{},\"references\":[", self.symbols));
+    for (i, entry) in self.entries.iter().enumerate() {
+      if i > 0 {
+        s.push(',');
+      }
+      s.push_str(&format!(
+        "{{\"source\":\"{}\",\"line\":{},\"tag\":\"{}\",\"target\":\"{}\",\"resolved\":",
+        esc(&display(&entry.source)),
+        entry.reference.line,
+        entry.reference.tag,
+        esc(&entry.reference.target)
+      ));
+      match &entry.resolved {
+        Some((file, line, symbol)) => s.push_str(&format!(
+          "{{\"file\":\"{}\",\"line\":{},\"symbol\":\"{}\"}}}}",
+          esc(&display(file)),
+          line,
+          esc(symbol)
+        )),
+        None => s.push_str("null}"),
+      }
+    }
+    s.push_str("]}");
+    s
+  }
+}
+
+/// Find the symbol `target` names, preferring the referencing file.
+fn resolve(
+  target: &str,
+  source: &Path,
+  files: &[(PathBuf, FileXref)],
+) -> Option<(PathBuf, usize, String)> {
+  // Parameter lists don't take part in matching
+  let target = target.split('(').next().unwrap_or(target);
+  let (owner, name) = match target.split_once('#') {
+    Some((owner, member)) => (Some(last_segment(owner)), member),
+    None => match target.rsplit_once('.') {
+      Some((owner, member)) => (Some(last_segment(owner)), member),
+      None => (None, target),
+    },
+  };
+  let matches = |file: &FileXref| {
+    file
+      .symbols
+      .iter()
+      .find(|s| {
+        s.name == name
+          && match owner {
+            // `#member`: any member of the referencing file
+            Some("") | None => true,
+            Some(owner) => s.owner.as_deref() == Some(owner),
+          }
+      })
+      .cloned()
+  };
+  // `pkg.Class` names a class, not a member of `pkg`
+  let class = |file: &FileXref| {
+    let class = last_segment(target);
+    (target.contains('.') && !target.contains('#'))
+      .then(|| {
+        file
+          .symbols
+          .iter()
+          .find(|s| s.owner.is_none() && s.name == class)
+      })
+      .flatten()
+      .cloned()
+  };
+  let same_file = files.iter().filter(|(path, _)| path == source);
+  let other_files = files.iter().filter(|(path, _)| path != source);
+  let local_only = owner == Some("");
+  same_file
+    .chain(other_files.filter(|_| !local_only))
+    .find_map(|(path, file)| {
+      matches(file)
+        .or_else(|| class(file))
+        .map(|s| (path.clone(), s))
+    })
+    .map(|(path, symbol)| (path, symbol.line, symbol.qualified()))
+}
+
+fn last_segment(path: &str) -> &str {
+  path.rsplit('.').next().unwrap_or(path)
+}
+
+fn display(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parsers::{coverage, JavaDocParser, JsDocParser};
+
+  fn java(path: &str, source: &str) -> (PathBuf, FileXref) {
+    let doc = JavaDocParser::new(source).parse();
+    let coverage = coverage::measure(source, DocumentType::Java);
+    (
+      PathBuf::from(path),
+      FileXref::collect(&doc, coverage.as_ref()),
+    )
+  }
+
+  #[test]
+  fn test_collect() {
+    let (_, xref) = java(
+      "Greeter.java",
+      "/** Greets. See {@link #name()} and {@link Other label}. */\npublic class Greeter {\n\n  \
+       /**\n   * @param who see {@link java.util.List}\n   * @see \"Book\"\n   * @see Greeter#greet\n   */\n  \
+       public void greet(String who) {\n  }\n\n  public String name() {\n  }\n}\n",
+    );
+    assert_eq!(
+      xref.symbols,
+      vec![
+        Symbol {
+          name: "Greeter".to_string(),
+          owner: None,
+          line: 1,
+        },
+        Symbol {
+          name: "greet".to_string(),
+          owner: Some("Greeter".to_string()),
+          line: 4,
+        },
+        Symbol {
+          name: "name".to_string(),
+          owner: Some("Greeter".to_string()),
+          line: 12,
+        },
+      ]
+    );
+    let targets: Vec<_> = xref
+      .references
+      .iter()
+      .map(|r| (r.tag, r.target.as_str(), r.line))
+      .collect();
+    assert_eq!(
+      targets,
+      vec![
+        ("link", "#name()", 1),
+        ("link", "Other", 1),
+        ("link", "java.util.List", 4),
+        ("see", "Greeter#greet", 4),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_resolve_across_files() {
+    let files = vec![
+      java(
+        "a/Greeter.java",
+        "/** Uses {@link Helper#help(int)}, {@link #missing} and {@link com.acme.Helper}. */\n\
+         public class Greeter {\n}\n",
+      ),
+      java(
+        "b/Helper.java",
+        "public class Helper {\n  /** Helps. */\n  public void help(int n) {\n  }\n}\n",
+      ),
+    ];
+    let table = XrefTable::build(&files);
+    let resolved: Vec<_> = table
+      .entries
+      .iter()
+      .map(|e| {
+        e.resolved
+          .as_ref()
+          .map(|(f, l, s)| (display(f), *l, s.as_str()))
+      })
+      .collect();
+    assert_eq!(
+      resolved,
+      vec![
+        Some(("b/Helper.java".to_string(), 2, "Helper#help")),
+        None,
+        Some(("b/Helper.java".to_string(), 1, "Helper")),
+      ]
+    );
+    assert_eq!(table.unresolved().count(), 1);
+    assert!(table.to_json().starts_with(
+      "{\"symbols\":3,\"references\":[{\"source\":\"a/Greeter.java\",\"line\":1,\"tag\":\"link\",\
+       \"target\":\"Helper#help(int)\",\"resolved\":{\"file\":\"b/Helper.java\",\"line\":2,\
+       \"symbol\":\"Helper#help\"}},"
+    ));
+  }
+
+  #[test]
+  fn test_jsdoc_links() {
+    let source = "/**\n * @typedef {Object} Point\n */\n\n/**\n * Add {@link Point|points}.\n \
+                  * @see https://example.com\n */\nfunction add(a) {}\n";
+    let doc = JsDocParser::new(source).parse();
+    let coverage = coverage::measure(source, DocumentType::JavaScript);
+    let files = vec![(
+      PathBuf::from("add.js"),
+      FileXref::collect(&doc, coverage.as_ref()),
+    )];
+    let table = XrefTable::build(&files);
+    assert_eq!(table.entries.len(), 1);
+    assert_eq!(
+      table.entries[0].resolved,
+      Some((PathBuf::from("add.js"), 1, "Point".to_string()))
+    );
+  }
+}
@@ -0,0 +1,14 @@
+/**
+ * Sends notifications.
+ *
+ * @author Ada
+ * @since 1.2
+ */
+public class Notifier {
+  /**
+   * Send one message.
+   * @param to recipient address
+   * @throws IOException when delivery fails
+   */
+  public void send(String to) throws IOException {}
+}
@@ -0,0 +1,11 @@
+/**
+ * Add two numbers.
+ * @param {number} a - First operand
+ * @param {number} b - Second operand
+ * @returns {number} The sum
+ * @example
+ * add(1, 2); // 3
+ */
+function add(a, b) {
+  return a + b;
+}
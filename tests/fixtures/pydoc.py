@@ -0,0 +1,14 @@
+def scale(x: float, factor: int = 2) -> float:
+    """Scale a value.
+
+    Args:
+        x: The value.
+        factor: Multiplier.
+
+    Returns:
+        The scaled value.
+
+    >>> scale(3)
+    6
+    """
+    return x * factor
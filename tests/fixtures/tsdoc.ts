@@ -0,0 +1,10 @@
+/**
+ * A cache keyed by string.
+ * @typeParam T - Value type
+ * @remarks Entries never expire.
+ * @beta
+ */
+export class Cache<T> {
+  /** @defaultValue 100 */
+  size = 100;
+}